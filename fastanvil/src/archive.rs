@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Mutex;
+
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+
+use crate::files::coords_from_region;
+use crate::{
+    Chunk, Error, LoaderResult, RCoord, RegionBuffer, RegionBytesLoader, RegionLoader, Result,
+};
+
+/// Loads regions straight out of a zipped world backup, without extracting
+/// it. The archive is searched for regions once when the loader is made, and
+/// each region is decompressed from the archive when it is loaded.
+///
+/// `region_dir` is the folder of the regions within the archive, such as
+/// `world/region` or `world/DIM-1/region`. Oversized chunks kept in `.mcc`
+/// files are not supported, these chunks fail to load.
+///
+/// Tar archives can't be read out of order, so for `.tar` and `.tar.gz`
+/// backups see [`RegionBytesLoader::from_tar`] instead.
+///
+/// ```no_run
+/// # use fastanvil::{CCoord, Dimension, JavaChunk, ZipRegionLoader};
+/// let loader = ZipRegionLoader::<JavaChunk>::open("backups/world.zip", "world/region")?;
+/// let dimension = Dimension::new(loader);
+///
+/// let chunk = dimension.chunk(CCoord(3), CCoord(5));
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct ZipRegionLoader<C: Chunk, R = File> {
+    archive: Mutex<zip::ZipArchive<R>>,
+    regions: HashMap<(RCoord, RCoord), usize>,
+    p: PhantomData<fn() -> C>,
+}
+
+impl<C: Chunk> ZipRegionLoader<C> {
+    /// Open the zip file at `path`.
+    pub fn open(path: impl AsRef<Path>, region_dir: &str) -> Result<Self> {
+        Self::new(File::open(path)?, region_dir)
+    }
+}
+
+impl<C: Chunk, R: Read + Seek> ZipRegionLoader<C, R> {
+    pub fn new(reader: R, region_dir: &str) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(zip_error)?;
+
+        let mut regions = HashMap::new();
+        for i in 0..archive.len() {
+            // Only the names are needed, so don't decompress anything yet.
+            let file = archive.by_index_raw(i).map_err(zip_error)?;
+            if file.size() == 0 {
+                continue;
+            }
+            if let Some(coords) = region_coords(file.name(), region_dir) {
+                regions.insert(coords, i);
+            }
+        }
+
+        Ok(Self {
+            archive: Mutex::new(archive),
+            regions,
+            p: PhantomData,
+        })
+    }
+}
+
+impl<C, R> RegionLoader<C> for ZipRegionLoader<C, R>
+where
+    C: Chunk + DeserializeOwned,
+    R: Read + Seek + Send,
+{
+    type RegionType = RegionBuffer<Cursor<Vec<u8>>>;
+
+    fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
+        let index = *self.regions.get(&(x, z))?;
+        let mut archive = self.archive.lock().unwrap();
+        let mut file = archive.by_index(index).ok()?;
+
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data).ok()?;
+        Some(RegionBuffer::new(Cursor::new(data)))
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        let mut regions: Vec<_> = self.regions.keys().copied().collect();
+        regions.sort_by_key(|(x, z)| (x.0, z.0));
+        Ok(regions)
+    }
+}
+
+impl<C: Chunk> RegionBytesLoader<C> {
+    /// Read the regions in `region_dir` out of a tar archive, such as a
+    /// `world.tar.gz` backup, where `region_dir` is eg `world/region`.
+    /// Gzipped archives are detected and decompressed. Tar archives can only
+    /// be read from start to end, so every region is read into memory.
+    pub fn from_tar(reader: impl Read, region_dir: &str) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+        if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+            read_tar(GzDecoder::new(reader), region_dir)
+        } else {
+            read_tar(reader, region_dir)
+        }
+    }
+}
+
+fn read_tar<C: Chunk>(reader: impl Read, region_dir: &str) -> Result<RegionBytesLoader<C>> {
+    let mut loader = RegionBytesLoader::new();
+    let mut archive = tar::Archive::new(reader);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+        let coords = path
+            .to_str()
+            .and_then(|name| region_coords(name, region_dir));
+
+        if let Some((x, z)) = coords {
+            let mut data = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut data)?;
+            loader.insert(x, z, data);
+        }
+    }
+
+    Ok(loader)
+}
+
+/// The coordinates of the region an archive entry is for, if it is a region
+/// file directly in `region_dir`. Archives often have paths starting with
+/// `./`, so this is ignored, as are slashes at either end.
+fn region_coords(name: &str, region_dir: &str) -> Option<(RCoord, RCoord)> {
+    let trim = |dir: &str| dir.trim_start_matches("./").trim_matches('/').to_owned();
+
+    let (dir, file) = name.rsplit_once('/').unwrap_or(("", name));
+    if trim(dir) != trim(region_dir) || !file.starts_with("r.") || !file.ends_with(".mca") {
+        return None;
+    }
+
+    coords_from_region(Path::new(file))
+}
+
+fn zip_error(err: zip::result::ZipError) -> Error {
+    match err {
+        zip::result::ZipError::Io(err) => Error::IO(err),
+        err => Error::Archive(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use zip::write::FileOptions;
+
+    use super::*;
+    use crate::JavaChunk;
+
+    fn region_data(chunk: &[u8]) -> Vec<u8> {
+        let mut region = RegionBuffer::new(Cursor::new(Vec::new()));
+        region.write_chunk(3, 4, chunk).unwrap();
+        region.into_inner().into_inner()
+    }
+
+    fn files() -> Vec<(&'static str, Vec<u8>)> {
+        vec![
+            ("world/level.dat", vec![1, 2]),
+            ("world/region/r.0.-1.mca", region_data(&[1, 2, 3])),
+            ("world/region/r.2.2.mca", vec![]),
+            ("world/region/c.5.6.mcc", vec![1]),
+            ("world/DIM-1/region/r.1.1.mca", region_data(&[4, 5])),
+        ]
+    }
+
+    #[test]
+    fn loads_regions_from_zip() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files() {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(&data).unwrap();
+        }
+        let zip = zip.finish().unwrap();
+
+        let loader = ZipRegionLoader::<JavaChunk, _>::new(zip.clone(), "world/region").unwrap();
+        assert_eq!(vec![(RCoord(0), RCoord(-1))], loader.list().unwrap());
+        let region = loader.region(RCoord(0), RCoord(-1)).unwrap();
+        assert_eq!(vec![1, 2, 3], region.load_chunk(3, 4).unwrap());
+        assert!(loader.region(RCoord(1), RCoord(1)).is_none());
+
+        let nether = ZipRegionLoader::<JavaChunk, _>::new(zip, "./world/DIM-1/region/").unwrap();
+        assert_eq!(vec![(RCoord(1), RCoord(1))], nether.list().unwrap());
+
+        let not_zip = ZipRegionLoader::<JavaChunk, _>::new(Cursor::new(vec![0; 64]), "region");
+        assert!(matches!(not_zip, Err(Error::Archive(_))));
+    }
+
+    #[test]
+    fn loads_regions_from_tar() {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (name, data) in files() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            tar.append_data(&mut header, format!("./{}", name), &data[..])
+                .unwrap();
+        }
+        let tar = tar.into_inner().unwrap();
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+
+        for archive in [tar, gz] {
+            let loader =
+                RegionBytesLoader::<JavaChunk>::from_tar(&archive[..], "world/region").unwrap();
+            assert_eq!(vec![(RCoord(0), RCoord(-1))], loader.list().unwrap());
+            let region = loader.region(RCoord(0), RCoord(-1)).unwrap();
+            assert_eq!(vec![1, 2, 3], region.load_chunk(3, 4).unwrap());
+        }
+    }
+}
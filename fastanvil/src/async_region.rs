@@ -0,0 +1,249 @@
+use std::io::SeekFrom;
+
+use byteorder::{BigEndian, ByteOrder};
+use futures_util::stream::{self, Stream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::{decompress_chunk, ChunkLocation, ChunkMeta, Error, Result, SECTOR_SIZE};
+
+/// An asynchronous version of [`RegionBuffer`](crate::RegionBuffer), for
+/// reading regions with tokio without blocking. Only reading is supported,
+/// and chunks stored in separate `.mcc` files cannot be read.
+///
+/// Decompression of chunks is done on the calling task. Chunks are small
+/// enough that this is usually fine, but consider `spawn_blocking` for
+/// deserializing them.
+///
+/// ```no_run
+/// # use fastanvil::AsyncRegionBuffer;
+/// # async fn run() -> fastanvil::Result<()> {
+/// let file = tokio::fs::File::open("r.0.0.mca").await?;
+/// let mut region = AsyncRegionBuffer::new(file);
+///
+/// let data = region.load_chunk(0, 0).await?;
+/// println!("chunk 0, 0 is {} bytes", data.len());
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncRegionBuffer<S> {
+    data: S,
+}
+
+impl<S: AsyncRead + AsyncSeek + Unpin> AsyncRegionBuffer<S> {
+    pub fn new(data: S) -> Self {
+        Self { data }
+    }
+
+    /// Take the underlying data of the region.
+    pub fn into_inner(self) -> S {
+        self.data
+    }
+
+    /// Return the (region-relative) Chunk location (x, z)
+    pub async fn chunk_location(&mut self, x: usize, z: usize) -> Result<ChunkLocation> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let pos = 4 * (x + z * 32);
+        self.data.seek(SeekFrom::Start(pos as u64)).await?;
+        let location = self.data.read_u32().await?;
+
+        Ok(ChunkLocation {
+            begin_sector: (location >> 8) as usize,
+            sector_count: (location & 0xff) as usize,
+            x,
+            z,
+        })
+    }
+
+    /// Return the last modification time of the chunk at the (region-relative)
+    /// Chunk location (x, z), in seconds since the Unix epoch.
+    pub async fn chunk_timestamp(&mut self, x: usize, z: usize) -> Result<u32> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let pos = SECTOR_SIZE + 4 * (x + z * 32);
+        self.data.seek(SeekFrom::Start(pos as u64)).await?;
+        Ok(self.data.read_u32().await?)
+    }
+
+    /// Return the raw, uncompressed NBT data for a chunk at the
+    /// (region-relative) Chunk location (x, z).
+    pub async fn load_chunk(&mut self, x: usize, z: usize) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.load_raw_chunk_into(x, z, &mut buf).await?;
+        decompress_chunk(&buf)
+    }
+
+    /// Read the raw, compressed data for a chunk at the (region-relative)
+    /// Chunk location (x, z) into `dest`, replacing its contents. See
+    /// [`RegionBuffer::load_raw_chunk_into`](crate::RegionBuffer::load_raw_chunk_into).
+    pub async fn load_raw_chunk_into(
+        &mut self,
+        x: usize,
+        z: usize,
+        dest: &mut Vec<u8>,
+    ) -> Result<()> {
+        let location = self.chunk_location(x, z).await?;
+
+        // 0,0 chunk location means the chunk isn't present.
+        if location.begin_sector == 0 || location.sector_count == 0 {
+            return Err(Error::ChunkNotFound);
+        }
+
+        self.load_raw_chunk(&location, dest).await
+    }
+
+    /// Stream each uncompressed, non-empty chunk in the region, giving
+    /// `(x, z, data)`. Chunks are visited in the order they appear in the
+    /// file. The header is read when the stream is first polled, if that
+    /// fails the stream gives the error and ends.
+    ///
+    /// ```no_run
+    /// # use fastanvil::AsyncRegionBuffer;
+    /// use futures_util::StreamExt;
+    ///
+    /// # async fn run() -> fastanvil::Result<()> {
+    /// let file = tokio::fs::File::open("r.0.0.mca").await?;
+    /// let mut region = AsyncRegionBuffer::new(file);
+    ///
+    /// let mut chunks = Box::pin(region.chunks());
+    /// while let Some(chunk) = chunks.next().await {
+    ///     let (x, z, data) = chunk?;
+    ///     println!("chunk {}, {} is {} bytes", x, z, data.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chunks(&mut self) -> impl Stream<Item = Result<(usize, usize, Vec<u8>)>> + '_ {
+        stream::unfold((self, None), |(region, locations)| async move {
+            let mut locations: Vec<ChunkLocation> = match locations {
+                Some(locations) => locations,
+                None => match region.present_chunk_locations().await {
+                    Ok(mut locations) => {
+                        // Stored in reverse so we can pop from the end.
+                        locations.reverse();
+                        locations
+                    }
+                    Err(e) => return Some((Err(e), (region, Some(vec![])))),
+                },
+            };
+
+            let loc = locations.pop()?;
+            let mut buf = Vec::new();
+            let chunk = match region.load_raw_chunk(&loc, &mut buf).await {
+                Ok(()) => decompress_chunk(&buf).map(|data| (loc.x, loc.z, data)),
+                Err(e) => Err(e),
+            };
+
+            Some((chunk, (region, Some(locations))))
+        })
+    }
+
+    /// Locations of all the chunks present in the region, in the order they
+    /// appear in the file.
+    async fn present_chunk_locations(&mut self) -> Result<Vec<ChunkLocation>> {
+        let mut header = vec![0; SECTOR_SIZE];
+        self.data.seek(SeekFrom::Start(0)).await?;
+        self.data.read_exact(&mut header).await?;
+
+        let mut locations = Vec::new();
+        for (i, location) in header.chunks_exact(4).enumerate() {
+            let location = BigEndian::read_u32(location);
+            let loc = ChunkLocation {
+                begin_sector: (location >> 8) as usize,
+                sector_count: (location & 0xff) as usize,
+                x: i % 32,
+                z: i / 32,
+            };
+
+            // 0,0 chunk location means the chunk isn't present.
+            if loc.begin_sector != 0 && loc.sector_count != 0 {
+                locations.push(loc);
+            }
+        }
+
+        locations.sort_by_key(|loc| loc.begin_sector);
+        Ok(locations)
+    }
+
+    async fn load_raw_chunk(&mut self, location: &ChunkLocation, dest: &mut Vec<u8>) -> Result<()> {
+        self.data
+            .seek(SeekFrom::Start(
+                location.begin_sector as u64 * SECTOR_SIZE as u64,
+            ))
+            .await?;
+
+        dest.resize(5, 0);
+        self.data.read_exact(&mut dest[..5]).await?;
+        let metadata = ChunkMeta::new(&dest[..5])?;
+        if metadata.external {
+            return Err(Error::ExternalChunkUnavailable);
+        }
+
+        dest.resize(5 + metadata.compressed_len as usize, 0);
+        self.data.read_exact(&mut dest[5..]).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::Builder;
+
+    fn region() -> AsyncRegionBuffer<std::io::Cursor<Vec<u8>>> {
+        // The chunk at (1, 0) comes first in the file.
+        let data = Builder::new()
+            .location(3, 1)
+            .location(2, 1)
+            .timestamp(1, 100)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4, 5])
+            .build();
+        AsyncRegionBuffer::new(data)
+    }
+
+    #[tokio::test]
+    async fn load_chunk() -> Result<()> {
+        let mut r = region();
+
+        assert_eq!(3, r.chunk_location(0, 0).await?.begin_sector);
+        assert_eq!(100, r.chunk_timestamp(1, 0).await?);
+        assert_eq!(vec![4, 5], r.load_chunk(0, 0).await?);
+        assert_eq!(vec![1, 2, 3], r.load_chunk(1, 0).await?);
+        assert!(matches!(
+            r.load_chunk(2, 0).await,
+            Err(Error::ChunkNotFound)
+        ));
+        assert!(matches!(
+            r.chunk_location(32, 0).await,
+            Err(Error::InvalidOffset(32, 0))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunks() -> Result<()> {
+        let mut r = region();
+
+        let chunks: Vec<_> = r.chunks().collect().await;
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(chunks, vec![(1, 0, vec![1, 2, 3]), (0, 0, vec![4, 5])]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunks_header_error() {
+        let data = Builder::new().location(2, 1).build_unpadded();
+        let mut r = AsyncRegionBuffer::new(data);
+
+        let chunks: Vec<_> = r.chunks().collect().await;
+        assert_eq!(1, chunks.len());
+        assert!(matches!(chunks[0], Err(Error::IO(_))));
+    }
+}
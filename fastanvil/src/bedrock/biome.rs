@@ -0,0 +1,104 @@
+use crate::biome::Biome;
+
+/// The biome for a Bedrock numeric biome ID. Bedrock's IDs match Java's
+/// legacy IDs for the older biomes, but differ for the oceans and for
+/// everything added since 1.16.
+///
+/// Values from https://minecraft.wiki/w/Biome/ID#Bedrock_Edition
+pub(crate) fn biome_from_id(id: i32) -> Option<Biome> {
+    use Biome::*;
+
+    let biome = match id {
+        0 => Ocean,
+        1 => Plains,
+        2 => Desert,
+        3 => Mountains,
+        4 => Forest,
+        5 => Taiga,
+        6 => Swamp,
+        7 => River,
+        8 => Nether,
+        9 => TheEnd,
+        // Unused since the ocean update, but still in old worlds.
+        10 => FrozenOcean,
+        11 => FrozenRiver,
+        12 => SnowyTundra,
+        13 => SnowyMountains,
+        14 => MushroomFields,
+        15 => MushroomFieldShore,
+        16 => Beach,
+        17 => DesertHills,
+        18 => WoodedHills,
+        19 => TaigaHills,
+        20 => MountainEdge,
+        21 => Jungle,
+        22 => JungleHills,
+        23 => JungleEdge,
+        24 => DeepOcean,
+        25 => StoneShore,
+        26 => SnowyBeach,
+        27 => BirchForest,
+        28 => BirchForestHills,
+        29 => DarkForest,
+        30 => SnowyTaiga,
+        31 => SnowyTaigaHills,
+        32 => GiantTreeTaiga,
+        33 => GiantTreeTaigaHills,
+        34 => WoodedMountains,
+        35 => Savanna,
+        36 => SavannaPlateau,
+        37 => Badlands,
+        38 => WoodedBadlandsPlateau,
+        39 => BadlandsPlateau,
+        40 => WarmOcean,
+        41 => DeepWarmOcean,
+        42 => LukewarmOcean,
+        43 => DeepLukewarmOcean,
+        44 => ColdOcean,
+        45 => DeepColdOcean,
+        46 => FrozenOcean,
+        47 => DeepFrozenOcean,
+        48 => BambooJungle,
+        49 => BambooJungleHills,
+        129 => SunflowerPlains,
+        130 => DesertLakes,
+        131 => GravellyMountains,
+        132 => FlowerForest,
+        133 => TaigaMountains,
+        134 => SwampHills,
+        140 => IceSpikes,
+        149 => ModifiedJungle,
+        151 => ModifiedJungleEdge,
+        155 => TallBirchForest,
+        156 => TallBirchHills,
+        157 => DarkForestHills,
+        158 => SnowyTaigaMountains,
+        160 => GiantSpruceTaiga,
+        161 => GiantSpruceTaigaHills,
+        162 => ModifiedGravellyMountains,
+        163 => ShatteredSavanna,
+        164 => ShatteredSavannaPlateau,
+        165 => ErodedBadlands,
+        166 => ModifiedWoodedBadlandsPlateau,
+        167 => ModifiedBadlandsPlateau,
+        178 => SoulSandValley,
+        179 => CrimsonForest,
+        180 => WarpedForest,
+        181 => BasaltDeltas,
+        182 => JaggedPeaks,
+        183 => FrozenPeaks,
+        184 => SnowySlopes,
+        185 => Grove,
+        186 => Meadow,
+        187 => LushCaves,
+        188 => DripstoneCaves,
+        189 => StonyPeaks,
+        190 => DeepDark,
+        191 => MangroveSwamp,
+        192 => CherryGrove,
+        193 => PaleGarden,
+        _ => return None,
+    };
+
+    Some(biome)
+}
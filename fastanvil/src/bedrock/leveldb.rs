@@ -0,0 +1,722 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Mutex;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::read::{DeflateDecoder, ZlibDecoder};
+
+use crate::{Error, Result};
+
+use super::BedrockDb;
+
+const TABLE_MAGIC: u64 = 0xdb47_7524_8b80_fb57;
+const FOOTER_LEN: usize = 48;
+const LOG_BLOCK_LEN: usize = 32 * 1024;
+const LOG_HEADER_LEN: usize = 7;
+
+// Compression of table blocks. Mojang's LevelDB adds the two zlib types.
+const NO_COMPRESSION: u8 = 0;
+const ZLIB_COMPRESSION: u8 = 2;
+const ZLIB_RAW_COMPRESSION: u8 = 4;
+
+/// A read-only view of the LevelDB database of a Bedrock world, the `db`
+/// folder of the world. This reads the format written by Mojang's fork of
+/// LevelDB, which compresses tables with zlib.
+///
+/// The database is opened as it was when [`open`](Self::open) was called, so
+/// the game should not be running on the world. Blocks of the tables that
+/// fail their checksum are treated as missing, as are records of the logs.
+///
+/// ```no_run
+/// use fastanvil::bedrock::{BedrockLoader, LevelDb};
+/// use fastanvil::Dimension;
+///
+/// let db = LevelDb::open("world/db")?;
+/// let dimension = Dimension::new(BedrockLoader::new(db));
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct LevelDb {
+    /// Writes from the log files that haven't been compacted into tables yet.
+    memtable: BTreeMap<Vec<u8>, Entry>,
+    tables: Vec<Table>,
+}
+
+/// The sequence number of a write, and the value written or `None` for a
+/// deletion.
+type Entry = (u64, Option<Vec<u8>>);
+
+impl LevelDb {
+    /// Open the database in the folder `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let dir = path.as_ref();
+        let current = fs::read_to_string(dir.join("CURRENT"))?;
+        let manifest = fs::read(dir.join(current.trim()))?;
+
+        let mut version = Version::default();
+        for record in log_records(&manifest) {
+            version.apply(&record)?;
+        }
+
+        let tables = version
+            .files
+            .iter()
+            .map(|(&number, (smallest, largest))| {
+                Table::open(dir, number, smallest.clone(), largest.clone())
+            })
+            .collect::<Result<_>>()?;
+
+        // Logs older than the manifest's log number have already been
+        // compacted into tables.
+        let mut logs: Vec<u64> = fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                let number = name.to_str()?.strip_suffix(".log")?.parse().ok()?;
+                if number >= version.log_number || Some(number) == version.prev_log_number {
+                    Some(number)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        logs.sort_unstable();
+
+        let mut memtable = BTreeMap::new();
+        for number in logs {
+            let log = fs::read(dir.join(format!("{:06}.log", number)))?;
+            for record in log_records(&log) {
+                apply_batch(&record, &mut memtable)?;
+            }
+        }
+
+        Ok(Self { memtable, tables })
+    }
+}
+
+impl BedrockDb for LevelDb {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let mut latest = self.memtable.get(key).cloned();
+
+        for table in &self.tables {
+            if key < &table.smallest[..] || key > &table.largest[..] {
+                continue;
+            }
+            match (table.get(key), &latest) {
+                (Some(entry), Some((seq, _))) if entry.0 < *seq => {}
+                (Some(entry), _) => latest = Some(entry),
+                (None, _) => {}
+            }
+        }
+
+        latest?.1
+    }
+
+    fn keys(&self) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        // Only the latest write of each key counts, which might be a
+        // deletion.
+        let mut latest: BTreeMap<Vec<u8>, (u64, bool)> = self
+            .memtable
+            .iter()
+            .map(|(key, (seq, value))| (key.clone(), (*seq, value.is_some())))
+            .collect();
+
+        for table in &self.tables {
+            for (_, handle) in &table.index {
+                let block = match table.block(*handle) {
+                    Some(block) => block,
+                    None => continue,
+                };
+                for (key, _) in block_entries(&block) {
+                    let (user_key, seq, present) = match split_internal_key(&key) {
+                        Some(parts) => parts,
+                        None => continue,
+                    };
+                    let entry = latest.entry(user_key.to_vec()).or_insert((0, false));
+                    if seq >= entry.0 {
+                        *entry = (seq, present);
+                    }
+                }
+            }
+        }
+
+        Box::new(
+            latest
+                .into_iter()
+                .filter(|(_, (_, present))| *present)
+                .map(|(key, _)| key),
+        )
+    }
+}
+
+/// The live files of the database, from replaying the edits in the
+/// manifest.
+#[derive(Default)]
+struct Version {
+    log_number: u64,
+    prev_log_number: Option<u64>,
+    /// The smallest and largest user keys of each table.
+    files: BTreeMap<u64, (Vec<u8>, Vec<u8>)>,
+}
+
+impl Version {
+    fn apply(&mut self, mut edit: &[u8]) -> Result<()> {
+        let bad = || Error::LevelDb("invalid manifest".to_owned());
+
+        while !edit.is_empty() {
+            match read_varint(&mut edit).ok_or_else(bad)? {
+                // Comparator name.
+                1 => {
+                    read_slice(&mut edit).ok_or_else(bad)?;
+                }
+                2 => self.log_number = read_varint(&mut edit).ok_or_else(bad)?,
+                // Next file number and last sequence number.
+                3 | 4 => {
+                    read_varint(&mut edit).ok_or_else(bad)?;
+                }
+                // Compaction pointer.
+                5 => {
+                    read_varint(&mut edit).ok_or_else(bad)?;
+                    read_slice(&mut edit).ok_or_else(bad)?;
+                }
+                6 => {
+                    let _level = read_varint(&mut edit).ok_or_else(bad)?;
+                    let number = read_varint(&mut edit).ok_or_else(bad)?;
+                    self.files.remove(&number);
+                }
+                7 => {
+                    let _level = read_varint(&mut edit).ok_or_else(bad)?;
+                    let number = read_varint(&mut edit).ok_or_else(bad)?;
+                    let _size = read_varint(&mut edit).ok_or_else(bad)?;
+                    let smallest = read_slice(&mut edit).ok_or_else(bad)?;
+                    let largest = read_slice(&mut edit).ok_or_else(bad)?;
+                    let (smallest, _, _) = split_internal_key(smallest).ok_or_else(bad)?;
+                    let (largest, _, _) = split_internal_key(largest).ok_or_else(bad)?;
+                    self.files
+                        .insert(number, (smallest.to_vec(), largest.to_vec()));
+                }
+                9 => self.prev_log_number = Some(read_varint(&mut edit).ok_or_else(bad)?),
+                tag => return Err(Error::LevelDb(format!("unknown manifest tag: {}", tag))),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A sorted table file, with its index read up front.
+struct Table {
+    file: Mutex<File>,
+    len: u64,
+    smallest: Vec<u8>,
+    largest: Vec<u8>,
+    /// For each data block, a user key at or after the last key of the block
+    /// and before the first key of the next.
+    index: Vec<(Vec<u8>, BlockHandle)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BlockHandle {
+    offset: u64,
+    size: u64,
+}
+
+impl Table {
+    fn open(dir: &Path, number: u64, smallest: Vec<u8>, largest: Vec<u8>) -> Result<Self> {
+        // Older versions of LevelDB named tables `.sst`.
+        let path = dir.join(format!("{:06}.ldb", number));
+        let path = if path.exists() {
+            path
+        } else {
+            dir.join(format!("{:06}.sst", number))
+        };
+
+        let mut file = File::open(path)?;
+        let len = file.seek(SeekFrom::End(0))?;
+        if len < FOOTER_LEN as u64 {
+            return Err(Error::LevelDb(format!("table {} is too short", number)));
+        }
+        let mut footer = [0; FOOTER_LEN];
+        file.seek(SeekFrom::Start(len - FOOTER_LEN as u64))?;
+        file.read_exact(&mut footer)?;
+
+        let magic = (&footer[FOOTER_LEN - 8..]).read_u64::<LittleEndian>()?;
+        let mut handles = &footer[..];
+        let index = read_handle(&mut handles)
+            .and_then(|_metaindex| read_handle(&mut handles))
+            .filter(|_| magic == TABLE_MAGIC)
+            .ok_or_else(|| Error::LevelDb(format!("table {} has an invalid footer", number)))?;
+
+        let mut table = Self {
+            file: Mutex::new(file),
+            len,
+            smallest,
+            largest,
+            index: Vec::new(),
+        };
+
+        let index = table
+            .block(index)
+            .ok_or_else(|| Error::LevelDb(format!("table {} has an invalid index", number)))?;
+        table.index = block_entries(&index)
+            .into_iter()
+            .filter_map(|(key, mut value)| {
+                let key = split_internal_key(&key)?.0.to_vec();
+                Some((key, read_handle(&mut value)?))
+            })
+            .collect();
+
+        Ok(table)
+    }
+
+    /// The latest write of `key` in this table.
+    fn get(&self, key: &[u8]) -> Option<Entry> {
+        // Writes of the same key are sorted newest first, so the latest is
+        // in the first block that can hold the key.
+        let (_, handle) = self.index.iter().find(|(last, _)| &last[..] >= key)?;
+        let block = self.block(*handle)?;
+
+        block_entries(&block).into_iter().find_map(|(k, value)| {
+            let (user_key, seq, present) = split_internal_key(&k)?;
+            if user_key != key {
+                return None;
+            }
+            Some((seq, if present { Some(value.to_vec()) } else { None }))
+        })
+    }
+
+    /// Read and decompress a block.
+    fn block(&self, handle: BlockHandle) -> Option<Vec<u8>> {
+        // Blocks are followed by their compression type and a checksum. The
+        // handle is read from the file, so check it before allocating.
+        let len = handle.size.checked_add(5)?;
+        if handle.offset.checked_add(len)? > self.len {
+            return None;
+        }
+
+        let mut data = vec![0; len as usize];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(handle.offset)).ok()?;
+            file.read_exact(&mut data).ok()?;
+        }
+
+        let size = handle.size as usize;
+        let expected = (&data[size + 1..]).read_u32::<LittleEndian>().ok()?;
+        if checksum(&[&data[..size + 1]]) != expected {
+            return None;
+        }
+
+        let compression = data[size];
+        data.truncate(size);
+
+        let mut out = Vec::new();
+        match compression {
+            NO_COMPRESSION => return Some(data),
+            ZLIB_COMPRESSION => ZlibDecoder::new(&data[..]).read_to_end(&mut out).ok()?,
+            ZLIB_RAW_COMPRESSION => DeflateDecoder::new(&data[..]).read_to_end(&mut out).ok()?,
+            // Snappy is never used by the game.
+            _ => return None,
+        };
+        Some(out)
+    }
+}
+
+/// The entries of a table block, as full keys and their values. Keys share
+/// a prefix with the key before them, so they have to be read in order.
+fn block_entries(block: &[u8]) -> Vec<(Vec<u8>, &[u8])> {
+    let mut entries = Vec::new();
+
+    let restarts = match block.len().checked_sub(4) {
+        Some(at) => (&block[at..]).read_u32::<LittleEndian>().unwrap() as usize,
+        None => return entries,
+    };
+    let end = match block.len().checked_sub(4 + 4 * restarts) {
+        Some(end) => end,
+        None => return entries,
+    };
+
+    let mut data = &block[..end];
+    let mut key: Vec<u8> = Vec::new();
+    while !data.is_empty() {
+        let header = (|| {
+            let shared = read_varint(&mut data)? as usize;
+            let unshared = read_varint(&mut data)? as usize;
+            let value_len = read_varint(&mut data)? as usize;
+            Some((shared, unshared, value_len))
+        })();
+        let (shared, unshared, value_len) = match header {
+            Some(header) if header.0 <= key.len() && header.1 + header.2 <= data.len() => header,
+            _ => break,
+        };
+
+        key.truncate(shared);
+        key.extend_from_slice(&data[..unshared]);
+        entries.push((key.clone(), &data[unshared..unshared + value_len]));
+        data = &data[unshared + value_len..];
+    }
+
+    entries
+}
+
+/// Split a key as stored in tables into the user's key, its sequence number
+/// and whether it is a value rather than a deletion.
+fn split_internal_key(key: &[u8]) -> Option<(&[u8], u64, bool)> {
+    let at = key.len().checked_sub(8)?;
+    let trailer = (&key[at..]).read_u64::<LittleEndian>().ok()?;
+    Some((&key[..at], trailer >> 8, trailer & 0xff == 1))
+}
+
+/// The records of a log file, as used for the manifest and for recent
+/// writes. Records are split into fragments so that they don't cross the
+/// 32KiB blocks of the file. A truncated record at the end is left out, and
+/// like LevelDB a fragment with a bad checksum drops the rest of its block.
+fn log_records(mut data: &[u8]) -> Vec<Vec<u8>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+
+    while !data.is_empty() {
+        let (mut block, rest) = data.split_at(data.len().min(LOG_BLOCK_LEN));
+        data = rest;
+
+        while block.len() >= LOG_HEADER_LEN {
+            let expected = (&block[..4]).read_u32::<LittleEndian>().unwrap();
+            let len = (&block[4..6]).read_u16::<LittleEndian>().unwrap() as usize;
+            let kind = block[6];
+            let fragment = match block.get(LOG_HEADER_LEN..LOG_HEADER_LEN + len) {
+                Some(fragment) => fragment,
+                None => return records,
+            };
+            block = &block[LOG_HEADER_LEN + len..];
+
+            // Padding at the end of a block has no checksum.
+            if kind != 0 && checksum(&[&[kind], fragment]) != expected {
+                record.clear();
+                break;
+            }
+
+            match kind {
+                // Whole record.
+                1 => records.push(fragment.to_vec()),
+                // First, middle and last fragments.
+                2 => record = fragment.to_vec(),
+                3 => record.extend_from_slice(fragment),
+                4 => {
+                    record.extend_from_slice(fragment);
+                    records.push(std::mem::take(&mut record));
+                }
+                // Padding to the end of the block.
+                _ => break,
+            }
+        }
+    }
+
+    records
+}
+
+/// Apply a batch of writes from a log file.
+fn apply_batch(mut batch: &[u8], memtable: &mut BTreeMap<Vec<u8>, Entry>) -> Result<()> {
+    let bad = || Error::LevelDb("invalid write batch".to_owned());
+
+    let seq = batch.read_u64::<LittleEndian>().map_err(|_| bad())?;
+    let count = batch.read_u32::<LittleEndian>().map_err(|_| bad())?;
+
+    for i in 0..count as u64 {
+        let kind = batch.read_u8().map_err(|_| bad())?;
+        let key = read_slice(&mut batch).ok_or_else(bad)?.to_vec();
+        let value = match kind {
+            0 => None,
+            1 => Some(read_slice(&mut batch).ok_or_else(bad)?.to_vec()),
+            _ => return Err(bad()),
+        };
+        memtable.insert(key, (seq + i, value));
+    }
+
+    Ok(())
+}
+
+/// The CRC-32C of `data`, masked as LevelDB stores it.
+fn checksum(data: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data.iter().flat_map(|part| part.iter()) {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    (!crc).rotate_right(15).wrapping_add(0xa282_ead8)
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = data.read_u8().ok()?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Read a slice prefixed by its length.
+fn read_slice<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = read_varint(data)? as usize;
+    if len > data.len() {
+        return None;
+    }
+    let (slice, rest) = data.split_at(len);
+    *data = rest;
+    Some(slice)
+}
+
+fn read_handle(data: &mut &[u8]) -> Option<BlockHandle> {
+    Some(BlockHandle {
+        offset: read_varint(data)?,
+        size: read_varint(data)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    fn varint(mut v: u64, out: &mut Vec<u8>) {
+        while v >= 0x80 {
+            out.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn slice(s: &[u8], out: &mut Vec<u8>) {
+        varint(s.len() as u64, out);
+        out.extend_from_slice(s);
+    }
+
+    fn internal_key(key: &[u8], seq: u64, present: bool) -> Vec<u8> {
+        let mut k = key.to_vec();
+        k.extend_from_slice(&(seq << 8 | present as u64).to_le_bytes());
+        k
+    }
+
+    /// A block with a restart point at every entry.
+    fn block(entries: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+        let mut block = Vec::new();
+        let mut restarts = Vec::new();
+        for (key, value) in entries {
+            restarts.push(block.len() as u32);
+            varint(0, &mut block);
+            varint(key.len() as u64, &mut block);
+            varint(value.len() as u64, &mut block);
+            block.extend_from_slice(key);
+            block.extend_from_slice(value);
+        }
+        restarts.push(block.len() as u32);
+        for restart in &restarts {
+            block.extend(restart.to_le_bytes());
+        }
+        block.extend((restarts.len() as u32).to_le_bytes());
+        block
+    }
+
+    fn write_block(file: &mut Vec<u8>, block: &[u8], compress: bool) -> Vec<u8> {
+        let (data, kind) = if compress {
+            let mut enc = DeflateEncoder::new(Vec::new(), Compression::default());
+            enc.write_all(block).unwrap();
+            (enc.finish().unwrap(), ZLIB_RAW_COMPRESSION)
+        } else {
+            (block.to_vec(), NO_COMPRESSION)
+        };
+
+        let mut handle = Vec::new();
+        varint(file.len() as u64, &mut handle);
+        varint(data.len() as u64, &mut handle);
+        file.extend_from_slice(&data);
+        file.push(kind);
+        file.extend(checksum(&[&data, &[kind]]).to_le_bytes());
+        handle
+    }
+
+    /// A table with one data block per entry.
+    fn table(entries: &[(Vec<u8>, Vec<u8>)], compress: bool) -> Vec<u8> {
+        let mut file = Vec::new();
+        let mut index = Vec::new();
+        for entry in entries {
+            let handle = write_block(&mut file, &block(std::slice::from_ref(entry)), compress);
+            index.push((entry.0.clone(), handle));
+        }
+        let metaindex = write_block(&mut file, &block(&[]), false);
+        let index = write_block(&mut file, &block(&index), false);
+
+        let mut footer = metaindex;
+        footer.extend(index);
+        footer.resize(FOOTER_LEN - 8, 0);
+        footer.extend(TABLE_MAGIC.to_le_bytes());
+        file.extend(footer);
+        file
+    }
+
+    /// A log file of records, each fitting in one fragment.
+    fn log(records: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = Vec::new();
+        for record in records {
+            file.extend(checksum(&[&[1], record]).to_le_bytes());
+            file.extend((record.len() as u16).to_le_bytes());
+            file.push(1);
+            file.extend_from_slice(record);
+        }
+        file
+    }
+
+    fn batch(seq: u64, writes: &[(&[u8], Option<&[u8]>)]) -> Vec<u8> {
+        let mut batch = seq.to_le_bytes().to_vec();
+        batch.extend((writes.len() as u32).to_le_bytes());
+        for (key, value) in writes {
+            batch.push(value.is_some() as u8);
+            slice(key, &mut batch);
+            if let Some(value) = value {
+                slice(value, &mut batch);
+            }
+        }
+        batch
+    }
+
+    fn new_file(number: u64, smallest: &[u8], largest: &[u8], edit: &mut Vec<u8>) {
+        edit.extend([7, 0]);
+        varint(number, edit);
+        varint(100, edit);
+        slice(&internal_key(smallest, 1, true), edit);
+        slice(&internal_key(largest, 1, true), edit);
+    }
+
+    #[test]
+    fn checksum_is_masked_crc32c() {
+        // The CRC-32C of this is 0xe3069283.
+        assert_eq!(0xc78a_b0e5, checksum(&[b"1234", b"56789"]));
+    }
+
+    #[test]
+    fn corrupt_log_record_is_dropped() {
+        let mut file = log(&[batch(1, &[(b"a", Some(b"1"))])]);
+        assert_eq!(1, log_records(&file).len());
+
+        let last = file.len() - 1;
+        file[last] ^= 1;
+        assert!(log_records(&file).is_empty());
+    }
+
+    #[test]
+    fn corrupt_or_oversized_block_is_missing() {
+        let dir =
+            std::env::temp_dir().join(format!("fastanvil-leveldb-bad-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let key = internal_key(b"a", 1, true);
+        let mut file = table(&[(key.clone(), b"1".to_vec())], false);
+        fs::write(dir.join("000001.ldb"), &file).unwrap();
+        let good = Table::open(&dir, 1, b"a".to_vec(), b"a".to_vec()).unwrap();
+        assert!(good.get(b"a").is_some());
+
+        // The data block starts the file, its value is the byte before the
+        // restart points.
+        file[key.len() + 3] ^= 1;
+        fs::write(dir.join("000001.ldb"), &file).unwrap();
+        let corrupt = Table::open(&dir, 1, b"a".to_vec(), b"a".to_vec()).unwrap();
+        assert!(corrupt.get(b"a").is_none());
+
+        let huge = BlockHandle {
+            offset: 0,
+            size: u64::MAX - 1,
+        };
+        assert!(corrupt.block(huge).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reads_tables_and_logs() {
+        let dir = std::env::temp_dir().join(format!("fastanvil-leveldb-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, data: &[u8]| fs::write(dir.join(name), data).unwrap();
+
+        let entry = |k: &[u8], seq, v: &[u8]| (internal_key(k, seq, true), v.to_vec());
+        write(
+            "000003.ldb",
+            &table(
+                &[
+                    entry(b"a", 1, b"1"),
+                    entry(b"b", 5, b"new"),
+                    entry(b"b", 2, b"old"),
+                    entry(b"c", 3, b"3"),
+                ],
+                true,
+            ),
+        );
+        write("000004.ldb", &table(&[entry(b"d", 4, b"4")], false));
+        write("000006.sst", &table(&[entry(b"e", 4, b"5")], false));
+
+        let mut edit = Vec::new();
+        edit.push(1);
+        slice(b"leveldb.BytewiseComparator", &mut edit);
+        edit.push(2);
+        varint(5, &mut edit);
+        new_file(3, b"a", b"c", &mut edit);
+        new_file(4, b"d", b"d", &mut edit);
+        let mut compaction = Vec::new();
+        new_file(6, b"e", b"e", &mut compaction);
+        compaction.extend([6, 0, 4]);
+        write("MANIFEST-000002", &log(&[edit, compaction]));
+        write("CURRENT", b"MANIFEST-000002\n");
+
+        // Log 1 has been compacted, only log 5 is live.
+        write("000001.log", &log(&[batch(1, &[(b"z", Some(b"old"))])]));
+        write(
+            "000005.log",
+            &log(&[batch(7, &[(b"c", None), (b"f", Some(b"6"))])]),
+        );
+
+        let db = LevelDb::open(&dir).unwrap();
+        assert_eq!(Some(b"1".to_vec()), db.get(b"a"));
+        assert_eq!(Some(b"new".to_vec()), db.get(b"b"));
+        assert_eq!(None, db.get(b"c"));
+        assert_eq!(None, db.get(b"d"));
+        assert_eq!(Some(b"5".to_vec()), db.get(b"e"));
+        assert_eq!(Some(b"6".to_vec()), db.get(b"f"));
+        assert_eq!(None, db.get(b"z"));
+
+        let keys: Vec<_> = db.keys().collect();
+        assert_eq!(
+            vec![b"a".to_vec(), b"b".to_vec(), b"e".to_vec(), b"f".to_vec()],
+            keys
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
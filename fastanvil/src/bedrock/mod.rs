@@ -0,0 +1,378 @@
+//! Support for reading Bedrock Edition worlds.
+//!
+//! Bedrock does not use region files. Instead all chunk data is stored in a
+//! LevelDB database (in the `db` folder of a world) under keys made from the
+//! chunk coordinates and the type of record. This module understands those
+//! keys and the palettized block storage of the records, and presents them
+//! through the same [`RegionLoader`]/[`Region`]/[`Chunk`] traits used for
+//! Java worlds, grouping chunks into 32x32 'regions'.
+//!
+//! Mojang uses a modified LevelDB with zlib compression that the common LevelDB
+//! crates cannot open. [`LevelDb`] reads it directly, without needing
+//! Mojang's library. Other sources of the keys and values can be used by
+//! implementing [`BedrockDb`], and an implementation is provided for a
+//! `HashMap`, which can be useful if the database has been exported by some
+//! other tool.
+//!
+//! Only chunks saved by versions since 1.2.13 are supported, older chunks
+//! store blocks by numeric ID and are treated as missing.
+//!
+//! ```no_run
+//! use fastanvil::bedrock::{BedrockChunk, BedrockLoader, LevelDb};
+//! use fastanvil::{CCoord, Chunk, Dimension, RCoord, Region};
+//!
+//! let db = LevelDb::open("world/db")?;
+//! let dimension = Dimension::new(BedrockLoader::new(db));
+//!
+//! let region = dimension.region(RCoord(0), RCoord(0)).unwrap();
+//! let chunk: BedrockChunk = region.chunk(CCoord(0), CCoord(0)).unwrap();
+//! println!("{:?}", chunk.block(0, 64, 0));
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::{TryFrom, TryInto};
+use std::ops::Range;
+use std::rc::Rc;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::biome::Biome;
+use crate::{
+    Block, CCoord, Chunk, ChunkStatus, HeightMode, LoaderResult, RCoord, Region, RegionLoader,
+};
+
+mod biome;
+mod leveldb;
+mod nbt;
+mod subchunk;
+
+pub use leveldb::LevelDb;
+pub use subchunk::SubChunk;
+
+use biome::biome_from_id;
+use subchunk::{index, read_storage};
+
+// Record types, the last byte of the key for a chunk.
+const DATA_3D: u8 = 43;
+const VERSION: u8 = 44;
+const DATA_2D: u8 = 45;
+const SUB_CHUNK_PREFIX: u8 = 47;
+const LEGACY_VERSION: u8 = 118;
+
+/// Access to the key-value store of a Bedrock world. See the [module
+/// docs](self) for more information.
+pub trait BedrockDb {
+    /// Get the value stored for a key.
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// All the keys in the database. This is used to find out which regions
+    /// exist.
+    fn keys(&self) -> Box<dyn Iterator<Item = Vec<u8>> + '_>;
+}
+
+impl BedrockDb for HashMap<Vec<u8>, Vec<u8>> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        HashMap::get(self, key).cloned()
+    }
+
+    fn keys(&self) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        Box::new(HashMap::keys(self).cloned())
+    }
+}
+
+/// The dimensions of a Bedrock world. Each is stored in the same database
+/// with a different key format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedrockDimension {
+    Overworld,
+    Nether,
+    End,
+}
+
+impl BedrockDimension {
+    fn id(self) -> Option<i32> {
+        match self {
+            BedrockDimension::Overworld => None,
+            BedrockDimension::Nether => Some(1),
+            BedrockDimension::End => Some(2),
+        }
+    }
+
+    /// Range of sub-chunk indexes that might be stored for this dimension.
+    fn sub_chunks(self) -> Range<i8> {
+        match self {
+            BedrockDimension::Overworld => -4..20,
+            _ => 0..16,
+        }
+    }
+
+    /// The lowest Y value of this dimension since 1.18.
+    fn min_y(self) -> isize {
+        match self {
+            BedrockDimension::Overworld => -64,
+            _ => 0,
+        }
+    }
+}
+
+fn chunk_key(x: i32, z: i32, dimension: BedrockDimension, record: u8) -> Vec<u8> {
+    let mut key = Vec::with_capacity(14);
+    key.extend_from_slice(&x.to_le_bytes());
+    key.extend_from_slice(&z.to_le_bytes());
+    if let Some(id) = dimension.id() {
+        key.extend_from_slice(&id.to_le_bytes());
+    }
+    key.push(record);
+    key
+}
+
+/// Loads 'regions' of 32x32 chunks from a Bedrock world.
+pub struct BedrockLoader<D: BedrockDb> {
+    db: Rc<D>,
+    dimension: BedrockDimension,
+}
+
+impl<D: BedrockDb> BedrockLoader<D> {
+    /// Create a loader for the overworld.
+    pub fn new(db: D) -> Self {
+        Self {
+            db: Rc::new(db),
+            dimension: BedrockDimension::Overworld,
+        }
+    }
+
+    /// Load a different dimension from the same database.
+    pub fn with_dimension(self, dimension: BedrockDimension) -> Self {
+        Self { dimension, ..self }
+    }
+}
+
+impl<D: BedrockDb> RegionLoader<BedrockChunk> for BedrockLoader<D> {
+    type RegionType = BedrockRegion<D>;
+
+    fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
+        Some(BedrockRegion {
+            db: Rc::clone(&self.db),
+            dimension: self.dimension,
+            x,
+            z,
+        })
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        let dim_len = if self.dimension.id().is_some() { 4 } else { 0 };
+        let mut regions = HashSet::new();
+
+        for key in self.db.keys() {
+            if key.len() != 9 + dim_len {
+                continue;
+            }
+
+            let record = key[8 + dim_len];
+            if record != VERSION && record != LEGACY_VERSION {
+                continue;
+            }
+
+            let mut k = key.as_slice();
+            let x = k.read_i32::<LittleEndian>().unwrap();
+            let z = k.read_i32::<LittleEndian>().unwrap();
+            if dim_len != 0 && Some(k.read_i32::<LittleEndian>().unwrap()) != self.dimension.id() {
+                continue;
+            }
+
+            regions.insert((
+                RCoord(x.div_euclid(32) as isize),
+                RCoord(z.div_euclid(32) as isize),
+            ));
+        }
+
+        Ok(regions.into_iter().collect())
+    }
+}
+
+/// A 32x32 group of chunks in a Bedrock world. Bedrock has no such concept,
+/// but it lets Bedrock worlds be used with [`Dimension`][crate::Dimension] and
+/// the renderers.
+pub struct BedrockRegion<D: BedrockDb> {
+    db: Rc<D>,
+    dimension: BedrockDimension,
+    x: RCoord,
+    z: RCoord,
+}
+
+impl<D: BedrockDb> Region<BedrockChunk> for BedrockRegion<D> {
+    fn chunk(&self, x: CCoord, z: CCoord) -> Option<BedrockChunk> {
+        let x = self.x.0 * 32 + x.0;
+        let z = self.z.0 * 32 + z.0;
+        BedrockChunk::load(&*self.db, self.dimension, x, z)
+    }
+}
+
+/// A chunk of a Bedrock world.
+#[derive(Debug)]
+pub struct BedrockChunk {
+    sub_chunks: BTreeMap<i8, SubChunk>,
+    heights: Option<Vec<isize>>,
+    biomes: Option<Biomes>,
+}
+
+#[derive(Debug)]
+enum Biomes {
+    // Before 1.18, a biome ID per column.
+    Flat(Vec<u8>),
+    // Since 1.18, palettized like blocks for each sub-chunk from the bottom
+    // of the world.
+    Cubic {
+        min_y: isize,
+        sub_chunks: Vec<(Vec<u16>, Vec<i32>)>,
+    },
+}
+
+impl BedrockChunk {
+    /// Load the chunk at the given (absolute) chunk coordinates. Returns
+    /// `None` if the chunk does not exist or is in an unsupported format.
+    pub fn load(
+        db: &impl BedrockDb,
+        dimension: BedrockDimension,
+        x: isize,
+        z: isize,
+    ) -> Option<Self> {
+        let x: i32 = x.try_into().ok()?;
+        let z: i32 = z.try_into().ok()?;
+
+        db.get(&chunk_key(x, z, dimension, VERSION))
+            .or_else(|| db.get(&chunk_key(x, z, dimension, LEGACY_VERSION)))?;
+
+        let mut sub_chunks = BTreeMap::new();
+        for y in dimension.sub_chunks() {
+            let mut key = chunk_key(x, z, dimension, SUB_CHUNK_PREFIX);
+            key.push(y as u8);
+
+            if let Some(data) = db.get(&key) {
+                sub_chunks.insert(y, SubChunk::from_bytes(&data)?);
+            }
+        }
+
+        let (heights, biomes) = if let Some(data) = db.get(&chunk_key(x, z, dimension, DATA_3D)) {
+            let min_y = dimension.min_y();
+            let (heights, rest) = read_heights(&data, min_y)?;
+            let biomes =
+                read_cubic_biomes(rest).map(|sub_chunks| Biomes::Cubic { min_y, sub_chunks });
+            (Some(heights), biomes)
+        } else if let Some(data) = db.get(&chunk_key(x, z, dimension, DATA_2D)) {
+            let (heights, rest) = read_heights(&data, 0)?;
+            let biomes = rest.get(..256).map(|b| Biomes::Flat(b.to_vec()));
+            (Some(heights), biomes)
+        } else {
+            (None, None)
+        };
+
+        Some(Self {
+            sub_chunks,
+            heights,
+            biomes,
+        })
+    }
+
+    /// Get the sub-chunk at the given index, each covering 16 blocks of height.
+    /// Index 0 covers Y values 0 to 15.
+    pub fn sub_chunk(&self, y: i8) -> Option<&SubChunk> {
+        self.sub_chunks.get(&y)
+    }
+
+    fn calculate_height(&self, x: usize, z: usize) -> isize {
+        let range = self.y_range();
+        for y in range.clone().rev() {
+            match self.block(x, y, z) {
+                Some(b) if b.name() != "minecraft:air" => return y + 1,
+                _ => {}
+            }
+        }
+        range.start
+    }
+}
+
+/// Read the 16x16 height map at the start of a Data2D or Data3D record,
+/// returning it and the rest of the data.
+fn read_heights(mut data: &[u8], min_y: isize) -> Option<(Vec<isize>, &[u8])> {
+    let heights = (0..256)
+        .map(|_| {
+            data.read_i16::<LittleEndian>()
+                .ok()
+                .map(|h| h as isize + min_y)
+        })
+        .collect::<Option<_>>()?;
+    Some((heights, data))
+}
+
+fn read_cubic_biomes(mut data: &[u8]) -> Option<Vec<(Vec<u16>, Vec<i32>)>> {
+    let mut sub_chunks: Vec<(Vec<u16>, Vec<i32>)> = vec![];
+
+    while !data.is_empty() {
+        // A header of all ones means 'same as the previous sub-chunk'.
+        if data[0] == 0xff {
+            data = &data[1..];
+            let prev = sub_chunks.last()?.clone();
+            sub_chunks.push(prev);
+            continue;
+        }
+
+        let (indices, palette_len) = read_storage(&mut data)?;
+        let palette = (0..palette_len)
+            .map(|_| data.read_i32::<LittleEndian>().ok())
+            .collect::<Option<Vec<_>>>()?;
+
+        if indices.iter().any(|i| *i as usize >= palette.len()) {
+            return None;
+        }
+        sub_chunks.push((indices, palette));
+    }
+
+    Some(sub_chunks)
+}
+
+impl Chunk for BedrockChunk {
+    fn status(&self) -> ChunkStatus {
+        // Bedrock only saves chunks once they are fully generated.
+        ChunkStatus::Full
+    }
+
+    fn surface_height(&self, x: usize, z: usize, mode: HeightMode) -> isize {
+        assert!(x < 16 && z < 16);
+
+        match (mode, &self.heights) {
+            (HeightMode::Trust, Some(heights)) => heights[z * 16 + x],
+            _ => self.calculate_height(x, z),
+        }
+    }
+
+    fn biome(&self, x: usize, y: isize, z: usize) -> Option<Biome> {
+        let id = match self.biomes.as_ref()? {
+            Biomes::Flat(ids) => ids[z * 16 + x] as i32,
+            Biomes::Cubic { min_y, sub_chunks } => {
+                let sub = usize::try_from((y - min_y).div_euclid(16)).ok()?;
+                let (indices, palette) = sub_chunks.get(sub)?;
+                let y = y.rem_euclid(16) as usize;
+                palette[indices[index(x, y, z)] as usize]
+            }
+        };
+
+        biome_from_id(id)
+    }
+
+    fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+        let sub = i8::try_from(y.div_euclid(16)).ok()?;
+        let sub = self.sub_chunks.get(&sub)?;
+        Some(sub.block(x, y.rem_euclid(16) as usize, z))
+    }
+
+    fn y_range(&self) -> Range<isize> {
+        let min = self.sub_chunks.keys().next().copied().unwrap_or(0) as isize;
+        let max = self.sub_chunks.keys().last().copied().unwrap_or(-1) as isize;
+        (min * 16)..((max + 1) * 16)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,162 @@
+//! A minimal reader for Bedrock's little-endian flavour of NBT. This is only
+//! used for the small compounds found in block palettes, so it reads straight
+//! into a [`Value`] rather than going through serde.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use fastnbt::{ByteArray, IntArray, LongArray, Tag, Value};
+
+/// Read a named root compound from the front of `input`, advancing it past the
+/// compound. Returns `None` if the data is malformed.
+pub(crate) fn read_root_compound(input: &mut &[u8]) -> Option<HashMap<String, Value>> {
+    let tag = read_tag(input)?;
+    if tag != Tag::Compound {
+        return None;
+    }
+
+    read_string(input)?;
+    match read_value(input, Tag::Compound, 0)? {
+        Value::Compound(map) => Some(map),
+        _ => None,
+    }
+}
+
+// Palette entries are shallow, this only protects against malicious input.
+const MAX_DEPTH: usize = 64;
+
+fn read_tag(input: &mut &[u8]) -> Option<Tag> {
+    Tag::try_from(input.read_u8().ok()?).ok()
+}
+
+fn read_string(input: &mut &[u8]) -> Option<String> {
+    let len = input.read_u16::<LittleEndian>().ok()? as usize;
+    if len > input.len() {
+        return None;
+    }
+
+    let (s, rest) = input.split_at(len);
+    *input = rest;
+    Some(String::from_utf8_lossy(s).into_owned())
+}
+
+fn read_len(input: &mut &[u8]) -> Option<usize> {
+    let len = input.read_i32::<LittleEndian>().ok()?;
+    let len = usize::try_from(len).ok()?;
+
+    // Every element takes at least one byte, so a length longer than the
+    // remaining input must be bogus. This stops us allocating huge vectors.
+    if len > input.len() {
+        return None;
+    }
+    Some(len)
+}
+
+fn read_value(input: &mut &[u8], tag: Tag, depth: usize) -> Option<Value> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    Some(match tag {
+        Tag::Byte => Value::Byte(input.read_i8().ok()?),
+        Tag::Short => Value::Short(input.read_i16::<LittleEndian>().ok()?),
+        Tag::Int => Value::Int(input.read_i32::<LittleEndian>().ok()?),
+        Tag::Long => Value::Long(input.read_i64::<LittleEndian>().ok()?),
+        Tag::Float => Value::Float(input.read_f32::<LittleEndian>().ok()?),
+        Tag::Double => Value::Double(input.read_f64::<LittleEndian>().ok()?),
+        Tag::String => Value::String(read_string(input)?),
+        Tag::ByteArray => {
+            let len = read_len(input)?;
+            let data = (0..len)
+                .map(|_| input.read_i8().ok())
+                .collect::<Option<_>>()?;
+            Value::ByteArray(ByteArray::new(data))
+        }
+        Tag::IntArray => {
+            let len = read_len(input)?;
+            let data = (0..len)
+                .map(|_| input.read_i32::<LittleEndian>().ok())
+                .collect::<Option<_>>()?;
+            Value::IntArray(IntArray::new(data))
+        }
+        Tag::LongArray => {
+            let len = read_len(input)?;
+            let data = (0..len)
+                .map(|_| input.read_i64::<LittleEndian>().ok())
+                .collect::<Option<_>>()?;
+            Value::LongArray(LongArray::new(data))
+        }
+        Tag::List => {
+            let element_tag = read_tag(input)?;
+            let len = read_len(input)?;
+            if element_tag == Tag::End && len > 0 {
+                return None;
+            }
+
+            let list = (0..len)
+                .map(|_| read_value(input, element_tag, depth + 1))
+                .collect::<Option<_>>()?;
+            Value::List(list)
+        }
+        Tag::Compound => {
+            let mut map = HashMap::new();
+            loop {
+                let tag = read_tag(input)?;
+                if tag == Tag::End {
+                    break;
+                }
+
+                let name = read_string(input)?;
+                let value = read_value(input, tag, depth + 1)?;
+                map.insert(name, value);
+            }
+            Value::Compound(map)
+        }
+        Tag::End => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn palette_entry() {
+        let mut data: Vec<u8> = vec![10, 0, 0];
+        data.extend([8, 4, 0]);
+        data.extend(b"name");
+        data.extend([15, 0]);
+        data.extend(b"minecraft:stone");
+        data.extend([10, 6, 0]);
+        data.extend(b"states");
+        data.extend([1, 3, 0]);
+        data.extend(b"bit");
+        data.push(1);
+        data.push(0);
+        data.extend([3, 7, 0]);
+        data.extend(b"version");
+        data.extend(17959425i32.to_le_bytes());
+        data.push(0);
+        data.push(0xff); // trailing data is left alone
+
+        let mut input = data.as_slice();
+        let map = read_root_compound(&mut input).unwrap();
+
+        assert_eq!(input, [0xff]);
+        assert_eq!(map["name"], Value::String("minecraft:stone".to_owned()));
+        assert_eq!(map["version"], Value::Int(17959425));
+        match &map["states"] {
+            Value::Compound(states) => assert_eq!(states["bit"], Value::Byte(1)),
+            _ => panic!("expected states compound"),
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        let data = [10, 0, 0, 8, 4, 0, b'n', b'a', b'm', b'e', 15, 0, b'm'];
+        for len in 0..data.len() {
+            assert!(read_root_compound(&mut &data[..len]).is_none());
+        }
+    }
+}
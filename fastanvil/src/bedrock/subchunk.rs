@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use fastnbt::Value;
+
+use crate::Block;
+
+use super::nbt::read_root_compound;
+
+/// A 16x16x16 cube of blocks, decoded from a `SubChunkPrefix` record.
+#[derive(Debug)]
+pub struct SubChunk {
+    palette: Vec<Block>,
+    indices: Vec<u16>,
+}
+
+impl SubChunk {
+    /// Parse the value of a `SubChunkPrefix` record. Only the palettized
+    /// formats (versions 1, 8 and 9) are supported, older worlds that store
+    /// numeric block IDs give `None`.
+    ///
+    /// Sub-chunks can contain several layers of blocks, the second usually
+    /// being water for waterlogged blocks. Only the first layer is kept.
+    pub fn from_bytes(mut data: &[u8]) -> Option<Self> {
+        let version = data.read_u8().ok()?;
+        let layers = match version {
+            1 => 1,
+            8 => data.read_u8().ok()?,
+            9 => {
+                let layers = data.read_u8().ok()?;
+                let _y_index = data.read_i8().ok()?;
+                layers
+            }
+            _ => return None,
+        };
+
+        if layers == 0 {
+            return Some(Self::empty());
+        }
+
+        let (indices, palette_len) = read_storage(&mut data)?;
+
+        let palette = (0..palette_len)
+            .map(|_| read_root_compound(&mut data).map(block_from_nbt))
+            .collect::<Option<Vec<_>>>()?;
+
+        // Make sure every index is valid so we never need to check later.
+        if indices.iter().any(|i| *i as usize >= palette.len()) {
+            return None;
+        }
+
+        Some(Self { palette, indices })
+    }
+
+    fn empty() -> Self {
+        Self {
+            palette: vec![Block::new("minecraft:air".to_owned(), HashMap::new())],
+            indices: vec![0; 4096],
+        }
+    }
+
+    /// Get the block at the given sub-chunk relative coordinates. Will panic
+    /// if any coordinate is outside of 0..16.
+    pub fn block(&self, x: usize, y: usize, z: usize) -> &Block {
+        assert!(x < 16 && y < 16 && z < 16);
+        &self.palette[self.indices[index(x, y, z)] as usize]
+    }
+
+    /// The distinct blocks in this sub-chunk.
+    pub fn palette(&self) -> &[Block] {
+        &self.palette
+    }
+}
+
+/// Bedrock stores blocks in XZY order, unlike Java's YZX.
+pub(crate) fn index(x: usize, y: usize, z: usize) -> usize {
+    (x << 8) | (z << 4) | y
+}
+
+/// Read a palettized storage of 4096 values, returning the palette index for
+/// each and the size of the palette that follows. The palette itself is left
+/// in `data` as its format depends on what is being stored.
+pub(crate) fn read_storage(data: &mut &[u8]) -> Option<(Vec<u16>, usize)> {
+    let header = data.read_u8().ok()?;
+
+    // The lowest bit marks runtime IDs, which are only used over the network.
+    if header & 1 == 1 {
+        return None;
+    }
+
+    let bits_per_value = (header >> 1) as usize;
+    if bits_per_value == 0 {
+        // A single value, with no index data and an implied palette size.
+        return Some((vec![0; 4096], 1));
+    }
+    if bits_per_value > 16 {
+        return None;
+    }
+
+    // Values do not span across words, so some bits are left unused.
+    let values_per_word = 32 / bits_per_value;
+    let word_count = 4096 / values_per_word + usize::from(4096 % values_per_word != 0);
+    let mask = (1u32 << bits_per_value) - 1;
+
+    let mut indices = Vec::with_capacity(4096);
+    for _ in 0..word_count {
+        let word = data.read_u32::<LittleEndian>().ok()?;
+        for i in 0..values_per_word {
+            if indices.len() == 4096 {
+                break;
+            }
+            indices.push(((word >> (i * bits_per_value)) & mask) as u16);
+        }
+    }
+
+    let palette_len = data.read_i32::<LittleEndian>().ok()?;
+    let palette_len = usize::try_from(palette_len).ok()?;
+
+    Some((indices, palette_len))
+}
+
+fn block_from_nbt(mut nbt: HashMap<String, Value>) -> Block {
+    let name = match nbt.remove("name") {
+        Some(Value::String(name)) => name,
+        _ => "minecraft:air".to_owned(),
+    };
+
+    let mut properties = HashMap::new();
+    if let Some(Value::Compound(states)) = nbt.remove("states") {
+        for (k, v) in states {
+            let v = match v {
+                Value::String(s) => s,
+                Value::Byte(b) => b.to_string(),
+                Value::Short(s) => s.to_string(),
+                Value::Int(i) => i.to_string(),
+                Value::Long(l) => l.to_string(),
+                _ => continue,
+            };
+            properties.insert(k, v);
+        }
+    }
+
+    Block::new(name, properties)
+}
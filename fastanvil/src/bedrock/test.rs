@@ -0,0 +1,167 @@
+use super::*;
+
+fn palette_entry(name: &str) -> Vec<u8> {
+    let mut data: Vec<u8> = vec![10, 0, 0];
+    data.extend([8, 4, 0]);
+    data.extend(b"name");
+    data.extend((name.len() as u16).to_le_bytes());
+    data.extend(name.as_bytes());
+    data.extend([10, 6, 0]);
+    data.extend(b"states");
+    data.extend([8, 4, 0]);
+    data.extend(b"kind");
+    data.extend([3, 0]);
+    data.extend(b"red");
+    data.push(0);
+    data.push(0);
+    data
+}
+
+/// A version 9 sub-chunk with stone at every block with y == 0, and air
+/// everywhere else.
+fn stone_floor_sub_chunk(y_index: i8) -> Vec<u8> {
+    let mut data = vec![9, 1, y_index as u8];
+
+    // 1 bit per block, so 32 blocks per word.
+    data.push(1 << 1);
+    for _ in 0..128 {
+        // Each word covers y 0..16 for two x/z columns.
+        data.extend(0x0001_0001u32.to_le_bytes());
+    }
+
+    data.extend(2i32.to_le_bytes());
+    data.extend(palette_entry("minecraft:air"));
+    data.extend(palette_entry("minecraft:stone"));
+    data
+}
+
+fn key(x: i32, z: i32, record: u8) -> Vec<u8> {
+    chunk_key(x, z, BedrockDimension::Overworld, record)
+}
+
+fn sub_chunk_key(x: i32, z: i32, y: i8) -> Vec<u8> {
+    let mut k = key(x, z, SUB_CHUNK_PREFIX);
+    k.push(y as u8);
+    k
+}
+
+fn world() -> HashMap<Vec<u8>, Vec<u8>> {
+    let mut db = HashMap::new();
+
+    db.insert(key(0, 0, VERSION), vec![40]);
+    db.insert(sub_chunk_key(0, 0, 0), stone_floor_sub_chunk(0));
+    db.insert(sub_chunk_key(0, 0, 2), stone_floor_sub_chunk(2));
+
+    let mut data_2d = vec![];
+    for i in 0..256i16 {
+        data_2d.extend(i.to_le_bytes());
+    }
+    data_2d.extend([1u8; 256]);
+    db.insert(key(0, 0, DATA_2D), data_2d);
+
+    db.insert(key(-1, 40, LEGACY_VERSION), vec![20]);
+    db
+}
+
+#[test]
+fn blocks_in_sub_chunks() {
+    let db = world();
+    let chunk = BedrockChunk::load(&db, BedrockDimension::Overworld, 0, 0).unwrap();
+
+    assert_eq!(chunk.block(0, 0, 0).unwrap().name(), "minecraft:stone");
+    assert_eq!(chunk.block(15, 1, 15).unwrap().name(), "minecraft:air");
+    assert_eq!(chunk.block(3, 32, 7).unwrap().name(), "minecraft:stone");
+    assert_eq!(
+        chunk.block(3, 32, 7).unwrap().properties()["kind"],
+        "red".to_owned()
+    );
+
+    // missing sub-chunk in the middle
+    assert!(chunk.block(0, 16, 0).is_none());
+    assert!(chunk.block(0, -1, 0).is_none());
+    assert!(chunk.block(0, 1000, 0).is_none());
+
+    assert_eq!(chunk.y_range(), 0..48);
+}
+
+#[test]
+fn heights_and_biomes() {
+    let db = world();
+    let chunk = BedrockChunk::load(&db, BedrockDimension::Overworld, 0, 0).unwrap();
+
+    assert_eq!(chunk.surface_height(1, 2, HeightMode::Trust), 33);
+    assert_eq!(chunk.surface_height(1, 2, HeightMode::Calculate), 33);
+    assert!(matches!(chunk.biome(4, 10, 4), Some(Biome::Plains)));
+}
+
+#[test]
+fn cubic_biomes() {
+    let mut db = world();
+
+    let mut data_3d = vec![0u8; 512];
+    // Bottom sub-chunk is all desert.
+    data_3d.push(0);
+    data_3d.extend(2i32.to_le_bytes());
+    // Next is a copy of the bottom.
+    data_3d.push(0xff);
+    // Then half forest, half meadow, split on x.
+    data_3d.push(1 << 1);
+    for i in 0..128 {
+        let word = if i < 64 { 0 } else { u32::MAX };
+        data_3d.extend(word.to_le_bytes());
+    }
+    data_3d.extend(2i32.to_le_bytes());
+    data_3d.extend(4i32.to_le_bytes());
+    // Meadow's Bedrock ID, which isn't a Java ID at all.
+    data_3d.extend(186i32.to_le_bytes());
+    db.insert(key(0, 0, DATA_3D), data_3d);
+
+    let chunk = BedrockChunk::load(&db, BedrockDimension::Overworld, 0, 0).unwrap();
+
+    assert!(matches!(chunk.biome(0, -64, 0), Some(Biome::Desert)));
+    assert!(matches!(chunk.biome(0, -40, 0), Some(Biome::Desert)));
+    assert!(matches!(chunk.biome(0, -32, 0), Some(Biome::Forest)));
+    assert!(matches!(chunk.biome(15, -20, 0), Some(Biome::Meadow)));
+    assert!(chunk.biome(0, -10, 0).is_none());
+
+    // heights in Data3D are relative to the bottom of the world.
+    assert_eq!(chunk.surface_height(0, 0, HeightMode::Trust), -64);
+}
+
+#[test]
+fn missing_chunk() {
+    let db = world();
+    assert!(BedrockChunk::load(&db, BedrockDimension::Overworld, 1, 0).is_none());
+    assert!(BedrockChunk::load(&db, BedrockDimension::Nether, 0, 0).is_none());
+}
+
+#[test]
+fn unsupported_sub_chunk_version() {
+    let mut db = world();
+    db.insert(sub_chunk_key(0, 0, 1), vec![0; 10241]);
+    assert!(BedrockChunk::load(&db, BedrockDimension::Overworld, 0, 0).is_none());
+}
+
+#[test]
+fn loader_lists_and_loads_regions() {
+    let mut db = world();
+    db.insert(
+        chunk_key(100, 100, BedrockDimension::Nether, VERSION),
+        vec![40],
+    );
+
+    let loader = BedrockLoader::new(db);
+    let mut regions = loader.list().unwrap();
+    regions.sort();
+    assert_eq!(
+        regions,
+        vec![(RCoord(-1), RCoord(1)), (RCoord(0), RCoord(0))]
+    );
+
+    let region = loader.region(RCoord(0), RCoord(0)).unwrap();
+    assert!(region.chunk(CCoord(0), CCoord(0)).is_some());
+    assert!(region.chunk(CCoord(1), CCoord(0)).is_none());
+
+    let loader = loader.with_dimension(BedrockDimension::Nether);
+    assert_eq!(loader.list().unwrap(), vec![(RCoord(3), RCoord(3))]);
+}
@@ -1,11 +1,52 @@
 //! functionality relating to Minecraft biomes.
 
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+use crate::upgrade::upgraded_biome_name;
+
+/// Defines the Biome enum. Biomes from before 1.18 have a numeric ID, which
+/// is how they are stored in chunks from those versions. Biomes added since
+/// only have a name.
+macro_rules! biomes {
+    ($($numbered:ident = $id:expr,)* ; $($named:ident,)*) => {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum Biome {
+            $($numbered,)*
+            $($named,)*
+
+            /// A biome not known to fastanvil, such as one from a datapack
+            /// or a newer version of Minecraft. Holds the namespaced name of
+            /// the biome, eg `mydatapack:crystal_caves`.
+            Unknown(String),
+        }
+
+        impl Biome {
+            /// The numeric ID of the biome, as used in chunks before 1.18.
+            /// Biomes added since 1.18 have no ID.
+            pub fn id(&self) -> Option<i32> {
+                match self {
+                    $(Biome::$numbered => Some($id),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl TryFrom<i32> for Biome {
+            type Error = InvalidBiomeId;
+
+            fn try_from(id: i32) -> Result<Self, Self::Error> {
+                match id {
+                    $($id => Ok(Biome::$numbered),)*
+                    _ => Err(InvalidBiomeId(id)),
+                }
+            }
+        }
+    };
+}
 
 // Values from https://minecraft.gamepedia.com/Java_Edition_data_value#Biomes
-#[derive(TryFromPrimitive, IntoPrimitive, Debug, Clone, Copy)]
-#[repr(i32)] // i32 as in corresponding NBT.
-pub enum Biome {
+biomes! {
     Ocean = 0,
     DeepOcean = 24,
     FrozenOcean = 10,
@@ -84,7 +125,186 @@ pub enum Biome {
     CrimsonForest = 171,
     WarpedForest = 172,
     BasaltDeltas = 173,
-    TheVoid = 127,
+    TheVoid = 127,;
+    Meadow,
+    Grove,
+    SnowySlopes,
+    FrozenPeaks,
+    JaggedPeaks,
+    StonyPeaks,
+    LushCaves,
+    DripstoneCaves,
+    DeepDark,
+    MangroveSwamp,
+    CherryGrove,
+    PaleGarden,
+}
+
+/// The error given when converting an unknown numeric ID into a [`Biome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBiomeId(pub i32);
+
+impl Display for InvalidBiomeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid biome id: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBiomeId {}
+
+impl Biome {
+    /// Get the biome for a namespaced biome name, as used in chunks since
+    /// 1.18. Names from 1.18 that replaced older biomes map to the older
+    /// biome. Names without a namespace are taken to be from `minecraft`.
+    ///
+    /// Any other name, such as a biome from a datapack, gives
+    /// [`Biome::Unknown`].
+    ///
+    /// ```
+    /// use fastanvil::biome::Biome;
+    ///
+    /// assert_eq!(Biome::from_name("minecraft:meadow"), Biome::Meadow);
+    /// assert_eq!(Biome::from_name("snowy_plains"), Biome::SnowyTundra);
+    /// assert_eq!(
+    ///     Biome::from_name("terralith:moonlight_grove"),
+    ///     Biome::Unknown("terralith:moonlight_grove".to_owned())
+    /// );
+    /// ```
+    pub fn from_name(name: &str) -> Biome {
+        use Biome::*;
+
+        let short = name.strip_prefix("minecraft:").unwrap_or(name);
+        match short {
+            "ocean" => Ocean,
+            "deep_ocean" => DeepOcean,
+            "frozen_ocean" => FrozenOcean,
+            "deep_frozen_ocean" => DeepFrozenOcean,
+            "cold_ocean" => ColdOcean,
+            "deep_cold_ocean" => DeepColdOcean,
+            "lukewarm_ocean" => LukewarmOcean,
+            "deep_lukewarm_ocean" => DeepLukewarmOcean,
+            "warm_ocean" => WarmOcean,
+            "deep_warm_ocean" => DeepWarmOcean,
+            "river" => River,
+            "frozen_river" => FrozenRiver,
+            "beach" => Beach,
+            "stone_shore" | "stony_shore" => StoneShore,
+            "snowy_beach" => SnowyBeach,
+            "forest" => Forest,
+            "wooded_hills" => WoodedHills,
+            "flower_forest" => FlowerForest,
+            "birch_forest" => BirchForest,
+            "birch_forest_hills" => BirchForestHills,
+            "tall_birch_forest" | "old_growth_birch_forest" => TallBirchForest,
+            "tall_birch_hills" => TallBirchHills,
+            "dark_forest" => DarkForest,
+            "dark_forest_hills" => DarkForestHills,
+            "jungle" => Jungle,
+            "jungle_hills" => JungleHills,
+            "modified_jungle" => ModifiedJungle,
+            "jungle_edge" | "sparse_jungle" => JungleEdge,
+            "modified_jungle_edge" => ModifiedJungleEdge,
+            "bamboo_jungle" => BambooJungle,
+            "bamboo_jungle_hills" => BambooJungleHills,
+            "taiga" => Taiga,
+            "taiga_hills" => TaigaHills,
+            "taiga_mountains" => TaigaMountains,
+            "snowy_taiga" => SnowyTaiga,
+            "snowy_taiga_hills" => SnowyTaigaHills,
+            "snowy_taiga_mountains" => SnowyTaigaMountains,
+            "giant_tree_taiga" | "old_growth_pine_taiga" => GiantTreeTaiga,
+            "giant_tree_taiga_hills" => GiantTreeTaigaHills,
+            "giant_spruce_taiga" | "old_growth_spruce_taiga" => GiantSpruceTaiga,
+            "giant_spruce_taiga_hills" => GiantSpruceTaigaHills,
+            "mushroom_fields" => MushroomFields,
+            "mushroom_field_shore" => MushroomFieldShore,
+            "swamp" => Swamp,
+            "swamp_hills" => SwampHills,
+            "savanna" => Savanna,
+            "savanna_plateau" => SavannaPlateau,
+            "shattered_savanna" | "windswept_savanna" => ShatteredSavanna,
+            "shattered_savanna_plateau" => ShatteredSavannaPlateau,
+            "plains" => Plains,
+            "sunflower_plains" => SunflowerPlains,
+            "desert" => Desert,
+            "desert_hills" => DesertHills,
+            "desert_lakes" => DesertLakes,
+            "snowy_tundra" | "snowy_plains" => SnowyTundra,
+            "snowy_mountains" => SnowyMountains,
+            "ice_spikes" => IceSpikes,
+            "mountains" | "windswept_hills" => Mountains,
+            "wooded_mountains" | "windswept_forest" => WoodedMountains,
+            "gravelly_mountains" | "windswept_gravelly_hills" => GravellyMountains,
+            "modified_gravelly_mountains" => ModifiedGravellyMountains,
+            "mountain_edge" => MountainEdge,
+            "badlands" => Badlands,
+            "badlands_plateau" => BadlandsPlateau,
+            "modified_badlands_plateau" => ModifiedBadlandsPlateau,
+            "wooded_badlands_plateau" | "wooded_badlands" => WoodedBadlandsPlateau,
+            "modified_wooded_badlands_plateau" => ModifiedWoodedBadlandsPlateau,
+            "eroded_badlands" => ErodedBadlands,
+            "nether" | "nether_wastes" => Nether,
+            "the_end" => TheEnd,
+            "small_end_islands" => SmallEndIslands,
+            "end_midlands" => EndMidlands,
+            "end_highlands" => EndHighlands,
+            "end_barrens" => EndBarrens,
+            "soul_sand_valley" => SoulSandValley,
+            "crimson_forest" => CrimsonForest,
+            "warped_forest" => WarpedForest,
+            "basalt_deltas" => BasaltDeltas,
+            "the_void" => TheVoid,
+            "meadow" => Meadow,
+            "grove" => Grove,
+            "snowy_slopes" => SnowySlopes,
+            "frozen_peaks" => FrozenPeaks,
+            "jagged_peaks" => JaggedPeaks,
+            "stony_peaks" => StonyPeaks,
+            "lush_caves" => LushCaves,
+            "dripstone_caves" => DripstoneCaves,
+            "deep_dark" => DeepDark,
+            "mangrove_swamp" => MangroveSwamp,
+            "cherry_grove" => CherryGrove,
+            "pale_garden" => PaleGarden,
+            _ if name.contains(':') => Unknown(name.to_owned()),
+            _ => Unknown(format!("minecraft:{}", name)),
+        }
+    }
+}
+
+impl Biome {
+    /// The namespaced name of the biome as stored in chunks since 1.18. Biomes
+    /// removed in 1.18 give the name of the biome the game replaced them with.
+    ///
+    /// ```
+    /// use fastanvil::biome::Biome;
+    ///
+    /// assert_eq!(Biome::Meadow.name(), "minecraft:meadow");
+    /// assert_eq!(Biome::SnowyTundra.name(), "minecraft:snowy_plains");
+    /// assert_eq!(Biome::from_name("minecraft:wooded_hills").name(), "minecraft:forest");
+    /// ```
+    pub fn name(&self) -> String {
+        use Biome::*;
+
+        let name = match self {
+            Meadow => "minecraft:meadow",
+            Grove => "minecraft:grove",
+            SnowySlopes => "minecraft:snowy_slopes",
+            FrozenPeaks => "minecraft:frozen_peaks",
+            JaggedPeaks => "minecraft:jagged_peaks",
+            StonyPeaks => "minecraft:stony_peaks",
+            LushCaves => "minecraft:lush_caves",
+            DripstoneCaves => "minecraft:dripstone_caves",
+            DeepDark => "minecraft:deep_dark",
+            MangroveSwamp => "minecraft:mangrove_swamp",
+            CherryGrove => "minecraft:cherry_grove",
+            PaleGarden => "minecraft:pale_garden",
+            Unknown(name) => return name.clone(),
+            numbered => upgraded_biome_name(numbered.id().unwrap_or_default()),
+        };
+
+        name.to_owned()
+    }
 }
 
 pub struct Climate {
@@ -179,5 +399,20 @@ pub fn climate(b: Biome) -> Climate {
         Biome::CrimsonForest => climate(2.0, 0.0),
         Biome::WarpedForest => climate(2.0, 0.0),
         Biome::BasaltDeltas => climate(2.0, 0.0),
+        Biome::Meadow => climate(0.5, 0.8),
+        Biome::Grove => climate(-0.2, 0.8),
+        Biome::SnowySlopes => climate(-0.3, 0.9),
+        Biome::FrozenPeaks => climate(-0.7, 0.9),
+        Biome::JaggedPeaks => climate(-0.7, 0.9),
+        Biome::StonyPeaks => climate(1.0, 0.3),
+        Biome::LushCaves => climate(0.5, 0.5),
+        Biome::DripstoneCaves => climate(0.8, 0.4),
+        Biome::DeepDark => climate(0.8, 0.4),
+        Biome::MangroveSwamp => climate(0.8, 0.9),
+        Biome::CherryGrove => climate(0.5, 0.8),
+        Biome::PaleGarden => climate(0.7, 0.8),
+        // Without knowing anything about the biome, plains is as good a
+        // guess as any.
+        Biome::Unknown(_) => climate(0.8, 0.4),
     }
 }
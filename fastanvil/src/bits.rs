@@ -2,7 +2,7 @@
 
 use bit_field::{BitArray, BitField};
 use fastnbt::LongArray;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // Various data versions for the anvil format
 const V1_17_0: i32 = 2724;
@@ -12,10 +12,114 @@ const V1_17_1: i32 = 2730;
 /// allocating memory for them when they might not be needed. This object by
 /// default just retains a reference to the data in the input, and `unpack_into`
 /// can be used to get the unpacked version when needed.
-#[derive(Deserialize, Debug)]
+///
+/// Minecraft packs block states, biomes and heightmaps this way, with each
+/// value taking a fixed number of bits. How values are laid out in the longs
+/// changed in 1.16, see [`PackingFormat`]. PackedBits can also be built from
+/// values with [`pack`](Self::pack) in order to write chunks.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct PackedBits(pub LongArray);
 
+/// How values are laid out in packed longs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackingFormat {
+    /// Values are packed tightly and can be split across two longs, used
+    /// before 1.16.
+    Spanning,
+    /// Each long holds as many whole values as fit, with the remaining high
+    /// bits unused, used from 1.16.
+    Padded,
+}
+
+impl PackingFormat {
+    /// The number of longs needed to hold `count` values of `bits` bits.
+    pub fn packed_len(self, count: usize, bits: usize) -> usize {
+        match self {
+            PackingFormat::Spanning => (count * bits).div_ceil(64),
+            PackingFormat::Padded => count.div_ceil(64 / bits),
+        }
+    }
+}
+
 impl PackedBits {
+    /// Pack values of `bits` bits into longs. Values are truncated to `bits`
+    /// bits. Will panic if `bits` is not in `1..=16`.
+    ///
+    /// ```
+    /// # use fastanvil::{PackedBits, PackingFormat};
+    /// let values: Vec<u16> = (0..4096).map(|i| (i % 5) as u16).collect();
+    /// let packed = PackedBits::pack(&values, 4, PackingFormat::Padded);
+    ///
+    /// assert_eq!(256, packed.0.len());
+    /// assert_eq!(Some(values), packed.unpack(4, PackingFormat::Padded, 4096));
+    /// ```
+    pub fn pack(values: &[u16], bits: usize, format: PackingFormat) -> Self {
+        assert!((1..=16).contains(&bits));
+
+        let mut data = vec![0u64; format.packed_len(values.len(), bits)];
+        let mask = (1u64 << bits) - 1;
+
+        for (i, &value) in values.iter().enumerate() {
+            let value = value as u64 & mask;
+            let (long, shift) = Self::position(i, bits, format);
+
+            data[long] |= value << shift;
+            if shift + bits > 64 {
+                // Only possible when spanning, the rest goes in the next long.
+                data[long + 1] |= value >> (64 - shift);
+            }
+        }
+
+        Self(LongArray::new(data.into_iter().map(|l| l as i64).collect()))
+    }
+
+    /// Pack the palette indices of the 4096 blocks of a section, in the
+    /// format used since 1.16.
+    pub fn pack_blockstates(indices: &[u16], palette_len: usize) -> Self {
+        Self::pack(indices, bits_per_block(palette_len), PackingFormat::Padded)
+    }
+
+    /// Get the value at `index`, or none if the data is too short to hold
+    /// it or `bits` is not in `1..=16`.
+    pub fn get(&self, index: usize, bits: usize, format: PackingFormat) -> Option<u16> {
+        if !(1..=16).contains(&bits) {
+            return None;
+        }
+
+        let (long, shift) = Self::position(index, bits, format);
+        let mut value = *self.0.get(long)? as u64 >> shift;
+
+        if shift + bits > 64 {
+            value |= (*self.0.get(long + 1)? as u64) << (64 - shift);
+        }
+
+        Some((value & ((1 << bits) - 1)) as u16)
+    }
+
+    /// Unpack the first `count` values. Values missing from the end of the
+    /// data are zero. Gives none if `bits` is not in `1..=16`.
+    pub fn unpack(&self, bits: usize, format: PackingFormat, count: usize) -> Option<Vec<u16>> {
+        if !(1..=16).contains(&bits) {
+            return None;
+        }
+
+        let values = (0..count)
+            .map(|i| self.get(i, bits, format).unwrap_or(0))
+            .collect();
+        Some(values)
+    }
+
+    /// The long a value starts in, and the bit it starts at.
+    fn position(index: usize, bits: usize, format: PackingFormat) -> (usize, usize) {
+        match format {
+            PackingFormat::Spanning => (index * bits / 64, index * bits % 64),
+            PackingFormat::Padded => {
+                let per_long = 64 / bits;
+                (index / per_long, index % per_long * bits)
+            }
+        }
+    }
+
     pub fn unpack_blockstates(&self, bits_per_item: usize, buf: &mut [u16]) {
         let bpi = match self.0.len() {
             256 => 4,
@@ -147,6 +251,48 @@ pub fn expand_heightmap(data: &[i64], y_min: isize, data_version: i32) -> Vec<i1
     }
 }
 
+/// Get a single value from a packed heightmap without expanding the whole
+/// thing. The value is unsigned, relative to the bottom of the world. Returns
+/// None if the format of the heightmap is not recognised.
+///
+/// The number of bits per value depends on the height of the world, which
+/// is needed to tell apart some formats.
+pub(crate) fn heightmap_value(data: &[i64], index: usize, world_height: usize) -> Option<u16> {
+    if index >= 256 {
+        return None;
+    }
+
+    // Before 1.16 values could be split across two longs. Since then each long
+    // holds as many whole values as it can, with the remaining bits unused.
+    let (bits, padded) = match data.len() {
+        36 => (9, false),
+        37 => (9, true),
+        43 => (10, true),
+        52 if world_height >= 2048 => (12, true),
+        52 => (11, true),
+        _ => return None,
+    };
+
+    let value = if padded {
+        let per_long = 64 / bits;
+        let long = data[index / per_long] as u64;
+        let shift = (index % per_long) * bits;
+        long.get_bits(shift..shift + bits)
+    } else {
+        let begin = index * bits;
+        let long = data[begin / 64] as u64;
+        let shift = begin % 64;
+
+        let mut value = long >> shift;
+        if shift + bits > 64 {
+            value |= (data[begin / 64 + 1] as u64) << (64 - shift);
+        }
+        value & ((1 << bits) - 1)
+    };
+
+    Some(value as u16)
+}
+
 /// Expand generic bit-packed data in the 1.16 format, ie with padding bits.
 pub fn expand_generic_1_16(data: &[i64], bits: usize) -> Vec<u16> {
     let values_per_64bits = 64 / bits;
@@ -196,6 +342,56 @@ mod tests {
     use super::*;
     use byteorder::{BigEndian, ReadBytesExt};
 
+    #[test]
+    fn pack_round_trips() {
+        let values: Vec<u16> = (0..4096).map(|i| (i * 7 % 37) as u16).collect();
+
+        for bits in [6, 9, 12] {
+            let padded = PackedBits::pack(&values, bits, PackingFormat::Padded);
+            assert_eq!(expand_generic_1_16(&padded.0, bits)[..4096], values[..]);
+            assert_eq!(
+                Some(&values),
+                padded.unpack(bits, PackingFormat::Padded, 4096).as_ref()
+            );
+
+            let spanning = PackedBits::pack(&values, bits, PackingFormat::Spanning);
+            assert_eq!(4096 * bits / 64, spanning.0.len());
+            assert_eq!(expand_generic_1_15(&spanning.0, bits), values);
+            assert_eq!(
+                Some(&values),
+                spanning
+                    .unpack(bits, PackingFormat::Spanning, 4096)
+                    .as_ref()
+            );
+        }
+    }
+
+    #[test]
+    fn pack_blockstates_matches_unpack() {
+        let indices: Vec<u16> = (0..4096).map(|i| (i % 20) as u16).collect();
+        let packed = PackedBits::pack_blockstates(&indices, 20);
+
+        // 5 bits per block, padded to 12 per long.
+        assert_eq!(342, packed.0.len());
+
+        let mut buf = [0; 4096];
+        packed.unpack_blockstates(5, &mut buf);
+        assert_eq!(indices[..], buf[..]);
+    }
+
+    #[test]
+    fn packed_get() {
+        let packed = PackedBits::pack(&[1, 2, 3], 9, PackingFormat::Padded);
+        assert_eq!(Some(3), packed.get(2, 9, PackingFormat::Padded));
+        assert_eq!(None, packed.get(7, 9, PackingFormat::Padded));
+        assert_eq!(None, packed.get(0, 0, PackingFormat::Padded));
+        assert_eq!(None, packed.get(0, 64, PackingFormat::Spanning));
+        assert_eq!(None, packed.unpack(0, PackingFormat::Padded, 3));
+        assert_eq!(1, PackingFormat::Padded.packed_len(7, 9));
+        assert_eq!(2, PackingFormat::Padded.packed_len(8, 9));
+        assert_eq!(2, PackingFormat::Spanning.packed_len(8, 9));
+    }
+
     #[test]
     fn nether_heightmap_v1_15_2() {
         let input: Vec<i64> = vec![
@@ -239,6 +435,10 @@ mod tests {
 
         let actual = expand_heightmap(&input[..], 0, 0);
         assert_eq!(&[128; 16 * 16][..], actual.as_slice());
+
+        for i in 0..256 {
+            assert_eq!(Some(128), heightmap_value(&input, i, 256));
+        }
     }
 
     #[test]
@@ -301,6 +501,31 @@ mod tests {
             ][..],
             &actual[..]
         );
+
+        for (i, height) in actual.iter().enumerate() {
+            assert_eq!(Some(*height as u16), heightmap_value(&input, i, 256));
+        }
+    }
+
+    #[test]
+    fn heightmap_value_padded() {
+        // 1.17 with a custom world height: 10 bits, 6 values per long.
+        let mut data = vec![0i64; 43];
+        data[0] = 1 | (2 << 10) | (1023 << 50);
+        data[42] = 7 << 30;
+
+        assert_eq!(Some(1), heightmap_value(&data, 0, 512));
+        assert_eq!(Some(2), heightmap_value(&data, 1, 512));
+        assert_eq!(Some(1023), heightmap_value(&data, 5, 512));
+        assert_eq!(Some(0), heightmap_value(&data, 6, 512));
+        assert_eq!(Some(7), heightmap_value(&data, 255, 512));
+        assert_eq!(None, heightmap_value(&data, 256, 512));
+    }
+
+    #[test]
+    fn heightmap_value_unknown_format() {
+        assert_eq!(None, heightmap_value(&[0; 40], 0, 256));
+        assert_eq!(None, heightmap_value(&[], 0, 256));
     }
 
     #[test]
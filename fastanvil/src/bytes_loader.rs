@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
+use crate::files::coords_from_region;
+use crate::{Chunk, LoaderResult, RCoord, RegionBuffer, RegionLoader};
+
+/// Loads regions from region files already held in memory, rather than from a
+/// folder. This never touches the filesystem, so can be used where there isn't
+/// one, such as in the browser on `wasm32-unknown-unknown`, with region files
+/// the user has uploaded or that have been fetched.
+///
+/// Region data is kept behind an `Arc`, so handing out regions does not copy
+/// the data. Oversized chunks kept in `.mcc` files are not supported, these
+/// chunks fail to load.
+///
+/// ```no_run
+/// # use fastanvil::{Dimension, JavaChunk, RCoord, RegionBytesLoader};
+/// # let data: Vec<u8> = vec![];
+/// let mut loader = RegionBytesLoader::<JavaChunk>::new();
+/// loader.insert(RCoord(0), RCoord(-1), data);
+///
+/// let dimension = Dimension::new(loader);
+/// ```
+pub struct RegionBytesLoader<C: Chunk> {
+    regions: HashMap<(RCoord, RCoord), Arc<[u8]>>,
+    p: PhantomData<fn() -> C>,
+}
+
+impl<C: Chunk> RegionBytesLoader<C> {
+    pub fn new() -> Self {
+        Self {
+            regions: HashMap::new(),
+            p: PhantomData,
+        }
+    }
+
+    /// Add the data of the region file for the region (x, z), replacing any
+    /// region already there.
+    pub fn insert(&mut self, x: RCoord, z: RCoord, data: impl Into<Arc<[u8]>>) {
+        self.regions.insert((x, z), data.into());
+    }
+
+    /// Add the data of a region file, taking the region's coordinates from
+    /// the file name, eg `r.0.-1.mca`. Returns the coordinates, or None if
+    /// they could not be parsed from the name, in which case the data is not
+    /// added.
+    pub fn insert_file(
+        &mut self,
+        name: &str,
+        data: impl Into<Arc<[u8]>>,
+    ) -> Option<(RCoord, RCoord)> {
+        let (x, z) = coords_from_region(Path::new(name))?;
+        self.insert(x, z, data);
+        Some((x, z))
+    }
+
+    /// Remove the region (x, z), returning its data if it was present.
+    pub fn remove(&mut self, x: RCoord, z: RCoord) -> Option<Arc<[u8]>> {
+        self.regions.remove(&(x, z))
+    }
+
+    /// The number of regions held.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+}
+
+impl<C: Chunk> Default for RegionBytesLoader<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, D> FromIterator<((RCoord, RCoord), D)> for RegionBytesLoader<C>
+where
+    C: Chunk,
+    D: Into<Arc<[u8]>>,
+{
+    fn from_iter<I: IntoIterator<Item = ((RCoord, RCoord), D)>>(iter: I) -> Self {
+        let mut loader = Self::new();
+        for ((x, z), data) in iter {
+            loader.insert(x, z, data);
+        }
+        loader
+    }
+}
+
+impl<C: Chunk + DeserializeOwned> RegionLoader<C> for RegionBytesLoader<C> {
+    type RegionType = RegionBuffer<Cursor<Arc<[u8]>>>;
+
+    fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
+        let data = self.regions.get(&(x, z))?;
+        Some(RegionBuffer::new(Cursor::new(Arc::clone(data))))
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        let mut regions: Vec<_> = self
+            .regions
+            .iter()
+            .filter(|(_, data)| !data.is_empty())
+            .map(|(coords, _)| *coords)
+            .collect();
+        regions.sort_by_key(|(x, z)| (x.0, z.0));
+        Ok(regions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JavaChunk;
+
+    fn region_data(chunk: &[u8]) -> Vec<u8> {
+        let mut region = RegionBuffer::new(Cursor::new(Vec::new()));
+        region.write_chunk(3, 4, chunk).unwrap();
+        region.into_inner().into_inner()
+    }
+
+    #[test]
+    fn loads_regions_from_memory() {
+        let mut loader = RegionBytesLoader::<JavaChunk>::new();
+        loader.insert(RCoord(0), RCoord(0), region_data(&[1, 2, 3]));
+        assert_eq!(
+            Some((RCoord(-1), RCoord(2))),
+            loader.insert_file("r.-1.2.mca", region_data(&[4, 5]))
+        );
+        assert_eq!(None, loader.insert_file("level.dat", vec![0]));
+        loader.insert(RCoord(5), RCoord(5), vec![]);
+
+        assert_eq!(
+            vec![(RCoord(-1), RCoord(2)), (RCoord(0), RCoord(0))],
+            loader.list().unwrap()
+        );
+
+        let region = loader.region(RCoord(-1), RCoord(2)).unwrap();
+        assert_eq!(vec![4, 5], region.load_chunk(3, 4).unwrap());
+        assert!(loader.region(RCoord(1), RCoord(1)).is_none());
+
+        assert!(loader.remove(RCoord(0), RCoord(0)).is_some());
+        assert_eq!(2, loader.len());
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Statistics of a cache, such as the region cache of a
+/// [`Dimension`](crate::Dimension).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that found the value in the cache.
+    pub hits: u64,
+
+    /// Lookups that had to load the value.
+    pub misses: u64,
+
+    /// Values dropped from the cache to make room for others. Explicit
+    /// evictions are not counted.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The fraction of lookups that were hits, or zero if there have been no
+    /// lookups.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// A map that drops its least recently used values once it holds more than
+/// its capacity. Without a capacity it grows without bound.
+pub(crate) struct LruCache<K, V> {
+    // Each value is stored with the tick it was last used at.
+    values: HashMap<K, (V, u64)>,
+    // Keys by the tick they were last used at, oldest first.
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: Option<usize>,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: Option<usize>) -> Self {
+        Self {
+            values: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            capacity,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Get a value, marking it as the most recently used. Counts as a hit or
+    /// a miss.
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        match self.values.get_mut(key) {
+            Some((value, used)) => {
+                self.order.remove(used);
+                self.order.insert(self.tick, key.clone());
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Insert a value as the most recently used, dropping the least recently
+    /// used values if over capacity.
+    pub fn insert(&mut self, key: K, value: V) {
+        self.remove(&key);
+
+        self.tick += 1;
+        self.order.insert(self.tick, key.clone());
+        self.values.insert(key, (value, self.tick));
+
+        if let Some(capacity) = self.capacity {
+            while self.values.len() > capacity {
+                let oldest = match self.order.keys().next() {
+                    Some(tick) => *tick,
+                    None => break,
+                };
+                if let Some(key) = self.order.remove(&oldest) {
+                    self.values.remove(&key);
+                    self.stats.evictions += 1;
+                }
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (value, used) = self.values.remove(key)?;
+        self.order.remove(&used);
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.order.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbounded() {
+        let mut cache = LruCache::new(None);
+        for i in 0..100 {
+            cache.insert(i, i * 2);
+        }
+
+        assert_eq!(100, cache.len());
+        assert_eq!(Some(&20), cache.get(&10));
+        assert_eq!(0, cache.stats().evictions);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new(Some(2));
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+
+        // Using 1 makes 2 the least recently used.
+        assert_eq!(Some(&"a"), cache.get(&1));
+        cache.insert(3, "c");
+
+        assert_eq!(None, cache.get(&2));
+        assert_eq!(Some(&"a"), cache.get(&1));
+        assert_eq!(Some(&"c"), cache.get(&3));
+        assert_eq!(
+            CacheStats {
+                hits: 3,
+                misses: 1,
+                evictions: 1
+            },
+            cache.stats()
+        );
+        assert_eq!(0.75, cache.stats().hit_rate());
+    }
+
+    #[test]
+    fn reinsert_and_remove() {
+        let mut cache = LruCache::new(Some(2));
+        cache.insert(1, "a");
+        cache.insert(1, "b");
+        assert_eq!(1, cache.len());
+
+        assert_eq!(Some("b"), cache.remove(&1));
+        assert_eq!(None, cache.remove(&1));
+
+        cache.insert(2, "c");
+        cache.clear();
+        assert_eq!(0, cache.len());
+        assert_eq!(0, cache.stats().evictions);
+    }
+}
@@ -0,0 +1,460 @@
+//! Convert between the coordinates of blocks, chunks and regions. A chunk is
+//! 16 blocks wide and a region 32 chunks wide, and coordinates are divided
+//! rounding down, so that negative coordinates work too: block -1 is in
+//! chunk -1, not chunk 0.
+//!
+//! Chunks are split vertically into sections 16 blocks tall. Since 1.18 worlds
+//! can start below Y 0, so the index of a section from the bottom of the
+//! world depends on where the world starts, its `min_y`.
+//!
+//! ```
+//! use fastanvil::coords;
+//! use fastanvil::{CCoord, RCoord};
+//!
+//! assert_eq!(CCoord(-1), coords::block_to_chunk(-1));
+//! assert_eq!(15, coords::block_in_chunk(-1));
+//! assert_eq!(RCoord(-1), coords::block_to_region(-1));
+//!
+//! assert_eq!(RCoord(-2), RCoord::from(CCoord(-33)));
+//! assert_eq!(CCoord(3), CCoord(1) + 2);
+//!
+//! assert_eq!(Some(0), coords::section_index(-64, -64));
+//! assert_eq!(-1, coords::section_y(-1));
+//! ```
+//!
+//! Positions can also be kept as a [`BlockPos`], [`ChunkPos`] or
+//! [`RegionPos`], so that X and Z can't be mixed up, or a chunk's
+//! coordinates taken for a region's.
+//!
+//! ```
+//! use fastanvil::{BlockPos, CCoord, ChunkPos, RCoord, RegionPos};
+//!
+//! let pos = BlockPos::new(-1, 64, 600);
+//! assert_eq!(ChunkPos::new(CCoord(-1), CCoord(37)), pos.chunk());
+//! assert_eq!(RegionPos::new(RCoord(-1), RCoord(1)), pos.region());
+//! assert_eq!((15, 64, 8), pos.in_chunk());
+//! ```
+
+use std::ops::{Add, Sub};
+
+use crate::{CCoord, RCoord};
+
+/// The width of a chunk in blocks.
+pub const CHUNK_WIDTH: isize = 16;
+
+/// The width of a region in chunks.
+pub const REGION_WIDTH: isize = 32;
+
+/// The height of a section in blocks.
+pub const SECTION_HEIGHT: isize = 16;
+
+/// The chunk containing the block at the given X or Z.
+pub fn block_to_chunk(block: isize) -> CCoord {
+    CCoord(block.div_euclid(CHUNK_WIDTH))
+}
+
+/// The region containing the block at the given X or Z.
+pub fn block_to_region(block: isize) -> RCoord {
+    chunk_to_region(block_to_chunk(block))
+}
+
+/// The region containing the chunk.
+pub fn chunk_to_region(chunk: CCoord) -> RCoord {
+    RCoord(chunk.0.div_euclid(REGION_WIDTH))
+}
+
+/// The X or Z of the first block of the chunk, on its north or west edge.
+pub fn chunk_to_block(chunk: CCoord) -> isize {
+    chunk.0 * CHUNK_WIDTH
+}
+
+/// The first chunk of the region, on its north or west edge.
+pub fn region_to_chunk(region: RCoord) -> CCoord {
+    CCoord(region.0 * REGION_WIDTH)
+}
+
+/// The X or Z of the first block of the region, on its north or west edge.
+pub fn region_to_block(region: RCoord) -> isize {
+    chunk_to_block(region_to_chunk(region))
+}
+
+/// How far into its chunk the block at the given X or Z is, from 0 to 15.
+pub fn block_in_chunk(block: isize) -> usize {
+    block.rem_euclid(CHUNK_WIDTH) as usize
+}
+
+/// How far into its region the chunk is, from 0 to 31. This is how chunks are
+/// given to [`Region`](crate::Region) and [`RegionBuffer`](crate::RegionBuffer).
+pub fn chunk_in_region(chunk: CCoord) -> usize {
+    chunk.0.rem_euclid(REGION_WIDTH) as usize
+}
+
+/// The Y of the section containing the block at `y`, as stored in the `Y`
+/// tag of the section.
+pub fn section_y(y: isize) -> isize {
+    y.div_euclid(SECTION_HEIGHT)
+}
+
+/// How far into its section the block at `y` is, from 0 to 15.
+pub fn y_in_section(y: isize) -> usize {
+    y.rem_euclid(SECTION_HEIGHT) as usize
+}
+
+/// The index of the section containing the block at `y`, counting up from
+/// the bottom of a world starting at `min_y`. None if `y` is below the
+/// world.
+pub fn section_index(y: isize, min_y: isize) -> Option<usize> {
+    if y < min_y {
+        return None;
+    }
+    Some((section_y(y) - section_y(min_y)) as usize)
+}
+
+impl From<CCoord> for RCoord {
+    /// The region containing the chunk.
+    fn from(chunk: CCoord) -> Self {
+        chunk_to_region(chunk)
+    }
+}
+
+impl Add<isize> for CCoord {
+    type Output = Self;
+
+    fn add(self, rhs: isize) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+impl Sub<isize> for CCoord {
+    type Output = Self;
+
+    fn sub(self, rhs: isize) -> Self {
+        Self(self.0 - rhs)
+    }
+}
+
+impl Sub for CCoord {
+    /// The number of chunks between the two.
+    type Output = isize;
+
+    fn sub(self, rhs: Self) -> isize {
+        self.0 - rhs.0
+    }
+}
+
+impl Add<isize> for RCoord {
+    type Output = Self;
+
+    fn add(self, rhs: isize) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+impl Sub<isize> for RCoord {
+    type Output = Self;
+
+    fn sub(self, rhs: isize) -> Self {
+        Self(self.0 - rhs)
+    }
+}
+
+impl Sub for RCoord {
+    /// The number of regions between the two.
+    type Output = isize;
+
+    fn sub(self, rhs: Self) -> isize {
+        self.0 - rhs.0
+    }
+}
+
+/// The position of a block in the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockPos {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+/// The position of a chunk, in chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkPos {
+    pub x: CCoord,
+    pub z: CCoord,
+}
+
+/// The position of a region, in regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RegionPos {
+    pub x: RCoord,
+    pub z: RCoord,
+}
+
+impl BlockPos {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+
+    /// The chunk containing the block.
+    pub fn chunk(&self) -> ChunkPos {
+        ChunkPos::new(block_to_chunk(self.x), block_to_chunk(self.z))
+    }
+
+    /// The region containing the block.
+    pub fn region(&self) -> RegionPos {
+        self.chunk().region()
+    }
+
+    /// The position of the block within its chunk, as X and Z from 0 to 15
+    /// and the Y of the world, as taken by [`Chunk::block`](crate::Chunk::block).
+    pub fn in_chunk(&self) -> (usize, isize, usize) {
+        (block_in_chunk(self.x), self.y, block_in_chunk(self.z))
+    }
+}
+
+impl ChunkPos {
+    pub fn new(x: CCoord, z: CCoord) -> Self {
+        Self { x, z }
+    }
+
+    /// The region containing the chunk.
+    pub fn region(&self) -> RegionPos {
+        RegionPos::new(chunk_to_region(self.x), chunk_to_region(self.z))
+    }
+
+    /// The position of the chunk within its region, as X and Z from 0 to 31,
+    /// as taken by [`Region`](crate::Region).
+    pub fn in_region(&self) -> (usize, usize) {
+        (chunk_in_region(self.x), chunk_in_region(self.z))
+    }
+
+    /// The block in the chunk at the given X and Z from 0 to 15, and Y of the
+    /// world.
+    pub fn block(&self, x: usize, y: isize, z: usize) -> BlockPos {
+        BlockPos::new(
+            chunk_to_block(self.x) + x as isize,
+            y,
+            chunk_to_block(self.z) + z as isize,
+        )
+    }
+}
+
+impl RegionPos {
+    pub fn new(x: RCoord, z: RCoord) -> Self {
+        Self { x, z }
+    }
+
+    /// The chunk in the region at the given X and Z from 0 to 31.
+    pub fn chunk(&self, x: usize, z: usize) -> ChunkPos {
+        ChunkPos::new(
+            region_to_chunk(self.x) + x as isize,
+            region_to_chunk(self.z) + z as isize,
+        )
+    }
+}
+
+impl From<BlockPos> for ChunkPos {
+    fn from(pos: BlockPos) -> Self {
+        pos.chunk()
+    }
+}
+
+impl From<BlockPos> for RegionPos {
+    fn from(pos: BlockPos) -> Self {
+        pos.region()
+    }
+}
+
+impl From<ChunkPos> for RegionPos {
+    fn from(pos: ChunkPos) -> Self {
+        pos.region()
+    }
+}
+
+impl From<(isize, isize, isize)> for BlockPos {
+    fn from((x, y, z): (isize, isize, isize)) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<(CCoord, CCoord)> for ChunkPos {
+    fn from((x, z): (CCoord, CCoord)) -> Self {
+        Self::new(x, z)
+    }
+}
+
+impl From<ChunkPos> for (CCoord, CCoord) {
+    fn from(pos: ChunkPos) -> Self {
+        (pos.x, pos.z)
+    }
+}
+
+impl From<(RCoord, RCoord)> for RegionPos {
+    fn from((x, z): (RCoord, RCoord)) -> Self {
+        Self::new(x, z)
+    }
+}
+
+impl From<RegionPos> for (RCoord, RCoord) {
+    fn from(pos: RegionPos) -> Self {
+        (pos.x, pos.z)
+    }
+}
+
+impl Add<(isize, isize, isize)> for BlockPos {
+    type Output = Self;
+
+    fn add(self, (dx, dy, dz): (isize, isize, isize)) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+}
+
+impl Sub<(isize, isize, isize)> for BlockPos {
+    type Output = Self;
+
+    fn sub(self, (dx, dy, dz): (isize, isize, isize)) -> Self {
+        Self::new(self.x - dx, self.y - dy, self.z - dz)
+    }
+}
+
+impl Sub for BlockPos {
+    /// The number of blocks between the two along X, Y and Z.
+    type Output = (isize, isize, isize);
+
+    fn sub(self, rhs: Self) -> (isize, isize, isize) {
+        (self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Add<(isize, isize)> for ChunkPos {
+    type Output = Self;
+
+    fn add(self, (dx, dz): (isize, isize)) -> Self {
+        Self::new(self.x + dx, self.z + dz)
+    }
+}
+
+impl Sub<(isize, isize)> for ChunkPos {
+    type Output = Self;
+
+    fn sub(self, (dx, dz): (isize, isize)) -> Self {
+        Self::new(self.x - dx, self.z - dz)
+    }
+}
+
+impl Sub for ChunkPos {
+    /// The number of chunks between the two along X and Z.
+    type Output = (isize, isize);
+
+    fn sub(self, rhs: Self) -> (isize, isize) {
+        (self.x - rhs.x, self.z - rhs.z)
+    }
+}
+
+impl Add<(isize, isize)> for RegionPos {
+    type Output = Self;
+
+    fn add(self, (dx, dz): (isize, isize)) -> Self {
+        Self::new(self.x + dx, self.z + dz)
+    }
+}
+
+impl Sub<(isize, isize)> for RegionPos {
+    type Output = Self;
+
+    fn sub(self, (dx, dz): (isize, isize)) -> Self {
+        Self::new(self.x - dx, self.z - dz)
+    }
+}
+
+impl Sub for RegionPos {
+    /// The number of regions between the two along X and Z.
+    type Output = (isize, isize);
+
+    fn sub(self, rhs: Self) -> (isize, isize) {
+        (self.x - rhs.x, self.z - rhs.z)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blocks_to_chunks_and_regions() {
+        for (block, chunk, offset, region) in [
+            (0, 0, 0, 0),
+            (15, 0, 15, 0),
+            (16, 1, 0, 0),
+            (-1, -1, 15, -1),
+            (-16, -1, 0, -1),
+            (-17, -2, 15, -1),
+            (511, 31, 15, 0),
+            (512, 32, 0, 1),
+            (-512, -32, 0, -1),
+            (-513, -33, 15, -2),
+        ] {
+            assert_eq!(CCoord(chunk), block_to_chunk(block), "block {}", block);
+            assert_eq!(offset, block_in_chunk(block), "block {}", block);
+            assert_eq!(RCoord(region), block_to_region(block), "block {}", block);
+            assert_eq!(block, chunk_to_block(CCoord(chunk)) + offset as isize);
+        }
+    }
+
+    #[test]
+    fn chunks_to_regions() {
+        assert_eq!(RCoord(-1), chunk_to_region(CCoord(-1)));
+        assert_eq!(31, chunk_in_region(CCoord(-1)));
+        assert_eq!(RCoord(-2), RCoord::from(CCoord(-33)));
+        assert_eq!(CCoord(-64), region_to_chunk(RCoord(-2)));
+        assert_eq!(-1024, region_to_block(RCoord(-2)));
+        assert_eq!(0, chunk_in_region(CCoord(64)));
+    }
+
+    #[test]
+    fn sections() {
+        assert_eq!(-4, section_y(-64));
+        assert_eq!(-1, section_y(-1));
+        assert_eq!(15, y_in_section(-1));
+        assert_eq!(0, y_in_section(16));
+
+        assert_eq!(Some(0), section_index(-64, -64));
+        assert_eq!(Some(3), section_index(-1, -64));
+        assert_eq!(Some(4), section_index(0, -64));
+        assert_eq!(Some(23), section_index(319, -64));
+        assert_eq!(None, section_index(-65, -64));
+        assert_eq!(Some(0), section_index(15, 0));
+    }
+
+    #[test]
+    fn positions() {
+        let pos = BlockPos::new(-513, -64, 17);
+        assert_eq!(ChunkPos::new(CCoord(-33), CCoord(1)), pos.chunk());
+        assert_eq!(RegionPos::new(RCoord(-2), RCoord(0)), RegionPos::from(pos));
+        assert_eq!((15, -64, 1), pos.in_chunk());
+        assert_eq!((31, 1), pos.chunk().in_region());
+        assert_eq!(pos, pos.chunk().block(15, -64, 1));
+
+        let region = RegionPos::from((RCoord(-1), RCoord(2)));
+        assert_eq!(ChunkPos::new(CCoord(-1), CCoord(64)), region.chunk(31, 0));
+        assert_eq!(region, region.chunk(31, 0).region());
+        assert_eq!((RCoord(-1), RCoord(2)), region.into());
+
+        assert_eq!((0, 0, 0), pos - pos);
+        assert_eq!(BlockPos::new(-512, -66, 20), pos + (1, -2, 3));
+        assert_eq!(pos, pos + (1, -2, 3) - (1, -2, 3));
+
+        let chunk = pos.chunk() + (1, 2);
+        assert_eq!(ChunkPos::new(CCoord(-32), CCoord(3)), chunk);
+        assert_eq!((1, 2), chunk - pos.chunk());
+        assert_eq!(pos.chunk(), chunk - (1, 2));
+        assert_eq!((0, -3), region.chunk(0, 0).region() - (region + (0, 3)));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(CCoord(-1), CCoord(1) - 2);
+        assert_eq!(RCoord(0), RCoord(-1) + 1);
+        assert_eq!(-3, CCoord(-1) - CCoord(2));
+        assert_eq!(2, RCoord(1) - RCoord(-1));
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use fastnbt::Value;
+
+use crate::{Block, Chunk, ChunkLocation, RegionBuffer, Result};
+
+/// How [`diff_regions`] decides whether a chunk present in both regions has
+/// been modified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffBy {
+    /// Compare the timestamps in the region headers. This only reads the
+    /// headers, so is fast, but counts chunks that were saved without
+    /// changing as modified.
+    Timestamp,
+
+    /// Compare the NBT of the chunks. Every chunk present in both regions is
+    /// decompressed and parsed. The game updates `LastUpdate` and
+    /// `InhabitedTime` every time it saves a loaded chunk, so these are
+    /// ignored.
+    Content,
+}
+
+/// How a chunk differs between two regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkChange {
+    /// The chunk is only in the second region.
+    Added,
+
+    /// The chunk is only in the first region.
+    Removed,
+
+    /// The chunk is in both regions, but differs.
+    Modified,
+}
+
+/// A chunk that differs between two regions, see [`diff_regions`]. The chunk
+/// coordinates are region-relative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkDiff {
+    pub x: usize,
+    pub z: usize,
+    pub change: ChunkChange,
+}
+
+/// A block that differs between two chunks, see [`diff_chunk_blocks`]. The
+/// coordinates are chunk-relative, except `y` which is the world height. A
+/// missing block is `None`, such as in a section that isn't stored, and is
+/// treated the same as air when comparing.
+#[derive(Debug, Clone)]
+pub struct BlockChange {
+    pub x: usize,
+    pub y: isize,
+    pub z: usize,
+    pub before: Option<Block>,
+    pub after: Option<Block>,
+}
+
+/// Compare two versions of a region, such as a backup and the live world,
+/// giving every chunk that was added, removed or modified going from `a` to
+/// `b`. Chunks are ordered by z then x. See [`DiffBy`] for how modified
+/// chunks are found. An empty region file counts as a region with no chunks.
+///
+/// Use [`diff_chunk_blocks`] to find which blocks changed in a modified chunk.
+///
+/// ```no_run
+/// use fastanvil::{diff_chunk_blocks, diff_regions, ChunkChange, DiffBy, JavaChunk, RegionBuffer};
+///
+/// let backup = RegionBuffer::new(std::fs::File::open("backup/region/r.0.0.mca")?);
+/// let live = RegionBuffer::new(std::fs::File::open("world/region/r.0.0.mca")?);
+///
+/// for diff in diff_regions(&backup, &live, DiffBy::Content)? {
+///     if diff.change != ChunkChange::Modified {
+///         println!("chunk {},{} {:?}", diff.x, diff.z, diff.change);
+///         continue;
+///     }
+///
+///     let before = JavaChunk::from_bytes(&backup.load_chunk(diff.x, diff.z)?)?;
+///     let after = JavaChunk::from_bytes(&live.load_chunk(diff.x, diff.z)?)?;
+///     for change in diff_chunk_blocks(&before, &after) {
+///         println!("{},{},{} changed to {:?}", change.x, change.y, change.z, change.after);
+///     }
+/// }
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn diff_regions<A, B>(
+    a: &RegionBuffer<A>,
+    b: &RegionBuffer<B>,
+    by: DiffBy,
+) -> Result<Vec<ChunkDiff>>
+where
+    A: Seek + Read,
+    B: Seek + Read,
+{
+    let mut present = [(false, false); 1024];
+    for loc in present_chunks(a)? {
+        present[loc.x + loc.z * 32].0 = true;
+    }
+    for loc in present_chunks(b)? {
+        present[loc.x + loc.z * 32].1 = true;
+    }
+
+    let mut diffs = Vec::new();
+
+    for (i, &(in_a, in_b)) in present.iter().enumerate() {
+        let (x, z) = (i % 32, i / 32);
+
+        let change = match (in_a, in_b) {
+            (false, false) => continue,
+            (false, true) => ChunkChange::Added,
+            (true, false) => ChunkChange::Removed,
+            (true, true) => {
+                let modified = match by {
+                    DiffBy::Timestamp => a.chunk_timestamp(x, z)? != b.chunk_timestamp(x, z)?,
+                    DiffBy::Content => {
+                        let a_nbt: Value = fastnbt::de::from_bytes(&a.load_chunk(x, z)?)?;
+                        let b_nbt: Value = fastnbt::de::from_bytes(&b.load_chunk(x, z)?)?;
+                        without_ticking_tags(a_nbt) != without_ticking_tags(b_nbt)
+                    }
+                };
+
+                if !modified {
+                    continue;
+                }
+                ChunkChange::Modified
+            }
+        };
+
+        diffs.push(ChunkDiff { x, z, change });
+    }
+
+    Ok(diffs)
+}
+
+/// Tags that change whenever a chunk is saved, whether or not anything
+/// happened in it.
+const TICKING_TAGS: &[&str] = &["LastUpdate", "InhabitedTime"];
+
+/// Remove the [`TICKING_TAGS`] from a chunk. Chunks from before 1.18 have
+/// them in the `Level` compound.
+fn without_ticking_tags(mut nbt: Value) -> Value {
+    if let Value::Compound(root) = &mut nbt {
+        for tag in TICKING_TAGS {
+            root.remove(*tag);
+        }
+        if let Some(Value::Compound(level)) = root.get_mut("Level") {
+            for tag in TICKING_TAGS {
+                level.remove(*tag);
+            }
+        }
+    }
+    nbt
+}
+
+/// The chunks present in a region. Minecraft sometimes leaves empty region
+/// files behind, which have no chunks rather than a truncated header.
+fn present_chunks<S: Seek + Read>(region: &RegionBuffer<S>) -> Result<Vec<ChunkLocation>> {
+    if region.data().seek(SeekFrom::End(0))? == 0 {
+        return Ok(Vec::new());
+    }
+    region.locations()
+}
+
+/// Compare the blocks of two versions of a chunk, giving every block that
+/// differs in name or properties. The heights compared cover both chunks, so
+/// a chunk upgraded to a taller world has its new blocks included.
+pub fn diff_chunk_blocks<A: Chunk, B: Chunk>(a: &A, b: &B) -> Vec<BlockChange> {
+    let (a_range, b_range) = (a.y_range(), b.y_range());
+    let ys = a_range.start.min(b_range.start)..a_range.end.max(b_range.end);
+
+    let is_air = |block: Option<&Block>| match block {
+        Some(block) => block.name() == "minecraft:air",
+        None => true,
+    };
+    let same = |before: Option<&Block>, after: Option<&Block>| match (before, after) {
+        (Some(before), Some(after)) => {
+            before.name() == after.name() && before.properties() == after.properties()
+        }
+        _ => is_air(before) && is_air(after),
+    };
+
+    let mut changes = Vec::new();
+
+    for y in ys {
+        for z in 0..16 {
+            for x in 0..16 {
+                let (before, after) = (a.block(x, y, z), b.block(x, y, z));
+                if !same(before, after) {
+                    changes.push(BlockChange {
+                        x,
+                        y,
+                        z,
+                        before: before.cloned(),
+                        after: after.cloned(),
+                    });
+                }
+            }
+        }
+    }
+
+    changes
+}
@@ -1,9 +1,6 @@
-use std::{
-    cell::RefCell, collections::HashMap, error::Error, fmt::Display, marker::PhantomData,
-    ops::Range, rc::Rc,
-};
+use std::{cell::RefCell, error::Error, fmt::Display, ops::Range, rc::Rc};
 
-use crate::{biome::Biome, Block};
+use crate::{biome::Biome, Block, BlockEntity, BlockPos, CacheStats, LruCache};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct RCoord(pub isize);
@@ -11,19 +8,167 @@ pub struct RCoord(pub isize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CCoord(pub isize);
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeightMode {
     Trust,     // trust height maps from chunk data
     Calculate, // calculate height maps manually, much slower.
 }
 
+/// The kind of dimension a world is, which changes how it is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DimensionKind {
+    Overworld,
+
+    /// The nether, which has a roof of bedrock over the whole dimension.
+    Nether,
+
+    /// The end, which is mostly void.
+    End,
+}
+
+impl DimensionKind {
+    /// The kind of a dimension from its ID, such as `minecraft:the_nether`.
+    /// Custom dimensions are taken to be like the overworld.
+    pub fn from_id(id: &str) -> Self {
+        match id.strip_prefix("minecraft:").unwrap_or(id) {
+            "the_nether" => DimensionKind::Nether,
+            "the_end" => DimensionKind::End,
+            _ => DimensionKind::Overworld,
+        }
+    }
+
+    /// The biome to assume when a chunk has no biome data. Chunks in the
+    /// nether and end are the same biome everywhere in older versions, so
+    /// this gives their blocks the right tint.
+    pub fn default_biome(&self) -> Option<Biome> {
+        match self {
+            DimensionKind::Overworld => None,
+            DimensionKind::Nether => Some(Biome::Nether),
+            DimensionKind::End => Some(Biome::TheEnd),
+        }
+    }
+}
+
+/// How far through world generation a chunk is. Chunks at the edge of
+/// the explored world are often left part way through, and only chunks that
+/// are [`generated`](Self::is_generated) are worth rendering.
+///
+/// Statuses are listed in the order they happen. Versions before 1.14 used
+/// different statuses, which are kept as [`Other`](Self::Other).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ChunkStatus {
+    Empty,
+    StructureStarts,
+    StructureReferences,
+    Biomes,
+    Noise,
+    Surface,
+    Carvers,
+
+    /// Removed in 1.18.
+    LiquidCarvers,
+    Features,
+
+    /// Added in 1.20.
+    InitializeLight,
+    Light,
+    Spawn,
+
+    /// Removed in 1.18.
+    Heightmaps,
+    Full,
+
+    /// A status not listed here, by name.
+    Other(String),
+}
+
+impl ChunkStatus {
+    /// Parse the status stored in a chunk. The namespace statuses gained in
+    /// 1.20 is optional.
+    pub fn from_name(name: &str) -> Self {
+        match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "empty" => ChunkStatus::Empty,
+            "structure_starts" => ChunkStatus::StructureStarts,
+            "structure_references" => ChunkStatus::StructureReferences,
+            "biomes" => ChunkStatus::Biomes,
+            "noise" => ChunkStatus::Noise,
+            "surface" => ChunkStatus::Surface,
+            "carvers" => ChunkStatus::Carvers,
+            "liquid_carvers" => ChunkStatus::LiquidCarvers,
+            "features" => ChunkStatus::Features,
+            "initialize_light" => ChunkStatus::InitializeLight,
+            "light" => ChunkStatus::Light,
+            "spawn" => ChunkStatus::Spawn,
+            "heightmaps" => ChunkStatus::Heightmaps,
+            "full" => ChunkStatus::Full,
+            other => ChunkStatus::Other(other.to_owned()),
+        }
+    }
+
+    /// The name of the status, without a namespace.
+    pub fn name(&self) -> &str {
+        match self {
+            ChunkStatus::Empty => "empty",
+            ChunkStatus::StructureStarts => "structure_starts",
+            ChunkStatus::StructureReferences => "structure_references",
+            ChunkStatus::Biomes => "biomes",
+            ChunkStatus::Noise => "noise",
+            ChunkStatus::Surface => "surface",
+            ChunkStatus::Carvers => "carvers",
+            ChunkStatus::LiquidCarvers => "liquid_carvers",
+            ChunkStatus::Features => "features",
+            ChunkStatus::InitializeLight => "initialize_light",
+            ChunkStatus::Light => "light",
+            ChunkStatus::Spawn => "spawn",
+            ChunkStatus::Heightmaps => "heightmaps",
+            ChunkStatus::Full => "full",
+            ChunkStatus::Other(name) => name,
+        }
+    }
+
+    /// Whether the blocks of the chunk have all been generated. Chunks are
+    /// complete apart from spawning mobs from the `spawn` status on.
+    pub fn is_generated(&self) -> bool {
+        matches!(
+            self,
+            ChunkStatus::Spawn | ChunkStatus::Heightmaps | ChunkStatus::Full
+        )
+    }
+}
+
+impl Display for ChunkStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 pub trait Chunk {
-    fn status(&self) -> String;
+    fn status(&self) -> ChunkStatus;
+
+    /// The total time in ticks players have spent in the chunk. Chunks that
+    /// were generated but never visited have an inhabited time of zero.
+    /// `None` if the chunk doesn't record it.
+    fn inhabited_time(&self) -> Option<i64> {
+        None
+    }
 
     /// Get the height of the first air-like block above something not air-like.
     /// Will panic if given x/z coordinates outside of 0..16.
     fn surface_height(&self, x: usize, z: usize, mode: HeightMode) -> isize;
 
+    /// Get the surface height of every column of the chunk at once, indexed
+    /// by `[z][x]`. Chunks that cache their heights can return them all
+    /// without working each one out separately.
+    fn surface_heights(&self, mode: HeightMode) -> [[isize; 16]; 16] {
+        let mut heights = [[0; 16]; 16];
+        for (z, row) in heights.iter_mut().enumerate() {
+            for (x, h) in row.iter_mut().enumerate() {
+                *h = self.surface_height(x, z, mode);
+            }
+        }
+        heights
+    }
+
     /// Get the biome of the given coordinate. A biome may not exist if the
     /// section of the chunk accessed is not present. For example,
     /// trying to access the block at height 1234 would return None.
@@ -34,6 +179,36 @@ pub trait Chunk {
     /// trying to access the block at height 1234 would return None.
     fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block>;
 
+    /// Get the block entities of the chunk, such as chests and signs.
+    /// Chunks without block entity support have none.
+    fn block_entities(&self) -> &[BlockEntity] {
+        &[]
+    }
+
+    /// Get the block entity at the given coordinates, if there is one. Will
+    /// panic if given x/z coordinates outside of 0..16.
+    fn block_entity_at(&self, x: usize, y: isize, z: usize) -> Option<&BlockEntity> {
+        assert!(x < 16 && z < 16);
+        self.block_entities()
+            .iter()
+            .find(|e| e.chunk_position() == (x, y, z))
+    }
+
+    /// Get the light level from blocks such as torches at the given
+    /// coordinates, from 0 to 15. Returns None if the chunk has no light data
+    /// there, for example if it has not been lit yet. See
+    /// [`light`](crate::light) for calculating it.
+    fn block_light(&self, _x: usize, _y: isize, _z: usize) -> Option<u8> {
+        None
+    }
+
+    /// Get the light level from the sky at the given coordinates, from 0 to
+    /// 15. Returns None if the chunk has no light data there, which is always
+    /// the case in dimensions without a sky.
+    fn sky_light(&self, _x: usize, _y: isize, _z: usize) -> Option<u8> {
+        None
+    }
+
     /// Get the range of Y values that are valid for this chunk.
     fn y_range(&self) -> Range<isize>;
 }
@@ -43,6 +218,22 @@ pub trait Region<C: Chunk> {
     /// Implmentations do not need to be concerned with caching chunks they have
     /// loaded, this will be handled by the types using the region.
     fn chunk(&self, x: CCoord, z: CCoord) -> Option<C>;
+
+    /// Whether the chunk at the given chunk coordinates, ie 0..32 for x and
+    /// z, exists. By default this loads the chunk, so implementations that
+    /// can tell more cheaply, such as from a header, should do so.
+    fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        self.chunk(x, z).is_some()
+    }
+
+    /// The last modification times of every chunk in the region, in seconds
+    /// since the Unix epoch, ordered by z then x so the chunk at (x, z) is at
+    /// index `x + z * 32`. Chunks that are not present typically have a
+    /// timestamp of zero. `None` if the region doesn't record them, which is
+    /// the default.
+    fn chunk_timestamps(&self) -> Option<Vec<u32>> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -71,38 +262,399 @@ pub trait RegionLoader<C: Chunk> {
     /// List the regions that this loader can return. Implmentations need to
     /// provide this so that callers can efficiently find regions to process.
     fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>>;
+
+    /// Whether the chunk at the given (absolute) chunk coordinates exists.
+    /// By default this asks the region, see [`Region::chunk_exists`].
+    fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        let region = match self.region(RCoord(x.0.div_euclid(32)), RCoord(z.0.div_euclid(32))) {
+            Some(region) => region,
+            None => return false,
+        };
+        region.chunk_exists(CCoord(x.0.rem_euclid(32)), CCoord(z.0.rem_euclid(32)))
+    }
+
+    /// The chunk timestamps of a region, see [`Region::chunk_timestamps`].
+    /// `None` if the region doesn't exist or doesn't record them.
+    fn chunk_timestamps(&self, x: RCoord, z: RCoord) -> Option<Vec<u32>> {
+        self.region(x, z)?.chunk_timestamps()
+    }
 }
 
-type RegionsMap<R> = RefCell<HashMap<(RCoord, RCoord), Rc<R>>>;
+type RegionsMap<R> = RefCell<LruCache<(RCoord, RCoord), Rc<R>>>;
+type ChunksMap<C> = RefCell<LruCache<(CCoord, CCoord), Option<Rc<C>>>>;
+
+/// The number of chunks a Dimension caches unless told otherwise, a region's
+/// worth.
+const DEFAULT_CHUNK_CAPACITY: usize = 32 * 32;
 
 /// Dimension provides a cache on top of a RegionLoader.
+///
+/// By default every region loaded is kept forever. When scanning a large
+/// world use [`with_region_capacity`](Self::with_region_capacity) to keep
+/// only the most recently used regions.
+///
+/// Chunks loaded through [`chunk`](Self::chunk) and [`block`](Self::block)
+/// are cached separately, up to 1024 chunks by default. See
+/// [`with_chunk_capacity`](Self::with_chunk_capacity).
 pub struct Dimension<C: Chunk, R: RegionLoader<C>> {
     loader: R,
     regions: RegionsMap<R::RegionType>,
-    p: PhantomData<C>,
+    chunks: ChunksMap<C>,
 }
 
 impl<C: Chunk, R: RegionLoader<C>> Dimension<C, R> {
     pub fn new(loader: R) -> Self {
         Self {
             loader,
-            regions: Default::default(),
-            p: PhantomData,
+            regions: RefCell::new(LruCache::new(None)),
+            chunks: RefCell::new(LruCache::new(Some(DEFAULT_CHUNK_CAPACITY))),
+        }
+    }
+
+    /// Create a Dimension that caches at most `capacity` regions, dropping
+    /// the least recently used region when another is loaded. Regions that
+    /// are still referenced elsewhere stay alive until those references are
+    /// dropped.
+    pub fn with_region_capacity(loader: R, capacity: usize) -> Self {
+        Self {
+            regions: RefCell::new(LruCache::new(Some(capacity))),
+            ..Self::new(loader)
+        }
+    }
+
+    /// Cache at most `capacity` decoded chunks, rather than the default of
+    /// 1024. Any chunks already cached are dropped.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::with_region_capacity(loader, 16).with_chunk_capacity(4096);
+    /// ```
+    pub fn with_chunk_capacity(self, capacity: usize) -> Self {
+        Self {
+            chunks: RefCell::new(LruCache::new(Some(capacity))),
+            ..self
         }
     }
 
+    /// The loader the Dimension reads regions from.
+    pub fn loader(&self) -> &R {
+        &self.loader
+    }
+
+    /// The loader the Dimension reads regions from, for editing. Regions
+    /// already cached are not reloaded, [`evict`](Self::evict) them to see
+    /// the changes.
+    pub fn loader_mut(&mut self) -> &mut R {
+        &mut self.loader
+    }
+
     /// Get a region, maybe from Dimension's internal cache.
     pub fn region(&self, x: RCoord, z: RCoord) -> Option<Rc<R::RegionType>> {
         let mut cache = self.regions.borrow_mut();
 
-        cache.get(&(x, z)).map(|r| Rc::clone(r)).or_else(|| {
-            let r = Rc::from(self.loader.region(x, z)?);
-            cache.insert((x, z), Rc::clone(&r));
-            Some(r)
+        if let Some(r) = cache.get(&(x, z)) {
+            return Some(Rc::clone(r));
+        }
+
+        let r = Rc::from(self.loader.region(x, z)?);
+        cache.insert((x, z), Rc::clone(&r));
+        Some(r)
+    }
+
+    /// Get the chunk at the given (absolute) chunk coordinates, maybe from
+    /// Dimension's internal cache. Chunks that do not exist are cached too,
+    /// so are cheap to ask for again.
+    pub fn chunk(&self, x: CCoord, z: CCoord) -> Option<Rc<C>> {
+        if let Some(chunk) = self.chunks.borrow_mut().get(&(x, z)) {
+            return chunk.clone();
+        }
+
+        let chunk = self
+            .region(RCoord(x.0.div_euclid(32)), RCoord(z.0.div_euclid(32)))
+            .and_then(|region| region.chunk(CCoord(x.0.rem_euclid(32)), CCoord(z.0.rem_euclid(32))))
+            .map(Rc::new);
+
+        self.chunks.borrow_mut().insert((x, z), chunk.clone());
+        chunk
+    }
+
+    /// Whether the chunk at the given (absolute) chunk coordinates exists,
+    /// without loading it if the region can tell from its header. Chunks
+    /// already in the cache are answered from there.
+    pub fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        if let Some(chunk) = self.chunks.borrow_mut().get(&(x, z)) {
+            return chunk.is_some();
+        }
+
+        match self.region(RCoord(x.0.div_euclid(32)), RCoord(z.0.div_euclid(32))) {
+            Some(region) => {
+                region.chunk_exists(CCoord(x.0.rem_euclid(32)), CCoord(z.0.rem_euclid(32)))
+            }
+            None => false,
+        }
+    }
+
+    /// The chunk timestamps of a region, maybe from Dimension's internal
+    /// cache. See [`Region::chunk_timestamps`]. These let a region be
+    /// processed again only when its chunks have changed, whatever the
+    /// regions are loaded from.
+    pub fn chunk_timestamps(&self, x: RCoord, z: RCoord) -> Option<Vec<u32>> {
+        self.region(x, z)?.chunk_timestamps()
+    }
+
+    /// Get the block at the given world position, a [`BlockPos`] or an
+    /// `(x, y, z)` tuple. Returns None if the chunk or the section containing
+    /// the block is not present.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::new(loader);
+    ///
+    /// if let Some(block) = dimension.block((-120, 64, 3000)) {
+    ///     println!("{}", block.name());
+    /// }
+    /// ```
+    pub fn block(&self, pos: impl Into<BlockPos>) -> Option<Block> {
+        let pos = pos.into();
+        let chunk = pos.chunk();
+        let (x, y, z) = pos.in_chunk();
+        self.chunk(chunk.x, chunk.z)?.block(x, y, z).cloned()
+    }
+
+    /// Iterate over every block in the box between the world positions `min`
+    /// and `max`, inclusive, giving the position of each block along with
+    /// it. Blocks in chunks or sections that are not present are
+    /// skipped.
+    ///
+    /// Blocks are visited a chunk at a time, and chunks a region at a time,
+    /// so that each chunk and region only needs to be loaded once. Within a
+    /// chunk blocks are ordered by y, then z, then x.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::new(loader);
+    ///
+    /// for (pos, block) in dimension.iter_blocks((-100, -64, -100), (100, 16, 100)) {
+    ///     if block.name() == "minecraft:diamond_ore" {
+    ///         println!("diamonds at {}, {}, {}", pos.x, pos.y, pos.z);
+    ///     }
+    /// }
+    /// ```
+    pub fn iter_blocks(
+        &self,
+        min: impl Into<BlockPos>,
+        max: impl Into<BlockPos>,
+    ) -> impl Iterator<Item = (BlockPos, Block)> + '_ {
+        let (min, max) = (min.into(), max.into());
+        let (cx_min, cx_max) = (min.x.div_euclid(16), max.x.div_euclid(16));
+        let (cz_min, cz_max) = (min.z.div_euclid(16), max.z.div_euclid(16));
+
+        let regions = (cz_min.div_euclid(32)..=cz_max.div_euclid(32)).flat_map(move |rz| {
+            (cx_min.div_euclid(32)..=cx_max.div_euclid(32)).map(move |rx| (rx, rz))
+        });
+
+        let chunks = regions.flat_map(move |(rx, rz)| {
+            let xs = cx_min.max(rx * 32)..=cx_max.min(rx * 32 + 31);
+            let zs = cz_min.max(rz * 32)..=cz_max.min(rz * 32 + 31);
+            zs.flat_map(move |cz| xs.clone().map(move |cx| (cx, cz)))
+        });
+
+        chunks
+            .filter_map(move |(cx, cz)| Some((cx, cz, self.chunk(CCoord(cx), CCoord(cz))?)))
+            .flat_map(move |(cx, cz, chunk)| blocks_in_chunk(chunk, cx, cz, min, max))
+    }
+
+    /// Like [`iter_blocks`](Self::iter_blocks), but only giving blocks that
+    /// match `predicate`.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::new(loader);
+    ///
+    /// let diamonds = dimension
+    ///     .find_blocks((-100, -64, -100), (100, 16, 100), |b| {
+    ///         b.name() == "minecraft:deepslate_diamond_ore"
+    ///     })
+    ///     .count();
+    /// ```
+    pub fn find_blocks<'a, P>(
+        &'a self,
+        min: impl Into<BlockPos>,
+        max: impl Into<BlockPos>,
+        mut predicate: P,
+    ) -> impl Iterator<Item = (BlockPos, Block)> + 'a
+    where
+        P: FnMut(&Block) -> bool + 'a,
+    {
+        self.iter_blocks(min, max)
+            .filter(move |(_, block)| predicate(block))
+    }
+
+    /// Find every chunk in the dimension that matches `predicate`, giving
+    /// their (absolute) chunk coordinates ordered by region. Regions are
+    /// loaded through the cache, but the chunks are not cached, so that
+    /// scanning a whole world doesn't push out chunks being worked on.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{Chunk, ChunkStatus, Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::with_region_capacity(loader, 16);
+    ///
+    /// // Chunks that were generated as the world was explored, but that no
+    /// // one has spent any time in.
+    /// let unvisited = dimension.find_chunks(|chunk| {
+    ///     chunk.status() == ChunkStatus::Full && chunk.inhabited_time() == Some(0)
+    /// })?;
+    /// # Ok::<(), fastanvil::LoaderError>(())
+    /// ```
+    pub fn find_chunks<P>(&self, mut predicate: P) -> LoaderResult<Vec<(CCoord, CCoord)>>
+    where
+        P: FnMut(&C) -> bool,
+    {
+        let mut found = Vec::new();
+
+        for (rx, rz) in self.loader.list()? {
+            let region = match self.region(rx, rz) {
+                Some(region) => region,
+                None => continue,
+            };
+
+            for z in 0..32 {
+                for x in 0..32 {
+                    let matches = match region.chunk(CCoord(x), CCoord(z)) {
+                        Some(chunk) => predicate(&chunk),
+                        None => false,
+                    };
+                    if matches {
+                        found.push((CCoord(rx.0 * 32 + x), CCoord(rz.0 * 32 + z)));
+                    }
+                }
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Drop a region from the cache. Returns whether the region was cached.
+    pub fn evict(&self, x: RCoord, z: RCoord) -> bool {
+        self.regions.borrow_mut().remove(&(x, z)).is_some()
+    }
+
+    /// Drop every region and chunk from the cache.
+    pub fn clear(&self) {
+        self.regions.borrow_mut().clear();
+        self.chunks.borrow_mut().clear();
+    }
+
+    /// The number of regions currently cached.
+    pub fn cached_regions(&self) -> usize {
+        self.regions.borrow().len()
+    }
+
+    /// Statistics of the region cache.
+    pub fn region_cache_stats(&self) -> CacheStats {
+        self.regions.borrow().stats()
+    }
+
+    /// Statistics of the chunk cache.
+    pub fn chunk_cache_stats(&self) -> CacheStats {
+        self.chunks.borrow().stats()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<C: Chunk, R: RegionLoader<C> + Sync> Dimension<C, R> {
+    /// Call `f` with every chunk in the dimension, along with its (absolute)
+    /// chunk coordinates, spread across rayon's thread pool a region at a
+    /// time. Each thread opens the regions it works on straight from the
+    /// loader, so neither regions nor chunks go through the cache, and
+    /// chunks are never sent between threads.
+    ///
+    /// Stops with an error if a listed region can't be opened, or a chunk
+    /// the region says is present fails to load. Chunks already passed to
+    /// `f` on other threads are not undone.
+    ///
+    /// ```no_run
+    /// # use std::sync::atomic::{AtomicUsize, Ordering};
+    /// # use fastanvil::{Chunk, Dimension, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+    /// let dimension = Dimension::new(loader);
+    ///
+    /// let generated = AtomicUsize::new(0);
+    /// dimension.par_for_each_chunk(|_x, _z, chunk| {
+    ///     if chunk.status().is_generated() {
+    ///         generated.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// })?;
+    /// println!("{} chunks", generated.into_inner());
+    /// # Ok::<(), fastanvil::LoaderError>(())
+    /// ```
+    pub fn par_for_each_chunk<F>(&self, f: F) -> LoaderResult<()>
+    where
+        F: Fn(CCoord, CCoord, C) + Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        // Only the loader is shared between threads, the cache can't be.
+        let loader = &self.loader;
+
+        loader.list()?.into_par_iter().try_for_each(|(rx, rz)| {
+            let region = loader.region(rx, rz).ok_or_else(|| {
+                LoaderError(format!("region {}, {} could not be opened", rx.0, rz.0))
+            })?;
+
+            for z in 0..32 {
+                for x in 0..32 {
+                    let (cx, cz) = (CCoord(rx.0 * 32 + x), CCoord(rz.0 * 32 + z));
+                    match region.chunk(CCoord(x), CCoord(z)) {
+                        Some(chunk) => f(cx, cz, chunk),
+                        None if region.chunk_exists(CCoord(x), CCoord(z)) => {
+                            return Err(LoaderError(format!(
+                                "chunk {}, {} could not be loaded",
+                                cx.0, cz.0
+                            )));
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            Ok(())
         })
     }
 }
 
+/// The blocks of a chunk at the chunk coordinates (cx, cz) that are inside
+/// the box between the world coordinates `min` and `max`.
+fn blocks_in_chunk<C: Chunk>(
+    chunk: Rc<C>,
+    cx: isize,
+    cz: isize,
+    min: BlockPos,
+    max: BlockPos,
+) -> impl Iterator<Item = (BlockPos, Block)> {
+    let xs = min.x.max(cx * 16)..=max.x.min(cx * 16 + 15);
+    let zs = min.z.max(cz * 16)..=max.z.min(cz * 16 + 15);
+
+    let y_range = chunk.y_range();
+    let ys = min.y.max(y_range.start)..=max.y.min(y_range.end - 1);
+
+    ys.flat_map(move |y| {
+        let xs = xs.clone();
+        zs.clone()
+            .flat_map(move |z| xs.clone().map(move |x| (x, y, z)))
+    })
+    .filter_map(move |(x, y, z)| {
+        let pos = BlockPos::new(x, y, z);
+        let (x, y, z) = pos.in_chunk();
+        Some((pos, chunk.block(x, y, z)?.clone()))
+    })
+}
+
 #[cfg(test)]
 mod test {
     use std::marker::PhantomData;
@@ -129,4 +681,357 @@ mod test {
             todo!()
         }
     }
+
+    fn dimension(capacity: Option<usize>) -> Dimension<DummyChunk, DummyLoader<DummyChunk>> {
+        let loader = DummyLoader::<DummyChunk>(PhantomData);
+        match capacity {
+            Some(capacity) => Dimension::with_region_capacity(loader, capacity),
+            None => Dimension::new(loader),
+        }
+    }
+
+    struct DummyChunk;
+
+    impl Chunk for DummyChunk {
+        fn status(&self) -> ChunkStatus {
+            unimplemented!()
+        }
+
+        fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+            unimplemented!()
+        }
+
+        fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+            unimplemented!()
+        }
+
+        fn block(&self, _x: usize, _y: isize, _z: usize) -> Option<&Block> {
+            unimplemented!()
+        }
+
+        fn y_range(&self) -> Range<isize> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn region_cache_unbounded() {
+        let dim = dimension(None);
+        for x in 0..10 {
+            dim.region(RCoord(x), RCoord(0)).unwrap();
+        }
+        dim.region(RCoord(0), RCoord(0)).unwrap();
+
+        assert_eq!(10, dim.cached_regions());
+        assert_eq!(
+            CacheStats {
+                hits: 1,
+                misses: 10,
+                evictions: 0
+            },
+            dim.region_cache_stats()
+        );
+    }
+
+    #[test]
+    fn region_cache_bounded() {
+        let dim = dimension(Some(2));
+        let first = dim.region(RCoord(0), RCoord(0)).unwrap();
+        dim.region(RCoord(1), RCoord(0)).unwrap();
+        dim.region(RCoord(2), RCoord(0)).unwrap();
+
+        assert_eq!(2, dim.cached_regions());
+        assert_eq!(1, dim.region_cache_stats().evictions);
+
+        // The evicted region is loaded again rather than reused.
+        let again = dim.region(RCoord(0), RCoord(0)).unwrap();
+        assert!(!Rc::ptr_eq(&first, &again));
+        assert_eq!(4, dim.region_cache_stats().misses);
+    }
+
+    #[test]
+    fn region_cache_evict_and_clear() {
+        let dim = dimension(None);
+        dim.region(RCoord(0), RCoord(0)).unwrap();
+        dim.region(RCoord(1), RCoord(0)).unwrap();
+
+        assert!(dim.evict(RCoord(0), RCoord(0)));
+        assert!(!dim.evict(RCoord(0), RCoord(0)));
+        assert_eq!(1, dim.cached_regions());
+
+        dim.clear();
+        assert_eq!(0, dim.cached_regions());
+    }
+
+    /// A chunk holding a single block, named after the absolute coordinates
+    /// of the chunk.
+    struct CoordChunk(Block);
+
+    impl Chunk for CoordChunk {
+        fn status(&self) -> ChunkStatus {
+            unimplemented!()
+        }
+
+        fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+            unimplemented!()
+        }
+
+        fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+            unimplemented!()
+        }
+
+        fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+            if (x, y, z) == (15, 64, 1) {
+                Some(&self.0)
+            } else {
+                None
+            }
+        }
+
+        fn y_range(&self) -> Range<isize> {
+            -64..320
+        }
+    }
+
+    struct CoordRegion(RCoord, RCoord);
+
+    impl Region<CoordChunk> for CoordRegion {
+        fn chunk(&self, x: CCoord, z: CCoord) -> Option<CoordChunk> {
+            assert!((0..32).contains(&x.0) && (0..32).contains(&z.0));
+
+            // Pretend this chunk is missing in every region.
+            if (x.0, z.0) == (5, 5) {
+                return None;
+            }
+
+            let name = format!("{},{}", self.0 .0 * 32 + x.0, self.1 .0 * 32 + z.0);
+            Some(CoordChunk(Block::new(name, Default::default())))
+        }
+    }
+
+    struct CoordLoader;
+
+    impl RegionLoader<CoordChunk> for CoordLoader {
+        type RegionType = CoordRegion;
+
+        fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
+            Some(CoordRegion(x, z))
+        }
+
+        fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+            Ok(vec![(RCoord(-1), RCoord(0)), (RCoord(0), RCoord(0))])
+        }
+    }
+
+    #[test]
+    fn find_chunks_in_listed_regions() {
+        let dim = Dimension::new(CoordLoader);
+
+        let row = |z: isize| {
+            let name = format!(",{}", z);
+            dim.find_chunks(|chunk| chunk.0.name().ends_with(&name))
+                .unwrap()
+        };
+
+        let found = row(3);
+        assert_eq!(64, found.len());
+        assert_eq!((CCoord(-32), CCoord(3)), found[0]);
+        assert_eq!((CCoord(31), CCoord(3)), found[63]);
+
+        // The missing chunk in each region is skipped.
+        let found = row(5);
+        assert_eq!(62, found.len());
+        assert!(!found.contains(&(CCoord(-27), CCoord(5))));
+        assert!(!found.contains(&(CCoord(5), CCoord(5))));
+
+        assert_eq!(0, dim.chunk_cache_stats().misses);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_for_each_chunk() {
+        use std::sync::Mutex;
+
+        let dim = Dimension::new(CoordLoader);
+        let seen = Mutex::new(Vec::new());
+
+        dim.par_for_each_chunk(|x, z, chunk| {
+            assert_eq!(format!("{},{}", x.0, z.0), chunk.0.name());
+            seen.lock().unwrap().push((x, z));
+        })
+        .unwrap();
+
+        // Every chunk of both listed regions but the missing ones.
+        let seen = seen.into_inner().unwrap();
+        assert_eq!(2 * 1023, seen.len());
+        assert!(seen.contains(&(CCoord(-32), CCoord(0))));
+        assert!(!seen.contains(&(CCoord(5), CCoord(5))));
+        assert_eq!(0, dim.cached_regions());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_for_each_chunk_errors() {
+        // Lists a region it can't open, and has one that says chunk 0, 0 is
+        // present but fails to load it.
+        struct BrokenRegion;
+
+        impl Region<CoordChunk> for BrokenRegion {
+            fn chunk(&self, _x: CCoord, _z: CCoord) -> Option<CoordChunk> {
+                None
+            }
+
+            fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+                (x.0, z.0) == (0, 0)
+            }
+        }
+
+        struct BrokenLoader(Vec<(RCoord, RCoord)>);
+
+        impl RegionLoader<CoordChunk> for BrokenLoader {
+            type RegionType = BrokenRegion;
+
+            fn region(&self, x: RCoord, _z: RCoord) -> Option<Self::RegionType> {
+                if x.0 == 0 {
+                    Some(BrokenRegion)
+                } else {
+                    None
+                }
+            }
+
+            fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+                Ok(self.0.clone())
+            }
+        }
+
+        let dim = Dimension::new(BrokenLoader(vec![(RCoord(1), RCoord(2))]));
+        let err = dim.par_for_each_chunk(|_, _, _| {}).unwrap_err();
+        assert_eq!("region 1, 2 could not be opened", err.to_string());
+
+        let dim = Dimension::new(BrokenLoader(vec![(RCoord(0), RCoord(-1))]));
+        let err = dim.par_for_each_chunk(|_, _, _| {}).unwrap_err();
+        assert_eq!("chunk 0, -32 could not be loaded", err.to_string());
+    }
+
+    #[test]
+    fn chunk_status_names() {
+        assert_eq!(ChunkStatus::Full, ChunkStatus::from_name("full"));
+        assert_eq!(
+            ChunkStatus::Features,
+            ChunkStatus::from_name("minecraft:features")
+        );
+        assert_eq!(
+            ChunkStatus::Other("postprocessed".to_owned()),
+            ChunkStatus::from_name("postprocessed")
+        );
+        assert_eq!("initialize_light", ChunkStatus::InitializeLight.to_string());
+        assert!(ChunkStatus::Spawn.is_generated());
+        assert!(!ChunkStatus::Light.is_generated());
+    }
+
+    #[test]
+    fn chunk_in_negative_region() {
+        let dim = Dimension::new(CoordLoader);
+
+        let chunk = dim.chunk(CCoord(-1), CCoord(33)).unwrap();
+        assert_eq!("-1,33", chunk.0.name());
+        assert!(dim.chunk(CCoord(-27), CCoord(5)).is_none());
+    }
+
+    #[test]
+    fn chunk_exists_and_timestamps() {
+        let dim = Dimension::new(CoordLoader);
+
+        assert!(dim.chunk_exists(CCoord(-1), CCoord(33)));
+        assert!(!dim.chunk_exists(CCoord(-27), CCoord(5)));
+        assert!(!CoordLoader.chunk_exists(CCoord(5), CCoord(5)));
+        assert_eq!(None, dim.chunk_timestamps(RCoord(0), RCoord(0)));
+
+        // Once loaded, the cached chunk is used.
+        dim.chunk(CCoord(2), CCoord(2));
+        assert!(dim.chunk_exists(CCoord(2), CCoord(2)));
+        assert_eq!(1, dim.chunk_cache_stats().hits);
+    }
+
+    #[test]
+    fn block_at_world_coordinates() {
+        let dim = Dimension::new(CoordLoader);
+
+        assert_eq!("-1,1", dim.block((-1, 64, 17)).unwrap().name());
+        assert_eq!("-1,1", dim.block(BlockPos::new(-1, 64, 17)).unwrap().name());
+        assert!(dim.block((-2, 64, 17)).is_none());
+
+        assert_eq!(
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                evictions: 0
+            },
+            dim.chunk_cache_stats()
+        );
+        assert_eq!(1, dim.region_cache_stats().misses);
+    }
+
+    #[test]
+    fn chunk_cache_capacity() {
+        let dim = Dimension::new(CoordLoader).with_chunk_capacity(1);
+
+        let first = dim.chunk(CCoord(0), CCoord(0)).unwrap();
+        dim.chunk(CCoord(1), CCoord(0)).unwrap();
+        let again = dim.chunk(CCoord(0), CCoord(0)).unwrap();
+
+        assert!(!Rc::ptr_eq(&first, &again));
+        assert_eq!(2, dim.chunk_cache_stats().evictions);
+
+        dim.clear();
+        assert_eq!(0, dim.cached_regions());
+    }
+
+    #[test]
+    fn iter_blocks_across_regions() {
+        let dim = Dimension::new(CoordLoader);
+
+        let blocks: Vec<_> = dim
+            .iter_blocks((-20, 60, 0), (20, 70, 40))
+            .map(|(pos, block)| (pos, block.name().to_owned()))
+            .collect();
+
+        // Chunks in region -1, 0 come before those in region 0, 0.
+        let expected: Vec<_> = [
+            ((-17, 64, 1), "-2,0"),
+            ((-1, 64, 1), "-1,0"),
+            ((-17, 64, 17), "-2,1"),
+            ((-1, 64, 17), "-1,1"),
+            ((-17, 64, 33), "-2,2"),
+            ((-1, 64, 33), "-1,2"),
+            ((15, 64, 1), "0,0"),
+            ((15, 64, 17), "0,1"),
+            ((15, 64, 33), "0,2"),
+        ]
+        .iter()
+        .map(|(pos, name)| (BlockPos::from(*pos), name.to_string()))
+        .collect();
+
+        assert_eq!(expected, blocks);
+    }
+
+    #[test]
+    fn iter_blocks_outside_y_range() {
+        let dim = Dimension::new(CoordLoader);
+
+        assert_eq!(0, dim.iter_blocks((0, 65, 0), (16, 1000, 16)).count());
+        assert_eq!(0, dim.iter_blocks((16, 64, 0), (0, 64, 16)).count());
+    }
+
+    #[test]
+    fn find_blocks() {
+        let dim = Dimension::new(CoordLoader);
+
+        let found: Vec<_> = dim
+            .find_blocks((-20, 60, 0), (20, 70, 40), |b| b.name() == "-1,1")
+            .map(|(pos, _)| pos)
+            .collect();
+
+        assert_eq!(vec![BlockPos::new(-1, 64, 17)], found);
+    }
 }
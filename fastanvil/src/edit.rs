@@ -0,0 +1,672 @@
+//! Build and edit the NBT of chunks, in the layout used since 1.18, for
+//! generating worlds from code. Chunks made here can be written with a
+//! [`DimensionWriter`](crate::DimensionWriter) or a
+//! [`MemoryRegionLoader`](crate::MemoryRegionLoader), and the game loads
+//! them as fully generated.
+//!
+//! New chunks are not lit, and are marked as such so the game lights them
+//! when they are loaded. Use [`light::relight_chunk`](crate::light) to light
+//! them up front instead.
+//!
+//! ```no_run
+//! use fastanvil::edit::FlatChunk;
+//! use fastanvil::{Block, CCoord, DimensionWriter};
+//!
+//! let lobby = FlatChunk::new(vec![
+//!     (Block::from_state_str("minecraft:bedrock"), 1),
+//!     (Block::from_state_str("minecraft:stone"), 62),
+//!     (Block::from_state_str("minecraft:grass_block[snowy=false]"), 1),
+//! ]);
+//!
+//! let mut writer = DimensionWriter::new("world/region".into());
+//! for z in -4..4 {
+//!     for x in -4..4 {
+//!         writer.insert_chunk((CCoord(x), CCoord(z)), lobby.build(CCoord(x), CCoord(z))?);
+//!     }
+//! }
+//! writer.flush()?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use fastnbt::Value;
+
+use crate::biome::Biome;
+use crate::java::V1_18;
+use crate::upgrade::UPGRADED_DATA_VERSION;
+use crate::{
+    bits_per_block, Block, BoundingBox, CCoord, ChunkNbt, Error, PackedBits, PackingFormat, Result,
+};
+
+/// The heightmaps kept in full chunks.
+const HEIGHTMAPS: &[&str] = &[
+    "MOTION_BLOCKING",
+    "MOTION_BLOCKING_NO_LEAVES",
+    "OCEAN_FLOOR",
+    "WORLD_SURFACE",
+];
+
+/// A template for chunks of flat terrain, made of layers of blocks from the
+/// bottom of the world up, with air above. A template with no layers makes
+/// chunks of just air.
+#[derive(Debug, Clone)]
+pub struct FlatChunk {
+    /// The blocks of each layer and how many blocks thick it is, from the
+    /// bottom of the world up. Layers above the top of the world are left
+    /// out.
+    pub layers: Vec<(Block, usize)>,
+
+    /// The name of the biome of the whole chunk, eg `minecraft:plains`.
+    pub biome: String,
+
+    /// The lowest Y of the world, a multiple of 16.
+    pub min_y: isize,
+
+    /// The height of the world in blocks, a multiple of 16.
+    pub height: usize,
+
+    /// The `DataVersion` of the chunks, which should be from 1.18 onwards.
+    pub data_version: i32,
+}
+
+impl Default for FlatChunk {
+    /// Air in plains, for an overworld from 1.18.2, from Y -64 to 320.
+    fn default() -> Self {
+        Self {
+            layers: Vec::new(),
+            biome: "minecraft:plains".to_owned(),
+            min_y: -64,
+            height: 384,
+            data_version: UPGRADED_DATA_VERSION,
+        }
+    }
+}
+
+impl FlatChunk {
+    /// Chunks of the given layers, otherwise as the default.
+    pub fn new(layers: Vec<(Block, usize)>) -> Self {
+        Self {
+            layers,
+            ..Default::default()
+        }
+    }
+
+    /// The block at `y` blocks above the bottom of the world.
+    fn block_at(&self, y: usize) -> Option<&Block> {
+        let mut top = 0;
+        for (block, thickness) in &self.layers {
+            top += thickness;
+            if y < top {
+                return Some(block);
+            }
+        }
+        None
+    }
+
+    /// The NBT of the chunk at the given (absolute) chunk coordinates. Fails
+    /// with [`Error::Edit`] if `min_y` or `height` isn't a multiple of 16, or
+    /// the world is too tall for the section Y to fit in a byte.
+    // `is_multiple_of` needs a much newer compiler than the rest of the crate.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn build(&self, x: CCoord, z: CCoord) -> Result<ChunkNbt> {
+        if self.min_y % 16 != 0 || self.height % 16 != 0 {
+            return Err(error("min_y and height must be multiples of 16"));
+        }
+        let top = self.min_y / 16 + (self.height / 16) as isize;
+        if i8::try_from(self.min_y / 16).is_err() || i8::try_from(top - 1).is_err() {
+            return Err(error("Y is outside the world"));
+        }
+
+        let sections: Vec<Value> = (0..self.height / 16).map(|i| self.section(i)).collect();
+
+        // Heightmaps count up from the bottom of the world, to the block
+        // above the highest that isn't air.
+        let surface = (0..self.height)
+            .rev()
+            .find(|&y| matches!(self.block_at(y), Some(b) if !is_air(b)))
+            .map_or(0, |y| y + 1);
+        let heightmap = PackedBits::pack(
+            &[surface as u16; 256],
+            heightmap_bits(self.height),
+            PackingFormat::Padded,
+        );
+        let heightmaps = HEIGHTMAPS
+            .iter()
+            .map(|name| (name.to_string(), Value::LongArray(heightmap.0.clone())))
+            .collect();
+
+        let mut structures = HashMap::new();
+        structures.insert("References".to_owned(), Value::Compound(HashMap::new()));
+        structures.insert("starts".to_owned(), Value::Compound(HashMap::new()));
+
+        let post_processing = vec![Value::List(Vec::new()); sections.len()];
+
+        let mut nbt = ChunkNbt::new();
+        nbt.insert("DataVersion".to_owned(), Value::Int(self.data_version));
+        nbt.insert("xPos".to_owned(), Value::Int(x.0 as i32));
+        nbt.insert("zPos".to_owned(), Value::Int(z.0 as i32));
+        nbt.insert("yPos".to_owned(), Value::Int((self.min_y / 16) as i32));
+        nbt.insert("Status".to_owned(), Value::String("full".to_owned()));
+        nbt.insert("LastUpdate".to_owned(), Value::Long(0));
+        nbt.insert("InhabitedTime".to_owned(), Value::Long(0));
+        nbt.insert("isLightOn".to_owned(), Value::Byte(0));
+        nbt.insert("sections".to_owned(), Value::List(sections));
+        nbt.insert("Heightmaps".to_owned(), Value::Compound(heightmaps));
+        nbt.insert("block_entities".to_owned(), Value::List(Vec::new()));
+        nbt.insert("block_ticks".to_owned(), Value::List(Vec::new()));
+        nbt.insert("fluid_ticks".to_owned(), Value::List(Vec::new()));
+        nbt.insert("PostProcessing".to_owned(), Value::List(post_processing));
+        nbt.insert("structures".to_owned(), Value::Compound(structures));
+        Ok(nbt)
+    }
+
+    /// The NBT of the `i`th section from the bottom of the world.
+    fn section(&self, i: usize) -> Value {
+        let air = Block::from_state_str("minecraft:air");
+
+        let mut palette: Vec<&Block> = Vec::new();
+        let mut indices = Vec::with_capacity(16 * 16 * 16);
+        for y in 0..16 {
+            let block = self.block_at(i * 16 + y).unwrap_or(&air);
+            let index = match palette
+                .iter()
+                .position(|b| b.encoded_description() == block.encoded_description())
+            {
+                Some(index) => index,
+                None => {
+                    palette.push(block);
+                    palette.len() - 1
+                }
+            };
+            // Every block of a layer is the same.
+            indices.resize(indices.len() + 16 * 16, index as u16);
+        }
+
+        let mut block_states = HashMap::new();
+        if palette.len() > 1 {
+            let packed = PackedBits::pack_blockstates(&indices, palette.len());
+            block_states.insert("data".to_owned(), Value::LongArray(packed.0));
+        }
+        block_states.insert(
+            "palette".to_owned(),
+            Value::List(palette.into_iter().map(block_value).collect()),
+        );
+
+        let mut biomes = HashMap::new();
+        biomes.insert(
+            "palette".to_owned(),
+            Value::List(vec![Value::String(self.biome.clone())]),
+        );
+
+        let mut section = HashMap::new();
+        section.insert(
+            "Y".to_owned(),
+            Value::Byte((self.min_y / 16 + i as isize) as i8),
+        );
+        section.insert("block_states".to_owned(), Value::Compound(block_states));
+        section.insert("biomes".to_owned(), Value::Compound(biomes));
+        Value::Compound(section)
+    }
+}
+
+/// The NBT of a chunk of just air, for an overworld from 1.18.2. See
+/// [`FlatChunk`] to make other chunks.
+pub fn empty_chunk(x: CCoord, z: CCoord) -> ChunkNbt {
+    FlatChunk::default()
+        .build(x, z)
+        .expect("default template is valid")
+}
+
+/// Set the block at the given position in the NBT of a chunk, where `x` and
+/// `z` are from 0 to 15 within the chunk and `y` is the Y of the world. The
+/// block is added to the palette of its section if it isn't there already,
+/// and the section's block states are repacked, growing or shrinking the
+/// bits per block as needed. Blocks no longer used are dropped from the
+/// palette. A section is added if the chunk has none at `y`.
+///
+/// Any block entity at the position is removed, as it belonged to the old
+/// block. The heightmaps of the chunk are removed and the chunk is marked as
+/// unlit, so the game works them out again when it loads the chunk.
+///
+/// Only chunks in the layout used since 1.18 can be edited, others give
+/// [`Error::Edit`], see the [`upgrade`](crate::upgrade) module, as does an
+/// `x` or `z` outside the chunk.
+pub fn set_block(nbt: &mut ChunkNbt, x: usize, y: isize, z: usize, block: &Block) -> Result<()> {
+    if x >= 16 || z >= 16 {
+        return Err(error("position is outside the chunk"));
+    }
+
+    let section = section_mut(nbt, y.div_euclid(16))?;
+    let block_states = match section
+        .entry("block_states".to_owned())
+        .or_insert_with(|| Value::Compound(air_states()))
+    {
+        Value::Compound(block_states) => block_states,
+        _ => return Err(error("section block states are not a compound")),
+    };
+
+    let index = y.rem_euclid(16) as usize * 256 + z * 16 + x;
+    set_paletted(
+        block_states,
+        index,
+        16 * 16 * 16,
+        block_value(block),
+        bits_per_block,
+    )?;
+
+    if let (Some(Value::Int(cx)), Some(Value::Int(cz)), Some(Value::List(entities))) = (
+        nbt.get("xPos").cloned(),
+        nbt.get("zPos").cloned(),
+        nbt.get_mut("block_entities"),
+    ) {
+        let pos = [cx * 16 + x as i32, y as i32, cz * 16 + z as i32];
+        entities.retain(|entity| match entity {
+            Value::Compound(entity) => ["x", "y", "z"]
+                .iter()
+                .zip(pos)
+                .any(|(key, p)| entity.get(*key) != Some(&Value::Int(p))),
+            _ => true,
+        });
+    }
+
+    nbt.remove("Heightmaps");
+    nbt.insert("isLightOn".to_owned(), Value::Byte(0));
+    Ok(())
+}
+
+/// Set the biome at the given position in the NBT of a chunk, where `x` and
+/// `z` are from 0 to 15 within the chunk and `y` is the Y of the world.
+/// Biomes are stored for cells of 4x4x4 blocks, so this sets the biome of
+/// the whole cell the position is in. A section is added if the chunk has
+/// none at `y`.
+///
+/// Only chunks in the layout used since 1.18 can be edited, others give
+/// [`Error::Edit`], as does an `x` or `z` outside the chunk.
+pub fn set_biome(nbt: &mut ChunkNbt, x: usize, y: isize, z: usize, biome: &Biome) -> Result<()> {
+    if x >= 16 || z >= 16 {
+        return Err(error("position is outside the chunk"));
+    }
+
+    let section = section_mut(nbt, y.div_euclid(16))?;
+    let index = y.rem_euclid(16) as usize / 4 * 16 + z / 4 * 4 + x / 4;
+    set_section_biome(section, index, biome)
+}
+
+/// Set the biome of every cell of the chunk with a block in the box, which
+/// is in world coordinates. Only the sections the chunk already has are
+/// changed, so a box reaching past the top or bottom of the world is fine.
+///
+/// Only chunks in the layout used since 1.18 can be edited, others give
+/// [`Error::Edit`].
+///
+/// ```
+/// # use fastanvil::{biome::Biome, edit, BoundingBox, CCoord};
+/// let mut nbt = edit::empty_chunk(CCoord(0), CCoord(0));
+///
+/// let area = BoundingBox { min: [-100, -100, 4], max: [100, 100, 7] };
+/// edit::fill_biome(&mut nbt, &area, &Biome::CherryGrove)?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn fill_biome(nbt: &mut ChunkNbt, bb: &BoundingBox, biome: &Biome) -> Result<()> {
+    check_version(nbt)?;
+    let (cx, cz) = match (nbt.get("xPos"), nbt.get("zPos")) {
+        (Some(Value::Int(x)), Some(Value::Int(z))) => (*x, *z),
+        _ => return Err(error("chunk has no position")),
+    };
+
+    let section_ys: Vec<i8> = match nbt.get("sections") {
+        Some(Value::List(sections)) => sections
+            .iter()
+            .filter_map(|s| match s {
+                Value::Compound(s) => match s.get("Y") {
+                    Some(Value::Byte(y)) => Some(*y),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // Whether the cell starting at the given block overlaps the box.
+    let overlaps =
+        |start: [i32; 3]| (0..3).all(|i| start[i] <= bb.max[i] && start[i] + 3 >= bb.min[i]);
+
+    for sy in section_ys {
+        let section = section_mut(nbt, sy as isize)?;
+        for i in 0..64 {
+            let (x, y, z) = (i % 4 * 4, i / 16 * 4, i / 4 % 4 * 4);
+            let start = [cx * 16 + x, sy as i32 * 16 + y, cz * 16 + z];
+            if overlaps(start) {
+                set_section_biome(section, i as usize, biome)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the biome of the cell at `index` of a section.
+fn set_section_biome(section: &mut ChunkNbt, index: usize, biome: &Biome) -> Result<()> {
+    let biomes = match section
+        .entry("biomes".to_owned())
+        .or_insert_with(|| Value::Compound(plains_biomes()))
+    {
+        Value::Compound(biomes) => biomes,
+        _ => return Err(error("section biomes are not a compound")),
+    };
+
+    // Unlike block states, biomes use as few bits as possible.
+    let bits = |len: usize| (usize::BITS - (len - 1).leading_zeros()) as usize;
+    set_paletted(biomes, index, 4 * 4 * 4, Value::String(biome.name()), bits)
+}
+
+fn error(msg: &str) -> Error {
+    Error::Edit(msg.to_owned())
+}
+
+fn check_version(nbt: &ChunkNbt) -> Result<()> {
+    match nbt.get("DataVersion") {
+        Some(Value::Int(v)) if *v >= V1_18 => Ok(()),
+        _ => Err(error("only chunks from 1.18 onwards can be edited")),
+    }
+}
+
+/// The section of a chunk at the given section Y, added if it is missing.
+fn section_mut(nbt: &mut ChunkNbt, y: isize) -> Result<&mut ChunkNbt> {
+    check_version(nbt)?;
+
+    let y = Value::Byte(i8::try_from(y).map_err(|_| error("Y is outside the world"))?);
+    let sections = match nbt
+        .entry("sections".to_owned())
+        .or_insert_with(|| Value::List(Vec::new()))
+    {
+        Value::List(sections) => sections,
+        _ => return Err(error("chunk sections are not a list")),
+    };
+
+    let i = match sections
+        .iter()
+        .position(|s| matches!(s, Value::Compound(s) if s.get("Y") == Some(&y)))
+    {
+        Some(i) => i,
+        None => {
+            let mut section = HashMap::new();
+            section.insert("Y".to_owned(), y);
+            section.insert("block_states".to_owned(), Value::Compound(air_states()));
+            section.insert("biomes".to_owned(), Value::Compound(plains_biomes()));
+            sections.push(Value::Compound(section));
+            sections.len() - 1
+        }
+    };
+
+    match &mut sections[i] {
+        Value::Compound(section) => Ok(section),
+        _ => unreachable!("found by matching a compound"),
+    }
+}
+
+/// The block states of a section of just air.
+fn air_states() -> HashMap<String, Value> {
+    let mut states = HashMap::new();
+    states.insert(
+        "palette".to_owned(),
+        Value::List(vec![block_value(&Block::from_state_str("minecraft:air"))]),
+    );
+    states
+}
+
+/// The biomes of a section of just plains, which the game assumes for
+/// sections without biomes.
+fn plains_biomes() -> HashMap<String, Value> {
+    let mut biomes = HashMap::new();
+    biomes.insert(
+        "palette".to_owned(),
+        Value::List(vec![Value::String("minecraft:plains".to_owned())]),
+    );
+    biomes
+}
+
+/// Set the value at `index` of a paletted container, the compound of a
+/// `palette` and packed `data` that sections keep their blocks and biomes
+/// in. `count` is the number of values in the container and `bits` gives the
+/// bits per value for a palette length.
+fn set_paletted(
+    container: &mut HashMap<String, Value>,
+    index: usize,
+    count: usize,
+    value: Value,
+    bits: fn(usize) -> usize,
+) -> Result<()> {
+    let mut palette = match container.remove("palette") {
+        Some(Value::List(palette)) if !palette.is_empty() => palette,
+        _ => return Err(error("section has no palette")),
+    };
+
+    // A palette of one value has no data, everything is that value.
+    let mut indices = match container.remove("data") {
+        Some(Value::LongArray(data)) if palette.len() > 1 => PackedBits(data)
+            .unpack(bits(palette.len()), PackingFormat::Padded, count)
+            .ok_or_else(|| error("palette is too large"))?,
+        _ => vec![0; count],
+    };
+
+    indices[index] = match palette.iter().position(|v| *v == value) {
+        Some(i) => i as u16,
+        None => {
+            palette.push(value);
+            (palette.len() - 1) as u16
+        }
+    };
+
+    // Drop values that are no longer used, so repeated edits don't keep
+    // growing the palette.
+    let mut used = vec![false; palette.len()];
+    for &i in &indices {
+        if let Some(used) = used.get_mut(i as usize) {
+            *used = true;
+        }
+    }
+
+    let mut remap = vec![0; palette.len()];
+    let mut kept = Vec::new();
+    for (i, value) in palette.into_iter().enumerate() {
+        if used[i] {
+            remap[i] = kept.len() as u16;
+            kept.push(value);
+        }
+    }
+    for i in indices.iter_mut() {
+        *i = remap.get(*i as usize).copied().unwrap_or(0);
+    }
+
+    if kept.len() > 1 {
+        let packed = PackedBits::pack(&indices, bits(kept.len()), PackingFormat::Padded);
+        container.insert("data".to_owned(), Value::LongArray(packed.0));
+    }
+    container.insert("palette".to_owned(), Value::List(kept));
+    Ok(())
+}
+
+/// The NBT of a block in a palette.
+fn block_value(block: &Block) -> Value {
+    let mut compound = HashMap::new();
+    compound.insert("Name".to_owned(), Value::String(block.name().to_owned()));
+
+    if !block.properties().is_empty() {
+        let properties = block
+            .properties()
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        compound.insert("Properties".to_owned(), Value::Compound(properties));
+    }
+
+    Value::Compound(compound)
+}
+
+fn is_air(block: &Block) -> bool {
+    matches!(
+        block.name(),
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
+/// The bits each column of a heightmap takes, enough to count from 0 to
+/// `height`.
+fn heightmap_bits(height: usize) -> usize {
+    (usize::BITS - height.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunk, HeightMode, JavaChunk};
+
+    fn chunk(nbt: &ChunkNbt) -> JavaChunk {
+        fastnbt::de::from_bytes(&fastnbt::ser::to_bytes(nbt).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn flat_chunk() {
+        let template = FlatChunk::new(vec![
+            (Block::from_state_str("minecraft:bedrock"), 1),
+            (Block::from_state_str("minecraft:stone"), 20),
+            (
+                Block::from_state_str("minecraft:grass_block[snowy=false]"),
+                1,
+            ),
+        ]);
+        let nbt = template.build(CCoord(-3), CCoord(7)).unwrap();
+        assert_eq!(Some(&Value::Int(-3)), nbt.get("xPos"));
+        assert_eq!(Some(&Value::Int(-4)), nbt.get("yPos"));
+
+        let chunk = chunk(&nbt);
+        assert_eq!("minecraft:bedrock", chunk.block(5, -64, 5).unwrap().name());
+        assert_eq!("minecraft:stone", chunk.block(0, -44, 15).unwrap().name());
+        let grass = chunk.block(15, -43, 0).unwrap();
+        assert_eq!(Some("false"), grass.property("snowy"));
+        assert_eq!("minecraft:air", chunk.block(15, -42, 0).unwrap().name());
+
+        assert_eq!(-42, chunk.surface_height(3, 3, HeightMode::Trust));
+        assert_eq!(-42, chunk.surface_height(3, 3, HeightMode::Calculate));
+        assert_eq!(-64..320, chunk.y_range());
+
+        let bad = FlatChunk {
+            min_y: -60,
+            ..template
+        };
+        assert!(matches!(
+            bad.build(CCoord(0), CCoord(0)),
+            Err(Error::Edit(_))
+        ));
+    }
+
+    #[test]
+    fn set_blocks() {
+        let stone = Block::from_state_str("minecraft:stone");
+        let mut nbt = FlatChunk::new(vec![(stone.clone(), 80)])
+            .build(CCoord(1), CCoord(-1))
+            .unwrap();
+
+        let mut entity = ChunkNbt::new();
+        for (key, v) in [("x", 18), ("y", 0), ("z", -13)] {
+            entity.insert(key.to_owned(), Value::Int(v));
+        }
+        nbt.insert(
+            "block_entities".to_owned(),
+            Value::List(vec![Value::Compound(entity)]),
+        );
+
+        // More blocks than fit in 4 bits, in the section from Y 0.
+        let blocks: Vec<Block> = (0..20)
+            .map(|i| Block::from_state_str(&format!("minecraft:block_{}", i)))
+            .collect();
+        for (i, block) in blocks.iter().enumerate() {
+            set_block(&mut nbt, i % 16, i as isize / 16 - 32, 3, block).unwrap();
+            set_block(&mut nbt, i % 16, 0, 3, block).unwrap();
+        }
+        set_block(&mut nbt, 2, 100, 3, &stone).unwrap();
+
+        assert!(!nbt.contains_key("Heightmaps"));
+        assert_eq!(Some(&Value::List(Vec::new())), nbt.get("block_entities"));
+
+        let chunk = chunk(&nbt);
+        assert_eq!("minecraft:block_17", chunk.block(1, -31, 3).unwrap().name());
+        assert_eq!("minecraft:block_4", chunk.block(4, 0, 3).unwrap().name());
+        assert_eq!("minecraft:stone", chunk.block(4, 0, 4).unwrap().name());
+        assert_eq!("minecraft:stone", chunk.block(2, 100, 3).unwrap().name());
+        assert_eq!(101, chunk.surface_height(2, 3, HeightMode::Calculate));
+
+        // Blocks covered up again are dropped from the palette.
+        for x in 0..16 {
+            set_block(&mut nbt, x, 0, 3, &stone).unwrap();
+        }
+        let section = section_mut(&mut nbt, 0).unwrap();
+        match &section["block_states"] {
+            Value::Compound(states) => {
+                assert_eq!(
+                    Some(&Value::List(vec![block_value(&stone)])),
+                    states.get("palette")
+                );
+                assert!(!states.contains_key("data"));
+            }
+            _ => panic!("no block states"),
+        }
+
+        let mut old = ChunkNbt::new();
+        old.insert("DataVersion".to_owned(), Value::Int(2730));
+        assert!(matches!(
+            set_block(&mut old, 0, 0, 0, &stone),
+            Err(Error::Edit(_))
+        ));
+        assert!(matches!(
+            set_block(&mut nbt, 16, 0, 0, &stone),
+            Err(Error::Edit(_))
+        ));
+    }
+
+    #[test]
+    fn set_biomes() {
+        let mut nbt = empty_chunk(CCoord(-1), CCoord(2));
+        set_biome(&mut nbt, 5, -60, 9, &Biome::Meadow).unwrap();
+
+        let area = BoundingBox {
+            min: [-9, 62, 0],
+            max: [-7, 200, 40],
+        };
+        fill_biome(&mut nbt, &area, &Biome::SnowyTundra).unwrap();
+        set_biome(&mut nbt, 8, 100, 0, &Biome::Unknown("test:moon".to_owned())).unwrap();
+
+        let chunk = chunk(&nbt);
+        let biome = |x, y, z| chunk.biome(x, y, z).unwrap();
+        assert_eq!(Biome::Meadow, biome(4, -57, 11));
+        assert_eq!(Biome::Plains, biome(5, -61, 9));
+
+        // As whole cells, the box covers X 4 to 11, Y 60 to 203 and Z 0 to
+        // 11 of the chunk.
+        assert_eq!(Biome::SnowyTundra, biome(4, 60, 11));
+        assert_eq!(Biome::SnowyTundra, biome(11, 203, 0));
+        assert_eq!(Biome::Plains, biome(12, 100, 0));
+        assert_eq!(Biome::Plains, biome(4, 59, 0));
+        assert_eq!(Biome::Plains, biome(4, 204, 0));
+        assert_eq!(Biome::Plains, biome(4, 100, 12));
+        assert_eq!(Biome::Unknown("test:moon".to_owned()), biome(8, 100, 0));
+        assert_eq!(Biome::SnowyTundra, biome(4, 100, 0));
+
+        assert!(matches!(
+            set_biome(&mut nbt, 0, 0, 16, &Biome::Meadow),
+            Err(Error::Edit(_))
+        ));
+    }
+
+    #[test]
+    fn empty_chunk_is_air() {
+        let chunk = chunk(&empty_chunk(CCoord(0), CCoord(0)));
+
+        assert_eq!("minecraft:air", chunk.block(0, 100, 0).unwrap().name());
+        assert_eq!(-64, chunk.surface_height(0, 0, HeightMode::Trust));
+    }
+}
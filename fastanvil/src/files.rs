@@ -1,21 +1,87 @@
 use serde::de::DeserializeOwned;
 
-use crate::{Chunk, LoaderError};
+use crate::{compact_region, prune_region_at, ChunkInfo, CompactReport, Progress, Result};
+use crate::{CCoord, Chunk, DimensionKind, Entity, EntityChunk, LoaderError, PoiChunk};
 use crate::{LoaderResult, RegionBuffer};
 use crate::{RCoord, RegionLoader};
 use std::fs::File;
+use std::io::BufWriter;
 use std::marker::PhantomData;
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 
+/// Identifies a dimension of a Java Edition world, to find where its files
+/// are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DimensionId {
+    Overworld,
+    Nether,
+    End,
+
+    /// A dimension added by a datapack or mod, by its namespaced ID such as
+    /// `mypack:mining`. These are kept in `dimensions/<namespace>/<name>`.
+    Custom(String),
+}
+
+impl DimensionId {
+    /// The dimension with the given ID, such as `minecraft:the_nether`. IDs
+    /// without a namespace are taken to be from `minecraft`.
+    pub fn from_id(id: &str) -> Self {
+        match id.strip_prefix("minecraft:").unwrap_or(id) {
+            "overworld" => DimensionId::Overworld,
+            "the_nether" => DimensionId::Nether,
+            "the_end" => DimensionId::End,
+            _ if id.contains(':') => DimensionId::Custom(id.to_owned()),
+            _ => DimensionId::Custom(format!("minecraft:{}", id)),
+        }
+    }
+
+    /// The namespaced ID of the dimension.
+    pub fn id(&self) -> String {
+        match self {
+            DimensionId::Overworld => "minecraft:overworld".to_owned(),
+            DimensionId::Nether => "minecraft:the_nether".to_owned(),
+            DimensionId::End => "minecraft:the_end".to_owned(),
+            DimensionId::Custom(id) => id.clone(),
+        }
+    }
+
+    /// How the dimension should be rendered. Custom dimensions are rendered
+    /// like the overworld.
+    pub fn kind(&self) -> DimensionKind {
+        match self {
+            DimensionId::Nether => DimensionKind::Nether,
+            DimensionId::End => DimensionKind::End,
+            DimensionId::Overworld | DimensionId::Custom(_) => DimensionKind::Overworld,
+        }
+    }
+
+    /// The folder of the dimension within the world folder `world`, which
+    /// holds its `region`, `entities` and `poi` folders.
+    pub fn dir(&self, world: impl AsRef<Path>) -> PathBuf {
+        let world = world.as_ref();
+        match self {
+            DimensionId::Overworld => world.to_owned(),
+            DimensionId::Nether => world.join("DIM-1"),
+            DimensionId::End => world.join("DIM1"),
+            DimensionId::Custom(id) => {
+                let (namespace, name) = id.split_once(':').unwrap_or(("minecraft", id));
+                world.join("dimensions").join(namespace).join(name)
+            }
+        }
+    }
+}
+
 pub struct RegionFileLoader<C>
 where
     C: Chunk,
 {
     region_dir: PathBuf,
-    p: PhantomData<C>,
+    // Loaders only make chunks, so can be shared between threads whatever
+    // the chunk type.
+    p: PhantomData<fn() -> C>,
 }
 
 impl<C> RegionFileLoader<C>
@@ -28,43 +94,298 @@ where
             p: PhantomData,
         }
     }
+
+    /// Load the regions of a dimension of the world in the folder `world`.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{DimensionId, JavaChunk, RegionFileLoader};
+    /// let loader = RegionFileLoader::<JavaChunk>::for_dimension("world", &DimensionId::Nether);
+    /// ```
+    pub fn for_dimension(world: impl AsRef<Path>, dimension: &DimensionId) -> Self {
+        Self::new(dimension.dir(world).join("region"))
+    }
 }
 
 impl<C: Chunk + DeserializeOwned> RegionLoader<C> for RegionFileLoader<C> {
     type RegionType = RegionBuffer<File>;
 
     fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
-        let path = self.region_dir.join(format!("r.{}.{}.mca", x.0, z.0));
-        let file = std::fs::File::open(path).ok()?;
-        let region = RegionBuffer::new(file);
-
-        Some(region)
+        open_region(&self.region_dir, x, z)
     }
 
     fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
-        let paths = std::fs::read_dir(&self.region_dir).map_err(|e| LoaderError(e.to_string()))?;
+        list_regions(&self.region_dir)
+    }
+}
+
+/// Loads the entities of a world from the region files in its `entities`
+/// folder. Since 1.17 entities are stored here rather than alongside the
+/// blocks of a chunk.
+pub struct EntityRegionFileLoader {
+    entities_dir: PathBuf,
+}
+
+impl EntityRegionFileLoader {
+    pub fn new(entities_dir: PathBuf) -> Self {
+        Self { entities_dir }
+    }
+
+    /// Load the entities of a dimension of the world in the folder `world`.
+    pub fn for_dimension(world: impl AsRef<Path>, dimension: &DimensionId) -> Self {
+        Self::new(dimension.dir(world).join("entities"))
+    }
+
+    /// Get a particular region. Returns None if region does not exist.
+    pub fn region(&self, x: RCoord, z: RCoord) -> Option<RegionBuffer<File>> {
+        open_region(&self.entities_dir, x, z)
+    }
+
+    /// List the regions that this loader can return.
+    pub fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        list_regions(&self.entities_dir)
+    }
+
+    /// Load the entity chunk at the given (absolute) chunk coordinates. Returns
+    /// None if the chunk does not exist or could not be deserialized.
+    pub fn chunk(&self, x: CCoord, z: CCoord) -> Option<EntityChunk> {
+        load_chunk(&self.entities_dir, x, z)
+    }
+
+    /// Get the entities in the chunk at the given (absolute) chunk
+    /// coordinates. Chunks that do not exist have no entities.
+    pub fn entities(&self, x: CCoord, z: CCoord) -> Vec<Entity> {
+        self.chunk(x, z)
+            .map(|chunk| chunk.entities)
+            .unwrap_or_default()
+    }
+}
+
+/// Loads the points of interest of a world from the region files in its `poi`
+/// folder.
+pub struct PoiRegionFileLoader {
+    poi_dir: PathBuf,
+}
+
+impl PoiRegionFileLoader {
+    pub fn new(poi_dir: PathBuf) -> Self {
+        Self { poi_dir }
+    }
+
+    /// Load the points of interest of a dimension of the world in the folder
+    /// `world`.
+    pub fn for_dimension(world: impl AsRef<Path>, dimension: &DimensionId) -> Self {
+        Self::new(dimension.dir(world).join("poi"))
+    }
+
+    /// Get a particular region. Returns None if region does not exist.
+    pub fn region(&self, x: RCoord, z: RCoord) -> Option<RegionBuffer<File>> {
+        open_region(&self.poi_dir, x, z)
+    }
 
-        let paths = paths
-            .into_iter()
-            .filter_map(|path| path.ok())
-            .map(|path| path.path())
-            .filter(|path| path.is_file())
-            .filter(|path| {
-                let ext = path.extension();
-                ext.is_some() && ext.unwrap() == "mca"
-            })
-            .filter(|path| fs::metadata(path).unwrap().len() > 0)
-            .filter_map(|p| coords_from_region(&p))
-            .collect();
+    /// List the regions that this loader can return.
+    pub fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        list_regions(&self.poi_dir)
+    }
 
-        Ok(paths)
+    /// Load the point of interest chunk at the given (absolute) chunk
+    /// coordinates. Returns None if the chunk does not exist or could not be
+    /// deserialized.
+    pub fn chunk(&self, x: CCoord, z: CCoord) -> Option<PoiChunk> {
+        load_chunk(&self.poi_dir, x, z)
     }
 }
 
-fn coords_from_region(region: &Path) -> Option<(RCoord, RCoord)> {
+/// Load and deserialize the chunk at the given (absolute) chunk coordinates
+/// from the region files in `dir`.
+fn load_chunk<T: DeserializeOwned>(dir: &Path, x: CCoord, z: CCoord) -> Option<T> {
+    let region = open_region(dir, RCoord(x.0.div_euclid(32)), RCoord(z.0.div_euclid(32)))?;
+    let data = region
+        .load_chunk(x.0.rem_euclid(32) as usize, z.0.rem_euclid(32) as usize)
+        .ok()?;
+
+    fastnbt::de::from_bytes(&data).ok()
+}
+
+/// Compact every region file in a directory such as `world/region` in
+/// place, using [`compact_region`]. Each region is written to a temporary
+/// file next to it, which then replaces the original. Gives the totals of
+/// every region compacted.
+///
+/// Each region is reported to `progress`. Regions that can't be compacted
+/// are passed to [`Progress::error`], and are left as they were.
+///
+/// The world must not be open in the game while this runs.
+///
+/// ```no_run
+/// # use fastanvil::compact_dimension;
+/// let report = compact_dimension("world/region", &mut ())?;
+/// println!("reclaimed {} bytes", report.bytes_reclaimed());
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn compact_dimension(
+    region_dir: impl AsRef<Path>,
+    progress: &mut impl Progress,
+) -> Result<CompactReport> {
+    rewrite_dimension(region_dir.as_ref(), progress, |src, dst, _, _| {
+        compact_region(src, dst)
+    })
+}
+
+/// Remove every chunk for which `remove` returns true from the region files
+/// in a directory such as `world/region`, using [`prune_region`](crate::prune_region). The regions
+/// are compacted as they are rewritten, like [`compact_dimension`]. The
+/// chunk coordinates given to `remove` are absolute.
+///
+/// Each region is reported to `progress`, with the number of chunks kept.
+/// Regions that can't be pruned are passed to [`Progress::error`], and are
+/// left as they were. Chunks stored in `.mcc` files have the file left
+/// behind when removed.
+///
+/// The world must not be open in the game while this runs.
+///
+/// ```no_run
+/// # use fastanvil::prune_dimension;
+/// // Remove chunks no one has visited, or that are over 2000 blocks out.
+/// let report = prune_dimension(
+///     "world/region",
+///     |chunk| chunk.inhabited_time() == Some(0) || chunk.x.abs().max(chunk.z.abs()) > 125,
+///     &mut (),
+/// )?;
+/// println!("removed {} chunks", report.removed);
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn prune_dimension(
+    region_dir: impl AsRef<Path>,
+    mut remove: impl FnMut(&ChunkInfo) -> bool,
+    progress: &mut impl Progress,
+) -> Result<CompactReport> {
+    rewrite_dimension(region_dir.as_ref(), progress, |src, dst, x, z| {
+        prune_region_at(src, dst, (x.0 * 32, z.0 * 32), &mut remove)
+    })
+}
+
+/// Rewrite every region in `dir` in place with `rewrite`, giving the totals
+/// of every region rewritten.
+fn rewrite_dimension<F>(
+    dir: &Path,
+    progress: &mut impl Progress,
+    mut rewrite: F,
+) -> Result<CompactReport>
+where
+    F: FnMut(&RegionBuffer<File>, BufWriter<File>, RCoord, RCoord) -> Result<CompactReport>,
+{
+    let regions = list_regions(dir)?;
+    progress.start(regions.len());
+
+    let mut total = CompactReport {
+        chunks: 0,
+        removed: 0,
+        bytes_before: 0,
+        bytes_after: 0,
+    };
+
+    for (x, z) in regions {
+        match rewrite_in_place(dir, x, z, &mut rewrite) {
+            Ok(report) => {
+                total.chunks += report.chunks;
+                total.removed += report.removed;
+                total.bytes_before += report.bytes_before;
+                total.bytes_after += report.bytes_after;
+                progress.region_done(x, z, report.chunks);
+            }
+            Err(e) => progress.error(x, z, e)?,
+        }
+    }
+
+    progress.finish();
+    Ok(total)
+}
+
+/// Rewrite a region to a temporary file next to it, which then replaces the
+/// original.
+fn rewrite_in_place<F>(dir: &Path, x: RCoord, z: RCoord, rewrite: &mut F) -> Result<CompactReport>
+where
+    F: FnMut(&RegionBuffer<File>, BufWriter<File>, RCoord, RCoord) -> Result<CompactReport>,
+{
+    let path = dir.join(format!("r.{}.{}.mca", x.0, z.0));
+    let tmp = path.with_extension("mca.compact");
+
+    let src = RegionBuffer::new(File::open(&path)?).with_external_chunks(dir, x, z);
+    let report = match rewrite(&src, BufWriter::new(File::create(&tmp)?), x, z) {
+        Ok(report) => report,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+    };
+
+    // The original has to be closed before it can be replaced on Windows.
+    drop(src);
+    fs::rename(&tmp, &path)?;
+
+    Ok(report)
+}
+
+pub(crate) fn open_region(dir: &Path, x: RCoord, z: RCoord) -> Option<RegionBuffer<File>> {
+    try_open_region(dir, x, z).ok()
+}
+
+pub(crate) fn try_open_region(
+    dir: &Path,
+    x: RCoord,
+    z: RCoord,
+) -> std::io::Result<RegionBuffer<File>> {
+    let path = dir.join(format!("r.{}.{}.mca", x.0, z.0));
+    let file = std::fs::File::open(path)?;
+    Ok(RegionBuffer::new(file).with_external_chunks(dir, x, z))
+}
+
+pub(crate) fn list_regions(dir: &Path) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+    let paths = std::fs::read_dir(dir).map_err(|e| LoaderError(e.to_string()))?;
+
+    let paths = paths
+        .into_iter()
+        .filter_map(|path| path.ok())
+        .map(|path| path.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let ext = path.extension();
+            ext.is_some() && ext.unwrap() == "mca"
+        })
+        .filter(|path| fs::metadata(path).unwrap().len() > 0)
+        .filter_map(|p| coords_from_region(&p))
+        .collect();
+
+    Ok(paths)
+}
+
+pub(crate) fn coords_from_region(region: &Path) -> Option<(RCoord, RCoord)> {
     let filename = region.file_name()?.to_str()?;
     let mut parts = filename.split('.').skip(1);
     let x = parts.next()?.parse::<isize>().ok()?;
     let z = parts.next()?.parse::<isize>().ok()?;
     Some((RCoord(x), RCoord(z)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimension_dirs() {
+        let world = Path::new("world");
+        assert_eq!(world, DimensionId::Overworld.dir(world));
+        assert_eq!(world.join("DIM-1"), DimensionId::Nether.dir(world));
+        assert_eq!(
+            world.join("dimensions/mypack/mining"),
+            DimensionId::from_id("mypack:mining").dir(world)
+        );
+
+        assert_eq!(DimensionId::End, DimensionId::from_id("the_end"));
+        assert_eq!(
+            "minecraft:the_end",
+            DimensionId::from_id("minecraft:the_end").id()
+        );
+        assert_eq!(DimensionKind::Nether, DimensionId::Nether.kind());
+    }
+}
@@ -0,0 +1,329 @@
+use std::io::Read;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use byteorder::{BigEndian, ByteOrder};
+use fastnbt::de::from_bytes;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    decompress_chunk, CCoord, Chunk, ChunkMeta, Error, LoaderError, LoaderResult, RCoord, Region,
+    RegionLoader, Result, HEADER_SIZE, SECTOR_SIZE,
+};
+
+/// Loads regions over HTTP or HTTPS, such as from a web server or an object
+/// store like S3 serving a world's `region` folder. Only the header of a
+/// region is downloaded when it is opened, and then just the sectors of each
+/// chunk as it is loaded, using range requests. Servers that ignore ranges
+/// still work, but send the whole file every time.
+///
+/// Web servers can't list the files in a folder, so the regions to
+/// [`list`](RegionLoader::list) have to be given with
+/// [`with_regions`](Self::with_regions).
+///
+/// ```no_run
+/// # use fastanvil::{CCoord, Dimension, HttpRegionLoader, JavaChunk, RCoord};
+/// let loader = HttpRegionLoader::<JavaChunk>::new("https://example.com/world/region")
+///     .with_regions(vec![(RCoord(0), RCoord(0)), (RCoord(-1), RCoord(0))]);
+/// let dimension = Dimension::new(loader);
+///
+/// let chunk = dimension.chunk(CCoord(3), CCoord(5));
+/// ```
+pub struct HttpRegionLoader<C: Chunk> {
+    base_url: String,
+    agent: ureq::Agent,
+    regions: Option<Vec<(RCoord, RCoord)>>,
+    p: PhantomData<fn() -> C>,
+}
+
+impl<C: Chunk> HttpRegionLoader<C> {
+    /// Load regions from the folder at `base_url`, where the region (x, z)
+    /// is at `<base_url>/r.<x>.<z>.mca`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        while base_url.ends_with('/') {
+            base_url.pop();
+        }
+
+        Self {
+            base_url,
+            agent: ureq::Agent::new(),
+            regions: None,
+            p: PhantomData,
+        }
+    }
+
+    /// Make requests with the given agent, to set timeouts, a proxy, or
+    /// middleware that adds headers for authentication.
+    pub fn with_agent(self, agent: ureq::Agent) -> Self {
+        Self { agent, ..self }
+    }
+
+    /// The regions the loader lists, for example from a listing of the
+    /// bucket the world is kept in.
+    pub fn with_regions(self, regions: Vec<(RCoord, RCoord)>) -> Self {
+        Self {
+            regions: Some(regions),
+            ..self
+        }
+    }
+}
+
+impl<C: Chunk + DeserializeOwned> RegionLoader<C> for HttpRegionLoader<C> {
+    type RegionType = HttpRegion;
+
+    /// Download the header of the region. Regions that fail to download,
+    /// including those that don't exist, are `None`.
+    fn region(&self, x: RCoord, z: RCoord) -> Option<HttpRegion> {
+        HttpRegion::open(self.agent.clone(), &self.base_url, x, z).ok()
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        self.regions.clone().ok_or_else(|| {
+            LoaderError("regions of an HTTP loader must be given with with_regions".to_owned())
+        })
+    }
+}
+
+/// A region on a web server, see [`HttpRegionLoader`]. Its header is kept,
+/// and chunks are downloaded as they are loaded.
+pub struct HttpRegion {
+    agent: ureq::Agent,
+    base_url: String,
+    x: RCoord,
+    z: RCoord,
+    header: Vec<u8>,
+}
+
+impl HttpRegion {
+    /// Download the header of the region (x, z) in the folder at `base_url`.
+    pub fn open(agent: ureq::Agent, base_url: &str, x: RCoord, z: RCoord) -> Result<Self> {
+        let url = format!("{}/r.{}.{}.mca", base_url, x.0, z.0);
+        let header = fetch(&agent, &url, Some(0..HEADER_SIZE as u64))?;
+        if header.len() < HEADER_SIZE {
+            return Err(Error::InsufficientData);
+        }
+
+        Ok(Self {
+            agent,
+            base_url: base_url.to_owned(),
+            x,
+            z,
+            header,
+        })
+    }
+
+    /// The sectors the chunk at the (region-relative) Chunk location (x, z)
+    /// is stored in, or `None` if it is not present.
+    fn sectors(&self, x: usize, z: usize) -> Result<Option<Range<u64>>> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let entry = BigEndian::read_u32(&self.header[4 * (x + z * 32)..]);
+        let (begin, count) = ((entry >> 8) as u64, (entry & 0xff) as u64);
+
+        // 0,0 chunk location means the chunk isn't present.
+        if begin == 0 || count == 0 {
+            return Ok(None);
+        }
+
+        let sector = SECTOR_SIZE as u64;
+        Ok(Some(begin * sector..(begin + count) * sector))
+    }
+
+    /// Return the last modification time of the chunk at the (region-relative)
+    /// Chunk location (x, z), in seconds since the Unix epoch.
+    pub fn chunk_timestamp(&self, x: usize, z: usize) -> Result<u32> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        Ok(BigEndian::read_u32(
+            &self.header[SECTOR_SIZE + 4 * (x + z * 32)..],
+        ))
+    }
+
+    /// Download the chunk at the (region-relative) Chunk location (x, z),
+    /// returning its uncompressed NBT data. Chunks stored in `.mcc` files are
+    /// downloaded from next to the region.
+    pub fn load_chunk(&self, x: usize, z: usize) -> Result<Vec<u8>> {
+        let sectors = self.sectors(x, z)?.ok_or(Error::ChunkNotFound)?;
+        let url = format!("{}/r.{}.{}.mca", self.base_url, self.x.0, self.z.0);
+        let mut data = fetch(&self.agent, &url, Some(sectors))?;
+
+        let meta = ChunkMeta::new(&data)?;
+        if meta.external {
+            let url = format!(
+                "{}/c.{}.{}.mcc",
+                self.base_url,
+                self.x.0 * 32 + x as isize,
+                self.z.0 * 32 + z as isize
+            );
+            let external = fetch(&self.agent, &url, None)?;
+            let mut decoder = meta.decoder(&external[..])?;
+
+            let mut chunk = Vec::new();
+            decoder.read_to_end(&mut chunk)?;
+            return Ok(chunk);
+        }
+
+        let len = 5 + meta.compressed_len as usize;
+        if data.len() < len {
+            return Err(Error::InsufficientData);
+        }
+        data.truncate(len);
+        decompress_chunk(&data)
+    }
+}
+
+impl<C: Chunk + DeserializeOwned> Region<C> for HttpRegion {
+    fn chunk(&self, x: CCoord, z: CCoord) -> Option<C> {
+        let data = self.load_chunk(x.0 as usize, z.0 as usize).ok()?;
+        from_bytes::<C>(&data).ok()
+    }
+
+    fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        matches!(self.sectors(x.0 as usize, z.0 as usize), Ok(Some(_)))
+    }
+
+    fn chunk_timestamps(&self) -> Option<Vec<u32>> {
+        let timestamps = &self.header[SECTOR_SIZE..HEADER_SIZE];
+        Some(
+            timestamps
+                .chunks_exact(4)
+                .map(BigEndian::read_u32)
+                .collect(),
+        )
+    }
+}
+
+/// Download `url`, or just the bytes in `range` of it.
+fn fetch(agent: &ureq::Agent, url: &str, range: Option<Range<u64>>) -> Result<Vec<u8>> {
+    let mut request = agent.get(url);
+    if let Some(range) = &range {
+        request = request.set("Range", &format!("bytes={}-{}", range.start, range.end - 1));
+    }
+
+    let response = request.call().map_err(|e| Error::Http(e.to_string()))?;
+    let partial = response.status() == 206;
+
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+
+    // Servers that don't support ranges send the whole file.
+    match range {
+        Some(range) if !partial => {
+            let end = (range.end as usize).min(body.len());
+            Ok(body.get(range.start as usize..end).unwrap_or(&[]).to_vec())
+        }
+        _ => Ok(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Cursor, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::{JavaChunk, RegionBuffer};
+
+    /// Serve the given files on localhost, honouring range requests, and
+    /// record the ranges asked for. Returns the base URL.
+    fn serve(files: Vec<(&'static str, Vec<u8>)>, ranges: Arc<Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let path = request.split(' ').nth(1).unwrap().to_owned();
+
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(r) = line.to_lowercase().strip_prefix("range: bytes=") {
+                        range = Some(r.trim().to_owned());
+                    }
+                }
+
+                let file = files.iter().find(|(name, _)| path == format!("/{}", name));
+                let (status, body) = match (file, &range) {
+                    (None, _) => ("404 Not Found", vec![]),
+                    (Some((_, data)), None) => ("200 OK", data.clone()),
+                    (Some((_, data)), Some(range)) => {
+                        ranges.lock().unwrap().push(range.clone());
+                        let (start, end) = range.split_once('-').unwrap();
+                        let start: usize = start.parse().unwrap();
+                        let end = (end.parse::<usize>().unwrap() + 1).min(data.len());
+                        ("206 Partial Content", data[start..end].to_vec())
+                    }
+                };
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        });
+
+        url
+    }
+
+    fn region_data() -> Vec<u8> {
+        let mut region = RegionBuffer::new(Cursor::new(Vec::new()));
+        region.write_chunk(3, 4, &[1, 2, 3]).unwrap();
+        region.write_chunk(0, 0, &[4, 5]).unwrap();
+        region.into_inner().into_inner()
+    }
+
+    #[test]
+    fn loads_only_header_and_chunk() {
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let url = serve(
+            vec![("world/r.0.-1.mca", region_data())],
+            Arc::clone(&ranges),
+        );
+
+        let loader = HttpRegionLoader::<JavaChunk>::new(format!("{}/world/", url));
+        let region = loader.region(RCoord(0), RCoord(-1)).unwrap();
+
+        assert!(Region::<JavaChunk>::chunk_exists(
+            &region,
+            CCoord(3),
+            CCoord(4)
+        ));
+        assert!(!Region::<JavaChunk>::chunk_exists(
+            &region,
+            CCoord(4),
+            CCoord(3)
+        ));
+        assert!(region.chunk_timestamp(3, 4).unwrap() > 0);
+
+        assert_eq!(vec![1, 2, 3], region.load_chunk(3, 4).unwrap());
+        assert!(matches!(region.load_chunk(1, 1), Err(Error::ChunkNotFound)));
+
+        // The two header sectors, then the single sector of the chunk.
+        assert_eq!(
+            vec!["0-8191".to_owned(), "8192-12287".to_owned()],
+            *ranges.lock().unwrap()
+        );
+
+        assert!(loader.region(RCoord(5), RCoord(5)).is_none());
+        assert!(loader.list().is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use image::error::{EncodingError, ImageFormatHint};
+use image::{ImageError, ImageFormat, RgbaImage};
+
+use crate::{Result, Rgba};
+
+/// An image of at most 256 colours, with each pixel an index into a table of
+/// colours. Written as a PNG these are around a quarter of the size of the
+/// same image in RGBA, which adds up for the many tiles of a web map.
+///
+/// Renders shade and blend the colours of the palette, so they usually have
+/// more than 256 colours. [`quantize`](Self::quantize) picks the colours that
+/// represent the image best.
+///
+/// ```no_run
+/// # use fastanvil::{IndexedImage, RegionMap, Rgba};
+/// # fn render() -> RegionMap<Rgba> { unimplemented!() }
+/// let map = render();
+/// IndexedImage::quantize(&map.to_image(), 256).save_png("r.0.0.png")?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedImage {
+    width: u32,
+    height: u32,
+
+    /// The colours pixels refer to, at most 256.
+    pub colours: Vec<Rgba>,
+
+    /// An index into `colours` for each pixel, a row at a time from the top
+    /// left.
+    pub pixels: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Convert an image that has at most 256 distinct colours without losing
+    /// anything. `None` if there are more colours than that.
+    pub fn from_rgba(img: &RgbaImage) -> Option<Self> {
+        let mut indices = HashMap::new();
+        let mut colours = Vec::new();
+        let mut pixels = Vec::with_capacity(img.width() as usize * img.height() as usize);
+
+        for pixel in img.pixels() {
+            let index = match indices.get(&pixel.0) {
+                Some(&index) => index,
+                None => {
+                    if colours.len() == 256 {
+                        return None;
+                    }
+                    let index = colours.len() as u8;
+                    indices.insert(pixel.0, index);
+                    colours.push(pixel.0);
+                    index
+                }
+            };
+            pixels.push(index);
+        }
+
+        Some(Self {
+            width: img.width(),
+            height: img.height(),
+            colours,
+            pixels,
+        })
+    }
+
+    /// Convert an image to at most `max_colours` colours, which is clamped to
+    /// 1 to 256. Images with few enough colours are converted exactly.
+    /// Otherwise colours are grouped by median cut, with each group replaced
+    /// by its average, weighted by how many pixels have each colour.
+    pub fn quantize(img: &RgbaImage, max_colours: usize) -> Self {
+        let max_colours = max_colours.clamp(1, 256);
+
+        let mut counts: HashMap<Rgba, usize> = HashMap::new();
+        for pixel in img.pixels() {
+            *counts.entry(pixel.0).or_default() += 1;
+        }
+
+        if counts.len() <= max_colours {
+            if let Some(exact) = Self::from_rgba(img) {
+                return exact;
+            }
+        }
+
+        let mut boxes = vec![counts.into_iter().collect::<Vec<_>>()];
+
+        // Split the box with the widest range of a channel until there are
+        // enough boxes, or no box has more than one colour.
+        while boxes.len() < max_colours {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| b.len() > 1)
+                .map(|(i, b)| (i, widest_channel(b)))
+                .max_by_key(|&(_, (_, range))| range);
+
+            let (i, channel) = match widest {
+                Some((i, (channel, _))) => (i, channel),
+                None => break,
+            };
+
+            let mut b = boxes.swap_remove(i);
+            b.sort_unstable_by_key(|(colour, _)| colour[channel]);
+
+            // Split at the median pixel, keeping at least one colour in each
+            // half.
+            let total: usize = b.iter().map(|(_, count)| count).sum();
+            let mut seen = 0;
+            let mut split = 1;
+            for (j, (_, count)) in b.iter().enumerate() {
+                seen += count;
+                if seen * 2 >= total {
+                    split = (j + 1).clamp(1, b.len() - 1);
+                    break;
+                }
+            }
+
+            let upper = b.split_off(split);
+            boxes.push(b);
+            boxes.push(upper);
+        }
+
+        let mut indices = HashMap::new();
+        let colours = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                for (colour, _) in b {
+                    indices.insert(*colour, i as u8);
+                }
+                average(b)
+            })
+            .collect();
+
+        Self {
+            width: img.width(),
+            height: img.height(),
+            colours,
+            pixels: img.pixels().map(|p| indices[&p.0]).collect(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the colour of the pixel at the given coordinates. Will panic if
+    /// they are outside the image.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Rgba {
+        assert!(x < self.width && y < self.height);
+        self.colours[self.pixels[(y * self.width + x) as usize] as usize]
+    }
+
+    /// Convert back to an RGBA image.
+    pub fn to_rgba(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            image::Rgba(self.get_pixel(x, y))
+        })
+    }
+
+    /// Write the image as an indexed PNG. Transparency is only written if
+    /// a colour is not opaque.
+    pub fn write_png(&self, writer: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(self.colours.iter().flat_map(|c| c[..3].to_vec()).collect());
+        if self.colours.iter().any(|c| c[3] != 255) {
+            encoder.set_trns(self.colours.iter().map(|c| c[3]).collect());
+        }
+
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&self.pixels).map_err(png_error)?;
+        Ok(())
+    }
+
+    /// Save the image as an indexed PNG file.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        self.write_png(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// The channel of the colours with the largest range, and that range.
+fn widest_channel(colours: &[(Rgba, usize)]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let values = colours.iter().map(|(c, _)| c[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+/// The average of the colours, weighted by their pixel counts.
+fn average(colours: &[(Rgba, usize)]) -> Rgba {
+    let total: usize = colours.iter().map(|(_, count)| count).sum();
+    let mut sums = [0usize; 4];
+    for (colour, count) in colours {
+        for (sum, c) in sums.iter_mut().zip(colour) {
+            *sum += *c as usize * count;
+        }
+    }
+
+    let mut average = [0; 4];
+    for (a, sum) in average.iter_mut().zip(sums) {
+        *a = ((sum + total / 2) / total) as u8;
+    }
+    average
+}
+
+fn png_error(err: png::EncodingError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Png),
+        err,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(data: &[u8]) -> RgbaImage {
+        image::load_from_memory_with_format(data, ImageFormat::Png)
+            .unwrap()
+            .into_rgba8()
+    }
+
+    #[test]
+    fn few_colours_are_exact() {
+        let img = RgbaImage::from_fn(16, 16, |x, y| match (x + y) % 3 {
+            0 => image::Rgba([0, 0, 0, 0]),
+            1 => image::Rgba([10, 200, 30, 255]),
+            _ => image::Rgba([0, 0, 255, 100]),
+        });
+
+        let indexed = IndexedImage::quantize(&img, 256);
+        assert_eq!(Some(&indexed), IndexedImage::from_rgba(&img).as_ref());
+        assert_eq!(3, indexed.colours.len());
+        assert_eq!(img, indexed.to_rgba());
+
+        let mut png = Vec::new();
+        indexed.write_png(&mut png).unwrap();
+        assert_eq!(img, decode(&png));
+    }
+
+    #[test]
+    fn many_colours_are_quantized() {
+        let img = RgbaImage::from_fn(64, 64, |x, y| {
+            image::Rgba([x as u8 * 4, y as u8 * 4, 0, 255])
+        });
+        assert!(IndexedImage::from_rgba(&img).is_none());
+
+        let indexed = IndexedImage::quantize(&img, 16);
+        assert_eq!(16, indexed.colours.len());
+
+        // Every pixel is close to its original colour.
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let quantized = indexed.get_pixel(x, y);
+            for c in 0..4 {
+                let diff = (pixel.0[c] as i32 - quantized[c] as i32).abs();
+                assert!(diff <= 32, "{:?} became {:?}", pixel.0, quantized);
+            }
+        }
+
+        let mut png = Vec::new();
+        indexed.write_png(&mut png).unwrap();
+        assert_eq!(indexed.to_rgba(), decode(&png));
+    }
+}
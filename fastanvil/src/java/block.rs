@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use serde::Deserialize;
+use serde::{ser::SerializeStruct, Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct Block {
@@ -11,6 +11,35 @@ pub struct Block {
 }
 
 impl Block {
+    /// Create a block from its name, eg `minecraft:stone`, and its properties.
+    pub fn new(name: String, properties: HashMap<String, String>) -> Self {
+        let snowy = properties.get("snowy").map(String::as_str) == Some("true");
+
+        let mut id = name.clone() + "|";
+        let mut sep = "";
+
+        let mut props = properties
+            .iter()
+            .filter(|(k, _)| *k != "waterlogged") // TODO: Handle water logging. See note below
+            .filter(|(k, _)| *k != "powered") // TODO: Handle power
+            .collect::<Vec<_>>();
+
+        // need to sort the properties for a consistent ID
+        props.sort_unstable();
+
+        for (k, v) in props {
+            id = id + sep + k + "=" + v;
+            sep = ",";
+        }
+
+        Self {
+            name,
+            snowy,
+            encoded: id,
+            properties,
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -19,10 +48,70 @@ impl Block {
         self.snowy
     }
 
+    /// All of the block state properties of the block, eg `facing` or `half`.
     pub fn properties(&self) -> &HashMap<String, String> {
         &self.properties
     }
 
+    /// Get the value of a block state property, eg `property("facing")` might
+    /// return `Some("north")`.
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(String::as_str)
+    }
+
+    /// Whether the block is filled with water as well as itself, eg a
+    /// waterlogged slab or fence.
+    pub fn is_waterlogged(&self) -> bool {
+        self.property("waterlogged") == Some("true")
+    }
+
+    /// Whether the block is powered by redstone. Only some blocks, like
+    /// levers and rails, have this property.
+    pub fn is_powered(&self) -> bool {
+        self.property("powered") == Some("true")
+    }
+
+    /// The direction the block is facing, eg `north` or `up`, if the block
+    /// has a direction.
+    pub fn facing(&self) -> Option<&str> {
+        self.property("facing")
+    }
+
+    /// Parse a block state string like `minecraft:oak_log[axis=x]`, as used
+    /// in commands and schematic palettes.
+    pub fn from_state_str(state: &str) -> Self {
+        let (name, props) = match state.split_once('[') {
+            Some((name, props)) => (name, props.trim_end_matches(']')),
+            None => (state, ""),
+        };
+
+        let properties = props
+            .split(',')
+            .filter_map(|p| p.split_once('='))
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect();
+
+        Self::new(name.to_owned(), properties)
+    }
+
+    /// The block as a block state string like `minecraft:oak_log[axis=x]`,
+    /// the inverse of [`from_state_str`](Self::from_state_str). Properties are
+    /// sorted so the same block always gives the same string.
+    pub(crate) fn state_string(&self) -> String {
+        if self.properties.is_empty() {
+            return self.name.clone();
+        }
+
+        let mut props = self
+            .properties
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>();
+        props.sort_unstable();
+
+        format!("{}[{}]", self.name, props.join(","))
+    }
+
     /// A string of the format "id|prop1=val1,prop2=val2". The properties are
     /// ordered lexigraphically. This somewhat matches the way Minecraft stores
     /// variants in blockstates, but with the block ID/name prepended.
@@ -46,31 +135,55 @@ impl<'de> Deserialize<'de> for Block {
         D: serde::Deserializer<'de>,
     {
         let raw: BlockRaw = Deserialize::deserialize(deserializer)?;
-        let snowy = raw.properties.get("snowy").map(String::as_str) == Some("true");
+        Ok(Self::new(raw.name, raw.properties))
+    }
+}
 
-        let mut id = raw.name.clone() + "|";
-        let mut sep = "";
+impl Serialize for Block {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Blocks without properties have no Properties tag at all.
+        let len = if self.properties.is_empty() { 1 } else { 2 };
+        let mut s = serializer.serialize_struct("Block", len)?;
+        s.serialize_field("Name", &self.name)?;
+        if !self.properties.is_empty() {
+            s.serialize_field("Properties", &self.properties)?;
+        }
+        s.end()
+    }
+}
 
-        let mut props = raw
-            .properties
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties() {
+        let props = [("facing", "east"), ("waterlogged", "true"), ("half", "top")]
             .iter()
-            .filter(|(k, _)| *k != "waterlogged") // TODO: Handle water logging. See note below
-            .filter(|(k, _)| *k != "powered") // TODO: Handle power
-            .collect::<Vec<_>>();
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let block = Block::new("minecraft:oak_stairs".to_owned(), props);
 
-        // need to sort the properties for a consistent ID
-        props.sort_unstable();
+        assert_eq!(block.property("half"), Some("top"));
+        assert_eq!(block.property("shape"), None);
+        assert_eq!(block.facing(), Some("east"));
+        assert!(block.is_waterlogged());
+        assert!(!block.is_powered());
+        assert_eq!(
+            block.encoded_description(),
+            "minecraft:oak_stairs|facing=east,half=top"
+        );
+    }
 
-        for (k, v) in props {
-            id = id + sep + k + "=" + v;
-            sep = ",";
-        }
+    #[test]
+    fn no_properties() {
+        let block = Block::new("minecraft:stone".to_owned(), HashMap::new());
 
-        Ok(Self {
-            name: raw.name,
-            snowy,
-            encoded: id,
-            properties: raw.properties,
-        })
+        assert_eq!(block.facing(), None);
+        assert!(!block.is_waterlogged());
+        assert!(block.properties().is_empty());
     }
 }
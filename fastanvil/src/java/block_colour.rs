@@ -0,0 +1,668 @@
+use crate::{biome, biome::Biome, map_colour, Rgba};
+
+/// The colour of a block as seen from above, from a table built into
+/// fastanvil, so that no textures are needed. Blocks coloured by their biome,
+/// such as grass, leaves and water, take the colour the biome gives them.
+/// Without a biome they are coloured as in plains.
+///
+/// Other blocks take the colour the game uses for them on map items, which
+/// is coarser than a palette rendered from textures, but covers most blocks
+/// in the game. Blocks that don't show on maps, such as air and glass, are
+/// transparent. Blocks fastanvil doesn't know are `None`.
+///
+/// Names can be given with or without the `minecraft:` namespace.
+///
+/// ```
+/// # use fastanvil::{block_colour, biome::Biome};
+/// assert_eq!(Some([112, 112, 112, 255]), block_colour("minecraft:stone", None));
+/// assert_eq!(Some([0, 0, 0, 0]), block_colour("air", None));
+/// assert_ne!(
+///     block_colour("grass_block", Some(Biome::Desert)),
+///     block_colour("grass_block", Some(Biome::Jungle)),
+/// );
+/// assert_eq!(None, block_colour("mymod:mystery_block", None));
+/// ```
+pub fn block_colour(name: &str, biome: Option<Biome>) -> Option<Rgba> {
+    let short = name.strip_prefix("minecraft:").unwrap_or(name);
+    let biome = biome.unwrap_or(Biome::Plains);
+
+    let tinted = match short {
+        "grass_block" | "grass" | "short_grass" | "tall_grass" | "fern" | "large_fern"
+        | "sugar_cane" => Some(grass_colour(&biome)),
+        "oak_leaves" | "jungle_leaves" | "acacia_leaves" | "dark_oak_leaves"
+        | "mangrove_leaves" | "vine" => Some(foliage_colour(&biome)),
+        "birch_leaves" => Some([0x80, 0xa7, 0x55, 255]),
+        "spruce_leaves" => Some([0x61, 0x99, 0x61, 255]),
+        "water" | "bubble_column" => Some(water_colour(Some(biome))),
+        _ => None,
+    };
+
+    tinted.or_else(|| block_map_colour(name).map(|id| map_colour(id * 4 + 2)))
+}
+
+/// The base map colour of a block, as used on map items. The map colour IDs
+/// of the block's shades are `4 * base` to `4 * base + 3`, see
+/// [`map_colour`]. Blocks that don't show on maps have a base of 0. Blocks
+/// fastanvil doesn't know are `None`.
+///
+/// ```
+/// # use fastanvil::block_map_colour;
+/// assert_eq!(Some(11), block_map_colour("minecraft:stone_brick_stairs"));
+/// assert_eq!(Some(0), block_map_colour("minecraft:glass"));
+/// ```
+pub fn block_map_colour(name: &str) -> Option<u8> {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    let name = name.strip_prefix("waxed_").unwrap_or(name);
+
+    if let Some(id) = material(name) {
+        return Some(id);
+    }
+
+    if name.ends_with("_leaves") || name.ends_with("_sapling") || is_flower(name) {
+        return Some(PLANT);
+    }
+
+    if let Some(id) = dyed(name) {
+        return Some(id);
+    }
+
+    if let Some(id) = wooden(name) {
+        return Some(id);
+    }
+
+    if let Some(ore) = name.strip_suffix("_ore") {
+        return Some(if ore.starts_with("deepslate_") {
+            DEEPSLATE
+        } else if ore.starts_with("nether_") {
+            NETHER
+        } else {
+            STONE
+        });
+    }
+
+    if let Some(id) = coral(name) {
+        return Some(id);
+    }
+
+    if name.starts_with("infested_") {
+        return Some(CLAY);
+    }
+    if name.starts_with("potted_") {
+        return Some(NONE);
+    }
+
+    if let Some(id) = copper(name) {
+        return Some(id);
+    }
+
+    // Shapes and variants of a block, eg polished_andesite_stairs, are
+    // coloured like the block itself.
+    let mut base = name;
+    if let Some(stripped) = SHAPES.iter().find_map(|shape| base.strip_suffix(shape)) {
+        base = stripped;
+    }
+    for prefix in VARIANTS {
+        base = base.strip_prefix(prefix).unwrap_or(base);
+    }
+
+    // Shapes made of bricks and tiles drop the plural, eg stone_brick_slab,
+    // and bricks of a block are coloured like the block, eg deepslate_tiles.
+    material(base)
+        .or_else(|| material(&format!("{}s", base)))
+        .or_else(|| {
+            let block = BRICKS.iter().find_map(|bricks| base.strip_suffix(bricks))?;
+            material(block)
+        })
+}
+
+/// Shapes that blocks come in.
+const SHAPES: [&str; 6] = [
+    "_stairs",
+    "_slab",
+    "_wall",
+    "_fence",
+    "_pillar",
+    "_pressure_plate",
+];
+
+/// Variants of blocks that are coloured the same as the block.
+const VARIANTS: [&str; 6] = [
+    "polished_",
+    "chiseled_",
+    "cut_",
+    "smooth_",
+    "cracked_",
+    "mossy_",
+];
+
+const BRICKS: [&str; 4] = ["_bricks", "_brick", "_tiles", "_tile"];
+
+// Base map colours, the index of the colour in the game's list.
+const NONE: u8 = 0;
+const GRASS: u8 = 1;
+const SAND: u8 = 2;
+const WOOL: u8 = 3;
+const FIRE: u8 = 4;
+const ICE: u8 = 5;
+const METAL: u8 = 6;
+const PLANT: u8 = 7;
+const SNOW: u8 = 8;
+const CLAY: u8 = 9;
+const DIRT: u8 = 10;
+const STONE: u8 = 11;
+const WATER: u8 = 12;
+const WOOD: u8 = 13;
+const QUARTZ: u8 = 14;
+const ORANGE: u8 = 15;
+const MAGENTA: u8 = 16;
+const YELLOW: u8 = 18;
+const LIME: u8 = 19;
+const PINK: u8 = 20;
+const GRAY: u8 = 21;
+const CYAN: u8 = 23;
+const PURPLE: u8 = 24;
+const BLUE: u8 = 25;
+const BROWN: u8 = 26;
+const GREEN: u8 = 27;
+const RED: u8 = 28;
+const BLACK: u8 = 29;
+const GOLD: u8 = 30;
+const DIAMOND: u8 = 31;
+const LAPIS: u8 = 32;
+const EMERALD: u8 = 33;
+const PODZOL: u8 = 34;
+const NETHER: u8 = 35;
+const TERRACOTTA_WHITE: u8 = 36;
+const TERRACOTTA_GRAY: u8 = 43;
+const TERRACOTTA_LIGHT_GRAY: u8 = 44;
+const TERRACOTTA_CYAN: u8 = 45;
+const TERRACOTTA_BROWN: u8 = 48;
+const CRIMSON_NYLIUM: u8 = 52;
+const CRIMSON_STEM: u8 = 53;
+const CRIMSON_HYPHAE: u8 = 54;
+const WARPED_NYLIUM: u8 = 55;
+const WARPED_STEM: u8 = 56;
+const WARPED_HYPHAE: u8 = 57;
+const WARPED_WART_BLOCK: u8 = 58;
+const DEEPSLATE: u8 = 59;
+const RAW_IRON: u8 = 60;
+const GLOW_LICHEN: u8 = 61;
+
+/// Dye colours in the order of their map colours. White is the exception,
+/// with the snow map colour rather than its own.
+const DYES: [&str; 16] = [
+    "white",
+    "orange",
+    "magenta",
+    "light_blue",
+    "yellow",
+    "lime",
+    "pink",
+    "gray",
+    "light_gray",
+    "cyan",
+    "purple",
+    "blue",
+    "brown",
+    "green",
+    "red",
+    "black",
+];
+
+/// Blocks that come in every dye colour, eg red_wool.
+fn dyed(name: &str) -> Option<u8> {
+    let (i, block) = DYES
+        .iter()
+        .enumerate()
+        .find_map(|(i, dye)| Some((i as u8, name.strip_prefix(dye)?.strip_prefix('_')?)))?;
+
+    match block {
+        "terracotta" => Some(TERRACOTTA_WHITE + i),
+        "banner" | "wall_banner" => Some(WOOD),
+        "wool" | "carpet" | "concrete" | "concrete_powder" | "stained_glass"
+        | "stained_glass_pane" | "shulker_box" | "bed" | "candle" | "glazed_terracotta" => {
+            Some(if i == 0 { SNOW } else { ORANGE + i - 1 })
+        }
+        _ => None,
+    }
+}
+
+/// Blocks made of a kind of wood, eg birch_fence. The tops of logs show the
+/// colour of the planks.
+fn wooden(name: &str) -> Option<u8> {
+    let woods = [
+        ("oak", WOOD),
+        ("spruce", PODZOL),
+        ("birch", SAND),
+        ("jungle", DIRT),
+        ("acacia", ORANGE),
+        ("dark_oak", BROWN),
+        ("mangrove", RED),
+        ("cherry", TERRACOTTA_WHITE),
+        ("bamboo", YELLOW),
+        ("crimson", CRIMSON_STEM),
+        ("warped", WARPED_STEM),
+    ];
+
+    let name = name.strip_prefix("stripped_").unwrap_or(name);
+    let (colour, block) = woods
+        .iter()
+        .find_map(|&(wood, colour)| Some((colour, name.strip_prefix(wood)?.strip_prefix('_')?)))?;
+
+    match block {
+        "hyphae" if colour == CRIMSON_STEM => Some(CRIMSON_HYPHAE),
+        "hyphae" if colour == WARPED_STEM => Some(WARPED_HYPHAE),
+        "planks" | "log" | "wood" | "stem" | "hyphae" | "stairs" | "slab" | "fence"
+        | "fence_gate" | "door" | "trapdoor" | "pressure_plate" | "sign" | "wall_sign"
+        | "hanging_sign" | "wall_hanging_sign" | "block" | "mosaic" | "mosaic_stairs"
+        | "mosaic_slab" => Some(colour),
+        "button" => Some(NONE),
+        _ => None,
+    }
+}
+
+/// Copper blocks, which change colour as they oxidise.
+fn copper(name: &str) -> Option<u8> {
+    if !name.contains("copper") {
+        return None;
+    }
+
+    Some(if name.starts_with("exposed_") {
+        TERRACOTTA_LIGHT_GRAY
+    } else if name.starts_with("weathered_") {
+        WARPED_STEM
+    } else if name.starts_with("oxidized_") {
+        WARPED_NYLIUM
+    } else {
+        ORANGE
+    })
+}
+
+/// Coral and coral blocks.
+fn coral(name: &str) -> Option<u8> {
+    if name.starts_with("dead_") && name.contains("coral") {
+        return Some(GRAY);
+    }
+
+    let kind = name.split('_').next()?;
+    let rest = &name[kind.len()..];
+    if !matches!(
+        rest,
+        "_coral" | "_coral_block" | "_coral_fan" | "_coral_wall_fan"
+    ) {
+        return None;
+    }
+
+    match kind {
+        "tube" => Some(BLUE),
+        "brain" => Some(PINK),
+        "bubble" => Some(PURPLE),
+        "fire" => Some(RED),
+        "horn" => Some(YELLOW),
+        _ => None,
+    }
+}
+
+fn is_flower(name: &str) -> bool {
+    name.ends_with("_tulip")
+        || matches!(
+            name,
+            "dandelion"
+                | "poppy"
+                | "blue_orchid"
+                | "allium"
+                | "azure_bluet"
+                | "oxeye_daisy"
+                | "cornflower"
+                | "lily_of_the_valley"
+                | "wither_rose"
+                | "torchflower"
+                | "pink_petals"
+                | "sunflower"
+                | "lilac"
+                | "rose_bush"
+                | "peony"
+                | "pitcher_plant"
+                | "spore_blossom"
+        )
+}
+
+/// Blocks with a colour of their own, by name without the namespace.
+fn material(name: &str) -> Option<u8> {
+    let id = match name {
+        "air"
+        | "cave_air"
+        | "void_air"
+        | "glass"
+        | "glass_pane"
+        | "tinted_glass"
+        | "barrier"
+        | "light"
+        | "structure_void"
+        | "moving_piston"
+        | "torch"
+        | "wall_torch"
+        | "soul_torch"
+        | "soul_wall_torch"
+        | "redstone_torch"
+        | "redstone_wall_torch"
+        | "redstone_wire"
+        | "repeater"
+        | "comparator"
+        | "lever"
+        | "ladder"
+        | "rail"
+        | "powered_rail"
+        | "detector_rail"
+        | "activator_rail"
+        | "tripwire"
+        | "tripwire_hook"
+        | "flower_pot"
+        | "nether_portal"
+        | "stone_button"
+        | "polished_blackstone_button"
+        | "end_rod"
+        | "cake" => NONE,
+
+        "grass_block" | "slime_block" => GRASS,
+        "sand" | "sandstone" | "end_stone" | "glowstone" | "bone_block" | "scaffolding"
+        | "candle" | "turtle_egg" | "sniffer_egg" => SAND,
+        "cobweb" | "mushroom_stem" => WOOL,
+        "lava" | "tnt" | "redstone_block" | "fire" => FIRE,
+        "ice" | "packed_ice" | "blue_ice" | "frosted_ice" => ICE,
+        "iron_block"
+        | "iron_door"
+        | "iron_trapdoor"
+        | "iron_bars"
+        | "anvil"
+        | "chipped_anvil"
+        | "damaged_anvil"
+        | "lodestone"
+        | "brewing_stand"
+        | "heavy_weighted_pressure_plate"
+        | "lantern"
+        | "soul_lantern"
+        | "chain"
+        | "grindstone" => METAL,
+        "grass" | "short_grass" | "tall_grass" | "fern" | "large_fern" | "dead_bush" | "vine"
+        | "lily_pad" | "cactus" | "sugar_cane" | "bamboo" | "bamboo_sapling" | "wheat"
+        | "carrots" | "potatoes" | "beetroots" | "sweet_berry_bush" | "azalea"
+        | "flowering_azalea" | "big_dripleaf" | "small_dripleaf" | "cave_vines"
+        | "cave_vines_plant" | "twisting_vines" | "weeping_vines" | "hanging_roots"
+        | "mangrove_roots" | "pumpkin_stem" | "melon_stem" | "cocoa" => PLANT,
+        "snow" | "snow_block" | "powder_snow" => SNOW,
+        "clay" => CLAY,
+        "dirt"
+        | "coarse_dirt"
+        | "rooted_dirt"
+        | "farmland"
+        | "dirt_path"
+        | "granite"
+        | "brown_mushroom_block"
+        | "jukebox"
+        | "packed_mud" => DIRT,
+        "stone"
+        | "cobblestone"
+        | "stone_bricks"
+        | "andesite"
+        | "gravel"
+        | "bedrock"
+        | "furnace"
+        | "blast_furnace"
+        | "smoker"
+        | "dispenser"
+        | "dropper"
+        | "observer"
+        | "piston"
+        | "sticky_piston"
+        | "hopper"
+        | "cauldron"
+        | "water_cauldron"
+        | "lava_cauldron"
+        | "powder_snow_cauldron"
+        | "stonecutter"
+        | "spawner"
+        | "ender_chest"
+        | "smooth_stone" => STONE,
+        "water" | "bubble_column" | "kelp" | "kelp_plant" | "seagrass" | "tall_seagrass" => WATER,
+        "bookshelf" | "chiseled_bookshelf" | "crafting_table" | "chest" | "trapped_chest"
+        | "barrel" | "note_block" | "lectern" | "loom" | "cartography_table"
+        | "fletching_table" | "smithing_table" | "composter" | "beehive" | "bee_nest"
+        | "campfire" | "soul_campfire" | "daylight_detector" => WOOD,
+        "diorite" | "quartz_block" | "quartz_bricks" | "sea_lantern" | "target" | "quartz" => {
+            QUARTZ
+        }
+        "red_sand" | "red_sandstone" | "pumpkin" | "carved_pumpkin" | "jack_o_lantern"
+        | "terracotta" | "honey_block" | "honeycomb_block" | "raw_copper_block"
+        | "lightning_rod" => ORANGE,
+        "purpur_block" | "purpur" | "purpur_pillar" => MAGENTA,
+        "hay_block" | "sponge" | "wet_sponge" => YELLOW,
+        "melon" => LIME,
+        "prismarine" => CYAN,
+        "mycelium" | "amethyst_block" | "budding_amethyst" | "amethyst_cluster" | "shulker_box"
+        | "chorus_plant" | "chorus_flower" => PURPLE,
+        "soul_sand" | "soul_soil" => BROWN,
+        "moss_block" | "moss_carpet" | "dried_kelp_block" | "sculk_vein" => GREEN,
+        "bricks" | "brick" | "red_mushroom_block" | "nether_wart_block" | "nether_wart"
+        | "shroomlight" | "enchanting_table" => RED,
+        "obsidian"
+        | "crying_obsidian"
+        | "coal_block"
+        | "blackstone"
+        | "gilded_blackstone"
+        | "basalt"
+        | "smooth_basalt"
+        | "end_portal"
+        | "end_portal_frame"
+        | "end_gateway"
+        | "dragon_egg"
+        | "respawn_anchor"
+        | "ancient_debris"
+        | "netherite_block"
+        | "sculk"
+        | "sculk_sensor"
+        | "calibrated_sculk_sensor"
+        | "sculk_catalyst"
+        | "sculk_shrieker" => BLACK,
+        "gold_block" | "raw_gold_block" | "bell" | "light_weighted_pressure_plate" => GOLD,
+        "diamond_block" | "beacon" | "conduit" | "prismarine_bricks" | "dark_prismarine" => DIAMOND,
+        "lapis_block" => LAPIS,
+        "emerald_block" => EMERALD,
+        "podzol" => PODZOL,
+        "netherrack" | "nether_bricks" | "nether_brick" | "red_nether_bricks"
+        | "red_nether_brick" | "magma_block" => NETHER,
+        "calcite" => TERRACOTTA_WHITE,
+        "mud_bricks" | "mud_brick" => TERRACOTTA_LIGHT_GRAY,
+        "mud" => TERRACOTTA_CYAN,
+        "tuff" => TERRACOTTA_GRAY,
+        "dripstone_block" | "pointed_dripstone" => TERRACOTTA_BROWN,
+        "crimson_nylium" => CRIMSON_NYLIUM,
+        "crimson_fungus" | "crimson_roots" | "weeping_vines_plant" => NETHER,
+        "warped_nylium" => WARPED_NYLIUM,
+        "warped_fungus" | "warped_roots" | "nether_sprouts" | "twisting_vines_plant" => CYAN,
+        "warped_wart_block" => WARPED_WART_BLOCK,
+        "deepslate" | "cobbled_deepslate" | "reinforced_deepslate" => DEEPSLATE,
+        "raw_iron_block" => RAW_IRON,
+        "glow_lichen" => GLOW_LICHEN,
+        _ => return None,
+    };
+
+    Some(id)
+}
+
+/// The colour of grass in a biome. The game takes this from a colour map
+/// indexed by temperature and rainfall. Here the colour map is approximated
+/// by blending the colours of its three corners.
+fn grass_colour(b: &Biome) -> Rgba {
+    if let Some(colour) = grass_override(b) {
+        return colour;
+    }
+
+    let colour = colour_map(
+        b,
+        [0xbf, 0xb7, 0x55],
+        [0x47, 0xcd, 0x33],
+        [0x80, 0xb4, 0x97],
+    );
+    if matches!(b, Biome::DarkForest | Biome::DarkForestHills) {
+        dark_forest_grass(colour)
+    } else {
+        colour
+    }
+}
+
+/// The colour of leaves in a biome, approximated in the same way as grass.
+fn foliage_colour(b: &Biome) -> Rgba {
+    foliage_override(b).unwrap_or_else(|| {
+        colour_map(
+            b,
+            [0xae, 0xa4, 0x2a],
+            [0x1a, 0xbf, 0x00],
+            [0x60, 0xa1, 0x7b],
+        )
+    })
+}
+
+/// Blend the corners of a colour map for the climate of the biome. The
+/// corners are for hot and dry, hot and wet, and cold.
+fn colour_map(b: &Biome, hot_dry: [u8; 3], hot_wet: [u8; 3], cold: [u8; 3]) -> Rgba {
+    let climate = biome::climate(b.clone());
+    let t = climate.temperature.clamp(0., 1.);
+    let r = climate.rainfall.clamp(0., 1.) * t;
+
+    let (wet, dry, cold_weight) = (r, t - r, 1. - t);
+    let channel = |i: usize| {
+        (hot_wet[i] as f64 * wet + hot_dry[i] as f64 * dry + cold[i] as f64 * cold_weight).round()
+            as u8
+    };
+
+    [channel(0), channel(1), channel(2), 255]
+}
+
+/// Grass colours that some biomes set directly, rather than using the colour
+/// map.
+pub(crate) fn grass_override(b: &Biome) -> Option<Rgba> {
+    use Biome::*;
+    match b {
+        Swamp | SwampHills | MangroveSwamp => Some([0x6a, 0x70, 0x39, 255]),
+        Badlands
+        | BadlandsPlateau
+        | ModifiedBadlandsPlateau
+        | WoodedBadlandsPlateau
+        | ModifiedWoodedBadlandsPlateau
+        | ErodedBadlands => Some([0x90, 0x81, 0x4d, 255]),
+        _ => None,
+    }
+}
+
+/// Foliage colours that some biomes set directly, rather than using the
+/// colour map.
+pub(crate) fn foliage_override(b: &Biome) -> Option<Rgba> {
+    use Biome::*;
+    match b {
+        Swamp | SwampHills => Some([0x6a, 0x70, 0x39, 255]),
+        Badlands
+        | BadlandsPlateau
+        | ModifiedBadlandsPlateau
+        | WoodedBadlandsPlateau
+        | ModifiedWoodedBadlandsPlateau
+        | ErodedBadlands => Some([0x9e, 0x81, 0x4d, 255]),
+        _ => None,
+    }
+}
+
+/// Dark forests darken the grass colour from the colour map.
+pub(crate) fn dark_forest_grass(colour: Rgba) -> Rgba {
+    let darken = |c: u8, d: u8| ((c as u16 + d as u16) / 2) as u8;
+    [
+        darken(colour[0], 0x28),
+        darken(colour[1], 0x34),
+        darken(colour[2], 0x0a),
+        colour[3],
+    ]
+}
+
+/// The colour of water in a biome.
+pub(crate) fn water_colour(b: Option<Biome>) -> Rgba {
+    use Biome::*;
+    match b {
+        Some(Swamp) => [0x61, 0x7B, 0x64, 255],
+        Some(LukewarmOcean) => [0x45, 0xAD, 0xF2, 255],
+        Some(WarmOcean) => [0x43, 0xD5, 0xEE, 255],
+        Some(ColdOcean) => [0x3D, 0x57, 0xD6, 255],
+        Some(FrozenRiver) | Some(FrozenOcean) => [0x39, 0x38, 0xC9, 255],
+        _ => [0x3f, 0x76, 0xe4, 255],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shapes_and_variants() {
+        assert_eq!(
+            Some(STONE),
+            block_map_colour("minecraft:stone_brick_stairs")
+        );
+        assert_eq!(
+            Some(STONE),
+            block_map_colour("minecraft:mossy_cobblestone_wall")
+        );
+        assert_eq!(
+            Some(DIRT),
+            block_map_colour("minecraft:polished_granite_slab")
+        );
+        assert_eq!(
+            Some(NETHER),
+            block_map_colour("minecraft:nether_brick_fence")
+        );
+        assert_eq!(
+            Some(DEEPSLATE),
+            block_map_colour("minecraft:deepslate_tile_stairs")
+        );
+        assert_eq!(
+            Some(DEEPSLATE),
+            block_map_colour("minecraft:deepslate_diamond_ore")
+        );
+        assert_eq!(
+            Some(ORANGE),
+            block_map_colour("minecraft:waxed_cut_copper_slab")
+        );
+    }
+
+    #[test]
+    fn dyes_and_woods() {
+        assert_eq!(Some(SNOW), block_map_colour("white_wool"));
+        assert_eq!(Some(17), block_map_colour("light_blue_carpet"));
+        assert_eq!(Some(BLUE), block_map_colour("blue_concrete"));
+        assert_eq!(Some(22), block_map_colour("light_gray_stained_glass"));
+        assert_eq!(Some(51), block_map_colour("black_terracotta"));
+        assert_eq!(Some(BROWN), block_map_colour("dark_oak_fence_gate"));
+        assert_eq!(Some(WOOD), block_map_colour("stripped_oak_log"));
+        assert_eq!(Some(WARPED_HYPHAE), block_map_colour("warped_hyphae"));
+        assert_eq!(Some(PLANT), block_map_colour("cherry_leaves"));
+        assert_eq!(Some(NONE), block_map_colour("birch_button"));
+    }
+
+    #[test]
+    fn biome_tints() {
+        let plains = block_colour("grass_block", Some(Biome::Plains)).unwrap();
+        assert_eq!(plains, block_colour("grass_block", None).unwrap());
+        assert_eq!(
+            Some([0x6a, 0x70, 0x39, 255]),
+            block_colour("grass", Some(Biome::Swamp))
+        );
+
+        // Hot and wet biomes are greener than cold ones.
+        let jungle = block_colour("oak_leaves", Some(Biome::Jungle)).unwrap();
+        let tundra = block_colour("oak_leaves", Some(Biome::SnowyTundra)).unwrap();
+        assert!(jungle[1] > tundra[1] && jungle[2] < tundra[2]);
+
+        assert_eq!(
+            Some([0x43, 0xD5, 0xEE, 255]),
+            block_colour("minecraft:water", Some(Biome::WarmOcean))
+        );
+        assert_eq!(Some(map_colour(RED * 4 + 2)), block_colour("bricks", None));
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use fastnbt::Value;
+use serde::Deserialize;
+
+use super::ItemStack;
+
+/// A block entity (previously called a tile entity). These hold the extra
+/// data of blocks like chests, signs and spawners that doesn't fit in the
+/// block state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntity {
+    /// The block entity ID, eg `minecraft:chest`. Worlds from before 1.11 use
+    /// IDs like `Chest`.
+    pub id: String,
+
+    /// The position of the block entity in world coordinates.
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+
+    pub kind: BlockEntityKind,
+}
+
+/// The data of a block entity. Common block entities are decoded, everything
+/// else is left as raw NBT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockEntityKind {
+    Chest(Chest),
+    Sign(Sign),
+    Spawner(Spawner),
+
+    /// Any other block entity, or one that could not be decoded. Contains all
+    /// of the NBT of the block entity other than the ID and position.
+    Other(HashMap<String, Value>),
+}
+
+/// A chest or trapped chest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chest {
+    /// The custom name of the chest, as a JSON text component.
+    pub custom_name: Option<String>,
+
+    /// The loot table of a chest that hasn't been opened yet. Such chests
+    /// have no items until they are opened.
+    pub loot_table: Option<String>,
+
+    pub items: Vec<ItemStack>,
+}
+
+/// A stack of items in a slot of a chest.
+#[deprecated(note = "chests now hold ItemStack, which is also used for other items")]
+pub type ChestItem = ItemStack;
+
+/// A sign. Only the front text is available for signs since 1.20.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sign {
+    /// The lines of the sign, each a JSON text component.
+    pub text: [String; 4],
+
+    /// The dye colour of the text, eg `black`.
+    pub color: Option<String>,
+    pub glowing: bool,
+}
+
+/// A monster spawner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spawner {
+    /// The ID of the entity that will be spawned next, eg
+    /// `minecraft:zombie`.
+    pub entity: Option<String>,
+
+    /// Ticks until the next spawn.
+    pub delay: i16,
+    pub min_spawn_delay: i16,
+    pub max_spawn_delay: i16,
+    pub spawn_count: i16,
+    pub spawn_range: i16,
+    pub required_player_range: i16,
+}
+
+impl BlockEntity {
+    /// Get the position of the block entity within its chunk, ie with x and z
+    /// in 0..16.
+    pub fn chunk_position(&self) -> (usize, isize, usize) {
+        (
+            self.x.rem_euclid(16) as usize,
+            self.y as isize,
+            self.z.rem_euclid(16) as usize,
+        )
+    }
+
+    fn from_compound(mut map: HashMap<String, Value>) -> Option<Self> {
+        let id = match map.remove("id")? {
+            Value::String(id) => id,
+            _ => return None,
+        };
+
+        let x = int(&map.remove("x")?)? as i32;
+        let y = int(&map.remove("y")?)? as i32;
+        let z = int(&map.remove("z")?)? as i32;
+
+        let kind = match id.as_str() {
+            "minecraft:chest" | "minecraft:trapped_chest" | "Chest" => {
+                Chest::from_compound(&map).map(BlockEntityKind::Chest)
+            }
+            "minecraft:sign" | "minecraft:hanging_sign" | "Sign" => {
+                Sign::from_compound(&map).map(BlockEntityKind::Sign)
+            }
+            "minecraft:mob_spawner" | "MobSpawner" => {
+                Some(BlockEntityKind::Spawner(Spawner::from_compound(&map)))
+            }
+            _ => None,
+        }
+        .unwrap_or(BlockEntityKind::Other(map));
+
+        Some(Self { id, x, y, z, kind })
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockEntity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let map = HashMap::<String, Value>::deserialize(deserializer)?;
+        Self::from_compound(map)
+            .ok_or_else(|| serde::de::Error::custom("block entity is missing its id or position"))
+    }
+}
+
+impl Chest {
+    fn from_compound(map: &HashMap<String, Value>) -> Option<Self> {
+        let items = match map.get("Items") {
+            Some(Value::List(items)) => items
+                .iter()
+                .map(ItemStack::from_value)
+                .collect::<Option<_>>()?,
+            Some(_) => return None,
+            None => vec![],
+        };
+
+        Some(Self {
+            custom_name: string(map.get("CustomName")),
+            loot_table: string(map.get("LootTable")),
+            items,
+        })
+    }
+}
+
+impl Sign {
+    fn from_compound(map: &HashMap<String, Value>) -> Option<Self> {
+        // 1.20 moved the text into a compound for each side of the sign.
+        if let Some(Value::Compound(front)) = map.get("front_text") {
+            let messages = match front.get("messages") {
+                Some(Value::List(messages)) if messages.len() == 4 => messages,
+                _ => return None,
+            };
+
+            return Some(Self {
+                text: [
+                    string(messages.first())?,
+                    string(messages.get(1))?,
+                    string(messages.get(2))?,
+                    string(messages.get(3))?,
+                ],
+                color: string(front.get("color")),
+                glowing: flag(front.get("has_glowing_text")),
+            });
+        }
+
+        Some(Self {
+            text: [
+                string(map.get("Text1"))?,
+                string(map.get("Text2"))?,
+                string(map.get("Text3"))?,
+                string(map.get("Text4"))?,
+            ],
+            color: string(map.get("Color")),
+            glowing: flag(map.get("GlowingText")),
+        })
+    }
+}
+
+impl Spawner {
+    fn from_compound(map: &HashMap<String, Value>) -> Self {
+        let short = |key: &str| map.get(key).and_then(int).unwrap_or(0) as i16;
+
+        // The entity ID has moved around over the versions. Before 1.9 it was
+        // EntityId, then SpawnData.id, and since 1.18 SpawnData.entity.id.
+        let entity = match map.get("SpawnData") {
+            Some(Value::Compound(data)) => match data.get("entity") {
+                Some(Value::Compound(entity)) => string(entity.get("id")),
+                _ => string(data.get("id")),
+            },
+            _ => string(map.get("EntityId")),
+        };
+
+        Self {
+            entity,
+            delay: short("Delay"),
+            min_spawn_delay: short("MinSpawnDelay"),
+            max_spawn_delay: short("MaxSpawnDelay"),
+            spawn_count: short("SpawnCount"),
+            spawn_range: short("SpawnRange"),
+            required_player_range: short("RequiredPlayerRange"),
+        }
+    }
+}
+
+pub(crate) fn int(value: &Value) -> Option<i64> {
+    match value {
+        Value::Byte(v) => Some(*v as i64),
+        Value::Short(v) => Some(*v as i64),
+        Value::Int(v) => Some(*v as i64),
+        Value::Long(v) => Some(*v),
+        _ => None,
+    }
+}
+
+pub(crate) fn string(value: Option<&Value>) -> Option<String> {
+    match value {
+        Some(Value::String(s)) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn flag(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::Byte(v)) if *v != 0)
+}
@@ -16,11 +16,7 @@ impl Blockstates {
     pub fn state(&self, x: usize, sec_y: usize, z: usize, pal_len: usize) -> usize {
         // 🤮 This is a very hot function, so the ugly is worth the speed.
         if !self.done.get() {
-            let bits_per_item = bits_per_block(pal_len);
-            let mut buf = self.unpacked.borrow_mut();
-
-            self.packed.unpack_blockstates(bits_per_item, buf.as_mut());
-            self.done.replace(true);
+            self.unpack(pal_len);
         }
 
         let state_index = (sec_y * 16 * 16) + z * 16 + x;
@@ -28,6 +24,37 @@ impl Blockstates {
         // We *know* unpacked is filled in because we just made it above.
         self.unpacked.borrow().as_ref()[state_index] as usize
     }
+
+    /// Count how many times each palette index is used, for a palette of
+    /// `pal_len` blocks. Indices past the end of the palette, which only
+    /// appear in corrupt chunks, are ignored.
+    pub fn histogram(&self, pal_len: usize) -> Vec<usize> {
+        if !self.done.get() {
+            self.unpack(pal_len);
+        }
+
+        let mut counts = vec![0; pal_len];
+        for &index in self.unpacked.borrow().iter() {
+            if let Some(count) = counts.get_mut(index as usize) {
+                *count += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// The packed palette indices, as stored in the chunk.
+    pub fn packed(&self) -> &PackedBits {
+        &self.packed
+    }
+
+    fn unpack(&self, pal_len: usize) {
+        let bits_per_item = bits_per_block(pal_len);
+        let mut buf = self.unpacked.borrow_mut();
+
+        self.packed.unpack_blockstates(bits_per_item, buf.as_mut());
+        self.done.replace(true);
+    }
 }
 
 impl<'de> Deserialize<'de> for Blockstates {
@@ -36,10 +63,16 @@ impl<'de> Deserialize<'de> for Blockstates {
         D: serde::Deserializer<'de>,
     {
         let packed: PackedBits = Deserialize::deserialize(d)?;
-        Ok(Self {
+        Ok(Self::from(packed))
+    }
+}
+
+impl From<PackedBits> for Blockstates {
+    fn from(packed: PackedBits) -> Self {
+        Self {
             done: Cell::new(false),
             packed,
             unpacked: RefCell::new([0; 16 * 16 * 16]),
-        })
+        }
     }
 }
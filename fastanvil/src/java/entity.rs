@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use fastnbt::{IntArray, Value};
+use serde::Deserialize;
+
+use super::ItemStack;
+
+/// The contents of a chunk in an entity region file, found in the `entities`
+/// folder of a world since 1.17.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct EntityChunk {
+    pub data_version: i32,
+
+    /// The chunk coordinates of this chunk, as `[x, z]`.
+    pub position: IntArray,
+
+    #[serde(default)]
+    pub entities: Vec<Entity>,
+}
+
+/// A Minecraft entity such as a mob, item frame or dropped item. The fields
+/// common to all entities are available directly, everything else is kept in
+/// `other`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Entity {
+    /// The entity ID, eg `minecraft:creeper`.
+    pub id: String,
+
+    /// The position of the entity as x, y, z.
+    #[serde(rename = "Pos")]
+    pub pos: [f64; 3],
+
+    /// The velocity of the entity as x, y, z.
+    #[serde(rename = "Motion", default)]
+    pub motion: [f64; 3],
+
+    /// The rotation of the entity as yaw, pitch.
+    #[serde(rename = "Rotation", default)]
+    pub rotation: [f32; 2],
+
+    /// The custom name of the entity, as a JSON text component.
+    #[serde(rename = "CustomName")]
+    pub custom_name: Option<String>,
+
+    /// Entities riding this entity.
+    #[serde(rename = "Passengers", default)]
+    pub passengers: Vec<Entity>,
+
+    #[serde(rename = "UUID")]
+    uuid: Option<IntArray>,
+
+    /// All other NBT of the entity, which differs between types of entity.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl Entity {
+    /// The UUID of the entity. This is stored as four ints, most significant
+    /// first. Entities from before 1.16 store their UUID in a different
+    /// format, which can be found in `other`.
+    pub fn uuid(&self) -> Option<u128> {
+        uuid_from_ints(self.uuid.as_ref()?)
+    }
+
+    /// The item held by an item frame or dropped item entity.
+    pub fn item(&self) -> Option<ItemStack> {
+        ItemStack::from_value(self.other.get("Item")?)
+    }
+}
+
+/// Decode a UUID stored as four ints, most significant first.
+pub(crate) fn uuid_from_ints(ints: &[i32]) -> Option<u128> {
+    let ints = <[i32; 4]>::try_from(ints).ok()?;
+    Some(fastnbt::uuid::from_ints(ints))
+}
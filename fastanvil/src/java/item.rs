@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+
+use fastnbt::Value;
+use serde::Deserialize;
+
+use super::block_entity::{int, string};
+
+/// A stack of items, as found in chests, player inventories and item frames.
+/// Items changed a lot in 1.20.5, when the free-form `tag` was replaced by
+/// item components. Methods like [`enchantments`](Self::enchantments) read
+/// from whichever the item has.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    /// The slot the item is in, for items in a container. Item frames and
+    /// dropped items have no slot.
+    pub slot: Option<i8>,
+
+    /// The item ID, eg `minecraft:diamond`.
+    pub id: String,
+    pub count: i32,
+
+    /// Extra data of the item such as enchantments or a custom name, before
+    /// 1.20.5.
+    pub tag: Option<HashMap<String, Value>>,
+
+    /// The item components of the item, since 1.20.5. Keys are namespaced,
+    /// eg `minecraft:damage`.
+    pub components: Option<HashMap<String, Value>>,
+}
+
+/// An enchantment of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enchantment {
+    /// The enchantment ID, eg `minecraft:sharpness`. Numeric IDs from before
+    /// 1.13 are converted to names.
+    pub id: String,
+    pub level: i32,
+}
+
+impl ItemStack {
+    /// Get an item component. The namespace can be left off for components
+    /// from `minecraft`.
+    pub fn component(&self, name: &str) -> Option<&Value> {
+        let components = self.components.as_ref()?;
+        if name.contains(':') {
+            components.get(name)
+        } else {
+            components.get(&format!("minecraft:{}", name))
+        }
+    }
+
+    /// The enchantments of the item, sorted by ID.
+    pub fn enchantments(&self) -> Vec<Enchantment> {
+        self.read_enchantments("minecraft:enchantments", &["Enchantments", "ench"])
+    }
+
+    /// The enchantments stored in an enchanted book, sorted by ID.
+    pub fn stored_enchantments(&self) -> Vec<Enchantment> {
+        self.read_enchantments("minecraft:stored_enchantments", &["StoredEnchantments"])
+    }
+
+    /// The custom name of the item, as a JSON text component.
+    pub fn custom_name(&self) -> Option<String> {
+        if self.components.is_some() {
+            return string(self.component("minecraft:custom_name"));
+        }
+
+        match self.tag.as_ref()?.get("display") {
+            Some(Value::Compound(display)) => string(display.get("Name")),
+            _ => None,
+        }
+    }
+
+    /// The damage taken by the item, zero for undamaged or unbreakable items.
+    pub fn damage(&self) -> i32 {
+        let damage = match &self.components {
+            Some(_) => self.component("minecraft:damage"),
+            None => self.tag.as_ref().and_then(|tag| tag.get("Damage")),
+        };
+
+        damage.and_then(int).unwrap_or(0) as i32
+    }
+
+    /// The items inside the item, for items like shulker boxes that keep
+    /// their contents.
+    pub fn container_items(&self) -> Vec<ItemStack> {
+        if self.components.is_some() {
+            // Since 1.20.5 each entry is the slot and the item, separately.
+            return match self.component("minecraft:container") {
+                Some(Value::List(entries)) => entries
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        Value::Compound(entry) => {
+                            let mut item = ItemStack::from_value(entry.get("item")?)?;
+                            item.slot = entry.get("slot").and_then(int).map(|s| s as i8);
+                            Some(item)
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+        }
+
+        let items = match self.tag.as_ref().and_then(|tag| tag.get("BlockEntityTag")) {
+            Some(Value::Compound(entity)) => entity.get("Items"),
+            _ => None,
+        };
+
+        match items {
+            Some(Value::List(items)) => items.iter().filter_map(ItemStack::from_value).collect(),
+            _ => vec![],
+        }
+    }
+
+    pub(crate) fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Compound(map) => Self::from_compound(map),
+            _ => None,
+        }
+    }
+
+    fn from_compound(map: &HashMap<String, Value>) -> Option<Self> {
+        let compound = |key: &str| match map.get(key) {
+            Some(Value::Compound(c)) => Some(c.clone()),
+            _ => None,
+        };
+
+        Some(Self {
+            slot: map
+                .get("Slot")
+                .or_else(|| map.get("slot"))
+                .and_then(int)
+                .map(|s| s as i8),
+            id: string(map.get("id"))?,
+            count: map
+                .get("Count")
+                .or_else(|| map.get("count"))
+                .and_then(int)
+                .unwrap_or(1) as i32,
+            tag: compound("tag"),
+            components: compound("components"),
+        })
+    }
+
+    fn read_enchantments(&self, component: &str, tag_keys: &[&str]) -> Vec<Enchantment> {
+        let mut enchantments = match &self.components {
+            Some(_) => match self.component(component) {
+                Some(Value::Compound(map)) => {
+                    // Until 1.21.5 the levels were nested in `levels`.
+                    let levels = match map.get("levels") {
+                        Some(Value::Compound(levels)) => levels,
+                        _ => map,
+                    };
+
+                    levels
+                        .iter()
+                        .filter_map(|(id, level)| {
+                            Some(Enchantment {
+                                id: id.clone(),
+                                level: int(level)? as i32,
+                            })
+                        })
+                        .collect()
+                }
+                _ => vec![],
+            },
+            None => {
+                let list = self
+                    .tag
+                    .as_ref()
+                    .and_then(|tag| tag_keys.iter().find_map(|key| tag.get(*key)));
+
+                match list {
+                    Some(Value::List(list)) => {
+                        list.iter().filter_map(Enchantment::from_value).collect()
+                    }
+                    _ => vec![],
+                }
+            }
+        };
+
+        enchantments.sort_by(|a, b| a.id.cmp(&b.id));
+        enchantments
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemStack {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let map = HashMap::<String, Value>::deserialize(deserializer)?;
+        Self::from_compound(&map).ok_or_else(|| serde::de::Error::custom("item is missing its id"))
+    }
+}
+
+impl Enchantment {
+    fn from_value(value: &Value) -> Option<Self> {
+        let map = match value {
+            Value::Compound(map) => map,
+            _ => return None,
+        };
+
+        let id = match map.get("id")? {
+            Value::String(id) => id.clone(),
+            numeric => legacy_enchantment_name(int(numeric)?)?.to_owned(),
+        };
+
+        Some(Self {
+            id,
+            level: int(map.get("lvl")?)? as i32,
+        })
+    }
+}
+
+/// The name of an enchantment from its numeric ID, used before 1.13.
+fn legacy_enchantment_name(id: i64) -> Option<&'static str> {
+    Some(match id {
+        0 => "minecraft:protection",
+        1 => "minecraft:fire_protection",
+        2 => "minecraft:feather_falling",
+        3 => "minecraft:blast_protection",
+        4 => "minecraft:projectile_protection",
+        5 => "minecraft:respiration",
+        6 => "minecraft:aqua_affinity",
+        7 => "minecraft:thorns",
+        8 => "minecraft:depth_strider",
+        9 => "minecraft:frost_walker",
+        10 => "minecraft:binding_curse",
+        16 => "minecraft:sharpness",
+        17 => "minecraft:smite",
+        18 => "minecraft:bane_of_arthropods",
+        19 => "minecraft:knockback",
+        20 => "minecraft:fire_aspect",
+        21 => "minecraft:looting",
+        22 => "minecraft:sweeping",
+        32 => "minecraft:efficiency",
+        33 => "minecraft:silk_touch",
+        34 => "minecraft:unbreaking",
+        35 => "minecraft:fortune",
+        48 => "minecraft:power",
+        49 => "minecraft:punch",
+        50 => "minecraft:flame",
+        51 => "minecraft:infinity",
+        61 => "minecraft:luck_of_the_sea",
+        62 => "minecraft:lure",
+        70 => "minecraft:mending",
+        71 => "minecraft:vanishing_curse",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_owned(), v))
+                .collect(),
+        )
+    }
+
+    fn item(entries: Vec<(&str, Value)>) -> ItemStack {
+        ItemStack::from_value(&compound(entries)).unwrap()
+    }
+
+    fn enchantment(id: &str, level: i32) -> Enchantment {
+        Enchantment {
+            id: id.to_owned(),
+            level,
+        }
+    }
+
+    #[test]
+    fn tag_item() {
+        let sword = item(vec![
+            ("Slot", Value::Byte(2)),
+            ("id", Value::String("minecraft:diamond_sword".to_owned())),
+            ("Count", Value::Byte(1)),
+            (
+                "tag",
+                compound(vec![
+                    ("Damage", Value::Int(12)),
+                    (
+                        "display",
+                        compound(vec![("Name", Value::String("\"Sting\"".to_owned()))]),
+                    ),
+                    (
+                        "Enchantments",
+                        Value::List(vec![
+                            compound(vec![
+                                ("id", Value::String("minecraft:unbreaking".to_owned())),
+                                ("lvl", Value::Short(3)),
+                            ]),
+                            compound(vec![
+                                ("id", Value::String("minecraft:sharpness".to_owned())),
+                                ("lvl", Value::Short(5)),
+                            ]),
+                        ]),
+                    ),
+                ]),
+            ),
+        ]);
+
+        assert_eq!(Some(2), sword.slot);
+        assert_eq!(12, sword.damage());
+        assert_eq!(Some("\"Sting\"".to_owned()), sword.custom_name());
+        assert_eq!(
+            vec![
+                enchantment("minecraft:sharpness", 5),
+                enchantment("minecraft:unbreaking", 3)
+            ],
+            sword.enchantments()
+        );
+    }
+
+    #[test]
+    fn legacy_enchantment_ids() {
+        let book = item(vec![
+            ("id", Value::String("minecraft:enchanted_book".to_owned())),
+            (
+                "tag",
+                compound(vec![(
+                    "StoredEnchantments",
+                    Value::List(vec![compound(vec![
+                        ("id", Value::Short(70)),
+                        ("lvl", Value::Short(1)),
+                    ])]),
+                )]),
+            ),
+        ]);
+
+        assert_eq!(1, book.count);
+        assert!(book.enchantments().is_empty());
+        assert_eq!(
+            vec![enchantment("minecraft:mending", 1)],
+            book.stored_enchantments()
+        );
+    }
+
+    #[test]
+    fn component_item() {
+        let levels = compound(vec![("minecraft:efficiency", Value::Int(4))]);
+        let pick = item(vec![
+            ("id", Value::String("minecraft:iron_pickaxe".to_owned())),
+            ("count", Value::Int(1)),
+            (
+                "components",
+                compound(vec![
+                    ("minecraft:damage", Value::Int(3)),
+                    (
+                        "minecraft:enchantments",
+                        compound(vec![
+                            ("levels", levels),
+                            ("show_in_tooltip", Value::Byte(1)),
+                        ]),
+                    ),
+                ]),
+            ),
+        ]);
+
+        assert_eq!(3, pick.damage());
+        assert_eq!(Some(&Value::Int(3)), pick.component("damage"));
+        assert_eq!(
+            vec![enchantment("minecraft:efficiency", 4)],
+            pick.enchantments()
+        );
+
+        // 1.21.5 dropped the nesting.
+        let pick = item(vec![
+            ("id", Value::String("minecraft:iron_pickaxe".to_owned())),
+            (
+                "components",
+                compound(vec![(
+                    "minecraft:enchantments",
+                    compound(vec![("minecraft:efficiency", Value::Int(4))]),
+                )]),
+            ),
+        ]);
+        assert_eq!(
+            vec![enchantment("minecraft:efficiency", 4)],
+            pick.enchantments()
+        );
+    }
+
+    #[test]
+    fn shulker_box_contents() {
+        let stone = || {
+            compound(vec![
+                ("id", Value::String("minecraft:stone".to_owned())),
+                ("count", Value::Int(64)),
+            ])
+        };
+
+        let shulker = item(vec![
+            ("id", Value::String("minecraft:shulker_box".to_owned())),
+            (
+                "components",
+                compound(vec![(
+                    "minecraft:container",
+                    Value::List(vec![compound(vec![
+                        ("slot", Value::Int(4)),
+                        ("item", stone()),
+                    ])]),
+                )]),
+            ),
+        ]);
+
+        let contents = shulker.container_items();
+        assert_eq!(1, contents.len());
+        assert_eq!(Some(4), contents[0].slot);
+        assert_eq!(64, contents[0].count);
+    }
+}
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, convert::TryFrom, ops::Range};
+
+use fastnbt::{ByteArray, IntArray};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use super::nibble;
+use crate::{biome::Biome, Block, BlockEntity, Chunk, ChunkStatus, HeightMode};
+
+lazy_static! {
+    static ref LEGACY_TABLE: Vec<((u16, u8), Block)> = parse_table(include_str!("legacy_blocks.txt"));
+    static ref LEGACY_BLOCKS: HashMap<(u16, u8), Block> = LEGACY_TABLE.iter().cloned().collect();
+    static ref LEGACY_IDS: HashMap<String, (u16, u8)> = {
+        // Several IDs can give the same block, the first in the table wins.
+        let mut ids = HashMap::new();
+        for (id, block) in LEGACY_TABLE.iter() {
+            ids.entry(block.state_string()).or_insert(*id);
+            ids.entry(block.name.clone()).or_insert(*id);
+        }
+        ids
+    };
+}
+
+/// Get the modern block for a pre-1.13 numeric block ID and data value. Data
+/// values that the conversion table doesn't know about fall back to the block
+/// for data value 0. Returns `None` for unknown IDs, such as those added by
+/// mods.
+pub fn legacy_block(id: u16, data: u8) -> Option<&'static Block> {
+    LEGACY_BLOCKS
+        .get(&(id, data))
+        .or_else(|| LEGACY_BLOCKS.get(&(id, 0)))
+}
+
+/// Get the pre-1.13 numeric block ID and data value for a modern block, the
+/// reverse of [`legacy_block`]. Blocks whose exact state has no legacy
+/// equivalent fall back to the first ID for a block of the same name. Returns
+/// `None` for blocks added since 1.13.
+pub fn legacy_id(block: &Block) -> Option<(u16, u8)> {
+    LEGACY_IDS
+        .get(&block.state_string())
+        .or_else(|| LEGACY_IDS.get(&block.name))
+        .copied()
+}
+
+fn parse_table(table: &str) -> Vec<((u16, u8), Block)> {
+    table
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(|line| {
+            let (key, state) = line.split_once(' ').expect("legacy table: missing state");
+            let (id, data) = key.split_once(':').expect("legacy table: missing data");
+            (
+                (id.parse().unwrap(), data.parse().unwrap()),
+                Block::from_state_str(state),
+            )
+        })
+        .collect()
+}
+
+/// A chunk from before 1.13, where blocks are stored as numeric IDs rather
+/// than a palette of block states. Blocks are converted to their modern
+/// equivalent when accessed.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyChunk {
+    /// Only present from 1.9 onwards.
+    #[serde(default)]
+    pub data_version: i32,
+    pub level: LegacyLevel,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacyLevel {
+    #[serde(rename = "xPos")]
+    pub x_pos: i32,
+
+    #[serde(rename = "zPos")]
+    pub z_pos: i32,
+
+    #[serde(default)]
+    pub sections: Vec<LegacySection>,
+
+    /// The lowest Y in each column where the full strength of sunlight
+    /// reaches, indexed by `z * 16 + x`.
+    pub height_map: Option<IntArray>,
+
+    /// A biome ID for each column, indexed by `z * 16 + x`.
+    pub biomes: Option<ByteArray>,
+
+    /// Whether features like trees and ores have been generated in the chunk.
+    #[serde(default)]
+    pub terrain_populated: bool,
+
+    /// The total time in ticks players have spent in the chunk.
+    pub inhabited_time: Option<i64>,
+
+    #[serde(default)]
+    pub tile_entities: Vec<BlockEntity>,
+}
+
+/// A 16x16x16 section of a legacy chunk. Blocks are indexed by
+/// `y * 256 + z * 16 + x`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct LegacySection {
+    pub y: i8,
+
+    /// The lowest 8 bits of the block ID of each block.
+    pub blocks: ByteArray,
+
+    /// The data value of each block, 4 bits per block.
+    pub data: ByteArray,
+
+    /// The top 4 bits of the block ID of each block. Only present if a block
+    /// in the section has an ID above 255.
+    pub add: Option<ByteArray>,
+
+    /// Light from blocks such as torches, 4 bits per block.
+    pub block_light: Option<ByteArray>,
+
+    /// Light from the sky, 4 bits per block.
+    pub sky_light: Option<ByteArray>,
+}
+
+impl LegacySection {
+    /// Get the numeric block ID and data value of the block at the given
+    /// section-relative coordinates.
+    pub fn block_id(&self, x: usize, y: usize, z: usize) -> Option<(u16, u8)> {
+        let index = y * 256 + z * 16 + x;
+
+        let low = *self.blocks.get(index)? as u8 as u16;
+        let high = match &self.add {
+            Some(add) => nibble(add, index)? as u16,
+            None => 0,
+        };
+
+        Some((high << 8 | low, nibble(&self.data, index)?))
+    }
+}
+
+impl LegacyChunk {
+    fn section_for_y(&self, y: isize) -> Option<&LegacySection> {
+        if !self.y_range().contains(&y) {
+            return None;
+        }
+
+        let section_y = (y / 16) as i8;
+        self.level.sections.iter().find(|s| s.y == section_y)
+    }
+}
+
+impl Chunk for LegacyChunk {
+    fn status(&self) -> ChunkStatus {
+        if self.level.terrain_populated {
+            ChunkStatus::Full
+        } else {
+            ChunkStatus::Empty
+        }
+    }
+
+    fn inhabited_time(&self) -> Option<i64> {
+        self.level.inhabited_time
+    }
+
+    fn surface_height(&self, x: usize, z: usize, mode: HeightMode) -> isize {
+        if let (HeightMode::Trust, Some(heights)) = (mode, &self.level.height_map) {
+            if let Some(h) = heights.get(z * 16 + x) {
+                return *h as isize;
+            }
+        }
+
+        let top = self.y_range().end;
+        (0..top)
+            .rev()
+            .find(|y| matches!(self.block(x, *y, z), Some(b) if b.name() != "minecraft:air"))
+            .map_or(0, |y| y + 1)
+    }
+
+    fn biome(&self, x: usize, _y: isize, z: usize) -> Option<Biome> {
+        let biome = *self.level.biomes.as_ref()?.get(z * 16 + x)? as u8;
+        Biome::try_from(biome as i32).ok()
+    }
+
+    fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+        let section = self.section_for_y(y)?;
+        let (id, data) = section.block_id(x, (y % 16) as usize, z)?;
+
+        legacy_block(id, data)
+    }
+
+    fn block_entities(&self) -> &[BlockEntity] {
+        &self.level.tile_entities
+    }
+
+    fn block_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        let section = self.section_for_y(y)?;
+        nibble(
+            section.block_light.as_ref()?,
+            (y as usize % 16) * 256 + z * 16 + x,
+        )
+    }
+
+    fn sky_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        let section = self.section_for_y(y)?;
+        nibble(
+            section.sky_light.as_ref()?,
+            (y as usize % 16) * 256 + z * 16 + x,
+        )
+    }
+
+    fn y_range(&self) -> Range<isize> {
+        0..256
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use crate::Result;
+
+/// The contents of a world's `level.dat`, which holds global information about
+/// the world such as its name, seed and spawn point. Fields that are not
+/// present in every version are optional, everything not modelled here is kept
+/// in `other`.
+///
+/// Use [`load_level_dat`] or [`read_level_dat`] to load one.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct LevelDat {
+    pub data_version: Option<i32>,
+
+    pub level_name: String,
+
+    /// The seed of the world before 1.16. Later versions store it in
+    /// [`world_gen_settings`](Self::world_gen_settings), see
+    /// [`seed`](Self::seed) for getting it from either.
+    pub random_seed: Option<i64>,
+
+    /// How the world is generated, since 1.16.
+    #[serde(rename = "WorldGenSettings")]
+    pub world_gen_settings: Option<WorldGenSettings>,
+
+    #[serde(rename = "SpawnX")]
+    pub spawn_x: i32,
+    #[serde(rename = "SpawnY")]
+    pub spawn_y: i32,
+    #[serde(rename = "SpawnZ")]
+    pub spawn_z: i32,
+
+    /// The game rules, which are all stored as strings, eg `"true"`.
+    #[serde(default)]
+    pub game_rules: HashMap<String, String>,
+
+    /// The version of Minecraft that last saved the world, since 1.9.
+    pub version: Option<LevelVersion>,
+
+    /// The default game mode. 0 is survival, 1 creative, 2 adventure and 3
+    /// spectator.
+    #[serde(default)]
+    pub game_type: i32,
+
+    #[serde(rename = "hardcore", default)]
+    pub hardcore: bool,
+
+    pub difficulty: Option<i8>,
+
+    #[serde(rename = "allowCommands", default)]
+    pub allow_commands: bool,
+
+    /// Number of ticks since the start of the world.
+    #[serde(default)]
+    pub time: i64,
+
+    /// Time of day in ticks, this does not reset each day.
+    #[serde(default)]
+    pub day_time: i64,
+
+    /// Unix time in milliseconds that the world was last played.
+    #[serde(default)]
+    pub last_played: i64,
+
+    /// The datapacks of the world, since 1.13.
+    pub data_packs: Option<DataPacks>,
+
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl LevelDat {
+    /// The seed of the world, wherever it is stored for this version.
+    pub fn seed(&self) -> Option<i64> {
+        self.world_gen_settings
+            .as_ref()
+            .map(|s| s.seed)
+            .or(self.random_seed)
+    }
+
+    /// The world spawn point as x, y, z.
+    pub fn spawn(&self) -> (i32, i32, i32) {
+        (self.spawn_x, self.spawn_y, self.spawn_z)
+    }
+}
+
+/// World generation settings, stored in `level.dat` since 1.16.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WorldGenSettings {
+    pub seed: i64,
+
+    #[serde(default)]
+    pub generate_features: bool,
+
+    #[serde(default)]
+    pub bonus_chest: bool,
+
+    /// Generator settings for each dimension, keyed by dimension ID such as
+    /// `minecraft:overworld`.
+    #[serde(default)]
+    pub dimensions: HashMap<String, Value>,
+}
+
+/// The datapacks of a world, by name. Packs from the world's `datapacks`
+/// folder are named `file/<name>`, others such as `vanilla` are built in.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct DataPacks {
+    #[serde(default)]
+    pub enabled: Vec<String>,
+
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+/// The version of Minecraft that last saved a world.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct LevelVersion {
+    /// The data version, eg 2730 for 1.17.1.
+    pub id: i32,
+
+    /// The name of the version, eg `1.17.1`.
+    pub name: String,
+
+    #[serde(default)]
+    pub snapshot: bool,
+}
+
+#[derive(Deserialize)]
+struct LevelDatFile {
+    #[serde(rename = "Data")]
+    data: LevelDat,
+}
+
+/// Load a `level.dat` file, usually found in the root folder of a world.
+///
+/// ```no_run
+/// let level = fastanvil::load_level_dat("world/level.dat")?;
+/// println!("{} has seed {:?}", level.level_name, level.seed());
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn load_level_dat(path: impl AsRef<Path>) -> Result<LevelDat> {
+    let file = File::open(path)?;
+    read_level_dat(BufReader::new(file))
+}
+
+/// Read the contents of a `level.dat` file. The data should still be gzip
+/// compressed, as it is on disk.
+pub fn read_level_dat(reader: impl Read) -> Result<LevelDat> {
+    let file: LevelDatFile = fastnbt::de::from_reader(GzDecoder::new(reader))?;
+    Ok(file.data)
+}
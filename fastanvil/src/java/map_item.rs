@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use fastnbt::{ByteArray, Value};
+use flate2::write::GzEncoder;
+#[cfg(feature = "image")]
+use image::RgbaImage;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::saved_data::{load_saved_data, read_saved_data};
+use crate::{
+    block_map_colour, Block, CCoord, Chunk, Dimension, DimensionId, HeightMode, RegionLoader,
+    Result, Rgba,
+};
+
+/// The number of pixels along each side of a map.
+pub const MAP_SIZE: usize = 128;
+
+/// The contents of a `map_N.dat` file from the `data` folder of a world,
+/// holding the picture drawn on the in-game map item with ID `N`.
+///
+/// Maps can also be made from the terrain of a world, to be given to players
+/// or hung on walls in item frames.
+///
+/// ```no_run
+/// # use fastanvil::{Dimension, DimensionId, JavaChunk, MapData, RegionFileLoader};
+/// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+/// let dimension = Dimension::new(loader);
+///
+/// let mut map = MapData::new(DimensionId::Overworld, 0, 0, 1);
+/// map.locked = true;
+/// map.draw_terrain(&dimension);
+/// map.save("world/data/map_100.dat")?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct MapData {
+    /// How zoomed out the map is, 0 to 4. Each pixel covers `2^scale` blocks
+    /// along each side.
+    #[serde(deserialize_with = "map_scale")]
+    pub scale: i8,
+
+    #[serde(rename = "xCenter")]
+    pub x_center: i32,
+
+    #[serde(rename = "zCenter")]
+    pub z_center: i32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimension: Option<Value>,
+
+    /// Whether the map was locked in a cartography table, and no longer
+    /// updates.
+    #[serde(default)]
+    pub locked: bool,
+
+    #[serde(rename = "trackingPosition", default)]
+    pub tracking_position: bool,
+
+    #[serde(rename = "unlimitedTracking", default)]
+    pub unlimited_tracking: bool,
+
+    /// The colour IDs of the map's pixels, indexed by `z * 128 + x`. Use
+    /// [`map_colour`] to turn these into colours.
+    pub colors: ByteArray,
+
+    /// Any other tags, such as the banners and item frames marked on the map.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl MapData {
+    /// A blank map of the given dimension, zoomed out by `scale` from 0 to 4,
+    /// covering the block X and Z given. Like maps made in the game, the
+    /// area covered is lined up to a grid of maps of the same scale, so the
+    /// centre is not necessarily the block given. Other scales are clamped
+    /// to 0 to 4.
+    pub fn new(dimension: DimensionId, x: i32, z: i32, scale: i8) -> Self {
+        let scale = scale.clamp(0, 4);
+        let size = (MAP_SIZE as i32) << scale;
+        let align = |pos: i32| (pos + 64).div_euclid(size) * size + size / 2 - 64;
+
+        Self {
+            scale,
+            x_center: align(x),
+            z_center: align(z),
+            dimension: Some(Value::String(dimension.id())),
+            locked: false,
+            tracking_position: true,
+            unlimited_tracking: false,
+            colors: ByteArray::new(vec![0; MAP_SIZE * MAP_SIZE]),
+            other: HashMap::new(),
+        }
+    }
+
+    /// The dimension the map shows. Maps from before 1.16 recorded this as a
+    /// number.
+    pub fn dimension(&self) -> Option<DimensionId> {
+        match self.dimension.as_ref()? {
+            Value::String(id) => Some(DimensionId::from_id(id)),
+            Value::Byte(0) | Value::Int(0) => Some(DimensionId::Overworld),
+            Value::Byte(-1) | Value::Int(-1) => Some(DimensionId::Nether),
+            Value::Byte(1) | Value::Int(1) => Some(DimensionId::End),
+            _ => None,
+        }
+    }
+
+    /// The number of blocks along each side of a pixel. A scale outside of
+    /// 0 to 4 is treated as the nearest of those.
+    pub fn blocks_per_pixel(&self) -> i32 {
+        1 << self.scale.clamp(0, 4)
+    }
+
+    /// The block X and Z at the north-west corner of the map.
+    pub fn origin(&self) -> (i32, i32) {
+        let half = MAP_SIZE as i32 / 2 * self.blocks_per_pixel();
+        (self.x_center - half, self.z_center - half)
+    }
+
+    /// The colour of the pixel at the given coordinates, transparent for
+    /// unexplored pixels. Will panic for coordinates outside of 0..128.
+    pub fn colour(&self, x: usize, z: usize) -> Rgba {
+        assert!(x < MAP_SIZE && z < MAP_SIZE);
+
+        match self.colors.get(z * MAP_SIZE + x) {
+            Some(&id) => map_colour(id as u8),
+            None => [0, 0, 0, 0],
+        }
+    }
+
+    /// Draw the map as a 128x128 image.
+    ///
+    /// ```no_run
+    /// # use fastanvil::load_map_data;
+    /// let map = load_map_data("world/data/map_0.dat")?;
+    /// map.to_image().save("map_0.png")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn to_image(&self) -> RgbaImage {
+        let size = MAP_SIZE as u32;
+        RgbaImage::from_fn(size, size, |x, z| {
+            image::Rgba(self.colour(x as usize, z as usize))
+        })
+    }
+
+    /// Draw the terrain of a dimension onto the map, as the game does when a
+    /// player holding the map explores. Every pixel is drawn at once rather
+    /// than only those near a player. Pixels in chunks that don't exist are
+    /// left as they were.
+    ///
+    /// The dimension should be the one the map is of. Maps of the nether are
+    /// drawn with the game's pattern for dimensions with a roof.
+    pub fn draw_terrain<C: Chunk, R: RegionLoader<C>>(&mut self, dimension: &Dimension<C, R>) {
+        let per_pixel = self.blocks_per_pixel();
+        let has_roof = self.dimension() == Some(DimensionId::Nether);
+        let mut colors = self.colors.to_vec();
+        colors.resize(MAP_SIZE * MAP_SIZE, 0);
+
+        for px in 0..MAP_SIZE as i32 {
+            // Pixels are shaded by how much higher they are than the pixel
+            // to their north, so start a pixel above the map.
+            let mut north_height = 0.;
+
+            for pz in -1..MAP_SIZE as i32 {
+                let x = (self.x_center / per_pixel + px - 64) * per_pixel;
+                let z = (self.z_center / per_pixel + pz - 64) * per_pixel;
+
+                let sample = match sample_pixel(dimension, x, z, per_pixel, has_roof) {
+                    Some(sample) => sample,
+                    None => continue,
+                };
+
+                let id = sample.colour_id(north_height, (px + pz) & 1 == 1, per_pixel);
+                north_height = sample.height;
+
+                if pz >= 0 {
+                    colors[pz as usize * MAP_SIZE + px as usize] = id as i8;
+                }
+            }
+        }
+
+        self.colors = ByteArray::new(colors);
+    }
+
+    /// Save the map as a `map_N.dat` file, replacing it if it exists. When
+    /// adding a new map to a world, also raise the count in `idcounts.dat`
+    /// to at least `N` so that the game doesn't reuse the ID.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = File::create(path)?;
+        self.write(BufWriter::new(file))
+    }
+
+    /// Write the map as a gzip compressed `map_N.dat` file.
+    pub fn write(&self, writer: impl Write) -> Result<()> {
+        #[derive(Serialize)]
+        struct SavedData<'a> {
+            data: &'a MapData,
+            #[serde(rename = "DataVersion")]
+            data_version: i32,
+        }
+
+        let file = SavedData {
+            data: self,
+            data_version: MAP_DATA_VERSION,
+        };
+
+        let mut enc = GzEncoder::new(writer, flate2::Compression::default());
+        fastnbt::ser::to_writer(&mut enc, &file)?;
+        enc.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// Maps are only ever zoomed out up to four times, anything else in a file
+/// is corrupt.
+fn map_scale<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<i8, D::Error> {
+    let scale = i8::deserialize(d)?;
+    if !(0..=4).contains(&scale) {
+        return Err(D::Error::custom(format!(
+            "map scale {} is not 0 to 4",
+            scale
+        )));
+    }
+    Ok(scale)
+}
+
+/// The data version maps are written with, that of 1.17.1. This is the
+/// oldest version with every map colour, newer versions upgrade the map when
+/// loading it.
+const MAP_DATA_VERSION: i32 = 2730;
+
+// Base map colours used when drawing maps.
+const STONE: u8 = 11;
+const DIRT: u8 = 10;
+const WATER: u8 = 12;
+const FIRE: u8 = 4;
+
+/// What a pixel of a map shows, before shading.
+struct Sample {
+    /// The base map colour most of the blocks of the pixel have.
+    colour: u8,
+
+    /// The average height of the blocks of the pixel.
+    height: f64,
+
+    /// The total depth of water over the blocks of the pixel.
+    water_depth: i32,
+}
+
+impl Sample {
+    /// The map colour ID of the pixel. The lower two bits are the shade,
+    /// from the height compared to the pixel to the north, or the depth of
+    /// water. `odd` pixels, in a checkerboard, are shaded slightly
+    /// differently to break up flat areas.
+    fn colour_id(&self, north_height: f64, odd: bool, per_pixel: i32) -> u8 {
+        const DARK: u8 = 0;
+        const NORMAL: u8 = 1;
+        const LIGHT: u8 = 2;
+
+        let odd = if odd { 1. } else { 0. };
+        let shade = if self.colour == WATER {
+            let depth = (self.water_depth / (per_pixel * per_pixel)) as f64 * 0.1 + odd * 0.2;
+            if depth < 0.5 {
+                LIGHT
+            } else if depth > 0.9 {
+                DARK
+            } else {
+                NORMAL
+            }
+        } else {
+            let slope =
+                (self.height - north_height) * 4. / (per_pixel + 4) as f64 + (odd - 0.5) * 0.4;
+            if slope > 0.6 {
+                LIGHT
+            } else if slope < -0.6 {
+                DARK
+            } else {
+                NORMAL
+            }
+        };
+
+        self.colour * 4 + shade
+    }
+}
+
+/// Look at the blocks of the map pixel with its north-west corner at the
+/// given block X and Z, as the game does. `None` if the chunk isn't there.
+fn sample_pixel<C: Chunk, R: RegionLoader<C>>(
+    dimension: &Dimension<C, R>,
+    x: i32,
+    z: i32,
+    per_pixel: i32,
+    has_roof: bool,
+) -> Option<Sample> {
+    let chunk = dimension.chunk(
+        CCoord(x.div_euclid(16) as isize),
+        CCoord(z.div_euclid(16) as isize),
+    )?;
+
+    if has_roof {
+        // The game draws a pattern of dirt and stone, done with Java's
+        // 32-bit ints.
+        let mut n = x.wrapping_add(z.wrapping_mul(231871));
+        n = n
+            .wrapping_mul(n)
+            .wrapping_mul(31287121)
+            .wrapping_add(n.wrapping_mul(11));
+        let colour = if (n >> 20) & 1 == 0 { DIRT } else { STONE };
+
+        return Some(Sample {
+            colour,
+            height: 100.,
+            water_depth: 0,
+        });
+    }
+
+    let y_min = chunk.y_range().start;
+    let block_colour = |block: Option<&Block>| block.and_then(|b| block_map_colour(b.name()));
+
+    // The colours of the columns and how many columns have them, in the
+    // order first seen.
+    let mut counts: Vec<(u8, usize)> = Vec::new();
+    let mut height = 0.;
+    let mut water_depth = 0;
+
+    for dz in 0..per_pixel {
+        for dx in 0..per_pixel {
+            // The game keeps to the chunk of the corner for every column.
+            let (cx, cz) = (
+                (x + dx).rem_euclid(16) as usize,
+                (z + dz).rem_euclid(16) as usize,
+            );
+            let mut y = chunk.surface_height(cx, cz, HeightMode::Trust) + 1;
+
+            // Columns with nothing in them show bedrock.
+            let mut colour = STONE;
+            if y > y_min + 1 {
+                let mut block;
+                loop {
+                    y -= 1;
+                    block = chunk.block(cx, y, cz);
+                    colour = block_colour(block).unwrap_or(0);
+                    if colour != 0 || y <= y_min {
+                        break;
+                    }
+                }
+
+                if let Some(fluid) = block.filter(|&b| y > y_min && is_fluid(b)) {
+                    let mut below = y - 1;
+                    loop {
+                        let more = matches!(chunk.block(cx, below, cz), Some(b) if is_fluid(b));
+                        below -= 1;
+                        water_depth += 1;
+                        if below <= y_min || !more {
+                            break;
+                        }
+                    }
+
+                    colour = if fluid.name() == "minecraft:lava" {
+                        FIRE
+                    } else {
+                        WATER
+                    };
+                }
+            }
+
+            height += y as f64 / (per_pixel * per_pixel) as f64;
+            match counts.iter_mut().find(|(c, _)| *c == colour) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((colour, 1)),
+            }
+        }
+    }
+
+    let mut colour = 0;
+    let mut most = 0;
+    for (c, count) in counts {
+        if count > most {
+            colour = c;
+            most = count;
+        }
+    }
+
+    Some(Sample {
+        colour,
+        height,
+        water_depth,
+    })
+}
+
+/// Whether a block holds water or lava.
+fn is_fluid(block: &Block) -> bool {
+    block.is_waterlogged()
+        || matches!(
+            block.name(),
+            "minecraft:water"
+                | "minecraft:lava"
+                | "minecraft:bubble_column"
+                | "minecraft:kelp"
+                | "minecraft:kelp_plant"
+                | "minecraft:seagrass"
+                | "minecraft:tall_seagrass"
+        )
+}
+
+/// Load a `map_N.dat` file.
+pub fn load_map_data(path: impl AsRef<Path>) -> Result<MapData> {
+    load_saved_data(path)
+}
+
+/// Read the contents of a `map_N.dat` file. The data should still be gzip
+/// compressed, as it is on disk.
+pub fn read_map_data(reader: impl Read) -> Result<MapData> {
+    read_saved_data(reader)
+}
+
+/// The colour of a map colour ID. The lower two bits pick a shade of one of
+/// the base colours, and IDs 0 to 3 are transparent. IDs past the colours
+/// Minecraft knows about are also transparent.
+pub fn map_colour(id: u8) -> Rgba {
+    let base = (id / 4) as usize;
+    let shade = match id % 4 {
+        0 => 180,
+        1 => 220,
+        2 => 255,
+        _ => 135,
+    };
+
+    match MAP_BASE_COLOURS.get(base) {
+        Some(&rgb) if base > 0 => {
+            let channel = |shift: u32| (((rgb >> shift) & 0xff) * shade / 255) as u8;
+            [channel(16), channel(8), channel(0), 255]
+        }
+        _ => [0, 0, 0, 0],
+    }
+}
+
+/// The base colours of maps, in the order of their IDs. Index 0 is
+/// unexplored.
+const MAP_BASE_COLOURS: [u32; 62] = [
+    0x000000, // none
+    0x7fb238, // grass
+    0xf7e9a3, // sand
+    0xc7c7c7, // wool
+    0xff0000, // fire
+    0xa0a0ff, // ice
+    0xa7a7a7, // metal
+    0x007c00, // plant
+    0xffffff, // snow
+    0xa4a8b8, // clay
+    0x976d4d, // dirt
+    0x707070, // stone
+    0x4040ff, // water
+    0x8f7748, // wood
+    0xfffcf5, // quartz
+    0xd87f33, // orange
+    0xb24cd8, // magenta
+    0x6699d8, // light blue
+    0xe5e533, // yellow
+    0x7fcc19, // lime
+    0xf27fa5, // pink
+    0x4c4c4c, // gray
+    0x999999, // light gray
+    0x4c7f99, // cyan
+    0x7f3fb2, // purple
+    0x334cb2, // blue
+    0x664c33, // brown
+    0x667f33, // green
+    0x993333, // red
+    0x191919, // black
+    0xfaee4d, // gold
+    0x5cdbd5, // diamond
+    0x4a80ff, // lapis
+    0x00d93a, // emerald
+    0x815631, // podzol
+    0x700200, // nether
+    0xd1b1a1, // white terracotta
+    0x9f5224, // orange terracotta
+    0x95576c, // magenta terracotta
+    0x706c8a, // light blue terracotta
+    0xba8524, // yellow terracotta
+    0x677535, // lime terracotta
+    0xa04d4e, // pink terracotta
+    0x392923, // gray terracotta
+    0x876b62, // light gray terracotta
+    0x575c5c, // cyan terracotta
+    0x7a4958, // purple terracotta
+    0x4c3e5c, // blue terracotta
+    0x4c3223, // brown terracotta
+    0x4c522a, // green terracotta
+    0x8e3c2e, // red terracotta
+    0x251610, // black terracotta
+    0xbd3031, // crimson nylium
+    0x943f61, // crimson stem
+    0x5c191d, // crimson hyphae
+    0x167e86, // warped nylium
+    0x3a8e8c, // warped stem
+    0x562c3e, // warped hyphae
+    0x14b485, // warped wart block
+    0x646464, // deepslate
+    0xd8af93, // raw iron
+    0x7fa796, // glow lichen
+];
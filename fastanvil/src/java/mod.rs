@@ -1,21 +1,56 @@
-use std::{cell::RefCell, convert::TryFrom, ops::Range};
-
-use fastnbt::{IntArray, LongArray};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    convert::TryFrom,
+    io::{Read, Seek},
+    ops::Range,
+};
+
+use bit_field::BitField;
+use fastnbt::{ByteArray, IntArray, LongArray};
 use lazy_static::lazy_static;
 
 use serde::Deserialize;
 
-use crate::{expand_heightmap, Chunk, HeightMode};
+use crate::{heightmap_value, Chunk, ChunkStatus, HeightMode, RegionBuffer};
 
 use super::biome::Biome;
 
 mod block;
+mod block_colour;
+mod block_entity;
 mod blockstates;
+mod entity;
+mod item;
+mod legacy;
+mod level;
+mod map_item;
+mod network;
+mod player;
+mod poi;
+mod remap;
+mod saved_data;
 mod section_tower;
+mod structures;
+mod versioned;
 
 pub use block::*;
+pub use block_colour::*;
+pub use block_entity::*;
 pub use blockstates::*;
+pub use entity::*;
+pub use item::*;
+pub use legacy::*;
+pub use level::*;
+pub use map_item::*;
+pub use network::*;
+pub use player::*;
+pub use poi::*;
+pub use remap::*;
+pub use saved_data::*;
 pub use section_tower::*;
+pub use structures::*;
+pub use versioned::*;
 
 lazy_static! {
     pub static ref AIR: Block = Block {
@@ -33,28 +68,126 @@ lazy_static! {
 }
 
 /// A Minecraft chunk.
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
+///
+/// Chunks from 1.18 onwards no longer have a `Level` tag, with its contents
+/// moved to the root of the chunk instead. These are converted when
+/// deserializing, so `level` is present for every version.
+#[derive(Debug)]
 pub struct JavaChunk {
     pub data_version: i32,
     pub level: Level,
 }
 
+impl<'de> Deserialize<'de> for JavaChunk {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let raw: JavaChunkRaw = Deserialize::deserialize(deserializer)?;
+
+        let level = match raw.level {
+            Some(level) => level,
+            None => {
+                let missing = |field| serde::de::Error::missing_field(field);
+
+                // Sections without block states in 1.18 only hold light data,
+                // and can be above or below the world.
+                let sections = raw.sections.map(|sections| {
+                    let sections = sections
+                        .into_iter()
+                        .filter(|s| !s.palette.is_empty())
+                        .collect::<Vec<_>>();
+                    SectionTower::from(sections)
+                });
+
+                let status = raw.status.ok_or_else(|| missing("Status"))?;
+
+                Level {
+                    x_pos: raw.x_pos.ok_or_else(|| missing("xPos"))?,
+                    z_pos: raw.z_pos.ok_or_else(|| missing("zPos"))?,
+                    biomes: None,
+                    sections,
+                    heightmaps: raw.heightmaps,
+                    tile_entities: raw.block_entities,
+                    // Statuses gained a namespace in 1.20.
+                    status: status.trim_start_matches("minecraft:").to_owned(),
+                    inhabited_time: raw.inhabited_time,
+                    lazy_heightmap: Default::default(),
+                }
+            }
+        };
+
+        Ok(Self {
+            data_version: raw.data_version,
+            level,
+        })
+    }
+}
+
+/// The layout of a chunk on disk. Before 1.18 everything is under `Level`,
+/// after it is at the root and some tags were renamed.
+#[derive(Deserialize)]
+struct JavaChunkRaw {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+
+    #[serde(rename = "Level")]
+    level: Option<Level>,
+
+    #[serde(rename = "xPos")]
+    x_pos: Option<i32>,
+
+    #[serde(rename = "zPos")]
+    z_pos: Option<i32>,
+
+    #[serde(rename = "Status")]
+    status: Option<String>,
+
+    #[serde(rename = "InhabitedTime")]
+    inhabited_time: Option<i64>,
+
+    #[serde(rename = "Heightmaps")]
+    heightmaps: Option<Heightmaps>,
+
+    sections: Option<Vec<Section>>,
+
+    #[serde(default)]
+    block_entities: Vec<BlockEntity>,
+}
+
 impl Chunk for JavaChunk {
-    fn status(&self) -> String {
-        self.level.status.to_owned()
+    fn status(&self) -> ChunkStatus {
+        ChunkStatus::from_name(&self.level.status)
+    }
+
+    fn inhabited_time(&self) -> Option<i64> {
+        self.level.inhabited_time
     }
 
     fn surface_height(&self, x: usize, z: usize, mode: HeightMode) -> isize {
-        if self.level.lazy_heightmap.borrow().is_none() {
-            self.recalculate_heightmap(mode);
-        }
+        self.cached_heightmap(mode)[z * 16 + x] as isize
+    }
 
-        self.level.lazy_heightmap.borrow().unwrap()[z * 16 + x] as isize
+    fn surface_heights(&self, mode: HeightMode) -> [[isize; 16]; 16] {
+        let map = self.cached_heightmap(mode);
+        let mut heights = [[0; 16]; 16];
+        for (i, h) in map.iter().enumerate() {
+            heights[i / 16][i % 16] = *h as isize;
+        }
+        heights
     }
 
     fn biome(&self, x: usize, y: isize, z: usize) -> Option<Biome> {
-        let biomes = self.level.biomes.as_ref()?;
+        let biomes = match self.level.biomes.as_ref() {
+            Some(biomes) => biomes,
+            None => {
+                // Since 1.18 biomes are stored in each section.
+                let sec = self.level.sections.as_ref()?.get_section_for_y(y)?;
+                let sec_y = (y - sec.y as isize * 16) as usize;
+                let name = sec.biomes.as_ref()?.biome(x, sec_y, z)?;
+                return Some(Biome::from_name(name));
+            }
+        };
 
         // After 1.15 Each biome in i32, biomes split into 4-wide cubes, so
         // 4x4x4 per section.
@@ -84,10 +217,11 @@ impl Chunk for JavaChunk {
     fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
         let sec = self.level.sections.as_ref()?.get_section_for_y(y)?;
 
-        // If a section is entirely air, then the block states are missing
-        // entirely, presumably to save space.
+        // If a section is entirely one block, then the block states are
+        // missing entirely, presumably to save space. Before 1.18 this was
+        // only done for air, and the palette is empty.
         match &sec.block_states {
-            None => Some(&AIR),
+            None => Some(sec.palette.first().unwrap_or(&AIR)),
             Some(blockstates) => {
                 let sec_y = (y - sec.y as isize * 16) as usize;
                 let pal_index = blockstates.state(x, sec_y, z, sec.palette.len());
@@ -96,6 +230,20 @@ impl Chunk for JavaChunk {
         }
     }
 
+    fn block_entities(&self) -> &[BlockEntity] {
+        &self.level.tile_entities
+    }
+
+    fn block_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        let sec = self.level.sections.as_ref()?.get_section_for_y(y)?;
+        sec.block_light(x, (y - sec.y as isize * 16) as usize, z)
+    }
+
+    fn sky_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        let sec = self.level.sections.as_ref()?.get_section_for_y(y)?;
+        sec.sky_light(x, (y - sec.y as isize * 16) as usize, z)
+    }
+
     fn y_range(&self) -> std::ops::Range<isize> {
         match &self.level.sections {
             Some(sections) => Range {
@@ -124,92 +272,423 @@ pub struct Level {
 
     pub heightmaps: Option<Heightmaps>,
 
+    #[serde(default)]
+    pub tile_entities: Vec<BlockEntity>,
+
     // Status of the chunk. Typically anything except 'full' means the chunk
     // hasn't been fully generated yet. We use this to skip chunks on map edges
     // that haven't been fully generated yet.
     pub status: String,
 
+    /// The total time in ticks players have spent in the chunk.
+    pub inhabited_time: Option<i64>,
+
+    /// Surface heights indexed by `z * 16 + x`, and the mode they were
+    /// worked out with.
     #[serde(skip)]
-    lazy_heightmap: RefCell<Option<[i16; 256]>>,
+    lazy_heightmap: RefCell<Option<(HeightMode, [i16; 256])>>,
 }
 
-/// Various heightmaps kept up to date by Minecraft.
+/// Various heightmaps kept up to date by Minecraft. These are stored packed,
+/// use [`JavaChunk::height_at`] or [`JavaChunk::heightmap`] to decode them.
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Heightmaps {
     pub motion_blocking: Option<LongArray>,
-    //pub motion_blocking_no_leaves: Option<Heightmap>,
-    //pub ocean_floor: Option<Heightmap>,
-    //pub world_surface: Option<Heightmap>,
+    pub motion_blocking_no_leaves: Option<LongArray>,
+    pub ocean_floor: Option<LongArray>,
+    pub ocean_floor_wg: Option<LongArray>,
+    pub world_surface: Option<LongArray>,
+    pub world_surface_wg: Option<LongArray>,
+}
+
+impl Heightmaps {
+    /// Get the packed data of a particular heightmap.
+    pub fn get(&self, kind: HeightmapKind) -> Option<&LongArray> {
+        match kind {
+            HeightmapKind::MotionBlocking => self.motion_blocking.as_ref(),
+            HeightmapKind::MotionBlockingNoLeaves => self.motion_blocking_no_leaves.as_ref(),
+            HeightmapKind::OceanFloor => self.ocean_floor.as_ref(),
+            HeightmapKind::OceanFloorWg => self.ocean_floor_wg.as_ref(),
+            HeightmapKind::WorldSurface => self.world_surface.as_ref(),
+            HeightmapKind::WorldSurfaceWg => self.world_surface_wg.as_ref(),
+        }
+    }
+}
+
+/// The kinds of heightmap Minecraft keeps. Each records, for every column of
+/// a chunk, the Y of the block above the highest block matching some
+/// criteria.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeightmapKind {
+    /// Highest block that blocks motion or contains a fluid.
+    MotionBlocking,
+    /// Like `MotionBlocking`, but ignoring leaves.
+    MotionBlockingNoLeaves,
+    /// Highest block that blocks motion.
+    OceanFloor,
+    /// `OceanFloor` used during world generation, only in chunks that are not
+    /// fully generated.
+    OceanFloorWg,
+    /// Highest non-air block.
+    WorldSurface,
+    /// `WorldSurface` used during world generation, only in chunks that are
+    /// not fully generated.
+    WorldSurfaceWg,
 }
 
 /// A vertical section of a chunk (ie a 16x16x16 block cube)
-#[derive(Deserialize, Debug)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug)]
 pub struct Section {
     pub y: i8,
 
     pub block_states: Option<Blockstates>,
 
-    #[serde(default)]
     pub palette: Vec<Block>,
+
+    /// The biomes of the section, only present from 1.18 onwards.
+    pub biomes: Option<SectionBiomes>,
+
+    /// Light from blocks such as torches, 4 bits per block indexed by
+    /// `y * 256 + z * 16 + x`. Missing if the chunk has not been lit.
+    pub block_light: Option<ByteArray>,
+
+    /// Light from the sky, stored the same as `block_light`. Missing if the
+    /// chunk has not been lit, or in dimensions without a sky.
+    pub sky_light: Option<ByteArray>,
+}
+
+impl<'de> Deserialize<'de> for Section {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct SectionRaw {
+            #[serde(rename = "Y")]
+            y: i8,
+
+            #[serde(rename = "BlockStates")]
+            block_states: Option<Blockstates>,
+
+            #[serde(rename = "Palette", default)]
+            palette: Vec<Block>,
+
+            // 1.18 moved the block states and palette into one compound.
+            #[serde(rename = "block_states")]
+            paletted: Option<PalettedBlocks>,
+
+            biomes: Option<SectionBiomes>,
+
+            #[serde(rename = "BlockLight")]
+            block_light: Option<ByteArray>,
+
+            #[serde(rename = "SkyLight")]
+            sky_light: Option<ByteArray>,
+        }
+
+        #[derive(Deserialize)]
+        struct PalettedBlocks {
+            palette: Vec<Block>,
+            data: Option<Blockstates>,
+        }
+
+        let raw: SectionRaw = Deserialize::deserialize(deserializer)?;
+
+        let (block_states, palette) = match raw.paletted {
+            Some(p) => (p.data, p.palette),
+            None => (raw.block_states, raw.palette),
+        };
+
+        Ok(Self {
+            y: raw.y,
+            block_states,
+            palette,
+            biomes: raw.biomes,
+            block_light: raw.block_light,
+            sky_light: raw.sky_light,
+        })
+    }
+}
+
+impl Section {
+    /// The blocks used in the section. Block states index into this.
+    pub fn palette(&self) -> &[Block] {
+        &self.palette
+    }
+
+    /// Iterate over the palette index of every block of the section, in
+    /// `y * 256 + z * 16 + x` order. Sections that are entirely one block
+    /// give 4096 zeros.
+    pub fn states_iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let pal_len = self.palette.len();
+        (0..16 * 16 * 16).map(move |i| match &self.block_states {
+            Some(states) => states.state(i % 16, i / 256, (i / 16) % 16, pal_len),
+            None => 0,
+        })
+    }
+
+    /// Iterate over every block of the section, in the same order as
+    /// [`states_iter`](Self::states_iter). Sections from before 1.18 with an
+    /// empty palette are all air.
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> + '_ {
+        self.states_iter()
+            .map(move |i| self.palette.get(i).unwrap_or(&AIR))
+    }
+
+    /// Count how many blocks of the section use each entry of the palette,
+    /// so the result lines up with `palette`. Sections that are entirely one
+    /// block have no block states, and count 4096 of the first entry.
+    pub fn palette_histogram(&self) -> Vec<usize> {
+        match &self.block_states {
+            Some(states) => states.histogram(self.palette.len()),
+            None => {
+                let mut counts = vec![0; self.palette.len()];
+                if let Some(first) = counts.first_mut() {
+                    *first = 16 * 16 * 16;
+                }
+                counts
+            }
+        }
+    }
+
+    /// Count the blocks of the section by name, eg `minecraft:stone`.
+    /// Different states of the same block are counted together. Sections
+    /// from before 1.18 with an empty palette are all air, and count nothing.
+    pub fn block_counts(&self) -> HashMap<&str, usize> {
+        let mut counts = HashMap::new();
+        add_counts(&mut counts, self);
+        counts
+    }
+
+    /// Get the block light at the given section-relative coordinates, if
+    /// the section has any.
+    pub fn block_light(&self, x: usize, sec_y: usize, z: usize) -> Option<u8> {
+        nibble(self.block_light.as_ref()?, sec_y * 256 + z * 16 + x)
+    }
+
+    /// Get the sky light at the given section-relative coordinates, if the
+    /// section has any.
+    pub fn sky_light(&self, x: usize, sec_y: usize, z: usize) -> Option<u8> {
+        nibble(self.sky_light.as_ref()?, sec_y * 256 + z * 16 + x)
+    }
+}
+
+/// Get the 4 bit value at `index` of an array of nibbles, as used for light
+/// and legacy block data. Even indices are in the low half of each byte.
+pub(crate) fn nibble(data: &[i8], index: usize) -> Option<u8> {
+    let byte = *data.get(index / 2)? as u8;
+    Some((byte >> (index % 2 * 4)) & 0xf)
+}
+
+fn add_counts<'a>(counts: &mut HashMap<&'a str, usize>, section: &'a Section) {
+    for (block, count) in section.palette.iter().zip(section.palette_histogram()) {
+        if count > 0 {
+            *counts.entry(block.name()).or_insert(0) += count;
+        }
+    }
+}
+
+/// Count the blocks of every chunk in a region by name, adding up
+/// [`JavaChunk::block_counts`] for each.
+///
+/// ```no_run
+/// # use fastanvil::{region_block_counts, RegionBuffer};
+/// let file = std::fs::File::open("r.0.0.mca")?;
+/// let counts = region_block_counts(&RegionBuffer::new(file))?;
+/// println!("{} diamond ore", counts.get("minecraft:diamond_ore").unwrap_or(&0));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn region_block_counts<S: Seek + Read>(
+    region: &RegionBuffer<S>,
+) -> crate::Result<HashMap<String, usize>> {
+    let mut counts = HashMap::new();
+
+    for chunk in region.iter() {
+        let (_, _, data) = chunk?;
+        let chunk: JavaChunk = fastnbt::de::from_bytes(&data)?;
+
+        for (name, count) in chunk.block_counts() {
+            *counts.entry(name.to_owned()).or_insert(0) += count;
+        }
+    }
+
+    Ok(counts)
+}
+
+/// The biomes of a section since 1.18. Biomes are stored for 4x4x4 cells as
+/// indices into a palette of biome names.
+#[derive(Deserialize, Debug)]
+pub struct SectionBiomes {
+    pub palette: Vec<String>,
+
+    /// Packed palette indices, indexed by `y * 16 + z * 4 + x` in cells.
+    /// Missing if the palette has a single biome.
+    pub data: Option<LongArray>,
+}
+
+impl SectionBiomes {
+    /// Get the name of the biome at the given section-relative coordinates,
+    /// eg `minecraft:plains`.
+    pub fn biome(&self, x: usize, sec_y: usize, z: usize) -> Option<&str> {
+        let index = match &self.data {
+            None => 0,
+            Some(data) => {
+                // Bits needed to store an index in to the palette, with each
+                // long padded rather than spilling into the next.
+                let bits = (usize::BITS - (self.palette.len() - 1).leading_zeros()) as usize;
+                let per_long = 64 / bits.max(1);
+
+                let i = (sec_y / 4) * 16 + (z / 4) * 4 + (x / 4);
+                let long = *data.get(i / per_long)? as u64;
+                long.get_bits((i % per_long) * bits..(i % per_long + 1) * bits) as usize
+            }
+        };
+
+        self.palette.get(index).map(String::as_str)
+    }
 }
 
 impl JavaChunk {
-    pub fn recalculate_heightmap(&self, mode: HeightMode) {
-        // TODO: Find top section and start there, pointless checking 320 down
-        // if its a 1.16 chunk.
+    /// Iterate over the sections of the chunk from the bottom up, for working
+    /// with the palette and block states directly.
+    ///
+    /// ```no_run
+    /// # use fastanvil::JavaChunk;
+    /// # let data = vec![];
+    /// let chunk: JavaChunk = fastnbt::de::from_bytes(&data)?;
+    /// for section in chunk.sections() {
+    ///     let ores = section
+    ///         .states_iter()
+    ///         .filter(|&i| section.palette()[i].name() == "minecraft:diamond_ore")
+    ///         .count();
+    ///     println!("{} diamond ore in section {}", ores, section.y);
+    /// }
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn sections(&self) -> impl Iterator<Item = &Section> + '_ {
+        self.level
+            .sections
+            .iter()
+            .flat_map(|sections| sections.iter())
+    }
+
+    /// Count the blocks of the chunk by name, eg `minecraft:stone`. This
+    /// works from the palette indices of each section, which is much faster
+    /// than going through [`Chunk::block`] for every position. Sections that
+    /// are not saved are not counted, so the air above the surface is
+    /// usually missing.
+    pub fn block_counts(&self) -> HashMap<&str, usize> {
+        let mut counts = HashMap::new();
+        for section in self.sections() {
+            add_counts(&mut counts, section);
+        }
+
+        counts
+    }
+
+    /// Get the height of a heightmap at the given column, if the chunk has
+    /// that heightmap. Only the requested value is decoded. Will panic if
+    /// given x/z coordinates outside of 0..16.
+    pub fn height_at(&self, kind: HeightmapKind, x: usize, z: usize) -> Option<isize> {
+        assert!(x < 16 && z < 16);
+
+        let data = self.level.heightmaps.as_ref()?.get(kind)?;
+        let range = self.y_range();
+        let world_height = (range.end - range.start) as usize;
+
+        let height = heightmap_value(data, z * 16 + x, world_height)?;
+        Some(height as isize + range.start)
+    }
 
+    /// Decode a whole heightmap, if the chunk has it. Values are indexed by
+    /// `z * 16 + x`.
+    pub fn heightmap(&self, kind: HeightmapKind) -> Option<Vec<isize>> {
+        (0..256)
+            .map(|i| self.height_at(kind, i % 16, i / 16))
+            .collect()
+    }
+
+    /// Work out the surface height of every column for the given mode, and
+    /// cache it for [`Chunk::surface_height`] and [`Chunk::surface_heights`].
+    /// This happens automatically the first time either is called with a
+    /// mode, so is only needed to pay the cost up front.
+    pub fn recalculate_heightmap(&self, mode: HeightMode) {
         let mut map = [0; 256];
 
         match mode {
             HeightMode::Trust => {
-                let updated = self
-                    .level
-                    .heightmaps
-                    .as_ref()
-                    .and_then(|hm| hm.motion_blocking.as_ref())
-                    .map(|hm| {
-                        // unwrap, if heightmaps exists, sections should... 🤞
-                        let y_min = self.level.sections.as_ref().unwrap().y_min();
-                        expand_heightmap(hm.as_slice(), y_min, self.data_version)
-                    })
-                    .map(|hm| map.copy_from_slice(hm.as_slice()))
-                    .is_some();
-
-                if updated {
-                    self.level.lazy_heightmap.replace(Some(map));
+                if let Some(heights) = self.heightmap(HeightmapKind::MotionBlocking) {
+                    for (h, height) in map.iter_mut().zip(heights) {
+                        *h = height as i16;
+                    }
+
+                    self.level.lazy_heightmap.replace(Some((mode, map)));
                     return;
                 }
             }
             HeightMode::Calculate => {} // fall through to calc mode
         }
 
-        let y_range = self.y_range();
-        let y_end = y_range.end;
-
-        for z in 0..16 {
-            for x in 0..16 {
-                // start at top until we hit a non-air block.
-                for i in y_range.clone() {
-                    let y = y_end - i;
-                    let block = self.block(x, y - 1, z);
+        // Work down through the sections from the top, rather than block by
+        // block, so each section's palette only has to be checked once.
+        // Heights can be zero or negative, so which columns are done is kept
+        // separately. Columns with no blocks at all are left at zero.
+        let mut found = [false; 256];
+        let mut remaining = 256;
+        let sections = self.sections().collect::<Vec<_>>();
+
+        for sec in sections.into_iter().rev() {
+            let solid = sec
+                .palette
+                .iter()
+                .map(|b| !["minecraft:air", "minecraft:cave_air"].contains(&b.name()))
+                .collect::<Vec<_>>();
+
+            if !solid.contains(&true) {
+                continue;
+            }
 
-                    if block.is_none() {
-                        continue;
-                    }
+            for (i, h) in map.iter_mut().enumerate() {
+                if found[i] {
+                    continue;
+                }
 
-                    if !["minecraft:air", "minecraft:cave_air"]
-                        .as_ref()
-                        .contains(&block.unwrap().name())
-                    {
-                        map[z * 16 + x] = y as i16;
-                        break;
-                    }
+                let (x, z) = (i % 16, i / 16);
+                let top = (0..16).rev().find(|&sec_y| {
+                    let state = match &sec.block_states {
+                        Some(states) => states.state(x, sec_y, z, sec.palette.len()),
+                        None => 0,
+                    };
+                    solid.get(state).copied().unwrap_or(false)
+                });
+
+                if let Some(sec_y) = top {
+                    *h = (sec.y as isize * 16 + sec_y as isize + 1) as i16;
+                    found[i] = true;
+                    remaining -= 1;
                 }
             }
+
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        self.level.lazy_heightmap.replace(Some((mode, map)));
+    }
+
+    fn cached_heightmap(&self, mode: HeightMode) -> [i16; 256] {
+        match *self.level.lazy_heightmap.borrow() {
+            Some((cached, map)) if cached == mode => return map,
+            _ => {}
         }
 
-        self.level.lazy_heightmap.replace(Some(map));
+        self.recalculate_heightmap(mode);
+        self.level
+            .lazy_heightmap
+            .borrow()
+            .expect("just calculated")
+            .1
     }
 }
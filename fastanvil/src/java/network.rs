@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use fastnbt::LongArray;
+
+use crate::{
+    Block, Blockstates, Error, PackedBits, PackingFormat, Result, Section, SectionBiomes,
+    SectionTower,
+};
+
+/// The ids the game gives block states and biomes, which network data uses in
+/// place of names. Both are indexed by id.
+///
+/// Block state ids can be found by running the game's data generator with
+/// `--reports`, and biome ids are sent by the server in its registry data.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkRegistry {
+    /// Block states by id.
+    pub block_states: Vec<Block>,
+
+    /// Biome names by id, eg `minecraft:plains`.
+    pub biomes: Vec<String>,
+}
+
+/// How paletted data is sent, which changed in 1.21.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetworkFormat {
+    /// The number of longs is sent before each packed array, up to 1.21.4.
+    #[default]
+    LengthPrefixed,
+    /// The number of longs is left out since it follows from the number of
+    /// bits per entry, from 1.21.5.
+    Implied,
+}
+
+/// Parse the sections in the chunk data of a `LevelChunkWithLight` packet,
+/// from 1.18 onwards, into the same [`Section`]s used for chunks on disk.
+///
+/// `data` is the section data only, the byte array that comes after the
+/// heightmaps in the packet. Sections are read until it runs out, with the
+/// first at `min_section_y`, the bottom of the dimension divided by 16 (`-4`
+/// for the overworld).
+///
+/// ```no_run
+/// # use fastanvil::{read_network_sections, NetworkFormat, NetworkRegistry};
+/// # let registry = NetworkRegistry::default();
+/// # let data = vec![];
+/// let sections = read_network_sections(&data, -4, &registry, NetworkFormat::LengthPrefixed)?;
+/// for section in sections.iter() {
+///     println!("{:?}", section.block_counts());
+/// }
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn read_network_sections(
+    data: &[u8],
+    min_section_y: i8,
+    registry: &NetworkRegistry,
+    format: NetworkFormat,
+) -> Result<SectionTower> {
+    let mut input = Input { data, format };
+    let mut sections = vec![];
+
+    while !input.data.is_empty() {
+        let y = i8::try_from(min_section_y as isize + sections.len() as isize)
+            .map_err(|_| Error::Network("too many sections".to_owned()))?;
+
+        // The number of non-air blocks, which we can work out ourselves.
+        input.i16()?;
+
+        let blocks = input.paletted(16 * 16 * 16, 8)?;
+        let biomes = input.paletted(4 * 4 * 4, 3)?;
+
+        sections.push(Section {
+            y,
+            block_states: blocks.indices.as_ref().map(|indices| {
+                Blockstates::from(PackedBits::pack_blockstates(indices, blocks.ids.len()))
+            }),
+            palette: lookup(&blocks.ids, &registry.block_states, "block state")?,
+            biomes: Some(SectionBiomes {
+                palette: lookup(&biomes.ids, &registry.biomes, "biome")?,
+                data: biomes.indices.as_ref().map(|indices| {
+                    let bits = (usize::BITS - (biomes.ids.len() - 1).leading_zeros()) as usize;
+                    PackedBits::pack(indices, bits, PackingFormat::Padded).0
+                }),
+            }),
+            // Light is sent in its own packet.
+            block_light: None,
+            sky_light: None,
+        });
+    }
+
+    Ok(SectionTower::from(sections))
+}
+
+fn lookup<T: Clone>(ids: &[u32], registry: &[T], what: &str) -> Result<Vec<T>> {
+    ids.iter()
+        .map(|&id| {
+            registry
+                .get(id as usize)
+                .cloned()
+                .ok_or_else(|| Error::Network(format!("unknown {} id {}", what, id)))
+        })
+        .collect()
+}
+
+/// A paletted container, with the palette as registry ids. Indices are left
+/// out if the palette has a single entry.
+struct Paletted {
+    ids: Vec<u32>,
+    indices: Option<Vec<u16>>,
+}
+
+struct Input<'a> {
+    data: &'a [u8],
+    format: NetworkFormat,
+}
+
+impl<'a> Input<'a> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        if self.data.len() < N {
+            return Err(Error::Network("unexpected end of data".to_owned()));
+        }
+        let (bytes, rest) = self.data.split_at(N);
+        self.data = rest;
+        Ok(bytes.try_into().expect("split at N"))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.bytes()?))
+    }
+
+    fn varint(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for i in 0..5 {
+            let b = self.u8()?;
+            value |= ((b & 0x7f) as u32) << (7 * i);
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Network("varint too long".to_owned()))
+    }
+
+    fn longs(&mut self, count: usize, bits: usize) -> Result<PackedBits> {
+        let len = match self.format {
+            NetworkFormat::LengthPrefixed => self.varint()? as usize,
+            NetworkFormat::Implied => PackingFormat::Padded.packed_len(count, bits),
+        };
+
+        // Don't trust the length to allocate for, each long is 8 bytes.
+        if len > self.data.len() / 8 {
+            return Err(Error::Network("unexpected end of data".to_owned()));
+        }
+
+        let longs = (0..len).map(|_| self.i64()).collect::<Result<Vec<_>>>()?;
+        Ok(PackedBits(LongArray::new(longs)))
+    }
+
+    /// Read a container of `count` entries. Up to `max_indirect` bits per
+    /// entry the values index into a palette, above it they are registry ids.
+    fn paletted(&mut self, count: usize, max_indirect: u8) -> Result<Paletted> {
+        let mut paletted = self.paletted_raw(count, max_indirect)?;
+
+        // Sections on disk leave the indices out if there's nothing to index.
+        if paletted.ids.len() <= 1 {
+            paletted.indices = None;
+        }
+        Ok(paletted)
+    }
+
+    fn paletted_raw(&mut self, count: usize, max_indirect: u8) -> Result<Paletted> {
+        let bits = self.u8()?;
+
+        if bits == 0 {
+            let id = self.varint()?;
+            if self.format == NetworkFormat::LengthPrefixed {
+                self.longs(0, 1)?;
+            }
+            return Ok(Paletted {
+                ids: vec![id],
+                indices: None,
+            });
+        }
+
+        if bits > 16 {
+            return Err(Error::Network(format!("{} bits per entry", bits)));
+        }
+
+        if bits <= max_indirect {
+            let len = self.varint()? as usize;
+            if len > self.data.len() {
+                return Err(Error::Network("unexpected end of data".to_owned()));
+            }
+            let ids = (0..len)
+                .map(|_| self.varint())
+                .collect::<Result<Vec<_>>>()?;
+
+            // Block palettes always use at least 4 bits.
+            let bits = if max_indirect == 8 { bits.max(4) } else { bits } as usize;
+            let indices = self
+                .longs(count, bits)?
+                .unpack(bits, PackingFormat::Padded, count)
+                .ok_or_else(|| Error::Network(format!("{} bits per entry", bits)))?;
+
+            if indices.iter().any(|&i| i as usize >= ids.len()) {
+                return Err(Error::Network("palette index out of range".to_owned()));
+            }
+
+            Ok(Paletted {
+                ids,
+                indices: Some(indices),
+            })
+        } else {
+            // Values are registry ids, so build a palette of the ones used.
+            let bits = bits as usize;
+            let values = self
+                .longs(count, bits)?
+                .unpack(bits, PackingFormat::Padded, count)
+                .ok_or_else(|| Error::Network(format!("{} bits per entry", bits)))?;
+
+            let mut ids = vec![];
+            let mut lookup = HashMap::new();
+            let indices = values
+                .into_iter()
+                .map(|id| {
+                    *lookup.entry(id).or_insert_with(|| {
+                        ids.push(id as u32);
+                        (ids.len() - 1) as u16
+                    })
+                })
+                .collect();
+
+            Ok(Paletted {
+                ids,
+                indices: Some(indices),
+            })
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use fastnbt::{IntArray, Value};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use super::entity::uuid_from_ints;
+use crate::{DimensionId, ItemStack, Result};
+
+/// The contents of a player's data file, found in the `playerdata` folder of
+/// a world and named by the player's UUID. The fields most tools need are
+/// available directly, everything else is kept in `other`.
+///
+/// Use [`PlayerFileLoader`] or [`read_player_data`] to load one.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlayerData {
+    #[serde(rename = "DataVersion")]
+    pub data_version: Option<i32>,
+
+    /// The position of the player as x, y, z.
+    #[serde(rename = "Pos")]
+    pub pos: [f64; 3],
+
+    /// The rotation of the player as yaw, pitch.
+    #[serde(rename = "Rotation", default)]
+    pub rotation: [f32; 2],
+
+    // A namespaced ID since 1.16, a number before.
+    #[serde(rename = "Dimension")]
+    dimension: Option<Value>,
+
+    /// The items in the player's inventory. In the inventory slots 0 to 8
+    /// are the hotbar, 9 to 35 the rest of the inventory, 100 to 103 armour
+    /// from boots to helmet, and -106 the offhand.
+    #[serde(rename = "Inventory", default)]
+    pub inventory: Vec<ItemStack>,
+
+    #[serde(rename = "EnderItems", default)]
+    pub ender_items: Vec<ItemStack>,
+
+    /// The selected hotbar slot, 0 to 8.
+    #[serde(rename = "SelectedItemSlot", default)]
+    pub selected_item_slot: i32,
+
+    #[serde(rename = "XpLevel", default)]
+    pub xp_level: i32,
+
+    /// Progress towards the next level, from 0 to 1.
+    #[serde(rename = "XpP", default)]
+    pub xp_progress: f32,
+
+    #[serde(rename = "XpTotal", default)]
+    pub xp_total: i32,
+
+    #[serde(rename = "Health", default)]
+    pub health: f32,
+
+    #[serde(rename = "foodLevel", default)]
+    pub food_level: i32,
+
+    /// The game mode of the player. 0 is survival, 1 creative, 2 adventure
+    /// and 3 spectator.
+    #[serde(rename = "playerGameType", default)]
+    pub game_type: i32,
+
+    #[serde(rename = "UUID")]
+    uuid: Option<IntArray>,
+
+    /// All other NBT of the player, such as abilities and effects.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl PlayerData {
+    /// The dimension the player is in. Worlds from before 1.16 only record
+    /// the overworld, nether and end.
+    pub fn dimension(&self) -> Option<DimensionId> {
+        match self.dimension.as_ref()? {
+            Value::String(id) => Some(DimensionId::from_id(id)),
+            Value::Int(0) => Some(DimensionId::Overworld),
+            Value::Int(-1) => Some(DimensionId::Nether),
+            Value::Int(1) => Some(DimensionId::End),
+            _ => None,
+        }
+    }
+
+    /// The UUID of the player, since 1.16.
+    pub fn uuid(&self) -> Option<u128> {
+        uuid_from_ints(self.uuid.as_ref()?)
+    }
+
+    /// The item in the given inventory slot, if there is one.
+    pub fn item_in_slot(&self, slot: i8) -> Option<&ItemStack> {
+        self.inventory.iter().find(|item| item.slot == Some(slot))
+    }
+
+    /// The item in the player's hand.
+    pub fn selected_item(&self) -> Option<&ItemStack> {
+        self.item_in_slot(self.selected_item_slot as i8)
+    }
+
+    /// The armour the player is wearing, from boots to helmet.
+    pub fn armor(&self) -> [Option<&ItemStack>; 4] {
+        [
+            self.item_in_slot(100),
+            self.item_in_slot(101),
+            self.item_in_slot(102),
+            self.item_in_slot(103),
+        ]
+    }
+}
+
+/// Loads players' data from the `playerdata` folder of a world.
+pub struct PlayerFileLoader {
+    world_dir: PathBuf,
+}
+
+impl PlayerFileLoader {
+    /// A loader for the players of the world in the folder `world_dir`.
+    pub fn new(world_dir: PathBuf) -> Self {
+        Self { world_dir }
+    }
+
+    /// The UUIDs of the players with data files, as they appear in the file
+    /// names, sorted.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut players = vec![];
+        for entry in fs::read_dir(self.world_dir.join("playerdata"))? {
+            let name = entry?.file_name();
+            if let Some(uuid) = name.to_str().and_then(|n| n.strip_suffix(".dat")) {
+                players.push(uuid.to_owned());
+            }
+        }
+
+        players.sort();
+        Ok(players)
+    }
+
+    /// The path of the data file of a player.
+    pub fn path(&self, uuid: &str) -> PathBuf {
+        self.world_dir
+            .join("playerdata")
+            .join(format!("{}.dat", uuid))
+    }
+
+    /// The path of the file of a player's advancements, which is JSON rather
+    /// than NBT.
+    pub fn advancements_path(&self, uuid: &str) -> PathBuf {
+        self.world_dir
+            .join("advancements")
+            .join(format!("{}.json", uuid))
+    }
+
+    /// The path of the file of a player's statistics, which is JSON rather
+    /// than NBT.
+    pub fn stats_path(&self, uuid: &str) -> PathBuf {
+        self.world_dir.join("stats").join(format!("{}.json", uuid))
+    }
+
+    /// Load the data of a player.
+    ///
+    /// ```no_run
+    /// # use fastanvil::PlayerFileLoader;
+    /// let loader = PlayerFileLoader::new("world".into());
+    /// for uuid in loader.list()? {
+    ///     let player = loader.load(&uuid)?;
+    ///     println!("{} is at {:?} with {} items", uuid, player.pos, player.inventory.len());
+    /// }
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn load(&self, uuid: &str) -> Result<PlayerData> {
+        load_player_data(self.path(uuid))
+    }
+}
+
+/// Load a player data file.
+pub fn load_player_data(path: impl AsRef<Path>) -> Result<PlayerData> {
+    let file = File::open(path)?;
+    read_player_data(BufReader::new(file))
+}
+
+/// Read the contents of a player data file. The data should still be gzip
+/// compressed, as it is on disk.
+pub fn read_player_data(reader: impl Read) -> Result<PlayerData> {
+    Ok(fastnbt::de::from_reader(GzDecoder::new(reader))?)
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use fastnbt::IntArray;
+use serde::Deserialize;
+
+/// The contents of a chunk in a point of interest region file, found in the
+/// `poi` folder of a world. Points of interest are blocks that villagers and
+/// other mobs look for, such as workstations, beds and nether portals.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct PoiChunk {
+    pub data_version: i32,
+
+    /// Sections of the chunk by their Y index, stored as strings such as
+    /// `"-4"`. Use [`PoiChunk::section`] to look one up by number.
+    #[serde(default)]
+    pub sections: HashMap<String, PoiSection>,
+}
+
+impl PoiChunk {
+    /// Get the section at the given section Y index, each covering 16 blocks
+    /// of height.
+    pub fn section(&self, y: isize) -> Option<&PoiSection> {
+        self.sections.get(&y.to_string())
+    }
+
+    /// Iterate over the records of every section in the chunk.
+    pub fn records(&self) -> impl Iterator<Item = &PoiRecord> {
+        self.sections.values().flat_map(|s| s.records.iter())
+    }
+}
+
+/// The points of interest in a 16x16x16 section of a chunk.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct PoiSection {
+    /// Whether the game considers the records up to date. If not, they are
+    /// recalculated when the chunk is next loaded.
+    pub valid: bool,
+
+    #[serde(default)]
+    pub records: Vec<PoiRecord>,
+}
+
+/// A single point of interest.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PoiRecord {
+    /// The type of point of interest, eg `minecraft:nether_portal` or
+    /// `minecraft:armorer`.
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    /// How many more mobs can claim this point of interest. For example a
+    /// bed that has been claimed by a villager has none.
+    pub free_tickets: i32,
+
+    pos: IntArray,
+}
+
+impl PoiRecord {
+    /// The block position of the point of interest as x, y, z.
+    pub fn pos(&self) -> Option<[i32; 3]> {
+        match self.pos.as_slice() {
+            [x, y, z] => Some([*x, *y, *z]),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref RENAMES: Vec<Rename> = parse_renames(include_str!("renames.txt"));
+}
+
+/// What kind of thing a [`Rename`] renames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenameKind {
+    Block,
+    Biome,
+}
+
+/// A block or biome renamed by a version of Minecraft.
+#[derive(Debug)]
+struct Rename {
+    data_version: i32,
+    kind: RenameKind,
+    old: String,
+    new: String,
+}
+
+fn parse_renames(table: &str) -> Vec<Rename> {
+    table
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let mut next = || parts.next().expect("malformed rename table");
+
+            let data_version = next().parse().expect("malformed rename table");
+            let kind = match next() {
+                "block" => RenameKind::Block,
+                "biome" => RenameKind::Biome,
+                kind => panic!("unknown kind in rename table: {}", kind),
+            };
+
+            Rename {
+                data_version,
+                kind,
+                old: next().to_owned(),
+                new: next().to_owned(),
+            }
+        })
+        .collect()
+}
+
+fn remap(kind: RenameKind, name: &str, from_version: i32, to_version: i32) -> &str {
+    let renames = RENAMES.iter().filter(|r| r.kind == kind);
+    let mut name = name;
+
+    if from_version <= to_version {
+        for rename in renames.filter(|r| r.data_version > from_version) {
+            if rename.data_version > to_version {
+                break;
+            }
+            if rename.old == name {
+                name = &rename.new;
+            }
+        }
+    } else {
+        let renames: Vec<_> = renames
+            .filter(|r| r.data_version > to_version && r.data_version <= from_version)
+            .collect();
+
+        for rename in renames.iter().rev() {
+            if rename.new == name {
+                // Several biomes can have been merged into one, pick the
+                // first listed for the version.
+                let first = renames
+                    .iter()
+                    .find(|r| r.data_version == rename.data_version && r.new == name)
+                    .unwrap_or(rename);
+                name = &first.old;
+            }
+        }
+    }
+
+    name
+}
+
+/// Get the name a block has in the version of Minecraft with DataVersion
+/// `to_version`, given its name in the version with `from_version`. Works in
+/// either direction, so can also give the old name of a block. Names must
+/// include their namespace, eg `minecraft:grass_path`. Blocks that were not
+/// renamed between the versions are returned as they are.
+///
+/// ```
+/// # use fastanvil::remap_block;
+/// assert_eq!("minecraft:dirt_path", remap_block("minecraft:grass_path", 2586, 2730));
+/// assert_eq!("minecraft:grass_path", remap_block("minecraft:dirt_path", 2730, 2586));
+/// assert_eq!("minecraft:stone", remap_block("minecraft:stone", 1451, 3700));
+/// ```
+///
+/// Only renames are covered, blocks that were split or merged with others in
+/// ways that depend on their properties are not.
+pub fn remap_block(name: &str, from_version: i32, to_version: i32) -> &str {
+    remap(RenameKind::Block, name, from_version, to_version)
+}
+
+/// Get the name a biome has in the version of Minecraft with DataVersion
+/// `to_version`, given its name in the version with `from_version`, the same
+/// as [`remap_block`]. Biomes removed in 1.18 are mapped to the biome the
+/// game replaced them with, going back from 1.18 gives the first of the old
+/// biomes that were merged.
+///
+/// ```
+/// # use fastanvil::remap_biome;
+/// assert_eq!("minecraft:windswept_hills", remap_biome("minecraft:mountains", 2730, 2975));
+/// assert_eq!("minecraft:forest", remap_biome("minecraft:wooded_hills", 2730, 2975));
+/// assert_eq!("minecraft:snowy_tundra", remap_biome("minecraft:snowy_plains", 2975, 2730));
+/// ```
+pub fn remap_biome(name: &str, from_version: i32, to_version: i32) -> &str {
+    remap(RenameKind::Biome, name, from_version, to_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_ordered() {
+        assert!(RENAMES
+            .windows(2)
+            .all(|w| w[0].data_version <= w[1].data_version));
+    }
+
+    #[test]
+    fn renames_chain_across_versions() {
+        // Only renames between the two versions apply.
+        assert_eq!(
+            "minecraft:grass",
+            remap_block("minecraft:grass", 1451, 2975)
+        );
+        assert_eq!(
+            "minecraft:short_grass",
+            remap_block("minecraft:grass", 1451, 3700)
+        );
+        assert_eq!(
+            "minecraft:grass",
+            remap_block("minecraft:short_grass", 3700, 1451)
+        );
+        assert_eq!(
+            "minecraft:sign",
+            remap_block("minecraft:oak_sign", 2975, 1631)
+        );
+    }
+
+    #[test]
+    fn merged_biomes_go_back_to_the_first() {
+        assert_eq!(
+            "minecraft:tall_birch_forest",
+            remap_biome("minecraft:old_growth_birch_forest", 2975, 2730)
+        );
+        assert_eq!(
+            "minecraft:nether",
+            remap_biome("minecraft:nether_wastes", 2975, 1631)
+        );
+    }
+}
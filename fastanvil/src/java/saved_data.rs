@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use fastnbt::{IntArray, Value};
+use flate2::read::GzDecoder;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::entity::uuid_from_ints;
+use crate::Result;
+
+/// The contents of `idcounts.dat`, which records the last ID given out for
+/// in-game maps.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct IdCounts {
+    /// The ID of the most recently created map, or none if no maps have been
+    /// made.
+    #[serde(rename = "map")]
+    pub map: Option<i32>,
+}
+
+/// The contents of `scoreboard.dat`, which holds the objectives, scores and
+/// teams set up with the `/scoreboard` and `/team` commands.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Scoreboard {
+    #[serde(default)]
+    pub objectives: Vec<Objective>,
+
+    #[serde(default)]
+    pub player_scores: Vec<PlayerScore>,
+
+    #[serde(default)]
+    pub teams: Vec<Team>,
+
+    /// The objective shown in each display slot, keyed by slot such as
+    /// `slot_1` for the sidebar.
+    #[serde(default)]
+    pub display_slots: HashMap<String, String>,
+}
+
+/// A scoreboard objective.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Objective {
+    pub name: String,
+
+    /// The criteria that updates the score, eg `dummy` or
+    /// `minecraft.mined:minecraft.stone`.
+    pub criteria_name: String,
+
+    /// The name shown for the objective. This is a JSON text component in a
+    /// string, or an NBT text component since 1.21.5.
+    pub display_name: Option<Value>,
+
+    /// How scores are shown in the tab list, `integer` or `hearts`.
+    pub render_type: Option<String>,
+}
+
+/// The score of a player or entity for an objective.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct PlayerScore {
+    /// The name of the player, or the UUID of an entity.
+    pub name: String,
+    pub objective: String,
+    pub score: i32,
+
+    /// Whether the score is locked for `trigger` objectives.
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// A team of players and entities.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Team {
+    pub name: String,
+
+    /// The name shown for the team, stored like [`Objective::display_name`].
+    pub display_name: Option<Value>,
+
+    /// The colour of the team, eg `red`.
+    pub team_color: Option<String>,
+
+    #[serde(default)]
+    pub allow_friendly_fire: bool,
+
+    #[serde(default)]
+    pub see_friendly_invisibles: bool,
+
+    pub name_tag_visibility: Option<String>,
+    pub death_message_visibility: Option<String>,
+    pub collision_rule: Option<String>,
+
+    /// The names of the players and UUIDs of the entities in the team.
+    #[serde(default)]
+    pub players: Vec<String>,
+
+    /// Any other tags, such as the team prefix and suffix.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+/// The contents of `raids.dat`, which tracks the raids going on in a
+/// dimension.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Raids {
+    #[serde(default)]
+    pub raids: Vec<Raid>,
+
+    /// The ID the next raid will be given.
+    #[serde(rename = "NextAvailableID")]
+    pub next_available_id: Option<i32>,
+
+    pub tick: Option<i32>,
+}
+
+/// A raid on a village.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct Raid {
+    pub id: i32,
+
+    #[serde(default)]
+    pub started: bool,
+
+    #[serde(default)]
+    pub active: bool,
+
+    /// The state of the raid: `ongoing`, `victory`, `loss` or `stopped`.
+    pub status: String,
+
+    /// The level of Bad Omen that started the raid.
+    #[serde(default)]
+    pub bad_omen_level: i32,
+
+    /// The number of waves spawned so far, out of `num_groups`.
+    #[serde(default)]
+    pub groups_spawned: i32,
+
+    #[serde(default)]
+    pub num_groups: i32,
+
+    #[serde(default)]
+    pub ticks_active: i64,
+
+    /// The block at the centre of the raid.
+    #[serde(rename = "CX")]
+    pub cx: i32,
+
+    #[serde(rename = "CY")]
+    pub cy: i32,
+
+    #[serde(rename = "CZ")]
+    pub cz: i32,
+
+    #[serde(default)]
+    heroes_of_the_village: Vec<IntArray>,
+
+    /// Any other tags of the raid.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
+
+impl Raid {
+    /// The UUIDs of the players that have helped defend the village.
+    pub fn heroes(&self) -> Vec<u128> {
+        self.heroes_of_the_village
+            .iter()
+            .filter_map(|uuid| uuid_from_ints(uuid))
+            .collect()
+    }
+}
+
+/// Load a file from the `data` folder of a world, such as `scoreboard.dat`.
+/// These files keep their contents in a `data` compound, which is what is
+/// deserialized into `T`.
+///
+/// ```no_run
+/// # use fastanvil::{load_saved_data, Scoreboard};
+/// let scoreboard: Scoreboard = load_saved_data("world/data/scoreboard.dat")?;
+/// for score in &scoreboard.player_scores {
+///     println!("{} has {} in {}", score.name, score.score, score.objective);
+/// }
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn load_saved_data<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let file = File::open(path)?;
+    read_saved_data(BufReader::new(file))
+}
+
+/// Read the contents of a file from the `data` folder of a world. The data
+/// should still be gzip compressed, as it is on disk.
+pub fn read_saved_data<T: DeserializeOwned>(reader: impl Read) -> Result<T> {
+    #[derive(Deserialize)]
+    struct SavedData<T> {
+        data: T,
+    }
+
+    let file: SavedData<T> = fastnbt::de::from_reader(GzDecoder::new(reader))?;
+    Ok(file.data)
+}
@@ -34,6 +34,18 @@ impl SectionTower {
     pub fn y_max(&self) -> isize {
         self.y_max
     }
+
+    /// The sections of the chunk, in the order they were stored.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Iterate over the sections from the bottom of the world up. Unlike
+    /// [`sections`](Self::sections) this skips the empty section some
+    /// versions store below the world.
+    pub fn iter(&self) -> impl Iterator<Item = &Section> + '_ {
+        self.map.iter().flatten().map(move |&i| &self.sections[i])
+    }
 }
 
 impl<'de> Deserialize<'de> for SectionTower {
@@ -42,13 +54,19 @@ impl<'de> Deserialize<'de> for SectionTower {
         D: serde::Deserializer<'de>,
     {
         let sections: Vec<Section> = Deserialize::deserialize(deserializer)?;
+        Ok(Self::from(sections))
+    }
+}
+
+impl From<Vec<Section>> for SectionTower {
+    fn from(sections: Vec<Section>) -> Self {
         if sections.is_empty() {
-            return Ok(Self {
+            return Self {
                 sections,
                 map: vec![],
                 y_min: 0,
                 y_max: 0,
-            });
+            };
         }
 
         // We need to figure out how deep the world goes. Since 1.17 the depth
@@ -86,12 +104,12 @@ impl<'de> Deserialize<'de> for SectionTower {
             sparse_sections[sec_index] = Some(i);
         }
 
-        Ok(Self {
+        Self {
             sections,
             map: sparse_sections,
             y_min: 16 * min,
             y_max: 16 * (max + 1),
-        })
+        }
     }
 }
 
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use fastnbt::{IntArray, LongArray};
+use serde::Deserialize;
+
+use crate::Result;
+
+/// The structures of a chunk, such as villages, fortresses and monuments.
+/// Structures have pieces that can cover many chunks. The chunk a structure
+/// starts in has its [`StructureStart`], with the bounding box of every
+/// piece, and every chunk the pieces overlap has a reference to that chunk.
+///
+/// Structure IDs are the keys used by the version of Minecraft that saved the
+/// chunk, eg `Village` in 1.13, `village` from 1.14 and `minecraft:village`
+/// from 1.18.
+///
+/// ```no_run
+/// # use fastanvil::{ChunkStructures, RegionBuffer};
+/// # let region = RegionBuffer::new(std::fs::File::open("r.0.0.mca")?);
+/// let structures = ChunkStructures::from_bytes(&region.load_chunk(0, 0)?)?;
+///
+/// for (id, start) in structures.starts() {
+///     println!("{} starts here, covering {:?}", id, start.bounding_box());
+/// }
+/// for (id, (x, z)) in structures.references() {
+///     println!("part of the {} starting in chunk {}, {}", id, x, z);
+/// }
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ChunkStructures {
+    #[serde(alias = "Starts", default)]
+    starts: HashMap<String, StructureStart>,
+
+    #[serde(rename = "References", default)]
+    references: HashMap<String, LongArray>,
+}
+
+impl ChunkStructures {
+    /// Read the structures from the NBT of a chunk, as loaded from a region.
+    /// Both chunks from before 1.18 with a `Level` tag and newer chunks are
+    /// read. Chunks without structure data have no structures.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Level {
+            #[serde(rename = "Structures")]
+            structures: Option<ChunkStructures>,
+        }
+
+        #[derive(Deserialize)]
+        struct Chunk {
+            structures: Option<ChunkStructures>,
+            #[serde(rename = "Level")]
+            level: Option<Level>,
+        }
+
+        let Chunk { structures, level } = fastnbt::de::from_bytes(data)?;
+        Ok(structures.or_else(|| level?.structures).unwrap_or_default())
+    }
+
+    /// The structures that start in this chunk, by ID. Before 1.18 every
+    /// kind of structure has a start, with those that did not generate
+    /// marked invalid, these are left out.
+    pub fn starts(&self) -> impl Iterator<Item = (&str, &StructureStart)> {
+        self.starts
+            .iter()
+            .filter(|(_, start)| start.is_valid())
+            .map(|(id, start)| (id.as_str(), start))
+    }
+
+    /// Get the start of the structure with the given ID, if it starts in this
+    /// chunk.
+    pub fn start(&self, id: &str) -> Option<&StructureStart> {
+        self.starts.get(id).filter(|start| start.is_valid())
+    }
+
+    /// The structures that overlap this chunk, as their ID and the chunk
+    /// coordinates of the chunk they start in. A structure that starts in
+    /// this chunk references it too.
+    pub fn references(&self) -> impl Iterator<Item = (&str, (i32, i32))> {
+        self.references.iter().flat_map(|(id, chunks)| {
+            chunks
+                .iter()
+                .map(move |&packed| (id.as_str(), unpack_chunk_pos(packed)))
+        })
+    }
+}
+
+/// Chunk positions are packed into a long with X in the low 32 bits and Z in
+/// the high 32 bits.
+fn unpack_chunk_pos(packed: i64) -> (i32, i32) {
+    (packed as i32, (packed >> 32) as i32)
+}
+
+/// A structure that starts in a chunk, along with all its pieces.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StructureStart {
+    /// The ID of the structure, or `INVALID` if a structure didn't generate.
+    pub id: String,
+
+    /// The chunk the structure starts in.
+    #[serde(rename = "ChunkX", default)]
+    pub chunk_x: i32,
+
+    #[serde(rename = "ChunkZ", default)]
+    pub chunk_z: i32,
+
+    /// The bounding box of the whole structure, only stored before 1.18.
+    #[serde(rename = "BB")]
+    bb: Option<IntArray>,
+
+    #[serde(rename = "Children", default)]
+    pub children: Vec<StructurePiece>,
+}
+
+impl StructureStart {
+    /// Whether the structure actually generated.
+    pub fn is_valid(&self) -> bool {
+        self.id != "INVALID"
+    }
+
+    /// The box covering every piece of the structure. `None` if the
+    /// structure has no pieces.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        if let Some(bb) = self.bb.as_ref().and_then(|bb| BoundingBox::from_slice(bb)) {
+            return Some(bb);
+        }
+
+        self.children
+            .iter()
+            .filter_map(StructurePiece::bounding_box)
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+/// A piece of a structure, such as a room of a stronghold or a house in a
+/// village. Pieces hold data particular to their kind, which is not read.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StructurePiece {
+    /// The kind of piece, eg `minecraft:shdpt` for a stronghold portal room.
+    pub id: String,
+
+    #[serde(rename = "BB")]
+    bb: Option<IntArray>,
+}
+
+impl StructurePiece {
+    /// The box the piece covers.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.bb.as_ref().and_then(|bb| BoundingBox::from_slice(bb))
+    }
+}
+
+/// A box of blocks in world coordinates, with both corners inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+}
+
+impl BoundingBox {
+    /// A box from how it is stored in NBT, as min X, Y, Z then max X, Y, Z.
+    pub fn from_slice(bb: &[i32]) -> Option<Self> {
+        match *bb {
+            [x0, y0, z0, x1, y1, z1] => Some(Self {
+                min: [x0, y0, z0],
+                max: [x1, y1, z1],
+            }),
+            _ => None,
+        }
+    }
+
+    /// The smallest box covering both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = *self;
+        for i in 0..3 {
+            union.min[i] = union.min[i].min(other.min[i]);
+            union.max[i] = union.max[i].max(other.max[i]);
+        }
+        union
+    }
+
+    /// Whether the block at the given world coordinates is in the box.
+    pub fn contains(&self, pos: [i32; 3]) -> bool {
+        (0..3).all(|i| (self.min[i]..=self.max[i]).contains(&pos[i]))
+    }
+
+    /// Whether the box overlaps the column of the chunk at the given chunk
+    /// coordinates.
+    pub fn intersects_chunk(&self, x: i32, z: i32) -> bool {
+        self.min[0] <= x * 16 + 15
+            && self.max[0] >= x * 16
+            && self.min[2] <= z * 16 + 15
+            && self.max[2] >= z * 16
+    }
+}
@@ -0,0 +1,134 @@
+use std::ops::Range;
+
+use serde::Deserialize;
+
+use crate::{
+    biome::Biome, Block, BlockEntity, Chunk, ChunkStatus, HeightMode, JavaChunk, LegacyChunk,
+};
+
+/// DataVersion of 17w47a, the first snapshot with block state palettes.
+pub(crate) const FLATTENING: i32 = 1451;
+
+/// DataVersion of 20w17a, where block states stopped spanning longs.
+pub(crate) const V1_16: i32 = 2529;
+
+/// DataVersion of 20w49a, the first snapshot with custom world heights.
+pub(crate) const V1_17: i32 = 2685;
+
+/// DataVersion of 21w43a, where the `Level` tag was removed.
+pub(crate) const V1_18: i32 = 2844;
+
+/// A chunk of any version, as returned by [`JavaChunk::from_bytes`]. Worlds
+/// that have been played across several versions of the game can contain
+/// chunks in many formats, since chunks are only upgraded when they are
+/// loaded by the game.
+///
+/// All versions from 1.13 onwards are read by [`JavaChunk`], the variant
+/// tells you which layout was found.
+#[derive(Debug)]
+pub enum VersionedChunk {
+    /// Before 1.13, with numeric block IDs.
+    Legacy(LegacyChunk),
+
+    /// 1.13 to 1.15, with block states packed across longs and 2D biomes
+    /// before 1.15.
+    V1_15(JavaChunk),
+
+    /// 1.16, with block states padded to fit within each long.
+    V1_16(JavaChunk),
+
+    /// 1.17, where worlds can have custom heights.
+    V1_17(JavaChunk),
+
+    /// 1.18 onwards, with sections at the root of the chunk and biomes stored
+    /// in each section.
+    V1_18(JavaChunk),
+}
+
+impl JavaChunk {
+    /// Deserialize a chunk of any version, picking the representation based
+    /// on the `DataVersion` of the chunk. Chunks from before 1.9 have no
+    /// `DataVersion` and are read as [`VersionedChunk::Legacy`].
+    pub fn from_bytes(data: &[u8]) -> fastnbt::error::Result<VersionedChunk> {
+        #[derive(Deserialize)]
+        struct Version {
+            #[serde(rename = "DataVersion", default)]
+            data_version: i32,
+        }
+
+        let Version { data_version } = fastnbt::de::from_bytes(data)?;
+
+        Ok(match data_version {
+            v if v < FLATTENING => VersionedChunk::Legacy(fastnbt::de::from_bytes(data)?),
+            v if v < V1_16 => VersionedChunk::V1_15(fastnbt::de::from_bytes(data)?),
+            v if v < V1_17 => VersionedChunk::V1_16(fastnbt::de::from_bytes(data)?),
+            v if v < V1_18 => VersionedChunk::V1_17(fastnbt::de::from_bytes(data)?),
+            _ => VersionedChunk::V1_18(fastnbt::de::from_bytes(data)?),
+        })
+    }
+}
+
+impl VersionedChunk {
+    /// The chunk as a [`JavaChunk`], unless it predates 1.13.
+    pub fn as_java(&self) -> Option<&JavaChunk> {
+        match self {
+            VersionedChunk::Legacy(_) => None,
+            VersionedChunk::V1_15(c)
+            | VersionedChunk::V1_16(c)
+            | VersionedChunk::V1_17(c)
+            | VersionedChunk::V1_18(c) => Some(c),
+        }
+    }
+
+    fn chunk(&self) -> &dyn Chunk {
+        match self {
+            VersionedChunk::Legacy(c) => c,
+            VersionedChunk::V1_15(c)
+            | VersionedChunk::V1_16(c)
+            | VersionedChunk::V1_17(c)
+            | VersionedChunk::V1_18(c) => c,
+        }
+    }
+}
+
+impl Chunk for VersionedChunk {
+    fn status(&self) -> ChunkStatus {
+        self.chunk().status()
+    }
+
+    fn inhabited_time(&self) -> Option<i64> {
+        self.chunk().inhabited_time()
+    }
+
+    fn surface_height(&self, x: usize, z: usize, mode: HeightMode) -> isize {
+        self.chunk().surface_height(x, z, mode)
+    }
+
+    fn surface_heights(&self, mode: HeightMode) -> [[isize; 16]; 16] {
+        self.chunk().surface_heights(mode)
+    }
+
+    fn biome(&self, x: usize, y: isize, z: usize) -> Option<Biome> {
+        self.chunk().biome(x, y, z)
+    }
+
+    fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+        self.chunk().block(x, y, z)
+    }
+
+    fn block_entities(&self) -> &[BlockEntity] {
+        self.chunk().block_entities()
+    }
+
+    fn block_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        self.chunk().block_light(x, y, z)
+    }
+
+    fn sky_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        self.chunk().sky_light(x, y, z)
+    }
+
+    fn y_range(&self) -> Range<isize> {
+        self.chunk().y_range()
+    }
+}
@@ -1,14 +1,28 @@
 //! For handling Minecraft's region format, Anvil.
 //!
 //! `anvil::Region` can be given a `Read` and `Seek` type eg a file in order to extract chunk data.
+//! If the type also implements `Write`, chunks can be written back to it.
+//!
+//! Chunks are (de)compressed with the pure-Rust backend of `flate2` by default,
+//! so no C toolchain is needed. Enable the `zlib` feature to use the zlib C
+//! library instead.
+//!
+//! Renders can be drawn into any [`PixelSink`], such as a plain RGBA buffer.
+//! The default `image` feature adds `image::RgbaImage` as a sink, along with
+//! the palettes, tiles and overlays built on it. Turn it off to render
+//! without depending on `image`.
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
 use fastnbt::de::from_bytes;
-use flate2::read::ZlibDecoder;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
 use num_enum::TryFromPrimitive;
 use serde::de::DeserializeOwned;
-use std::io::{Read, Seek, SeekFrom};
-use std::{cell::RefCell, convert::TryFrom};
+use std::convert::TryFrom;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// the size in bytes of a 'sector' in a region file. Sectors are Minecraft's size unit
 /// for chunks. For example, a chunk might be `3 * SECTOR_SIZE` bytes.
@@ -17,38 +31,189 @@ pub const SECTOR_SIZE: usize = 4096;
 /// the size of the region file header.
 pub const HEADER_SIZE: usize = 2 * SECTOR_SIZE;
 
+pub mod bedrock;
 pub mod biome;
+pub mod coords;
+pub mod edit;
+pub mod light;
+pub mod relocate;
+pub mod schematic;
+pub mod seed;
+pub mod structure;
 pub mod tex;
+#[cfg(feature = "image")]
+pub mod tiles;
+pub mod upgrade;
 
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "tokio")]
+mod async_region;
 mod bits;
+mod bytes_loader;
+mod cache;
+mod diff;
 mod dimension;
 mod files;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "image")]
+mod indexed;
 mod java;
+#[cfg(feature = "lz4")]
+mod lz4;
+mod memory;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "image")]
+mod overlay;
+mod progress;
 mod render;
+#[cfg(feature = "image")]
 mod rendered_palette;
+mod sink;
+mod validate;
+mod world;
+mod writer;
 
+#[cfg(feature = "archive")]
+pub use archive::*;
+#[cfg(feature = "tokio")]
+pub use async_region::*;
 pub use bits::*;
+pub use bytes_loader::*;
+pub use cache::CacheStats;
+pub(crate) use cache::LruCache;
+pub use coords::{BlockPos, ChunkPos, RegionPos};
+pub use diff::*;
 pub use dimension::*;
 pub use files::*;
+#[cfg(feature = "http")]
+pub use http::*;
+#[cfg(feature = "image")]
+pub use indexed::*;
 pub use java::*;
+pub use memory::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
+#[cfg(feature = "image")]
+pub use overlay::*;
+pub use progress::*;
 pub use render::*;
+#[cfg(feature = "image")]
 pub use rendered_palette::*;
+pub use sink::*;
+pub use validate::*;
+pub use world::*;
+pub use writer::*;
 
 #[cfg(test)]
 mod test;
 
 /// Various compression schemes that NBT data is typically compressed with.
-#[derive(Debug, TryFromPrimitive)]
+///
+/// Each scheme is the byte stored before each chunk in a region, so
+/// `CompressionScheme::Zlib as u8` is 2. The top bit of that byte marks chunks
+/// stored in `.mcc` files (see [`ChunkMeta::external`]) so is not part of the
+/// scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum CompressionScheme {
     Gzip = 1,
     Zlib = 2,
     Uncompressed = 3,
+
+    /// LZ4, in the block format of lz4-java. Used by Minecraft 1.20.5 and
+    /// later when `region-file-compression=lz4` is set. Needs the `lz4`
+    /// feature.
+    Lz4 = 4,
+
+    /// zstd. Not used by Minecraft itself, but by some servers. Needs the
+    /// `zstd` feature.
+    Zstd = 5,
+
+    /// A custom scheme added by a server or mod. Since Minecraft 1.20.5 the
+    /// data of such chunks starts with the namespaced ID of the scheme.
+    ///
+    /// Chunks with a scheme byte this crate does not know are read as custom
+    /// too, see [`ChunkMeta::scheme_id`]. Their raw data can still be read
+    /// with [`RegionBuffer::load_raw_chunk_into`] and decompressed by the
+    /// caller.
+    Custom = 127,
+}
+
+impl CompressionScheme {
+    /// Wrap `compressed` in a reader that decompresses it with this scheme.
+    /// Custom schemes, and those whose feature is not enabled, give
+    /// [`Error::UnsupportedCompression`].
+    pub fn decoder<'a, R: Read + 'a>(self, compressed: R) -> Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            CompressionScheme::Gzip => Box::new(GzDecoder::new(compressed)),
+            CompressionScheme::Zlib => Box::new(ZlibDecoder::new(compressed)),
+            CompressionScheme::Uncompressed => Box::new(compressed),
+            #[cfg(feature = "lz4")]
+            CompressionScheme::Lz4 => Box::new(lz4::Lz4Decoder::new(compressed)),
+            #[cfg(feature = "zstd")]
+            CompressionScheme::Zstd => Box::new(zstd::Decoder::new(compressed)?),
+            _ => return Err(Error::UnsupportedCompression(self as u8)),
+        })
+    }
+
+    /// Compress `data` with this scheme. Custom schemes, and those whose
+    /// feature is not enabled, give [`Error::UnsupportedCompression`].
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            CompressionScheme::Gzip => {
+                let mut enc = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+            CompressionScheme::Zlib => {
+                let mut enc = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                enc.write_all(data)?;
+                enc.finish()?
+            }
+            CompressionScheme::Uncompressed => data.to_vec(),
+            #[cfg(feature = "lz4")]
+            CompressionScheme::Lz4 => lz4::compress(data),
+            #[cfg(feature = "zstd")]
+            CompressionScheme::Zstd => zstd::encode_all(data, 0)?,
+            _ => return Err(Error::UnsupportedCompression(self as u8)),
+        })
+    }
 }
 
 /// A Minecraft Region. Allows access to chunk data, handling decompression.
+///
+/// The underlying data is behind a mutex, so a region can be shared between
+/// threads if `S` can be sent between them, such as a `File`. Reads of
+/// different chunks are serialized, see `RegionMmap` (with the `mmap`
+/// feature) for truly concurrent reads.
+///
+/// Chunks too large for a region are stored in separate `.mcc` files next to
+/// the region file. These can only be read and written if the region knows
+/// where it is, see [`with_external_chunks`](Self::with_external_chunks).
 pub struct RegionBuffer<S: Seek + Read> {
-    data: RefCell<S>,
+    data: Mutex<S>,
+    external: Option<ExternalChunks>,
+    compression: CompressionScheme,
+}
+
+/// Where a region's oversized chunks are stored.
+struct ExternalChunks {
+    dir: PathBuf,
+    x: RCoord,
+    z: RCoord,
+}
+
+impl ExternalChunks {
+    /// The path of the file for the chunk at the (region-relative) Chunk
+    /// location (x, z). Files are named by absolute chunk coordinates.
+    fn path(&self, x: usize, z: usize) -> PathBuf {
+        let x = self.x.0 * 32 + x as isize;
+        let z = self.z.0 * 32 + z as isize;
+        self.dir.join(format!("c.{}.{}.mcc", x, z))
+    }
 }
 
 impl<S: Seek + Read, C: Chunk + DeserializeOwned> Region<C> for RegionBuffer<S> {
@@ -59,10 +224,18 @@ impl<S: Seek + Read, C: Chunk + DeserializeOwned> Region<C> for RegionBuffer<S>
 
         from_bytes::<C>(&data).ok()
     }
+
+    fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        RegionBuffer::chunk_exists(self, x.0 as usize, z.0 as usize)
+    }
+
+    fn chunk_timestamps(&self) -> Option<Vec<u32>> {
+        RegionBuffer::chunk_timestamps(self).ok()
+    }
 }
 
 /// The location of chunk data within a Region file.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ChunkLocation {
     pub begin_sector: usize,
     pub sector_count: usize,
@@ -75,6 +248,16 @@ pub struct ChunkLocation {
 pub struct ChunkMeta {
     pub compressed_len: u32,
     pub compression_scheme: CompressionScheme,
+
+    /// The scheme byte as stored, without the flag for external chunks. For
+    /// schemes this crate does not know, which are read as
+    /// [`Custom`](CompressionScheme::Custom), this is the only record of
+    /// the scheme.
+    pub scheme_id: u8,
+
+    /// Whether the chunk is stored in a separate `.mcc` file because it is
+    /// too large for the region. The data in the region is then empty.
+    pub external: bool,
 }
 
 impl ChunkMeta {
@@ -86,22 +269,95 @@ impl ChunkMeta {
         let mut buf = &data[..5];
         let len = buf.read_u32::<BigEndian>()?;
         let scheme = buf.read_u8()?;
-        let scheme = CompressionScheme::try_from(scheme).map_err(|_| Error::InvalidChunkMeta)?;
+        let external = scheme & EXTERNAL_FLAG != 0;
+        let scheme_id = scheme & !EXTERNAL_FLAG;
+        let scheme = CompressionScheme::try_from(scheme_id).unwrap_or(CompressionScheme::Custom);
 
         Ok(Self {
-            compressed_len: len - 1, // this len include the compression byte.
+            compressed_len: len.saturating_sub(1), // this len include the compression byte.
             compression_scheme: scheme,
+            scheme_id,
+            external,
         })
     }
+
+    /// Wrap `compressed` in a reader that decompresses it with the chunk's
+    /// scheme, see [`CompressionScheme::decoder`]. Unknown schemes give
+    /// [`Error::UnsupportedCompression`] with their scheme byte.
+    pub fn decoder<'a, R: Read + 'a>(&self, compressed: R) -> Result<Box<dyn Read + 'a>> {
+        if self.compression_scheme as u8 != self.scheme_id {
+            return Err(Error::UnsupportedCompression(self.scheme_id));
+        }
+        self.compression_scheme.decoder(compressed)
+    }
 }
 
+/// Set in the compression scheme byte of chunks stored in `.mcc` files.
+const EXTERNAL_FLAG: u8 = 0x80;
+
 impl<S: Seek + Read> RegionBuffer<S> {
     pub fn new(data: S) -> Self {
         Self {
-            data: RefCell::new(data),
+            data: Mutex::new(data),
+            external: None,
+            compression: CompressionScheme::Zlib,
+        }
+    }
+
+    /// Set the compression scheme chunks are written with, zlib by default.
+    /// Chunks already in the region are read whatever scheme they use. LZ4
+    /// and zstd need the `lz4` and `zstd` features.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{CompressionScheme, RegionBuffer};
+    /// let file = std::fs::OpenOptions::new().read(true).write(true).open("r.0.0.mca")?;
+    /// let mut region = RegionBuffer::new(file).with_compression(CompressionScheme::Lz4);
+    /// region.write_chunk(0, 0, &[10, 0, 0, 0])?;
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn with_compression(self, compression: CompressionScheme) -> Self {
+        Self {
+            compression,
+            ..self
         }
     }
 
+    /// Tell the region where its oversized chunks are stored: `dir` is the
+    /// folder containing the region file, and (x, z) are the coordinates of
+    /// the region. Without this, loading such a chunk gives
+    /// [`Error::ExternalChunkUnavailable`] and writing one gives
+    /// [`Error::ChunkTooLarge`].
+    ///
+    /// ```no_run
+    /// # use fastanvil::{RCoord, RegionBuffer};
+    /// let file = std::fs::File::open("world/region/r.-1.2.mca")?;
+    /// let region = RegionBuffer::new(file).with_external_chunks("world/region", RCoord(-1), RCoord(2));
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn with_external_chunks(self, dir: impl Into<PathBuf>, x: RCoord, z: RCoord) -> Self {
+        Self {
+            external: Some(ExternalChunks {
+                dir: dir.into(),
+                x,
+                z,
+            }),
+            ..self
+        }
+    }
+
+    /// Take the underlying data of the region.
+    pub fn into_inner(self) -> S {
+        self.data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the underlying data. Every use of the data seeks before reading,
+    /// so data left mid-read by a panicking thread is still usable.
+    fn data(&self) -> MutexGuard<'_, S> {
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Return the (region-relative) Chunk location (x, z)
     pub fn chunk_location(&self, x: usize, z: usize) -> Result<ChunkLocation> {
         if x >= 32 || z >= 32 {
@@ -110,11 +366,12 @@ impl<S: Seek + Read> RegionBuffer<S> {
 
         let pos = 4 * ((x % 32) + (z % 32) * 32);
 
-        self.data.borrow_mut().seek(SeekFrom::Start(pos as u64))?;
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(pos as u64))?;
 
         let mut buf = [0u8; 4];
 
-        self.data.borrow_mut().read_exact(&mut buf[..])?;
+        data.read_exact(&mut buf[..])?;
 
         let mut off = 0usize;
         off |= (buf[0] as usize) << 16;
@@ -129,6 +386,87 @@ impl<S: Seek + Read> RegionBuffer<S> {
         })
     }
 
+    /// Whether the chunk at the (region-relative) Chunk location (x, z) is
+    /// present, going only by the region header. Nothing is decompressed, so
+    /// a chunk can exist and still fail to load if its data is corrupt.
+    /// Coordinates outside the region and regions with an unreadable header
+    /// have no chunks.
+    pub fn chunk_exists(&self, x: usize, z: usize) -> bool {
+        matches!(
+            self.chunk_location(x, z),
+            Ok(loc) if loc.begin_sector != 0 && loc.sector_count != 0
+        )
+    }
+
+    /// Return the locations of every chunk present in the region, reading
+    /// only the header. Chunks are ordered by z then x. This is a cheap way
+    /// to find out how many chunks there are, or to split them up for
+    /// processing, before loading any of them.
+    ///
+    /// ```no_run
+    /// # use fastanvil::RegionBuffer;
+    /// # let file = std::fs::File::open("r.0.0.mca").unwrap();
+    /// let region = RegionBuffer::new(file);
+    /// let locations = region.locations()?;
+    ///
+    /// for (i, loc) in locations.iter().enumerate() {
+    ///     let data = region.load_chunk(loc.x, loc.z)?;
+    ///     println!("{}/{}: {} bytes", i + 1, locations.len(), data.len());
+    /// }
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn locations(&self) -> Result<Vec<ChunkLocation>> {
+        let mut header = vec![0; SECTOR_SIZE];
+
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(0))?;
+        data.read_exact(&mut header)?;
+
+        let locations = header
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(i, entry)| ChunkLocation {
+                begin_sector: BigEndian::read_u24(entry) as usize,
+                sector_count: entry[3] as usize,
+                x: i % 32,
+                z: i / 32,
+            })
+            // 0,0 chunk location means the chunk isn't present.
+            .filter(|loc| loc.begin_sector != 0 && loc.sector_count != 0)
+            .collect();
+
+        Ok(locations)
+    }
+
+    /// Return the last modification time of the chunk at the (region-relative)
+    /// Chunk location (x, z), in seconds since the Unix epoch. This is stored
+    /// in the second sector of the region header. Chunks that are not present
+    /// typically have a timestamp of zero.
+    pub fn chunk_timestamp(&self, x: usize, z: usize) -> Result<u32> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let pos = SECTOR_SIZE + 4 * (x + z * 32);
+
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(pos as u64))?;
+        Ok(data.read_u32::<BigEndian>()?)
+    }
+
+    /// Return the last modification times of every chunk in the region, in
+    /// seconds since the Unix epoch. Chunks are ordered by z then x, so the
+    /// chunk at (x, z) is at index `x + z * 32`.
+    pub fn chunk_timestamps(&self) -> Result<Vec<u32>> {
+        let mut header = vec![0; SECTOR_SIZE];
+
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(SECTOR_SIZE as u64))?;
+        data.read_exact(&mut header)?;
+
+        Ok(header.chunks_exact(4).map(BigEndian::read_u32).collect())
+    }
+
     /// Return the raw, uncompressed NBT data for a chunk at the
     /// (region-relative) Chunk location (x, z). Region's hold 32 by 32 chunks.
     ///
@@ -141,8 +479,81 @@ impl<S: Seek + Read> RegionBuffer<S> {
         decompress_chunk(&data)
     }
 
-    /// Call function with each uncompressed, non-empty chunk, calls f(x, z, data).
-    pub fn for_each_chunk(&mut self, mut f: impl FnMut(usize, usize, &Vec<u8>)) -> Result<()> {
+    /// Read the raw, compressed data for a chunk at the (region-relative)
+    /// Chunk location (x, z) into `dest`, replacing its contents. This
+    /// includes the 5 bytes of length and compression scheme, see
+    /// [`ChunkMeta`]. For chunks stored in `.mcc` files these bytes describe
+    /// the data from the file, as if it had been stored in the region.
+    ///
+    /// Reusing `dest` across chunks avoids allocating for every chunk when
+    /// scanning many chunks.
+    pub fn load_raw_chunk_into(&self, x: usize, z: usize, dest: &mut Vec<u8>) -> Result<()> {
+        let location = self.chunk_location(x, z)?;
+
+        // 0,0 chunk location means the chunk isn't present.
+        if location.begin_sector != 0 && location.sector_count != 0 {
+            self.load_raw_chunk(&location, dest)
+        } else {
+            Err(Error::ChunkNotFound)
+        }
+    }
+
+    /// Get a reader of the uncompressed NBT data for a chunk at the
+    /// (region-relative) Chunk location (x, z). The data is decompressed as
+    /// it is read rather than all at once.
+    ///
+    /// The reader holds the lock on the underlying data of the region, so
+    /// other methods of the region block until it is dropped. Calling them on
+    /// the same thread while the reader is alive will deadlock.
+    ///
+    /// ```no_run
+    /// # use fastanvil::RegionBuffer;
+    /// # use std::io::Read;
+    /// # let file = std::fs::File::open("r.0.0.mca").unwrap();
+    /// let region = RegionBuffer::new(file);
+    /// let mut buf = Vec::new();
+    ///
+    /// for z in 0..32 {
+    ///     for x in 0..32 {
+    ///         buf.clear();
+    ///         if let Ok(mut reader) = region.chunk_reader(x, z) {
+    ///             reader.read_to_end(&mut buf)?;
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn chunk_reader(&self, x: usize, z: usize) -> Result<impl Read + '_> {
+        let location = self.chunk_location(x, z)?;
+
+        // 0,0 chunk location means the chunk isn't present.
+        if location.begin_sector == 0 || location.sector_count == 0 {
+            return Err(Error::ChunkNotFound);
+        }
+
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(
+            location.begin_sector as u64 * SECTOR_SIZE as u64,
+        ))?;
+
+        let mut meta = [0u8; 5];
+        data.read_exact(&mut meta)?;
+        let meta = ChunkMeta::new(&meta)?;
+
+        let compressed: Box<dyn Read + '_> = if meta.external {
+            drop(data);
+            Box::new(Cursor::new(self.read_external(x, z)?))
+        } else {
+            Box::new(BorrowedReader(data).take(meta.compressed_len as u64))
+        };
+
+        meta.decoder(compressed)
+    }
+
+    /// Call function with each uncompressed, non-empty chunk, calls f(x, z,
+    /// timestamp, data). The timestamp is the last modification time of the
+    /// chunk, see [`chunk_timestamp`](Self::chunk_timestamp).
+    pub fn for_each_chunk(&mut self, mut f: impl FnMut(usize, usize, u32, &Vec<u8>)) -> Result<()> {
         let mut offsets = Vec::<ChunkLocation>::new();
 
         // Build list of existing chunks
@@ -162,42 +573,537 @@ impl<S: Seek + Read> RegionBuffer<S> {
         offsets.sort_by(|o1, o2| o2.begin_sector.cmp(&o1.begin_sector));
 
         for offset in offsets {
+            let timestamp = self.chunk_timestamp(offset.x, offset.z)?;
             let chunk = self.load_chunk(offset.x, offset.z)?;
-            f(offset.x, offset.z, &chunk);
+            f(offset.x, offset.z, timestamp, &chunk);
         }
 
         Ok(())
     }
 
-    /// Return the raw, compressed data for a chunk at ChunkLocation
+    /// Iterate over each uncompressed, non-empty chunk in the region, giving
+    /// `(x, z, data)`. Chunks are visited in the order they appear in the
+    /// file rather than by coordinate. An error reading one chunk does not
+    /// stop the iteration; collect into a `Result` or use `?` in a loop to
+    /// stop at the first error.
+    ///
+    /// ```no_run
+    /// # use fastanvil::RegionBuffer;
+    /// # let file = std::fs::File::open("r.0.0.mca").unwrap();
+    /// let region = RegionBuffer::new(file);
+    ///
+    /// for chunk in region.iter().take(5) {
+    ///     let (x, z, data) = chunk?;
+    ///     println!("chunk {}, {} is {} bytes", x, z, data.len());
+    /// }
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn iter(&self) -> RegionIter<'_, S> {
+        RegionIter {
+            region: self,
+            locations: None,
+        }
+    }
+
+    /// Deserialize each non-empty chunk into a `T` and call `f(x, z, chunk)`
+    /// with it, spread across rayon's thread pool. Reading from the underlying
+    /// data is done up front on the calling thread, decompression and
+    /// deserialization happen in parallel. This means `f` is called in no
+    /// particular order.
+    ///
+    /// Stops at the first error, but other chunks may have been processed
+    /// by the time it is returned.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{JavaChunk, RegionBuffer};
+    /// # let file = std::fs::File::open("r.0.0.mca").unwrap();
+    /// let mut region = RegionBuffer::new(file);
+    ///
+    /// region.par_for_each_chunk(|x, z, chunk: JavaChunk| {
+    ///     println!("chunk {}, {} is {}", x, z, chunk.level.status);
+    /// })?;
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_for_each_chunk<T, F>(&mut self, f: F) -> Result<()>
+    where
+        T: DeserializeOwned,
+        F: Fn(usize, usize, T) + Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let mut raw = Vec::new();
+        for loc in self.present_chunk_locations()? {
+            let mut buf = Vec::new();
+            self.load_raw_chunk(&loc, &mut buf)?;
+            raw.push((loc.x, loc.z, buf));
+        }
+
+        raw.into_par_iter().try_for_each(|(x, z, data)| {
+            let data = decompress_chunk(&data)?;
+            let chunk = from_bytes(&data)?;
+            f(x, z, chunk);
+            Ok(())
+        })
+    }
+
+    /// Locations of all the chunks present in the region, in the order they
+    /// appear in the file.
+    fn present_chunk_locations(&self) -> Result<Vec<ChunkLocation>> {
+        let mut locations = self.locations()?;
+        locations.sort_by_key(|loc| loc.begin_sector);
+        Ok(locations)
+    }
+
+    /// Return the raw, compressed data for a chunk at ChunkLocation, reading
+    /// it from its `.mcc` file if it is stored externally.
     fn load_raw_chunk(&self, offset: &ChunkLocation, dest: &mut Vec<u8>) -> Result<()> {
-        self.data.borrow_mut().seek(SeekFrom::Start(
+        let metadata = self.load_stored_chunk(offset, dest)?;
+
+        if metadata.external {
+            let external = self.read_external(offset.x, offset.z)?;
+
+            // Describe the external data as if it were stored in the region.
+            dest.clear();
+            dest.write_u32::<BigEndian>(external.len() as u32 + 1)?;
+            dest.write_u8(metadata.scheme_id)?;
+            dest.extend_from_slice(&external);
+        }
+
+        Ok(())
+    }
+
+    /// Return the data for a chunk at ChunkLocation as it is stored in the
+    /// region, which is just the header for external chunks.
+    fn load_stored_chunk(&self, offset: &ChunkLocation, dest: &mut Vec<u8>) -> Result<ChunkMeta> {
+        let mut data = self.data();
+        data.seek(SeekFrom::Start(
             offset.begin_sector as u64 * SECTOR_SIZE as u64,
         ))?;
 
         dest.resize(5, 0);
-        self.data.borrow_mut().read_exact(&mut dest[0..5])?;
+        data.read_exact(&mut dest[0..5])?;
         let metadata = ChunkMeta::new(&dest[..5])?;
 
         dest.resize(5 + metadata.compressed_len as usize, 0u8);
 
-        self.data.borrow_mut().read_exact(&mut dest[5..])?;
-        Ok(())
+        data.read_exact(&mut dest[5..])?;
+        Ok(metadata)
+    }
+
+    /// Read the compressed data of the chunk at the (region-relative) Chunk
+    /// location (x, z) from its `.mcc` file.
+    fn read_external(&self, x: usize, z: usize) -> Result<Vec<u8>> {
+        let external = self
+            .external
+            .as_ref()
+            .ok_or(Error::ExternalChunkUnavailable)?;
+        Ok(std::fs::read(external.path(x, z))?)
     }
 
     /// Return the raw, compressed data for a chunk at the (region-relative) Chunk location (x, z)
     fn load_raw_chunk_at(&self, x: usize, z: usize) -> Result<Vec<u8>> {
-        let location = self.chunk_location(x, z)?;
+        let mut buf = Vec::new();
+        self.load_raw_chunk_into(x, z, &mut buf)?;
+        Ok(buf)
+    }
+}
 
-        // 0,0 chunk location means the chunk isn't present.
-        if location.begin_sector != 0 && location.sector_count != 0 {
-            let mut buf = Vec::new();
-            self.load_raw_chunk(&location, &mut buf)?;
-            Ok(buf)
+impl<S: Seek + Read + Write> RegionBuffer<S> {
+    fn data_mut(&mut self) -> &mut S {
+        self.data.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Compress and write the uncompressed NBT data of a chunk at the
+    /// (region-relative) Chunk location (x, z), replacing any chunk already
+    /// there. The chunk's timestamp is set to the current time.
+    ///
+    /// The chunk is written over its old sectors if it still fits in them,
+    /// otherwise it is moved to the first gap in the file big enough to hold
+    /// it, or appended to the end. Sectors no longer used are left in place,
+    /// the region header is the only record of which sectors are in use.
+    ///
+    /// An empty region can be written to, the header will be created.
+    ///
+    /// Chunks too large to fit in the region are written to a `.mcc` file
+    /// instead, if the region knows where to put it (see
+    /// [`with_external_chunks`](RegionBuffer::with_external_chunks)).
+    pub fn write_chunk(&mut self, x: usize, z: usize, data: &[u8]) -> Result<()> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let mut compressed = self.compression.compress(data)?;
+        let mut scheme = self.compression as u8;
+
+        // 4 bytes of length and 1 byte of compression scheme come first.
+        let len = compressed.len() + 5;
+        let mut sector_count = len / SECTOR_SIZE;
+        if len % SECTOR_SIZE != 0 {
+            sector_count += 1;
+        }
+
+        // The sector count in the header is a single byte.
+        let is_external = sector_count > u8::MAX as usize;
+        if is_external {
+            let external = self.external.as_ref().ok_or(Error::ChunkTooLarge)?;
+            std::fs::write(external.path(x, z), &compressed)?;
+
+            // Only the length and scheme are left in the region.
+            compressed.clear();
+            scheme |= EXTERNAL_FLAG;
+            sector_count = 1;
+        }
+
+        self.ensure_header()?;
+        let old = self.chunk_location(x, z)?;
+
+        let begin_sector = if old.begin_sector >= 2 && old.sector_count >= sector_count {
+            old.begin_sector
         } else {
-            Err(Error::ChunkNotFound)
+            self.find_free_sectors(sector_count, &old)?
+        };
+
+        if begin_sector > 0xff_ffff {
+            return Err(Error::ChunkTooLarge);
+        }
+
+        let mut buf = Vec::with_capacity(sector_count * SECTOR_SIZE);
+        buf.write_u32::<BigEndian>(compressed.len() as u32 + 1)?;
+        buf.write_u8(scheme)?;
+        buf.extend_from_slice(&compressed);
+        buf.resize(sector_count * SECTOR_SIZE, 0);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as u32)
+            .unwrap_or(0);
+
+        let data = self.data_mut();
+        data.seek(SeekFrom::Start((begin_sector * SECTOR_SIZE) as u64))?;
+        data.write_all(&buf)?;
+
+        self.write_header_entry(x, z, begin_sector, sector_count, timestamp)?;
+
+        // A previous version of the chunk might have been external. It is
+        // only deleted once the region no longer points to it, so a failed
+        // write leaves the old chunk readable.
+        if !is_external {
+            self.remove_external(x, z)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the chunk at the (region-relative) Chunk location (x, z) by
+    /// clearing its entry in the header. The sectors it used are free to be
+    /// reused by later writes. The chunk's `.mcc` file is deleted if it has
+    /// one.
+    pub fn remove_chunk(&mut self, x: usize, z: usize) -> Result<()> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        self.ensure_header()?;
+        self.write_header_entry(x, z, 0, 0, 0)?;
+        self.remove_external(x, z)
+    }
+
+    /// Delete the `.mcc` file of a chunk, if there is one.
+    fn remove_external(&self, x: usize, z: usize) -> Result<()> {
+        if let Some(external) = &self.external {
+            match std::fs::remove_file(external.path(x, z)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Pad the data with zeros up to the size of the header, for writing to
+    /// an empty region.
+    fn ensure_header(&mut self) -> Result<()> {
+        let data = self.data_mut();
+        let len = data.seek(SeekFrom::End(0))? as usize;
+        if len < HEADER_SIZE {
+            data.write_all(&vec![0; HEADER_SIZE - len])?;
+        }
+        Ok(())
+    }
+
+    fn write_header_entry(
+        &mut self,
+        x: usize,
+        z: usize,
+        begin_sector: usize,
+        sector_count: usize,
+        timestamp: u32,
+    ) -> Result<()> {
+        let pos = 4 * (x + z * 32);
+        let location = (begin_sector as u32) << 8 | sector_count as u32;
+
+        let data = self.data_mut();
+        data.seek(SeekFrom::Start(pos as u64))?;
+        data.write_u32::<BigEndian>(location)?;
+        data.seek(SeekFrom::Start((SECTOR_SIZE + pos) as u64))?;
+        data.write_u32::<BigEndian>(timestamp)?;
+        Ok(())
+    }
+
+    /// Find the first run of `count` sectors that are not used by any chunk
+    /// other than the one at `replacing`.
+    fn find_free_sectors(&self, count: usize, replacing: &ChunkLocation) -> Result<usize> {
+        let used = self
+            .present_chunk_locations()?
+            .into_iter()
+            .filter(|loc| (loc.x, loc.z) != (replacing.x, replacing.z));
+
+        // The first two sectors are the header.
+        let mut begin = 2;
+        for loc in used {
+            if loc.begin_sector >= begin + count {
+                return Ok(begin);
+            }
+            begin = begin.max(loc.begin_sector + loc.sector_count);
+        }
+
+        Ok(begin)
+    }
+}
+
+/// Reads from the locked data of a region.
+struct BorrowedReader<'a, S>(MutexGuard<'a, S>);
+
+impl<'a, S: Read> Read for BorrowedReader<'a, S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+/// Iterator over the chunks of a [`RegionBuffer`], see
+/// [`RegionBuffer::iter`].
+pub struct RegionIter<'a, S: Seek + Read> {
+    region: &'a RegionBuffer<S>,
+    // Locations of the chunks still to visit, read from the header on the
+    // first call to next. Stored in reverse so we can pop from the end.
+    locations: Option<Vec<ChunkLocation>>,
+}
+
+impl<'a, S: Seek + Read> Iterator for RegionIter<'a, S> {
+    type Item = Result<(usize, usize, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.locations.is_none() {
+            match self.region.present_chunk_locations() {
+                Ok(mut locations) => {
+                    locations.reverse();
+                    self.locations = Some(locations);
+                }
+                Err(e) => {
+                    // Can't get any further without the header.
+                    self.locations = Some(vec![]);
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        let loc = self.locations.as_mut()?.pop()?;
+        Some(
+            self.region
+                .load_chunk(loc.x, loc.z)
+                .map(|data| (loc.x, loc.z, data)),
+        )
+    }
+}
+
+/// The result of [`compact_region`] and [`prune_region`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactReport {
+    /// The number of chunks copied to the new region.
+    pub chunks: usize,
+
+    /// The number of chunks left out of the new region by [`prune_region`].
+    /// Always zero for [`compact_region`].
+    pub removed: usize,
+
+    /// The size in bytes of the original region.
+    pub bytes_before: u64,
+
+    /// The size in bytes of the compacted region.
+    pub bytes_after: u64,
+}
+
+impl CompactReport {
+    /// The number of bytes saved by compacting the region.
+    pub fn bytes_reclaimed(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Write a copy of the `src` region to `dst` with its chunks packed
+/// contiguously after a fresh header. This drops the dead sectors left behind
+/// as chunks are rewritten and grow, as well as any sectors allocated beyond
+/// what each chunk needs.
+///
+/// Chunks are copied without being decompressed, and keep their timestamps.
+/// Chunks stored in `.mcc` files are left there.
+///
+/// ```no_run
+/// # use fastanvil::{compact_region, RegionBuffer};
+/// let src = RegionBuffer::new(std::fs::File::open("r.0.0.mca")?);
+/// let dst = std::fs::File::create("r.0.0.mca.compact")?;
+///
+/// let report = compact_region(&src, dst)?;
+/// println!("reclaimed {} bytes", report.bytes_reclaimed());
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn compact_region<S: Seek + Read, W: Write>(
+    src: &RegionBuffer<S>,
+    dst: W,
+) -> Result<CompactReport> {
+    copy_region(src, dst, |_| Ok(false))
+}
+
+/// A chunk given to the predicate of [`prune_region`] or
+/// [`prune_dimension`] to decide whether it should be removed.
+#[derive(Debug, Clone)]
+pub struct ChunkInfo {
+    /// The chunk coordinates. These are region-relative for
+    /// [`prune_region`], as the region doesn't know where it is, and absolute
+    /// for [`prune_dimension`].
+    pub x: isize,
+    pub z: isize,
+
+    /// When the chunk was last saved, in seconds since the Unix epoch.
+    pub timestamp: u32,
+
+    /// The uncompressed NBT of the chunk.
+    pub data: Vec<u8>,
+}
+
+impl ChunkInfo {
+    /// The total time in ticks players have spent in the chunk, from its
+    /// `InhabitedTime`. Chunks that were generated but never visited have an
+    /// inhabited time of zero. `None` if the chunk doesn't have one.
+    pub fn inhabited_time(&self) -> Option<i64> {
+        #[derive(serde::Deserialize)]
+        struct Inhabited {
+            #[serde(rename = "InhabitedTime")]
+            time: Option<i64>,
+        }
+
+        // Before 1.18 everything is inside a Level compound.
+        #[derive(serde::Deserialize)]
+        struct Chunk {
+            #[serde(rename = "InhabitedTime")]
+            time: Option<i64>,
+            #[serde(rename = "Level")]
+            level: Option<Inhabited>,
+        }
+
+        let chunk: Chunk = from_bytes(&self.data).ok()?;
+        chunk.time.or_else(|| chunk.level?.time)
+    }
+}
+
+/// Like [`compact_region`], but leaving out every chunk for which `remove`
+/// returns true, such as chunks that were never visited or that are outside a
+/// radius. Each chunk is decompressed to be given to `remove`, see
+/// [`ChunkInfo`].
+///
+/// ```no_run
+/// # use fastanvil::{prune_region, RegionBuffer};
+/// let src = RegionBuffer::new(std::fs::File::open("r.0.0.mca")?);
+/// let dst = std::fs::File::create("r.0.0.mca.pruned")?;
+///
+/// let report = prune_region(&src, dst, |chunk| chunk.inhabited_time() == Some(0))?;
+/// println!("removed {} chunks", report.removed);
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub fn prune_region<S, W, F>(src: &RegionBuffer<S>, dst: W, remove: F) -> Result<CompactReport>
+where
+    S: Seek + Read,
+    W: Write,
+    F: FnMut(&ChunkInfo) -> bool,
+{
+    prune_region_at(src, dst, (0, 0), remove)
+}
+
+/// Like [`prune_region`], with `origin` added to the chunk coordinates given
+/// to `remove`.
+pub(crate) fn prune_region_at<S, W, F>(
+    src: &RegionBuffer<S>,
+    dst: W,
+    origin: (isize, isize),
+    mut remove: F,
+) -> Result<CompactReport>
+where
+    S: Seek + Read,
+    W: Write,
+    F: FnMut(&ChunkInfo) -> bool,
+{
+    copy_region(src, dst, |loc| {
+        let info = ChunkInfo {
+            x: origin.0 + loc.x as isize,
+            z: origin.1 + loc.z as isize,
+            timestamp: src.chunk_timestamp(loc.x, loc.z)?,
+            data: src.load_chunk(loc.x, loc.z)?,
+        };
+        Ok(remove(&info))
+    })
+}
+
+/// Write the chunks of `src` packed into `dst`, leaving out those `remove`
+/// returns true for.
+fn copy_region<S, W, F>(src: &RegionBuffer<S>, mut dst: W, mut remove: F) -> Result<CompactReport>
+where
+    S: Seek + Read,
+    W: Write,
+    F: FnMut(&ChunkLocation) -> Result<bool>,
+{
+    let bytes_before = src.data().seek(SeekFrom::End(0))?;
+
+    let mut locations = vec![0u32; 1024];
+    let mut timestamps = vec![0u32; 1024];
+    let mut body = Vec::new();
+    let mut chunks = 0;
+    let mut removed = 0;
+    let mut raw = Vec::new();
+
+    for loc in src.present_chunk_locations()? {
+        if remove(&loc)? {
+            removed += 1;
+            continue;
         }
+
+        // Chunks in .mcc files stay there, only their header is copied.
+        src.load_stored_chunk(&loc, &mut raw)?;
+
+        let begin_sector = HEADER_SIZE / SECTOR_SIZE + body.len() / SECTOR_SIZE;
+        body.extend_from_slice(&raw);
+        let partial = body.len() % SECTOR_SIZE;
+        if partial != 0 {
+            body.resize(body.len() + SECTOR_SIZE - partial, 0);
+        }
+        let sector_count = HEADER_SIZE / SECTOR_SIZE + body.len() / SECTOR_SIZE - begin_sector;
+
+        let index = loc.x + loc.z * 32;
+        locations[index] = (begin_sector as u32) << 8 | sector_count as u32;
+        timestamps[index] = src.chunk_timestamp(loc.x, loc.z)?;
+        chunks += 1;
+    }
+
+    for location in locations.into_iter().chain(timestamps) {
+        dst.write_u32::<BigEndian>(location)?;
     }
+    dst.write_all(&body)?;
+    dst.flush()?;
+
+    Ok(CompactReport {
+        chunks,
+        removed,
+        bytes_before,
+        bytes_after: (HEADER_SIZE + body.len()) as u64,
+    })
 }
 
 // Read Information Bytes of Minecraft Chunk and decompress it
@@ -206,11 +1112,7 @@ fn decompress_chunk(data: &[u8]) -> Result<Vec<u8>> {
     let meta = ChunkMeta::new(data)?;
 
     // compressed data starts at byte 5
-    let inbuf = &mut &data[5..];
-    let mut decoder = match meta.compression_scheme {
-        CompressionScheme::Zlib => ZlibDecoder::new(inbuf),
-        _ => panic!("unknown compression scheme (gzip?)"),
-    };
+    let mut decoder = meta.decoder(&data[5..])?;
     let mut outbuf = Vec::new();
     // read the whole Chunk
     decoder.read_to_end(&mut outbuf)?;
@@ -224,6 +1126,52 @@ pub enum Error {
     InvalidOffset(usize, usize),
     InvalidChunkMeta,
     ChunkNotFound,
+    ChunkTooLarge,
+
+    /// The chunk is stored in a separate `.mcc` file, but the region was not
+    /// told where to find it.
+    ExternalChunkUnavailable,
+    Nbt(fastnbt::error::Error),
+
+    /// A schematic could not be read or written, see the
+    /// [`schematic`] module.
+    Schematic(String),
+    #[cfg(feature = "image")]
+    Image(image::ImageError),
+
+    /// Chunk data from the network protocol could not be parsed, see
+    /// [`read_network_sections`].
+    Network(String),
+
+    /// The chunk is compressed with a scheme this crate cannot handle, given
+    /// by its scheme byte. See [`CompressionScheme`].
+    UnsupportedCompression(u8),
+
+    /// A chunk could not be upgraded, see the [`upgrade`] module.
+    Upgrade(String),
+
+    /// A resource pack could not be read, see `tex::ResourcePack`.
+    ResourcePack(String),
+
+    /// A saved palette could not be loaded, see
+    /// `RenderedPalette::from_bytes`.
+    Palette(String),
+
+    /// A region could not be downloaded, see `HttpRegionLoader`.
+    Http(String),
+
+    /// A world backup could not be read, see `ZipRegionLoader`.
+    Archive(String),
+
+    /// A chunk could not be edited, see the [`edit`] module.
+    Edit(String),
+
+    /// The regions of a dimension could not be listed, see [`LoaderError`].
+    Loader(String),
+
+    /// The database of a Bedrock world could not be read, see
+    /// [`bedrock::LevelDb`].
+    LevelDb(String),
 }
 
 impl From<std::io::Error> for Error {
@@ -232,6 +1180,25 @@ impl From<std::io::Error> for Error {
     }
 }
 
+#[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Error {
+        Error::Image(err)
+    }
+}
+
+impl From<LoaderError> for Error {
+    fn from(err: LoaderError) -> Error {
+        Error::Loader(err.0)
+    }
+}
+
+impl From<fastnbt::error::Error> for Error {
+    fn from(err: fastnbt::error::Error) -> Error {
+        Error::Nbt(err)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl std::fmt::Display for Error {
@@ -246,14 +1213,32 @@ impl std::fmt::Display for Error {
                 f.write_str("compression scheme was not recognised for chunk")
             }
             Error::ChunkNotFound => f.write_str("chunk not found in region"),
+            Error::ChunkTooLarge => f.write_str("chunk is too large to store in a region"),
+            Error::ExternalChunkUnavailable => {
+                f.write_str("chunk is stored in an external file that could not be located")
+            }
+            Error::Nbt(e) => f.write_fmt(format_args!("nbt error: {}", e)),
+            Error::Schematic(e) => f.write_fmt(format_args!("schematic error: {}", e)),
+            #[cfg(feature = "image")]
+            Error::Image(e) => f.write_fmt(format_args!("image error: {}", e)),
+            Error::Network(e) => f.write_fmt(format_args!("network chunk error: {}", e)),
+            Error::UnsupportedCompression(id) => {
+                f.write_fmt(format_args!("unsupported compression scheme: {}", id))
+            }
+            Error::Upgrade(e) => f.write_fmt(format_args!("chunk upgrade error: {}", e)),
+            Error::ResourcePack(e) => f.write_fmt(format_args!("resource pack error: {}", e)),
+            Error::Palette(e) => f.write_fmt(format_args!("palette error: {}", e)),
+            Error::Http(e) => f.write_fmt(format_args!("http error: {}", e)),
+            Error::Archive(e) => f.write_fmt(format_args!("archive error: {}", e)),
+            Error::Edit(e) => f.write_fmt(format_args!("chunk edit error: {}", e)),
+            Error::Loader(e) => f.write_fmt(format_args!("loader error: {}", e)),
+            Error::LevelDb(e) => f.write_fmt(format_args!("leveldb error: {}", e)),
         }
     }
 }
 
 impl std::error::Error for Error {}
 
-#[cfg(test)]
-use std::io::Cursor;
 #[cfg(test)]
 pub struct Builder {
     inner: Vec<u8>,
@@ -271,6 +1256,43 @@ impl Builder {
         self
     }
 
+    /// Set the timestamp of the chunk at the given index in the header.
+    pub fn timestamp(mut self, index: usize, timestamp: u32) -> Self {
+        let pos = SECTOR_SIZE + 4 * index;
+        if self.inner.len() < pos + 4 {
+            self.inner.resize(pos + 4, 0);
+        }
+        self.inner[pos..pos + 4].copy_from_slice(&timestamp.to_be_bytes());
+        self
+    }
+
+    /// Append a zlib compressed chunk, padded to a whole number of sectors.
+    /// The first chunk is placed straight after the header.
+    pub fn chunk(mut self, data: &[u8]) -> Self {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        if self.inner.len() < HEADER_SIZE {
+            self.inner.resize(HEADER_SIZE, 0);
+        }
+
+        let mut enc = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        let compressed = enc.finish().unwrap();
+
+        self.inner
+            .extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        self.inner.push(CompressionScheme::Zlib as u8);
+        self.inner.extend_from_slice(&compressed);
+
+        let partial = self.inner.len() % SECTOR_SIZE;
+        if partial != 0 {
+            self.inner
+                .resize(self.inner.len() + SECTOR_SIZE - partial, 0);
+        }
+        self
+    }
+
     pub fn build(mut self) -> Cursor<Vec<u8>> {
         let padded_sector_count = (self.inner.len() / SECTOR_SIZE) + 1;
         self.inner.resize(padded_sector_count * SECTOR_SIZE, 0);
@@ -341,4 +1363,524 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn locations_from_header() -> Result<()> {
+        // The chunk at (1, 0) is missing.
+        let r = Builder::new()
+            .location(2, 1)
+            .location(0, 0)
+            .location(3, 2)
+            .build();
+        let r = RegionBuffer::new(r);
+
+        let locations = r.locations()?;
+        assert_eq!(2, locations.len());
+        assert_eq!((2, 0), (locations[1].x, locations[1].z));
+        assert_eq!(
+            (3, 2),
+            (locations[1].begin_sector, locations[1].sector_count)
+        );
+
+        assert!(r.chunk_exists(0, 0));
+        assert!(!r.chunk_exists(1, 0));
+        assert!(r.chunk_exists(2, 0));
+        assert!(!r.chunk_exists(31, 31));
+        assert!(!r.chunk_exists(32, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn locations_of_truncated_header() {
+        let r = Builder::new().location(2, 1).build_unpadded();
+        let r = RegionBuffer::new(r);
+
+        assert!(matches!(r.locations(), Err(Error::IO(_))));
+        assert!(r.chunk_exists(0, 0));
+        assert!(!r.chunk_exists(5, 0));
+    }
+
+    #[test]
+    fn iter_chunks() -> Result<()> {
+        // The chunk at (1, 0) comes first in the file.
+        let r = Builder::new()
+            .location(3, 1)
+            .location(2, 1)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4, 5])
+            .build();
+        let r = RegionBuffer::new(r);
+
+        let chunks = r.iter().collect::<Result<Vec<_>>>()?;
+        assert_eq!(chunks, vec![(1, 0, vec![1, 2, 3]), (0, 0, vec![4, 5])]);
+
+        assert_eq!(r.iter().take(1).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn iter_chunks_header_error() {
+        let r = Builder::new().location(2, 1).build_unpadded();
+        let r = RegionBuffer::new(r);
+
+        let mut iter = r.iter();
+        assert!(matches!(iter.next(), Some(Err(Error::IO(_)))));
+        assert!(iter.next().is_none());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_for_each_chunk() -> Result<()> {
+        use std::collections::HashMap;
+        use std::sync::Mutex;
+
+        // An empty compound with a single byte field 'a'.
+        let nbt = |a: u8| vec![10, 0, 0, 1, 0, 1, b'a', a, 0];
+
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .location(4, 1)
+            .chunk(&nbt(1))
+            .chunk(&nbt(2))
+            .chunk(&nbt(3))
+            .build();
+        let mut r = RegionBuffer::new(r);
+
+        let seen = Mutex::new(vec![]);
+        r.par_for_each_chunk(|x, z, chunk: HashMap<String, u8>| {
+            seen.lock().unwrap().push((x, z, chunk["a"]));
+        })?;
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(seen, vec![(0, 0, 1), (1, 0, 2), (2, 0, 3)]);
+        Ok(())
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_for_each_chunk_parse_error() {
+        let r = Builder::new().location(2, 1).chunk(&[1, 2, 3]).build();
+        let mut r = RegionBuffer::new(r);
+
+        let res = r.par_for_each_chunk(|_, _, _: std::collections::HashMap<String, u8>| {});
+        assert!(matches!(res, Err(Error::Nbt(_))));
+    }
+
+    #[test]
+    fn timestamp() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .timestamp(0, 1234)
+            .timestamp(31 * 32 + 31, u32::MAX)
+            .build();
+        let r = RegionBuffer::new(r);
+
+        assert_eq!(1234, r.chunk_timestamp(0, 0)?);
+        assert_eq!(0, r.chunk_timestamp(1, 0)?);
+        assert_eq!(u32::MAX, r.chunk_timestamp(31, 31)?);
+        assert!(matches!(
+            r.chunk_timestamp(32, 0),
+            Err(Error::InvalidOffset(32, 0))
+        ));
+
+        // The same through the Region trait, as Dimension uses.
+        let region: &dyn Region<JavaChunk> = &r;
+        assert!(region.chunk_exists(CCoord(0), CCoord(0)));
+        assert!(!region.chunk_exists(CCoord(1), CCoord(0)));
+        assert_eq!(Some(1234), region.chunk_timestamps().map(|t| t[0]));
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_chunk_gives_timestamps() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .timestamp(0, 100)
+            .timestamp(1, 200)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4, 5])
+            .build();
+        let mut r = RegionBuffer::new(r);
+
+        let mut seen = vec![];
+        r.for_each_chunk(|x, z, timestamp, data| {
+            seen.push((x, z, timestamp, data.clone()));
+        })?;
+        seen.sort();
+
+        assert_eq!(
+            seen,
+            vec![(0, 0, 100, vec![1, 2, 3]), (1, 0, 200, vec![4, 5])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_chunk_to_empty_region() -> Result<()> {
+        let mut r = RegionBuffer::new(Cursor::new(Vec::new()));
+        r.write_chunk(1, 2, &[1, 2, 3])?;
+
+        assert_eq!(
+            ChunkLocation {
+                begin_sector: 2,
+                sector_count: 1,
+                x: 1,
+                z: 2
+            },
+            r.chunk_location(1, 2)?
+        );
+        assert_eq!(vec![1, 2, 3], r.load_chunk(1, 2)?);
+        assert!(r.chunk_timestamp(1, 2)? > 0);
+        assert_eq!(0, r.chunk_timestamp(0, 0)?);
+        assert_eq!(3 * SECTOR_SIZE, r.into_inner().into_inner().len());
+        Ok(())
+    }
+
+    #[test]
+    fn write_chunk_replaces_in_place() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1])
+            .chunk(&[2])
+            .build();
+        let mut r = RegionBuffer::new(r);
+
+        r.write_chunk(0, 0, &[3, 4])?;
+
+        assert_eq!(2, r.chunk_location(0, 0)?.begin_sector);
+        assert_eq!(vec![3, 4], r.load_chunk(0, 0)?);
+        assert_eq!(vec![2], r.load_chunk(1, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn write_chunk_reallocates_when_grown() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1])
+            .chunk(&[2])
+            .build();
+        let mut r = RegionBuffer::new(r);
+
+        // Pseudo-random data doesn't compress, so needs more than one sector.
+        let mut state = 1u32;
+        let big: Vec<u8> = (0..2 * SECTOR_SIZE)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        r.write_chunk(0, 0, &big)?;
+
+        let loc = r.chunk_location(0, 0)?;
+        assert!(loc.begin_sector >= 4);
+        assert!(loc.sector_count > 1);
+        assert_eq!(big, r.load_chunk(0, 0)?);
+        assert_eq!(vec![2], r.load_chunk(1, 0)?);
+
+        // The old sector of the chunk is free for a new chunk to use.
+        r.write_chunk(2, 0, &[5])?;
+        assert_eq!(2, r.chunk_location(2, 0)?.begin_sector);
+        assert_eq!(vec![5], r.load_chunk(2, 0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn write_chunk_with_other_compression() -> Result<()> {
+        let data: Vec<u8> = b"minecraft:deepslate".repeat(100);
+
+        for scheme in [
+            CompressionScheme::Gzip,
+            CompressionScheme::Uncompressed,
+            #[cfg(feature = "lz4")]
+            CompressionScheme::Lz4,
+            #[cfg(feature = "zstd")]
+            CompressionScheme::Zstd,
+        ] {
+            let mut r = RegionBuffer::new(Cursor::new(Vec::new())).with_compression(scheme);
+            r.write_chunk(1, 1, &data)?;
+
+            let mut raw = Vec::new();
+            r.load_raw_chunk_into(1, 1, &mut raw)?;
+            assert_eq!(scheme, ChunkMeta::new(&raw)?.compression_scheme);
+
+            assert_eq!(data, r.load_chunk(1, 1)?);
+            let mut buf = Vec::new();
+            r.chunk_reader(1, 1)?.read_to_end(&mut buf)?;
+            assert_eq!(data, buf);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_compression() -> Result<()> {
+        let mut r = Builder::new().location(2, 1).chunk(&[1, 2, 3]).build();
+        r.get_mut()[HEADER_SIZE + 4] = 42;
+        let r = RegionBuffer::new(r);
+
+        assert!(matches!(
+            r.load_chunk(0, 0),
+            Err(Error::UnsupportedCompression(42))
+        ));
+        assert!(matches!(
+            r.chunk_reader(0, 0),
+            Err(Error::UnsupportedCompression(42))
+        ));
+
+        // The raw data is still available to decompress some other way.
+        let mut raw = Vec::new();
+        r.load_raw_chunk_into(0, 0, &mut raw)?;
+        let meta = ChunkMeta::new(&raw)?;
+        assert_eq!(CompressionScheme::Custom, meta.compression_scheme);
+        assert_eq!(42, meta.scheme_id);
+
+        let mut r =
+            RegionBuffer::new(Cursor::new(Vec::new())).with_compression(CompressionScheme::Custom);
+        assert!(matches!(
+            r.write_chunk(0, 0, &[1]),
+            Err(Error::UnsupportedCompression(127))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn remove_chunk() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .timestamp(0, 100)
+            .chunk(&[1])
+            .build();
+        let mut r = RegionBuffer::new(r);
+
+        r.remove_chunk(0, 0)?;
+
+        assert!(matches!(r.load_chunk(0, 0), Err(Error::ChunkNotFound)));
+        assert_eq!(0, r.chunk_timestamp(0, 0)?);
+        assert!(matches!(
+            r.remove_chunk(32, 0),
+            Err(Error::InvalidOffset(32, 0))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn compact_region_packs_chunks() -> Result<()> {
+        // Chunk (1, 0) is allocated more sectors than it needs, and there is
+        // a dead sector before it.
+        let r = Builder::new()
+            .location(2, 1)
+            .location(4, 3)
+            .timestamp(1, 200)
+            .chunk(&[1, 2, 3])
+            .chunk(&[9])
+            .chunk(&[4, 5])
+            .build();
+        let r = RegionBuffer::new(r);
+        r.data().get_mut().resize(8 * SECTOR_SIZE, 0);
+
+        let mut out = Vec::new();
+        let report = compact_region(&r, &mut out)?;
+
+        assert_eq!(
+            CompactReport {
+                chunks: 2,
+                removed: 0,
+                bytes_before: 8 * SECTOR_SIZE as u64,
+                bytes_after: 4 * SECTOR_SIZE as u64,
+            },
+            report
+        );
+        assert_eq!(4 * SECTOR_SIZE as u64, report.bytes_reclaimed());
+
+        let compacted = RegionBuffer::new(Cursor::new(out));
+        assert_eq!(3, compacted.chunk_location(1, 0)?.begin_sector);
+        assert_eq!(1, compacted.chunk_location(1, 0)?.sector_count);
+        assert_eq!(vec![1, 2, 3], compacted.load_chunk(0, 0)?);
+        assert_eq!(vec![4, 5], compacted.load_chunk(1, 0)?);
+        assert_eq!(200, compacted.chunk_timestamp(1, 0)?);
+        assert!(matches!(
+            compacted.load_chunk(2, 0),
+            Err(Error::ChunkNotFound)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn load_raw_chunk_into_reuses_buffer() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4])
+            .build();
+        let r = RegionBuffer::new(r);
+
+        let mut buf = Vec::new();
+        r.load_raw_chunk_into(0, 0, &mut buf)?;
+        assert_eq!(vec![1, 2, 3], decompress_chunk(&buf)?);
+
+        r.load_raw_chunk_into(1, 0, &mut buf)?;
+        assert_eq!(vec![4], decompress_chunk(&buf)?);
+
+        assert!(matches!(
+            r.load_raw_chunk_into(2, 0, &mut buf),
+            Err(Error::ChunkNotFound)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_reader() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4])
+            .build();
+        let r = RegionBuffer::new(r);
+
+        let mut buf = Vec::new();
+        r.chunk_reader(1, 0)?.read_to_end(&mut buf)?;
+        assert_eq!(vec![4], buf);
+
+        buf.clear();
+        r.chunk_reader(0, 0)?.read_to_end(&mut buf)?;
+        assert_eq!(vec![1, 2, 3], buf);
+
+        assert!(matches!(r.chunk_reader(2, 0), Err(Error::ChunkNotFound)));
+        Ok(())
+    }
+
+    #[test]
+    fn region_buffer_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RegionBuffer<std::fs::File>>();
+        assert_send_sync::<RegionBuffer<Cursor<Vec<u8>>>>();
+    }
+
+    #[test]
+    fn load_chunks_from_many_threads() -> Result<()> {
+        let mut builder = Builder::new();
+        for i in 0..32 {
+            builder = builder.location(2 + i, 1);
+        }
+        for i in 0..32u8 {
+            builder = builder.chunk(&[i]);
+        }
+        let r = RegionBuffer::new(builder.build());
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for x in 0..32 {
+                        assert_eq!(vec![x as u8], r.load_chunk(x, 0).unwrap());
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    /// Data that zlib can't compress, so that it needs more than 255 sectors.
+    fn oversized_chunk() -> Vec<u8> {
+        let mut state = 1u32;
+        (0..256 * SECTOR_SIZE)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn external_chunk_round_trip() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("fastanvil-mcc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let mcc = dir.join("c.-31.33.mcc");
+
+        let mut r = RegionBuffer::new(Cursor::new(Vec::new())).with_external_chunks(
+            &dir,
+            RCoord(-1),
+            RCoord(1),
+        );
+
+        let big = oversized_chunk();
+        r.write_chunk(1, 1, &big)?;
+        assert!(mcc.exists());
+        assert_eq!(1, r.chunk_location(1, 1)?.sector_count);
+        assert_eq!(big, r.load_chunk(1, 1)?);
+
+        let mut buf = Vec::new();
+        r.chunk_reader(1, 1)?.read_to_end(&mut buf)?;
+        assert_eq!(big, buf);
+
+        // Compacting leaves the chunk where it is.
+        let report = compact_region(&r, std::io::sink())?;
+        assert_eq!(HEADER_SIZE as u64 + SECTOR_SIZE as u64, report.bytes_after);
+
+        // A small enough chunk goes back in the region.
+        r.write_chunk(1, 1, &[1, 2, 3])?;
+        assert!(!mcc.exists());
+        assert_eq!(vec![1, 2, 3], r.load_chunk(1, 1)?);
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn external_chunk_kept_when_write_fails() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("fastanvil-mcc-fail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let mcc = dir.join("c.0.0.mcc");
+
+        let mut r = RegionBuffer::new(Cursor::new(Vec::new())).with_external_chunks(
+            &dir,
+            RCoord(0),
+            RCoord(0),
+        );
+        r.write_chunk(0, 0, &oversized_chunk())?;
+
+        // A fixed size buffer has no room for a chunk needing more sectors.
+        let mut data = r.into_inner().into_inner();
+        let mut r = RegionBuffer::new(Cursor::new(&mut data[..])).with_external_chunks(
+            &dir,
+            RCoord(0),
+            RCoord(0),
+        );
+        assert!(r
+            .write_chunk(0, 0, &oversized_chunk()[..2 * SECTOR_SIZE])
+            .is_err());
+        assert!(mcc.exists());
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn external_chunk_needs_location() -> Result<()> {
+        let mut r = RegionBuffer::new(Cursor::new(Vec::new()));
+        assert!(matches!(
+            r.write_chunk(0, 0, &oversized_chunk()),
+            Err(Error::ChunkTooLarge)
+        ));
+
+        // A chunk marked as external, with zlib compression.
+        let mut data = Builder::new().location(2, 1).build().into_inner();
+        data.resize(HEADER_SIZE, 0);
+        data.extend_from_slice(&[0, 0, 0, 1, 0x82]);
+        data.resize(3 * SECTOR_SIZE, 0);
+
+        let r = RegionBuffer::new(Cursor::new(data));
+        assert!(matches!(
+            r.load_chunk(0, 0),
+            Err(Error::ExternalChunkUnavailable)
+        ));
+        assert!(r.validate()?.is_ok());
+        Ok(())
+    }
 }
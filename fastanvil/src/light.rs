@@ -0,0 +1,673 @@
+//! Calculate the light of chunks. Minecraft stores the light level of every
+//! block, and only recalculates it when blocks change in game. Tools that
+//! edit chunks need to update the light themselves, or the game will show
+//! dark patches where blocks were removed and bright ones inside new walls.
+//!
+//! Light is calculated the same way as the game does, spreading out from
+//! light sources and the sky and dropping by one for each block travelled,
+//! or more through blocks like leaves and water. How much light a block
+//! blocks and gives off comes from [`light_opacity`] and
+//! [`light_emission`], which cover vanilla blocks but are approximate for
+//! blocks that are not full cubes. Each chunk is lit on its own, so light
+//! from neighbouring chunks is not spread across chunk borders.
+//!
+//! ```no_run
+//! use fastanvil::{light, ChunkNbt, RegionBuffer};
+//!
+//! let file = std::fs::OpenOptions::new().read(true).write(true).open("r.0.0.mca")?;
+//! let mut region = RegionBuffer::new(file);
+//!
+//! let mut nbt: ChunkNbt = fastnbt::de::from_bytes(&region.load_chunk(0, 0)?)?;
+//! // ...edit the blocks of the chunk...
+//! light::relight_chunk(&mut nbt, true)?;
+//! region.write_chunk(0, 0, &fastnbt::ser::to_bytes(&nbt)?)?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use fastnbt::{ByteArray, Value};
+
+use crate::{Block, Chunk, ChunkNbt, JavaChunk, Result, VersionedChunk};
+
+/// The brightest light level.
+pub const MAX_LIGHT: u8 = 15;
+
+/// Blocks that let light through unchanged, besides those matched by
+/// [`TRANSPARENT_SUFFIXES`].
+const TRANSPARENT: &[&str] = &[
+    "air",
+    "cave_air",
+    "void_air",
+    "glass",
+    "barrier",
+    "structure_void",
+    "light",
+    "beacon",
+    "fire",
+    "soul_fire",
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "end_rod",
+    "lightning_rod",
+    "lantern",
+    "soul_lantern",
+    "chain",
+    "iron_bars",
+    "ladder",
+    "lever",
+    "rail",
+    "snow",
+    "cake",
+    "repeater",
+    "comparator",
+    "redstone_wire",
+    "tripwire",
+    "tripwire_hook",
+    "daylight_detector",
+    "flower_pot",
+    "brewing_stand",
+    "cauldron",
+    "water_cauldron",
+    "lava_cauldron",
+    "powder_snow_cauldron",
+    "hopper",
+    "enchanting_table",
+    "anvil",
+    "chipped_anvil",
+    "damaged_anvil",
+    "chest",
+    "trapped_chest",
+    "ender_chest",
+    "bell",
+    "campfire",
+    "soul_campfire",
+    "conduit",
+    "dragon_egg",
+    "spawner",
+    "lectern",
+    "stonecutter",
+    "grindstone",
+    "scaffolding",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "grass",
+    "short_grass",
+    "tall_grass",
+    "fern",
+    "large_fern",
+    "dead_bush",
+    "vine",
+    "glow_lichen",
+    "sculk_vein",
+    "lily_pad",
+    "sea_pickle",
+    "cocoa",
+    "nether_wart",
+    "sweet_berry_bush",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "sugar_cane",
+    "cactus",
+    "bamboo",
+    "bamboo_sapling",
+    "big_dripleaf",
+    "big_dripleaf_stem",
+    "small_dripleaf",
+    "pointed_dripstone",
+    "amethyst_cluster",
+    "cave_vines",
+    "cave_vines_plant",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "spore_blossom",
+    "azalea",
+    "flowering_azalea",
+    "turtle_egg",
+    "frogspawn",
+    "pink_petals",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "wither_rose",
+    "sunflower",
+    "lilac",
+    "rose_bush",
+    "peony",
+    "torchflower",
+    "candle",
+];
+
+/// Endings of the names of block families that let light through, such as
+/// every kind of stairs.
+const TRANSPARENT_SUFFIXES: &[&str] = &[
+    "_stairs",
+    "_fence",
+    "_fence_gate",
+    "_wall",
+    "_door",
+    "_trapdoor",
+    "_sign",
+    "_banner",
+    "_carpet",
+    "_pressure_plate",
+    "_button",
+    "_bed",
+    "_pane",
+    "_glass",
+    "_head",
+    "_skull",
+    "_sapling",
+    "_mushroom",
+    "_fungus",
+    "_roots",
+    "_rail",
+    "_tulip",
+    "_candle",
+    "_candle_cake",
+    "_coral",
+    "_coral_fan",
+    "_bud",
+    "_propagule",
+];
+
+/// Blocks that let light through, but reduce it by one more than usual.
+const DAMPENING: &[&str] = &[
+    "water",
+    "bubble_column",
+    "kelp",
+    "kelp_plant",
+    "seagrass",
+    "tall_seagrass",
+    "ice",
+    "frosted_ice",
+    "cobweb",
+    "slime_block",
+    "honey_block",
+];
+
+/// How much light is reduced by passing through a block, on top of the one
+/// level it drops for every block travelled. Solid blocks block all light,
+/// giving [`MAX_LIGHT`]. Waterlogged blocks reduce light at least as much as
+/// water.
+///
+/// ```
+/// # use fastanvil::{Block, light::light_opacity};
+/// let block = Block::new("minecraft:stone".to_owned(), Default::default());
+/// assert_eq!(15, light_opacity(&block));
+/// let block = Block::new("minecraft:oak_leaves".to_owned(), Default::default());
+/// assert_eq!(1, light_opacity(&block));
+/// let block = Block::new("minecraft:torch".to_owned(), Default::default());
+/// assert_eq!(0, light_opacity(&block));
+/// ```
+pub fn light_opacity(block: &Block) -> u8 {
+    let name = block.name().trim_start_matches("minecraft:");
+
+    let opacity = if DAMPENING.contains(&name) || name.ends_with("_leaves") {
+        1
+    } else if name.ends_with("_slab") {
+        match block.property("type") {
+            Some("double") => MAX_LIGHT,
+            _ => 0,
+        }
+    } else if name == "tinted_glass" {
+        // The one glass that blocks light.
+        MAX_LIGHT
+    } else if TRANSPARENT.contains(&name)
+        || name.starts_with("potted_")
+        || TRANSPARENT_SUFFIXES.iter().any(|s| name.ends_with(s))
+    {
+        0
+    } else {
+        MAX_LIGHT
+    };
+
+    if block.is_waterlogged() {
+        opacity.max(1)
+    } else {
+        opacity
+    }
+}
+
+/// The light level a block gives off, taking into account properties such as
+/// whether a furnace is lit or how many candles there are.
+///
+/// ```
+/// # use fastanvil::{Block, light::light_emission};
+/// let block = Block::new("minecraft:glowstone".to_owned(), Default::default());
+/// assert_eq!(15, light_emission(&block));
+/// let block = Block::new("minecraft:torch".to_owned(), Default::default());
+/// assert_eq!(14, light_emission(&block));
+/// let block = Block::new("minecraft:furnace".to_owned(), Default::default());
+/// assert_eq!(0, light_emission(&block));
+/// ```
+pub fn light_emission(block: &Block) -> u8 {
+    let name = block.name().trim_start_matches("minecraft:");
+    let lit = block.property("lit") == Some("true");
+    let number = |prop| {
+        block
+            .property(prop)
+            .and_then(|v| v.parse::<u8>().ok())
+            .unwrap_or(0)
+    };
+
+    match name {
+        "glowstone"
+        | "sea_lantern"
+        | "jack_o_lantern"
+        | "lava"
+        | "lava_cauldron"
+        | "fire"
+        | "shroomlight"
+        | "beacon"
+        | "conduit"
+        | "lantern"
+        | "end_portal"
+        | "end_gateway"
+        | "ochre_froglight"
+        | "verdant_froglight"
+        | "pearlescent_froglight" => 15,
+        "torch" | "wall_torch" | "end_rod" => 14,
+        "nether_portal" => 11,
+        "soul_torch" | "soul_wall_torch" | "soul_lantern" | "soul_fire" | "crying_obsidian" => 10,
+        "glow_lichen" | "enchanting_table" | "ender_chest" => 7,
+        "amethyst_cluster" => 5,
+        "large_amethyst_bud" => 4,
+        "magma_block" => 3,
+        "medium_amethyst_bud" => 2,
+        "small_amethyst_bud"
+        | "brewing_stand"
+        | "brown_mushroom"
+        | "dragon_egg"
+        | "end_portal_frame"
+        | "sculk_sensor"
+        | "calibrated_sculk_sensor" => 1,
+        "redstone_lamp" | "campfire" if lit => 15,
+        "furnace" | "blast_furnace" | "smoker" if lit => 13,
+        "soul_campfire" if lit => 10,
+        "redstone_ore" | "deepslate_redstone_ore" if lit => 9,
+        "redstone_torch" | "redstone_wall_torch" if lit => 7,
+        "cave_vines" | "cave_vines_plant" if block.property("berries") == Some("true") => 14,
+        "sea_pickle" if block.is_waterlogged() => 3 * (number("pickles") + 1),
+        "respawn_anchor" => [0, 3, 7, 11, 15][number("charges").min(4) as usize],
+        "light" => number("level").min(MAX_LIGHT),
+        n if lit && n.ends_with("candle_cake") => 3,
+        n if lit && (n == "candle" || n.ends_with("_candle")) => 3 * number("candles"),
+        _ => 0,
+    }
+}
+
+/// The light levels of every block of a chunk, as calculated by
+/// [`ChunkLight::calculate`].
+#[derive(Debug, Clone)]
+pub struct ChunkLight {
+    y_range: Range<isize>,
+
+    /// Indexed by `(y - y_range.start) * 256 + z * 16 + x`.
+    block: Vec<u8>,
+    sky: Option<Vec<u8>>,
+}
+
+impl ChunkLight {
+    /// Calculate the light of every block of a chunk within its
+    /// [`y_range`](Chunk::y_range). Sky light is only calculated if `sky` is
+    /// true, which should be false for dimensions without a sky such as the
+    /// nether. Blocks missing from the chunk are treated as air.
+    pub fn calculate<C: Chunk + ?Sized>(chunk: &C, sky: bool) -> Self {
+        let y_range = chunk.y_range();
+        let height = (y_range.end - y_range.start).max(0) as usize;
+
+        // Many blocks share a state, so only work out the light of each
+        // state once.
+        let mut cache: HashMap<(&str, bool), (u8, u8)> = HashMap::new();
+        let mut opacity = vec![0; height * 256];
+        let mut emission = vec![0; height * 256];
+
+        for (i, y) in y_range.clone().enumerate() {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let block = match chunk.block(x, y, z) {
+                        Some(block) => block,
+                        None => continue,
+                    };
+
+                    let key = (block.encoded_description(), block.is_waterlogged());
+                    let (o, e) = *cache
+                        .entry(key)
+                        .or_insert_with(|| (light_opacity(block), light_emission(block)));
+
+                    opacity[i * 256 + z * 16 + x] = o;
+                    emission[i * 256 + z * 16 + x] = e;
+                }
+            }
+        }
+
+        let sources = (0..emission.len()).filter(|&i| emission[i] > 0).collect();
+        let mut block = emission;
+        spread(&mut block, &opacity, sources);
+
+        let sky = sky.then(|| {
+            let mut light = vec![0; height * 256];
+            let mut sources = VecDeque::new();
+
+            // Sky light shines straight down without dropping, until
+            // something is in the way.
+            for column in 0..256 {
+                let mut level = MAX_LIGHT;
+                for i in (0..height).rev().map(|y| y * 256 + column) {
+                    level = level.saturating_sub(opacity[i]);
+                    if level == 0 {
+                        break;
+                    }
+                    light[i] = level;
+                    sources.push_back(i);
+                }
+            }
+
+            spread(&mut light, &opacity, sources);
+            light
+        });
+
+        Self {
+            y_range,
+            block,
+            sky,
+        }
+    }
+
+    /// The range of Y values the light was calculated for.
+    pub fn y_range(&self) -> Range<isize> {
+        self.y_range.clone()
+    }
+
+    fn index(&self, x: usize, y: isize, z: usize) -> Option<usize> {
+        if x >= 16 || z >= 16 || !self.y_range.contains(&y) {
+            return None;
+        }
+
+        Some((y - self.y_range.start) as usize * 256 + z * 16 + x)
+    }
+
+    /// Get the block light at the given coordinates. Returns None outside of
+    /// the chunk.
+    pub fn block_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        Some(self.block[self.index(x, y, z)?])
+    }
+
+    /// Get the sky light at the given coordinates. Returns None outside of
+    /// the chunk, or if sky light was not calculated.
+    pub fn sky_light(&self, x: usize, y: isize, z: usize) -> Option<u8> {
+        Some(self.sky.as_ref()?[self.index(x, y, z)?])
+    }
+
+    /// Get the block light of a section packed as Minecraft stores it, ready
+    /// to be saved as the `BlockLight` tag of the section. Returns None if
+    /// the section is outside of the chunk.
+    pub fn section_block_light(&self, section_y: i8) -> Option<ByteArray> {
+        self.pack_section(&self.block, section_y)
+    }
+
+    /// Get the sky light of a section packed as Minecraft stores it, ready
+    /// to be saved as the `SkyLight` tag of the section. Returns None if the
+    /// section is outside of the chunk, or if sky light was not calculated.
+    pub fn section_sky_light(&self, section_y: i8) -> Option<ByteArray> {
+        self.pack_section(self.sky.as_ref()?, section_y)
+    }
+
+    fn pack_section(&self, light: &[u8], section_y: i8) -> Option<ByteArray> {
+        let start = self.index(0, section_y as isize * 16, 0)?;
+        let levels = light.get(start..start + 16 * 16 * 16)?;
+
+        let packed = levels
+            .chunks(2)
+            .map(|pair| (pair[0] | pair[1] << 4) as i8)
+            .collect();
+
+        Some(ByteArray::new(packed))
+    }
+}
+
+/// Spread light out from the given blocks, dropping by one for each block
+/// travelled plus the opacity of the block it enters.
+fn spread(light: &mut [u8], opacity: &[u8], mut queue: VecDeque<usize>) {
+    let len = light.len();
+
+    while let Some(i) = queue.pop_front() {
+        let level = light[i];
+        if level <= 1 {
+            continue;
+        }
+
+        let (x, z) = (i % 16, (i / 16) % 16);
+        let neighbours = [
+            (x > 0).then(|| i - 1),
+            (x < 15).then(|| i + 1),
+            (z > 0).then(|| i - 16),
+            (z < 15).then(|| i + 16),
+            i.checked_sub(256),
+            Some(i + 256).filter(|&n| n < len),
+        ];
+
+        for n in neighbours.iter().flatten().copied() {
+            let new = level.saturating_sub(opacity[n].max(1));
+            if new > light[n] {
+                light[n] = new;
+                queue.push_back(n);
+            }
+        }
+    }
+}
+
+/// Recalculate the light of a chunk and store it in the chunk's NBT, so that
+/// the game shows it correctly after the chunk has been edited. See
+/// [`ChunkLight::calculate`] for what `sky` means.
+///
+/// Light is only stored for the sections the chunk already has, so sections
+/// that have been added need to be in the NBT before calling this. Whether
+/// the chunk is marked as lit is left alone, since light from neighbouring
+/// chunks is missing. The game only fixes that if it lights the chunk again
+/// itself.
+pub fn relight_chunk(nbt: &mut ChunkNbt, sky: bool) -> Result<()> {
+    let chunk = JavaChunk::from_bytes(&fastnbt::ser::to_bytes(nbt)?)?;
+    let light = ChunkLight::calculate(&chunk, sky);
+
+    let (container, sections_tag) = match &chunk {
+        VersionedChunk::V1_18(_) => (Some(nbt), "sections"),
+        _ => (level_mut(nbt), "Sections"),
+    };
+
+    let container = match container {
+        Some(container) => container,
+        None => return Ok(()),
+    };
+
+    if let Some(Value::List(sections)) = container.get_mut(sections_tag) {
+        for section in sections {
+            let section = match section {
+                Value::Compound(section) => section,
+                _ => continue,
+            };
+
+            let y = match section.get("Y") {
+                Some(Value::Byte(y)) => *y,
+                _ => continue,
+            };
+
+            if let Some(block_light) = light.section_block_light(y) {
+                section.insert("BlockLight".to_owned(), Value::ByteArray(block_light));
+            }
+            if let Some(sky_light) = light.section_sky_light(y) {
+                section.insert("SkyLight".to_owned(), Value::ByteArray(sky_light));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn level_mut(nbt: &mut ChunkNbt) -> Option<&mut ChunkNbt> {
+    match nbt.get_mut("Level") {
+        Some(Value::Compound(level)) => Some(level),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{biome::Biome, ChunkStatus, HeightMode, AIR};
+
+    /// A chunk of stone with the given blocks in it.
+    struct TestChunk {
+        stone: Block,
+        blocks: HashMap<(usize, isize, usize), Block>,
+    }
+
+    impl TestChunk {
+        fn new(blocks: &[((usize, isize, usize), &str)]) -> Self {
+            Self {
+                stone: Block::from_state_str("minecraft:stone"),
+                blocks: blocks
+                    .iter()
+                    .map(|(pos, name)| (*pos, Block::from_state_str(name)))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Chunk for TestChunk {
+        fn status(&self) -> ChunkStatus {
+            ChunkStatus::Full
+        }
+
+        fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+            32
+        }
+
+        fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+            None
+        }
+
+        fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+            if y >= 24 {
+                return Some(&AIR);
+            }
+            Some(self.blocks.get(&(x, y, z)).unwrap_or(&self.stone))
+        }
+
+        fn y_range(&self) -> Range<isize> {
+            0..32
+        }
+    }
+
+    #[test]
+    fn torch_light_drops_with_distance() {
+        let mut blocks = vec![((8, 5, 8), "minecraft:torch")];
+        for x in 0..16 {
+            if x != 8 {
+                blocks.push(((x, 5, 8), "minecraft:air"));
+            }
+        }
+
+        let light = ChunkLight::calculate(&TestChunk::new(&blocks), true);
+
+        assert_eq!(Some(14), light.block_light(8, 5, 8));
+        assert_eq!(Some(13), light.block_light(7, 5, 8));
+        assert_eq!(Some(7), light.block_light(15, 5, 8));
+        assert_eq!(Some(6), light.block_light(0, 5, 8));
+
+        // Light doesn't get into solid blocks, or the sky into the tunnel.
+        assert_eq!(Some(0), light.block_light(8, 6, 8));
+        assert_eq!(Some(0), light.sky_light(8, 5, 8));
+    }
+
+    #[test]
+    fn sky_shines_down_shafts() {
+        let mut blocks = vec![((3, 10, 4), "minecraft:oak_leaves")];
+        for y in 10..24 {
+            blocks.push(((4, y, 4), "minecraft:air"));
+        }
+        let light = ChunkLight::calculate(&TestChunk::new(&blocks), true);
+
+        assert_eq!(Some(15), light.sky_light(0, 31, 0));
+        assert_eq!(Some(15), light.sky_light(4, 10, 4));
+        assert_eq!(Some(0), light.sky_light(4, 9, 4));
+        assert_eq!(Some(14), light.sky_light(3, 10, 4));
+        assert_eq!(Some(0), light.block_light(4, 10, 4));
+    }
+
+    #[test]
+    fn no_sky_without_sky() {
+        let light = ChunkLight::calculate(&TestChunk::new(&[]), false);
+        assert_eq!(None, light.sky_light(0, 31, 0));
+        assert!(light.section_sky_light(1).is_none());
+        assert_eq!(Some(0), light.block_light(0, 31, 0));
+    }
+
+    #[test]
+    fn sections_are_packed_as_nibbles() {
+        let light = ChunkLight::calculate(&TestChunk::new(&[]), true);
+
+        let top = light.section_sky_light(1).unwrap();
+        assert_eq!(2048, top.len());
+        // Y 24 and up is open to the sky.
+        assert!(top[8 * 128..].iter().all(|&b| b as u8 == 0xff));
+        assert!(light.section_block_light(2).is_none());
+        assert!(light.section_block_light(-1).is_none());
+    }
+
+    #[test]
+    fn emission_depends_on_state() {
+        let block = |state: &str| Block::from_state_str(state);
+
+        assert_eq!(13, light_emission(&block("minecraft:furnace[lit=true]")));
+        assert_eq!(
+            12,
+            light_emission(&block("minecraft:candle[candles=4,lit=true]"))
+        );
+        assert_eq!(
+            15,
+            light_emission(&block("minecraft:sea_pickle[pickles=4,waterlogged=true]"))
+        );
+        assert_eq!(0, light_emission(&block("minecraft:sea_pickle[pickles=4]")));
+        assert_eq!(
+            11,
+            light_emission(&block("minecraft:respawn_anchor[charges=3]"))
+        );
+    }
+
+    #[test]
+    fn opacity_depends_on_state() {
+        let block = |state: &str| Block::from_state_str(state);
+
+        assert_eq!(0, light_opacity(&block("minecraft:oak_slab[type=top]")));
+        assert_eq!(15, light_opacity(&block("minecraft:oak_slab[type=double]")));
+        assert_eq!(
+            1,
+            light_opacity(&block("minecraft:oak_stairs[waterlogged=true]"))
+        );
+        assert_eq!(15, light_opacity(&block("minecraft:oak_planks")));
+        assert_eq!(0, light_opacity(&block("minecraft:potted_fern")));
+        assert_eq!(0, light_opacity(&block("minecraft:red_stained_glass")));
+        assert_eq!(15, light_opacity(&block("minecraft:tinted_glass")));
+    }
+}
@@ -0,0 +1,218 @@
+//! LZ4 compression as used for chunks since Minecraft 1.20.5. Chunks are
+//! stored in the block format of lz4-java's `LZ4BlockOutputStream`: a series
+//! of blocks, each with a header giving its length and a checksum of its
+//! uncompressed data, ended by an empty block.
+
+use std::hash::Hasher;
+use std::io::{self, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+use twox_hash::XxHash32;
+
+const MAGIC: &[u8; 8] = b"LZ4Block";
+const HEADER_LEN: usize = MAGIC.len() + 13;
+
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+
+/// The block size lz4-java uses by default, 64 KiB.
+const BLOCK_SIZE: usize = 1 << 16;
+
+/// Low bits of the token, the block size as a power of two above 1 KiB.
+const BLOCK_SIZE_LEVEL: u8 = 6;
+
+const CHECKSUM_SEED: u32 = 0x9747b28c;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("lz4: {}", msg))
+}
+
+/// Decompresses LZ4 block-stream data as it is read.
+pub(crate) struct Lz4Decoder<R: Read> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+    compressed: Vec<u8>,
+    finished: bool,
+}
+
+impl<R: Read> Lz4Decoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            block: Vec::new(),
+            pos: 0,
+            compressed: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Read the next block into `self.block`. Sets `finished` on reaching the
+    /// end of the stream.
+    fn next_block(&mut self) -> io::Result<()> {
+        let mut header = [0u8; HEADER_LEN];
+        self.inner.read_exact(&mut header)?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid("bad block magic"));
+        }
+
+        let token = header[8];
+        let compressed_len = LittleEndian::read_i32(&header[9..]);
+        let len = LittleEndian::read_i32(&header[13..]);
+        let checksum = LittleEndian::read_u32(&header[17..]);
+
+        if compressed_len < 0 || len < 0 {
+            return Err(invalid("negative block length"));
+        }
+        let (compressed_len, len) = (compressed_len as usize, len as usize);
+        let max_len = 1 << (10 + (token & 0x0f));
+        if len > max_len {
+            return Err(invalid("block larger than its block size"));
+        }
+
+        self.pos = 0;
+        self.block.clear();
+
+        if len == 0 {
+            if compressed_len != 0 {
+                return Err(invalid("empty block with data"));
+            }
+            self.finished = true;
+            return Ok(());
+        }
+
+        match token & 0xf0 {
+            METHOD_RAW => {
+                if compressed_len != len {
+                    return Err(invalid("raw block length mismatch"));
+                }
+                self.block.resize(len, 0);
+                self.inner.read_exact(&mut self.block)?;
+            }
+            METHOD_LZ4 => {
+                self.compressed.resize(compressed_len, 0);
+                self.inner.read_exact(&mut self.compressed)?;
+                self.block.resize(len, 0);
+                let n = lz4_flex::block::decompress_into(&self.compressed, &mut self.block)
+                    .map_err(|e| invalid(&e.to_string()))?;
+                if n != len {
+                    return Err(invalid("block shorter than its length"));
+                }
+            }
+            _ => return Err(invalid("unknown block compression method")),
+        }
+
+        if checksum != block_checksum(&self.block) {
+            return Err(invalid("block checksum mismatch"));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Lz4Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.next_block()?;
+        }
+
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Compress `data` into the LZ4 block-stream format.
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 2 + HEADER_LEN);
+
+    for chunk in data.chunks(BLOCK_SIZE) {
+        let block = lz4_flex::block::compress(chunk);
+
+        let (method, body) = if block.len() < chunk.len() {
+            (METHOD_LZ4, &block[..])
+        } else {
+            (METHOD_RAW, chunk)
+        };
+
+        write_header(
+            &mut out,
+            method,
+            body.len(),
+            chunk.len(),
+            block_checksum(chunk),
+        );
+        out.extend_from_slice(body);
+    }
+
+    write_header(&mut out, METHOD_RAW, 0, 0, 0);
+    out
+}
+
+fn write_header(out: &mut Vec<u8>, method: u8, compressed_len: usize, len: usize, checksum: u32) {
+    let mut header = [0u8; HEADER_LEN];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[8] = method | BLOCK_SIZE_LEVEL;
+    LittleEndian::write_i32(&mut header[9..], compressed_len as i32);
+    LittleEndian::write_i32(&mut header[13..], len as i32);
+    LittleEndian::write_u32(&mut header[17..], checksum);
+    out.extend_from_slice(&header);
+}
+
+/// The checksum lz4-java stores for each block: XXH32 with its seed, keeping
+/// only the low 28 bits.
+fn block_checksum(data: &[u8]) -> u32 {
+    let mut hasher = XxHash32::with_seed(CHECKSUM_SEED);
+    hasher.write(data);
+    hasher.finish() as u32 & 0x0fff_ffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        Lz4Decoder::new(data).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn round_trip() -> io::Result<()> {
+        let repetitive: Vec<u8> = b"minecraft:stone"
+            .iter()
+            .copied()
+            .cycle()
+            .take(200_000)
+            .collect();
+        let noisy: Vec<u8> = (0..5000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+            .collect();
+
+        for data in [&b""[..], b"short", &repetitive[..], &noisy[..]] {
+            let compressed = compress(data);
+            assert_eq!(data, &decompress(&compressed)?[..]);
+        }
+
+        assert!(compress(&repetitive).len() < repetitive.len() / 10);
+        Ok(())
+    }
+
+    #[test]
+    fn bad_checksum_is_error() {
+        let mut compressed = compress(b"some chunk data, some chunk data");
+        compressed[17] ^= 1;
+        assert!(decompress(&compressed).is_err());
+    }
+
+    #[test]
+    fn truncated_is_error() {
+        let compressed = compress(&[7; 1000]);
+        assert!(decompress(&compressed[..compressed.len() - 21]).is_err());
+        assert!(decompress(&compressed[..30]).is_err());
+    }
+}
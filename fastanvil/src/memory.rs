@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+
+use crate::{Chunk, ChunkNbt, ChunkPos, LoaderResult, RCoord, RegionBuffer, RegionLoader, Result};
+
+/// A dimension held entirely in memory, as the NBT of each chunk. Chunks can
+/// be created and edited freely, loaded through a
+/// [`Dimension`](crate::Dimension) like any other loader, and then written
+/// out as region files.
+///
+/// This suits generating worlds from scratch, and testing tools that work on
+/// worlds without needing region files on disk.
+///
+/// Each region is serialized from the chunks when it is loaded, so a
+/// `Dimension` over the loader does not see edits made after it cached the
+/// region. Edit through [`loader_mut`](crate::Dimension::loader_mut) and
+/// then [`evict`](crate::Dimension::evict) the region to see them.
+///
+/// ```no_run
+/// # use fastanvil::{CCoord, ChunkNbt, JavaChunk, MemoryRegionLoader};
+/// # use fastnbt::Value;
+/// let mut loader = MemoryRegionLoader::<JavaChunk>::new();
+///
+/// let mut chunk = ChunkNbt::new();
+/// chunk.insert("Status".to_owned(), Value::String("full".to_owned()));
+/// loader.insert_chunk((CCoord(0), CCoord(0)), chunk);
+///
+/// loader.write_regions("world/region")?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct MemoryRegionLoader<C: Chunk> {
+    chunks: HashMap<ChunkPos, ChunkNbt>,
+    p: PhantomData<fn() -> C>,
+}
+
+impl<C: Chunk> MemoryRegionLoader<C> {
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+            p: PhantomData,
+        }
+    }
+
+    /// Get the NBT of the chunk at the given position, a [`ChunkPos`] or a
+    /// tuple of (absolute) chunk coordinates.
+    pub fn chunk(&self, pos: impl Into<ChunkPos>) -> Option<&ChunkNbt> {
+        self.chunks.get(&pos.into())
+    }
+
+    /// Get the NBT of the chunk at the given position for editing.
+    pub fn chunk_mut(&mut self, pos: impl Into<ChunkPos>) -> Option<&mut ChunkNbt> {
+        self.chunks.get_mut(&pos.into())
+    }
+
+    /// Set the NBT of the chunk at the given position, returning the chunk
+    /// it replaced, if any.
+    pub fn insert_chunk(&mut self, pos: impl Into<ChunkPos>, nbt: ChunkNbt) -> Option<ChunkNbt> {
+        self.chunks.insert(pos.into(), nbt)
+    }
+
+    /// Remove the chunk at the given position, returning it if it was
+    /// present.
+    pub fn remove_chunk(&mut self, pos: impl Into<ChunkPos>) -> Option<ChunkNbt> {
+        self.chunks.remove(&pos.into())
+    }
+
+    /// The positions of all chunks, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    /// The number of chunks held.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The regions that have chunks, sorted by X then Z.
+    pub fn regions(&self) -> Vec<(RCoord, RCoord)> {
+        let mut regions: Vec<_> = self.chunks.keys().map(|pos| pos.region().into()).collect();
+        regions.sort_by_key(|(x, z)| (x.0, z.0));
+        regions.dedup();
+        regions
+    }
+
+    /// The region file for the region (x, z), as it would be written to
+    /// `r.<x>.<z>.mca`. Returns None if the region has no chunks.
+    pub fn region_bytes(&self, x: RCoord, z: RCoord) -> Result<Option<Vec<u8>>> {
+        let mut region = RegionBuffer::new(Cursor::new(Vec::new()));
+        let mut any = false;
+
+        for (&pos, nbt) in &self.chunks {
+            if pos.region() != (x, z).into() {
+                continue;
+            }
+
+            let data = fastnbt::ser::to_bytes(nbt)?;
+            let (rel_x, rel_z) = pos.in_region();
+            region.write_chunk(rel_x, rel_z, &data)?;
+            any = true;
+        }
+
+        Ok(if any {
+            Some(region.into_inner().into_inner())
+        } else {
+            None
+        })
+    }
+
+    /// Write every region with chunks to a region file in `region_dir`,
+    /// creating the folder if needed. Existing region files for these
+    /// regions are replaced, others are left alone.
+    pub fn write_regions(&self, region_dir: impl AsRef<Path>) -> Result<()> {
+        let region_dir = region_dir.as_ref();
+        fs::create_dir_all(region_dir)?;
+
+        for (x, z) in self.regions() {
+            if let Some(data) = self.region_bytes(x, z)? {
+                fs::write(region_dir.join(format!("r.{}.{}.mca", x.0, z.0)), data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: Chunk> Default for MemoryRegionLoader<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Chunk + DeserializeOwned> RegionLoader<C> for MemoryRegionLoader<C> {
+    type RegionType = RegionBuffer<Cursor<Vec<u8>>>;
+
+    fn region(&self, x: RCoord, z: RCoord) -> Option<Self::RegionType> {
+        let data = self.region_bytes(x, z).ok()??;
+        Some(RegionBuffer::new(Cursor::new(data)))
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        Ok(self.regions())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastnbt::Value;
+
+    use super::*;
+    use crate::{CCoord, Dimension, JavaChunk};
+
+    fn chunk_nbt(x: i32, z: i32) -> ChunkNbt {
+        let mut nbt = ChunkNbt::new();
+        nbt.insert("xPos".to_owned(), Value::Int(x));
+        nbt.insert("zPos".to_owned(), Value::Int(z));
+        nbt
+    }
+
+    #[test]
+    fn edits_and_serializes_chunks() {
+        let mut loader = MemoryRegionLoader::<JavaChunk>::new();
+        loader.insert_chunk((CCoord(1), CCoord(2)), chunk_nbt(1, 2));
+        loader.insert_chunk((CCoord(-1), CCoord(33)), chunk_nbt(0, 0));
+        loader
+            .chunk_mut(ChunkPos::new(CCoord(-1), CCoord(33)))
+            .unwrap()
+            .insert("xPos".to_owned(), Value::Int(-1));
+
+        assert_eq!(
+            vec![(RCoord(-1), RCoord(1)), (RCoord(0), RCoord(0))],
+            loader.list().unwrap()
+        );
+
+        let region = loader.region(RCoord(-1), RCoord(1)).unwrap();
+        let nbt: ChunkNbt = fastnbt::de::from_bytes(&region.load_chunk(31, 1).unwrap()).unwrap();
+        assert_eq!(Some(&Value::Int(-1)), nbt.get("xPos"));
+        assert!(loader.region(RCoord(1), RCoord(1)).is_none());
+
+        let dimension = Dimension::new(loader);
+        assert!(dimension.chunk_exists(CCoord(1), CCoord(2)));
+        assert!(!dimension.chunk_exists(CCoord(2), CCoord(1)));
+    }
+
+    #[test]
+    fn edits_through_dimension() {
+        let mut loader = MemoryRegionLoader::<JavaChunk>::new();
+        loader.insert_chunk((CCoord(1), CCoord(2)), chunk_nbt(1, 2));
+
+        let mut dimension = Dimension::new(loader);
+        assert!(!dimension.chunk_exists(CCoord(3), CCoord(3)));
+
+        dimension
+            .loader_mut()
+            .insert_chunk((CCoord(3), CCoord(3)), chunk_nbt(3, 3));
+        assert_eq!(2, dimension.loader().len());
+        assert!(!dimension.chunk_exists(CCoord(3), CCoord(3)));
+
+        assert!(dimension.evict(RCoord(0), RCoord(0)));
+        assert!(dimension.chunk_exists(CCoord(3), CCoord(3)));
+    }
+
+    #[test]
+    fn writes_region_files() {
+        let dir = std::env::temp_dir().join(format!("fastanvil-memory-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut loader = MemoryRegionLoader::<JavaChunk>::new();
+        loader.insert_chunk((CCoord(40), CCoord(-3)), chunk_nbt(40, -3));
+        loader.write_regions(&dir).unwrap();
+
+        let region = RegionBuffer::new(fs::File::open(dir.join("r.1.-1.mca")).unwrap());
+        let nbt: ChunkNbt = fastnbt::de::from_bytes(&region.load_chunk(8, 29).unwrap()).unwrap();
+        assert_eq!(chunk_nbt(40, -3), nbt);
+
+        assert_eq!(
+            Some(chunk_nbt(40, -3)),
+            loader.remove_chunk((CCoord(40), CCoord(-3)))
+        );
+        assert!(loader.is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::path::Path;
+
+use byteorder::{BigEndian, ByteOrder};
+use fastnbt::de::from_bytes;
+use memmap2::Mmap;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    decompress_chunk, CCoord, Chunk, ChunkLocation, ChunkMeta, Error, Region, Result, SECTOR_SIZE,
+};
+
+/// A region backed by a memory mapped file. Unlike
+/// [`RegionBuffer`](crate::RegionBuffer) there is no cursor to move around,
+/// so chunks can be loaded through a shared reference from many threads at
+/// once.
+///
+/// ```no_run
+/// # use fastanvil::RegionMmap;
+/// // Safety: nothing else writes to the region while it is loaded.
+/// let region = unsafe { RegionMmap::open("r.0.0.mca")? };
+///
+/// std::thread::scope(|s| {
+///     for z in 0..32 {
+///         let region = &region;
+///         s.spawn(move || {
+///             for x in 0..32 {
+///                 if let Ok(data) = region.load_chunk(x, z) {
+///                     println!("chunk {}, {} is {} bytes", x, z, data.len());
+///                 }
+///             }
+///         });
+///     }
+/// });
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct RegionMmap {
+    map: Mmap,
+}
+
+impl RegionMmap {
+    /// Memory map the region file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// See [`from_file`](Self::from_file).
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// Memory map an open region file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, this
+    /// includes by other processes such as a running Minecraft server. Doing
+    /// so is undefined behaviour.
+    pub unsafe fn from_file(file: &File) -> Result<Self> {
+        let map = Mmap::map(file)?;
+        Ok(Self { map })
+    }
+
+    /// Return the (region-relative) Chunk location (x, z)
+    pub fn chunk_location(&self, x: usize, z: usize) -> Result<ChunkLocation> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let pos = 4 * (x + z * 32);
+        let location = self.map.get(pos..pos + 4).ok_or(Error::InsufficientData)?;
+        let location = BigEndian::read_u32(location);
+
+        Ok(ChunkLocation {
+            begin_sector: (location >> 8) as usize,
+            sector_count: (location & 0xff) as usize,
+            x,
+            z,
+        })
+    }
+
+    /// Return the last modification time of the chunk at the (region-relative)
+    /// Chunk location (x, z), in seconds since the Unix epoch.
+    pub fn chunk_timestamp(&self, x: usize, z: usize) -> Result<u32> {
+        if x >= 32 || z >= 32 {
+            return Err(Error::InvalidOffset(x, z));
+        }
+
+        let pos = SECTOR_SIZE + 4 * (x + z * 32);
+        let timestamp = self.map.get(pos..pos + 4).ok_or(Error::InsufficientData)?;
+        Ok(BigEndian::read_u32(timestamp))
+    }
+
+    /// Return the raw, compressed data for a chunk at the (region-relative)
+    /// Chunk location (x, z), straight from the mapped file. This includes
+    /// the 5 bytes of length and compression scheme, see [`ChunkMeta`].
+    /// Chunks stored in separate `.mcc` files are not supported.
+    pub fn load_raw_chunk(&self, x: usize, z: usize) -> Result<&[u8]> {
+        let location = self.chunk_location(x, z)?;
+
+        // 0,0 chunk location means the chunk isn't present.
+        if location.begin_sector == 0 || location.sector_count == 0 {
+            return Err(Error::ChunkNotFound);
+        }
+
+        let begin = location.begin_sector * SECTOR_SIZE;
+
+        let meta = self
+            .map
+            .get(begin..begin + 5)
+            .ok_or(Error::InsufficientData)?;
+        let meta = ChunkMeta::new(meta)?;
+        if meta.external {
+            return Err(Error::ExternalChunkUnavailable);
+        }
+        let len = usize::try_from(meta.compressed_len).map_err(|_| Error::InsufficientData)?;
+
+        self.map
+            .get(begin..begin + 5 + len)
+            .ok_or(Error::InsufficientData)
+    }
+
+    /// Return the raw, uncompressed NBT data for a chunk at the
+    /// (region-relative) Chunk location (x, z).
+    pub fn load_chunk(&self, x: usize, z: usize) -> Result<Vec<u8>> {
+        decompress_chunk(self.load_raw_chunk(x, z)?)
+    }
+}
+
+impl<C: Chunk + DeserializeOwned> Region<C> for RegionMmap {
+    fn chunk(&self, x: CCoord, z: CCoord) -> Option<C> {
+        let data = self.load_chunk(x.0 as usize, z.0 as usize).ok()?;
+        from_bytes::<C>(&data).ok()
+    }
+
+    fn chunk_exists(&self, x: CCoord, z: CCoord) -> bool {
+        matches!(
+            self.chunk_location(x.0 as usize, z.0 as usize),
+            Ok(loc) if loc.begin_sector != 0 && loc.sector_count != 0
+        )
+    }
+
+    fn chunk_timestamps(&self) -> Option<Vec<u32>> {
+        let timestamps = self.map.get(SECTOR_SIZE..2 * SECTOR_SIZE)?;
+        Some(
+            timestamps
+                .chunks_exact(4)
+                .map(BigEndian::read_u32)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::Builder;
+
+    fn region(name: &str, data: Vec<u8>) -> (std::path::PathBuf, RegionMmap) {
+        let path = std::env::temp_dir().join(format!(
+            "fastanvil-mmap-{}-{}.mca",
+            name,
+            std::process::id()
+        ));
+        File::create(&path).unwrap().write_all(&data).unwrap();
+        let region = unsafe { RegionMmap::open(&path).unwrap() };
+        (path, region)
+    }
+
+    #[test]
+    fn load_chunks() -> Result<()> {
+        let data = Builder::new()
+            .location(3, 1)
+            .location(2, 1)
+            .timestamp(1, 100)
+            .chunk(&[1, 2, 3])
+            .chunk(&[4, 5])
+            .build()
+            .into_inner();
+        let (path, r) = region("load", data);
+
+        assert_eq!(3, r.chunk_location(0, 0)?.begin_sector);
+        assert_eq!(100, r.chunk_timestamp(1, 0)?);
+        assert_eq!(vec![4, 5], r.load_chunk(0, 0)?);
+        assert_eq!(vec![1, 2, 3], r.load_chunk(1, 0)?);
+        assert!(matches!(r.load_chunk(2, 0), Err(Error::ChunkNotFound)));
+        assert!(matches!(
+            r.chunk_location(0, 32),
+            Err(Error::InvalidOffset(0, 32))
+        ));
+
+        let region: &dyn Region<crate::JavaChunk> = &r;
+        assert!(region.chunk_exists(CCoord(1), CCoord(0)));
+        assert!(!region.chunk_exists(CCoord(2), CCoord(0)));
+        assert_eq!(Some(100), region.chunk_timestamps().map(|t| t[1]));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_region() -> Result<()> {
+        let data = Builder::new().location(2, 4).build().into_inner();
+        let (path, r) = region("truncated", data);
+
+        assert!(matches!(r.load_chunk(0, 0), Err(Error::InsufficientData)));
+        assert!(matches!(
+            r.chunk_timestamp(31, 31),
+            Err(Error::InsufficientData)
+        ));
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn load_from_many_threads() -> Result<()> {
+        let mut builder = Builder::new();
+        for i in 0..32 {
+            builder = builder.location(2 + i, 1);
+        }
+        for i in 0..32u8 {
+            builder = builder.chunk(&[i]);
+        }
+        let (path, r) = region("threads", builder.build().into_inner());
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for x in 0..32 {
+                        assert_eq!(vec![x as u8], r.load_chunk(x, 0).unwrap());
+                    }
+                });
+            }
+        });
+
+        std::fs::remove_file(path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use image::RgbaImage;
+
+use crate::{a_over_b_colour, seed, CCoord, RegionBounds, Rgba};
+
+/// Things to draw over a rendered map: grid lines, slime chunks and markers.
+/// Maps are expected to have one pixel per block, as rendered by a
+/// [`ChunkRenderer`](crate::ChunkRenderer). Colours are blended over the
+/// map, so partly transparent colours leave the map visible below.
+///
+/// Slime chunks are drawn first, then the chunk grid, the region grid, and
+/// finally the markers in order.
+///
+/// ```no_run
+/// # use fastanvil::{Marker, Overlay, RegionBounds, RCoord};
+/// # fn render() -> image::RgbaImage { unimplemented!() }
+/// let bounds = RegionBounds::new((RCoord(-1), RCoord(-1)), (RCoord(0), RCoord(0)));
+/// let mut img = render();
+///
+/// let overlay = Overlay {
+///     chunk_grid: Some([0, 0, 0, 64]),
+///     region_grid: Some([0, 0, 0, 255]),
+///     slime_chunks: Some((12345, [0, 255, 0, 96])),
+///     markers: vec![Marker::new(0, 0, [255, 0, 0, 255])],
+/// };
+/// overlay.draw_regions(&mut img, bounds);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Overlay {
+    /// The colour of lines along the north and west edges of every chunk.
+    pub chunk_grid: Option<Rgba>,
+
+    /// The colour of lines along the north and west edges of every region.
+    pub region_grid: Option<Rgba>,
+
+    /// The seed of the world and the colour to fill its slime chunks with.
+    pub slime_chunks: Option<(i64, Rgba)>,
+
+    pub markers: Vec<Marker>,
+}
+
+/// A point of interest to mark on a map, such as a base or a portal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Marker {
+    /// The block coordinates of the marker.
+    pub x: isize,
+    pub z: isize,
+
+    pub colour: Rgba,
+
+    /// Markers are drawn as a filled circle of this radius in pixels, around
+    /// the pixel of the block. A radius of 0 colours just that pixel.
+    pub radius: u32,
+}
+
+impl Marker {
+    /// A marker with a radius of 3 pixels.
+    pub fn new(x: isize, z: isize, colour: Rgba) -> Self {
+        Self {
+            x,
+            z,
+            colour,
+            radius: 3,
+        }
+    }
+}
+
+impl Overlay {
+    /// Draw the overlay over a map, where the top left pixel is the block at
+    /// `origin`, as X and Z. Anything outside the image is left out.
+    pub fn draw(&self, img: &mut RgbaImage, origin: (isize, isize)) {
+        let (width, height) = (img.width() as isize, img.height() as isize);
+        let (x0, z0) = origin;
+
+        if let Some((seed, colour)) = self.slime_chunks {
+            // Check each chunk once rather than every pixel.
+            let first = (x0.div_euclid(16), z0.div_euclid(16));
+            let last = (
+                (x0 + width - 1).div_euclid(16),
+                (z0 + height - 1).div_euclid(16),
+            );
+
+            for cz in first.1..=last.1 {
+                for cx in first.0..=last.0 {
+                    if seed::is_slime_chunk(seed, CCoord(cx), CCoord(cz)) {
+                        let (left, top) = (cx * 16 - x0, cz * 16 - z0);
+                        fill(img, left, top, left + 16, top + 16, colour);
+                    }
+                }
+            }
+        }
+
+        let grids = [(self.chunk_grid, 16), (self.region_grid, 512)];
+        for (colour, spacing) in grids {
+            let colour = match colour {
+                Some(colour) => colour,
+                None => continue,
+            };
+
+            // Lines along the west edges, then the north edges.
+            let first_x = (spacing - x0.rem_euclid(spacing)) % spacing;
+            for px in (first_x..width).step_by(spacing as usize) {
+                fill(img, px, 0, px + 1, height, colour);
+            }
+            let first_z = (spacing - z0.rem_euclid(spacing)) % spacing;
+            for pz in (first_z..height).step_by(spacing as usize) {
+                fill(img, 0, pz, width, pz + 1, colour);
+            }
+        }
+
+        for marker in &self.markers {
+            let (cx, cz) = (marker.x - x0, marker.z - z0);
+            let r = marker.radius as isize;
+
+            for pz in cz - r..=cz + r {
+                for px in cx - r..=cx + r {
+                    let (dx, dz) = (px - cx, pz - cz);
+                    if dx * dx + dz * dz <= r * r {
+                        fill(img, px, pz, px + 1, pz + 1, marker.colour);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw the overlay over a map of the regions in `bounds`, such as one
+    /// from [`render_dimension`](crate::render_dimension) or
+    /// [`stitch_regions`](crate::stitch_regions).
+    pub fn draw_regions(&self, img: &mut RgbaImage, bounds: RegionBounds) {
+        self.draw(img, (bounds.min.0 .0 * 512, bounds.min.1 .0 * 512));
+    }
+}
+
+/// Blend `colour` over the pixels from `left`, `top` up to but not including
+/// `right`, `bottom`, clipped to the image.
+fn fill(img: &mut RgbaImage, left: isize, top: isize, right: isize, bottom: isize, colour: Rgba) {
+    let right = right.min(img.width() as isize);
+    let bottom = bottom.min(img.height() as isize);
+
+    for pz in top.max(0)..bottom {
+        for px in left.max(0)..right {
+            let pixel = img.get_pixel_mut(px as u32, pz as u32);
+            pixel.0 = a_over_b_colour(colour, pixel.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::RCoord;
+
+    const RED: Rgba = [255, 0, 0, 255];
+    const BLACK: Rgba = [0, 0, 0, 255];
+
+    #[test]
+    fn grid_lines_follow_world_coordinates() {
+        let mut img = RgbaImage::new(40, 40);
+        let overlay = Overlay {
+            chunk_grid: Some(BLACK),
+            ..Default::default()
+        };
+
+        // The image starts 4 blocks into a chunk, with negative coordinates.
+        overlay.draw(&mut img, (-20, -36));
+        let black_column = |px| (0..40).all(|pz| img.get_pixel(px, pz).0 == BLACK);
+
+        assert!(black_column(4));
+        assert!(black_column(20));
+        assert!(!black_column(5));
+        assert_eq!(BLACK, img.get_pixel(1, 4).0);
+        assert_eq!([0, 0, 0, 0], img.get_pixel(1, 5).0);
+    }
+
+    #[test]
+    fn slime_chunks_and_markers() {
+        let bounds = RegionBounds::new((RCoord(-1), RCoord(-1)), (RCoord(0), RCoord(0)));
+        let mut img = RgbaImage::new(1024, 1024);
+        let overlay = Overlay {
+            slime_chunks: Some((12345, BLACK)),
+            markers: vec![Marker::new(-512, -512, RED)],
+            ..Default::default()
+        };
+        overlay.draw_regions(&mut img, bounds);
+
+        // Chunk 0, -2 is a slime chunk, 0, -1 is not.
+        assert_eq!(BLACK, img.get_pixel(512, 512 - 32).0);
+        assert_eq!(BLACK, img.get_pixel(527, 512 - 17).0);
+        assert_eq!([0, 0, 0, 0], img.get_pixel(512, 512 - 16).0);
+
+        // The marker is clipped at the corner.
+        assert_eq!(RED, img.get_pixel(0, 0).0);
+        assert_eq!(RED, img.get_pixel(3, 0).0);
+        assert_ne!(RED, img.get_pixel(3, 3).0);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{Error, RCoord, Result};
+
+/// Receives updates from operations over a whole dimension, such as
+/// [`TilePyramid::render_with_progress`](crate::tiles::TilePyramid::render_with_progress),
+/// [`RenderManifest::scan_with_progress`](crate::tiles::RenderManifest::scan_with_progress)
+/// and [`compact_dimension`](crate::compact_dimension), for showing a
+/// progress bar. Every method does nothing by default, and `()` can be used
+/// when progress isn't needed.
+///
+/// ```
+/// use fastanvil::{Error, Progress, RCoord};
+///
+/// #[derive(Default)]
+/// struct Log {
+///     total: usize,
+///     done: usize,
+/// }
+///
+/// impl Progress for Log {
+///     fn start(&mut self, regions: usize) {
+///         self.total = regions;
+///     }
+///
+///     fn region_done(&mut self, x: RCoord, z: RCoord, chunks: usize) {
+///         self.done += 1;
+///         println!("[{}/{}] r.{}.{}: {} chunks", self.done, self.total, x.0, z.0, chunks);
+///     }
+///
+///     // Skip broken regions rather than stopping.
+///     fn error(&mut self, x: RCoord, z: RCoord, error: Error) -> Result<(), Error> {
+///         self.done += 1;
+///         eprintln!("r.{}.{} skipped: {}", x.0, z.0, error);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Progress {
+    /// Called before any work is done, with the number of regions that will
+    /// be processed.
+    fn start(&mut self, _regions: usize) {}
+
+    /// Called after each region has been processed, with the number of its
+    /// chunks that were processed.
+    fn region_done(&mut self, _x: RCoord, _z: RCoord, _chunks: usize) {}
+
+    /// Called when a region could not be processed. Returning the error
+    /// stops the operation with it, which is the default. Returning `Ok`
+    /// skips the region and carries on with the next.
+    fn error(&mut self, _x: RCoord, _z: RCoord, error: Error) -> Result<()> {
+        Err(error)
+    }
+
+    /// Called once every region has been processed.
+    fn finish(&mut self) {}
+}
+
+/// No progress reporting, stopping at the first error.
+impl Progress for () {}
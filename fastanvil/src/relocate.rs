@@ -0,0 +1,300 @@
+//! Move chunks to a different position, for merging worlds or moving the
+//! area around spawn. Chunks are moved by a whole number of chunks, so
+//! everything inside them keeps its position relative to the chunk.
+//!
+//! Moving a chunk means more than changing where it is stored: its NBT holds
+//! absolute positions too. [`relocate_chunk`] updates
+//!
+//! * the `xPos` and `zPos` of the chunk, or `Position` for entity chunks,
+//! * the positions of entities and their passengers, including the block
+//!   hanging entities like paintings are attached to,
+//! * the positions of block entities and scheduled ticks,
+//! * structure starts, their pieces' bounding boxes, and references to
+//!   structures in other chunks,
+//! * point of interest records.
+//!
+//! Positions stored inside the data of particular entities and block
+//! entities, such as a bee's hive or an end gateway's exit, are left alone.
+//! Chunks from before 1.18 with everything inside a `Level` tag are handled
+//! as well as newer chunks.
+//!
+//! ```no_run
+//! use fastanvil::{relocate, CCoord};
+//!
+//! // Copy the 64 by 64 chunks around spawn of one world into another, 1000
+//! // chunks to the east.
+//! let copied = relocate::copy_chunks(
+//!     "old/region",
+//!     "new/region",
+//!     (CCoord(-32), CCoord(-32)),
+//!     (CCoord(31), CCoord(31)),
+//!     (1000, 0),
+//! )?;
+//! println!("copied {} chunks", copied);
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use fastnbt::{IntArray, LongArray, Value};
+
+use crate::files::{list_regions, try_open_region};
+use crate::{ChunkNbt, ChunkPos, DimensionWriter, RegionPos, Result};
+
+/// Update the positions in the NBT of a chunk for it to be moved `dx` chunks
+/// along X and `dz` chunks along Z. Works for chunks from `region`, as well
+/// as the chunks of the `entities` and `poi` folders. See the
+/// [module](self) documentation for what is changed.
+pub fn relocate_chunk(nbt: &mut ChunkNbt, dx: isize, dz: isize) {
+    let offset = Offset {
+        chunks: (dx as i32, dz as i32),
+        blocks: (dx as i32 * 16, dz as i32 * 16),
+    };
+
+    // Chunks from before 1.18 keep almost everything in `Level`, and the
+    // root has none of the tags we are looking for.
+    relocate_level(nbt, &offset);
+    if let Some(Value::Compound(level)) = nbt.get_mut("Level") {
+        relocate_level(level, &offset);
+    }
+}
+
+/// Copy the chunks from `min` to `max` inclusive, in the region files in
+/// `src_dir`, to the region files in `dst_dir`, moved by `offset` chunks.
+/// `min` and `max` are [`ChunkPos`] or tuples of (absolute) chunk
+/// coordinates.
+/// Chunks already at the destination are replaced, and `dst_dir` and any
+/// region files that don't exist yet are created. Returns the number of
+/// chunks copied, or the first error reading a source region or chunk.
+///
+/// This can be used for each of the `region`, `entities` and `poi` folders
+/// of a dimension. The same folder can be used for the source and
+/// destination, as long as the area copied doesn't overlap the destination.
+pub fn copy_chunks(
+    src_dir: impl AsRef<Path>,
+    dst_dir: impl AsRef<Path>,
+    min: impl Into<ChunkPos>,
+    max: impl Into<ChunkPos>,
+    offset: (isize, isize),
+) -> Result<usize> {
+    let (src_dir, dst_dir) = (src_dir.as_ref(), dst_dir.as_ref());
+    let (min, max) = (min.into(), max.into());
+    std::fs::create_dir_all(dst_dir)?;
+    let regions = list_regions(src_dir)?;
+
+    let in_range =
+        |pos: ChunkPos| (min.x..=max.x).contains(&pos.x) && (min.z..=max.z).contains(&pos.z);
+    let mut copied = 0;
+
+    for (rx, rz) in regions {
+        let region_pos = RegionPos::new(rx, rz);
+        let (first, last) = (region_pos.chunk(0, 0), region_pos.chunk(31, 31));
+        let overlaps = first.x <= max.x && last.x >= min.x && first.z <= max.z && last.z >= min.z;
+        if !overlaps {
+            continue;
+        }
+
+        let region = try_open_region(src_dir, rx, rz)?;
+
+        // Write each source region as we go, so that only one region's worth
+        // of chunks is in memory.
+        let mut writer = DimensionWriter::new(dst_dir.to_owned());
+
+        for loc in region.locations()? {
+            let pos = region_pos.chunk(loc.x, loc.z);
+            if !in_range(pos) {
+                continue;
+            }
+
+            let mut nbt: ChunkNbt = fastnbt::de::from_bytes(&region.load_chunk(loc.x, loc.z)?)?;
+            relocate_chunk(&mut nbt, offset.0, offset.1);
+            writer.insert_chunk(pos + offset, nbt);
+            copied += 1;
+        }
+
+        writer.flush()?;
+    }
+
+    Ok(copied)
+}
+
+struct Offset {
+    chunks: (i32, i32),
+    blocks: (i32, i32),
+}
+
+type Compound = HashMap<String, Value>;
+
+fn relocate_level(level: &mut Compound, offset: &Offset) {
+    add_int(level, "xPos", offset.chunks.0);
+    add_int(level, "zPos", offset.chunks.1);
+
+    // Entity chunks store their position as an array.
+    if let Some(Value::IntArray(pos)) = level.get_mut("Position") {
+        if pos.len() == 2 {
+            *pos = IntArray::new(vec![pos[0] + offset.chunks.0, pos[1] + offset.chunks.1]);
+        }
+    }
+
+    for key in ["Entities", "entities"] {
+        for entity in compounds_mut(level, key) {
+            relocate_entity(entity, offset);
+        }
+    }
+
+    for key in [
+        "TileEntities",
+        "block_entities",
+        "TileTicks",
+        "LiquidTicks",
+        "block_ticks",
+        "fluid_ticks",
+    ] {
+        for compound in compounds_mut(level, key) {
+            add_int(compound, "x", offset.blocks.0);
+            add_int(compound, "z", offset.blocks.1);
+        }
+    }
+
+    for key in ["Structures", "structures"] {
+        if let Some(Value::Compound(structures)) = level.get_mut(key) {
+            relocate_structures(structures, offset);
+        }
+    }
+
+    // Point of interest chunks have a compound of sections, where region
+    // chunks have a list.
+    if let Some(Value::Compound(sections)) = level.get_mut("Sections") {
+        for section in sections.values_mut() {
+            if let Value::Compound(section) = section {
+                for record in compounds_mut(section, "Records") {
+                    if let Some(Value::IntArray(pos)) = record.get_mut("pos") {
+                        offset_pos(pos, offset);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn relocate_entity(entity: &mut Compound, offset: &Offset) {
+    if let Some(Value::List(pos)) = entity.get_mut("Pos") {
+        if let [Value::Double(x), _, Value::Double(z)] = &mut pos[..] {
+            *x += offset.blocks.0 as f64;
+            *z += offset.blocks.1 as f64;
+        }
+    }
+
+    // The block that paintings and item frames hang on.
+    add_int(entity, "TileX", offset.blocks.0);
+    add_int(entity, "TileZ", offset.blocks.1);
+
+    for passenger in compounds_mut(entity, "Passengers") {
+        relocate_entity(passenger, offset);
+    }
+}
+
+fn relocate_structures(structures: &mut Compound, offset: &Offset) {
+    // 1.18 renamed `Starts` to `starts`.
+    for key in ["Starts", "starts"] {
+        let starts = match structures.get_mut(key) {
+            Some(Value::Compound(starts)) => starts,
+            _ => continue,
+        };
+
+        for start in starts.values_mut() {
+            let start = match start {
+                Value::Compound(start) => start,
+                _ => continue,
+            };
+
+            add_int(start, "ChunkX", offset.chunks.0);
+            add_int(start, "ChunkZ", offset.chunks.1);
+            relocate_piece(start, offset);
+
+            for piece in compounds_mut(start, "Children") {
+                relocate_piece(piece, offset);
+            }
+        }
+    }
+
+    // References are the chunk positions of structure starts, packed into a
+    // long with X in the low half and Z in the high half.
+    if let Some(Value::Compound(references)) = structures.get_mut("References") {
+        for chunks in references.values_mut() {
+            if let Value::LongArray(chunks) = chunks {
+                let moved = chunks
+                    .iter()
+                    .map(|&packed| {
+                        let x = packed as i32 + offset.chunks.0;
+                        let z = (packed >> 32) as i32 + offset.chunks.1;
+                        (x as u32 as i64) | (z as i64) << 32
+                    })
+                    .collect();
+                *chunks = LongArray::new(moved);
+            }
+        }
+    }
+}
+
+fn relocate_piece(piece: &mut Compound, offset: &Offset) {
+    if let Some(Value::IntArray(bb)) = piece.get_mut("BB") {
+        offset_bounding_box(bb, offset);
+    }
+
+    // Jigsaw pieces, such as those of villages.
+    add_int(piece, "PosX", offset.blocks.0);
+    add_int(piece, "PosZ", offset.blocks.1);
+
+    // Mineshaft rooms.
+    if let Some(Value::List(entrances)) = piece.get_mut("Entrances") {
+        for entrance in entrances {
+            if let Value::IntArray(bb) = entrance {
+                offset_bounding_box(bb, offset);
+            }
+        }
+    }
+}
+
+/// Move a bounding box stored as min X, Y, Z then max X, Y, Z.
+fn offset_bounding_box(bb: &mut IntArray, offset: &Offset) {
+    if let [x0, y0, z0, x1, y1, z1] = bb[..] {
+        *bb = IntArray::new(vec![
+            x0 + offset.blocks.0,
+            y0,
+            z0 + offset.blocks.1,
+            x1 + offset.blocks.0,
+            y1,
+            z1 + offset.blocks.1,
+        ]);
+    }
+}
+
+fn offset_pos(pos: &mut IntArray, offset: &Offset) {
+    if let [x, y, z] = pos[..] {
+        *pos = IntArray::new(vec![x + offset.blocks.0, y, z + offset.blocks.1]);
+    }
+}
+
+fn add_int(compound: &mut Compound, key: &str, by: i32) {
+    if let Some(Value::Int(v)) = compound.get_mut(key) {
+        *v += by;
+    }
+}
+
+/// The compounds in the list `key` of `compound`, if there is one.
+fn compounds_mut<'a>(
+    compound: &'a mut Compound,
+    key: &str,
+) -> impl Iterator<Item = &'a mut Compound> {
+    let list = match compound.get_mut(key) {
+        Some(Value::List(list)) => Some(list),
+        _ => None,
+    };
+
+    list.into_iter().flatten().filter_map(|v| match v {
+        Value::Compound(c) => Some(c),
+        _ => None,
+    })
+}
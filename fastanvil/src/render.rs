@@ -1,6 +1,13 @@
 use std::cmp::Ordering;
+use std::ops::Range;
 
-use crate::{Block, CCoord, Chunk, Dimension, HeightMode, RCoord, RegionLoader};
+#[cfg(feature = "image")]
+use image::RgbaImage;
+
+use crate::{
+    Block, CCoord, Chunk, Dimension, DimensionKind, HeightMode, PixelSink, RCoord, RegionLoader,
+    RegionPos,
+};
 
 use super::biome::Biome;
 use crate::dimension::Region;
@@ -11,11 +18,348 @@ pub type Rgba = [u8; 4];
 /// should render to.
 pub trait Palette {
     fn pick(&self, block: &Block, biome: Option<Biome>) -> Rgba;
+
+    /// Pick the colour of one face of a block, for renders that show the
+    /// sides of blocks. By default this is the colour from `pick`, with the
+    /// sides darkened.
+    fn pick_face(&self, block: &Block, biome: Option<Biome>, face: BlockFace) -> Rgba {
+        let colour = self.pick(block, biome);
+        match face {
+            BlockFace::Top => colour,
+            BlockFace::South => scale_colour(colour, 0.8),
+            BlockFace::East => scale_colour(colour, 0.65),
+        }
+    }
+
+    /// How much a block hides the blocks below it when seen from above, from
+    /// 0 for blocks like flowers and torches that are drawn as if they
+    /// weren't there, to 255 for blocks that cover the whole column. Drawing
+    /// small blocks makes the surface look speckled, and as they stick up
+    /// from the ground they throw off the shading of their neighbours. By
+    /// default every block covers the whole column.
+    fn cover(&self, _block: &Block) -> u8 {
+        255
+    }
+}
+
+/// The faces of a block that can be seen in an isometric render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFace {
+    Top,
+    South,
+    East,
+}
+
+/// ChunkRenderer turns a chunk into the pixels of a map, one per column. This
+/// lets [`render_region`] drive different kinds of render.
+pub trait ChunkRenderer {
+    /// Render a chunk. The chunk to the north is given if there is one, for
+    /// renderers that shade based on neighbouring columns.
+    fn render<C: Chunk>(&self, chunk: &C, north: Option<&C>) -> [Rgba; 16 * 16];
+}
+
+/// The surroundings of the top block of a column, given to a [`Shader`].
+/// Heights are of the first air block above the column.
+#[derive(Debug, Clone)]
+pub struct ShadeContext {
+    /// The chunk-relative coordinates of the column.
+    pub x: usize,
+    pub z: usize,
+
+    pub height: isize,
+
+    /// The height of the column to the north. For the top row of a chunk this
+    /// comes from the chunk to the north if there is one, otherwise it is the
+    /// same as `height`.
+    pub north_height: isize,
+
+    /// The height of the column to the west. Neighbouring chunks are not
+    /// available, so for the first column of a chunk this is the same as
+    /// `height`.
+    pub west_height: isize,
+
+    pub biome: Option<Biome>,
+
+    /// The light levels from blocks and from the sky in the air above the
+    /// column, from 0 to 15. `None` if the chunk has no light data there.
+    pub block_light: Option<u8>,
+    pub sky_light: Option<u8>,
+}
+
+impl ShadeContext {
+    /// Fill in the light of the air above a column at `height`. Above the top
+    /// of the chunk there is nothing to block the sky.
+    fn with_light<C: Chunk>(mut self, chunk: &C) -> Self {
+        let (x, y, z) = (self.x, self.height, self.z);
+        if y >= chunk.y_range().end {
+            self.block_light = Some(0);
+            self.sky_light = Some(15);
+        } else {
+            self.block_light = chunk.block_light(x, y, z);
+            self.sky_light = chunk.sky_light(x, y, z);
+        }
+        self
+    }
+}
+
+/// Shader decides how the colour of a column is adjusted for its
+/// surroundings, such as darkening slopes to show the shape of the land.
+///
+/// ```no_run
+/// # use fastanvil::{HeightMode, HillShade, Palette, TopShadeRenderer};
+/// # struct MyPalette;
+/// # impl fastanvil::Palette for MyPalette {
+/// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+/// #         [0; 4]
+/// #     }
+/// # }
+/// # fn palette() -> MyPalette { MyPalette }
+/// let palette = palette();
+/// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust).with_shader(HillShade {
+///     azimuth: 315.0,
+///     strength: 0.1,
+/// });
+/// ```
+pub trait Shader {
+    /// Shade the colour of the top of a column.
+    fn shade(&self, colour: Rgba, context: &ShadeContext) -> Rgba;
+
+    /// The opacity of water of the given depth, drawn over the blocks below
+    /// it. Deeper water should be more opaque.
+    fn water_alpha(&self, depth: isize) -> u8 {
+        water_depth_to_alpha(depth)
+    }
+
+    /// The colour of a block seen through water of the given depth. By
+    /// default deeper blocks are darker, so that the shape of the sea floor
+    /// shows through.
+    fn underwater(&self, colour: Rgba, depth: isize) -> Rgba {
+        scale_colour(colour, (1.0 - 0.02 * depth as f64).max(0.5))
+    }
+}
+
+/// The default shader, which shades columns like Minecraft's own maps:
+/// darker if the column to the north is taller, and lighter if it is shorter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TopShade;
+
+impl Shader for TopShade {
+    fn shade(&self, colour: Rgba, context: &ShadeContext) -> Rgba {
+        top_shade_colour(colour, context.height, context.north_height)
+    }
+}
+
+/// A shader that leaves colours as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoShade;
+
+impl Shader for NoShade {
+    fn shade(&self, colour: Rgba, _context: &ShadeContext) -> Rgba {
+        colour
+    }
+}
+
+/// Hillshading, lighting slopes as if by a light in the direction
+/// `azimuth`, in degrees clockwise from north. Slopes facing the light are
+/// lightened and slopes facing away are darkened by `strength` per block of
+/// height difference.
+#[derive(Debug, Clone, Copy)]
+pub struct HillShade {
+    pub azimuth: f64,
+    pub strength: f64,
+}
+
+impl Default for HillShade {
+    /// Light from the north-west, as is usual for maps.
+    fn default() -> Self {
+        Self {
+            azimuth: 315.0,
+            strength: 0.1,
+        }
+    }
+}
+
+impl Shader for HillShade {
+    fn shade(&self, colour: Rgba, context: &ShadeContext) -> Rgba {
+        // North is negative z.
+        let (light_x, light_z) = {
+            let azimuth = self.azimuth.to_radians();
+            (azimuth.sin(), -azimuth.cos())
+        };
+
+        // How much the land rises heading east and south.
+        let rise_x = (context.height - context.west_height) as f64;
+        let rise_z = (context.height - context.north_height) as f64;
+
+        // Land rising away from the light faces towards it.
+        let brightness = 1.0 - self.strength * (rise_x * light_x + rise_z * light_z);
+        scale_colour(colour, brightness.max(0.0))
+    }
+}
+
+/// Draws contour lines on top of another shader, where the height crosses a
+/// multiple of `interval` between a column and its north or west neighbour.
+#[derive(Debug, Clone, Copy)]
+pub struct Contours<S: Shader> {
+    pub shader: S,
+    pub interval: isize,
+    pub colour: Rgba,
+}
+
+impl<S: Shader> Shader for Contours<S> {
+    fn shade(&self, colour: Rgba, context: &ShadeContext) -> Rgba {
+        let band = |height: isize| height.div_euclid(self.interval.max(1));
+        let here = band(context.height);
+
+        if here != band(context.north_height) || here != band(context.west_height) {
+            self.colour
+        } else {
+            self.shader.shade(colour, context)
+        }
+    }
+
+    fn water_alpha(&self, depth: isize) -> u8 {
+        self.shader.water_alpha(depth)
+    }
+
+    fn underwater(&self, colour: Rgba, depth: isize) -> Rgba {
+        self.shader.underwater(colour, depth)
+    }
 }
 
-pub struct TopShadeRenderer<'a, P: Palette> {
+/// Shades columns by the light in the air above them, for a view of the
+/// world at night where only lit areas stand out. Light is combined like
+/// Minecraft does, taking the brighter of block light and sky light dimmed
+/// for the time of day, then shaded by `shader`.
+///
+/// Columns without light data, such as in chunks that haven't been lit, are
+/// only shaded by `shader`.
+///
+/// ```no_run
+/// # use fastanvil::{HeightMode, NightShade, TopShadeRenderer};
+/// # struct MyPalette;
+/// # impl fastanvil::Palette for MyPalette {
+/// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+/// #         [0; 4]
+/// #     }
+/// # }
+/// # fn palette() -> MyPalette { MyPalette }
+/// let palette = palette();
+/// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust).with_shader(NightShade {
+///     spawnable: Some([255, 0, 0, 96]),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NightShade<S: Shader = TopShade> {
+    pub shader: S,
+
+    /// How bright the sky is, from 0 to 15. Full daylight is 15, and at
+    /// midnight the sky gives a light level of 4.
+    pub sky: u8,
+
+    /// A colour to draw over columns where monsters can spawn, which is where
+    /// there is no block light and the light from the sky is 7 or less.
+    pub spawnable: Option<Rgba>,
+}
+
+impl Default for NightShade {
+    /// Midnight, without highlighting where monsters can spawn.
+    fn default() -> Self {
+        Self {
+            shader: TopShade,
+            sky: 4,
+            spawnable: None,
+        }
+    }
+}
+
+impl<S: Shader> Shader for NightShade<S> {
+    fn shade(&self, colour: Rgba, context: &ShadeContext) -> Rgba {
+        let colour = self.shader.shade(colour, context);
+        let (block, sky) = match (context.block_light, context.sky_light) {
+            (Some(block), Some(sky)) => (block, sky.saturating_sub(15 - self.sky.min(15))),
+            _ => return colour,
+        };
+
+        let colour = scale_colour(colour, light_brightness(block.max(sky)));
+        match self.spawnable {
+            Some(highlight) if block == 0 && sky <= 7 => a_over_b_colour(highlight, colour),
+            _ => colour,
+        }
+    }
+
+    fn water_alpha(&self, depth: isize) -> u8 {
+        self.shader.water_alpha(depth)
+    }
+
+    fn underwater(&self, colour: Rgba, depth: isize) -> Rgba {
+        self.shader.underwater(colour, depth)
+    }
+}
+
+/// How bright a light level looks, from 0 to 1. Like Minecraft, each level
+/// is brighter by more than the last, and darkness is never quite black.
+fn light_brightness(level: u8) -> f64 {
+    let f = level.min(15) as f64 / 15.0;
+    let brightness = f / (4.0 - 3.0 * f);
+    0.05 + 0.95 * brightness
+}
+
+/// The height that rendering of the nether starts below, to see under its
+/// roof.
+const NETHER_ROOF_Y: isize = 120;
+
+/// Which blocks a [`TopShadeRenderer`] looks through to the blocks below.
+/// Blocks the palette gives a transparent colour, such as glass, are always
+/// looked through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transparency {
+    /// Draw water over the blocks below it, more opaque the deeper it is as
+    /// decided by [`Shader::water_alpha`], with the blocks below darkened by
+    /// [`Shader::underwater`]. Otherwise water is drawn as an opaque surface.
+    pub water: bool,
+
+    /// The opacity to draw leaves with, to see the ground under trees.
+    /// `None` draws them with the colour from the palette.
+    pub leaves: Option<u8>,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Self {
+            water: true,
+            leaves: None,
+        }
+    }
+}
+
+/// Options for what part of the world a [`TopShadeRenderer`] draws.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOpts {
+    /// The heights of blocks to draw. Each column is drawn from the topmost
+    /// block within the range, so a range below the surface shows a
+    /// horizontal slice of the world, such as `-59..0` for the deepslate
+    /// layer. Columns with nothing but air in the range are left
+    /// transparent. By default the whole height of the world is drawn.
+    pub y_range: Range<isize>,
+}
+
+impl Default for RenderOpts {
+    fn default() -> Self {
+        Self {
+            y_range: isize::MIN..isize::MAX,
+        }
+    }
+}
+
+pub struct TopShadeRenderer<'a, P: Palette, S: Shader = TopShade> {
     palette: &'a P,
     height_mode: HeightMode,
+    shader: S,
+    dimension: DimensionKind,
+    transparency: Transparency,
+    opts: RenderOpts,
 }
 
 impl<'a, P: Palette> TopShadeRenderer<'a, P> {
@@ -23,13 +367,179 @@ impl<'a, P: Palette> TopShadeRenderer<'a, P> {
         Self {
             palette,
             height_mode: mode,
+            shader: TopShade,
+            dimension: DimensionKind::Overworld,
+            transparency: Transparency::default(),
+            opts: RenderOpts::default(),
         }
     }
+}
+
+impl<'a, P: Palette, S: Shader> TopShadeRenderer<'a, P, S> {
+    /// Use `shader` to shade the render, rather than [`TopShade`].
+    pub fn with_shader<S2: Shader>(self, shader: S2) -> TopShadeRenderer<'a, P, S2> {
+        TopShadeRenderer {
+            palette: self.palette,
+            height_mode: self.height_mode,
+            shader,
+            dimension: self.dimension,
+            transparency: self.transparency,
+            opts: self.opts,
+        }
+    }
+
+    /// Choose which blocks are looked through. By default water is, and
+    /// leaves are not.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{HeightMode, TopShadeRenderer, Transparency};
+    /// # struct MyPalette;
+    /// # impl fastanvil::Palette for MyPalette {
+    /// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+    /// #         [0; 4]
+    /// #     }
+    /// # }
+    /// # fn palette() -> MyPalette { MyPalette }
+    /// let palette = palette();
+    /// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust).with_transparency(Transparency {
+    ///     water: true,
+    ///     leaves: Some(128),
+    /// });
+    /// ```
+    pub fn with_transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Choose what part of the world to draw.
+    ///
+    /// ```no_run
+    /// # use fastanvil::{HeightMode, RenderOpts, TopShadeRenderer};
+    /// # struct MyPalette;
+    /// # impl fastanvil::Palette for MyPalette {
+    /// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+    /// #         [0; 4]
+    /// #     }
+    /// # }
+    /// # fn palette() -> MyPalette { MyPalette }
+    /// let palette = palette();
+    /// // Look at the deepslate layer, to see ores and caves.
+    /// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust).with_opts(RenderOpts {
+    ///     y_range: -59..0,
+    /// });
+    /// ```
+    pub fn with_opts(mut self, opts: RenderOpts) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Render as the given kind of dimension. The default is the overworld.
+    ///
+    /// For the nether, the roof is stripped by starting below Y=120 and
+    /// drawing the floor of the first open space found, ignoring heightmaps.
+    /// For the end, columns of void are left transparent and do not affect
+    /// the shading of their neighbours. In both, chunks without biomes are
+    /// tinted as the dimension's own biome.
+    pub fn with_dimension(mut self, kind: DimensionKind) -> Self {
+        self.dimension = kind;
+        self
+    }
+
+    /// The highest block of the chunk that can be drawn.
+    fn top<C: Chunk>(&self, chunk: &C) -> isize {
+        chunk.y_range().end.min(self.opts.y_range.end) - 1
+    }
+
+    /// The height of the first air block above the column to draw.
+    fn surface_height<C: Chunk>(&self, chunk: &C, x: usize, z: usize) -> isize {
+        let top = self.top(chunk);
+        match self.dimension {
+            DimensionKind::Nether if self.opts.y_range.end > NETHER_ROOF_Y => {
+                // Through the roof, then through the open space below it.
+                let start = NETHER_ROOF_Y.min(top);
+                floor_below(chunk, x, z, start, &[false, true])
+                    .map_or(chunk.y_range().start, |y| y + 1)
+            }
+            DimensionKind::Nether => self.highest_block(chunk, x, z, top),
+            DimensionKind::Overworld | DimensionKind::End => {
+                let height = chunk.surface_height(x, z, self.height_mode);
+                self.clamp_height(chunk, x, z, height, top)
+            }
+        }
+    }
+
+    /// [`surface_height`](Self::surface_height) of every column, indexed by
+    /// `[z][x]`.
+    /// Blocks the palette says don't cover the column are left out, so that
+    /// they don't affect shading.
+    fn surface_heights<C: Chunk>(&self, chunk: &C) -> [[isize; 16]; 16] {
+        let mut heights = match self.dimension {
+            DimensionKind::Nether => {
+                let mut heights = [[0; 16]; 16];
+                for (z, row) in heights.iter_mut().enumerate() {
+                    for (x, h) in row.iter_mut().enumerate() {
+                        *h = self.surface_height(chunk, x, z);
+                    }
+                }
+                heights
+            }
+            DimensionKind::Overworld | DimensionKind::End => {
+                let top = self.top(chunk);
+                let mut heights = chunk.surface_heights(self.height_mode);
+                for (z, row) in heights.iter_mut().enumerate() {
+                    for (x, h) in row.iter_mut().enumerate() {
+                        *h = self.clamp_height(chunk, x, z, *h, top);
+                    }
+                }
+                heights
+            }
+        };
+
+        let y_min = chunk.y_range().start;
+        for (z, row) in heights.iter_mut().enumerate() {
+            for (x, h) in row.iter_mut().enumerate() {
+                while *h > y_min {
+                    match chunk.block(x, *h - 1, z) {
+                        Some(block) if self.palette.cover(block) == 0 => *h -= 1,
+                        _ => break,
+                    }
+                }
+            }
+        }
+        heights
+    }
+
+    /// Move a surface height above `top` down to the first block below it.
+    fn clamp_height<C: Chunk>(
+        &self,
+        chunk: &C,
+        x: usize,
+        z: usize,
+        height: isize,
+        top: isize,
+    ) -> isize {
+        if height > top + 1 {
+            self.highest_block(chunk, x, z, top)
+        } else {
+            height
+        }
+    }
+
+    /// The height of the air above the highest block at or below `top`.
+    fn highest_block<C: Chunk>(&self, chunk: &C, x: usize, z: usize, top: isize) -> isize {
+        floor_below(chunk, x, z, top, &[true]).map_or(chunk.y_range().start, |y| y + 1)
+    }
+
+    fn biome<C: Chunk>(&self, chunk: &C, x: usize, y: isize, z: usize) -> Option<Biome> {
+        chunk
+            .biome(x, y, z)
+            .or_else(|| self.dimension.default_biome())
+    }
 
     pub fn render<C: Chunk>(&self, chunk: &C, north: Option<&C>) -> [Rgba; 16 * 16] {
         let mut data = [[0, 0, 0, 0]; 16 * 16];
 
-        if chunk.status() != "full" && chunk.status() != "spawn" {
+        if !chunk.status().is_generated() {
             // Chunks that have been fully generated will have a 'full' status.
             // Skip chunks that don't; the way they render is unpredictable.
             return data;
@@ -37,21 +547,57 @@ impl<'a, P: Palette> TopShadeRenderer<'a, P> {
 
         let y_range = chunk.y_range();
 
+        // Columns with nothing to draw within the range of heights.
+        let y_min = y_range.start.max(self.opts.y_range.start.saturating_sub(1));
+        let is_empty = |height: isize| height <= self.opts.y_range.start;
+
+        // Void in the end is treated as level with whatever is next to it.
+        let is_void =
+            |height: isize| self.dimension == DimensionKind::End && height <= y_range.start;
+
+        let heights = self.surface_heights(chunk);
+        let north_heights = north.map(|c| self.surface_heights(c));
+
         for z in 0..16 {
             for x in 0..16 {
-                let air_height = chunk.surface_height(x, z, self.height_mode);
+                let air_height = heights[z][x];
+                if is_void(air_height) || is_empty(air_height) {
+                    continue;
+                }
                 let block_height = (air_height - 1).max(y_range.start);
 
-                let colour = self.drill_for_colour(x, block_height, z, chunk, y_range.start);
+                let colour = self.drill_for_colour(x, block_height, z, chunk, y_min);
 
                 let north_air_height = match z {
                     // if top of chunk, get height from the chunk above.
-                    0 => north
-                        .map(|c| c.surface_height(x, 15, self.height_mode))
-                        .unwrap_or(block_height),
-                    z => chunk.surface_height(x, z - 1, self.height_mode),
+                    0 => north_heights.map_or(block_height, |h| h[15][x]),
+                    z => heights[z - 1][x],
                 };
-                let colour = top_shade_colour(colour, air_height, north_air_height);
+                let west_air_height = match x {
+                    0 => air_height,
+                    x => heights[z][x - 1],
+                };
+
+                let context = ShadeContext {
+                    x,
+                    z,
+                    height: air_height,
+                    north_height: if is_void(north_air_height) {
+                        air_height
+                    } else {
+                        north_air_height
+                    },
+                    west_height: if is_void(west_air_height) {
+                        air_height
+                    } else {
+                        west_air_height
+                    },
+                    biome: self.biome(chunk, x, block_height, z),
+                    block_light: None,
+                    sky_light: None,
+                }
+                .with_light(chunk);
+                let colour = self.shader.shade(colour, &context);
 
                 data[z * 16 + x] = colour;
             }
@@ -73,35 +619,54 @@ impl<'a, P: Palette> TopShadeRenderer<'a, P> {
         let mut current_height = y_start; // -1 because we want the block below the air.
         let mut colour = [0, 0, 0, 0];
 
+        // How much water has been looked through so far.
+        let mut water_above = 0;
+
         // FIXME: Should this be >= y_min?
         while colour[3] != 255 && current_height > y_min {
-            let current_biome = chunk.biome(x, current_height, z);
+            let current_biome = self.biome(chunk, x, current_height, z);
             let current_block = chunk.block(x, current_height, z);
 
             if let Some(current_block) = current_block.as_ref() {
-                match current_block.name() {
+                let name = current_block.name();
+                match name {
                     "minecraft:air" | "minecraft:cave_air" => {
                         current_height -= 1;
                     }
                     // TODO: Can potentially optimize this for ocean floor using
                     // heightmaps.
-                    "minecraft:water"
-                    | "minecraft:bubble_column"
-                    | "minecraft:kelp"
-                    | "minecraft:kelp_plant"
-                    | "minecraft:sea_grass"
-                    | "minecraft:tall_seagrass" => {
+                    _ if is_water(name) && self.transparency.water => {
                         let mut block_colour = self.palette.pick(current_block, current_biome);
                         let water_depth = water_depth(x, current_height, z, chunk, y_min);
-                        let alpha = water_depth_to_alpha(water_depth);
+                        let alpha = self.shader.water_alpha(water_depth);
 
-                        block_colour[3] = alpha as u8;
+                        block_colour[3] = alpha;
 
                         colour = a_over_b_colour(colour, block_colour);
                         current_height -= water_depth;
+                        water_above += water_depth;
                     }
                     _ => {
-                        let block_colour = self.palette.pick(current_block, current_biome);
+                        let cover = self.palette.cover(current_block);
+                        if cover == 0 {
+                            current_height -= 1;
+                            continue;
+                        }
+
+                        let mut block_colour = self.palette.pick(current_block, current_biome);
+                        block_colour[3] = block_colour[3].min(cover);
+                        if is_water(name) {
+                            block_colour[3] = 255;
+                        }
+                        if let (Some(alpha), true) =
+                            (self.transparency.leaves, name.ends_with("_leaves"))
+                        {
+                            block_colour[3] = block_colour[3].min(alpha);
+                        }
+                        if water_above > 0 {
+                            block_colour = self.shader.underwater(block_colour, water_above);
+                        }
+
                         colour = a_over_b_colour(colour, block_colour);
                         current_height -= 1;
                     }
@@ -115,6 +680,595 @@ impl<'a, P: Palette> TopShadeRenderer<'a, P> {
     }
 }
 
+impl<'a, P: Palette, S: Shader> ChunkRenderer for TopShadeRenderer<'a, P, S> {
+    fn render<C: Chunk>(&self, chunk: &C, north: Option<&C>) -> [Rgba; 16 * 16] {
+        TopShadeRenderer::render(self, chunk, north)
+    }
+}
+
+/// Renders caves rather than the surface. Each column is drawn as the floor
+/// of the topmost air pocket that has a roof at or below `ceiling`. Any air
+/// at the ceiling itself, such as the sky, is passed through first. Columns
+/// without such a pocket are left transparent.
+///
+/// ```no_run
+/// # use fastanvil::{CaveRenderer, render_region, Dimension, JavaChunk, RCoord, RegionFileLoader};
+/// # struct MyPalette;
+/// # impl fastanvil::Palette for MyPalette {
+/// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+/// #         [0; 4]
+/// #     }
+/// # }
+/// # fn palette() -> MyPalette { MyPalette }
+/// let palette = palette();
+/// let loader = RegionFileLoader::new("world/region".into());
+/// let dimension = Dimension::<JavaChunk, _>::new(loader);
+///
+/// let renderer = CaveRenderer::new(&palette, 40);
+/// let map = render_region(RCoord(0), RCoord(0), dimension, renderer);
+/// ```
+pub struct CaveRenderer<'a, P: Palette, S: Shader = TopShade> {
+    palette: &'a P,
+    ceiling: isize,
+    shader: S,
+}
+
+impl<'a, P: Palette> CaveRenderer<'a, P> {
+    pub fn new(palette: &'a P, ceiling: isize) -> Self {
+        Self {
+            palette,
+            ceiling,
+            shader: TopShade,
+        }
+    }
+}
+
+impl<'a, P: Palette, S: Shader> CaveRenderer<'a, P, S> {
+    /// Use `shader` to shade the render, rather than [`TopShade`].
+    pub fn with_shader<S2: Shader>(self, shader: S2) -> CaveRenderer<'a, P, S2> {
+        CaveRenderer {
+            palette: self.palette,
+            ceiling: self.ceiling,
+            shader,
+        }
+    }
+
+    pub fn render<C: Chunk>(&self, chunk: &C, north: Option<&C>) -> [Rgba; 16 * 16] {
+        let mut data = [[0, 0, 0, 0]; 16 * 16];
+
+        if !chunk.status().is_generated() {
+            return data;
+        }
+
+        let mut floors = [None; 16 * 16];
+        for z in 0..16 {
+            for x in 0..16 {
+                floors[z * 16 + x] = self.cave_floor(chunk, x, z);
+            }
+        }
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let floor = match floors[z * 16 + x] {
+                    Some(floor) => floor,
+                    None => continue,
+                };
+                let block = match chunk.block(x, floor, z) {
+                    Some(block) => block,
+                    None => continue,
+                };
+
+                // Neighbours without a cave are treated as level with this
+                // column.
+                let height = floor + 1;
+                let north_height = match z {
+                    0 => north.and_then(|c| self.cave_floor(c, x, 15)),
+                    z => floors[(z - 1) * 16 + x],
+                };
+                let west_height = match x {
+                    0 => None,
+                    x => floors[z * 16 + x - 1],
+                };
+
+                let biome = chunk.biome(x, floor, z);
+                let colour = self.palette.pick(block, biome.clone());
+                let context = ShadeContext {
+                    x,
+                    z,
+                    height,
+                    north_height: north_height.map_or(height, |h| h + 1),
+                    west_height: west_height.map_or(height, |h| h + 1),
+                    biome,
+                    block_light: None,
+                    sky_light: None,
+                }
+                .with_light(chunk);
+
+                data[z * 16 + x] = self.shader.shade(colour, &context);
+            }
+        }
+
+        data
+    }
+
+    /// Find the height of the floor of the cave to draw for a column.
+    fn cave_floor<C: Chunk>(&self, chunk: &C, x: usize, z: usize) -> Option<isize> {
+        let start = self.ceiling.min(chunk.y_range().end - 1);
+
+        // Pass through anything open at the ceiling, then the roof of the
+        // cave, then the cave itself.
+        floor_below(chunk, x, z, start, &[true, false, true])
+    }
+}
+
+impl<'a, P: Palette, S: Shader> ChunkRenderer for CaveRenderer<'a, P, S> {
+    fn render<C: Chunk>(&self, chunk: &C, north: Option<&C>) -> [Rgba; 16 * 16] {
+        CaveRenderer::render(self, chunk, north)
+    }
+}
+
+/// Renders an isometric view of the world, looking down at the south-east
+/// side of blocks, like Minecraft Overviewer. Each block is drawn as a cube
+/// `4 * scale` pixels wide and tall, with each visible face coloured by
+/// [`Palette::pick_face`].
+///
+/// ```no_run
+/// # use fastanvil::{IsometricRenderer, Dimension, JavaChunk, RCoord, RegionFileLoader};
+/// # fn palette() -> fastanvil::RenderedPalette { unimplemented!() }
+/// let palette = palette();
+/// let loader = RegionFileLoader::new("world/region".into());
+/// let dimension = Dimension::<JavaChunk, _>::new(loader);
+///
+/// let renderer = IsometricRenderer::new(&palette).with_scale(2);
+/// let image = renderer.render_region(RCoord(0), RCoord(0), &dimension);
+/// image.save("region.png").unwrap();
+/// ```
+#[cfg(feature = "image")]
+pub struct IsometricRenderer<'a, P: Palette> {
+    palette: &'a P,
+    scale: u32,
+    y_range: Range<isize>,
+}
+
+#[cfg(feature = "image")]
+impl<'a, P: Palette> IsometricRenderer<'a, P> {
+    pub fn new(palette: &'a P) -> Self {
+        Self {
+            palette,
+            scale: 1,
+            y_range: -64..320,
+        }
+    }
+
+    /// Set the size of blocks in the render. Cubes are drawn `4 * scale`
+    /// pixels wide. Defaults to 1.
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Set the heights of blocks to render, which also decides the height
+    /// of the image. Defaults to -64..320, the heights of the overworld.
+    pub fn with_y_range(mut self, y_range: Range<isize>) -> Self {
+        self.y_range = y_range;
+        self
+    }
+
+    /// Render a single chunk.
+    pub fn render_chunk<C: Chunk>(&self, chunk: &C) -> RgbaImage {
+        let mut canvas = IsometricCanvas::new(16, self.scale, self.y_range.clone());
+        self.draw_chunk(&mut canvas, chunk, 0, 0);
+        canvas.image
+    }
+
+    /// Render a whole region. Missing chunks are left transparent.
+    pub fn render_region<C: Chunk, R: RegionLoader<C>>(
+        &self,
+        x: RCoord,
+        z: RCoord,
+        dimension: &Dimension<C, R>,
+    ) -> RgbaImage {
+        let mut canvas = IsometricCanvas::new(512, self.scale, self.y_range.clone());
+
+        let region = match dimension.region(x, z) {
+            Some(r) => r,
+            None => return canvas.image,
+        };
+
+        // Chunks further south and east cover those behind them, so must be
+        // drawn later.
+        for z in 0..32 {
+            for x in 0..32 {
+                if let Some(chunk) = region.chunk(CCoord(x), CCoord(z)) {
+                    self.draw_chunk(&mut canvas, &chunk, x * 16, z * 16);
+                }
+            }
+        }
+
+        canvas.image
+    }
+
+    fn draw_chunk<C: Chunk>(&self, canvas: &mut IsometricCanvas, chunk: &C, x0: isize, z0: isize) {
+        if !chunk.status().is_generated() {
+            return;
+        }
+
+        let chunk_range = chunk.y_range();
+        let start = chunk_range.start.max(self.y_range.start);
+        let end = chunk_range.end.min(self.y_range.end);
+
+        // Blocks above, south and east of a block are in front of it, so
+        // drawing bottom to top, north to south and west to east means
+        // blocks are always drawn over the ones they hide.
+        for y in start..end {
+            for z in 0..16 {
+                for x in 0..16 {
+                    let block = match chunk.block(x, y, z) {
+                        Some(b) if !is_air_like(b) => b,
+                        _ => continue,
+                    };
+
+                    if self.is_hidden(chunk, x, y, z, end) {
+                        continue;
+                    }
+
+                    let biome = chunk.biome(x, y, z);
+                    let pick = |face| self.palette.pick_face(block, biome.clone(), face);
+                    canvas.draw_block(
+                        x0 + x as isize,
+                        y,
+                        z0 + z as isize,
+                        [
+                            pick(BlockFace::Top),
+                            pick(BlockFace::South),
+                            pick(BlockFace::East),
+                        ],
+                    );
+                }
+            }
+        }
+    }
+
+    /// A block is hidden if the blocks in front of each of its visible faces
+    /// are opaque. Blocks on the edge of a chunk are never hidden.
+    fn is_hidden<C: Chunk>(&self, chunk: &C, x: usize, y: isize, z: usize, end: isize) -> bool {
+        if x == 15 || z == 15 || y + 1 >= end {
+            return false;
+        }
+
+        let opaque = |x: usize, y: isize, z: usize| match chunk.block(x, y, z) {
+            Some(b) => self.palette.pick(b, None)[3] == 255,
+            None => false,
+        };
+
+        opaque(x + 1, y, z) && opaque(x, y + 1, z) && opaque(x, y, z + 1)
+    }
+}
+
+/// An image that blocks are drawn to for an isometric render, covering a
+/// square area `size` blocks wide.
+#[cfg(feature = "image")]
+struct IsometricCanvas {
+    image: RgbaImage,
+    scale: isize,
+    size: isize,
+    y_max: isize,
+    // Which face each pixel of a cube belongs to, row by row.
+    sprite: Vec<Option<BlockFace>>,
+}
+
+#[cfg(feature = "image")]
+impl IsometricCanvas {
+    fn new(size: isize, scale: u32, y_range: Range<isize>) -> Self {
+        let k = scale as isize;
+        let height = (y_range.end - y_range.start).max(0);
+        let image = RgbaImage::new(
+            (4 * size * k) as u32,
+            (2 * size * k + 2 * height * k) as u32,
+        );
+
+        Self {
+            image,
+            scale: k,
+            size,
+            y_max: y_range.end,
+            sprite: cube_sprite(k),
+        }
+    }
+
+    /// Draw a block over what has already been drawn, with a colour for each
+    /// face given in the order of top, south, east.
+    fn draw_block(&mut self, x: isize, y: isize, z: isize, colours: [Rgba; 3]) {
+        let k = self.scale;
+        let left = (x - z + self.size - 1) * 2 * k;
+        let top = (x + z) * k + (self.y_max - 1 - y) * 2 * k;
+
+        for (i, face) in self.sprite.iter().enumerate() {
+            let colour = match face {
+                Some(BlockFace::Top) => colours[0],
+                Some(BlockFace::South) => colours[1],
+                Some(BlockFace::East) => colours[2],
+                None => continue,
+            };
+            if colour[3] == 0 {
+                continue;
+            }
+
+            let px = left + i as isize % (4 * k);
+            let py = top + i as isize / (4 * k);
+            if px < 0
+                || py < 0
+                || px >= self.image.width() as isize
+                || py >= self.image.height() as isize
+            {
+                continue;
+            }
+
+            let pixel = self.image.get_pixel_mut(px as u32, py as u32);
+            pixel.0 = a_over_b_colour(colour, pixel.0);
+        }
+    }
+}
+
+/// Work out which face each pixel of a cube `4 * k` pixels wide belongs to.
+/// The top is a diamond half as tall as it is wide, with the south and east
+/// faces below it on the left and right.
+#[cfg(feature = "image")]
+fn cube_sprite(k: isize) -> Vec<Option<BlockFace>> {
+    let k_f = k as f64;
+    let mut sprite = Vec::with_capacity((16 * k * k) as usize);
+
+    for py in 0..4 * k {
+        for px in 0..4 * k {
+            // Use the centre of the pixel.
+            let x = px as f64 + 0.5;
+            let y = py as f64 + 0.5;
+
+            let face = if (x - 2. * k_f).abs() / (2. * k_f) + (y - k_f).abs() / k_f <= 1. {
+                Some(BlockFace::Top)
+            } else if x < 2. * k_f {
+                let edge = k_f + x / 2.;
+                (y >= edge && y < edge + 2. * k_f).then_some(BlockFace::South)
+            } else {
+                let edge = 2. * k_f - (x - 2. * k_f) / 2.;
+                (y >= edge && y < edge + 2. * k_f).then_some(BlockFace::East)
+            };
+            sprite.push(face);
+        }
+    }
+
+    sprite
+}
+
+/// How a [`BiomeRenderer`] colours biomes.
+pub enum BiomeColours<'a> {
+    /// The colour of grass in the biome, from a palette.
+    Grass(&'a dyn Palette),
+
+    /// The colour of leaves in the biome, from a palette.
+    Foliage(&'a dyn Palette),
+
+    /// The colour of water in the biome, from a palette.
+    Water(&'a dyn Palette),
+
+    /// A distinct colour for each biome, so that similar biomes can be told
+    /// apart. Unknown biomes get a colour based on their name.
+    Categorical,
+}
+
+/// Renders a map of the biomes at the surface of the world, rather than the
+/// blocks. Columns are not shaded.
+///
+/// ```no_run
+/// # use fastanvil::{BiomeColours, BiomeRenderer, HeightMode, render_region, Dimension, JavaChunk, RCoord, RegionFileLoader};
+/// let loader = RegionFileLoader::new("world/region".into());
+/// let dimension = Dimension::<JavaChunk, _>::new(loader);
+///
+/// let renderer = BiomeRenderer::new(BiomeColours::Categorical, HeightMode::Trust);
+/// let map = render_region(RCoord(0), RCoord(0), dimension, renderer);
+/// ```
+pub struct BiomeRenderer<'a> {
+    colours: BiomeColours<'a>,
+    height_mode: HeightMode,
+}
+
+impl<'a> BiomeRenderer<'a> {
+    pub fn new(colours: BiomeColours<'a>, mode: HeightMode) -> Self {
+        Self {
+            colours,
+            height_mode: mode,
+        }
+    }
+
+    pub fn render<C: Chunk>(&self, chunk: &C) -> [Rgba; 16 * 16] {
+        let mut data = [[0, 0, 0, 0]; 16 * 16];
+
+        if !chunk.status().is_generated() {
+            return data;
+        }
+
+        // Blocks tinted by the biome, to get colours from palettes.
+        let tinted = |name: &str| Block::new(name.to_owned(), Default::default());
+        let tint = match self.colours {
+            BiomeColours::Grass(palette) => Some((palette, tinted("minecraft:grass"))),
+            BiomeColours::Foliage(palette) => Some((palette, tinted("minecraft:oak_leaves"))),
+            BiomeColours::Water(palette) => Some((palette, tinted("minecraft:water"))),
+            BiomeColours::Categorical => None,
+        };
+
+        let y_range = chunk.y_range();
+        let heights = chunk.surface_heights(self.height_mode);
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let y = (heights[z][x] - 1).max(y_range.start);
+                let biome = match chunk.biome(x, y, z) {
+                    Some(biome) => biome,
+                    None => continue,
+                };
+
+                data[z * 16 + x] = match &tint {
+                    Some((palette, block)) => {
+                        let mut colour = palette.pick(block, Some(biome));
+                        colour[3] = 255;
+                        colour
+                    }
+                    None => categorical_colour(&biome),
+                };
+            }
+        }
+
+        data
+    }
+}
+
+impl<'a> ChunkRenderer for BiomeRenderer<'a> {
+    fn render<C: Chunk>(&self, chunk: &C, _north: Option<&C>) -> [Rgba; 16 * 16] {
+        BiomeRenderer::render(self, chunk)
+    }
+}
+
+/// A distinct colour for a biome. Colours for biomes from before 1.18 mostly
+/// follow those used by Amidst, so that maps are familiar.
+fn categorical_colour(biome: &Biome) -> Rgba {
+    use Biome::*;
+
+    let rgb: u32 = match biome {
+        Ocean => 0x000070,
+        DeepOcean => 0x000030,
+        FrozenOcean => 0x7070d6,
+        DeepFrozenOcean => 0x404090,
+        ColdOcean => 0x202070,
+        DeepColdOcean => 0x202038,
+        LukewarmOcean => 0x000090,
+        DeepLukewarmOcean => 0x000040,
+        WarmOcean => 0x0000ac,
+        DeepWarmOcean => 0x000050,
+        River => 0x0000ff,
+        FrozenRiver => 0xa0a0ff,
+        Beach => 0xfade55,
+        StoneShore => 0xa2a284,
+        SnowyBeach => 0xfaf0c0,
+        Forest => 0x056621,
+        WoodedHills => 0x22551c,
+        FlowerForest => 0x2d8e49,
+        BirchForest => 0x307444,
+        BirchForestHills => 0x1f5f32,
+        TallBirchForest => 0x589c6c,
+        TallBirchHills => 0x47875a,
+        DarkForest => 0x40511a,
+        DarkForestHills => 0x687942,
+        Jungle => 0x537b09,
+        JungleHills => 0x2c4205,
+        ModifiedJungle => 0x7ba331,
+        JungleEdge => 0x628b17,
+        ModifiedJungleEdge => 0x8ab33f,
+        BambooJungle => 0x768e14,
+        BambooJungleHills => 0x3b470a,
+        Taiga => 0x0b6659,
+        TaigaHills => 0x163933,
+        TaigaMountains => 0x338e81,
+        SnowyTaiga => 0x31554a,
+        SnowyTaigaHills => 0x243f36,
+        SnowyTaigaMountains => 0x597d72,
+        GiantTreeTaiga => 0x596651,
+        GiantTreeTaigaHills => 0x454f3e,
+        GiantSpruceTaiga => 0x818e79,
+        GiantSpruceTaigaHills => 0x6d7766,
+        MushroomFields => 0xff00ff,
+        MushroomFieldShore => 0xa000ff,
+        Swamp => 0x07f9b2,
+        SwampHills => 0x2fffda,
+        Savanna => 0xbdb25f,
+        SavannaPlateau => 0xa79d64,
+        ShatteredSavanna => 0xe5da87,
+        ShatteredSavannaPlateau => 0xcfc58c,
+        Plains => 0x8db360,
+        SunflowerPlains => 0xb5db88,
+        Desert => 0xfa9418,
+        DesertHills => 0xd25f12,
+        DesertLakes => 0xffbc40,
+        SnowyTundra => 0xffffff,
+        SnowyMountains => 0xa0a0a0,
+        IceSpikes => 0xb4dcdc,
+        Mountains => 0x606060,
+        WoodedMountains => 0x507050,
+        GravellyMountains => 0x888888,
+        ModifiedGravellyMountains => 0x789878,
+        MountainEdge => 0x72789a,
+        Badlands => 0xd94515,
+        BadlandsPlateau => 0xca8c65,
+        ModifiedBadlandsPlateau => 0xf2b48d,
+        WoodedBadlandsPlateau => 0xb09765,
+        ModifiedWoodedBadlandsPlateau => 0xd8bf8d,
+        ErodedBadlands => 0xff6d3d,
+        Nether => 0xbf3b3b,
+        TheEnd | SmallEndIslands | EndMidlands | EndHighlands | EndBarrens => 0x8080ff,
+        SoulSandValley => 0x5e3830,
+        CrimsonForest => 0xdd0808,
+        WarpedForest => 0x49907b,
+        BasaltDeltas => 0x403636,
+        TheVoid => 0x000000,
+        Meadow => 0x60a445,
+        Grove => 0x47726c,
+        SnowySlopes => 0xc4c4c4,
+        FrozenPeaks => 0xa0b4dc,
+        JaggedPeaks => 0xdcdcc8,
+        StonyPeaks => 0x7b8f74,
+        LushCaves => 0x6b8e23,
+        DripstoneCaves => 0x866043,
+        DeepDark => 0x0a1414,
+        MangroveSwamp => 0x24c48e,
+        CherryGrove => 0xffb7c5,
+        PaleGarden => 0x696d5f,
+        Unknown(name) => {
+            // FNV-1a, so the colour is the same every run.
+            name.bytes().fold(0x811c9dc5u32, |hash, b| {
+                (hash ^ b as u32).wrapping_mul(0x01000193)
+            }) & 0xffffff
+        }
+    };
+
+    [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255]
+}
+
+/// Move down a column from `start`, passing through a run of air-like blocks
+/// or solid blocks for each of `runs`, where true is a run of air. Gives the
+/// height reached, if still within the chunk.
+fn floor_below<C: Chunk>(
+    chunk: &C,
+    x: usize,
+    z: usize,
+    start: isize,
+    runs: &[bool],
+) -> Option<isize> {
+    let y_min = chunk.y_range().start;
+    let is_air = |y: isize| match chunk.block(x, y, z) {
+        Some(block) => is_air_like(block),
+        None => true,
+    };
+
+    let mut y = start;
+    for &want_air in runs {
+        while y >= y_min && is_air(y) == want_air {
+            y -= 1;
+        }
+    }
+
+    if y >= y_min {
+        Some(y)
+    } else {
+        None
+    }
+}
+
+fn is_air_like(block: &Block) -> bool {
+    matches!(
+        block.name(),
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
 fn water_depth_to_alpha(water_depth: isize) -> u8 {
     // Water will absorb a fraction of the light per unit depth. So if we say
     // that every metre of water absorbs half the light going through it, then 2
@@ -133,18 +1287,21 @@ fn water_depth_to_alpha(water_depth: isize) -> u8 {
     (180 + 2 * water_depth).min(250) as u8
 }
 
-fn water_depth<C: Chunk>(x: usize, mut y: isize, z: usize, chunk: &C, y_min: isize) -> isize {
-    let is_water = |block_name: &str| {
-        matches! {block_name,
-            "minecraft:water"
+/// Whether a block is water, or something that only grows in water and is
+/// drawn as water.
+fn is_water(block_name: &str) -> bool {
+    matches!(
+        block_name,
+        "minecraft:water"
             | "minecraft:bubble_column"
             | "minecraft:kelp"
             | "minecraft:kelp_plant"
-            | "minecraft:sea_grass"
+            | "minecraft:seagrass"
             | "minecraft:tall_seagrass"
-        }
-    };
+    )
+}
 
+fn water_depth<C: Chunk>(x: usize, mut y: isize, z: usize, chunk: &C, y_min: isize) -> isize {
     let mut depth = 1;
     while y > y_min {
         let block = match chunk.block(x, y, z) {
@@ -166,7 +1323,7 @@ fn water_depth<C: Chunk>(x: usize, mut y: isize, z: usize, chunk: &C, y_min: isi
 /// was laid on top of colour B.
 ///
 /// See https://en.wikipedia.org/wiki/Alpha_compositing
-fn a_over_b_colour(colour: [u8; 4], below_colour: [u8; 4]) -> [u8; 4] {
+pub(crate) fn a_over_b_colour(colour: [u8; 4], below_colour: [u8; 4]) -> [u8; 4] {
     let linear = |c: u8| (((c as usize).pow(2)) as f32) / ((255 * 255) as f32);
 
     let over_component = |ca: u8, aa: u8, cb: u8, ab: u8| {
@@ -228,17 +1385,257 @@ impl<T: Clone> RegionMap<T> {
     }
 }
 
-pub fn render_region<P: Palette, C: Chunk + std::fmt::Debug, R: RegionLoader<C>>(
+impl RegionMap<Rgba> {
+    /// Turn the map into an image, with north at the top.
+    #[cfg(feature = "image")]
+    pub fn to_image(&self) -> RgbaImage {
+        let mut img = RgbaImage::new(32 * 16, 32 * 16);
+        self.draw_into(&mut img, 0, 0);
+        img
+    }
+
+    /// Draw the map onto part of a larger image, with its north-west corner
+    /// at the pixel `left`, `top`. Will panic if the sink isn't big enough.
+    pub fn draw_into<S: PixelSink + ?Sized>(&self, img: &mut S, left: u32, top: u32) {
+        for cz in 0..32 {
+            for cx in 0..32 {
+                let chunk = self.chunk(CCoord(cx), CCoord(cz));
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let px = left + (cx * 16) as u32 + x as u32;
+                        let pz = top + (cz * 16) as u32 + z as u32;
+                        img.put_pixel(px, pz, chunk[z * 16 + x]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A rectangle of regions, including both corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionBounds {
+    pub min: (RCoord, RCoord),
+    pub max: (RCoord, RCoord),
+}
+
+impl RegionBounds {
+    /// The bounds between two corners, given in any order, as a
+    /// [`RegionPos`] or a tuple of X and Z.
+    pub fn new(a: impl Into<RegionPos>, b: impl Into<RegionPos>) -> Self {
+        let (a, b) = (a.into(), b.into());
+        Self {
+            min: (a.x.min(b.x), a.z.min(b.z)),
+            max: (a.x.max(b.x), a.z.max(b.z)),
+        }
+    }
+
+    /// The smallest bounds covering every given region, such as those listed
+    /// by a [`RegionLoader`]. `None` if there are no regions.
+    pub fn covering(regions: &[(RCoord, RCoord)]) -> Option<Self> {
+        let (first, rest) = regions.split_first()?;
+        Some(
+            rest.iter()
+                .fold(Self::new(*first, *first), |bounds, &(x, z)| Self {
+                    min: (bounds.min.0.min(x), bounds.min.1.min(z)),
+                    max: (bounds.max.0.max(x), bounds.max.1.max(z)),
+                }),
+        )
+    }
+
+    /// Whether the region is inside the bounds.
+    pub fn contains(&self, x: RCoord, z: RCoord) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&z)
+    }
+
+    /// The number of regions across, from west to east.
+    pub fn width(&self) -> usize {
+        (self.max.0 .0 - self.min.0 .0) as usize + 1
+    }
+
+    /// The number of regions down, from north to south.
+    pub fn height(&self) -> usize {
+        (self.max.1 .0 - self.min.1 .0) as usize + 1
+    }
+
+    /// The width of a render of the bounds in pixels, one per block.
+    pub fn pixel_width(&self) -> u32 {
+        (self.width() * 32 * 16) as u32
+    }
+
+    /// The height of a render of the bounds in pixels, one per block.
+    pub fn pixel_height(&self) -> u32 {
+        (self.height() * 32 * 16) as u32
+    }
+
+    /// Every region in the bounds, a row at a time from the north-west
+    /// corner.
+    pub fn regions(&self) -> impl Iterator<Item = (RCoord, RCoord)> {
+        let (min, max) = (self.min, self.max);
+        (min.1 .0..=max.1 .0)
+            .flat_map(move |z| (min.0 .0..=max.0 .0).map(move |x| (RCoord(x), RCoord(z))))
+    }
+}
+
+/// Combine rendered regions into one image of the whole of `bounds`, with
+/// north at the top. Each region is 512 pixels square. Regions missing from
+/// `maps` are left transparent, and those outside the bounds are left out.
+///
+/// This is for when regions are rendered separately, such as in parallel.
+/// Otherwise see [`render_dimension`].
+#[cfg(feature = "image")]
+pub fn stitch_regions(
+    bounds: RegionBounds,
+    maps: impl IntoIterator<Item = RegionMap<Rgba>>,
+) -> RgbaImage {
+    let mut img = RgbaImage::new(bounds.pixel_width(), bounds.pixel_height());
+    stitch_regions_into(bounds, maps, &mut img);
+    img
+}
+
+/// Like [`stitch_regions`], but drawing into `sink`, which must be at least
+/// [`pixel_width`](RegionBounds::pixel_width) by
+/// [`pixel_height`](RegionBounds::pixel_height) of the bounds. Pixels of
+/// missing regions are left as they are.
+pub fn stitch_regions_into<S: PixelSink + ?Sized>(
+    bounds: RegionBounds,
+    maps: impl IntoIterator<Item = RegionMap<Rgba>>,
+    sink: &mut S,
+) {
+    let region_len = 32 * 16;
+    let (width, height) = sink.dimensions();
+    assert!(width >= bounds.pixel_width() && height >= bounds.pixel_height());
+
+    for map in maps {
+        if !bounds.contains(map.x, map.z) {
+            continue;
+        }
+
+        let left = (map.x.0 - bounds.min.0 .0) as usize * region_len;
+        let top = (map.z.0 - bounds.min.1 .0) as usize * region_len;
+        map.draw_into(sink, left as u32, top as u32);
+    }
+}
+
+/// Render every region of a dimension in `bounds` into one image, with north
+/// at the top and a pixel per block. The top-left pixel is the north-west
+/// corner of the region at `bounds.min`. Regions that don't exist are left
+/// transparent.
+///
+/// Regions are rendered one at a time, so a dimension with a limited region
+/// cache keeps memory use down, but the image itself takes a megabyte per
+/// region. For large worlds consider a [`TilePyramid`](crate::tiles::TilePyramid).
+///
+/// ```no_run
+/// use fastanvil::{
+///     render_dimension, Dimension, HeightMode, JavaChunk, RegionBounds, RegionFileLoader,
+///     RegionLoader, TopShadeRenderer,
+/// };
+///
+/// # fn palette() -> fastanvil::RenderedPalette { unimplemented!() }
+/// let palette = palette();
+/// let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+/// let bounds = RegionBounds::covering(&loader.list()?).expect("no regions");
+/// let dimension = Dimension::with_region_capacity(loader, 64);
+///
+/// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
+/// render_dimension(&dimension, bounds, &renderer).save("map.png")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "image")]
+pub fn render_dimension<C, R, T>(
+    dimension: &Dimension<C, R>,
+    bounds: RegionBounds,
+    renderer: &T,
+) -> RgbaImage
+where
+    C: Chunk + std::fmt::Debug,
+    R: RegionLoader<C>,
+    T: ChunkRenderer,
+{
+    let mut img = RgbaImage::new(bounds.pixel_width(), bounds.pixel_height());
+    render_dimension_into(dimension, bounds, renderer, &mut img);
+    img
+}
+
+/// Like [`render_dimension`], but drawing into `sink`, such as a texture
+/// mapped into memory, rather than a new image. The sink must be at least
+/// [`pixel_width`](RegionBounds::pixel_width) by
+/// [`pixel_height`](RegionBounds::pixel_height) of the bounds. Pixels of
+/// regions that don't exist are left as they are.
+///
+/// ```no_run
+/// use fastanvil::{
+///     render_dimension_into, Dimension, HeightMode, JavaChunk, RCoord, RegionBounds,
+///     RegionFileLoader, RgbaBuffer, TopShadeRenderer,
+/// };
+///
+/// # struct MyPalette;
+/// # impl fastanvil::Palette for MyPalette {
+/// #     fn pick(&self, _: &fastanvil::Block, _: Option<fastanvil::biome::Biome>) -> fastanvil::Rgba {
+/// #         [0; 4]
+/// #     }
+/// # }
+/// # fn palette() -> MyPalette { MyPalette }
+/// let palette = palette();
+/// let dimension = Dimension::<JavaChunk, _>::new(RegionFileLoader::new("world/region".into()));
+/// let bounds = RegionBounds::new((RCoord(-1), RCoord(-1)), (RCoord(0), RCoord(0)));
+///
+/// let (width, height) = (bounds.pixel_width(), bounds.pixel_height());
+/// let mut pixels = vec![0; width as usize * height as usize * 4];
+/// let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
+/// render_dimension_into(
+///     &dimension,
+///     bounds,
+///     &renderer,
+///     &mut RgbaBuffer::new(&mut pixels, width, height),
+/// );
+/// ```
+pub fn render_dimension_into<C, R, T, S>(
+    dimension: &Dimension<C, R>,
+    bounds: RegionBounds,
+    renderer: &T,
+    sink: &mut S,
+) where
+    C: Chunk + std::fmt::Debug,
+    R: RegionLoader<C>,
+    T: ChunkRenderer,
+    S: PixelSink + ?Sized,
+{
+    let maps = bounds
+        .regions()
+        .filter(|&(x, z)| dimension.region(x, z).is_some())
+        .map(|(x, z)| render_region_from(x, z, dimension, renderer).0);
+    stitch_regions_into(bounds, maps, sink)
+}
+
+pub fn render_region<C: Chunk + std::fmt::Debug, R: RegionLoader<C>, T: ChunkRenderer>(
     x: RCoord,
     z: RCoord,
     dimension: Dimension<C, R>,
-    renderer: TopShadeRenderer<P>,
+    renderer: T,
 ) -> RegionMap<Rgba> {
+    render_region_from(x, z, &dimension, &renderer).0
+}
+
+/// Render a region without taking the dimension, so that it can be used for
+/// rendering many regions. Also gives the number of chunks rendered.
+pub(crate) fn render_region_from<
+    C: Chunk + std::fmt::Debug,
+    R: RegionLoader<C>,
+    T: ChunkRenderer,
+>(
+    x: RCoord,
+    z: RCoord,
+    dimension: &Dimension<C, R>,
+    renderer: &T,
+) -> (RegionMap<Rgba>, usize) {
     let mut map = RegionMap::new(x, z, [0u8; 4]);
+    let mut rendered = 0;
 
     let region = match dimension.region(x, z) {
         Some(r) => r,
-        None => return map,
+        None => return (map, rendered),
     };
 
     let mut cache: [Option<C>; 32] = Default::default();
@@ -274,11 +1671,12 @@ pub fn render_region<P: Palette, C: Chunk + std::fmt::Debug, R: RegionLoader<C>>
 
             if let Some(d) = chunk_data {
                 data[..].clone_from_slice(&d);
+                rendered += 1;
             }
         }
     }
 
-    map
+    (map, rendered)
 }
 
 /// Apply top-shading to the given colour based on the relative height of the
@@ -300,3 +1698,455 @@ fn top_shade_colour(colour: Rgba, height: isize, shade_height: isize) -> Rgba {
         colour[3],
     ]
 }
+
+/// Multiply the colour channels of a colour, clamping them to 255.
+fn scale_colour(colour: Rgba, factor: f64) -> Rgba {
+    let scale = |c: u8| (c as f64 * factor).round().min(255.0) as u8;
+    [
+        scale(colour[0]),
+        scale(colour[1]),
+        scale(colour[2]),
+        colour[3],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChunkStatus, RgbaBuffer};
+
+    /// The same column of blocks everywhere, from y = 0 upwards.
+    #[derive(Debug)]
+    struct ColumnChunk(Vec<Block>);
+
+    impl ColumnChunk {
+        fn new(names: &[&str]) -> Self {
+            Self(
+                names
+                    .iter()
+                    .map(|n| Block::new(n.to_string(), Default::default()))
+                    .collect(),
+            )
+        }
+    }
+
+    impl Chunk for ColumnChunk {
+        fn status(&self) -> ChunkStatus {
+            ChunkStatus::Full
+        }
+
+        fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+            self.0.len() as isize
+        }
+
+        fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+            None
+        }
+
+        fn block(&self, _x: usize, y: isize, _z: usize) -> Option<&Block> {
+            self.0.get(y as usize)
+        }
+
+        fn y_range(&self) -> std::ops::Range<isize> {
+            0..self.0.len() as isize
+        }
+    }
+
+    struct NamePalette;
+
+    impl Palette for NamePalette {
+        fn pick(&self, block: &Block, _biome: Option<Biome>) -> Rgba {
+            match block.name() {
+                "minecraft:stone" => [100, 100, 100, 255],
+                "minecraft:dirt" => [200, 100, 0, 255],
+                "minecraft:water" => [0, 0, 200, 255],
+                "minecraft:oak_leaves" => [0, 150, 0, 255],
+                _ => [255, 0, 255, 255],
+            }
+        }
+
+        fn cover(&self, block: &Block) -> u8 {
+            match block.name() {
+                "minecraft:poppy" => 0,
+                "minecraft:white_carpet" => 128,
+                _ => 255,
+            }
+        }
+    }
+
+    struct StoneRegion;
+
+    impl Region<ColumnChunk> for StoneRegion {
+        fn chunk(&self, _x: CCoord, _z: CCoord) -> Option<ColumnChunk> {
+            Some(ColumnChunk::new(&["minecraft:stone", "minecraft:stone"]))
+        }
+    }
+
+    /// Regions of stone at the given coordinates only.
+    struct StoneLoader(Vec<(RCoord, RCoord)>);
+
+    impl RegionLoader<ColumnChunk> for StoneLoader {
+        type RegionType = StoneRegion;
+
+        fn region(&self, x: RCoord, z: RCoord) -> Option<StoneRegion> {
+            self.0.contains(&(x, z)).then_some(StoneRegion)
+        }
+
+        fn list(&self) -> crate::LoaderResult<Vec<(RCoord, RCoord)>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_dimension_leaves_gaps() {
+        let regions = vec![(RCoord(-1), RCoord(0)), (RCoord(0), RCoord(1))];
+        let bounds = RegionBounds::covering(&regions).unwrap();
+        assert_eq!(
+            RegionBounds::new((RCoord(0), RCoord(1)), (RCoord(-1), RCoord(0))),
+            bounds
+        );
+        assert_eq!((2, 2), (bounds.width(), bounds.height()));
+
+        let dimension = Dimension::new(StoneLoader(regions));
+        let renderer =
+            TopShadeRenderer::new(&NamePalette, HeightMode::Calculate).with_shader(NoShade);
+        let image = render_dimension(&dimension, bounds, &renderer);
+
+        let stone = [100, 100, 100, 255];
+        assert_eq!((1024, 1024), image.dimensions());
+        assert_eq!(stone, image.get_pixel(0, 0).0);
+        assert_eq!(stone, image.get_pixel(511, 511).0);
+        assert_eq!([0, 0, 0, 0], image.get_pixel(512, 0).0);
+        assert_eq!([0, 0, 0, 0], image.get_pixel(511, 512).0);
+        assert_eq!(stone, image.get_pixel(1023, 1023).0);
+
+        // Only the part of the dimension in the bounds is rendered.
+        let bounds = RegionBounds::new((RCoord(0), RCoord(1)), (RCoord(0), RCoord(2)));
+        let image = render_dimension(&dimension, bounds, &renderer);
+        assert_eq!((512, 1024), image.dimensions());
+        assert_eq!(stone, image.get_pixel(0, 0).0);
+        assert_eq!([0, 0, 0, 0], image.get_pixel(0, 512).0);
+    }
+
+    #[test]
+    fn render_dimension_into_keeps_missing_regions() {
+        let regions = vec![(RCoord(0), RCoord(0))];
+        let bounds = RegionBounds::new((RCoord(0), RCoord(0)), (RCoord(1), RCoord(0)));
+
+        let dimension = Dimension::new(StoneLoader(regions));
+        let renderer =
+            TopShadeRenderer::new(&NamePalette, HeightMode::Calculate).with_shader(NoShade);
+
+        let background = [1, 2, 3, 4];
+        let mut pixels = background.repeat(1024 * 512);
+        render_dimension_into(
+            &dimension,
+            bounds,
+            &renderer,
+            &mut RgbaBuffer::new(&mut pixels, 1024, 512),
+        );
+
+        let pixel = |x: usize, y: usize| &pixels[(y * 1024 + x) * 4..][..4];
+        assert_eq!(&[100, 100, 100, 255], pixel(511, 511));
+        assert_eq!(&background, pixel(512, 0));
+        assert_eq!(&background, pixel(1023, 511));
+    }
+
+    #[test]
+    fn looks_through_water_and_leaves() {
+        let stone = [100, 100, 100, 255];
+        let water = ColumnChunk::new(&[
+            "minecraft:stone",
+            "minecraft:stone",
+            "minecraft:stone",
+            "minecraft:water",
+            "minecraft:water",
+        ]);
+        let renderer =
+            TopShadeRenderer::new(&NamePalette, HeightMode::Calculate).with_shader(NoShade);
+
+        // The stone is darkened by the water above it, and the water blended
+        // over it.
+        let surface = a_over_b_colour([0, 0, 0, 0], [0, 0, 200, NoShade.water_alpha(3)]);
+        let expected = a_over_b_colour(surface, NoShade.underwater(stone, 3));
+        assert_ne!(stone, NoShade.underwater(stone, 3));
+        assert_eq!(expected, renderer.render(&water, None)[0]);
+
+        let opaque = renderer.with_transparency(Transparency {
+            water: false,
+            leaves: None,
+        });
+        assert_eq!([0, 0, 200, 255], opaque.render(&water, None)[0]);
+
+        let tree =
+            ColumnChunk::new(&["minecraft:stone", "minecraft:stone", "minecraft:oak_leaves"]);
+        assert_eq!([0, 150, 0, 255], opaque.render(&tree, None)[0]);
+
+        let see_through = opaque.with_transparency(Transparency {
+            water: false,
+            leaves: Some(128),
+        });
+        assert_eq!(
+            a_over_b_colour([0, 150, 0, 128], stone),
+            see_through.render(&tree, None)[0]
+        );
+    }
+
+    #[test]
+    fn small_blocks_are_looked_past() {
+        let renderer = TopShadeRenderer::new(&NamePalette, HeightMode::Trust).with_shader(NoShade);
+
+        let flower = ColumnChunk::new(&["minecraft:stone", "minecraft:dirt", "minecraft:poppy"]);
+        assert_eq!([[2; 16]; 16], renderer.surface_heights(&flower));
+        assert_eq!([200, 100, 0, 255], renderer.render(&flower, None)[0]);
+
+        // Partly covering blocks are blended with what is below.
+        let carpet = ColumnChunk::new(&[
+            "minecraft:stone",
+            "minecraft:dirt",
+            "minecraft:white_carpet",
+        ]);
+        assert_eq!(
+            a_over_b_colour(
+                a_over_b_colour([0, 0, 0, 0], [255, 0, 255, 128]),
+                [200, 100, 0, 255]
+            ),
+            renderer.render(&carpet, None)[0]
+        );
+    }
+
+    #[test]
+    fn renders_slice_of_heights() {
+        let chunk = ColumnChunk::new(&[
+            "minecraft:stone",
+            "minecraft:dirt",
+            "minecraft:cave_air",
+            "minecraft:cave_air",
+            "minecraft:stone",
+            "minecraft:dirt",
+            "minecraft:stone",
+            "minecraft:water",
+        ]);
+        let renderer = TopShadeRenderer::new(&NamePalette, HeightMode::Trust).with_shader(NoShade);
+        let slice = |y_range| {
+            let renderer = TopShadeRenderer::new(&NamePalette, HeightMode::Trust)
+                .with_shader(NoShade)
+                .with_opts(RenderOpts { y_range });
+            renderer.render(&chunk, None)[0]
+        };
+
+        assert_eq!(renderer.render(&chunk, None)[0], slice(-64..320));
+        assert_eq!([200, 100, 0, 255], slice(0..6));
+        assert_eq!([100, 100, 100, 255], slice(4..5));
+
+        // Only air in the slice.
+        assert_eq!([0, 0, 0, 0], slice(2..4));
+    }
+
+    #[test]
+    fn cave_renders_floor_of_first_cave() {
+        let chunk = ColumnChunk::new(&[
+            "minecraft:stone",
+            "minecraft:dirt",
+            "minecraft:cave_air",
+            "minecraft:cave_air",
+            "minecraft:stone",
+            "minecraft:air",
+            "minecraft:air",
+        ]);
+
+        // From the sky, through the roof at 4, to the floor at 1.
+        let renderer = CaveRenderer::new(&NamePalette, 100).with_shader(NoShade);
+        assert_eq!(Some(1), renderer.cave_floor(&chunk, 0, 0));
+        assert_eq!([200, 100, 0, 255], renderer.render(&chunk, None)[17]);
+
+        // A ceiling inside the cave still looks for a roof below it.
+        let renderer = CaveRenderer::new(&NamePalette, 3);
+        assert_eq!(None, renderer.cave_floor(&chunk, 0, 0));
+        assert_eq!([0, 0, 0, 0], renderer.render(&chunk, None)[0]);
+
+        // Level neighbours are shaded the same as with the surface.
+        let renderer = CaveRenderer::new(&NamePalette, 4);
+        assert_eq!(Some(1), renderer.cave_floor(&chunk, 0, 0));
+        assert_eq!([172, 86, 0, 255], renderer.render(&chunk, Some(&chunk))[0]);
+    }
+
+    #[test]
+    fn nether_strips_roof() {
+        let mut names = vec!["minecraft:stone"; 11];
+        names.extend(vec!["minecraft:air"; 89]);
+        names.extend(vec!["minecraft:netherrack"; 30]);
+        let chunk = ColumnChunk::new(&names);
+
+        let renderer = TopShadeRenderer::new(&NamePalette, HeightMode::Trust)
+            .with_shader(NoShade)
+            .with_dimension(DimensionKind::Nether);
+
+        assert_eq!(11, renderer.surface_height(&chunk, 0, 0));
+        assert_eq!([100, 100, 100, 255], renderer.render(&chunk, None)[17]);
+        assert_eq!(
+            DimensionKind::Nether,
+            DimensionKind::from_id("minecraft:the_nether")
+        );
+    }
+
+    #[test]
+    fn biome_colours() {
+        assert_eq!([0x8d, 0xb3, 0x60, 255], categorical_colour(&Biome::Plains));
+
+        let unknown = |name: &str| categorical_colour(&Biome::Unknown(name.to_owned()));
+        assert_eq!(unknown("a:crystal_caves"), unknown("a:crystal_caves"));
+        assert_ne!(unknown("a:crystal_caves"), unknown("a:moonlight_grove"));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn isometric_layer() {
+        let chunk = ColumnChunk::new(&["minecraft:stone"]);
+        let image = IsometricRenderer::new(&NamePalette)
+            .with_y_range(0..1)
+            .render_chunk(&chunk);
+
+        assert_eq!((64, 34), image.dimensions());
+        assert_eq!([0, 0, 0, 0], image.get_pixel(0, 0).0);
+
+        // The top of the furthest block, then one in the middle.
+        assert_eq!([100, 100, 100, 255], image.get_pixel(31, 0).0);
+        assert_eq!([100, 100, 100, 255], image.get_pixel(31, 16).0);
+
+        // The south and east faces of the nearest block.
+        assert_eq!([80, 80, 80, 255], image.get_pixel(30, 32).0);
+        assert_eq!([65, 65, 65, 255], image.get_pixel(33, 32).0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn cube_sprite_faces() {
+        use BlockFace::*;
+        let sprite: Vec<_> = cube_sprite(1);
+        assert_eq!(
+            vec![
+                None,
+                Some(Top),
+                Some(Top),
+                None,
+                Some(South),
+                Some(Top),
+                Some(Top),
+                Some(East),
+                Some(South),
+                Some(South),
+                Some(East),
+                Some(East),
+                None,
+                Some(South),
+                Some(East),
+                None,
+            ],
+            sprite
+        );
+    }
+
+    fn context(height: isize, north_height: isize, west_height: isize) -> ShadeContext {
+        ShadeContext {
+            x: 1,
+            z: 1,
+            height,
+            north_height,
+            west_height,
+            biome: None,
+            block_light: None,
+            sky_light: None,
+        }
+    }
+
+    #[test]
+    fn top_shade_matches_north() {
+        let colour = [255, 255, 255, 255];
+        assert_eq!(
+            [255, 255, 255, 255],
+            TopShade.shade(colour, &context(65, 64, 64))
+        );
+        assert_eq!(
+            [220, 220, 220, 255],
+            TopShade.shade(colour, &context(64, 64, 64))
+        );
+        assert_eq!(
+            [180, 180, 180, 255],
+            TopShade.shade(colour, &context(63, 64, 64))
+        );
+        assert_eq!(colour, NoShade.shade(colour, &context(63, 64, 64)));
+    }
+
+    #[test]
+    fn hill_shade_lights_slopes_facing_light() {
+        let shader = HillShade {
+            azimuth: 0.0,
+            strength: 0.1,
+        };
+        let colour = [100, 100, 100, 255];
+
+        // Rising to the south faces north, towards the light.
+        assert_eq!(
+            [110, 110, 110, 255],
+            shader.shade(colour, &context(65, 64, 65))
+        );
+        assert_eq!(
+            [90, 90, 90, 255],
+            shader.shade(colour, &context(63, 64, 63))
+        );
+        assert_eq!(colour, shader.shade(colour, &context(64, 64, 64)));
+    }
+
+    #[test]
+    fn contours_on_interval_boundaries() {
+        let line = [0, 0, 0, 255];
+        let shader = Contours {
+            shader: NoShade,
+            interval: 10,
+            colour: line,
+        };
+        let colour = [100, 100, 100, 255];
+
+        assert_eq!(line, shader.shade(colour, &context(70, 69, 70)));
+        assert_eq!(line, shader.shade(colour, &context(70, 70, 69)));
+        assert_eq!(colour, shader.shade(colour, &context(71, 70, 79)));
+        assert_eq!(water_depth_to_alpha(3), shader.water_alpha(3));
+    }
+
+    #[test]
+    fn night_shade_by_light() {
+        let shader = NightShade {
+            shader: NoShade,
+            sky: 4,
+            spawnable: Some([255, 0, 0, 255]),
+        };
+        let colour = [200, 200, 200, 255];
+        let lit = |block, sky| ShadeContext {
+            block_light: Some(block),
+            sky_light: Some(sky),
+            ..context(64, 64, 64)
+        };
+
+        // A torch lights the column fully, whatever the time.
+        assert_eq!(colour, shader.shade(colour, &lit(15, 15)));
+
+        // Open sky at midnight is dim, and dark enough for monsters.
+        assert_eq!([255, 0, 0, 255], shader.shade(colour, &lit(0, 15)));
+        let night = NightShade {
+            spawnable: None,
+            ..shader
+        };
+        let dim = night.shade(colour, &lit(0, 15));
+        assert!(dim[0] < 50 && dim[0] > 10, "{:?}", dim);
+        assert!(night.shade(colour, &lit(8, 0))[0] > dim[0]);
+
+        // During the day the sky lights everything.
+        let day = NightShade { sky: 15, ..shader };
+        assert_eq!(colour, day.shade(colour, &lit(0, 15)));
+
+        // No light data, no shading.
+        assert_eq!(colour, shader.shade(colour, &context(64, 64, 64)));
+    }
+}
@@ -1,19 +1,360 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use log::debug;
 
 use crate::{
     biome::{self, Biome},
-    Block, Palette, Rgba, SNOW_BLOCK,
+    dark_forest_grass, foliage_override, grass_override, remap_block, water_colour, Block, Error,
+    Palette, Result, Rgba, SNOW_BLOCK,
 };
 
 pub struct RenderedPalette {
     pub blockstates: std::collections::HashMap<String, Rgba>,
     pub grass: image::RgbaImage,
     pub foliage: image::RgbaImage,
+
+    /// Blocks the game colours by biome, keyed like `blockstates`. Their
+    /// colour in `blockstates` is of their untinted texture, which is tinted
+    /// for the biome of each block as it is drawn.
+    pub tints: HashMap<String, Tint>,
+
+    /// How much blocks that don't cover the whole column hide the blocks
+    /// below them, keyed like `blockstates`. See [`Palette::cover`]. Small
+    /// blocks not in here, such as torches and flowers, are recognised by
+    /// name.
+    pub cover: HashMap<String, u8>,
+
+    /// What to draw for blocks not in `blockstates`, such as blocks from
+    /// mods. Without a fallback they are drawn magenta.
+    pub fallback: Option<Box<dyn PaletteFallback + Send + Sync>>,
+}
+
+/// How the game colours a block by the biome it is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tint {
+    /// Tinted like grass, from the grass colour map.
+    Grass,
+
+    /// Tinted like leaves, from the foliage colour map.
+    Foliage,
+
+    /// Tinted like water.
+    Water,
+}
+
+impl Tint {
+    fn to_byte(tint: Option<Tint>) -> u8 {
+        match tint {
+            None => 0,
+            Some(Tint::Grass) => 1,
+            Some(Tint::Foliage) => 2,
+            Some(Tint::Water) => 3,
+        }
+    }
+
+    fn from_byte(b: u8) -> Option<Option<Tint>> {
+        match b {
+            0 => Some(None),
+            1 => Some(Some(Tint::Grass)),
+            2 => Some(Some(Tint::Foliage)),
+            3 => Some(Some(Tint::Water)),
+            _ => None,
+        }
+    }
+}
+
+/// Decides how to draw blocks that a [`RenderedPalette`] has no colour for.
+/// This is implemented for closures, and by [`FallbackMap`] for common
+/// cases.
+///
+/// ```no_run
+/// # use fastanvil::{Fallback, RenderedPalette};
+/// # fn palette() -> RenderedPalette { unimplemented!() }
+/// let palette = palette().with_fallback(|block: &fastanvil::Block| {
+///     if block.name().ends_with("_ore") {
+///         Fallback::Substitute("minecraft:stone".to_owned())
+///     } else {
+///         Fallback::Missing
+///     }
+/// });
+/// ```
+pub trait PaletteFallback {
+    fn fallback(&self, block: &Block) -> Fallback;
+}
+
+impl<F: Fn(&Block) -> Fallback> PaletteFallback for F {
+    fn fallback(&self, block: &Block) -> Fallback {
+        self(block)
+    }
+}
+
+/// How to draw a block that is not in a palette.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fallback {
+    /// Draw the block in the given colour.
+    Colour(Rgba),
+
+    /// Draw the block as if it were another block, given as a block state
+    /// string such as `minecraft:oak_log[axis=y]`. Blocks like grass are
+    /// tinted by biome as usual. If the substitute is not in the palette
+    /// either, the block is drawn as missing.
+    Substitute(String),
+
+    /// Draw the block in the palette's colour for missing blocks.
+    Missing,
+}
+
+impl Fallback {
+    /// Draw the block in the average colour of a texture, such as one from
+    /// a mod's resource pack. Transparent pixels are ignored.
+    pub fn texture(texture: &image::RgbaImage) -> Self {
+        let mut sum = [0f64; 3];
+        let mut count = 0;
+
+        for p in texture.pixels().filter(|p| p.0[3] > 0) {
+            for (s, c) in sum.iter_mut().zip(p.0.iter()) {
+                // Average the squares, which is closer to how we see colour.
+                *s += (*c as f64) * (*c as f64);
+            }
+            count += 1;
+        }
+
+        if count == 0 {
+            return Fallback::Colour([0, 0, 0, 0]);
+        }
+
+        let channel = |s: f64| (s / count as f64).sqrt() as u8;
+        Fallback::Colour([channel(sum[0]), channel(sum[1]), channel(sum[2]), 255])
+    }
+}
+
+/// A [`PaletteFallback`] that looks blocks up by name, then by namespace,
+/// before using a default.
+///
+/// ```no_run
+/// # use fastanvil::{Fallback, FallbackMap, RenderedPalette};
+/// # fn palette() -> RenderedPalette { unimplemented!() }
+/// # let copper_texture = image::RgbaImage::new(16, 16);
+/// let fallback = FallbackMap::new()
+///     .block("create:andesite_casing", Fallback::Substitute("minecraft:andesite".to_owned()))
+///     .block("thermal:copper_ore", Fallback::texture(&copper_texture))
+///     .namespace("create", Fallback::Colour([150, 120, 80, 255]))
+///     .otherwise(Fallback::Colour([128, 128, 128, 255]));
+///
+/// let palette = palette().with_fallback(fallback);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FallbackMap {
+    blocks: HashMap<String, Fallback>,
+    namespaces: HashMap<String, Fallback>,
+    otherwise: Fallback,
+}
+
+impl FallbackMap {
+    /// Create a map where every block is drawn as missing.
+    pub fn new() -> Self {
+        Self {
+            blocks: HashMap::new(),
+            namespaces: HashMap::new(),
+            otherwise: Fallback::Missing,
+        }
+    }
+
+    /// How to draw the block with the given name, eg `create:shaft`.
+    pub fn block(mut self, name: impl Into<String>, fallback: Fallback) -> Self {
+        self.blocks.insert(name.into(), fallback);
+        self
+    }
+
+    /// How to draw blocks with the given namespace, eg `create`, that have
+    /// no fallback of their own.
+    pub fn namespace(mut self, namespace: impl Into<String>, fallback: Fallback) -> Self {
+        self.namespaces.insert(namespace.into(), fallback);
+        self
+    }
+
+    /// How to draw any other block.
+    pub fn otherwise(mut self, fallback: Fallback) -> Self {
+        self.otherwise = fallback;
+        self
+    }
+}
+
+impl Default for FallbackMap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
+impl PaletteFallback for FallbackMap {
+    fn fallback(&self, block: &Block) -> Fallback {
+        let namespace = block.name().split_once(':').map(|(ns, _)| ns);
+
+        self.blocks
+            .get(block.name())
+            .or_else(|| self.namespaces.get(namespace?))
+            .unwrap_or(&self.otherwise)
+            .clone()
+    }
+}
+
+/// The start of a palette saved by [`RenderedPalette::to_bytes`].
+const PALETTE_MAGIC: &[u8; 4] = b"FAPL";
+
+/// Raised whenever the saved format changes. Version 1 had no tints, and
+/// version 2 had no cover.
+const PALETTE_VERSION: u8 = 3;
+
+/// The most bytes a saved colour map can have. The game's are 256x256, this
+/// allows 4096x4096 so that a corrupt size can't exhaust memory.
+const MAX_COLOUR_MAP_LEN: usize = 4096 * 4096 * 4;
+
 impl RenderedPalette {
+    /// Save the palette as bytes, to be loaded with
+    /// [`from_bytes`](Self::from_bytes). This is much quicker to load than
+    /// making the palette again from a client jar, and is a single file to
+    /// ship with an application. The fallback is not saved.
+    ///
+    /// ```no_run
+    /// # use fastanvil::RenderedPalette;
+    /// # fn palette() -> RenderedPalette { unimplemented!() }
+    /// std::fs::write("palette.bin", palette().to_bytes())?;
+    ///
+    /// let palette = RenderedPalette::from_bytes(&std::fs::read("palette.bin")?)?;
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut body = ZlibEncoder::new(Vec::new(), Compression::default());
+
+        // Sorted so the same palette always gives the same bytes.
+        let mut blockstates: Vec<_> = self.blockstates.iter().collect();
+        blockstates.sort_unstable_by_key(|(name, _)| name.as_str());
+
+        // Writing to a Vec can't fail.
+        let write = || -> std::io::Result<()> {
+            body.write_u32::<BigEndian>(blockstates.len() as u32)?;
+            for (name, colour) in blockstates {
+                body.write_u16::<BigEndian>(name.len() as u16)?;
+                body.write_all(name.as_bytes())?;
+                body.write_all(colour)?;
+                body.write_u8(Tint::to_byte(self.tints.get(name).copied()))?;
+                body.write_u8(self.cover.get(name).copied().unwrap_or(255))?;
+            }
+
+            for map in [&self.grass, &self.foliage] {
+                body.write_u32::<BigEndian>(map.width())?;
+                body.write_u32::<BigEndian>(map.height())?;
+                body.write_all(map.as_raw())?;
+            }
+            Ok(())
+        };
+        write().expect("writing to memory");
+
+        let mut bytes = PALETTE_MAGIC.to_vec();
+        bytes.push(PALETTE_VERSION);
+        bytes.extend(body.finish().expect("writing to memory"));
+        bytes
+    }
+
+    /// Load a palette saved by [`to_bytes`](Self::to_bytes). The palette has
+    /// no fallback.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let invalid = |msg: &str| Error::Palette(msg.to_owned());
+
+        let body = data
+            .strip_prefix(PALETTE_MAGIC)
+            .ok_or_else(|| invalid("not a saved palette"))?;
+        let (&version, body) = body.split_first().ok_or_else(|| invalid("no version"))?;
+        if version < PALETTE_VERSION {
+            return Err(Error::Palette(format!(
+                "palette version {} is from an older fastanvil, make the palette again",
+                version
+            )));
+        }
+        if version != PALETTE_VERSION {
+            return Err(Error::Palette(format!(
+                "unsupported palette version {}",
+                version
+            )));
+        }
+
+        let mut body = ZlibDecoder::new(body);
+        let truncated = |_| invalid("palette is truncated or corrupt");
+
+        let count = body.read_u32::<BigEndian>().map_err(truncated)?;
+        let mut blockstates = HashMap::new();
+        let mut tints = HashMap::new();
+        let mut cover = HashMap::new();
+        for _ in 0..count {
+            let len = body.read_u16::<BigEndian>().map_err(truncated)?;
+            let mut name = vec![0; len as usize];
+            body.read_exact(&mut name).map_err(truncated)?;
+            let name = String::from_utf8(name).map_err(|_| invalid("block name not UTF-8"))?;
+
+            let mut colour = [0; 4];
+            body.read_exact(&mut colour).map_err(truncated)?;
+            let tint = Tint::from_byte(body.read_u8().map_err(truncated)?)
+                .ok_or_else(|| invalid("unknown tint"))?;
+
+            if let Some(tint) = tint {
+                tints.insert(name.clone(), tint);
+            }
+            match body.read_u8().map_err(truncated)? {
+                255 => {}
+                c => {
+                    cover.insert(name.clone(), c);
+                }
+            }
+            blockstates.insert(name, colour);
+        }
+
+        let mut read_map = || -> Result<image::RgbaImage> {
+            let width = body.read_u32::<BigEndian>().map_err(truncated)?;
+            let height = body.read_u32::<BigEndian>().map_err(truncated)?;
+            let len = (width as usize)
+                .checked_mul(height as usize)
+                .and_then(|pixels| pixels.checked_mul(4))
+                .filter(|&len| len <= MAX_COLOUR_MAP_LEN)
+                .ok_or_else(|| invalid("colour map is too large"))?;
+            let mut pixels = vec![0; len];
+            body.read_exact(&mut pixels).map_err(truncated)?;
+            image::RgbaImage::from_raw(width, height, pixels)
+                .ok_or_else(|| invalid("invalid colour map"))
+        };
+        let grass = read_map()?;
+        let foliage = read_map()?;
+
+        Ok(Self {
+            blockstates,
+            grass,
+            foliage,
+            tints,
+            cover,
+            fallback: None,
+        })
+    }
+
+    /// Use `fallback` to decide how to draw blocks not in the palette.
+    pub fn with_fallback(self, fallback: impl PaletteFallback + Send + Sync + 'static) -> Self {
+        Self {
+            fallback: Some(Box::new(fallback)),
+            ..self
+        }
+    }
+
     fn pick_grass(&self, b: Option<Biome>) -> Rgba {
         b.map(|b| {
+            // Some biomes override or adjust the colour map.
+            if let Some(colour) = grass_override(&b) {
+                return colour;
+            }
+            let dark_forest = matches!(b, Biome::DarkForest | Biome::DarkForestHills);
+
             let climate = biome::climate(b);
             let t = climate.temperature.min(1.).max(0.);
             let r = climate.rainfall.min(1.).max(0.) * t;
@@ -21,13 +362,22 @@ impl RenderedPalette {
             let t = 255 - (t * 255.).ceil() as u32;
             let r = 255 - (r * 255.).ceil() as u32;
 
-            self.grass.get_pixel(t, r).0
+            let colour = self.grass.get_pixel(t, r).0;
+            if dark_forest {
+                dark_forest_grass(colour)
+            } else {
+                colour
+            }
         })
         .unwrap_or([255, 0, 0, 0])
     }
 
     fn pick_foliage(&self, b: Option<Biome>) -> Rgba {
         b.map(|b| {
+            if let Some(colour) = foliage_override(&b) {
+                return colour;
+            }
+
             let climate = biome::climate(b);
             let t = climate.temperature.min(1.).max(0.);
             let r = climate.rainfall.min(1.).max(0.) * t;
@@ -41,26 +391,99 @@ impl RenderedPalette {
     }
 
     fn pick_water(&self, b: Option<Biome>) -> Rgba {
-        use Biome::*;
-        b.map(|b| match b {
-            Swamp => [0x61, 0x7B, 0x64, 255],
-            River => [0x3F, 0x76, 0xE4, 255],
-            Ocean => [0x3F, 0x76, 0xE4, 255],
-            LukewarmOcean => [0x45, 0xAD, 0xF2, 255],
-            WarmOcean => [0x43, 0xD5, 0xEE, 255],
-            ColdOcean => [0x3D, 0x57, 0xD6, 255],
-            FrozenRiver => [0x39, 0x38, 0xC9, 255],
-            FrozenOcean => [0x39, 0x38, 0xC9, 255],
-            _ => [0x3f, 0x76, 0xe4, 255],
-        })
-        .unwrap_or([0x3f, 0x76, 0xe4, 255])
+        water_colour(b)
     }
 }
 
 impl Palette for RenderedPalette {
     fn pick(&self, block: &Block, biome: Option<Biome>) -> Rgba {
+        self.pick_or_fallback(block, biome, true)
+    }
+
+    fn cover(&self, block: &Block) -> u8 {
+        if let Some(cover) = self.lookup(block).and_then(|(key, _)| self.cover.get(key)) {
+            return *cover;
+        }
+
+        if is_small_block(block.name()) {
+            0
+        } else {
+            255
+        }
+    }
+}
+
+/// Blocks that barely cover the block they are on, for palettes that don't
+/// know from the block models.
+fn is_small_block(name: &str) -> bool {
+    let id = match name.strip_prefix("minecraft:") {
+        Some(id) => id,
+        None => return false,
+    };
+
+    matches!(
+        id,
+        "torch"
+            | "wall_torch"
+            | "lever"
+            | "rail"
+            | "redstone_wire"
+            | "tripwire"
+            | "tripwire_hook"
+            | "dandelion"
+            | "poppy"
+            | "blue_orchid"
+            | "allium"
+            | "azure_bluet"
+            | "oxeye_daisy"
+            | "cornflower"
+            | "lily_of_the_valley"
+            | "wither_rose"
+            | "torchflower"
+            | "dead_bush"
+            | "brown_mushroom"
+            | "red_mushroom"
+    ) || id.ends_with("_torch")
+        || id.ends_with("_button")
+        || id.ends_with("_rail")
+        || id.ends_with("_sapling")
+        || id.ends_with("_tulip")
+}
+
+impl RenderedPalette {
+    /// The colour of a block in `blockstates`, and the key it was found by.
+    fn lookup(&self, block: &Block) -> Option<(&String, &Rgba)> {
+        // Palettes made for a newer version than the world may only know a
+        // block by its new name.
+        self.blockstates
+            .get_key_value(block.encoded_description())
+            .or_else(|| self.blockstates.get_key_value(block.name()))
+            .or_else(|| {
+                self.blockstates
+                    .get_key_value(remap_block(block.name(), 0, i32::MAX))
+            })
+    }
+
+    /// Pick the colour of a block, only using the fallback if `fallback` is
+    /// set. This stops substitute blocks from being substituted again.
+    fn pick_or_fallback(&self, block: &Block, biome: Option<Biome>, fallback: bool) -> Rgba {
         let missing_colour = [255, 0, 255, 255];
 
+        // Palettes that know which blocks are tinted draw them from their
+        // texture, which is truer than the colour map alone.
+        if let Some((key, colour)) = self.lookup(block) {
+            if let Some(&tint) = self.tints.get(key) {
+                // Blocks outside of any biome are drawn as in plains.
+                let biome = biome.or(Some(Biome::Plains));
+                let tint = match tint {
+                    Tint::Grass => self.pick_grass(biome),
+                    Tint::Foliage => self.pick_foliage(biome),
+                    Tint::Water => self.pick_water(biome),
+                };
+                return tint_colour(*colour, tint);
+            }
+        }
+
         // A bunch of blocks in the game seem to be special cased outside of the
         // blockstate/model mechanism. For example leaves get coloured based on
         // the tree type and the biome type, but this is not encoded in the
@@ -70,7 +493,7 @@ impl Palette for RenderedPalette {
         // of the most called functions. Yuck.
         if let Some(id) = block.name().strip_prefix("minecraft:") {
             match id {
-                "grass" | "tall_grass" | "vine" | "fern" | "large_fern" => {
+                "grass" | "short_grass" | "tall_grass" | "vine" | "fern" | "large_fern" => {
                     return self.pick_grass(biome);
                 }
                 "grass_block" => {
@@ -115,14 +538,21 @@ impl Palette for RenderedPalette {
             }
         }
 
-        let col = self
-            .blockstates
-            .get(block.encoded_description())
-            .or_else(|| self.blockstates.get(block.name()));
+        if let Some((_, c)) = self.lookup(block) {
+            return *c;
+        }
+
+        let fallback = match &self.fallback {
+            Some(f) if fallback => f.fallback(block),
+            _ => Fallback::Missing,
+        };
 
-        match col {
-            Some(c) => *c,
-            None => {
+        match fallback {
+            Fallback::Colour(c) => c,
+            Fallback::Substitute(state) => {
+                self.pick_or_fallback(&Block::from_state_str(&state), biome, false)
+            }
+            Fallback::Missing => {
                 debug!("could not draw {}", block.name());
                 debug!("description {}", block.encoded_description());
 
@@ -131,3 +561,203 @@ impl Palette for RenderedPalette {
         }
     }
 }
+
+/// Colour a texture's colour by a tint, as the game does.
+pub(crate) fn tint_colour(colour: Rgba, tint: Rgba) -> Rgba {
+    let channel = |c: u8, t: u8| (c as u32 * t as u32 / 255) as u8;
+    [
+        channel(colour[0], tint[0]),
+        channel(colour[1], tint[1]),
+        channel(colour[2], tint[2]),
+        colour[3],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> RenderedPalette {
+        let mut blockstates = HashMap::new();
+        blockstates.insert("minecraft:stone|".to_owned(), [1, 1, 1, 255]);
+
+        RenderedPalette {
+            blockstates,
+            grass: image::RgbaImage::new(256, 256),
+            foliage: image::RgbaImage::new(256, 256),
+            tints: HashMap::new(),
+            cover: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    fn block(name: &str) -> Block {
+        Block::new(name.to_owned(), Default::default())
+    }
+
+    #[test]
+    fn missing_without_fallback() {
+        let p = palette();
+        assert_eq!([1, 1, 1, 255], p.pick(&block("minecraft:stone"), None));
+        assert_eq!([255, 0, 255, 255], p.pick(&block("create:shaft"), None));
+    }
+
+    #[test]
+    fn renamed_blocks_use_new_name() {
+        let mut p = palette();
+        p.blockstates
+            .insert("minecraft:dirt_path".to_owned(), [5, 5, 5, 255]);
+
+        assert_eq!([5, 5, 5, 255], p.pick(&block("minecraft:grass_path"), None));
+    }
+
+    #[test]
+    fn fallback_map() {
+        let p = palette().with_fallback(
+            FallbackMap::new()
+                .block("create:shaft", Fallback::Colour([2, 2, 2, 255]))
+                .block(
+                    "create:andesite",
+                    Fallback::Substitute("minecraft:stone".to_owned()),
+                )
+                .block(
+                    "create:loop",
+                    Fallback::Substitute("create:loop".to_owned()),
+                )
+                .namespace("create", Fallback::Colour([3, 3, 3, 255]))
+                .otherwise(Fallback::Colour([4, 4, 4, 255])),
+        );
+
+        assert_eq!([1, 1, 1, 255], p.pick(&block("minecraft:stone"), None));
+        assert_eq!([2, 2, 2, 255], p.pick(&block("create:shaft"), None));
+        assert_eq!([1, 1, 1, 255], p.pick(&block("create:andesite"), None));
+        assert_eq!([3, 3, 3, 255], p.pick(&block("create:cogwheel"), None));
+        assert_eq!([4, 4, 4, 255], p.pick(&block("thermal:tin_ore"), None));
+
+        // Substitutes are not substituted again.
+        assert_eq!([255, 0, 255, 255], p.pick(&block("create:loop"), None));
+    }
+
+    #[test]
+    fn fallback_closure() {
+        let p = palette().with_fallback(|b: &Block| {
+            if b.name().ends_with("_ore") {
+                Fallback::Substitute("minecraft:stone".to_owned())
+            } else {
+                Fallback::Missing
+            }
+        });
+
+        assert_eq!([1, 1, 1, 255], p.pick(&block("thermal:tin_ore"), None));
+        assert_eq!([255, 0, 255, 255], p.pick(&block("create:shaft"), None));
+    }
+
+    #[test]
+    fn saved_palette_round_trips() {
+        let mut p = palette();
+        p.blockstates
+            .insert("minecraft:oak_log|axis=y".to_owned(), [5, 4, 3, 255]);
+        p.grass.put_pixel(10, 20, image::Rgba([0, 200, 0, 255]));
+        p.tints.insert("minecraft:stone|".to_owned(), Tint::Foliage);
+        p.cover.insert("minecraft:stone|".to_owned(), 10);
+
+        let bytes = p.to_bytes();
+        assert_eq!(bytes, p.to_bytes());
+
+        let loaded = RenderedPalette::from_bytes(&bytes).unwrap();
+        assert_eq!(p.blockstates, loaded.blockstates);
+        assert_eq!(p.grass, loaded.grass);
+        assert_eq!(p.foliage, loaded.foliage);
+        assert_eq!(p.tints, loaded.tints);
+        assert_eq!(p.cover, loaded.cover);
+
+        assert!(matches!(
+            RenderedPalette::from_bytes(&bytes[..bytes.len() / 2]),
+            Err(Error::Palette(_))
+        ));
+        assert!(matches!(
+            RenderedPalette::from_bytes(b"blockstates.json"),
+            Err(Error::Palette(_))
+        ));
+
+        let mut old = bytes.clone();
+        old[PALETTE_MAGIC.len()] = 2;
+        assert!(matches!(
+            RenderedPalette::from_bytes(&old),
+            Err(Error::Palette(msg)) if msg.contains("older")
+        ));
+
+        // No blocks, then a colour map claiming to be enormous.
+        let mut body = ZlibEncoder::new(Vec::new(), Compression::default());
+        for n in [0, u32::MAX, u32::MAX] {
+            body.write_u32::<BigEndian>(n).unwrap();
+        }
+        let mut bytes = PALETTE_MAGIC.to_vec();
+        bytes.push(PALETTE_VERSION);
+        bytes.extend(body.finish().unwrap());
+        assert!(matches!(
+            RenderedPalette::from_bytes(&bytes),
+            Err(Error::Palette(_))
+        ));
+    }
+
+    #[test]
+    fn tinted_by_biome() {
+        let mut p = palette();
+        p.blockstates.insert(
+            "minecraft:grass_block|snowy=false".to_owned(),
+            [200, 200, 200, 255],
+        );
+        p.tints
+            .insert("minecraft:grass_block|snowy=false".to_owned(), Tint::Grass);
+        for (x, y, pixel) in p.grass.enumerate_pixels_mut() {
+            *pixel = image::Rgba([x as u8, y as u8, 0, 255]);
+        }
+
+        let grass = Block::from_state_str("minecraft:grass_block[snowy=false]");
+        let tinted = |biome| p.pick(&grass, Some(biome));
+
+        // Savanna is hotter and drier than plains.
+        let plains = tinted(Biome::Plains);
+        let savanna = tinted(Biome::Savanna);
+        assert_eq!(tint_colour([200, 200, 200, 255], [51, 173, 0, 255]), plains);
+        assert!(savanna[0] < plains[0] && savanna[1] > plains[1]);
+
+        // Swamps and badlands have their own colours.
+        assert_eq!(
+            tint_colour([200, 200, 200, 255], [0x6a, 0x70, 0x39, 255]),
+            tinted(Biome::Swamp)
+        );
+        assert_eq!(
+            tint_colour([200, 200, 200, 255], [0x90, 0x81, 0x4d, 255]),
+            tinted(Biome::Badlands)
+        );
+        assert_eq!(plains, p.pick(&grass, None));
+    }
+
+    #[test]
+    fn small_blocks_cover_nothing() {
+        let mut p = palette();
+        p.blockstates
+            .insert("minecraft:white_carpet|".to_owned(), [9, 9, 9, 255]);
+        p.cover.insert("minecraft:white_carpet|".to_owned(), 0);
+
+        assert_eq!(255, p.cover(&block("minecraft:stone")));
+        assert_eq!(0, p.cover(&block("minecraft:white_carpet")));
+        assert_eq!(0, p.cover(&block("minecraft:red_tulip")));
+        assert_eq!(0, p.cover(&block("minecraft:soul_torch")));
+    }
+
+    #[test]
+    fn texture_average() {
+        let mut texture = image::RgbaImage::new(2, 2);
+        texture.put_pixel(0, 0, image::Rgba([30, 40, 0, 255]));
+        texture.put_pixel(1, 0, image::Rgba([40, 30, 0, 255]));
+
+        // The transparent pixels are ignored.
+        assert_eq!(
+            Fallback::Colour([35, 35, 0, 255]),
+            Fallback::texture(&texture)
+        );
+    }
+}
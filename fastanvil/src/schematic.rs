@@ -0,0 +1,893 @@
+//! Schematic files, as used by WorldEdit and other world editing tools.
+//! Schematics are converted to and from a [`Structure`], so they can be
+//! handled the same way as structure files.
+//!
+//! Supported formats are:
+//!
+//! * Sponge schematics (`.schem`). Versions 1 to 3 can be read, versions 2
+//!   and 3 can be written.
+//! * MCEdit schematics (`.schematic`), used before 1.13. Blocks are converted
+//!   from their numeric IDs with [`legacy_block`] and back with [`legacy_id`].
+//!
+//! ```no_run
+//! use fastanvil::schematic::{self, SchematicFormat};
+//!
+//! // Convert an old MCEdit schematic to a Sponge one.
+//! let structure = schematic::load("castle.schematic")?;
+//! schematic::save(&structure, SchematicFormat::Sponge3, "castle.schem")?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+//!
+//! Every position of a schematic holds a block, whereas a structure can leave
+//! positions empty. Empty positions are written to schematics as air.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use fastnbt::{ByteArray, IntArray, Value};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+
+use crate::structure::{Structure, StructureBlock, StructureEntity};
+use crate::{legacy_block, legacy_id, Block, Error, Result};
+
+/// The data version of 1.12.2, the last version MCEdit schematics were used
+/// with. MCEdit schematics do not record a data version.
+const MCEDIT_DATA_VERSION: i32 = 1343;
+
+/// The formats a [`Structure`] can be written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchematicFormat {
+    /// Version 2 of the Sponge schematic format, used by WorldEdit 7.
+    Sponge2,
+
+    /// Version 3 of the Sponge schematic format, used by WorldEdit 7.3 onwards.
+    Sponge3,
+
+    /// The MCEdit format, used before 1.13. Only blocks that existed before
+    /// 1.13 can be written.
+    MCEdit,
+}
+
+/// Load a schematic file of any supported format.
+pub fn load(path: impl AsRef<Path>) -> Result<Structure> {
+    let file = File::open(path)?;
+    read(BufReader::new(file))
+}
+
+/// Read the contents of a schematic file of any supported format. The data
+/// should still be gzip compressed, as it is on disk.
+pub fn read(reader: impl Read) -> Result<Structure> {
+    let mut data = Vec::new();
+    GzDecoder::new(reader).read_to_end(&mut data)?;
+
+    let probe: Probe = fastnbt::de::from_bytes(&data)?;
+
+    if probe.schematic.is_some() {
+        let file: SpongeV3File = fastnbt::de::from_bytes(&data)?;
+        from_sponge3(file.schematic)
+    } else if probe.version.is_some() {
+        from_sponge2(fastnbt::de::from_bytes(&data)?)
+    } else if probe.materials.is_some() {
+        from_mcedit(fastnbt::de::from_bytes(&data)?)
+    } else {
+        Err(Error::Schematic("unrecognised schematic format".to_owned()))
+    }
+}
+
+/// Save a structure as a schematic file, replacing it if it exists.
+pub fn save(structure: &Structure, format: SchematicFormat, path: impl AsRef<Path>) -> Result<()> {
+    let file = File::create(path)?;
+    write(structure, format, BufWriter::new(file))
+}
+
+/// Write a structure as a gzip compressed schematic. Only the first palette
+/// of structures with several palettes is used.
+pub fn write(structure: &Structure, format: SchematicFormat, writer: impl Write) -> Result<()> {
+    let mut enc = GzEncoder::new(writer, flate2::Compression::default());
+    {
+        let mut ser = fastnbt::ser::Serializer::with_root_name(&mut enc, "Schematic");
+        match format {
+            SchematicFormat::Sponge2 => to_sponge2(structure)?.serialize(&mut ser)?,
+            SchematicFormat::Sponge3 => {
+                // Version 3 nests everything in a compound of the same name.
+                let file = SpongeV3File {
+                    schematic: to_sponge3(structure)?,
+                };
+                file.serialize(&mut fastnbt::ser::Serializer::new(&mut enc))?
+            }
+            SchematicFormat::MCEdit => to_mcedit(structure)?.serialize(&mut ser)?,
+        }
+    }
+    enc.finish()?.flush()?;
+    Ok(())
+}
+
+/// Just enough of a schematic to tell which format it is.
+#[derive(Deserialize)]
+struct Probe {
+    #[serde(rename = "Schematic")]
+    schematic: Option<IgnoredAny>,
+
+    #[serde(rename = "Version")]
+    version: Option<i32>,
+
+    #[serde(rename = "Materials")]
+    materials: Option<String>,
+}
+
+/// Versions 1 and 2 of the Sponge format.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeV2 {
+    version: i32,
+
+    /// Missing from version 1.
+    #[serde(default)]
+    data_version: i32,
+
+    width: i16,
+    height: i16,
+    length: i16,
+
+    palette_max: Option<i32>,
+    palette: HashMap<String, i32>,
+    block_data: ByteArray,
+
+    /// Called `TileEntities` in version 1.
+    #[serde(default, alias = "TileEntities")]
+    block_entities: Vec<HashMap<String, Value>>,
+
+    #[serde(default)]
+    entities: Vec<HashMap<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpongeV3File {
+    #[serde(rename = "Schematic")]
+    schematic: SpongeV3,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeV3 {
+    version: i32,
+    data_version: i32,
+
+    width: i16,
+    height: i16,
+    length: i16,
+
+    blocks: Option<SpongeV3Blocks>,
+
+    #[serde(default)]
+    entities: Vec<SpongeV3Entity>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeV3Blocks {
+    palette: HashMap<String, i32>,
+    data: ByteArray,
+
+    #[serde(default)]
+    block_entities: Vec<SpongeV3BlockEntity>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeV3BlockEntity {
+    pos: IntArray,
+    id: String,
+    data: Option<HashMap<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SpongeV3Entity {
+    pos: Vec<f64>,
+    id: String,
+    data: Option<HashMap<String, Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MCEdit {
+    width: i16,
+    height: i16,
+    length: i16,
+    materials: String,
+
+    /// The lowest 8 bits of the block ID of each block.
+    blocks: ByteArray,
+
+    /// The data value of each block.
+    data: ByteArray,
+
+    /// The top 4 bits of the block ID of each block, two blocks to a byte.
+    add_blocks: Option<ByteArray>,
+
+    #[serde(default)]
+    entities: Vec<HashMap<String, Value>>,
+
+    #[serde(default)]
+    tile_entities: Vec<HashMap<String, Value>>,
+}
+
+/// The blocks of a schematic, which are stored in the same order by every
+/// format: x, then z, then y.
+struct Grid {
+    size: [usize; 3],
+}
+
+impl Grid {
+    /// Dimensions are stored as shorts, but are really unsigned.
+    fn new(width: i16, height: i16, length: i16) -> Self {
+        Self {
+            size: [
+                width as u16 as usize,
+                height as u16 as usize,
+                length as u16 as usize,
+            ],
+        }
+    }
+
+    fn of(structure: &Structure) -> Result<Self> {
+        let mut size = [0; 3];
+        for (dim, len) in size.iter_mut().zip(structure.size.iter()) {
+            *dim = u16::try_from(*len).map_err(|_| {
+                Error::Schematic(format!("structure too large: {:?}", structure.size))
+            })? as usize;
+        }
+        Ok(Self { size })
+    }
+
+    fn dims(&self) -> (i16, i16, i16) {
+        (
+            self.size[0] as i16,
+            self.size[1] as i16,
+            self.size[2] as i16,
+        )
+    }
+
+    fn structure_size(&self) -> [i32; 3] {
+        [
+            self.size[0] as i32,
+            self.size[1] as i32,
+            self.size[2] as i32,
+        ]
+    }
+
+    fn len(&self) -> usize {
+        self.size.iter().product()
+    }
+
+    fn pos(&self, i: usize) -> [i32; 3] {
+        let [w, _, l] = self.size;
+        [(i % w) as i32, (i / (w * l)) as i32, (i / w % l) as i32]
+    }
+
+    fn index(&self, pos: [i32; 3]) -> Result<usize> {
+        let [w, h, l] = self.size;
+        let [x, y, z] = pos;
+        let inside = |v: i32, len: usize| v >= 0 && (v as usize) < len;
+
+        if inside(x, w) && inside(y, h) && inside(z, l) {
+            Ok(x as usize + z as usize * w + y as usize * w * l)
+        } else {
+            Err(Error::Schematic(format!(
+                "position outside of schematic: {:?}",
+                pos
+            )))
+        }
+    }
+
+    /// The palette index at each position of a structure, or `None` if the
+    /// structure leaves it empty.
+    fn states(&self, structure: &Structure) -> Result<Vec<Option<usize>>> {
+        let mut states = vec![None; self.len()];
+        for block in &structure.blocks {
+            states[self.index(block.pos)?] = Some(block.state as usize);
+        }
+        Ok(states)
+    }
+}
+
+fn from_sponge2(s: SpongeV2) -> Result<Structure> {
+    let grid = Grid::new(s.width, s.height, s.length);
+    let mut structure = sponge_blocks(&grid, s.data_version, &s.palette, &s.block_data)?;
+
+    for mut nbt in s.block_entities {
+        let pos = match nbt.remove("Pos") {
+            Some(Value::IntArray(pos)) if pos.len() == 3 => [pos[0], pos[1], pos[2]],
+            _ => {
+                return Err(Error::Schematic(
+                    "block entity without a position".to_owned(),
+                ))
+            }
+        };
+        if let Some(Value::String(id)) = nbt.remove("Id") {
+            nbt.insert("id".to_owned(), Value::String(id));
+        }
+        structure.blocks[grid.index(pos)?].nbt = Some(nbt);
+    }
+
+    for mut nbt in s.entities {
+        let pos = nbt
+            .get("Pos")
+            .and_then(value_pos)
+            .ok_or_else(|| Error::Schematic("entity without a position".to_owned()))?;
+        if let Some(Value::String(id)) = nbt.remove("Id") {
+            nbt.insert("id".to_owned(), Value::String(id));
+        }
+        structure.entities.push(entity(pos, nbt));
+    }
+
+    Ok(structure)
+}
+
+fn from_sponge3(s: SpongeV3) -> Result<Structure> {
+    let grid = Grid::new(s.width, s.height, s.length);
+    let mut structure = match &s.blocks {
+        Some(blocks) => sponge_blocks(&grid, s.data_version, &blocks.palette, &blocks.data)?,
+        None => Structure::new(s.data_version, grid.structure_size()),
+    };
+
+    for be in s.blocks.into_iter().flat_map(|b| b.block_entities) {
+        if be.pos.len() != 3 {
+            return Err(Error::Schematic(
+                "block entity without a position".to_owned(),
+            ));
+        }
+        let mut nbt = be.data.unwrap_or_default();
+        nbt.insert("id".to_owned(), Value::String(be.id));
+        structure.blocks[grid.index([be.pos[0], be.pos[1], be.pos[2]])?].nbt = Some(nbt);
+    }
+
+    for e in s.entities {
+        let pos = match e.pos[..] {
+            [x, y, z] => [x, y, z],
+            _ => return Err(Error::Schematic("entity without a position".to_owned())),
+        };
+        let mut nbt = e.data.unwrap_or_default();
+        nbt.insert("id".to_owned(), Value::String(e.id));
+        structure.entities.push(entity(pos, nbt));
+    }
+
+    Ok(structure)
+}
+
+/// Create a structure from the blocks of a Sponge schematic, which are
+/// stored as varint palette indices.
+fn sponge_blocks(
+    grid: &Grid,
+    data_version: i32,
+    palette: &HashMap<String, i32>,
+    data: &[i8],
+) -> Result<Structure> {
+    let mut structure = Structure::new(data_version, grid.structure_size());
+
+    // Sponge palettes are a map from block state to index, which might not
+    // be contiguous.
+    let mut entries: Vec<_> = palette.iter().collect();
+    entries.sort_by_key(|(_, i)| **i);
+
+    let mut indices = HashMap::new();
+    for (state, i) in entries {
+        indices.insert(*i, structure.palette.len() as i32);
+        structure.palette.push(Block::from_state_str(state));
+    }
+
+    let states = read_varints(data)?;
+    if states.len() != grid.len() {
+        return Err(Error::Schematic(format!(
+            "expected {} blocks, found {}",
+            grid.len(),
+            states.len()
+        )));
+    }
+
+    for (i, state) in states.into_iter().enumerate() {
+        let state = *indices
+            .get(&state)
+            .ok_or_else(|| Error::Schematic(format!("block not in palette: {}", state)))?;
+        structure.blocks.push(StructureBlock {
+            state,
+            pos: grid.pos(i),
+            nbt: None,
+        });
+    }
+
+    Ok(structure)
+}
+
+/// The blocks of a structure in the form used by Sponge schematics, the
+/// palette and the varint encoded block data.
+fn to_sponge_blocks(grid: &Grid, structure: &Structure) -> Result<(HashMap<String, i32>, Vec<i8>)> {
+    let palette = structure.palettes.first().unwrap_or(&structure.palette);
+
+    let mut sponge_palette = HashMap::new();
+    let mut data = Vec::with_capacity(grid.len());
+
+    for state in grid.states(structure)? {
+        let state = match state {
+            Some(state) => palette
+                .get(state)
+                .ok_or_else(|| Error::Schematic(format!("block not in palette: {}", state)))?
+                .state_string(),
+            None => "minecraft:air".to_owned(),
+        };
+
+        let next = sponge_palette.len() as i32;
+        let index = *sponge_palette.entry(state).or_insert(next);
+        write_varint(&mut data, index as u32);
+    }
+
+    Ok((sponge_palette, data))
+}
+
+fn to_sponge2(structure: &Structure) -> Result<SpongeV2> {
+    let grid = Grid::of(structure)?;
+    let (palette, data) = to_sponge_blocks(&grid, structure)?;
+    let (width, height, length) = grid.dims();
+
+    let block_entities = structure
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.pos, b.nbt.clone()?)))
+        .map(|(pos, mut nbt)| {
+            if let Some(id) = nbt.remove("id") {
+                nbt.insert("Id".to_owned(), id);
+            }
+            nbt.insert(
+                "Pos".to_owned(),
+                Value::IntArray(IntArray::new(pos.to_vec())),
+            );
+            nbt
+        })
+        .collect();
+
+    let entities = structure
+        .entities
+        .iter()
+        .map(|e| {
+            let mut nbt = e.nbt.clone();
+            if let Some(id) = nbt.remove("id") {
+                nbt.insert("Id".to_owned(), id);
+            }
+            let pos = e.pos.iter().map(|v| Value::Double(*v)).collect();
+            nbt.insert("Pos".to_owned(), Value::List(pos));
+            nbt
+        })
+        .collect();
+
+    Ok(SpongeV2 {
+        version: 2,
+        data_version: structure.data_version,
+        width,
+        height,
+        length,
+        palette_max: Some(palette.len() as i32),
+        palette,
+        block_data: ByteArray::new(data),
+        block_entities,
+        entities,
+    })
+}
+
+fn to_sponge3(structure: &Structure) -> Result<SpongeV3> {
+    let grid = Grid::of(structure)?;
+    let (palette, data) = to_sponge_blocks(&grid, structure)?;
+    let (width, height, length) = grid.dims();
+
+    let block_entities = structure
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.pos, b.nbt.clone()?)))
+        .map(|(pos, mut nbt)| SpongeV3BlockEntity {
+            pos: IntArray::new(pos.to_vec()),
+            id: take_id(&mut nbt),
+            data: Some(nbt),
+        })
+        .collect();
+
+    let entities = structure
+        .entities
+        .iter()
+        .map(|e| {
+            let mut nbt = e.nbt.clone();
+            nbt.remove("Pos");
+            SpongeV3Entity {
+                pos: e.pos.to_vec(),
+                id: take_id(&mut nbt),
+                data: Some(nbt),
+            }
+        })
+        .collect();
+
+    Ok(SpongeV3 {
+        version: 3,
+        data_version: structure.data_version,
+        width,
+        height,
+        length,
+        blocks: Some(SpongeV3Blocks {
+            palette,
+            data: ByteArray::new(data),
+            block_entities,
+        }),
+        entities,
+    })
+}
+
+fn from_mcedit(s: MCEdit) -> Result<Structure> {
+    let grid = Grid::new(s.width, s.height, s.length);
+    if s.blocks.len() != grid.len() || s.data.len() != grid.len() {
+        return Err(Error::Schematic(format!(
+            "expected {} blocks, found {}",
+            grid.len(),
+            s.blocks.len()
+        )));
+    }
+
+    let mut structure = Structure::new(MCEDIT_DATA_VERSION, grid.structure_size());
+    let mut indices = HashMap::new();
+
+    for i in 0..grid.len() {
+        let low = s.blocks[i] as u8 as u16;
+        let high = match &s.add_blocks {
+            // Unlike chunks, the first block of each pair is in the high bits.
+            Some(add) => {
+                let byte = *add.get(i / 2).unwrap_or(&0) as u8;
+                (if i % 2 == 0 { byte >> 4 } else { byte & 0xf }) as u16
+            }
+            None => 0,
+        };
+        let id = (high << 8 | low, s.data[i] as u8 & 0xf);
+
+        let state = match indices.get(&id) {
+            Some(state) => *state,
+            None => {
+                let block = legacy_block(id.0, id.1).ok_or_else(|| {
+                    Error::Schematic(format!("unknown block id {}:{}", id.0, id.1))
+                })?;
+                structure.palette.push(block.clone());
+                let state = structure.palette.len() as i32 - 1;
+                indices.insert(id, state);
+                state
+            }
+        };
+
+        structure.blocks.push(StructureBlock {
+            state,
+            pos: grid.pos(i),
+            nbt: None,
+        });
+    }
+
+    for mut nbt in s.tile_entities {
+        let mut coord = |name| match nbt.remove(name) {
+            Some(Value::Int(v)) => Ok(v),
+            _ => Err(Error::Schematic(
+                "tile entity without a position".to_owned(),
+            )),
+        };
+        let pos = [coord("x")?, coord("y")?, coord("z")?];
+        structure.blocks[grid.index(pos)?].nbt = Some(nbt);
+    }
+
+    for nbt in s.entities {
+        let pos = nbt
+            .get("Pos")
+            .and_then(value_pos)
+            .ok_or_else(|| Error::Schematic("entity without a position".to_owned()))?;
+        structure.entities.push(entity(pos, nbt));
+    }
+
+    Ok(structure)
+}
+
+fn to_mcedit(structure: &Structure) -> Result<MCEdit> {
+    let grid = Grid::of(structure)?;
+    let palette = structure.palettes.first().unwrap_or(&structure.palette);
+
+    // Legacy IDs of each palette entry.
+    let ids = palette
+        .iter()
+        .map(|block| {
+            legacy_id(block).ok_or_else(|| {
+                Error::Schematic(format!("no legacy id for {}", block.state_string()))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut blocks = Vec::with_capacity(grid.len());
+    let mut data = Vec::with_capacity(grid.len());
+    let mut add = vec![0u8; grid.len() / 2 + grid.len() % 2];
+
+    for (i, state) in grid.states(structure)?.into_iter().enumerate() {
+        let (id, value) = match state {
+            Some(state) => *ids
+                .get(state)
+                .ok_or_else(|| Error::Schematic(format!("block not in palette: {}", state)))?,
+            None => (0, 0),
+        };
+
+        blocks.push(id as u8 as i8);
+        data.push(value as i8);
+
+        let high = (id >> 8) as u8 & 0xf;
+        add[i / 2] |= if i % 2 == 0 { high << 4 } else { high };
+    }
+
+    let add_blocks = if add.iter().any(|b| *b != 0) {
+        Some(ByteArray::new(add.into_iter().map(|b| b as i8).collect()))
+    } else {
+        None
+    };
+
+    let tile_entities = structure
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.pos, b.nbt.clone()?)))
+        .map(|(pos, mut nbt)| {
+            for (name, v) in ["x", "y", "z"].iter().zip(pos.iter()) {
+                nbt.insert(name.to_string(), Value::Int(*v));
+            }
+            nbt
+        })
+        .collect();
+
+    let entities = structure
+        .entities
+        .iter()
+        .map(|e| {
+            let mut nbt = e.nbt.clone();
+            let pos = e.pos.iter().map(|v| Value::Double(*v)).collect();
+            nbt.insert("Pos".to_owned(), Value::List(pos));
+            nbt
+        })
+        .collect();
+
+    let (width, height, length) = grid.dims();
+    Ok(MCEdit {
+        width,
+        height,
+        length,
+        materials: "Alpha".to_owned(),
+        blocks: ByteArray::new(blocks),
+        data: ByteArray::new(data),
+        add_blocks,
+        entities,
+        tile_entities,
+    })
+}
+
+fn entity(pos: [f64; 3], nbt: HashMap<String, Value>) -> StructureEntity {
+    StructureEntity {
+        pos,
+        block_pos: [
+            pos[0].floor() as i32,
+            pos[1].floor() as i32,
+            pos[2].floor() as i32,
+        ],
+        nbt,
+    }
+}
+
+/// Remove the ID from some NBT, or give an empty ID if there isn't one.
+fn take_id(nbt: &mut HashMap<String, Value>) -> String {
+    match nbt.remove("id") {
+        Some(Value::String(id)) => id,
+        _ => String::new(),
+    }
+}
+
+/// Get a position stored as a list of three doubles or floats.
+fn value_pos(value: &Value) -> Option<[f64; 3]> {
+    let list = match value {
+        Value::List(list) if list.len() == 3 => list,
+        _ => return None,
+    };
+
+    let mut pos = [0.0; 3];
+    for (p, v) in pos.iter_mut().zip(list) {
+        *p = match v {
+            Value::Double(v) => *v,
+            Value::Float(v) => *v as f64,
+            _ => return None,
+        };
+    }
+    Some(pos)
+}
+
+fn read_varints(data: &[i8]) -> Result<Vec<i32>> {
+    let mut values = Vec::new();
+    let mut value = 0;
+    let mut shift = 0;
+
+    for b in data {
+        let b = *b as u8;
+        value |= ((b & 0x7f) as i32) << shift;
+
+        if b & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+            if shift > 28 {
+                return Err(Error::Schematic("varint too long".to_owned()));
+            }
+        }
+    }
+
+    if shift != 0 {
+        return Err(Error::Schematic("block data ends mid varint".to_owned()));
+    }
+
+    Ok(values)
+}
+
+fn write_varint(out: &mut Vec<i8>, mut value: u32) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(b as i8);
+            return;
+        }
+        out.push((b | 0x80) as i8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(name: &str, props: &[(&str, &str)]) -> Block {
+        let props = props
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Block::new(name.to_owned(), props)
+    }
+
+    /// A 2x3x2 structure with a chest and a sheep.
+    fn structure() -> Structure {
+        let mut s = Structure::new(1343, [2, 3, 2]);
+        s.set_block([0, 0, 0], block("minecraft:stone", &[]));
+        s.set_block([1, 2, 1], block("minecraft:chest", &[]));
+        s.set_block([1, 0, 1], block("minecraft:oak_log", &[("axis", "x")]));
+        s.set_block(
+            [0, 1, 0],
+            block(
+                "minecraft:oak_stairs",
+                &[("facing", "south"), ("half", "top")],
+            ),
+        );
+
+        let mut chest = HashMap::new();
+        chest.insert("id".to_owned(), Value::String("minecraft:chest".to_owned()));
+        chest.insert("Lock".to_owned(), Value::String("key".to_owned()));
+        s.blocks[1].nbt = Some(chest);
+
+        let mut sheep = HashMap::new();
+        sheep.insert("id".to_owned(), Value::String("minecraft:sheep".to_owned()));
+        s.entities.push(entity([1.5, 1.0, 0.5], sheep));
+        s
+    }
+
+    fn round_trip(format: SchematicFormat) -> Result<Structure> {
+        let mut data = Vec::new();
+        write(&structure(), format, &mut data)?;
+        read(data.as_slice())
+    }
+
+    fn check(read: &Structure) {
+        assert_eq!([2, 3, 2], read.size);
+        assert_eq!(12, read.blocks.len());
+        assert_eq!("minecraft:stone", read.block([0, 0, 0]).unwrap().name());
+        assert_eq!("minecraft:air", read.block([1, 0, 0]).unwrap().name());
+        assert_eq!(Some("x"), read.block([1, 0, 1]).unwrap().property("axis"));
+        assert_eq!(
+            Some("south"),
+            read.block([0, 1, 0]).unwrap().property("facing")
+        );
+
+        let chest = read.blocks.iter().find(|b| b.pos == [1, 2, 1]).unwrap();
+        let nbt = chest.nbt.as_ref().unwrap();
+        assert_eq!(Some(&Value::String("key".to_owned())), nbt.get("Lock"));
+
+        assert_eq!(1, read.entities.len());
+        assert_eq!([1.5, 1.0, 0.5], read.entities[0].pos);
+        assert_eq!([1, 1, 0], read.entities[0].block_pos);
+        assert_eq!(
+            Some(&Value::String("minecraft:sheep".to_owned())),
+            read.entities[0].nbt.get("id")
+        );
+    }
+
+    #[test]
+    fn sponge2_round_trip() -> Result<()> {
+        let read = round_trip(SchematicFormat::Sponge2)?;
+        check(&read);
+        assert_eq!(1343, read.data_version);
+        Ok(())
+    }
+
+    #[test]
+    fn sponge3_round_trip() -> Result<()> {
+        let read = round_trip(SchematicFormat::Sponge3)?;
+        check(&read);
+        assert_eq!(1343, read.data_version);
+        Ok(())
+    }
+
+    #[test]
+    fn mcedit_round_trip() -> Result<()> {
+        let read = round_trip(SchematicFormat::MCEdit)?;
+        check(&read);
+        Ok(())
+    }
+
+    #[test]
+    fn mcedit_rejects_modern_blocks() {
+        let mut s = Structure::new(2975, [1, 1, 1]);
+        s.set_block([0, 0, 0], block("minecraft:deepslate", &[]));
+
+        let mut data = Vec::new();
+        assert!(matches!(
+            write(&s, SchematicFormat::MCEdit, &mut data),
+            Err(Error::Schematic(_))
+        ));
+    }
+
+    #[test]
+    fn sponge_large_palette() -> Result<()> {
+        // Over 127 palette entries need more than one byte per block.
+        let mut s = Structure::new(2975, [200, 1, 1]);
+        for x in 0..200 {
+            let name = format!("minecraft:block_{}", x);
+            s.set_block([x, 0, 0], block(&name, &[]));
+        }
+
+        let mut data = Vec::new();
+        write(&s, SchematicFormat::Sponge2, &mut data)?;
+        let read = read(data.as_slice())?;
+
+        assert_eq!(
+            "minecraft:block_150",
+            read.block([150, 0, 0]).unwrap().name()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn block_outside_structure() {
+        let mut s = Structure::new(2975, [1, 1, 1]);
+        s.set_block([1, 0, 0], block("minecraft:stone", &[]));
+
+        let mut data = Vec::new();
+        assert!(matches!(
+            write(&s, SchematicFormat::Sponge3, &mut data),
+            Err(Error::Schematic(_))
+        ));
+    }
+
+    #[test]
+    fn varints() -> Result<()> {
+        let mut data = Vec::new();
+        for v in &[0, 1, 127, 128, 300, 1 << 20] {
+            write_varint(&mut data, *v);
+        }
+        assert_eq!(vec![0, 1, 127, 128, 300, 1 << 20], read_varints(&data)?);
+        assert!(read_varints(&data[..data.len() - 1]).is_err());
+        Ok(())
+    }
+}
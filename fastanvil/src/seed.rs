@@ -0,0 +1,324 @@
+//! Things that can be worked out from the seed of a world, as found in its
+//! `level.dat`, without the chunks having been generated: which chunks are
+//! slime chunks, and where structures are attempted, see
+//! [`StructurePlacement`]. The random number generators the game uses are
+//! here too, for working out anything else.
+//!
+//! ```
+//! use fastanvil::{seed, CCoord};
+//!
+//! assert!(seed::is_slime_chunk(12345, CCoord(0), CCoord(-2)));
+//!
+//! let village = seed::StructurePlacement::VILLAGE.start_chunk(12345, CCoord(0), CCoord(0));
+//! ```
+
+use crate::{CCoord, ChunkPos};
+
+/// Whether slimes can spawn below Y=40 in the chunk at the given chunk
+/// coordinates, in any biome. Around one chunk in ten is a slime chunk.
+pub fn is_slime_chunk(seed: i64, x: CCoord, z: CCoord) -> bool {
+    // Minecraft does this arithmetic with Java's 32-bit ints, overflow and
+    // all, before widening to a long.
+    let (x, z) = (x.0 as i32, z.0 as i32);
+    let mixed = seed
+        .wrapping_add(x.wrapping_mul(x).wrapping_mul(0x4c1906) as i64)
+        .wrapping_add(x.wrapping_mul(0x5ac0db) as i64)
+        .wrapping_add((z.wrapping_mul(z) as i64).wrapping_mul(0x4307a7))
+        .wrapping_add(z.wrapping_mul(0x5f24f) as i64)
+        ^ 0x3ad8025f;
+
+    JavaRandom::new(mixed).next_int_bounded(10) == 0
+}
+
+/// How a kind of structure is spread over the world, as set by its
+/// structure set. The world is split into squares of `spacing` chunks, and
+/// the structure is attempted once in each, at a chunk picked from the seed
+/// that is at least `separation` chunks from the next square.
+///
+/// An attempt only succeeds if the biome and terrain there suit the
+/// structure, which is not checked here, so these are the chunks where the
+/// structure *can* start. Some structures, such as pillager outposts, also
+/// skip attempts at random and so aren't included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StructurePlacement {
+    /// The size of the squares in chunks.
+    pub spacing: i32,
+
+    /// The fewest chunks between attempts in neighbouring squares.
+    pub separation: i32,
+
+    /// Mixed into the seed so that each kind of structure is placed
+    /// differently.
+    pub salt: i32,
+
+    /// Whether the chunk is more likely to be near the middle of the square,
+    /// rather than anywhere in it.
+    pub triangular: bool,
+}
+
+impl StructurePlacement {
+    pub const VILLAGE: Self = Self::linear(34, 8, 10387312);
+    pub const DESERT_PYRAMID: Self = Self::linear(32, 8, 14357617);
+    pub const IGLOO: Self = Self::linear(32, 8, 14357618);
+    pub const JUNGLE_TEMPLE: Self = Self::linear(32, 8, 14357619);
+    pub const SWAMP_HUT: Self = Self::linear(32, 8, 14357620);
+    pub const OCEAN_RUIN: Self = Self::linear(20, 8, 14357621);
+    pub const SHIPWRECK: Self = Self::linear(24, 4, 165745295);
+    pub const RUINED_PORTAL: Self = Self::linear(40, 15, 34222645);
+    pub const ANCIENT_CITY: Self = Self::linear(24, 8, 20083232);
+    pub const OCEAN_MONUMENT: Self = Self::triangular(32, 5, 10387313);
+    pub const WOODLAND_MANSION: Self = Self::triangular(80, 20, 10387319);
+    pub const END_CITY: Self = Self::triangular(20, 11, 10387313);
+
+    /// Nether fortresses and bastions, which share their attempts. Which of
+    /// the two is attempted depends on the biome.
+    pub const NETHER_COMPLEX: Self = Self::linear(27, 4, 30084232);
+
+    const fn linear(spacing: i32, separation: i32, salt: i32) -> Self {
+        Self {
+            spacing,
+            separation,
+            salt,
+            triangular: false,
+        }
+    }
+
+    const fn triangular(spacing: i32, separation: i32, salt: i32) -> Self {
+        Self {
+            triangular: true,
+            ..Self::linear(spacing, separation, salt)
+        }
+    }
+
+    /// The chunk where the structure is attempted in the square containing
+    /// the chunk at the given chunk coordinates.
+    pub fn start_chunk(&self, seed: i64, x: CCoord, z: CCoord) -> ChunkPos {
+        let spacing = self.spacing as isize;
+        let (sx, sz) = (x.0.div_euclid(spacing), z.0.div_euclid(spacing));
+
+        let mut rng = JavaRandom::new(
+            (sx as i64)
+                .wrapping_mul(341873128712)
+                .wrapping_add((sz as i64).wrapping_mul(132897987541))
+                .wrapping_add(seed)
+                .wrapping_add(self.salt as i64),
+        );
+
+        let range = self.spacing - self.separation;
+        let mut offset = || {
+            if self.triangular {
+                (rng.next_int_bounded(range) + rng.next_int_bounded(range)) / 2
+            } else {
+                rng.next_int_bounded(range)
+            }
+        };
+        let (dx, dz) = (offset(), offset());
+
+        ChunkPos::new(
+            CCoord(sx * spacing + dx as isize),
+            CCoord(sz * spacing + dz as isize),
+        )
+    }
+
+    /// The chunks where the structure is attempted from the chunk `min` to
+    /// `max`, both inclusive, sorted by Z then X.
+    pub fn start_chunks(
+        &self,
+        seed: i64,
+        min: impl Into<ChunkPos>,
+        max: impl Into<ChunkPos>,
+    ) -> Vec<ChunkPos> {
+        let (min, max) = (min.into(), max.into());
+        let spacing = self.spacing as isize;
+        let square = |c: CCoord| c.0.div_euclid(spacing);
+
+        let mut chunks = Vec::new();
+        for sz in square(min.z)..=square(max.z) {
+            for sx in square(min.x)..=square(max.x) {
+                let pos = self.start_chunk(seed, CCoord(sx * spacing), CCoord(sz * spacing));
+                if (min.x..=max.x).contains(&pos.x) && (min.z..=max.z).contains(&pos.z) {
+                    chunks.push(pos);
+                }
+            }
+        }
+        chunks
+    }
+}
+
+/// The linear congruential generator of `java.util.Random`, which the game
+/// uses for things decided per chunk, and for placing structures.
+#[derive(Debug, Clone)]
+pub struct JavaRandom {
+    seed: i64,
+}
+
+impl JavaRandom {
+    const MULTIPLIER: i64 = 0x5DEECE66D;
+    const MASK: i64 = (1 << 48) - 1;
+
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    fn next(&mut self, bits: u32) -> i32 {
+        self.seed = self.seed.wrapping_mul(Self::MULTIPLIER).wrapping_add(0xB) & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+
+    /// A number from 0 up to but not including `bound`, which must be
+    /// positive.
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        debug_assert!(bound > 0);
+
+        if bound & -bound == bound {
+            return ((bound as i64 * self.next(31) as i64) >> 31) as i32;
+        }
+
+        // Reject the numbers at the top of the range that would make lower
+        // results more likely.
+        loop {
+            let bits = self.next(31);
+            let val = bits % bound;
+            if bits.wrapping_sub(val).wrapping_add(bound - 1) >= 0 {
+                return val;
+            }
+        }
+    }
+}
+
+/// The Xoroshiro128++ generator, which the game uses for world generation
+/// since 1.18.
+#[derive(Debug, Clone)]
+pub struct Xoroshiro {
+    lo: u64,
+    hi: u64,
+}
+
+impl Xoroshiro {
+    /// A generator from a world seed, spread over the 128 bits of state the
+    /// same way as the game.
+    pub fn new(seed: i64) -> Self {
+        let lo = seed as u64 ^ 0x6a09e667f3bcc909;
+        let hi = lo.wrapping_add(0x9e3779b97f4a7c15);
+        Self::from_state(mix_stafford13(lo), mix_stafford13(hi))
+    }
+
+    /// A generator with the given state. The state can't be all zeros, so
+    /// that is replaced the way the game does.
+    pub fn from_state(lo: u64, hi: u64) -> Self {
+        if lo == 0 && hi == 0 {
+            return Self {
+                lo: 0x9e3779b97f4a7c15,
+                hi: 0x6a09e667f3bcc909,
+            };
+        }
+        Self { lo, hi }
+    }
+
+    /// The next 64 random bits.
+    pub fn next_long(&mut self) -> i64 {
+        let (lo, mut hi) = (self.lo, self.hi);
+        let result = lo.wrapping_add(hi).rotate_left(17).wrapping_add(lo);
+
+        hi ^= lo;
+        self.lo = lo.rotate_left(49) ^ hi ^ (hi << 21);
+        self.hi = hi.rotate_left(28);
+        result as i64
+    }
+
+    /// A number from 0 up to but not including `bound`, which must be
+    /// positive.
+    pub fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        debug_assert!(bound > 0);
+        let bound = bound as u64;
+
+        let mut product = (self.next_long() as u32 as u64) * bound;
+        let mut low = product & 0xffff_ffff;
+        if low < bound {
+            // Reject the numbers that would make lower results more likely.
+            let threshold = (bound as u32).wrapping_neg() as u64 % bound;
+            while low < threshold {
+                product = (self.next_long() as u32 as u64) * bound;
+                low = product & 0xffff_ffff;
+            }
+        }
+
+        (product >> 32) as i32
+    }
+}
+
+fn mix_stafford13(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn slime_chunks(seed: i64) -> Vec<(isize, isize)> {
+        let mut chunks = Vec::new();
+        for z in -3..3 {
+            for x in -3..3 {
+                if is_slime_chunk(seed, CCoord(x), CCoord(z)) {
+                    chunks.push((x, z));
+                }
+            }
+        }
+        chunks
+    }
+
+    #[test]
+    fn java_random() {
+        assert_eq!(-1155484576, JavaRandom::new(0).next(32));
+        assert_eq!(0, JavaRandom::new(42).next_int_bounded(10));
+    }
+
+    #[test]
+    fn xoroshiro() {
+        // The first output of the reference implementation.
+        assert_eq!(393217, Xoroshiro::from_state(1, 2).next_long());
+
+        // The seed is mixed as SplitMix64 does, whose first output from 0 is
+        // well known.
+        let rng = Xoroshiro::new(0x6a09e667f3bcc909);
+        assert_eq!(0xe220a8397b1dcdaf, rng.hi);
+
+        let mut rng = Xoroshiro::new(12345);
+        assert!((0..1000).all(|_| (0..7).contains(&rng.next_int_bounded(7))));
+    }
+
+    #[test]
+    fn structure_placement() {
+        let village = StructurePlacement::VILLAGE;
+        let pos = village.start_chunk(12345, CCoord(-1), CCoord(40));
+        let (x, z) = pos.into();
+
+        // In the square of the chunk, and not too close to the next.
+        assert!((-34..-8).contains(&x.0));
+        assert!((34..60).contains(&z.0));
+        assert_eq!(pos, village.start_chunk(12345, CCoord(-34), CCoord(34)));
+        assert_ne!(pos, village.start_chunk(54321, CCoord(-1), CCoord(40)));
+
+        let chunks =
+            village.start_chunks(12345, (CCoord(-34), CCoord(0)), (CCoord(33), CCoord(67)));
+        assert_eq!(4, chunks.len());
+        assert!(chunks.contains(&pos));
+
+        let mansion = StructurePlacement::WOODLAND_MANSION;
+        let pos = mansion.start_chunk(0, CCoord(0), CCoord(0));
+        assert!((0..60).contains(&pos.x.0) && (0..60).contains(&pos.z.0));
+    }
+
+    #[test]
+    fn slime_chunks_match_game() {
+        // Worked out with the game's own code.
+        assert_eq!(vec![(1, -3), (2, -3), (-2, 0), (2, 2)], slime_chunks(0));
+        assert_eq!(vec![(0, -2), (-2, 1), (-1, 2)], slime_chunks(12345));
+        assert_eq!(vec![(1, 0), (1, 1)], slime_chunks(-4172144997902289642));
+        assert!(!is_slime_chunk(12345, CCoord(100000), CCoord(-99999)));
+    }
+}
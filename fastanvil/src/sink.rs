@@ -0,0 +1,110 @@
+#[cfg(feature = "image")]
+use image::RgbaImage;
+
+use crate::Rgba;
+
+/// Somewhere to write the pixels of a render, so that maps can be drawn
+/// straight into the memory of whatever displays them, such as a texture or
+/// a canvas, rather than into an `RgbaImage` that then has to be copied.
+///
+/// Implemented for [`RgbaBuffer`], a plain slice of bytes, and for
+/// `image::RgbaImage` with the `image` feature.
+pub trait PixelSink {
+    /// The width and height in pixels.
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Set the colour of the pixel at the given coordinates, from the top
+    /// left. Will panic if they are outside the sink.
+    fn put_pixel(&mut self, x: u32, y: u32, colour: Rgba);
+}
+
+#[cfg(feature = "image")]
+impl PixelSink for RgbaImage {
+    fn dimensions(&self) -> (u32, u32) {
+        RgbaImage::dimensions(self)
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, colour: Rgba) {
+        RgbaImage::put_pixel(self, x, y, image::Rgba(colour))
+    }
+}
+
+/// A [`PixelSink`] over a slice of bytes, with four bytes per pixel in RGBA
+/// order, a row at a time from the top.
+///
+/// ```
+/// # use fastanvil::{RCoord, RegionMap, RgbaBuffer};
+/// let map = RegionMap::new(RCoord(0), RCoord(0), [0, 0, 0, 255]);
+/// let mut pixels = vec![0; 512 * 512 * 4];
+///
+/// map.draw_into(&mut RgbaBuffer::new(&mut pixels, 512, 512), 0, 0);
+/// assert_eq!(255, pixels[3]);
+/// ```
+#[derive(Debug)]
+pub struct RgbaBuffer<'a> {
+    data: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+}
+
+impl<'a> RgbaBuffer<'a> {
+    /// Wrap a buffer with rows packed together. Will panic if `data` is too
+    /// small for the given size.
+    pub fn new(data: &'a mut [u8], width: u32, height: u32) -> Self {
+        Self::with_stride(data, width, height, width as usize * 4)
+    }
+
+    /// Wrap a buffer with `stride` bytes from the start of one row to the
+    /// start of the next, for when rows are padded, as textures often need.
+    /// Will panic if `data` is too small or rows overlap.
+    pub fn with_stride(data: &'a mut [u8], width: u32, height: u32, stride: usize) -> Self {
+        assert!(stride >= width as usize * 4, "rows overlap");
+        if height > 0 {
+            let needed = stride * (height as usize - 1) + width as usize * 4;
+            assert!(data.len() >= needed, "buffer too small for image");
+        }
+
+        Self {
+            data,
+            width,
+            height,
+            stride,
+        }
+    }
+}
+
+impl<'a> PixelSink for RgbaBuffer<'a> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn put_pixel(&mut self, x: u32, y: u32, colour: Rgba) {
+        assert!(x < self.width && y < self.height);
+        let i = y as usize * self.stride + x as usize * 4;
+        self.data[i..i + 4].copy_from_slice(&colour);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn buffer_with_padded_rows() {
+        let mut data = vec![0; 2 * 12];
+        let mut buffer = RgbaBuffer::with_stride(&mut data, 2, 2, 12);
+        buffer.put_pixel(1, 1, [1, 2, 3, 4]);
+
+        assert_eq!((2, 2), buffer.dimensions());
+        assert_eq!(&[1, 2, 3, 4], &data[16..20]);
+        assert!(data[20..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn buffer_too_small() {
+        let mut data = vec![0; 15];
+        RgbaBuffer::new(&mut data, 2, 2);
+    }
+}
@@ -0,0 +1,222 @@
+//! Structure files, the `.nbt` templates saved by structure blocks and found
+//! in the `generated/<namespace>/structures` folder of a world.
+//!
+//! ```no_run
+//! use fastanvil::structure::Structure;
+//!
+//! let structure = Structure::load("house.nbt")?;
+//! for block in &structure.blocks {
+//!     let state = &structure.palette[block.state as usize];
+//!     println!("{:?}: {}", block.pos, state.name());
+//! }
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+
+use crate::{Block, Result};
+
+/// A structure template. Positions are relative to the corner of the
+/// structure with the lowest coordinates, and must lie within `size`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Structure {
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+
+    /// The size of the structure as x, y, z.
+    pub size: [i32; 3],
+
+    /// The block states used by the structure. Blocks refer to these by
+    /// index.
+    #[serde(default)]
+    pub palette: Vec<Block>,
+
+    /// Some structures, such as shipwrecks, have several palettes that one
+    /// is picked from at random when placing. These have no `palette`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palettes: Vec<Vec<Block>>,
+
+    /// The blocks of the structure. Positions not listed are left as they
+    /// are when the structure is placed, which is different to placing air.
+    pub blocks: Vec<StructureBlock>,
+
+    #[serde(default)]
+    pub entities: Vec<StructureEntity>,
+}
+
+/// A block in a [`Structure`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StructureBlock {
+    /// The index of the block state in the structure's palette.
+    pub state: i32,
+
+    pub pos: [i32; 3],
+
+    /// The block entity data of the block, without its position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbt: Option<HashMap<String, Value>>,
+}
+
+/// An entity in a [`Structure`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StructureEntity {
+    /// The exact position of the entity.
+    pub pos: [f64; 3],
+
+    /// The position of the block the entity is in.
+    #[serde(rename = "blockPos")]
+    pub block_pos: [i32; 3],
+
+    /// The NBT of the entity, including its ID. The position in here is
+    /// ignored when placing the structure.
+    pub nbt: HashMap<String, Value>,
+}
+
+impl Structure {
+    /// Create an empty structure of the given size.
+    pub fn new(data_version: i32, size: [i32; 3]) -> Self {
+        Self {
+            data_version,
+            size,
+            palette: vec![],
+            palettes: vec![],
+            blocks: vec![],
+            entities: vec![],
+        }
+    }
+
+    /// Load a structure file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::read(BufReader::new(file))
+    }
+
+    /// Read the contents of a structure file. The data should still be gzip
+    /// compressed, as it is on disk.
+    pub fn read(reader: impl Read) -> Result<Self> {
+        Ok(fastnbt::de::from_reader(GzDecoder::new(reader))?)
+    }
+
+    /// Save the structure to a file, replacing it if it exists.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let file = File::create(path)?;
+        self.write(BufWriter::new(file))
+    }
+
+    /// Write the structure as a gzip compressed structure file.
+    pub fn write(&self, writer: impl Write) -> Result<()> {
+        let mut enc = GzEncoder::new(writer, flate2::Compression::default());
+        fastnbt::ser::to_writer(&mut enc, self)?;
+        enc.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// Get the block at the given position from the first palette, if the
+    /// structure has a block there.
+    pub fn block(&self, pos: [i32; 3]) -> Option<&Block> {
+        let palette = self.palettes.first().unwrap_or(&self.palette);
+        let block = self.blocks.iter().find(|b| b.pos == pos)?;
+        palette.get(block.state as usize)
+    }
+
+    /// Set the block at the given position, adding it to the palette if it
+    /// is not already there. Any block entity data at the position is
+    /// removed. Structures with several palettes are not supported.
+    pub fn set_block(&mut self, pos: [i32; 3], block: Block) {
+        let state = match self
+            .palette
+            .iter()
+            .position(|b| b.name == block.name && b.properties == block.properties)
+        {
+            Some(i) => i,
+            None => {
+                self.palette.push(block);
+                self.palette.len() - 1
+            }
+        } as i32;
+
+        match self.blocks.iter_mut().find(|b| b.pos == pos) {
+            Some(existing) => {
+                existing.state = state;
+                existing.nbt = None;
+            }
+            None => self.blocks.push(StructureBlock {
+                state,
+                pos,
+                nbt: None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(name: &str, props: &[(&str, &str)]) -> Block {
+        let props = props
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Block::new(name.to_owned(), props)
+    }
+
+    #[test]
+    fn set_block_reuses_palette() {
+        let mut s = Structure::new(2975, [2, 1, 1]);
+        s.set_block([0, 0, 0], block("minecraft:stone", &[]));
+        s.set_block([1, 0, 0], block("minecraft:stone", &[]));
+        assert_eq!(1, s.palette.len());
+
+        s.set_block([1, 0, 0], block("minecraft:oak_log", &[("axis", "x")]));
+        assert_eq!(2, s.palette.len());
+        assert_eq!(2, s.blocks.len());
+        assert_eq!(
+            Some("x"),
+            s.block([1, 0, 0]).and_then(|b| b.property("axis"))
+        );
+        assert!(s.block([0, 1, 0]).is_none());
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let mut s = Structure::new(2975, [1, 2, 1]);
+        s.set_block([0, 0, 0], block("minecraft:chest", &[("facing", "north")]));
+        s.set_block([0, 1, 0], block("minecraft:torch", &[]));
+
+        let mut chest = HashMap::new();
+        chest.insert("id".to_owned(), Value::String("minecraft:chest".to_owned()));
+        s.blocks[0].nbt = Some(chest.clone());
+
+        s.entities.push(StructureEntity {
+            pos: [0.5, 1.0, 0.5],
+            block_pos: [0, 1, 0],
+            nbt: chest,
+        });
+
+        let mut data = Vec::new();
+        s.write(&mut data)?;
+        let read = Structure::read(data.as_slice())?;
+
+        assert_eq!(2975, read.data_version);
+        assert_eq!([1, 2, 1], read.size);
+        assert_eq!(s.blocks, read.blocks);
+        assert_eq!(s.entities, read.entities);
+        assert!(read.palettes.is_empty());
+        assert_eq!(
+            Some("north"),
+            read.block([0, 0, 0]).and_then(|b| b.facing())
+        );
+        assert_eq!("minecraft:torch", read.block([0, 1, 0]).unwrap().name());
+        assert!(read.palette[1].properties().is_empty());
+        Ok(())
+    }
+}
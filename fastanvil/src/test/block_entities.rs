@@ -0,0 +1,140 @@
+use fastnbt::Value;
+
+use crate::{BlockEntityKind, Chunk, JavaChunk};
+
+// Helpers for writing the NBT of a chunk by hand.
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn string(out: &mut Vec<u8>, n: &str, value: &str) {
+    name(out, 8, n);
+    out.extend((value.len() as u16).to_be_bytes());
+    out.extend(value.as_bytes());
+}
+
+fn int(out: &mut Vec<u8>, n: &str, value: i32) {
+    name(out, 3, n);
+    out.extend(value.to_be_bytes());
+}
+
+fn block_entity(out: &mut Vec<u8>, id: &str, pos: [i32; 3], extra: impl Fn(&mut Vec<u8>)) {
+    string(out, "id", id);
+    int(out, "x", pos[0]);
+    int(out, "y", pos[1]);
+    int(out, "z", pos[2]);
+    extra(out);
+    out.push(0);
+}
+
+fn chunk() -> Vec<u8> {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    int(&mut out, "DataVersion", 2730);
+
+    name(&mut out, 10, "Level");
+    int(&mut out, "xPos", -1);
+    int(&mut out, "zPos", 2);
+    string(&mut out, "Status", "full");
+
+    name(&mut out, 9, "TileEntities");
+    out.push(10);
+    out.extend(4i32.to_be_bytes());
+
+    block_entity(&mut out, "minecraft:chest", [-3, 64, 40], |out| {
+        string(out, "CustomName", r#"{"text":"loot"}"#);
+        name(out, 9, "Items");
+        out.push(10);
+        out.extend(1i32.to_be_bytes());
+        name(out, 1, "Slot");
+        out.push(5);
+        string(out, "id", "minecraft:diamond");
+        name(out, 1, "Count");
+        out.push(3);
+        out.push(0);
+    });
+
+    block_entity(&mut out, "minecraft:sign", [-16, -10, 47], |out| {
+        for (i, line) in ["a", "b", "c", "d"].iter().enumerate() {
+            string(out, &format!("Text{}", i + 1), line);
+        }
+        string(out, "Color", "black");
+    });
+
+    block_entity(&mut out, "minecraft:mob_spawner", [-1, 20, 32], |out| {
+        name(out, 2, "Delay");
+        out.extend(20i16.to_be_bytes());
+        name(out, 10, "SpawnData");
+        name(out, 10, "entity");
+        string(out, "id", "minecraft:zombie");
+        out.push(0);
+        out.push(0);
+    });
+
+    block_entity(&mut out, "minecraft:furnace", [-2, 70, 33], |out| {
+        name(out, 2, "BurnTime");
+        out.extend(100i16.to_be_bytes());
+    });
+
+    out.push(0);
+    out.push(0);
+    out
+}
+
+#[test]
+fn typed_block_entities() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk()).unwrap();
+    let entities = chunk.block_entities();
+    assert_eq!(entities.len(), 4);
+
+    match &entities[0].kind {
+        BlockEntityKind::Chest(chest) => {
+            assert_eq!(chest.custom_name.as_deref(), Some(r#"{"text":"loot"}"#));
+            assert_eq!(chest.items.len(), 1);
+            assert_eq!(chest.items[0].slot, Some(5));
+            assert_eq!(chest.items[0].id, "minecraft:diamond");
+            assert_eq!(chest.items[0].count, 3);
+        }
+        kind => panic!("expected chest, got {:?}", kind),
+    }
+
+    match &entities[1].kind {
+        BlockEntityKind::Sign(sign) => {
+            assert_eq!(sign.text, ["a", "b", "c", "d"]);
+            assert_eq!(sign.color.as_deref(), Some("black"));
+            assert!(!sign.glowing);
+        }
+        kind => panic!("expected sign, got {:?}", kind),
+    }
+
+    match &entities[2].kind {
+        BlockEntityKind::Spawner(spawner) => {
+            assert_eq!(spawner.entity.as_deref(), Some("minecraft:zombie"));
+            assert_eq!(spawner.delay, 20);
+        }
+        kind => panic!("expected spawner, got {:?}", kind),
+    }
+
+    match &entities[3].kind {
+        BlockEntityKind::Other(map) => assert_eq!(map["BurnTime"], Value::Short(100)),
+        kind => panic!("expected raw block entity, got {:?}", kind),
+    }
+}
+
+#[test]
+fn block_entity_at() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk()).unwrap();
+
+    assert_eq!(
+        chunk.block_entity_at(13, 64, 8).unwrap().id,
+        "minecraft:chest"
+    );
+    assert_eq!(
+        chunk.block_entity_at(0, -10, 15).unwrap().id,
+        "minecraft:sign"
+    );
+    assert!(chunk.block_entity_at(13, 65, 8).is_none());
+}
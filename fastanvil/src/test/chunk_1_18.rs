@@ -0,0 +1,253 @@
+use std::io::Cursor;
+
+use crate::{biome::Biome, Chunk, ChunkStatus, HeightMode, HeightmapKind, JavaChunk, RegionBuffer};
+
+// Helpers for writing the NBT of a chunk by hand.
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn string(out: &mut Vec<u8>, n: &str, value: &str) {
+    name(out, 8, n);
+    out.extend((value.len() as u16).to_be_bytes());
+    out.extend(value.as_bytes());
+}
+
+fn int(out: &mut Vec<u8>, n: &str, value: i32) {
+    name(out, 3, n);
+    out.extend(value.to_be_bytes());
+}
+
+fn long(out: &mut Vec<u8>, n: &str, value: i64) {
+    name(out, 4, n);
+    out.extend(value.to_be_bytes());
+}
+
+fn longs(out: &mut Vec<u8>, n: &str, values: &[i64]) {
+    name(out, 12, n);
+    out.extend((values.len() as i32).to_be_bytes());
+    for v in values {
+        out.extend(v.to_be_bytes());
+    }
+}
+
+fn list(out: &mut Vec<u8>, n: &str, tag: u8, len: i32) {
+    name(out, 9, n);
+    out.push(tag);
+    out.extend(len.to_be_bytes());
+}
+
+fn section(out: &mut Vec<u8>, y: i8, blocks: &[&str], data: Option<&[i64]>, biomes: &[&str]) {
+    name(out, 1, "Y");
+    out.push(y as u8);
+
+    name(out, 10, "block_states");
+    list(out, "palette", 10, blocks.len() as i32);
+    for block in blocks {
+        string(out, "Name", block);
+        out.push(0);
+    }
+    if let Some(data) = data {
+        longs(out, "data", data);
+    }
+    out.push(0);
+
+    name(out, 10, "biomes");
+    list(out, "palette", 8, biomes.len() as i32);
+    for biome in biomes {
+        out.extend((biome.len() as u16).to_be_bytes());
+        out.extend(biome.as_bytes());
+    }
+    if biomes.len() > 1 {
+        // Second biome for x in 4..8 of the bottom layer of cells.
+        longs(out, "data", &[0b10]);
+    }
+    out.push(0);
+
+    out.push(0);
+}
+
+/// A chunk in the 1.18 format, with a stone section at the bottom of the
+/// world and a log at 1, 2, 3.
+fn chunk_nbt() -> Vec<u8> {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    int(&mut out, "DataVersion", 2860);
+    int(&mut out, "xPos", 4);
+    int(&mut out, "yPos", -4);
+    int(&mut out, "zPos", -7);
+    string(&mut out, "Status", "minecraft:full");
+    long(&mut out, "InhabitedTime", 1234);
+
+    name(&mut out, 10, "Heightmaps");
+    let mut heights = vec![0i64; 37];
+    heights[0] = 70;
+    longs(&mut out, "MOTION_BLOCKING", &heights);
+    out.push(0);
+
+    list(&mut out, "sections", 10, 5);
+
+    // Sections just outside the world only hold light.
+    name(&mut out, 1, "Y");
+    out.push(-5i8 as u8);
+    out.push(0);
+
+    section(
+        &mut out,
+        -4,
+        &["minecraft:stone"],
+        None,
+        &["minecraft:plains"],
+    );
+
+    let mut states = vec![0i64; 256];
+    let log = 2 * 256 + 3 * 16 + 1;
+    states[log / 16] |= 1 << (log % 16 * 4);
+    section(
+        &mut out,
+        0,
+        &["minecraft:air", "minecraft:oak_log"],
+        Some(&states),
+        &["minecraft:desert", "minecraft:snowy_plains"],
+    );
+
+    section(
+        &mut out,
+        19,
+        &["minecraft:air"],
+        None,
+        &["terralith:moonlight_grove"],
+    );
+
+    name(&mut out, 1, "Y");
+    out.push(20);
+    out.push(0);
+
+    list(&mut out, "block_entities", 10, 1);
+    string(&mut out, "id", "minecraft:chest");
+    int(&mut out, "x", 64);
+    int(&mut out, "y", 2);
+    int(&mut out, "z", -112);
+    out.push(0);
+
+    out.push(0);
+    out
+}
+
+#[test]
+fn level_is_built_from_root() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+
+    assert_eq!(chunk.data_version, 2860);
+    assert_eq!(chunk.level.x_pos, 4);
+    assert_eq!(chunk.level.z_pos, -7);
+    assert_eq!(chunk.status(), ChunkStatus::Full);
+    assert_eq!(chunk.inhabited_time(), Some(1234));
+    assert_eq!(chunk.y_range(), -64..320);
+    assert_eq!(
+        chunk.block_entity_at(0, 2, 0).unwrap().id,
+        "minecraft:chest"
+    );
+}
+
+#[test]
+fn blocks() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+
+    assert_eq!(chunk.block(0, -64, 0).unwrap().name(), "minecraft:stone");
+    assert_eq!(chunk.block(15, -49, 15).unwrap().name(), "minecraft:stone");
+    assert_eq!(chunk.block(1, 2, 3).unwrap().name(), "minecraft:oak_log");
+    assert_eq!(chunk.block(1, 2, 4).unwrap().name(), "minecraft:air");
+    assert_eq!(chunk.block(15, 319, 15).unwrap().name(), "minecraft:air");
+
+    // No section in the middle of the world, or outside it.
+    assert!(chunk.block(0, -30, 0).is_none());
+    assert!(chunk.block(0, 320, 0).is_none());
+}
+
+#[test]
+fn biomes() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+
+    assert!(matches!(chunk.biome(0, -64, 0), Some(Biome::Plains)));
+    assert!(matches!(chunk.biome(0, 0, 0), Some(Biome::Desert)));
+    assert!(matches!(chunk.biome(5, 3, 2), Some(Biome::SnowyTundra)));
+    assert!(matches!(chunk.biome(5, 4, 2), Some(Biome::Desert)));
+    assert!(chunk.biome(0, -30, 0).is_none());
+
+    // Biomes from datapacks are kept by name.
+    assert_eq!(
+        chunk.biome(0, 19 * 16, 0),
+        Some(Biome::Unknown("terralith:moonlight_grove".to_owned()))
+    );
+}
+
+#[test]
+fn heights() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+
+    assert_eq!(
+        chunk.height_at(HeightmapKind::MotionBlocking, 0, 0),
+        Some(6)
+    );
+    assert_eq!(chunk.surface_height(0, 0, HeightMode::Trust), 6);
+
+    // Heights are cached, so a new chunk is needed to calculate them.
+    let calculated: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+    assert_eq!(calculated.surface_height(1, 3, HeightMode::Calculate), 3);
+    assert_eq!(calculated.surface_height(0, 0, HeightMode::Calculate), -48);
+}
+
+#[test]
+fn block_counts() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+    let counts = chunk.block_counts();
+
+    assert_eq!(3, counts.len());
+    assert_eq!(Some(&4096), counts.get("minecraft:stone"));
+    assert_eq!(Some(&(2 * 4096 - 1)), counts.get("minecraft:air"));
+    assert_eq!(Some(&1), counts.get("minecraft:oak_log"));
+
+    let sections = chunk.level.sections.as_ref().unwrap();
+    let section = sections.get_section_for_y(0).unwrap();
+    assert_eq!(vec![4095, 1], section.palette_histogram());
+}
+
+#[test]
+fn region_block_counts() {
+    let mut region = RegionBuffer::new(Cursor::new(vec![]));
+    region.write_chunk(0, 0, &chunk_nbt()).unwrap();
+    region.write_chunk(3, 1, &chunk_nbt()).unwrap();
+
+    let counts = crate::region_block_counts(&region).unwrap();
+    assert_eq!(Some(&8192), counts.get("minecraft:stone"));
+    assert_eq!(Some(&2), counts.get("minecraft:oak_log"));
+}
+
+#[test]
+fn section_access() {
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&chunk_nbt()).unwrap();
+
+    let ys: Vec<_> = chunk.sections().map(|s| s.y).collect();
+    assert_eq!(vec![-4, 0, 19], ys);
+
+    let section = chunk.sections().nth(1).unwrap();
+    assert_eq!("minecraft:oak_log", section.palette()[1].name());
+
+    let log = 2 * 256 + 3 * 16 + 1;
+    let states: Vec<_> = section.states_iter().collect();
+    assert_eq!(4096, states.len());
+    assert_eq!(1, states[log]);
+    assert_eq!(1, states.iter().sum::<usize>());
+    assert_eq!(
+        "minecraft:oak_log",
+        section.blocks().nth(log).unwrap().name()
+    );
+
+    let stone = chunk.sections().next().unwrap();
+    assert!(stone.states_iter().all(|i| i == 0));
+    assert!(stone.block_states.is_none());
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::ops::Range;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use crate::biome::Biome;
+use crate::{
+    diff_chunk_blocks, diff_regions, Block, Chunk, ChunkChange, ChunkDiff, ChunkStatus, DiffBy,
+    HeightMode, RegionBuffer, SECTOR_SIZE,
+};
+
+fn nbt(value: i32) -> Vec<u8> {
+    let mut map = HashMap::new();
+    map.insert("value".to_owned(), value);
+    fastnbt::ser::to_bytes(&map).unwrap()
+}
+
+/// A chunk from before 1.18, where the game last saved it at `tick`.
+fn level_nbt(value: i32, tick: i64) -> Vec<u8> {
+    let mut level = HashMap::new();
+    level.insert("value".to_owned(), value as i64);
+    level.insert("LastUpdate".to_owned(), tick);
+    level.insert("InhabitedTime".to_owned(), tick);
+    let mut map = HashMap::new();
+    map.insert("Level".to_owned(), level);
+    fastnbt::ser::to_bytes(&map).unwrap()
+}
+
+/// A region with a chunk at each `(x, z, value, timestamp)`.
+fn region(chunks: &[(usize, usize, i32, u32)]) -> RegionBuffer<Cursor<Vec<u8>>> {
+    let mut region = RegionBuffer::new(Cursor::new(Vec::new()));
+    for &(x, z, value, _) in chunks {
+        region.write_chunk(x, z, &nbt(value)).unwrap();
+    }
+
+    let mut data = region.into_inner();
+    for &(x, z, _, timestamp) in chunks {
+        data.set_position((SECTOR_SIZE + 4 * (x + z * 32)) as u64);
+        data.write_u32::<BigEndian>(timestamp).unwrap();
+    }
+    RegionBuffer::new(data)
+}
+
+fn diff(x: usize, z: usize, change: ChunkChange) -> ChunkDiff {
+    ChunkDiff { x, z, change }
+}
+
+#[test]
+fn diff_regions_by_content_and_timestamp() {
+    let a = region(&[(0, 0, 1, 10), (5, 0, 2, 10), (1, 3, 3, 10), (2, 3, 4, 10)]);
+    let b = region(&[(0, 0, 1, 20), (5, 0, 7, 10), (2, 3, 4, 10), (3, 0, 5, 20)]);
+
+    // Chunk 0,0 was saved again without changing.
+    assert_eq!(
+        vec![
+            diff(3, 0, ChunkChange::Added),
+            diff(5, 0, ChunkChange::Modified),
+            diff(1, 3, ChunkChange::Removed),
+        ],
+        diff_regions(&a, &b, DiffBy::Content).unwrap()
+    );
+
+    // Chunk 5,0 changed without its timestamp changing.
+    assert_eq!(
+        vec![
+            diff(0, 0, ChunkChange::Modified),
+            diff(3, 0, ChunkChange::Added),
+            diff(1, 3, ChunkChange::Removed),
+        ],
+        diff_regions(&a, &b, DiffBy::Timestamp).unwrap()
+    );
+
+    assert!(diff_regions(&a, &a, DiffBy::Content).unwrap().is_empty());
+}
+
+#[test]
+fn diff_regions_ignores_ticking_tags() {
+    let mut a = RegionBuffer::new(Cursor::new(Vec::new()));
+    let mut b = RegionBuffer::new(Cursor::new(Vec::new()));
+    a.write_chunk(0, 0, &level_nbt(1, 100)).unwrap();
+    b.write_chunk(0, 0, &level_nbt(1, 200)).unwrap();
+    a.write_chunk(1, 0, &level_nbt(1, 100)).unwrap();
+    b.write_chunk(1, 0, &level_nbt(2, 200)).unwrap();
+
+    assert_eq!(
+        vec![diff(1, 0, ChunkChange::Modified)],
+        diff_regions(&a, &b, DiffBy::Content).unwrap()
+    );
+}
+
+#[test]
+fn diff_regions_with_empty_region() {
+    let empty = region(&[]);
+    let a = region(&[(4, 4, 1, 10)]);
+
+    assert_eq!(
+        vec![diff(4, 4, ChunkChange::Added)],
+        diff_regions(&empty, &a, DiffBy::Content).unwrap()
+    );
+    assert_eq!(
+        vec![diff(4, 4, ChunkChange::Removed)],
+        diff_regions(&a, &empty, DiffBy::Timestamp).unwrap()
+    );
+}
+
+/// A chunk of the given height range with blocks only where given.
+struct TestChunk {
+    y_range: Range<isize>,
+    blocks: HashMap<(usize, isize, usize), Block>,
+}
+
+impl TestChunk {
+    fn new(y_range: Range<isize>, blocks: &[((usize, isize, usize), &str)]) -> Self {
+        let blocks = blocks
+            .iter()
+            .map(|(pos, state)| (*pos, Block::from_state_str(state)))
+            .collect();
+        Self { y_range, blocks }
+    }
+}
+
+impl Chunk for TestChunk {
+    fn status(&self) -> ChunkStatus {
+        ChunkStatus::Full
+    }
+
+    fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+        unimplemented!()
+    }
+
+    fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+        unimplemented!()
+    }
+
+    fn block(&self, x: usize, y: isize, z: usize) -> Option<&Block> {
+        self.blocks.get(&(x, y, z))
+    }
+
+    fn y_range(&self) -> Range<isize> {
+        self.y_range.clone()
+    }
+}
+
+#[test]
+fn diff_blocks() {
+    let a = TestChunk::new(
+        0..256,
+        &[
+            ((0, 0, 0), "minecraft:stone"),
+            ((1, 64, 2), "minecraft:oak_log[axis=y]"),
+            ((3, 70, 3), "minecraft:chest[facing=north]"),
+            ((4, 80, 4), "minecraft:air"),
+        ],
+    );
+    let b = TestChunk::new(
+        -64..320,
+        &[
+            ((0, 0, 0), "minecraft:stone"),
+            ((1, 64, 2), "minecraft:oak_log[axis=x]"),
+            ((5, 90, 5), "minecraft:cave_air"),
+            ((0, -64, 0), "minecraft:bedrock"),
+        ],
+    );
+
+    let changes: Vec<_> = diff_chunk_blocks(&a, &b)
+        .into_iter()
+        .map(|c| {
+            (
+                (c.x, c.y, c.z),
+                c.before.map(|b| b.name().to_owned()),
+                c.after.map(|b| b.name().to_owned()),
+            )
+        })
+        .collect();
+
+    // Missing blocks are the same as air, so the air at 4,80,4 isn't a
+    // change, but cave air is.
+    let name = |n: &str| Some(n.to_owned());
+    assert_eq!(
+        vec![
+            ((0, -64, 0), None, name("minecraft:bedrock")),
+            (
+                (1, 64, 2),
+                name("minecraft:oak_log"),
+                name("minecraft:oak_log")
+            ),
+            ((3, 70, 3), name("minecraft:chest"), None),
+            ((5, 90, 5), None, name("minecraft:cave_air")),
+        ],
+        changes
+    );
+
+    assert!(diff_chunk_blocks(&a, &a).is_empty());
+}
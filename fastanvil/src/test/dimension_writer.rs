@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::PathBuf;
+
+use fastnbt::Value;
+
+use crate::{CCoord, ChunkNbt, ChunkPos, DimensionWriter, RegionBuffer};
+
+/// A fresh directory for a test to write regions into.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fastanvil-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn chunk_nbt(x: i32, z: i32) -> ChunkNbt {
+    let mut nbt = ChunkNbt::new();
+    nbt.insert("xPos".to_owned(), Value::Int(x));
+    nbt.insert("zPos".to_owned(), Value::Int(z));
+    nbt.insert("Status".to_owned(), Value::String("full".to_owned()));
+    nbt
+}
+
+#[test]
+fn missing_chunks_are_none() {
+    let dir = temp_dir("missing");
+    let mut writer = DimensionWriter::new(dir.clone());
+
+    assert!(writer.chunk((CCoord(0), CCoord(0))).unwrap().is_none());
+    assert!(writer.chunk_mut((CCoord(0), CCoord(0))).unwrap().is_none());
+    assert!(!writer.is_dirty((CCoord(0), CCoord(0))));
+
+    writer.flush().unwrap();
+    assert!(!dir.join("r.0.0.mca").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn insert_and_flush_creates_regions() {
+    let dir = temp_dir("insert");
+    let mut writer = DimensionWriter::new(dir.clone());
+
+    writer.insert_chunk((CCoord(1), CCoord(2)), chunk_nbt(1, 2));
+    writer.insert_chunk((CCoord(-1), CCoord(33)), chunk_nbt(-1, 33));
+    assert!(writer.is_dirty((CCoord(1), CCoord(2))));
+    assert_eq!(2, writer.dirty_chunks().len());
+
+    writer.flush().unwrap();
+    assert!(writer.dirty_chunks().is_empty());
+
+    let region = RegionBuffer::new(fs::File::open(dir.join("r.-1.1.mca")).unwrap());
+    let data = region.load_chunk(31, 1).unwrap();
+    let nbt: ChunkNbt = fastnbt::de::from_bytes(&data).unwrap();
+    assert_eq!(chunk_nbt(-1, 33), nbt);
+    assert!(region.chunk_timestamp(31, 1).unwrap() > 0);
+
+    // A new writer reads back what was written.
+    let mut writer = DimensionWriter::new(dir.clone());
+    assert_eq!(
+        Some(&chunk_nbt(1, 2)),
+        writer.chunk((CCoord(1), CCoord(2))).unwrap()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn edit_existing_chunk() {
+    let dir = temp_dir("edit");
+    let mut writer = DimensionWriter::new(dir.clone());
+    writer.insert_chunk((CCoord(0), CCoord(0)), chunk_nbt(0, 0));
+    writer.insert_chunk((CCoord(1), CCoord(0)), chunk_nbt(1, 0));
+    writer.flush().unwrap();
+
+    let mut writer = DimensionWriter::new(dir.clone());
+    assert!(writer.chunk((CCoord(0), CCoord(0))).unwrap().is_some());
+    assert!(!writer.is_dirty((CCoord(0), CCoord(0))));
+
+    // Grow the chunk so that it no longer fits in its old sector.
+    let chunk = writer.chunk_mut((CCoord(0), CCoord(0))).unwrap().unwrap();
+    let big = (0..5000).map(|i| Value::Int(i * 7919)).collect();
+    chunk.insert("Big".to_owned(), Value::List(big));
+    assert_eq!(
+        vec![ChunkPos::new(CCoord(0), CCoord(0))],
+        writer.dirty_chunks()
+    );
+
+    writer.flush().unwrap();
+
+    let mut writer = DimensionWriter::new(dir.clone());
+    let chunk = writer.chunk((CCoord(0), CCoord(0))).unwrap().unwrap();
+    assert!(matches!(&chunk["Big"], Value::List(l) if l.len() == 5000));
+    assert_eq!(
+        Some(&chunk_nbt(1, 0)),
+        writer.chunk((CCoord(1), CCoord(0))).unwrap()
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
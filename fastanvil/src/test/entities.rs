@@ -0,0 +1,115 @@
+use fastnbt::Value;
+
+use crate::{Builder, CCoord, EntityChunk, EntityRegionFileLoader, RCoord};
+
+// Helpers for writing the NBT of an entity chunk by hand.
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn doubles(out: &mut Vec<u8>, n: &str, values: &[f64]) {
+    name(out, 9, n);
+    out.push(6);
+    out.extend((values.len() as i32).to_be_bytes());
+    for v in values {
+        out.extend(v.to_be_bytes());
+    }
+}
+
+fn entity(out: &mut Vec<u8>, id: &str, pos: [f64; 3], extra: impl Fn(&mut Vec<u8>)) {
+    name(out, 8, "id");
+    out.extend((id.len() as u16).to_be_bytes());
+    out.extend(id.as_bytes());
+    doubles(out, "Pos", &pos);
+    extra(out);
+    out.push(0);
+}
+
+fn entity_chunk() -> Vec<u8> {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+
+    name(&mut out, 3, "DataVersion");
+    out.extend(2730i32.to_be_bytes());
+
+    name(&mut out, 11, "Position");
+    out.extend(2i32.to_be_bytes());
+    out.extend(1i32.to_be_bytes());
+    out.extend((-2i32).to_be_bytes());
+
+    name(&mut out, 9, "Entities");
+    out.push(10);
+    out.extend(2i32.to_be_bytes());
+
+    entity(&mut out, "minecraft:creeper", [16.5, 64.0, -31.5], |out| {
+        name(out, 11, "UUID");
+        out.extend(4i32.to_be_bytes());
+        for i in [1i32, 2, 3, -1] {
+            out.extend(i.to_be_bytes());
+        }
+        name(out, 1, "ignited");
+        out.push(1);
+    });
+
+    entity(&mut out, "minecraft:pig", [17.0, 65.0, -20.0], |out| {
+        name(out, 8, "CustomName");
+        out.extend(12u16.to_be_bytes());
+        out.extend(br#"{"text":"a"}"#);
+        name(out, 9, "Passengers");
+        out.push(10);
+        out.extend(1i32.to_be_bytes());
+        entity(out, "minecraft:zombie", [17.0, 66.0, -20.0], |_| {});
+    });
+
+    out.push(0);
+    out
+}
+
+#[test]
+fn deserialize_entity_chunk() {
+    let chunk: EntityChunk = fastnbt::de::from_bytes(&entity_chunk()).unwrap();
+
+    assert_eq!(chunk.data_version, 2730);
+    assert_eq!(*chunk.position, [1, -2]);
+    assert_eq!(chunk.entities.len(), 2);
+
+    let creeper = &chunk.entities[0];
+    assert_eq!(creeper.id, "minecraft:creeper");
+    assert_eq!(creeper.pos, [16.5, 64.0, -31.5]);
+    assert_eq!(creeper.uuid(), Some(0x00000001_00000002_00000003_ffffffff));
+    assert_eq!(creeper.other.get("ignited"), Some(&Value::Byte(1)));
+    assert!(creeper.custom_name.is_none());
+
+    let pig = &chunk.entities[1];
+    assert_eq!(pig.custom_name.as_deref(), Some(r#"{"text":"a"}"#));
+    assert_eq!(pig.uuid(), None);
+    assert_eq!(pig.passengers.len(), 1);
+    assert_eq!(pig.passengers[0].id, "minecraft:zombie");
+    assert!(pig.other.is_empty());
+}
+
+#[test]
+fn load_entities_from_region_files() {
+    let dir = std::env::temp_dir().join(format!("fastanvil-entities-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // The chunk at 1, -2 is at 1, 30 in region 0, -1.
+    let mut header = Builder::new();
+    for _ in 0..(30 * 32 + 1) {
+        header = header.location(0, 0);
+    }
+    let region = header.location(2, 1).chunk(&entity_chunk()).build();
+    std::fs::write(dir.join("r.0.-1.mca"), region.into_inner()).unwrap();
+
+    let loader = EntityRegionFileLoader::new(dir.clone());
+
+    assert_eq!(loader.list().unwrap(), vec![(RCoord(0), RCoord(-1))]);
+    assert_eq!(loader.entities(CCoord(1), CCoord(-2)).len(), 2);
+    assert!(loader.entities(CCoord(0), CCoord(-2)).is_empty());
+    assert!(loader.chunk(CCoord(1), CCoord(2)).is_none());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,130 @@
+use fastnbt::de::from_bytes;
+
+use crate::edit::{empty_chunk, set_block};
+use crate::{Block, CCoord, Chunk, HeightMode, HeightmapKind, JavaChunk};
+
+const CHUNK_1_17_0: &[u8] = include_bytes!("../../resources/1.17.0.chunk");
+const CHUNK_1_17_1: &[u8] = include_bytes!("../../resources/1.17.1.chunk");
+const CHUNK_CUSTOM_HEIGHTS_1_17_1: &[u8] =
+    include_bytes!("../../resources/1.17.1-custom-heights.chunk");
+
+fn assert_matches_trusted_heights(data: &[u8]) {
+    let chunk: JavaChunk = from_bytes(data).unwrap();
+    let heights = chunk.heightmap(HeightmapKind::MotionBlocking).unwrap();
+
+    for z in 0..16 {
+        for x in 0..16 {
+            assert_eq!(
+                chunk.surface_height(x, z, HeightMode::Trust),
+                heights[z * 16 + x]
+            );
+            assert_eq!(
+                Some(heights[z * 16 + x]),
+                chunk.height_at(HeightmapKind::MotionBlocking, x, z)
+            );
+        }
+    }
+}
+
+#[test]
+fn motion_blocking_matches_surface_height() {
+    assert_matches_trusted_heights(CHUNK_1_17_0);
+    assert_matches_trusted_heights(CHUNK_1_17_1);
+    assert_matches_trusted_heights(CHUNK_CUSTOM_HEIGHTS_1_17_1);
+}
+
+#[test]
+fn other_heightmaps() {
+    for data in [CHUNK_1_17_1, CHUNK_CUSTOM_HEIGHTS_1_17_1] {
+        let chunk: JavaChunk = from_bytes(data).unwrap();
+
+        let surface = chunk.heightmap(HeightmapKind::WorldSurface).unwrap();
+        let floor = chunk.heightmap(HeightmapKind::OceanFloor).unwrap();
+        let no_leaves = chunk
+            .heightmap(HeightmapKind::MotionBlockingNoLeaves)
+            .unwrap();
+        let range = chunk.y_range();
+
+        for i in 0..256 {
+            assert!(surface[i] >= floor[i]);
+            assert!(surface[i] >= no_leaves[i]);
+            assert!(range.contains(&floor[i]) || floor[i] == range.end);
+        }
+    }
+}
+
+#[test]
+fn missing_heightmap() {
+    let chunk: JavaChunk = from_bytes(CHUNK_1_17_1).unwrap();
+    assert!(chunk
+        .height_at(HeightmapKind::WorldSurfaceWg, 0, 0)
+        .is_none());
+    assert!(chunk.heightmap(HeightmapKind::OceanFloorWg).is_none());
+}
+
+#[test]
+fn bulk_heights_match_single_columns() {
+    for data in [CHUNK_1_17_0, CHUNK_1_17_1, CHUNK_CUSTOM_HEIGHTS_1_17_1] {
+        for mode in [HeightMode::Trust, HeightMode::Calculate] {
+            let chunk: JavaChunk = from_bytes(data).unwrap();
+            let heights = chunk.surface_heights(mode);
+
+            for (z, row) in heights.iter().enumerate() {
+                for (x, &h) in row.iter().enumerate() {
+                    assert_eq!(chunk.surface_height(x, z, mode), h);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn calculated_heights_find_the_top_block() {
+    for data in [CHUNK_1_17_0, CHUNK_1_17_1, CHUNK_CUSTOM_HEIGHTS_1_17_1] {
+        let chunk: JavaChunk = from_bytes(data).unwrap();
+        let heights = chunk.surface_heights(HeightMode::Calculate);
+
+        for (z, row) in heights.iter().enumerate() {
+            for (x, &h) in row.iter().enumerate() {
+                let top = chunk
+                    .y_range()
+                    .rev()
+                    .find(|&y| !chunk.block(x, y, z).unwrap().name().ends_with("air"))
+                    .map_or(0, |y| y + 1);
+                assert_eq!(top, h);
+            }
+        }
+    }
+}
+
+#[test]
+fn cached_heights_follow_the_mode() {
+    let chunk: JavaChunk = from_bytes(CHUNK_1_17_0).unwrap();
+
+    // Trusted heights skip things like flowers that don't block motion,
+    // calculated ones include every block, so they disagree somewhere in
+    // this chunk.
+    let trusted = chunk.surface_heights(HeightMode::Trust);
+    let calculated = chunk.surface_heights(HeightMode::Calculate);
+    assert_ne!(trusted, calculated);
+
+    assert_eq!(trusted, chunk.surface_heights(HeightMode::Trust));
+    assert_eq!(
+        calculated[3][4],
+        chunk.surface_height(4, 3, HeightMode::Calculate)
+    );
+}
+
+#[test]
+fn calculated_height_of_zero() {
+    let mut nbt = empty_chunk(CCoord(0), CCoord(0));
+    let stone = Block::from_state_str("minecraft:stone");
+    set_block(&mut nbt, 0, -1, 0, &stone).unwrap();
+    set_block(&mut nbt, 0, -20, 0, &stone).unwrap();
+    set_block(&mut nbt, 1, -20, 0, &stone).unwrap();
+
+    let chunk: JavaChunk = from_bytes(&fastnbt::ser::to_bytes(&nbt).unwrap()).unwrap();
+    let heights = chunk.surface_heights(HeightMode::Calculate);
+    assert_eq!(0, heights[0][0]);
+    assert_eq!(-19, heights[0][1]);
+}
@@ -0,0 +1,141 @@
+use crate::{
+    biome::Biome, legacy_block, legacy_id, Block, Chunk, ChunkStatus, HeightMode, LegacyChunk,
+};
+
+// Helpers for writing the NBT of a chunk by hand.
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn int(out: &mut Vec<u8>, n: &str, value: i32) {
+    name(out, 3, n);
+    out.extend(value.to_be_bytes());
+}
+
+fn bytes(out: &mut Vec<u8>, n: &str, values: &[u8]) {
+    name(out, 7, n);
+    out.extend((values.len() as i32).to_be_bytes());
+    out.extend(values);
+}
+
+/// A chunk with a single section at Y 4, ie blocks 64..80. It has a stone
+/// floor, with a spruce log at 3, 65, 7 and a birch plank block with an ID
+/// using the Add array at 0, 66, 0.
+fn chunk() -> Vec<u8> {
+    let mut blocks = vec![0u8; 4096];
+    let mut data = vec![0u8; 2048];
+    let mut add = vec![0u8; 2048];
+
+    blocks[..256].fill(1);
+
+    let log = 256 + 7 * 16 + 3;
+    blocks[log] = 17;
+    data[log / 2] |= 1 << 4; // odd index, so top nibble
+
+    let modded = 2 * 256;
+    blocks[modded] = 0x34;
+    add[modded / 2] |= 0x1;
+
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    name(&mut out, 10, "Level");
+    int(&mut out, "xPos", 3);
+    int(&mut out, "zPos", -2);
+    name(&mut out, 1, "TerrainPopulated");
+    out.push(1);
+
+    name(&mut out, 11, "HeightMap");
+    out.extend(256i32.to_be_bytes());
+    for _ in 0..256 {
+        out.extend(65i32.to_be_bytes());
+    }
+
+    let mut biomes = vec![1u8; 256];
+    biomes[16] = 2;
+    bytes(&mut out, "Biomes", &biomes);
+
+    name(&mut out, 9, "Sections");
+    out.push(10);
+    out.extend(1i32.to_be_bytes());
+    name(&mut out, 1, "Y");
+    out.push(4);
+    bytes(&mut out, "Blocks", &blocks);
+    bytes(&mut out, "Data", &data);
+    bytes(&mut out, "Add", &add);
+    out.push(0);
+
+    out.push(0);
+    out.push(0);
+    out
+}
+
+#[test]
+fn legacy_blocks() {
+    let chunk: LegacyChunk = fastnbt::de::from_bytes(&chunk()).unwrap();
+
+    assert_eq!(chunk.status(), ChunkStatus::Full);
+    assert_eq!(chunk.block(0, 64, 0).unwrap().name(), "minecraft:stone");
+    assert_eq!(chunk.block(15, 65, 15).unwrap().name(), "minecraft:air");
+
+    let log = chunk.block(3, 65, 7).unwrap();
+    assert_eq!(log.name(), "minecraft:spruce_log");
+    assert_eq!(log.property("axis"), Some("y"));
+
+    // ID 0x134 is not a vanilla block.
+    assert!(chunk.block(0, 66, 0).is_none());
+
+    // No section.
+    assert!(chunk.block(0, 10, 0).is_none());
+    assert!(chunk.block(0, 256, 0).is_none());
+}
+
+#[test]
+fn legacy_heights_and_biomes() {
+    let chunk: LegacyChunk = fastnbt::de::from_bytes(&chunk()).unwrap();
+
+    assert_eq!(chunk.surface_height(4, 4, HeightMode::Trust), 65);
+    assert_eq!(chunk.surface_height(4, 4, HeightMode::Calculate), 65);
+    assert_eq!(chunk.surface_height(3, 7, HeightMode::Calculate), 66);
+
+    assert!(matches!(chunk.biome(0, 64, 0), Some(Biome::Plains)));
+    assert!(matches!(chunk.biome(0, 64, 1), Some(Biome::Desert)));
+}
+
+#[test]
+fn legacy_table() {
+    assert_eq!(legacy_block(35, 14).unwrap().name(), "minecraft:red_wool");
+    assert_eq!(
+        legacy_block(53, 6).unwrap().property("facing"),
+        Some("south")
+    );
+    assert_eq!(legacy_block(53, 6).unwrap().property("half"), Some("top"));
+    assert_eq!(
+        legacy_block(44, 11).unwrap().name(),
+        "minecraft:cobblestone_slab"
+    );
+    assert_eq!(
+        legacy_block(17, 13).unwrap().name(),
+        "minecraft:spruce_wood"
+    );
+
+    // unknown data values fall back to the default
+    assert_eq!(legacy_block(4, 3).unwrap().name(), "minecraft:cobblestone");
+    assert!(legacy_block(254, 0).is_none());
+}
+
+#[test]
+fn legacy_table_reverse() {
+    for (id, data) in [(35, 14), (53, 6), (17, 13), (1, 0)] {
+        assert_eq!(legacy_id(legacy_block(id, data).unwrap()), Some((id, data)));
+    }
+
+    // The chest's facing isn't in the table, so the name alone is used.
+    let chest = Block::from_state_str("minecraft:chest[facing=west]");
+    assert_eq!(legacy_id(&chest), Some((54, 0)));
+
+    let deepslate = Block::from_state_str("minecraft:deepslate");
+    assert!(legacy_id(&deepslate).is_none());
+}
@@ -0,0 +1,135 @@
+use std::io::Write;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::read_level_dat;
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn byte(out: &mut Vec<u8>, n: &str, v: i8) {
+    name(out, 1, n);
+    out.push(v as u8);
+}
+
+fn int(out: &mut Vec<u8>, n: &str, v: i32) {
+    name(out, 3, n);
+    out.extend(v.to_be_bytes());
+}
+
+fn long(out: &mut Vec<u8>, n: &str, v: i64) {
+    name(out, 4, n);
+    out.extend(v.to_be_bytes());
+}
+
+fn string(out: &mut Vec<u8>, n: &str, v: &str) {
+    name(out, 8, n);
+    out.extend((v.len() as u16).to_be_bytes());
+    out.extend(v.as_bytes());
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(data).unwrap();
+    enc.finish().unwrap()
+}
+
+#[test]
+fn modern_level_dat() {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    name(&mut out, 10, "Data");
+    int(&mut out, "DataVersion", 2730);
+    string(&mut out, "LevelName", "My World");
+
+    name(&mut out, 10, "WorldGenSettings");
+    long(&mut out, "seed", -123);
+    byte(&mut out, "generate_features", 1);
+    byte(&mut out, "bonus_chest", 0);
+    name(&mut out, 10, "dimensions");
+    out.push(0);
+    out.push(0);
+
+    int(&mut out, "SpawnX", 10);
+    int(&mut out, "SpawnY", 64);
+    int(&mut out, "SpawnZ", -10);
+
+    name(&mut out, 10, "GameRules");
+    string(&mut out, "doDaylightCycle", "true");
+    out.push(0);
+
+    name(&mut out, 10, "Version");
+    int(&mut out, "Id", 2730);
+    string(&mut out, "Name", "1.17.1");
+    byte(&mut out, "Snapshot", 0);
+    out.push(0);
+
+    int(&mut out, "GameType", 1);
+    byte(&mut out, "hardcore", 0);
+    byte(&mut out, "Difficulty", 2);
+    byte(&mut out, "allowCommands", 1);
+    long(&mut out, "Time", 100);
+    long(&mut out, "DayTime", 200);
+    long(&mut out, "LastPlayed", 300);
+    byte(&mut out, "raining", 1);
+
+    out.push(0);
+    out.push(0);
+
+    let level = read_level_dat(gzip(&out).as_slice()).unwrap();
+
+    assert_eq!(level.data_version, Some(2730));
+    assert_eq!(level.level_name, "My World");
+    assert_eq!(level.seed(), Some(-123));
+    assert!(level.world_gen_settings.as_ref().unwrap().generate_features);
+    assert_eq!(level.spawn(), (10, 64, -10));
+    assert_eq!(level.game_rules["doDaylightCycle"], "true");
+
+    let version = level.version.as_ref().unwrap();
+    assert_eq!(version.id, 2730);
+    assert_eq!(version.name, "1.17.1");
+    assert!(!version.snapshot);
+
+    assert_eq!(level.game_type, 1);
+    assert!(!level.hardcore);
+    assert_eq!(level.difficulty, Some(2));
+    assert!(level.allow_commands);
+    assert_eq!(
+        (level.time, level.day_time, level.last_played),
+        (100, 200, 300)
+    );
+    assert_eq!(level.other["raining"], fastnbt::Value::Byte(1));
+}
+
+#[test]
+fn old_level_dat() {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    name(&mut out, 10, "Data");
+    string(&mut out, "LevelName", "Old");
+    long(&mut out, "RandomSeed", 42);
+    int(&mut out, "SpawnX", 1);
+    int(&mut out, "SpawnY", 2);
+    int(&mut out, "SpawnZ", 3);
+    out.push(0);
+    out.push(0);
+
+    let level = read_level_dat(gzip(&out).as_slice()).unwrap();
+
+    assert_eq!(level.seed(), Some(42));
+    assert!(level.version.is_none());
+    assert!(level.game_rules.is_empty());
+}
+
+#[test]
+fn level_dat_must_be_compressed() {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+    out.push(0);
+
+    assert!(read_level_dat(out.as_slice()).is_err());
+}
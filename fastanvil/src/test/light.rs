@@ -0,0 +1,78 @@
+use fastnbt::Value;
+
+use crate::light::{self, ChunkLight};
+use crate::{Chunk, ChunkNbt, HeightMode, JavaChunk};
+
+const CHUNK_1_17_1: &[u8] = include_bytes!("../../resources/1.17.1.chunk");
+const ETHO: &[u8] = include_bytes!("../../resources/etho.chunk");
+
+#[test]
+fn stored_light_is_read() {
+    let chunk = JavaChunk::from_bytes(CHUNK_1_17_1).unwrap();
+
+    // The game leaves light out of sections where it is all the same, this
+    // chunk only has sky light for sections 3 and 4.
+    assert_eq!(Some(15), chunk.sky_light(0, 79, 0));
+    assert_eq!(None, chunk.sky_light(0, 80, 0));
+    assert_eq!(None, chunk.sky_light(0, 47, 0));
+    assert_eq!(None, chunk.block_light(0, 79, 0));
+    assert_eq!(None, chunk.sky_light(0, 1000, 0));
+}
+
+#[test]
+fn calculated_sky_light_matches_stored_above_the_surface() {
+    for data in [CHUNK_1_17_1, ETHO] {
+        let chunk = JavaChunk::from_bytes(data).unwrap();
+        let light = ChunkLight::calculate(&chunk, true);
+
+        for z in 0..16 {
+            for x in 0..16 {
+                let surface = chunk.surface_height(x, z, HeightMode::Calculate);
+                for y in surface..chunk.y_range().end {
+                    if let Some(stored) = chunk.sky_light(x, y, z) {
+                        assert_eq!(Some(stored), light.sky_light(x, y, z));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn relight_stores_light_in_sections() {
+    let mut nbt: ChunkNbt = fastnbt::de::from_bytes(CHUNK_1_17_1).unwrap();
+
+    // Darken the chunk and mark it as unlit.
+    let level = match nbt.get_mut("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => panic!("no level"),
+    };
+    level.insert("isLightOn".to_owned(), Value::Byte(0));
+    if let Some(Value::List(sections)) = level.get_mut("Sections") {
+        for section in sections {
+            if let Value::Compound(section) = section {
+                section.remove("SkyLight");
+                section.remove("BlockLight");
+            }
+        }
+    }
+
+    light::relight_chunk(&mut nbt, true).unwrap();
+
+    let data = fastnbt::ser::to_bytes(&nbt).unwrap();
+    let chunk = JavaChunk::from_bytes(&data).unwrap();
+    let light = ChunkLight::calculate(&chunk, true);
+
+    for y in chunk.y_range() {
+        assert_eq!(light.sky_light(3, y, 7), chunk.sky_light(3, y, 7));
+        assert_eq!(light.block_light(3, y, 7), chunk.block_light(3, y, 7));
+    }
+
+    let level = match nbt.get("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => panic!("no level"),
+    };
+    // Light from neighbouring chunks is missing, so the game should still
+    // light the chunk itself.
+    assert_eq!(Some(&Value::Byte(0)), level.get("isLightOn"));
+}
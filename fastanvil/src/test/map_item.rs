@@ -0,0 +1,238 @@
+use std::ops::Range;
+
+use fastnbt::{ByteArray, Value};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+
+use crate::{
+    biome::Biome, map_colour, read_map_data, Block, CCoord, Chunk, ChunkStatus, Dimension,
+    DimensionId, HeightMode, LoaderResult, MapData, RCoord, Region, RegionLoader, MAP_SIZE,
+};
+
+#[derive(Serialize)]
+struct MapFile {
+    data: Data,
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+}
+
+#[derive(Serialize)]
+struct Data {
+    scale: i8,
+    #[serde(rename = "xCenter")]
+    x_center: i32,
+    #[serde(rename = "zCenter")]
+    z_center: i32,
+    dimension: Value,
+    locked: i8,
+    colors: ByteArray,
+}
+
+fn map_file(dimension: Value) -> Vec<u8> {
+    scaled_map_file(dimension, 2)
+}
+
+fn scaled_map_file(dimension: Value, scale: i8) -> Vec<u8> {
+    let mut colors = vec![0; MAP_SIZE * MAP_SIZE];
+    // grass in the normal shade at 1,0 and water in the darkest at 0,1.
+    colors[1] = 4 + 2;
+    colors[MAP_SIZE] = 12 * 4 + 3;
+
+    let file = MapFile {
+        data: Data {
+            scale,
+            x_center: 64,
+            z_center: -64,
+            dimension,
+            locked: 1,
+            colors: ByteArray::new(colors),
+        },
+        data_version: 3700,
+    };
+
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    fastnbt::ser::to_writer(&mut enc, &file).unwrap();
+    enc.finish().unwrap()
+}
+
+#[test]
+fn map_metadata() {
+    let map =
+        read_map_data(map_file(Value::String("minecraft:the_nether".into())).as_slice()).unwrap();
+
+    assert_eq!(2, map.scale);
+    assert!(map.locked);
+    assert_eq!(Some(DimensionId::Nether), map.dimension());
+    assert_eq!(4, map.blocks_per_pixel());
+    assert_eq!((64 - 256, -64 - 256), map.origin());
+}
+
+#[test]
+fn bad_scale() {
+    for scale in [-1, 5, 100] {
+        assert!(read_map_data(scaled_map_file(Value::Byte(0), scale).as_slice()).is_err());
+    }
+}
+
+#[test]
+fn pre_1_16_dimension() {
+    let map = read_map_data(map_file(Value::Byte(1)).as_slice()).unwrap();
+    assert_eq!(Some(DimensionId::End), map.dimension());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn map_image() {
+    let map = read_map_data(map_file(Value::Byte(0)).as_slice()).unwrap();
+    let image = map.to_image();
+
+    assert_eq!((128, 128), image.dimensions());
+    assert_eq!([0, 0, 0, 0], image.get_pixel(0, 0).0);
+    assert_eq!([0x7f, 0xb2, 0x38, 255], image.get_pixel(1, 0).0);
+    assert_eq!(map_colour(51), image.get_pixel(0, 1).0);
+}
+
+#[test]
+fn colour_shades() {
+    assert_eq!([0, 0, 0, 0], map_colour(2));
+    assert_eq!([180, 180, 180, 255], map_colour(8 * 4));
+    assert_eq!([220, 220, 220, 255], map_colour(8 * 4 + 1));
+    assert_eq!([135, 135, 135, 255], map_colour(8 * 4 + 3));
+    assert_eq!([0, 0, 0, 0], map_colour(255));
+}
+
+/// A chunk with grass at Y 64 in its west half, and water 4 deep over sand
+/// in its east half.
+struct ShoreChunk {
+    grass: Block,
+    water: Block,
+    sand: Block,
+}
+
+impl Chunk for ShoreChunk {
+    fn status(&self) -> ChunkStatus {
+        ChunkStatus::Full
+    }
+
+    fn surface_height(&self, _x: usize, _z: usize, _mode: HeightMode) -> isize {
+        65
+    }
+
+    fn biome(&self, _x: usize, _y: isize, _z: usize) -> Option<Biome> {
+        None
+    }
+
+    fn block(&self, x: usize, y: isize, _z: usize) -> Option<&Block> {
+        match (x < 8, y) {
+            (true, 64) => Some(&self.grass),
+            (false, 61..=64) => Some(&self.water),
+            (false, 60) => Some(&self.sand),
+            _ => None,
+        }
+    }
+
+    fn y_range(&self) -> Range<isize> {
+        -64..320
+    }
+}
+
+/// Only has the chunk at 0, 0.
+struct ShoreLoader;
+
+struct ShoreRegion;
+
+impl Region<ShoreChunk> for ShoreRegion {
+    fn chunk(&self, x: CCoord, z: CCoord) -> Option<ShoreChunk> {
+        if (x.0, z.0) != (0, 0) {
+            return None;
+        }
+
+        Some(ShoreChunk {
+            grass: Block::from_state_str("minecraft:grass_block[snowy=false]"),
+            water: Block::from_state_str("minecraft:water[level=0]"),
+            sand: Block::from_state_str("minecraft:sand"),
+        })
+    }
+}
+
+impl RegionLoader<ShoreChunk> for ShoreLoader {
+    type RegionType = ShoreRegion;
+
+    fn region(&self, x: RCoord, z: RCoord) -> Option<ShoreRegion> {
+        if (x.0, z.0) == (0, 0) {
+            Some(ShoreRegion)
+        } else {
+            None
+        }
+    }
+
+    fn list(&self) -> LoaderResult<Vec<(RCoord, RCoord)>> {
+        Ok(vec![(RCoord(0), RCoord(0))])
+    }
+}
+
+#[test]
+fn new_maps_line_up() {
+    let map = MapData::new(DimensionId::Overworld, 10, -70, 0);
+    assert_eq!((0, -128), (map.x_center, map.z_center));
+    assert_eq!((-64, -192), map.origin());
+
+    let map = MapData::new(DimensionId::Nether, 0, 0, 1);
+    assert_eq!((64, 64), (map.x_center, map.z_center));
+    assert_eq!(Some(DimensionId::Nether), map.dimension());
+
+    let map = MapData::new(DimensionId::Overworld, 0, 0, 40);
+    assert_eq!(4, map.scale);
+    assert_eq!((960, 960), (map.x_center, map.z_center));
+}
+
+#[test]
+fn draw_terrain() {
+    let dimension = Dimension::new(ShoreLoader);
+    let mut map = MapData::new(DimensionId::Overworld, 0, 0, 0);
+    map.draw_terrain(&dimension);
+
+    // Chunk 0, 0 starts at pixel 64, 64.
+    let id = |x: usize, z: usize| map.colors[z * MAP_SIZE + x] as u8;
+
+    // Nothing is north of the first row, so it looks like a slope up.
+    assert_eq!(4 + 2, id(64, 64));
+    assert_eq!(4 + 1, id(64, 65));
+    assert_eq!(4 + 1, id(71, 70));
+
+    // Shallow water alternates between two shades.
+    assert_eq!(12 * 4 + 1, id(72, 65));
+    assert_eq!(12 * 4 + 2, id(72, 66));
+
+    assert_eq!(0, id(63, 64));
+    assert_eq!(0, id(80, 80));
+}
+
+#[test]
+fn draw_nether_roof() {
+    let dimension = Dimension::new(ShoreLoader);
+    let mut map = MapData::new(DimensionId::Nether, 0, 0, 0);
+    map.draw_terrain(&dimension);
+
+    let roof: Vec<u8> = (64..80)
+        .flat_map(|z| (64..80).map(move |x| (x, z)))
+        .map(|(x, z)| map.colors[z * MAP_SIZE + x] as u8 / 4)
+        .collect();
+
+    assert!(roof.contains(&10) && roof.contains(&11));
+    assert!(roof.iter().all(|&c| c == 10 || c == 11));
+}
+
+#[test]
+fn write_map() {
+    let dimension = Dimension::new(ShoreLoader);
+    let mut map = MapData::new(DimensionId::Overworld, 0, 0, 0);
+    map.locked = true;
+    map.draw_terrain(&dimension);
+
+    let mut file = Vec::new();
+    map.write(&mut file).unwrap();
+
+    assert_eq!(map, read_map_data(file.as_slice()).unwrap());
+}
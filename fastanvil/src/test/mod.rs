@@ -1,3 +1,24 @@
+mod block_entities;
+mod chunk_1_18;
+mod dimension_writer;
+mod entities;
+mod heightmaps;
+mod legacy_chunks;
+mod level_dat;
+mod map_item;
+mod network;
+mod player;
+mod poi;
 mod rogue_chunks;
+mod saved_data;
 mod unicode_chunk;
+mod versioned_chunks;
+mod world;
 mod standard_chunks;
+mod upgrade;
+mod light;
+mod progress;
+mod diff;
+mod prune;
+mod relocate;
+mod structures;
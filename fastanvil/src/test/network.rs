@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use crate::{
+    read_network_sections, Block, Error, NetworkFormat, NetworkRegistry, PackedBits, PackingFormat,
+};
+
+fn registry() -> NetworkRegistry {
+    NetworkRegistry {
+        block_states: vec![
+            Block::new("minecraft:air".to_owned(), HashMap::new()),
+            Block::new("minecraft:stone".to_owned(), HashMap::new()),
+            Block::new(
+                "minecraft:oak_log".to_owned(),
+                HashMap::from([("axis".to_owned(), "y".to_owned())]),
+            ),
+        ],
+        biomes: vec!["minecraft:plains".to_owned(), "minecraft:desert".to_owned()],
+    }
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let b = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(b);
+            return;
+        }
+        out.push(b | 0x80);
+    }
+}
+
+fn longs(out: &mut Vec<u8>, format: NetworkFormat, values: &[u16], bits: usize) {
+    let packed = PackedBits::pack(values, bits, PackingFormat::Padded);
+    if format == NetworkFormat::LengthPrefixed {
+        varint(out, packed.0.len() as u32);
+    }
+    for long in packed.0.iter() {
+        out.extend(long.to_be_bytes());
+    }
+}
+
+fn single(out: &mut Vec<u8>, format: NetworkFormat, id: u32) {
+    out.push(0);
+    varint(out, id);
+    if format == NetworkFormat::LengthPrefixed {
+        varint(out, 0);
+    }
+}
+
+fn indirect(out: &mut Vec<u8>, format: NetworkFormat, bits: u8, ids: &[u32], values: &[u16]) {
+    out.push(bits);
+    varint(out, ids.len() as u32);
+    for id in ids {
+        varint(out, *id);
+    }
+    longs(out, format, values, bits as usize);
+}
+
+fn direct(out: &mut Vec<u8>, format: NetworkFormat, bits: u8, values: &[u16]) {
+    out.push(bits);
+    longs(out, format, values, bits as usize);
+}
+
+/// Three sections: all stone, mostly air with stone at the bottom corner, and
+/// logs and stone as registry ids.
+fn sections(format: NetworkFormat) -> Vec<u8> {
+    let mut out = vec![];
+
+    out.extend(4096i16.to_be_bytes());
+    single(&mut out, format, 1);
+    single(&mut out, format, 0);
+
+    let mut blocks = vec![0u16; 4096];
+    blocks[0] = 1;
+    let mut biomes = vec![0u16; 64];
+    biomes[63] = 1;
+    out.extend(1i16.to_be_bytes());
+    indirect(&mut out, format, 4, &[0, 1], &blocks);
+    indirect(&mut out, format, 1, &[0, 1], &biomes);
+
+    let blocks: Vec<u16> = (0..4096).map(|i| if i < 2048 { 2 } else { 1 }).collect();
+    out.extend(4096i16.to_be_bytes());
+    direct(&mut out, format, 15, &blocks);
+    direct(&mut out, format, 6, &[1; 64]);
+
+    out
+}
+
+#[test]
+fn parse_sections() {
+    for format in [NetworkFormat::LengthPrefixed, NetworkFormat::Implied] {
+        let tower = read_network_sections(&sections(format), -4, &registry(), format).unwrap();
+        let sections: Vec<_> = tower.iter().collect();
+
+        assert_eq!(3, sections.len());
+        assert_eq!(-64, tower.y_min());
+        assert_eq!(-16, tower.y_max());
+
+        assert_eq!(-4, sections[0].y);
+        assert_eq!(
+            HashMap::from([("minecraft:stone", 4096)]),
+            sections[0].block_counts()
+        );
+        let biomes = sections[0].biomes.as_ref().unwrap();
+        assert_eq!(Some("minecraft:plains"), biomes.biome(15, 15, 15));
+
+        assert_eq!(
+            HashMap::from([("minecraft:stone", 1), ("minecraft:air", 4095)]),
+            sections[1].block_counts()
+        );
+        assert_eq!(
+            "minecraft:stone",
+            sections[1].blocks().next().unwrap().name()
+        );
+        let biomes = sections[1].biomes.as_ref().unwrap();
+        assert_eq!(Some("minecraft:plains"), biomes.biome(0, 0, 0));
+        assert_eq!(Some("minecraft:desert"), biomes.biome(15, 15, 15));
+
+        assert_eq!(
+            HashMap::from([("minecraft:stone", 2048), ("minecraft:oak_log", 2048)]),
+            sections[2].block_counts()
+        );
+        let log = sections[2].blocks().next().unwrap();
+        assert_eq!(Some("y"), log.property("axis"));
+        let biomes = sections[2].biomes.as_ref().unwrap();
+        assert_eq!(Some("minecraft:desert"), biomes.biome(0, 0, 0));
+    }
+}
+
+#[test]
+fn errors() {
+    let format = NetworkFormat::LengthPrefixed;
+    let data = sections(format);
+
+    let res = read_network_sections(&data[..data.len() - 1], -4, &registry(), format);
+    assert!(matches!(res, Err(Error::Network(_))));
+
+    let registry = NetworkRegistry {
+        block_states: vec![],
+        ..registry()
+    };
+    let res = read_network_sections(&data, -4, &registry, format);
+    assert!(matches!(res, Err(Error::Network(_))));
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use fastnbt::{IntArray, Value};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+
+use crate::{read_player_data, DimensionId};
+
+#[derive(Serialize)]
+struct Player {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Pos")]
+    pos: [f64; 3],
+    #[serde(rename = "Dimension")]
+    dimension: Value,
+    #[serde(rename = "Inventory")]
+    inventory: Vec<HashMap<&'static str, Value>>,
+    #[serde(rename = "SelectedItemSlot")]
+    selected_item_slot: i32,
+    #[serde(rename = "XpLevel")]
+    xp_level: i32,
+    #[serde(rename = "XpP")]
+    xp_progress: f32,
+    #[serde(rename = "Health")]
+    health: f32,
+    #[serde(rename = "UUID")]
+    uuid: IntArray,
+    abilities: HashMap<&'static str, Value>,
+}
+
+fn item(slot: i8, id: &str, count: Value) -> HashMap<&'static str, Value> {
+    let mut item = HashMap::new();
+    item.insert("Slot", Value::Byte(slot));
+    item.insert("id", Value::String(id.to_owned()));
+    match count {
+        Value::Byte(_) => item.insert("Count", count),
+        _ => item.insert("count", count),
+    };
+    item
+}
+
+fn player_file(dimension: Value) -> Vec<u8> {
+    let mut abilities = HashMap::new();
+    abilities.insert("flying", Value::Byte(0));
+
+    let player = Player {
+        data_version: 3700,
+        pos: [10.5, 70.0, -3.25],
+        dimension,
+        inventory: vec![
+            item(0, "minecraft:diamond_sword", Value::Byte(1)),
+            item(3, "minecraft:torch", Value::Int(32)),
+            item(103, "minecraft:iron_helmet", Value::Byte(1)),
+        ],
+        selected_item_slot: 3,
+        xp_level: 30,
+        xp_progress: 0.5,
+        health: 20.0,
+        uuid: IntArray::new(vec![0, 0, 0, 7]),
+        abilities,
+    };
+
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    fastnbt::ser::to_writer(&mut enc, &player).unwrap();
+    enc.finish().unwrap()
+}
+
+#[test]
+fn player_data() {
+    let data = player_file(Value::String("minecraft:the_nether".to_owned()));
+    let player = read_player_data(data.as_slice()).unwrap();
+
+    assert_eq!(Some(3700), player.data_version);
+    assert_eq!([10.5, 70.0, -3.25], player.pos);
+    assert_eq!(Some(DimensionId::Nether), player.dimension());
+    assert_eq!(Some(7), player.uuid());
+    assert_eq!(
+        (30, 0.5, 20.0),
+        (player.xp_level, player.xp_progress, player.health)
+    );
+    assert!(player.ender_items.is_empty());
+    assert!(player.other.contains_key("abilities"));
+
+    assert_eq!(3, player.inventory.len());
+    let selected = player.selected_item().unwrap();
+    assert_eq!(
+        ("minecraft:torch", 32),
+        (selected.id.as_str(), selected.count)
+    );
+    assert_eq!(
+        "minecraft:iron_helmet",
+        player.armor()[3].map(|i| i.id.as_str()).unwrap()
+    );
+    assert!(player.armor()[0].is_none());
+}
+
+#[test]
+fn old_numeric_dimension() {
+    let data = player_file(Value::Int(1));
+    let player = read_player_data(data.as_slice()).unwrap();
+    assert_eq!(Some(DimensionId::End), player.dimension());
+}
@@ -0,0 +1,74 @@
+use crate::PoiChunk;
+
+fn name(out: &mut Vec<u8>, tag: u8, name: &str) {
+    out.push(tag);
+    out.extend((name.len() as u16).to_be_bytes());
+    out.extend(name.as_bytes());
+}
+
+fn record(out: &mut Vec<u8>, kind: &str, pos: [i32; 3], free_tickets: i32) {
+    name(out, 8, "type");
+    out.extend((kind.len() as u16).to_be_bytes());
+    out.extend(kind.as_bytes());
+    name(out, 11, "pos");
+    out.extend(3i32.to_be_bytes());
+    for p in pos {
+        out.extend(p.to_be_bytes());
+    }
+    name(out, 3, "free_tickets");
+    out.extend(free_tickets.to_be_bytes());
+    out.push(0);
+}
+
+fn poi_chunk() -> Vec<u8> {
+    let mut out = vec![];
+    name(&mut out, 10, "");
+
+    name(&mut out, 3, "DataVersion");
+    out.extend(2730i32.to_be_bytes());
+
+    name(&mut out, 10, "Sections");
+
+    name(&mut out, 10, "-1");
+    name(&mut out, 1, "Valid");
+    out.push(1);
+    name(&mut out, 9, "Records");
+    out.push(10);
+    out.extend(2i32.to_be_bytes());
+    record(&mut out, "minecraft:nether_portal", [1, -10, 2], 0);
+    record(&mut out, "minecraft:nether_portal", [1, -9, 2], 0);
+    out.push(0);
+
+    name(&mut out, 10, "4");
+    name(&mut out, 1, "Valid");
+    out.push(0);
+    name(&mut out, 9, "Records");
+    out.push(10);
+    out.extend(1i32.to_be_bytes());
+    record(&mut out, "minecraft:armorer", [3, 70, 4], 1);
+    out.push(0);
+
+    out.push(0); // end Sections
+    out.push(0); // end root
+    out
+}
+
+#[test]
+fn deserialize_poi_chunk() {
+    let chunk: PoiChunk = fastnbt::de::from_bytes(&poi_chunk()).unwrap();
+
+    assert_eq!(chunk.data_version, 2730);
+    assert_eq!(chunk.records().count(), 3);
+
+    let portals = chunk.section(-1).unwrap();
+    assert!(portals.valid);
+    assert_eq!(portals.records[1].kind, "minecraft:nether_portal");
+    assert_eq!(portals.records[1].pos(), Some([1, -9, 2]));
+
+    let armorer = chunk.section(4).unwrap();
+    assert!(!armorer.valid);
+    assert_eq!(armorer.records[0].kind, "minecraft:armorer");
+    assert_eq!(armorer.records[0].free_tickets, 1);
+
+    assert!(chunk.section(0).is_none());
+}
@@ -0,0 +1,117 @@
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+
+#[cfg(feature = "image")]
+use crate::tiles::RenderManifest;
+use crate::{compact_dimension, Error, Progress, RCoord, RegionBuffer, Result};
+
+#[derive(Default)]
+struct Recorder {
+    started: Option<usize>,
+    done: Vec<(isize, isize, usize)>,
+    errors: Vec<(isize, isize)>,
+    finished: bool,
+}
+
+impl Progress for Recorder {
+    fn start(&mut self, regions: usize) {
+        self.started = Some(regions);
+    }
+
+    fn region_done(&mut self, x: RCoord, z: RCoord, chunks: usize) {
+        self.done.push((x.0, z.0, chunks));
+    }
+
+    fn error(&mut self, x: RCoord, z: RCoord, _error: Error) -> Result<()> {
+        self.errors.push((x.0, z.0));
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+/// Data that doesn't compress, so takes up several sectors.
+fn noise() -> Vec<u8> {
+    let mut state = 1u32;
+    (0..10000)
+        .map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+/// A region directory with one good region with two chunks, one of which
+/// has been rewritten leaving dead space, and one region with a truncated
+/// header.
+fn region_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fastanvil-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(dir.join("r.0.0.mca"))
+        .unwrap();
+    let mut region = RegionBuffer::new(file);
+    region.write_chunk(0, 0, &[1; 100]).unwrap();
+    region.write_chunk(1, 0, &[2; 100]).unwrap();
+    region.write_chunk(0, 0, &noise()).unwrap();
+
+    fs::write(dir.join("r.1.0.mca"), [0; 100]).unwrap();
+    dir
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn scan_reports_regions_and_errors() {
+    let dir = region_dir("progress-scan");
+
+    assert!(RenderManifest::scan(&dir).is_err());
+
+    let mut progress = Recorder::default();
+    let manifest = RenderManifest::scan_with_progress(&dir, &mut progress).unwrap();
+
+    assert_eq!(Some(2), progress.started);
+    assert_eq!(vec![(0, 0, 2)], progress.done);
+    assert_eq!(vec![(1, 0)], progress.errors);
+    assert!(progress.finished);
+
+    assert!(manifest.timestamps(RCoord(0), RCoord(0)).is_some());
+    assert!(manifest.timestamps(RCoord(1), RCoord(0)).is_none());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn compact_dimension_in_place() {
+    let dir = region_dir("progress-compact");
+    let before = fs::metadata(dir.join("r.0.0.mca")).unwrap().len();
+
+    let mut progress = Recorder::default();
+    let report = compact_dimension(&dir, &mut progress).unwrap();
+
+    assert_eq!(vec![(0, 0, 2)], progress.done);
+    assert_eq!(vec![(1, 0)], progress.errors);
+    assert_eq!(2, report.chunks);
+    assert_eq!(before, report.bytes_before);
+    assert!(report.bytes_reclaimed() > 0);
+
+    let after = fs::metadata(dir.join("r.0.0.mca")).unwrap().len();
+    assert_eq!(report.bytes_after, after);
+    assert!(!dir.join("r.0.0.mca.compact").exists());
+
+    let region = RegionBuffer::new(fs::File::open(dir.join("r.0.0.mca")).unwrap());
+    assert_eq!(noise(), region.load_chunk(0, 0).unwrap());
+    assert_eq!(vec![2; 100], region.load_chunk(1, 0).unwrap());
+
+    // The broken region is left alone.
+    assert_eq!(100, fs::metadata(dir.join("r.1.0.mca")).unwrap().len());
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Cursor;
+
+use fastnbt::Value;
+
+use crate::{prune_dimension, prune_region, ChunkInfo, RegionBuffer};
+
+/// Chunk NBT with the given inhabited time, at the root like 1.18 onwards
+/// or in the `Level` compound like older versions.
+fn chunk(inhabited: i64, level: bool) -> Vec<u8> {
+    let mut nbt = HashMap::new();
+    nbt.insert("InhabitedTime".to_owned(), Value::Long(inhabited));
+    if level {
+        let mut root = HashMap::new();
+        root.insert("Level".to_owned(), Value::Compound(nbt));
+        nbt = root;
+    }
+    nbt.insert("DataVersion".to_owned(), Value::Int(2730));
+    fastnbt::ser::to_bytes(&nbt).unwrap()
+}
+
+#[test]
+fn inhabited_time() {
+    let info = |data| ChunkInfo {
+        x: 0,
+        z: 0,
+        timestamp: 0,
+        data,
+    };
+
+    assert_eq!(Some(0), info(chunk(0, false)).inhabited_time());
+    assert_eq!(Some(1234), info(chunk(1234, true)).inhabited_time());
+    assert_eq!(None, info(vec![1, 2, 3]).inhabited_time());
+
+    let empty = fastnbt::ser::to_bytes(&HashMap::<String, i32>::new()).unwrap();
+    assert_eq!(None, info(empty).inhabited_time());
+}
+
+#[test]
+fn prune_region_removes_matching_chunks() {
+    let mut src = RegionBuffer::new(Cursor::new(Vec::new()));
+    src.write_chunk(0, 0, &chunk(0, false)).unwrap();
+    src.write_chunk(3, 1, &chunk(500, false)).unwrap();
+    src.write_chunk(31, 31, &chunk(0, true)).unwrap();
+    src.write_chunk(4, 4, &chunk(20, true)).unwrap();
+
+    let mut seen = Vec::new();
+    let mut out = Vec::new();
+    let report = prune_region(&src, &mut out, |chunk| {
+        seen.push((chunk.x, chunk.z));
+        chunk.inhabited_time() == Some(0)
+    })
+    .unwrap();
+
+    seen.sort_unstable();
+    assert_eq!(vec![(0, 0), (3, 1), (4, 4), (31, 31)], seen);
+    assert_eq!(2, report.chunks);
+    assert_eq!(2, report.removed);
+
+    let pruned = RegionBuffer::new(Cursor::new(out));
+    assert!(!pruned.chunk_exists(0, 0));
+    assert!(!pruned.chunk_exists(31, 31));
+    let value = |data: Vec<u8>| fastnbt::de::from_bytes::<Value>(&data).unwrap();
+    assert_eq!(
+        value(chunk(500, false)),
+        value(pruned.load_chunk(3, 1).unwrap())
+    );
+    assert_eq!(
+        value(chunk(20, true)),
+        value(pruned.load_chunk(4, 4).unwrap())
+    );
+    assert_eq!(
+        src.chunk_timestamp(3, 1).unwrap(),
+        pruned.chunk_timestamp(3, 1).unwrap()
+    );
+}
+
+#[test]
+fn prune_dimension_uses_absolute_coordinates() {
+    let dir = std::env::temp_dir().join(format!("fastanvil-prune-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(dir.join("r.-1.2.mca"))
+        .unwrap();
+    let mut region = RegionBuffer::new(file);
+    region.write_chunk(31, 0, &chunk(10, false)).unwrap();
+    region.write_chunk(0, 5, &chunk(10, false)).unwrap();
+    drop(region);
+
+    let mut seen = Vec::new();
+    let report = prune_dimension(
+        &dir,
+        |chunk| {
+            seen.push((chunk.x, chunk.z));
+            chunk.x < -10
+        },
+        &mut (),
+    )
+    .unwrap();
+
+    seen.sort_unstable();
+    assert_eq!(vec![(-32, 69), (-1, 64)], seen);
+    assert_eq!(1, report.chunks);
+    assert_eq!(1, report.removed);
+
+    let region = RegionBuffer::new(fs::File::open(dir.join("r.-1.2.mca")).unwrap());
+    assert!(region.chunk_exists(31, 0));
+    assert!(!region.chunk_exists(0, 5));
+    assert!(!dir.join("r.-1.2.mca.compact").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
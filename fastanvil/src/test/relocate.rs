@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+
+use fastnbt::{IntArray, LongArray, Value};
+
+use crate::relocate::{copy_chunks, relocate_chunk};
+use crate::{CCoord, ChunkNbt, RegionBuffer};
+
+fn compound(entries: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+    )
+}
+
+fn pos(x: f64, y: f64, z: f64) -> Value {
+    Value::List(vec![Value::Double(x), Value::Double(y), Value::Double(z)])
+}
+
+fn pack(x: i32, z: i32) -> i64 {
+    (x as u32 as i64) | (z as i64) << 32
+}
+
+/// The contents of a chunk at 2,-3 with something of each kind that is
+/// relocated.
+fn level() -> HashMap<String, Value> {
+    let nbt = compound(vec![
+        ("xPos", Value::Int(2)),
+        ("zPos", Value::Int(-3)),
+        (
+            "entities",
+            Value::List(vec![compound(vec![
+                ("Pos", pos(40.5, 64.0, -40.5)),
+                (
+                    "Passengers",
+                    Value::List(vec![compound(vec![("Pos", pos(40.5, 65.0, -40.5))])]),
+                ),
+            ])]),
+        ),
+        (
+            "block_entities",
+            Value::List(vec![compound(vec![
+                ("x", Value::Int(33)),
+                ("y", Value::Int(70)),
+                ("z", Value::Int(-47)),
+            ])]),
+        ),
+        (
+            "structures",
+            compound(vec![
+                (
+                    "References",
+                    compound(vec![(
+                        "minecraft:village",
+                        Value::LongArray(LongArray::new(vec![pack(2, -3), pack(-1, 5)])),
+                    )]),
+                ),
+                (
+                    "starts",
+                    compound(vec![(
+                        "minecraft:village",
+                        compound(vec![("ChunkX", Value::Int(2))]),
+                    )]),
+                ),
+                (
+                    "Starts",
+                    compound(vec![(
+                        "minecraft:mineshaft",
+                        compound(vec![
+                            ("ChunkX", Value::Int(2)),
+                            ("ChunkZ", Value::Int(-3)),
+                            (
+                                "Children",
+                                Value::List(vec![compound(vec![
+                                    (
+                                        "BB",
+                                        Value::IntArray(IntArray::new(vec![
+                                            30, 10, -50, 40, 20, -40,
+                                        ])),
+                                    ),
+                                    (
+                                        "Entrances",
+                                        Value::List(vec![Value::IntArray(IntArray::new(vec![
+                                            30, 10, -50, 32, 12, -48,
+                                        ]))]),
+                                    ),
+                                    ("PosX", Value::Int(35)),
+                                ])]),
+                            ),
+                        ]),
+                    )]),
+                ),
+            ]),
+        ),
+    ]);
+
+    match nbt {
+        Value::Compound(c) => c,
+        _ => unreachable!(),
+    }
+}
+
+fn get<'a>(value: &'a Value, path: &[&str]) -> &'a Value {
+    path.iter().fold(value, |v, key| match v {
+        Value::Compound(c) => &c[*key],
+        Value::List(l) => &l[key.parse::<usize>().unwrap()],
+        _ => panic!("no {} in {:?}", key, v),
+    })
+}
+
+fn check_relocated(level: &Value) {
+    assert_eq!(&Value::Int(-8), get(level, &["xPos"]));
+    assert_eq!(&Value::Int(1), get(level, &["zPos"]));
+
+    assert_eq!(
+        &pos(-119.5, 64.0, 23.5),
+        get(level, &["entities", "0", "Pos"])
+    );
+    assert_eq!(
+        &pos(-119.5, 65.0, 23.5),
+        get(level, &["entities", "0", "Passengers", "0", "Pos"])
+    );
+
+    assert_eq!(&Value::Int(-127), get(level, &["block_entities", "0", "x"]));
+    assert_eq!(&Value::Int(70), get(level, &["block_entities", "0", "y"]));
+    assert_eq!(&Value::Int(17), get(level, &["block_entities", "0", "z"]));
+
+    assert_eq!(
+        &Value::LongArray(LongArray::new(vec![pack(-8, 1), pack(-11, 9)])),
+        get(level, &["structures", "References", "minecraft:village"])
+    );
+
+    assert_eq!(
+        &Value::Int(-8),
+        get(
+            level,
+            &["structures", "starts", "minecraft:village", "ChunkX"]
+        )
+    );
+
+    let start = get(level, &["structures", "Starts", "minecraft:mineshaft"]);
+    assert_eq!(&Value::Int(-8), get(start, &["ChunkX"]));
+    assert_eq!(&Value::Int(1), get(start, &["ChunkZ"]));
+    assert_eq!(
+        &Value::IntArray(IntArray::new(vec![-130, 10, 14, -120, 20, 24])),
+        get(start, &["Children", "0", "BB"])
+    );
+    assert_eq!(
+        &Value::IntArray(IntArray::new(vec![-130, 10, 14, -128, 12, 16])),
+        get(start, &["Children", "0", "Entrances", "0"])
+    );
+    assert_eq!(&Value::Int(-125), get(start, &["Children", "0", "PosX"]));
+}
+
+#[test]
+fn relocate_chunk_at_root() {
+    let mut nbt: ChunkNbt = level();
+    relocate_chunk(&mut nbt, -10, 4);
+    check_relocated(&Value::Compound(nbt));
+}
+
+#[test]
+fn relocate_chunk_in_level() {
+    let mut nbt: ChunkNbt = HashMap::new();
+    nbt.insert("Level".to_owned(), Value::Compound(level()));
+    nbt.insert("DataVersion".to_owned(), Value::Int(2730));
+
+    relocate_chunk(&mut nbt, -10, 4);
+    check_relocated(&nbt["Level"]);
+    assert_eq!(Value::Int(2730), nbt["DataVersion"]);
+}
+
+#[test]
+fn relocate_entity_and_poi_chunks() {
+    let mut entities: ChunkNbt = HashMap::new();
+    entities.insert(
+        "Position".to_owned(),
+        Value::IntArray(IntArray::new(vec![2, -3])),
+    );
+    relocate_chunk(&mut entities, 1, 1);
+    assert_eq!(
+        Value::IntArray(IntArray::new(vec![3, -2])),
+        entities["Position"]
+    );
+
+    let record = compound(vec![(
+        "pos",
+        Value::IntArray(IntArray::new(vec![33, 64, -47])),
+    )]);
+    let mut poi: ChunkNbt = HashMap::new();
+    poi.insert(
+        "Sections".to_owned(),
+        compound(vec![(
+            "4",
+            compound(vec![("Records", Value::List(vec![record]))]),
+        )]),
+    );
+    relocate_chunk(&mut poi, 1, 1);
+    assert_eq!(
+        &Value::IntArray(IntArray::new(vec![49, 64, -31])),
+        get(&poi["Sections"], &["4", "Records", "0", "pos"])
+    );
+}
+
+#[test]
+fn copy_chunks_between_regions() {
+    let dir = std::env::temp_dir().join(format!("fastanvil-relocate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let (src, dst) = (dir.join("src"), dir.join("dst"));
+    fs::create_dir_all(&src).unwrap();
+
+    let chunk = |x: i32, z: i32| {
+        let mut nbt = HashMap::new();
+        nbt.insert("xPos".to_owned(), Value::Int(x));
+        nbt.insert("zPos".to_owned(), Value::Int(z));
+        fastnbt::ser::to_bytes(&nbt).unwrap()
+    };
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(src.join("r.0.0.mca"))
+        .unwrap();
+    let mut region = RegionBuffer::new(file);
+    region.write_chunk(0, 0, &chunk(0, 0)).unwrap();
+    region.write_chunk(1, 2, &chunk(1, 2)).unwrap();
+    region.write_chunk(10, 10, &chunk(10, 10)).unwrap();
+    drop(region);
+
+    let copied = copy_chunks(
+        &src,
+        &dst,
+        (CCoord(0), CCoord(0)),
+        (CCoord(4), CCoord(4)),
+        (-3, 40),
+    )
+    .unwrap();
+    assert_eq!(2, copied);
+
+    let region = RegionBuffer::new(fs::File::open(dst.join("r.-1.1.mca")).unwrap());
+    let load = |x, z| fastnbt::de::from_bytes::<Value>(&region.load_chunk(x, z).unwrap()).unwrap();
+    assert_eq!(
+        fastnbt::de::from_bytes::<Value>(&chunk(-3, 40)).unwrap(),
+        load(29, 8)
+    );
+    assert_eq!(
+        fastnbt::de::from_bytes::<Value>(&chunk(-2, 42)).unwrap(),
+        load(30, 10)
+    );
+    assert_eq!(2, region.locations().unwrap().len());
+
+    // Both chunks move into the same region, and nothing else is written.
+    assert_eq!(vec!["r.-1.1.mca"], {
+        let mut names: Vec<_> = fs::read_dir(&dst)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    });
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use fastnbt::{IntArray, Value};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::{read_saved_data, IdCounts, Raids, Scoreboard};
+
+fn compound(entries: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_owned())
+}
+
+fn saved_file(data: Value) -> Vec<u8> {
+    let file = compound(vec![("data", data), ("DataVersion", Value::Int(3700))]);
+
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    fastnbt::ser::to_writer(&mut enc, &file).unwrap();
+    enc.finish().unwrap()
+}
+
+#[test]
+fn id_counts() {
+    let file = saved_file(compound(vec![("map", Value::Int(7))]));
+    let counts: IdCounts = read_saved_data(file.as_slice()).unwrap();
+    assert_eq!(Some(7), counts.map);
+
+    let file = saved_file(compound(vec![]));
+    let counts: IdCounts = read_saved_data(file.as_slice()).unwrap();
+    assert_eq!(None, counts.map);
+}
+
+#[test]
+fn scoreboard() {
+    let file = saved_file(compound(vec![
+        (
+            "Objectives",
+            Value::List(vec![compound(vec![
+                ("Name", string("kills")),
+                ("CriteriaName", string("playerKillCount")),
+                ("DisplayName", string("{\"text\":\"Kills\"}")),
+                ("RenderType", string("integer")),
+            ])]),
+        ),
+        (
+            "PlayerScores",
+            Value::List(vec![compound(vec![
+                ("Name", string("Steve")),
+                ("Objective", string("kills")),
+                ("Score", Value::Int(12)),
+                ("Locked", Value::Byte(0)),
+            ])]),
+        ),
+        (
+            "Teams",
+            Value::List(vec![compound(vec![
+                ("Name", string("red")),
+                ("TeamColor", string("red")),
+                ("AllowFriendlyFire", Value::Byte(1)),
+                ("Prefix", string("{\"text\":\"[R] \"}")),
+                (
+                    "Players",
+                    Value::List(vec![string("Steve"), string("Alex")]),
+                ),
+            ])]),
+        ),
+        ("DisplaySlots", compound(vec![("slot_1", string("kills"))])),
+    ]));
+
+    let scoreboard: Scoreboard = read_saved_data(file.as_slice()).unwrap();
+
+    assert_eq!("playerKillCount", scoreboard.objectives[0].criteria_name);
+    assert_eq!(12, scoreboard.player_scores[0].score);
+    assert!(!scoreboard.player_scores[0].locked);
+
+    let team = &scoreboard.teams[0];
+    assert_eq!(Some("red"), team.team_color.as_deref());
+    assert!(team.allow_friendly_fire);
+    assert_eq!(vec!["Steve", "Alex"], team.players);
+    assert!(team.other.contains_key("Prefix"));
+
+    assert_eq!(
+        Some(&"kills".to_owned()),
+        scoreboard.display_slots.get("slot_1")
+    );
+}
+
+#[test]
+fn raids() {
+    let file = saved_file(compound(vec![
+        (
+            "Raids",
+            Value::List(vec![compound(vec![
+                ("Id", Value::Int(3)),
+                ("Started", Value::Byte(1)),
+                ("Active", Value::Byte(1)),
+                ("Status", string("ongoing")),
+                ("BadOmenLevel", Value::Int(2)),
+                ("GroupsSpawned", Value::Int(1)),
+                ("NumGroups", Value::Int(5)),
+                ("TicksActive", Value::Long(1200)),
+                ("CX", Value::Int(100)),
+                ("CY", Value::Int(64)),
+                ("CZ", Value::Int(-20)),
+                (
+                    "HeroesOfTheVillage",
+                    Value::List(vec![Value::IntArray(IntArray::new(vec![0, 0, 0, 42]))]),
+                ),
+            ])]),
+        ),
+        ("NextAvailableID", Value::Int(4)),
+        ("Tick", Value::Int(50000)),
+    ]));
+
+    let raids: Raids = read_saved_data(file.as_slice()).unwrap();
+
+    assert_eq!(Some(4), raids.next_available_id);
+    let raid = &raids.raids[0];
+    assert_eq!("ongoing", raid.status);
+    assert!(raid.started);
+    assert_eq!((100, 64, -20), (raid.cx, raid.cy, raid.cz));
+    assert_eq!(vec![42], raid.heroes());
+}
@@ -31,10 +31,11 @@ impl Palette for HashPalette {
 fn exercise_render(chunk: &impl Chunk) -> [[u8; 4]; 256] {
     let palette = HashPalette;
 
-    let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
+    let renderer = TopShadeRenderer::new(&palette, HeightMode::Calculate);
     renderer.render(chunk, None);
 
-    let renderer = TopShadeRenderer::new(&palette, HeightMode::Calculate);
+    // Heights are cached per mode, so this doesn't see the calculated ones.
+    let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
     renderer.render(chunk, None)
 }
 
@@ -305,262 +306,262 @@ fn chunk_1_17_0() {
 #[test]
 fn chunk_1_17_1() {
     let expected = [
-        [132, 66, 119, 255],
-        [159, 109, 90, 255],
-        [133, 65, 125, 255],
-        [145, 66, 119, 255],
-        [133, 65, 128, 255],
-        [146, 66, 122, 255],
-        [147, 66, 125, 255],
-        [147, 66, 125, 255],
-        [149, 66, 127, 255],
-        [149, 66, 127, 255],
-        [149, 66, 127, 255],
-        [150, 66, 130, 255],
-        [150, 66, 130, 255],
-        [150, 66, 130, 255],
-        [150, 66, 130, 255],
-        [150, 66, 130, 255],
-        [133, 90, 74, 255],
-        [136, 93, 76, 255],
-        [136, 93, 76, 255],
-        [124, 56, 99, 255],
-        [124, 56, 99, 255],
-        [125, 56, 102, 255],
-        [125, 56, 105, 255],
-        [125, 56, 105, 255],
-        [126, 56, 107, 255],
-        [126, 56, 107, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [129, 56, 112, 255],
-        [144, 57, 43, 255],
-        [113, 56, 100, 255],
-        [133, 90, 74, 255],
-        [113, 56, 100, 255],
-        [136, 93, 76, 255],
-        [123, 57, 98, 255],
-        [113, 56, 102, 255],
-        [124, 56, 99, 255],
-        [125, 56, 102, 255],
-        [125, 56, 105, 255],
-        [141, 98, 81, 255],
-        [142, 100, 83, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [144, 57, 42, 255],
-        [113, 56, 96, 255],
-        [132, 88, 71, 255],
-        [132, 88, 71, 255],
-        [133, 90, 74, 255],
-        [113, 56, 100, 255],
-        [113, 56, 100, 255],
-        [123, 57, 98, 255],
-        [124, 56, 99, 255],
-        [125, 56, 102, 255],
-        [125, 56, 105, 255],
-        [141, 98, 81, 255],
-        [142, 100, 83, 255],
-        [126, 56, 107, 255],
-        [128, 56, 109, 255],
-        [128, 56, 109, 255],
-        [144, 57, 42, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [113, 56, 96, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [123, 57, 98, 255],
-        [123, 57, 98, 255],
-        [137, 94, 77, 255],
-        [139, 95, 79, 255],
-        [141, 98, 81, 255],
-        [142, 100, 83, 255],
-        [126, 56, 107, 255],
-        [126, 56, 107, 255],
-        [115, 56, 115, 255],
-        [118, 57, 84, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [113, 56, 100, 255],
-        [113, 56, 102, 255],
-        [123, 57, 98, 255],
-        [137, 94, 77, 255],
-        [114, 56, 107, 255],
-        [141, 98, 81, 255],
-        [141, 98, 81, 255],
-        [142, 100, 83, 255],
-        [142, 57, 42, 255],
-        [117, 57, 81, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [112, 56, 90, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [121, 57, 94, 255],
-        [123, 57, 98, 255],
-        [123, 57, 98, 255],
-        [137, 94, 77, 255],
-        [139, 95, 79, 255],
-        [141, 98, 81, 255],
-        [141, 98, 81, 255],
-        [142, 57, 42, 255],
-        [106, 59, 56, 255],
-        [106, 59, 58, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [112, 56, 90, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [113, 56, 100, 255],
-        [123, 57, 98, 255],
-        [124, 56, 99, 255],
-        [139, 95, 79, 255],
-        [141, 98, 81, 255],
-        [140, 57, 42, 255],
-        [106, 59, 54, 255],
-        [106, 59, 56, 255],
-        [106, 59, 58, 255],
-        [117, 57, 81, 255],
-        [112, 56, 90, 255],
-        [112, 56, 90, 255],
-        [119, 57, 88, 255],
-        [112, 56, 93, 255],
-        [120, 57, 90, 255],
-        [113, 56, 96, 255],
-        [113, 56, 100, 255],
-        [121, 57, 94, 255],
-        [123, 57, 98, 255],
-        [124, 56, 99, 255],
-        [139, 95, 79, 255],
-        [141, 98, 81, 255],
-        [106, 59, 54, 255],
-        [110, 57, 79, 255],
-        [111, 57, 82, 255],
-        [116, 57, 76, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [118, 57, 84, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [113, 56, 96, 255],
-        [121, 57, 94, 255],
-        [121, 57, 94, 255],
-        [123, 57, 98, 255],
-        [123, 57, 98, 255],
-        [139, 95, 79, 255],
-        [106, 59, 54, 255],
-        [106, 59, 54, 255],
-        [106, 59, 56, 255],
-        [111, 57, 82, 255],
-        [116, 57, 76, 255],
-        [117, 57, 81, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [118, 57, 84, 255],
-        [112, 56, 93, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [121, 57, 94, 255],
-        [123, 57, 98, 255],
-        [123, 57, 98, 255],
-        [119, 75, 56, 255],
-        [106, 59, 54, 255],
-        [109, 57, 75, 255],
-        [106, 59, 56, 255],
-        [114, 58, 73, 255],
-        [116, 57, 76, 255],
-        [116, 57, 76, 255],
-        [116, 57, 76, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [112, 56, 93, 255],
-        [120, 57, 90, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [121, 57, 94, 255],
-        [109, 57, 75, 255],
-        [119, 75, 56, 255],
-        [106, 59, 54, 255],
-        [106, 59, 54, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [114, 58, 73, 255],
-        [110, 57, 79, 255],
-        [114, 58, 73, 255],
-        [117, 57, 81, 255],
-        [112, 56, 90, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [120, 57, 90, 255],
-        [121, 57, 94, 255],
-        [104, 70, 52, 255],
-        [119, 75, 56, 255],
-        [119, 75, 56, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [109, 57, 75, 255],
-        [114, 58, 73, 255],
-        [114, 58, 73, 255],
-        [116, 57, 76, 255],
-        [111, 57, 87, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [120, 57, 90, 255],
-        [104, 70, 52, 255],
-        [119, 75, 56, 255],
-        [119, 75, 56, 255],
-        [109, 57, 75, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [114, 58, 73, 255],
-        [116, 57, 76, 255],
-        [117, 57, 81, 255],
-        [112, 56, 90, 255],
-        [118, 57, 84, 255],
-        [119, 57, 88, 255],
-        [119, 57, 88, 255],
-        [120, 57, 90, 255],
-        [109, 57, 75, 255],
-        [119, 75, 56, 255],
-        [109, 57, 75, 255],
-        [119, 75, 56, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [113, 58, 69, 255],
-        [110, 57, 79, 255],
-        [116, 57, 76, 255],
-        [117, 57, 81, 255],
-        [118, 57, 84, 255],
-        [118, 57, 84, 255],
-        [106, 59, 65, 255],
-        [106, 59, 65, 255],
+        [125, 77, 57, 255],
+        [126, 78, 59, 255],
+        [121, 62, 74, 255],
+        [121, 62, 74, 255],
+        [122, 62, 77, 255],
+        [122, 62, 77, 255],
+        [123, 62, 81, 255],
+        [123, 62, 81, 255],
+        [124, 62, 84, 255],
+        [124, 62, 84, 255],
+        [124, 62, 84, 255],
+        [125, 61, 88, 255],
+        [125, 61, 88, 255],
+        [125, 61, 88, 255],
+        [125, 61, 88, 255],
+        [125, 61, 88, 255],
+        [106, 64, 47, 255],
+        [107, 66, 49, 255],
+        [107, 66, 49, 255],
+        [103, 53, 61, 255],
+        [103, 53, 61, 255],
+        [104, 53, 63, 255],
+        [105, 53, 66, 255],
+        [105, 53, 66, 255],
+        [106, 53, 69, 255],
+        [106, 53, 69, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [107, 52, 75, 255],
+        [115, 53, 37, 255],
+        [106, 64, 47, 255],
+        [106, 64, 47, 255],
+        [106, 64, 47, 255],
+        [107, 66, 49, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [103, 53, 61, 255],
+        [104, 53, 63, 255],
+        [105, 53, 66, 255],
+        [111, 70, 54, 255],
+        [113, 72, 56, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [113, 53, 37, 255],
+        [103, 54, 54, 255],
+        [106, 64, 46, 255],
+        [106, 64, 46, 255],
+        [106, 64, 47, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 53, 61, 255],
+        [104, 53, 63, 255],
+        [105, 53, 66, 255],
+        [111, 70, 54, 255],
+        [113, 72, 56, 255],
+        [106, 53, 69, 255],
+        [106, 53, 72, 255],
+        [106, 53, 72, 255],
+        [113, 53, 37, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [108, 67, 50, 255],
+        [110, 69, 52, 255],
+        [111, 70, 54, 255],
+        [113, 72, 56, 255],
+        [106, 53, 69, 255],
+        [106, 53, 69, 255],
+        [113, 53, 37, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [108, 67, 50, 255],
+        [110, 69, 52, 255],
+        [111, 70, 54, 255],
+        [111, 70, 54, 255],
+        [113, 72, 56, 255],
+        [112, 53, 37, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [108, 67, 50, 255],
+        [110, 69, 52, 255],
+        [111, 70, 54, 255],
+        [111, 70, 54, 255],
+        [112, 53, 37, 255],
+        [102, 56, 42, 255],
+        [101, 56, 43, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 53, 61, 255],
+        [110, 69, 52, 255],
+        [111, 70, 54, 255],
+        [111, 53, 36, 255],
+        [103, 56, 42, 255],
+        [102, 56, 42, 255],
+        [101, 56, 43, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 53, 61, 255],
+        [110, 69, 52, 255],
+        [111, 70, 54, 255],
+        [103, 56, 42, 255],
+        [102, 56, 42, 255],
+        [101, 56, 43, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [110, 69, 52, 255],
+        [103, 56, 42, 255],
+        [103, 56, 42, 255],
+        [102, 56, 42, 255],
+        [101, 56, 43, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [103, 54, 58, 255],
+        [103, 54, 58, 255],
+        [106, 62, 43, 255],
+        [103, 56, 42, 255],
+        [103, 56, 42, 255],
+        [102, 56, 42, 255],
+        [104, 56, 48, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [103, 54, 56, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [103, 56, 42, 255],
+        [103, 56, 42, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [104, 56, 48, 255],
+        [104, 56, 48, 255],
+        [104, 56, 48, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [103, 54, 56, 255],
+        [102, 60, 41, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [104, 56, 48, 255],
+        [104, 56, 48, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [103, 54, 54, 255],
+        [102, 60, 41, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [104, 56, 48, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [103, 55, 53, 255],
+        [103, 55, 53, 255],
+        [103, 54, 54, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [106, 62, 43, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [105, 56, 47, 255],
+        [104, 56, 48, 255],
+        [104, 56, 50, 255],
+        [104, 56, 50, 255],
+        [103, 55, 52, 255],
+        [103, 55, 52, 255],
+        [100, 55, 44, 255],
+        [100, 55, 44, 255],
     ];
     let chunk: JavaChunk = from_bytes(&CHUNK_1_17_1).unwrap();
     exercise_render(&chunk);
@@ -761,7 +762,7 @@ fn chunk_custom_heights_1_17_1() {
         [98, 57, 26, 255],
         [120, 69, 32, 255],
         [120, 69, 32, 255],
-        [85, 145, 67, 255],
+        [84, 140, 64, 255],
         [172, 23, 75, 255],
         [161, 115, 25, 255],
         [172, 23, 75, 255],
@@ -777,7 +778,7 @@ fn chunk_custom_heights_1_17_1() {
         [98, 57, 26, 255],
         [120, 69, 32, 255],
         [120, 69, 32, 255],
-        [85, 145, 67, 255],
+        [84, 140, 64, 255],
         [100, 145, 59, 255],
         [141, 19, 62, 255],
         [100, 145, 59, 255],
@@ -792,8 +793,8 @@ fn chunk_custom_heights_1_17_1() {
         [120, 69, 32, 255],
         [120, 69, 32, 255],
         [98, 57, 26, 255],
-        [85, 145, 67, 255],
-        [85, 145, 67, 255],
+        [84, 140, 64, 255],
+        [84, 140, 64, 255],
         [98, 57, 26, 255],
         [98, 57, 26, 255],
         [100, 145, 59, 255],
@@ -808,8 +809,8 @@ fn chunk_custom_heights_1_17_1() {
         [98, 57, 26, 255],
         [120, 69, 32, 255],
         [120, 69, 32, 255],
-        [85, 145, 67, 255],
-        [85, 144, 66, 255],
+        [84, 140, 64, 255],
+        [84, 136, 63, 255],
         [120, 69, 32, 255],
         [120, 69, 32, 255],
         [172, 23, 75, 255],
@@ -824,8 +825,8 @@ fn chunk_custom_heights_1_17_1() {
         [120, 69, 32, 255],
         [120, 69, 32, 255],
         [120, 69, 32, 255],
-        [85, 145, 67, 255],
-        [85, 144, 66, 255],
+        [84, 140, 64, 255],
+        [84, 136, 63, 255],
     ];
 
     let chunk: JavaChunk = from_bytes(&CHUNK_CUSTOM_HEIGHTS_1_17_1).unwrap();
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use fastnbt::{IntArray, LongArray, Value};
+
+use crate::{BoundingBox, ChunkStructures};
+
+fn compound(entries: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v))
+            .collect(),
+    )
+}
+
+fn bb(values: [i32; 6]) -> Value {
+    Value::IntArray(IntArray::new(values.to_vec()))
+}
+
+fn pack(x: i32, z: i32) -> i64 {
+    (x as u32 as i64) | (z as i64) << 32
+}
+
+fn to_bytes(root: Value) -> Vec<u8> {
+    fastnbt::ser::to_bytes(&root).unwrap()
+}
+
+#[test]
+fn structures_1_18() {
+    let start = compound(vec![
+        ("id", Value::String("minecraft:village_plains".to_owned())),
+        ("ChunkX", Value::Int(-3)),
+        ("ChunkZ", Value::Int(7)),
+        (
+            "Children",
+            Value::List(vec![
+                compound(vec![
+                    ("id", Value::String("minecraft:jigsaw".to_owned())),
+                    ("BB", bb([-48, 60, 112, -40, 70, 120])),
+                ]),
+                compound(vec![
+                    ("id", Value::String("minecraft:jigsaw".to_owned())),
+                    ("BB", bb([-60, 64, 100, -50, 75, 110])),
+                ]),
+            ]),
+        ),
+    ]);
+
+    let structures = compound(vec![
+        (
+            "starts",
+            compound(vec![("minecraft:village_plains", start)]),
+        ),
+        (
+            "References",
+            compound(vec![
+                (
+                    "minecraft:village_plains",
+                    Value::LongArray(LongArray::new(vec![pack(-3, 7)])),
+                ),
+                (
+                    "minecraft:mineshaft",
+                    Value::LongArray(LongArray::new(vec![pack(-4, 8), pack(100, -200)])),
+                ),
+            ]),
+        ),
+    ]);
+
+    let chunk = compound(vec![
+        ("DataVersion", Value::Int(2975)),
+        ("structures", structures),
+    ]);
+    let structures = ChunkStructures::from_bytes(&to_bytes(chunk)).unwrap();
+
+    let starts: Vec<_> = structures.starts().collect();
+    assert_eq!(1, starts.len());
+    let (id, start) = starts[0];
+    assert_eq!("minecraft:village_plains", id);
+    assert_eq!((-3, 7), (start.chunk_x, start.chunk_z));
+    assert_eq!(2, start.children.len());
+
+    let bounds = start.bounding_box().unwrap();
+    assert_eq!(
+        BoundingBox {
+            min: [-60, 60, 100],
+            max: [-40, 75, 120],
+        },
+        bounds
+    );
+    assert!(bounds.intersects_chunk(-4, 6));
+    assert!(bounds.intersects_chunk(-3, 7));
+    assert!(!bounds.intersects_chunk(-2, 7));
+    assert!(bounds.contains([-40, 75, 100]));
+    assert!(!bounds.contains([-40, 76, 100]));
+
+    let mut references: Vec<_> = structures.references().collect();
+    references.sort_unstable();
+    assert_eq!(
+        vec![
+            ("minecraft:mineshaft", (-4, 8)),
+            ("minecraft:mineshaft", (100, -200)),
+            ("minecraft:village_plains", (-3, 7)),
+        ],
+        references
+    );
+}
+
+#[test]
+fn structures_in_level() {
+    let invalid = compound(vec![("id", Value::String("INVALID".to_owned()))]);
+    let fortress = compound(vec![
+        ("id", Value::String("Fortress".to_owned())),
+        ("ChunkX", Value::Int(1)),
+        ("ChunkZ", Value::Int(2)),
+        ("BB", bb([10, 40, 20, 90, 80, 100])),
+        ("Children", Value::List(vec![])),
+    ]);
+
+    let structures = compound(vec![
+        (
+            "Starts",
+            compound(vec![("Fortress", fortress), ("Village", invalid)]),
+        ),
+        ("References", compound(vec![])),
+    ]);
+    let chunk = compound(vec![
+        ("DataVersion", Value::Int(2586)),
+        ("Level", compound(vec![("Structures", structures)])),
+    ]);
+    let structures = ChunkStructures::from_bytes(&to_bytes(chunk)).unwrap();
+
+    assert_eq!(
+        vec!["Fortress"],
+        structures.starts().map(|(id, _)| id).collect::<Vec<_>>()
+    );
+    assert!(structures.start("Village").is_none());
+    assert_eq!(
+        Some(BoundingBox {
+            min: [10, 40, 20],
+            max: [90, 80, 100],
+        }),
+        structures.start("Fortress").unwrap().bounding_box()
+    );
+    assert_eq!(0, structures.references().count());
+}
+
+#[test]
+fn no_structures() {
+    let chunk = Value::Compound(HashMap::new());
+    let structures = ChunkStructures::from_bytes(&to_bytes(chunk)).unwrap();
+    assert_eq!(0, structures.starts().count());
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use fastnbt::Value;
+
+use crate::biome::Biome;
+use crate::upgrade::{self, upgraded_biome_name, upgraded_block_name, UPGRADED_DATA_VERSION};
+use crate::{Chunk, ChunkNbt, ChunkStatus, HeightMode, JavaChunk, VersionedChunk};
+
+const ETHO: &[u8] = include_bytes!("../../resources/etho.chunk");
+const OLD_IN_NEW: &[u8] = include_bytes!("../../resources/etho-old-in-new.chunk");
+const CHUNK_1_17_1: &[u8] = include_bytes!("../../resources/1.17.1.chunk");
+
+fn assert_same_world(before: &[u8]) {
+    let old: JavaChunk = fastnbt::de::from_bytes(before).unwrap();
+    let upgraded = upgrade::upgrade_chunk_bytes(before).unwrap();
+
+    let new = JavaChunk::from_bytes(&upgraded).unwrap();
+    assert!(matches!(new, VersionedChunk::V1_18(_)));
+    let new = new.as_java().unwrap();
+
+    assert_eq!(UPGRADED_DATA_VERSION, new.data_version);
+    assert_eq!(old.level.x_pos, new.level.x_pos);
+    assert_eq!(old.level.z_pos, new.level.z_pos);
+    assert_eq!(old.status(), new.status());
+    assert_eq!(old.block_entities().len(), new.block_entities().len());
+    assert_eq!(old.y_range(), new.y_range());
+
+    for y in old.y_range() {
+        for z in 0..16 {
+            for x in 0..16 {
+                let old_block = old.block(x, y, z).unwrap();
+                let new_block = new.block(x, y, z).unwrap();
+                assert_eq!(
+                    upgraded_block_name(old_block.name(), old.data_version),
+                    new_block.name()
+                );
+                assert_eq!(old_block.properties(), new_block.properties());
+            }
+        }
+    }
+
+    for y in (old.y_range().start..old.y_range().end).step_by(4) {
+        for (x, z) in [(0, 0), (5, 9), (15, 15)] {
+            let old_biome = old.biome(x, y, z).unwrap();
+            let expected = Biome::from_name(upgraded_biome_name(old_biome.id().unwrap()));
+            assert_eq!(Some(expected), new.biome(x, y, z));
+        }
+    }
+
+    for (x, z) in [(0, 0), (7, 3), (15, 15)] {
+        assert_eq!(
+            old.surface_height(x, z, HeightMode::Calculate),
+            new.surface_height(x, z, HeightMode::Calculate)
+        );
+    }
+}
+
+#[test]
+fn upgraded_chunks_have_the_same_blocks_and_biomes() {
+    assert_same_world(ETHO);
+    assert_same_world(OLD_IN_NEW);
+    assert_same_world(CHUNK_1_17_1);
+}
+
+#[test]
+fn level_moves_to_root() {
+    let mut nbt: ChunkNbt = fastnbt::de::from_bytes(CHUNK_1_17_1).unwrap();
+    assert!(upgrade::upgrade_chunk(&mut nbt).unwrap());
+
+    assert!(!nbt.contains_key("Level"));
+    assert!(nbt.contains_key("sections"));
+    assert!(nbt.contains_key("block_entities"));
+    assert!(!nbt.contains_key("TileEntities"));
+    assert_eq!(Some(&Value::Int(0)), nbt.get("yPos"));
+
+    // Already upgraded chunks are left alone.
+    let before = nbt.clone();
+    assert!(!upgrade::upgrade_chunk(&mut nbt).unwrap());
+    assert_eq!(before, nbt);
+}
+
+fn section(palette: &[&str], states: Vec<i64>) -> Value {
+    let palette = palette
+        .iter()
+        .map(|name| {
+            let mut block = HashMap::new();
+            block.insert("Name".to_owned(), Value::String((*name).to_owned()));
+            Value::Compound(block)
+        })
+        .collect();
+
+    let mut section = HashMap::new();
+    section.insert("Y".to_owned(), Value::Byte(0));
+    section.insert("Palette".to_owned(), Value::List(palette));
+    section.insert(
+        "BlockStates".to_owned(),
+        Value::LongArray(fastnbt::LongArray::new(states)),
+    );
+    Value::Compound(section)
+}
+
+fn chunk_1_13(sections: Vec<Value>, biomes: Vec<i32>) -> ChunkNbt {
+    let mut level = HashMap::new();
+    level.insert("xPos".to_owned(), Value::Int(2));
+    level.insert("zPos".to_owned(), Value::Int(-3));
+    level.insert(
+        "Status".to_owned(),
+        Value::String("postprocessed".to_owned()),
+    );
+    level.insert("Sections".to_owned(), Value::List(sections));
+    level.insert(
+        "Biomes".to_owned(),
+        Value::IntArray(fastnbt::IntArray::new(biomes)),
+    );
+
+    let mut nbt = ChunkNbt::new();
+    nbt.insert("DataVersion".to_owned(), Value::Int(1631));
+    nbt.insert("Level".to_owned(), Value::Compound(level));
+    nbt
+}
+
+#[test]
+fn renames_blocks_biomes_and_statuses_of_1_13_chunks() {
+    // The first block is a sign, the rest are grass paths.
+    let mut states = vec![0x1111_1111_1111_1111u64 as i64; 256];
+    states[0] = 0x1111_1111_1111_1110;
+
+    // 2D biomes, wooded hills on the left half and plains on the right.
+    let biomes = (0..256).map(|i| if i % 16 < 8 { 18 } else { 1 }).collect();
+
+    let mut nbt = chunk_1_13(
+        vec![section(&["minecraft:sign", "minecraft:grass_path"], states)],
+        biomes,
+    );
+    upgrade::upgrade_chunk(&mut nbt).unwrap();
+    let data = fastnbt::ser::to_bytes(&nbt).unwrap();
+    let chunk: JavaChunk = fastnbt::de::from_bytes(&data).unwrap();
+
+    assert_eq!(ChunkStatus::Full, chunk.status());
+    assert_eq!("minecraft:oak_sign", chunk.block(0, 0, 0).unwrap().name());
+    assert_eq!("minecraft:dirt_path", chunk.block(1, 0, 0).unwrap().name());
+    assert_eq!(
+        "minecraft:dirt_path",
+        chunk.block(15, 15, 15).unwrap().name()
+    );
+
+    assert_eq!(Some(Biome::Forest), chunk.biome(0, 0, 0));
+    assert_eq!(Some(Biome::Plains), chunk.biome(15, 8, 3));
+}
+
+#[test]
+fn renames_generating_statuses_of_1_13_chunks() {
+    for (old, new) in [
+        ("base", "surface"),
+        ("carved", "carvers"),
+        ("liquid_carved", "liquid_carvers"),
+        ("decorated", "features"),
+    ] {
+        let mut nbt = chunk_1_13(vec![], vec![]);
+        if let Some(Value::Compound(level)) = nbt.get_mut("Level") {
+            level.insert("Status".to_owned(), Value::String(old.to_owned()));
+        }
+        upgrade::upgrade_chunk(&mut nbt).unwrap();
+        assert_eq!(Some(&Value::String(new.to_owned())), nbt.get("Status"));
+    }
+}
+
+#[test]
+fn pre_flattening_chunks_are_errors() {
+    let mut nbt = chunk_1_13(vec![], vec![]);
+    nbt.insert("DataVersion".to_owned(), Value::Int(1343));
+    assert!(upgrade::upgrade_chunk(&mut nbt).is_err());
+
+    nbt.remove("DataVersion");
+    assert!(upgrade::upgrade_chunk(&mut nbt).is_err());
+}
@@ -0,0 +1,47 @@
+use crate::{Chunk, ChunkStatus, JavaChunk, VersionedChunk};
+
+const CHUNK_1_17_1: &[u8] = include_bytes!("../../resources/1.17.1.chunk");
+const ETHO: &[u8] = include_bytes!("../../resources/etho.chunk");
+
+#[test]
+fn dispatch_on_data_version() {
+    let chunk = JavaChunk::from_bytes(CHUNK_1_17_1).unwrap();
+    assert!(matches!(chunk, VersionedChunk::V1_17(_)));
+    assert_eq!(chunk.as_java().unwrap().data_version, 2730);
+
+    let chunk = JavaChunk::from_bytes(ETHO).unwrap();
+    assert!(matches!(chunk, VersionedChunk::V1_16(_)));
+}
+
+#[test]
+fn delegates_to_chunk() {
+    let versioned = JavaChunk::from_bytes(CHUNK_1_17_1).unwrap();
+    let chunk: JavaChunk = fastnbt::de::from_bytes(CHUNK_1_17_1).unwrap();
+
+    assert_eq!(versioned.y_range(), chunk.y_range());
+    assert_eq!(versioned.status(), chunk.status());
+    for y in chunk.y_range() {
+        assert_eq!(
+            versioned.block(3, y, 7).map(|b| b.encoded_description()),
+            chunk.block(3, y, 7).map(|b| b.encoded_description()),
+        );
+    }
+}
+
+#[test]
+fn no_data_version_is_legacy() {
+    let mut data = vec![10, 0, 0, 10, 0, 5];
+    data.extend(b"Level");
+    for pos in ["xPos", "zPos"] {
+        data.extend([3, 0, 4]);
+        data.extend(pos.as_bytes());
+        data.extend(1i32.to_be_bytes());
+    }
+    data.extend([0, 0]);
+
+    let chunk = JavaChunk::from_bytes(&data).unwrap();
+    assert!(matches!(chunk, VersionedChunk::Legacy(_)));
+    assert!(chunk.as_java().is_none());
+    assert_eq!(chunk.status(), ChunkStatus::Empty);
+    assert!(chunk.block(0, 0, 0).is_none());
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+
+use crate::{DimensionId, World};
+
+#[derive(Serialize)]
+struct LevelFile {
+    #[serde(rename = "Data")]
+    data: Level,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Level {
+    level_name: &'static str,
+    #[serde(rename = "SpawnX")]
+    spawn_x: i32,
+    #[serde(rename = "SpawnY")]
+    spawn_y: i32,
+    #[serde(rename = "SpawnZ")]
+    spawn_z: i32,
+    data_packs: DataPacks,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct DataPacks {
+    enabled: Vec<&'static str>,
+    disabled: Vec<&'static str>,
+}
+
+fn create_world(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+    for sub in [
+        "region",
+        "DIM1/region",
+        "dimensions/mypack/mining/region",
+        "dimensions/mypack/empty",
+        "playerdata",
+        "datapacks/towers",
+        "data",
+    ] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    fs::write(dir.join("playerdata/b-uuid.dat"), []).unwrap();
+    fs::write(dir.join("playerdata/a-uuid.dat"), []).unwrap();
+    fs::write(dir.join("playerdata/a-uuid.dat_old"), []).unwrap();
+    fs::write(dir.join("datapacks/extra.zip"), []).unwrap();
+    for name in ["map_10.dat", "map_2.dat", "idcounts.dat"] {
+        fs::write(dir.join("data").join(name), []).unwrap();
+    }
+
+    let level = LevelFile {
+        data: Level {
+            level_name: "Test World",
+            spawn_x: 0,
+            spawn_y: 64,
+            spawn_z: 0,
+            data_packs: DataPacks {
+                enabled: vec!["vanilla", "file/towers"],
+                disabled: vec!["file/extra.zip"],
+            },
+        },
+    };
+    let file = fs::File::create(dir.join("level.dat")).unwrap();
+    let mut enc = GzEncoder::new(file, Compression::default());
+    fastnbt::ser::to_writer(&mut enc, &level).unwrap();
+    enc.finish().unwrap();
+}
+
+#[test]
+fn discovers_world_contents() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("fastanvil-world-{}", std::process::id()));
+    create_world(&dir);
+
+    let world = World::open(&dir).unwrap();
+    assert_eq!("Test World", world.level_dat().unwrap().level_name);
+
+    assert_eq!(
+        vec![
+            DimensionId::Overworld,
+            DimensionId::End,
+            DimensionId::Custom("mypack:mining".to_owned())
+        ],
+        world.dimensions()
+    );
+
+    assert_eq!(vec!["a-uuid", "b-uuid"], world.players());
+    assert_eq!(
+        dir.join("playerdata/a-uuid.dat"),
+        world.player_path("a-uuid")
+    );
+
+    assert_eq!(vec![2, 10], world.maps());
+    assert_eq!(dir.join("data/map_2.dat"), world.map_path(2));
+
+    let packs = world.datapacks().unwrap();
+    let packs: Vec<_> = packs.iter().map(|p| (p.name.as_str(), p.enabled)).collect();
+    assert_eq!(vec![("extra.zip", false), ("towers", true)], packs);
+
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(World::open(&dir).is_err());
+}
@@ -1,9 +1,14 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+#[cfg(feature = "resource-pack")]
+mod pack;
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "resource-pack")]
+pub use pack::*;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Variant {
     pub model: String,
@@ -60,6 +65,10 @@ pub struct Rotation {
 pub struct Face {
     texture: String,
     uv: Option<[f32; 4]>,
+
+    /// Faces with a tint index are coloured by the game, such as by the
+    /// biome for grass.
+    tintindex: Option<i32>,
 }
 
 pub type Texture = Vec<u8>; // RGBA 16x16 image.
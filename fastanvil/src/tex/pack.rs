@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use image::{ImageFormat, RgbaImage};
+
+use super::{Blockstate, Face, Model, Renderer, Texture, Variants};
+use crate::rendered_palette::tint_colour;
+use crate::{Error, RenderedPalette, Result, Rgba, Tint};
+
+/// The block models and textures of a Minecraft client jar or resource pack,
+/// read at runtime to make a [`RenderedPalette`]. This lets renders match
+/// whatever pack a player has installed, and new versions of the game,
+/// without a palette made ahead of time.
+///
+/// Resource packs usually only replace some textures, so are put on top of
+/// the client jar with [`extend`](Self::extend), like selecting them in the
+/// game.
+///
+/// ```no_run
+/// use fastanvil::tex::ResourcePack;
+///
+/// let mut pack = ResourcePack::open("versions/1.20.4/1.20.4.jar")?;
+/// pack.extend(ResourcePack::open("resourcepacks/faithful.zip")?);
+/// let palette = pack.palette()?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct ResourcePack {
+    renderer: Renderer,
+    colourmaps: HashMap<String, RgbaImage>,
+}
+
+impl ResourcePack {
+    /// An empty pack, to build up with [`extend`](Self::extend).
+    pub fn new() -> Self {
+        Self {
+            renderer: Renderer::new(HashMap::new(), HashMap::new(), HashMap::new()),
+            colourmaps: HashMap::new(),
+        }
+    }
+
+    /// Read a pack from a file, either a jar or a zip, or from a directory
+    /// the pack has been extracted to.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            let mut pack = Self::new();
+            pack.add_dir(path, "")?;
+            Ok(pack)
+        } else {
+            Self::from_zip(std::fs::File::open(path)?)
+        }
+    }
+
+    /// Read a pack from a jar or zip file.
+    pub fn from_zip(reader: impl Read + Seek) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(zip_error)?;
+        let mut pack = Self::new();
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(zip_error)?;
+            if !file.is_file() || !is_wanted(file.name()) {
+                continue;
+            }
+
+            let name = file.name().to_owned();
+            let mut data = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut data)?;
+            pack.add_file(&name, &data)?;
+        }
+
+        Ok(pack)
+    }
+
+    /// Put `pack` on top of this one. Blockstates, models and textures in
+    /// both are replaced by those of `pack`.
+    pub fn extend(&mut self, pack: ResourcePack) {
+        let renderer = pack.renderer;
+        self.renderer.blockstates.extend(renderer.blockstates);
+        self.renderer.models.extend(renderer.models);
+        self.renderer.textures.extend(renderer.textures);
+        self.colourmaps.extend(pack.colourmaps);
+    }
+
+    /// Make a palette with the average colour of the top of every block, and
+    /// of every variant of blocks whose look depends on their properties.
+    /// Blocks made of parts, like fences, take the colour of their first
+    /// part. Blocks the game tints by biome, such as grass and sugar cane,
+    /// are put in the palette's [`tints`](RenderedPalette::tints) to be tinted
+    /// as they are drawn.
+    ///
+    /// The pack must have the grass and foliage colour maps, which the client
+    /// jar has. Blocks that can't be worked out are left out of the palette.
+    pub fn palette(&self) -> Result<RenderedPalette> {
+        let colourmap = |name: &str| {
+            self.colourmaps
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Error::ResourcePack(format!("missing {} colour map", name)))
+        };
+        let grass = colourmap("minecraft:colormap/grass")?;
+        let foliage = colourmap("minecraft:colormap/foliage")?;
+
+        let mut blockstates = HashMap::new();
+        let mut tints = HashMap::new();
+        let mut cover = HashMap::new();
+        let mut insert = |description: String, look: Look| {
+            if let Some(tint) = look.tint {
+                tints.insert(description.clone(), tint);
+            }
+            if look.cover < 255 {
+                cover.insert(description.clone(), look.cover);
+            }
+            blockstates.insert(description, look.colour);
+        };
+
+        for (name, blockstate) in &self.renderer.blockstates {
+            match blockstate {
+                Blockstate::Variants(variants) => {
+                    for (props, variant) in variants {
+                        if let Some(look) = self.look(name, first_model(variant)) {
+                            // Blocks without properties are known by their
+                            // name alone.
+                            let description = if props.is_empty() {
+                                name.clone()
+                            } else {
+                                format!("{}|{}", name, props)
+                            };
+                            insert(description, look);
+                        }
+                    }
+                }
+                Blockstate::Multipart(parts) => {
+                    let look = parts
+                        .iter()
+                        .find_map(|part| self.look(name, first_model(&part.apply)));
+                    if let Some(look) = look {
+                        insert(name.clone(), look);
+                    }
+                }
+            }
+        }
+
+        Ok(RenderedPalette {
+            blockstates,
+            grass,
+            foliage,
+            tints,
+            cover,
+            fallback: None,
+        })
+    }
+
+    /// How a block drawn with the given model looks from above.
+    fn look(&self, name: &str, model: &str) -> Option<Look> {
+        let model = self.renderer.flatten_model(model).ok()?;
+        let face = top_face(&model)?;
+        let cover = top_cover(&model);
+
+        let texture = match face.texture.strip_prefix('#') {
+            Some(var) => model.textures.as_ref()?.get(var)?,
+            None => &face.texture,
+        };
+        let colour = average_colour(&self.renderer.extract_texture(texture).ok()?);
+
+        let look = |colour, tint| {
+            Some(Look {
+                colour,
+                tint,
+                cover,
+            })
+        };
+
+        if face.tintindex.is_none() {
+            return look(colour, None);
+        }
+
+        // Some blocks are tinted the same everywhere.
+        let fixed = match name {
+            "minecraft:birch_leaves" => Some([0x80, 0xa7, 0x55, 255]),
+            "minecraft:spruce_leaves" => Some([0x61, 0x99, 0x61, 255]),
+            "minecraft:lily_pad" => Some([0x20, 0x80, 0x30, 255]),
+            _ => None,
+        };
+        if let Some(tint) = fixed {
+            return look(tint_colour(colour, tint), None);
+        }
+
+        let tint = if name.ends_with("_leaves") || name.ends_with("vine") {
+            Tint::Foliage
+        } else if name.ends_with("water") || name.ends_with("water_cauldron") {
+            Tint::Water
+        } else {
+            Tint::Grass
+        };
+        look(colour, Some(tint))
+    }
+
+    fn add_dir(&mut self, dir: &Path, prefix: &str) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let name = format!("{}{}", prefix, file_name.to_string_lossy());
+
+            if entry.file_type()?.is_dir() {
+                self.add_dir(&entry.path(), &format!("{}/", name))?;
+            } else if is_wanted(&name) {
+                self.add_file(&name, &std::fs::read(entry.path())?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a file of the pack, given its path within the pack, such as
+    /// `assets/minecraft/blockstates/stone.json`.
+    fn add_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let (namespace, kind, rest) = match split_asset_path(path) {
+            Some(parts) => parts,
+            None => return Ok(()),
+        };
+
+        let json_error =
+            |e: serde_json::Error| Error::ResourcePack(format!("invalid {}: {}", path, e));
+
+        match kind {
+            "blockstates" => {
+                let name = format!("{}:{}", namespace, rest.trim_end_matches(".json"));
+                let blockstate = serde_json::from_slice(data).map_err(json_error)?;
+                self.renderer.blockstates.insert(name, blockstate);
+            }
+            "models" => {
+                let name = format!("{}:{}", namespace, rest.trim_end_matches(".json"));
+                let model = serde_json::from_slice(data).map_err(json_error)?;
+                self.renderer.models.insert(name, model);
+            }
+            "textures" => {
+                let name = format!("{}:{}", namespace, rest.trim_end_matches(".png"));
+
+                // Packs are known to have the odd broken image, which is no
+                // reason to give up on the rest.
+                let img = match image::load_from_memory_with_format(data, ImageFormat::Png) {
+                    Ok(img) => img.into_rgba8(),
+                    Err(_) => return Ok(()),
+                };
+                if rest.starts_with("colormap/") {
+                    self.colourmaps.insert(name, img);
+                } else {
+                    self.renderer.textures.insert(name, img.into_raw());
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ResourcePack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether a file in a pack is one we read, so that the rest of a jar is not
+/// decompressed.
+fn is_wanted(path: &str) -> bool {
+    match split_asset_path(path) {
+        Some((_, "blockstates", rest)) => rest.ends_with(".json"),
+        Some((_, "models", rest)) => rest.starts_with("block/") && rest.ends_with(".json"),
+        Some((_, "textures", rest)) => {
+            (rest.starts_with("block/") || rest.starts_with("colormap/")) && rest.ends_with(".png")
+        }
+        _ => false,
+    }
+}
+
+/// Split `assets/<namespace>/<kind>/<rest>` into its parts.
+fn split_asset_path(path: &str) -> Option<(&str, &str, &str)> {
+    let mut parts = path.strip_prefix("assets/")?.splitn(3, '/');
+    Some((parts.next()?, parts.next()?, parts.next()?))
+}
+
+/// What a block looks like from above, for a palette.
+struct Look {
+    colour: Rgba,
+    tint: Option<Tint>,
+    cover: u8,
+}
+
+/// The model of the first of the variants. The game picks one at random for
+/// each block, which a palette can't.
+fn first_model(variants: &Variants) -> &str {
+    match variants {
+        Variants::Single(variant) => &variant.model,
+        Variants::Many(variants) => variants.first().map_or("", |v| v.model.as_str()),
+    }
+}
+
+/// The face seen from above: the top of the first element that has one, or
+/// for models like flowers with no top, the first face of the model.
+fn top_face(model: &Model) -> Option<&Face> {
+    let elements = model.elements.as_ref()?;
+    elements
+        .iter()
+        .find_map(|el| el.faces.get("up"))
+        .or_else(|| elements.iter().find_map(|el| el.faces.values().next()))
+}
+
+/// How much of the top of a block its model covers, from the area of the
+/// elements that have a top face. Models like flowers, made of faces that
+/// cross, have no top at all.
+fn top_cover(model: &Model) -> u8 {
+    let area: f32 = model
+        .elements
+        .iter()
+        .flatten()
+        .filter(|el| el.faces.contains_key("up"))
+        .map(|el| (el.to[0] - el.from[0]).abs() * (el.to[2] - el.from[2]).abs())
+        .sum();
+
+    (area.min(256.) / 256. * 255.).round() as u8
+}
+
+/// The average of the pixels of a texture, by the root mean square of each
+/// channel as the values are roughly the square roots of brightness.
+fn average_colour(texture: &Texture) -> Rgba {
+    let mut sums = [0f64; 4];
+    let mut count = 0;
+
+    for pixel in texture.chunks_exact(4) {
+        for (sum, &c) in sums.iter_mut().zip(pixel) {
+            *sum += (c as f64).powi(2);
+        }
+        count += 1;
+    }
+
+    let mut average = [0; 4];
+    if count > 0 {
+        for (a, sum) in average.iter_mut().zip(sums) {
+            *a = (sum / count as f64).sqrt().round() as u8;
+        }
+    }
+    average
+}
+
+fn zip_error(err: zip::result::ZipError) -> Error {
+    match err {
+        zip::result::ZipError::Io(err) => Error::IO(err),
+        err => Error::ResourcePack(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use image::codecs::png::PngEncoder;
+    use image::ColorType;
+    use zip::write::FileOptions;
+
+    use super::*;
+    use crate::biome::Biome;
+    use crate::{Block, Palette};
+
+    fn png(colour: Rgba, size: u32) -> Vec<u8> {
+        let img = RgbaImage::from_pixel(size, size, image::Rgba(colour));
+        let mut data = Vec::new();
+        PngEncoder::new(&mut data)
+            .encode(&img, size, size, ColorType::Rgba8)
+            .unwrap();
+        data
+    }
+
+    fn zip(files: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn model(texture: &str, face: &str, tinted: bool) -> Vec<u8> {
+        let tint = if tinted { r#", "tintindex": 0"# } else { "" };
+        format!(
+            r##"{{
+                "textures": {{ "all": "{}" }},
+                "elements": [{{
+                    "from": [0, 0, 0],
+                    "to": [16, 16, 16],
+                    "faces": {{ "{}": {{ "texture": "#all"{} }} }}
+                }}]
+            }}"##,
+            texture, face, tint
+        )
+        .into_bytes()
+    }
+
+    fn vanilla() -> ResourcePack {
+        ResourcePack::from_zip(Cursor::new(zip(&[
+            (
+                "assets/minecraft/blockstates/stone.json",
+                br#"{ "variants": { "": { "model": "minecraft:block/stone" } } }"#.to_vec(),
+            ),
+            (
+                "assets/minecraft/blockstates/sugar_cane.json",
+                br#"{ "variants": { "age=0": { "model": "block/sugar_cane" } } }"#.to_vec(),
+            ),
+            (
+                "assets/minecraft/models/block/stone.json",
+                model("minecraft:block/stone", "up", false),
+            ),
+            (
+                "assets/minecraft/models/block/sugar_cane.json",
+                model("block/sugar_cane", "north", true),
+            ),
+            (
+                "assets/minecraft/textures/block/stone.png",
+                png([100, 100, 100, 255], 16),
+            ),
+            (
+                "assets/minecraft/textures/block/sugar_cane.png",
+                png([255, 255, 255, 255], 16),
+            ),
+            (
+                "assets/minecraft/textures/colormap/grass.png",
+                png([0, 200, 0, 255], 256),
+            ),
+            (
+                "assets/minecraft/textures/colormap/foliage.png",
+                png([0, 100, 0, 255], 256),
+            ),
+            ("assets/minecraft/sounds.json", b"{}".to_vec()),
+        ])))
+        .unwrap()
+    }
+
+    #[test]
+    fn palette_from_jar() {
+        let palette = vanilla().palette().unwrap();
+
+        assert_eq!(
+            Some(&[100, 100, 100, 255]),
+            palette.blockstates.get("minecraft:stone")
+        );
+        // Sugar cane has no top, and is tinted like grass.
+        assert_eq!(
+            Some(&[255, 255, 255, 255]),
+            palette.blockstates.get("minecraft:sugar_cane|age=0")
+        );
+        assert_eq!(
+            Some(&Tint::Grass),
+            palette.tints.get("minecraft:sugar_cane|age=0")
+        );
+        let cane = Block::from_state_str("minecraft:sugar_cane[age=0]");
+        assert_eq!([0, 200, 0, 255], palette.pick(&cane, Some(Biome::Forest)));
+
+        // With no top, it covers nothing below it.
+        assert_eq!(0, palette.cover(&cane));
+
+        let stone = Block::new("minecraft:stone".to_owned(), Default::default());
+        assert_eq!([100, 100, 100, 255], palette.pick(&stone, None));
+        assert_eq!(255, palette.cover(&stone));
+    }
+
+    #[test]
+    fn packs_replace_textures() {
+        let mut pack = vanilla();
+        pack.extend(
+            ResourcePack::from_zip(Cursor::new(zip(&[(
+                "assets/minecraft/textures/block/stone.png",
+                png([10, 20, 30, 255], 32),
+            )])))
+            .unwrap(),
+        );
+
+        let palette = pack.palette().unwrap();
+        assert_eq!(
+            Some(&[10, 20, 30, 255]),
+            palette.blockstates.get("minecraft:stone")
+        );
+    }
+
+    #[test]
+    fn colour_maps_required() {
+        let pack = ResourcePack::new();
+        assert!(matches!(pack.palette(), Err(Error::ResourcePack(_))));
+    }
+}
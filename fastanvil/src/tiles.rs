@@ -0,0 +1,474 @@
+//! Slippy-map tile pyramids, for showing renders of a world in web maps such
+//! as Leaflet.
+//!
+//! Tiles are written to `<dir>/<zoom>/<x>/<y>.png`. The most detailed zoom
+//! level has one tile per region, with a pixel per block, so tile `x`, `y` is
+//! region `r.x.y.mca`. Each level below it halves the resolution, combining
+//! four tiles into one. Coordinates can be negative, so the map should use a
+//! flat coordinate system such as Leaflet's `CRS.Simple`, with a tile size of
+//! [`TILE_SIZE`].
+//!
+//! ```no_run
+//! use fastanvil::tiles::TilePyramid;
+//! use fastanvil::{Dimension, HeightMode, JavaChunk, RegionFileLoader, RegionLoader, TopShadeRenderer};
+//!
+//! # fn palette() -> fastanvil::RenderedPalette { unimplemented!() }
+//! let palette = palette();
+//! let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+//! let regions = loader.list().unwrap();
+//! let dimension = Dimension::with_region_capacity(loader, 64);
+//!
+//! let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
+//! TilePyramid::new("map", 5).render(&dimension, &regions, &renderer)?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+//!
+//! To keep a map up to date without rendering the whole world again, save a
+//! [`RenderManifest`] of chunk timestamps with each render. Next time, only
+//! the regions that have changed since need rendering.
+//!
+//! ```no_run
+//! # use fastanvil::tiles::{RenderManifest, TilePyramid};
+//! # use fastanvil::{Dimension, HeightMode, JavaChunk, RegionFileLoader, TopShadeRenderer};
+//! # fn palette() -> fastanvil::RenderedPalette { unimplemented!() }
+//! # let palette = palette();
+//! # let renderer = TopShadeRenderer::new(&palette, HeightMode::Trust);
+//! let previous = RenderManifest::load("map/manifest.nbt").unwrap_or_default();
+//! let current = RenderManifest::scan("world/region")?;
+//!
+//! let loader = RegionFileLoader::<JavaChunk>::new("world/region".into());
+//! let dimension = Dimension::with_region_capacity(loader, 64);
+//! let changed = previous.changed_regions(&current);
+//! TilePyramid::new("map", 5).render(&dimension, &changed, &renderer)?;
+//!
+//! current.save("map/manifest.nbt")?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use fastnbt::IntArray;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::files::{list_regions, try_open_region};
+use crate::{render_region_from, Chunk, ChunkRenderer, Dimension, RCoord, RegionLoader};
+use crate::{Error, IndexedImage, Progress, RegionMap, Result, Rgba};
+
+/// The width and height of a tile in pixels, which is the width of a region
+/// in blocks.
+pub const TILE_SIZE: u32 = 512;
+
+/// A tile pyramid in a directory.
+pub struct TilePyramid {
+    dir: PathBuf,
+    levels: u32,
+    indexed: bool,
+}
+
+impl TilePyramid {
+    /// A pyramid with `levels` zoom levels, numbered from 0 for the least
+    /// detailed. There is always at least one level.
+    pub fn new(dir: impl Into<PathBuf>, levels: u32) -> Self {
+        Self {
+            dir: dir.into(),
+            levels: levels.max(1),
+            indexed: false,
+        }
+    }
+
+    /// Write tiles as indexed PNGs of at most 256 colours rather than RGBA,
+    /// making them around a quarter of the size at a small cost to quality.
+    /// See [`IndexedImage`].
+    pub fn indexed(mut self, indexed: bool) -> Self {
+        self.indexed = indexed;
+        self
+    }
+
+    /// The most detailed zoom level, where each tile is a region.
+    pub fn max_zoom(&self) -> u32 {
+        self.levels - 1
+    }
+
+    /// The path of a tile.
+    pub fn tile_path(&self, zoom: u32, x: isize, y: isize) -> PathBuf {
+        self.dir
+            .join(zoom.to_string())
+            .join(x.to_string())
+            .join(format!("{}.png", y))
+    }
+
+    /// Render the given regions of a dimension and write the whole pyramid.
+    /// Regions are rendered one at a time, so a dimension with a limited
+    /// region cache keeps memory use down for large worlds.
+    pub fn render<C, R, T>(
+        &self,
+        dimension: &Dimension<C, R>,
+        regions: &[(RCoord, RCoord)],
+        renderer: &T,
+    ) -> Result<()>
+    where
+        C: Chunk + Debug,
+        R: RegionLoader<C>,
+        T: ChunkRenderer,
+    {
+        self.render_with_progress(dimension, regions, renderer, &mut ())
+    }
+
+    /// [`render`](Self::render), reporting each region to `progress` once
+    /// its tile has been written. Regions whose tile can't be written are
+    /// passed to [`Progress::error`]. Building the less detailed zoom levels
+    /// happens after [`Progress::finish`].
+    pub fn render_with_progress<C, R, T>(
+        &self,
+        dimension: &Dimension<C, R>,
+        regions: &[(RCoord, RCoord)],
+        renderer: &T,
+        progress: &mut impl Progress,
+    ) -> Result<()>
+    where
+        C: Chunk + Debug,
+        R: RegionLoader<C>,
+        T: ChunkRenderer,
+    {
+        progress.start(regions.len());
+
+        for &(x, z) in regions {
+            let (map, chunks) = render_region_from(x, z, dimension, renderer);
+            match self.write_region(&map) {
+                Ok(()) => progress.region_done(x, z, chunks),
+                Err(e) => progress.error(x, z, e)?,
+            }
+        }
+
+        progress.finish();
+        self.build_zoom_levels(regions)
+    }
+
+    /// Write a rendered region as a tile of the most detailed zoom level.
+    pub fn write_region(&self, map: &RegionMap<Rgba>) -> Result<()> {
+        self.save(self.max_zoom(), map.x.0, map.z.0, &map.to_image())
+    }
+
+    /// Write the tiles of the less detailed zoom levels that contain the
+    /// given regions, from tiles already written with
+    /// [`write_region`](Self::write_region). Tiles of other regions written
+    /// before are included, so only regions that have changed need to be
+    /// given.
+    pub fn build_zoom_levels(&self, regions: &[(RCoord, RCoord)]) -> Result<()> {
+        let mut tiles: BTreeSet<(isize, isize)> = regions.iter().map(|(x, z)| (x.0, z.0)).collect();
+
+        for zoom in (0..self.max_zoom()).rev() {
+            let parents: BTreeSet<_> = tiles
+                .iter()
+                .map(|(x, y)| (x.div_euclid(2), y.div_euclid(2)))
+                .collect();
+
+            for &(x, y) in &parents {
+                let mut tile = RgbaImage::new(TILE_SIZE, TILE_SIZE);
+
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let path = self.tile_path(zoom + 1, 2 * x + dx, 2 * y + dy);
+                    if !path.exists() {
+                        continue;
+                    }
+
+                    let child = image::open(path)?.into_rgba8();
+                    let half = TILE_SIZE / 2;
+                    downsample_into(&child, &mut tile, dx as u32 * half, dy as u32 * half);
+                }
+
+                self.save(zoom, x, y, &tile)?;
+            }
+
+            tiles = parents;
+        }
+
+        Ok(())
+    }
+
+    fn save(&self, zoom: u32, x: isize, y: isize, tile: &RgbaImage) -> Result<()> {
+        let path = self.tile_path(zoom, x, y);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        if self.indexed {
+            IndexedImage::quantize(tile, 256).save_png(path)
+        } else {
+            tile.save(path)?;
+            Ok(())
+        }
+    }
+}
+
+/// The chunk timestamps of a dimension when it was rendered, for working out
+/// what needs rendering again. Saved as NBT.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderManifest {
+    regions: BTreeMap<(isize, isize), Vec<u32>>,
+}
+
+/// How a [`RenderManifest`] is stored.
+#[derive(Serialize, Deserialize)]
+struct ManifestNbt {
+    #[serde(rename = "Regions")]
+    regions: Vec<ManifestRegion>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestRegion {
+    #[serde(rename = "X")]
+    x: i32,
+    #[serde(rename = "Z")]
+    z: i32,
+    #[serde(rename = "Timestamps")]
+    timestamps: IntArray,
+}
+
+impl RenderManifest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the chunk timestamps of every region file in a directory, such
+    /// as `world/region`. Only the region headers are read.
+    pub fn scan(region_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::scan_with_progress(region_dir, &mut ())
+    }
+
+    /// [`scan`](Self::scan), reporting each region to `progress` with the
+    /// number of chunks it has. Regions that can't be opened or whose header
+    /// can't be read are passed to [`Progress::error`], and left out of the
+    /// manifest if skipped.
+    pub fn scan_with_progress(
+        region_dir: impl AsRef<Path>,
+        progress: &mut impl Progress,
+    ) -> Result<Self> {
+        let dir = region_dir.as_ref();
+        let regions = list_regions(dir)?;
+        progress.start(regions.len());
+
+        let mut manifest = Self::new();
+        for (x, z) in regions {
+            let header = try_open_region(dir, x, z)
+                .map_err(Error::from)
+                .and_then(|region| Ok((region.chunk_timestamps()?, region.locations()?.len())));
+
+            match header {
+                Ok((timestamps, chunks)) => {
+                    manifest.record(x, z, timestamps);
+                    progress.region_done(x, z, chunks);
+                }
+                Err(e) => progress.error(x, z, e)?,
+            }
+        }
+
+        progress.finish();
+        Ok(manifest)
+    }
+
+    /// Load a manifest saved with [`save`](Self::save).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let nbt: ManifestNbt = fastnbt::de::from_reader(file)?;
+
+        let regions = nbt
+            .regions
+            .into_iter()
+            .map(|r| {
+                let timestamps = r.timestamps.iter().map(|&t| t as u32).collect();
+                ((r.x as isize, r.z as isize), timestamps)
+            })
+            .collect();
+
+        Ok(Self { regions })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let nbt = ManifestNbt {
+            regions: self
+                .regions
+                .iter()
+                .map(|(&(x, z), timestamps)| ManifestRegion {
+                    x: x as i32,
+                    z: z as i32,
+                    timestamps: IntArray::new(timestamps.iter().map(|&t| t as i32).collect()),
+                })
+                .collect(),
+        };
+
+        let file = BufWriter::new(File::create(path)?);
+        fastnbt::ser::to_writer(file, &nbt).map_err(Error::from)
+    }
+
+    /// Record the chunk timestamps of a region, as given by
+    /// [`RegionBuffer::chunk_timestamps`](crate::RegionBuffer::chunk_timestamps).
+    pub fn record(&mut self, x: RCoord, z: RCoord, timestamps: Vec<u32>) {
+        self.regions.insert((x.0, z.0), timestamps);
+    }
+
+    /// The chunk timestamps of a region, if it is in the manifest.
+    pub fn timestamps(&self, x: RCoord, z: RCoord) -> Option<&[u32]> {
+        self.regions.get(&(x.0, z.0)).map(|t| t.as_slice())
+    }
+
+    /// The regions that are new, removed, or have any chunk changed in
+    /// `current` compared to this manifest.
+    pub fn changed_regions(&self, current: &RenderManifest) -> Vec<(RCoord, RCoord)> {
+        let keys: BTreeSet<_> = self.regions.keys().chain(current.regions.keys()).collect();
+
+        keys.into_iter()
+            .filter(|key| self.regions.get(key) != current.regions.get(key))
+            .map(|&(x, z)| (RCoord(x), RCoord(z)))
+            .collect()
+    }
+
+    /// The region-relative coordinates of chunks in a region that have
+    /// changed in `current` compared to this manifest. Every present chunk
+    /// of a region new to `current` counts as changed.
+    pub fn changed_chunks(
+        &self,
+        current: &RenderManifest,
+        x: RCoord,
+        z: RCoord,
+    ) -> Vec<(usize, usize)> {
+        let before = self.timestamps(x, z).unwrap_or(&[]);
+        let after = current.timestamps(x, z).unwrap_or(&[]);
+
+        (0..32 * 32)
+            .filter(|&i| before.get(i).unwrap_or(&0) != after.get(i).unwrap_or(&0))
+            .map(|i| (i % 32, i / 32))
+            .collect()
+    }
+}
+
+/// Shrink an image to half its size, writing it into `dest` with its top
+/// left corner at `x`, `y`. Each pixel is the average of four, weighted by
+/// their alpha so that transparent pixels do not darken edges.
+fn downsample_into(src: &RgbaImage, dest: &mut RgbaImage, x: u32, y: u32) {
+    for j in 0..src.height() / 2 {
+        for i in 0..src.width() / 2 {
+            let mut sum = [0u32; 3];
+            let mut alpha = 0u32;
+
+            for (di, dj) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let p = src.get_pixel(2 * i + di, 2 * j + dj).0;
+                let a = p[3] as u32;
+                for c in 0..3 {
+                    sum[c] += p[c] as u32 * a;
+                }
+                alpha += a;
+            }
+
+            let pixel = match alpha {
+                0 => [0, 0, 0, 0],
+                _ => [
+                    (sum[0] / alpha) as u8,
+                    (sum[1] / alpha) as u8,
+                    (sum[2] / alpha) as u8,
+                    (alpha / 4) as u8,
+                ],
+            };
+            dest.put_pixel(x + i, y + j, image::Rgba(pixel));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsample_weights_alpha() {
+        let mut src = RgbaImage::new(2, 2);
+        src.put_pixel(0, 0, image::Rgba([200, 100, 0, 255]));
+        src.put_pixel(1, 1, image::Rgba([100, 0, 0, 255]));
+
+        let mut dest = RgbaImage::new(2, 2);
+        downsample_into(&src, &mut dest, 1, 0);
+        assert_eq!([150, 50, 0, 127], dest.get_pixel(1, 0).0);
+        assert_eq!([0, 0, 0, 0], dest.get_pixel(0, 0).0);
+    }
+
+    #[test]
+    fn builds_zoom_levels() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("fastanvil-tiles-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let pyramid = TilePyramid::new(&dir, 3);
+
+        let mut map = RegionMap::new(RCoord(-1), RCoord(0), [0u8; 4]);
+        map.data[0] = [10, 20, 30, 255];
+        pyramid.write_region(&map)?;
+        pyramid.write_region(&RegionMap::new(RCoord(0), RCoord(0), [0u8; 4]))?;
+
+        pyramid.build_zoom_levels(&[(RCoord(-1), RCoord(0)), (RCoord(0), RCoord(0))])?;
+
+        assert!(pyramid.tile_path(2, -1, 0).exists());
+        assert!(pyramid.tile_path(1, -1, 0).exists());
+        assert!(pyramid.tile_path(1, 0, 0).exists());
+        assert!(!pyramid.tile_path(1, 0, 1).exists());
+
+        // Region -1 is the right half of tile -1 at zoom 1, which is the
+        // right half of tile -1 at zoom 0.
+        let tile = image::open(pyramid.tile_path(1, -1, 0))
+            .unwrap()
+            .into_rgba8();
+        assert_eq!([10, 20, 30, 63], tile.get_pixel(256, 0).0);
+        let tile = image::open(pyramid.tile_path(0, -1, 0))
+            .unwrap()
+            .into_rgba8();
+        assert_eq!([10, 20, 30, 15], tile.get_pixel(384, 0).0);
+
+        // Rebuilding for one region keeps its neighbour.
+        pyramid.build_zoom_levels(&[(RCoord(0), RCoord(0))])?;
+        let tile = image::open(pyramid.tile_path(0, -1, 0))
+            .unwrap()
+            .into_rgba8();
+        assert_eq!([10, 20, 30, 15], tile.get_pixel(384, 0).0);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn manifest_changes() -> Result<()> {
+        let mut before = RenderManifest::new();
+        before.record(RCoord(0), RCoord(0), vec![10; 1024]);
+        before.record(RCoord(1), RCoord(0), vec![10; 1024]);
+        before.record(RCoord(2), RCoord(0), vec![10; 1024]);
+
+        let mut after = before.clone();
+        let mut timestamps = vec![10; 1024];
+        timestamps[3 + 2 * 32] = 20;
+        after.record(RCoord(0), RCoord(0), timestamps);
+        after.regions.remove(&(1, 0));
+        after.record(RCoord(-1), RCoord(0), vec![0; 1024]);
+
+        assert_eq!(
+            vec![
+                (RCoord(-1), RCoord(0)),
+                (RCoord(0), RCoord(0)),
+                (RCoord(1), RCoord(0))
+            ],
+            before.changed_regions(&after)
+        );
+        assert_eq!(
+            vec![(3, 2)],
+            before.changed_chunks(&after, RCoord(0), RCoord(0))
+        );
+        assert!(before
+            .changed_chunks(&after, RCoord(-1), RCoord(0))
+            .is_empty());
+
+        let path =
+            std::env::temp_dir().join(format!("fastanvil-manifest-{}.nbt", std::process::id()));
+        after.save(&path)?;
+        assert_eq!(after, RenderManifest::load(&path)?);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+//! Upgrade the NBT of chunks from 1.13 to 1.17 to the layout used since
+//! 1.18, so tools only have to handle one layout. This is a small subset of
+//! what Minecraft's DataFixerUpper does when it loads an old chunk:
+//!
+//! * The contents of the `Level` tag are moved to the root of the chunk, and
+//!   tags renamed in 1.18 get their new names.
+//! * Block states and palettes of each section are moved into a
+//!   `block_states` compound, with block states repacked in the padded format
+//!   used since 1.16.
+//! * The biome array of the chunk is split into a palette of biome names per
+//!   section. Biomes removed in 1.18 are replaced as the game does.
+//! * Blocks renamed since 1.13 are given their new names, see
+//!   [`remap_block`].
+//!
+//! Chunks are not upgraded any further than 1.18, and things like entities,
+//! block entity contents and lighting are left as they are. Heightmaps from
+//! before 1.16 are dropped, as they are packed differently; they are
+//! recalculated when needed. Worlds are assumed to start at Y 0, so 1.17
+//! chunks from worlds with custom heights below 0 lose their lower biomes.
+//!
+//! ```no_run
+//! use fastanvil::{upgrade, JavaChunk, RegionBuffer};
+//!
+//! let file = std::fs::File::open("r.0.0.mca")?;
+//! let region = RegionBuffer::new(file);
+//!
+//! let data = upgrade::upgrade_chunk_bytes(&region.load_chunk(0, 0)?)?;
+//! let chunk: JavaChunk = fastnbt::de::from_bytes(&data)?;
+//! # Ok::<(), fastanvil::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use fastnbt::Value;
+
+use crate::biome::Biome;
+use crate::java::{FLATTENING, V1_16, V1_18};
+use crate::{bits_per_block, remap_block, ChunkNbt, Error, PackedBits, PackingFormat, Result};
+
+/// The `DataVersion` given to upgraded chunks, that of 1.18.2.
+pub const UPGRADED_DATA_VERSION: i32 = 2975;
+
+/// Tags of `Level` that were renamed when they moved to the root.
+const TAG_RENAMES: &[(&str, &str)] = &[
+    ("TileEntities", "block_entities"),
+    ("Structures", "structures"),
+    ("TileTicks", "block_ticks"),
+    ("LiquidTicks", "fluid_ticks"),
+];
+
+/// Statuses of chunks from 1.13, which were renamed in 1.14.
+const STATUS_RENAMES: &[(&str, &str)] = &[
+    ("postprocessed", "full"),
+    ("fullchunk", "full"),
+    ("mobs_spawned", "spawn"),
+    ("finalized", "heightmaps"),
+    ("lighted", "light"),
+    ("decorated", "features"),
+    ("base", "surface"),
+    ("carved", "carvers"),
+    ("liquid_carved", "liquid_carvers"),
+];
+
+fn error(msg: &str) -> Error {
+    Error::Upgrade(msg.to_owned())
+}
+
+/// Upgrade the NBT of a chunk in place to the layout used since 1.18.
+/// Returns whether the chunk was changed, chunks from 1.18 onwards are left
+/// alone.
+///
+/// Chunks from before 1.13 have numeric block IDs and cannot be upgraded,
+/// they give [`Error::Upgrade`], as do chunks missing required tags.
+pub fn upgrade_chunk(nbt: &mut ChunkNbt) -> Result<bool> {
+    let version = match nbt.get("DataVersion") {
+        Some(Value::Int(v)) => *v,
+        _ => return Err(error("chunks from before 1.13 cannot be upgraded")),
+    };
+
+    if version >= V1_18 {
+        return Ok(false);
+    }
+    if version < FLATTENING {
+        return Err(error("chunks from before 1.13 cannot be upgraded"));
+    }
+
+    let mut level = match nbt.remove("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => return Err(error("chunk has no Level tag")),
+    };
+
+    let biomes = match level.remove("Biomes") {
+        Some(Value::IntArray(biomes)) => Some(biomes.into_inner()),
+        _ => None,
+    };
+
+    let sections = match level.remove("Sections") {
+        Some(Value::List(sections)) => sections,
+        _ => Vec::new(),
+    };
+
+    // Heightmaps spanned longs before 1.16, the same as block states.
+    if version < V1_16 {
+        level.remove("Heightmaps");
+    }
+
+    if let Some(Value::String(status)) = level.get_mut("Status") {
+        if let Some((_, new)) = STATUS_RENAMES.iter().find(|(old, _)| old == status) {
+            *status = (*new).to_owned();
+        }
+    }
+
+    for (mut key, value) in level {
+        if let Some((_, new)) = TAG_RENAMES.iter().find(|(old, _)| *old == key) {
+            key = (*new).to_owned();
+        }
+        nbt.insert(key, value);
+    }
+
+    let sections = sections
+        .into_iter()
+        .map(|section| match section {
+            Value::Compound(section) => upgrade_section(section, version, biomes.as_deref()),
+            _ => Err(error("section is not a compound")),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    nbt.insert("yPos".to_owned(), Value::Int(0));
+    nbt.insert("sections".to_owned(), Value::List(sections));
+    nbt.insert("DataVersion".to_owned(), Value::Int(UPGRADED_DATA_VERSION));
+
+    Ok(true)
+}
+
+/// Upgrade the NBT of a chunk given as bytes, such as from
+/// [`RegionBuffer::load_chunk`](crate::RegionBuffer::load_chunk). Chunks
+/// that need no upgrade are still re-serialized.
+pub fn upgrade_chunk_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    let mut nbt: ChunkNbt = fastnbt::de::from_bytes(data)?;
+    upgrade_chunk(&mut nbt)?;
+    Ok(fastnbt::ser::to_bytes(&nbt)?)
+}
+
+/// The name of a block after the renames up to 1.18, for a block from a
+/// chunk with the given DataVersion. This is [`remap_block`] to
+/// [`UPGRADED_DATA_VERSION`].
+pub fn upgraded_block_name(name: &str, data_version: i32) -> &str {
+    remap_block(name, data_version, UPGRADED_DATA_VERSION)
+}
+
+fn upgrade_section(
+    mut section: HashMap<String, Value>,
+    version: i32,
+    biomes: Option<&[i32]>,
+) -> Result<Value> {
+    let y = match section.get("Y") {
+        Some(Value::Byte(y)) => *y,
+        _ => return Err(error("section has no Y")),
+    };
+
+    let states = match section.remove("BlockStates") {
+        Some(Value::LongArray(states)) => Some(states),
+        _ => None,
+    };
+
+    let mut palette = match section.remove("Palette") {
+        Some(Value::List(palette)) => palette,
+        _ => Vec::new(),
+    };
+
+    // Sections in the world without a palette are all air, since 1.18 they
+    // have a palette of just air. Sections outside it only hold light.
+    let in_world = y >= 0
+        && match biomes {
+            Some(biomes) if biomes.len() != 16 * 16 => (y as usize) < biomes.len() / 64,
+            _ => y < 16,
+        };
+
+    if palette.is_empty() && in_world {
+        let mut air = HashMap::new();
+        air.insert("Name".to_owned(), Value::String("minecraft:air".to_owned()));
+        palette.push(Value::Compound(air));
+    }
+
+    if !palette.is_empty() {
+        for block in palette.iter_mut() {
+            if let Value::Compound(block) = block {
+                if let Some(Value::String(name)) = block.get_mut("Name") {
+                    *name = upgraded_block_name(name, version).to_owned();
+                }
+            }
+        }
+
+        let mut block_states = HashMap::new();
+        if palette.len() > 1 {
+            let states = states.ok_or_else(|| error("section has no block states"))?;
+            // Some chunks from 1.16 still have sections packed the old way,
+            // so the format is worked out from the length.
+            let mut indices = [0; 16 * 16 * 16];
+            PackedBits(states).unpack_blockstates(bits_per_block(palette.len()), &mut indices);
+            let packed = PackedBits::pack_blockstates(&indices, palette.len());
+            block_states.insert("data".to_owned(), Value::LongArray(packed.0));
+        }
+        block_states.insert("palette".to_owned(), Value::List(palette));
+
+        section.insert("block_states".to_owned(), Value::Compound(block_states));
+    }
+
+    if in_world {
+        if let Some(biomes) = biomes.and_then(|biomes| section_biomes(biomes, y)) {
+            section.insert("biomes".to_owned(), biomes);
+        }
+    }
+
+    Ok(Value::Compound(section))
+}
+
+/// The biomes of the section at `y`, from the biome array of a chunk from
+/// before 1.18. Before 1.15 this held a biome per column, after it a biome
+/// for each 4x4x4 cell starting from the bottom of the world.
+fn section_biomes(biomes: &[i32], y: i8) -> Option<Value> {
+    let cells: Vec<i32> = if biomes.len() == 16 * 16 {
+        (0..64)
+            .map(|i| {
+                let (x, z) = (i % 4 * 4, i / 4 % 4 * 4);
+                biomes[z * 16 + x]
+            })
+            .collect()
+    } else {
+        let start = y as usize * 64;
+        biomes.get(start..start + 64)?.to_vec()
+    };
+
+    let mut palette: Vec<&str> = Vec::new();
+    let indices: Vec<u16> = cells
+        .into_iter()
+        .map(|id| {
+            let name = upgraded_biome_name(id);
+            match palette.iter().position(|&n| n == name) {
+                Some(i) => i as u16,
+                None => {
+                    palette.push(name);
+                    (palette.len() - 1) as u16
+                }
+            }
+        })
+        .collect();
+
+    let mut compound = HashMap::new();
+    if palette.len() > 1 {
+        let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+        let packed = PackedBits::pack(&indices, bits, PackingFormat::Padded);
+        compound.insert("data".to_owned(), Value::LongArray(packed.0));
+    }
+    compound.insert(
+        "palette".to_owned(),
+        Value::List(
+            palette
+                .into_iter()
+                .map(|name| Value::String(name.to_owned()))
+                .collect(),
+        ),
+    );
+
+    Some(Value::Compound(compound))
+}
+
+/// The name of the biome with a numeric ID from before 1.18, as it is known
+/// since 1.18. Biomes that were removed are replaced the way the game does,
+/// and unknown IDs become plains.
+pub fn upgraded_biome_name(id: i32) -> &'static str {
+    use Biome::*;
+
+    let biome = match Biome::try_from(id) {
+        Ok(biome) => biome,
+        Err(_) => return "minecraft:plains",
+    };
+
+    match biome {
+        Ocean => "minecraft:ocean",
+        DeepOcean => "minecraft:deep_ocean",
+        FrozenOcean => "minecraft:frozen_ocean",
+        DeepFrozenOcean => "minecraft:deep_frozen_ocean",
+        ColdOcean => "minecraft:cold_ocean",
+        DeepColdOcean => "minecraft:deep_cold_ocean",
+        LukewarmOcean => "minecraft:lukewarm_ocean",
+        DeepLukewarmOcean | DeepWarmOcean => "minecraft:deep_lukewarm_ocean",
+        WarmOcean => "minecraft:warm_ocean",
+        River => "minecraft:river",
+        FrozenRiver => "minecraft:frozen_river",
+        Beach => "minecraft:beach",
+        StoneShore => "minecraft:stony_shore",
+        SnowyBeach => "minecraft:snowy_beach",
+        Forest | WoodedHills => "minecraft:forest",
+        FlowerForest => "minecraft:flower_forest",
+        BirchForest | BirchForestHills => "minecraft:birch_forest",
+        TallBirchForest | TallBirchHills => "minecraft:old_growth_birch_forest",
+        DarkForest | DarkForestHills => "minecraft:dark_forest",
+        Jungle | JungleHills | ModifiedJungle => "minecraft:jungle",
+        JungleEdge | ModifiedJungleEdge => "minecraft:sparse_jungle",
+        BambooJungle | BambooJungleHills => "minecraft:bamboo_jungle",
+        Taiga | TaigaHills | TaigaMountains => "minecraft:taiga",
+        SnowyTaiga | SnowyTaigaHills | SnowyTaigaMountains => "minecraft:snowy_taiga",
+        GiantTreeTaiga | GiantTreeTaigaHills => "minecraft:old_growth_pine_taiga",
+        GiantSpruceTaiga | GiantSpruceTaigaHills => "minecraft:old_growth_spruce_taiga",
+        MushroomFields | MushroomFieldShore => "minecraft:mushroom_fields",
+        Swamp | SwampHills => "minecraft:swamp",
+        Savanna => "minecraft:savanna",
+        SavannaPlateau => "minecraft:savanna_plateau",
+        ShatteredSavanna | ShatteredSavannaPlateau => "minecraft:windswept_savanna",
+        Plains => "minecraft:plains",
+        SunflowerPlains => "minecraft:sunflower_plains",
+        Desert | DesertHills | DesertLakes => "minecraft:desert",
+        SnowyTundra | SnowyMountains => "minecraft:snowy_plains",
+        IceSpikes => "minecraft:ice_spikes",
+        Mountains | MountainEdge => "minecraft:windswept_hills",
+        WoodedMountains => "minecraft:windswept_forest",
+        GravellyMountains | ModifiedGravellyMountains => "minecraft:windswept_gravelly_hills",
+        Badlands | BadlandsPlateau | ModifiedBadlandsPlateau => "minecraft:badlands",
+        WoodedBadlandsPlateau | ModifiedWoodedBadlandsPlateau => "minecraft:wooded_badlands",
+        ErodedBadlands => "minecraft:eroded_badlands",
+        Nether => "minecraft:nether_wastes",
+        TheEnd => "minecraft:the_end",
+        SmallEndIslands => "minecraft:small_end_islands",
+        EndMidlands => "minecraft:end_midlands",
+        EndHighlands => "minecraft:end_highlands",
+        EndBarrens => "minecraft:end_barrens",
+        SoulSandValley => "minecraft:soul_sand_valley",
+        CrimsonForest => "minecraft:crimson_forest",
+        WarpedForest => "minecraft:warped_forest",
+        BasaltDeltas => "minecraft:basalt_deltas",
+        TheVoid => "minecraft:the_void",
+        _ => "minecraft:plains",
+    }
+}
@@ -0,0 +1,319 @@
+use std::convert::TryFrom;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{ChunkLocation, CompressionScheme, RegionBuffer, Result, HEADER_SIZE, SECTOR_SIZE};
+
+/// A problem found in a region by [`RegionBuffer::validate`]. Chunk
+/// coordinates are region-relative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionProblem {
+    /// The region is too short to contain a full header. No other checks can
+    /// be done.
+    TruncatedHeader { len: u64 },
+
+    /// The chunk's sectors start inside the header.
+    OverlapsHeader { x: usize, z: usize },
+
+    /// The chunk's sectors overlap those of another chunk, `other_x` and
+    /// `other_z`, which starts earlier in the file.
+    Overlapping {
+        x: usize,
+        z: usize,
+        other_x: usize,
+        other_z: usize,
+    },
+
+    /// The chunk's sectors extend past the end of the file.
+    PastEndOfFile { x: usize, z: usize },
+
+    /// The length stored at the start of the chunk is longer than the sectors
+    /// allocated to it, or is zero.
+    BadLength {
+        x: usize,
+        z: usize,
+        length: u32,
+        sector_count: usize,
+    },
+
+    /// The compression scheme byte of the chunk is not recognised.
+    BadCompression { x: usize, z: usize, scheme: u8 },
+}
+
+impl RegionProblem {
+    /// The region-relative coordinates of the chunk with the problem, if the
+    /// problem is with a single chunk.
+    pub fn chunk(&self) -> Option<(usize, usize)> {
+        match *self {
+            RegionProblem::TruncatedHeader { .. } => None,
+            RegionProblem::OverlapsHeader { x, z }
+            | RegionProblem::Overlapping { x, z, .. }
+            | RegionProblem::PastEndOfFile { x, z }
+            | RegionProblem::BadLength { x, z, .. }
+            | RegionProblem::BadCompression { x, z, .. } => Some((x, z)),
+        }
+    }
+}
+
+/// The result of [`RegionBuffer::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RegionReport {
+    /// The number of chunks the header says are present.
+    pub chunks: usize,
+    pub problems: Vec<RegionProblem>,
+}
+
+impl RegionReport {
+    /// Whether no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// The region-relative coordinates of every chunk with a problem, without
+    /// duplicates. For overlapping chunks both chunks are included, since
+    /// there is no way to tell which was written last.
+    pub fn corrupt_chunks(&self) -> Vec<(usize, usize)> {
+        let mut chunks = Vec::new();
+        for problem in &self.problems {
+            if let RegionProblem::Overlapping {
+                other_x, other_z, ..
+            } = *problem
+            {
+                chunks.push((other_x, other_z));
+            }
+            chunks.extend(problem.chunk());
+        }
+
+        chunks.sort_unstable();
+        chunks.dedup();
+        chunks
+    }
+}
+
+impl<S: Seek + Read> RegionBuffer<S> {
+    /// Check the region for problems that would stop chunks from being read,
+    /// such as those left behind by a crash while the region was being
+    /// written. Only the header and the first few bytes of each chunk are
+    /// read; chunk data is not decompressed.
+    ///
+    /// An error is only returned if the region could not be read at all.
+    ///
+    /// ```no_run
+    /// # use fastanvil::RegionBuffer;
+    /// let region = RegionBuffer::new(std::fs::File::open("r.0.0.mca")?);
+    ///
+    /// for problem in region.validate()?.problems {
+    ///     println!("{:?}", problem);
+    /// }
+    /// # Ok::<(), fastanvil::Error>(())
+    /// ```
+    pub fn validate(&self) -> Result<RegionReport> {
+        let len = self.data().seek(SeekFrom::End(0))?;
+        if len < HEADER_SIZE as u64 {
+            return Ok(RegionReport {
+                chunks: 0,
+                problems: vec![RegionProblem::TruncatedHeader { len }],
+            });
+        }
+
+        let locations = self.present_chunk_locations()?;
+        let mut problems = Vec::new();
+
+        // Locations are sorted by where they begin, so a chunk can only
+        // overlap the one that reaches furthest into the file so far.
+        let mut furthest: Option<&ChunkLocation> = None;
+
+        for loc in &locations {
+            let (x, z) = (loc.x, loc.z);
+
+            if loc.begin_sector < HEADER_SIZE / SECTOR_SIZE {
+                problems.push(RegionProblem::OverlapsHeader { x, z });
+                continue;
+            }
+
+            if let Some(other) = furthest {
+                if other.begin_sector + other.sector_count > loc.begin_sector {
+                    problems.push(RegionProblem::Overlapping {
+                        x,
+                        z,
+                        other_x: other.x,
+                        other_z: other.z,
+                    });
+                }
+            }
+
+            let end = loc.begin_sector + loc.sector_count;
+            if !matches!(furthest, Some(f) if f.begin_sector + f.sector_count >= end) {
+                furthest = Some(loc);
+            }
+
+            if (end * SECTOR_SIZE) as u64 > len {
+                problems.push(RegionProblem::PastEndOfFile { x, z });
+                continue;
+            }
+
+            let mut data = self.data();
+            data.seek(SeekFrom::Start((loc.begin_sector * SECTOR_SIZE) as u64))?;
+            let length = data.read_u32::<BigEndian>()?;
+            let scheme = data.read_u8()?;
+
+            // The length includes the compression scheme byte but not itself.
+            if length == 0 || length as usize + 4 > loc.sector_count * SECTOR_SIZE {
+                problems.push(RegionProblem::BadLength {
+                    x,
+                    z,
+                    length,
+                    sector_count: loc.sector_count,
+                });
+            }
+
+            // The top bit marks chunks stored in a separate .mcc file.
+            if CompressionScheme::try_from(scheme & 0x7f).is_err() {
+                problems.push(RegionProblem::BadCompression { x, z, scheme });
+            }
+        }
+
+        Ok(RegionReport {
+            chunks: locations.len(),
+            problems,
+        })
+    }
+}
+
+impl<S: Seek + Read + Write> RegionBuffer<S> {
+    /// Remove every chunk that [`validate`](Self::validate) finds a problem
+    /// with, so that the rest of the region can be read. A truncated header is
+    /// padded out, losing any chunks it referred to. Returns the report from
+    /// before the repair.
+    ///
+    /// The data of removed chunks is left in the region, see
+    /// [`compact_region`](crate::compact_region) to reclaim the space.
+    pub fn repair(&mut self) -> Result<RegionReport> {
+        let report = self.validate()?;
+
+        if matches!(
+            report.problems.first(),
+            Some(RegionProblem::TruncatedHeader { .. })
+        ) {
+            // Whatever header there was can't be trusted.
+            let data = self.data_mut();
+            data.seek(SeekFrom::Start(0))?;
+            data.write_all(&[0; HEADER_SIZE])?;
+        }
+
+        for (x, z) in report.corrupt_chunks() {
+            self.remove_chunk(x, z)?;
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Builder, Error};
+
+    #[test]
+    fn valid_region() -> Result<()> {
+        let r = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1])
+            .chunk(&[2])
+            .build();
+        let report = RegionBuffer::new(r).validate()?;
+
+        assert!(report.is_ok());
+        assert_eq!(2, report.chunks);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_header() -> Result<()> {
+        let r = Builder::new().location(2, 1).build_unpadded();
+        let mut r = RegionBuffer::new(r);
+
+        assert_eq!(
+            vec![RegionProblem::TruncatedHeader { len: 4 }],
+            r.validate()?.problems
+        );
+
+        r.repair()?;
+        assert!(r.validate()?.is_ok());
+        assert_eq!(0, r.validate()?.chunks);
+        Ok(())
+    }
+
+    #[test]
+    fn location_problems() -> Result<()> {
+        let r = Builder::new()
+            .location(1, 1)
+            .location(2, 2)
+            .location(3, 1)
+            .location(9, 1)
+            .chunk(&[1])
+            .chunk(&[2])
+            .build();
+        let report = RegionBuffer::new(r).validate()?;
+
+        assert_eq!(
+            vec![
+                RegionProblem::OverlapsHeader { x: 0, z: 0 },
+                RegionProblem::Overlapping {
+                    x: 2,
+                    z: 0,
+                    other_x: 1,
+                    other_z: 0
+                },
+                RegionProblem::PastEndOfFile { x: 3, z: 0 },
+            ],
+            report.problems
+        );
+        assert_eq!(
+            vec![(0, 0), (1, 0), (2, 0), (3, 0)],
+            report.corrupt_chunks()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_header_problems() -> Result<()> {
+        let mut data = Builder::new()
+            .location(2, 1)
+            .location(3, 1)
+            .chunk(&[1])
+            .chunk(&[2])
+            .build()
+            .into_inner();
+
+        // A length longer than the sector, and an unknown compression scheme.
+        data[2 * SECTOR_SIZE..2 * SECTOR_SIZE + 4].copy_from_slice(&5000u32.to_be_bytes());
+        data[3 * SECTOR_SIZE + 4] = 42;
+
+        let mut r = RegionBuffer::new(std::io::Cursor::new(data));
+        let report = r.validate()?;
+        assert_eq!(
+            vec![
+                RegionProblem::BadLength {
+                    x: 0,
+                    z: 0,
+                    length: 5000,
+                    sector_count: 1
+                },
+                RegionProblem::BadCompression {
+                    x: 1,
+                    z: 0,
+                    scheme: 42
+                },
+            ],
+            report.problems
+        );
+
+        assert_eq!(report, r.repair()?);
+        assert!(r.validate()?.is_ok());
+        assert!(matches!(r.load_chunk(0, 0), Err(Error::ChunkNotFound)));
+        Ok(())
+    }
+}
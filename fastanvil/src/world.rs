@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{load_level_dat, load_saved_data, Chunk, DimensionId, LevelDat, Result};
+use crate::{EntityRegionFileLoader, PlayerFileLoader, PoiRegionFileLoader, RegionFileLoader};
+use crate::{IdCounts, Raids, Scoreboard};
+
+/// A Java Edition world save folder, the folder that contains `level.dat`.
+/// This is the place to start for finding the rest of a world's data.
+///
+/// ```no_run
+/// use fastanvil::{JavaChunk, RegionLoader, World};
+///
+/// let world = World::open("saves/My World")?;
+/// println!("{}", world.level_dat()?.level_name);
+///
+/// for dimension in world.dimensions() {
+///     let loader = world.region_loader::<JavaChunk>(&dimension);
+///     println!("{} has {} regions", dimension.id(), loader.list().unwrap().len());
+/// }
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct World {
+    dir: PathBuf,
+}
+
+/// A datapack in the `datapacks` folder of a world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Datapack {
+    /// The name of the pack's folder or zip file.
+    pub name: String,
+    pub path: PathBuf,
+
+    /// Whether `level.dat` lists the pack as enabled.
+    pub enabled: bool,
+}
+
+impl World {
+    /// Open the world in the given folder. Fails if there is no `level.dat`
+    /// in it.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::metadata(dir.join("level.dat"))?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Load the world's `level.dat`.
+    pub fn level_dat(&self) -> Result<LevelDat> {
+        load_level_dat(self.dir.join("level.dat"))
+    }
+
+    /// The dimensions of the world that have been generated, which are those
+    /// with a `region` folder. The overworld, nether and end come first, then
+    /// custom dimensions sorted by ID.
+    pub fn dimensions(&self) -> Vec<DimensionId> {
+        let mut dimensions: Vec<_> = vec![
+            DimensionId::Overworld,
+            DimensionId::Nether,
+            DimensionId::End,
+        ]
+        .into_iter()
+        .filter(|d| self.has_regions(d))
+        .collect();
+
+        let mut custom = vec![];
+        for namespace in dir_names(&self.dir.join("dimensions")) {
+            for name in dir_names(&self.dir.join("dimensions").join(&namespace)) {
+                let dimension = DimensionId::Custom(format!("{}:{}", namespace, name));
+                if self.has_regions(&dimension) {
+                    custom.push(dimension);
+                }
+            }
+        }
+
+        custom.sort_by_key(|d| d.id());
+        dimensions.extend(custom);
+        dimensions
+    }
+
+    /// A loader for the chunks of a dimension.
+    pub fn region_loader<C: Chunk>(&self, dimension: &DimensionId) -> RegionFileLoader<C> {
+        RegionFileLoader::for_dimension(&self.dir, dimension)
+    }
+
+    /// A loader for the entities of a dimension.
+    pub fn entity_loader(&self, dimension: &DimensionId) -> EntityRegionFileLoader {
+        EntityRegionFileLoader::for_dimension(&self.dir, dimension)
+    }
+
+    /// A loader for the points of interest of a dimension.
+    pub fn poi_loader(&self, dimension: &DimensionId) -> PoiRegionFileLoader {
+        PoiRegionFileLoader::for_dimension(&self.dir, dimension)
+    }
+
+    /// The UUIDs of the players that have data saved in the world, as they
+    /// appear in the names of the files in `playerdata`, sorted.
+    pub fn players(&self) -> Vec<String> {
+        self.player_loader().list().unwrap_or_default()
+    }
+
+    /// The path of the data file of the player with the given UUID.
+    pub fn player_path(&self, uuid: &str) -> PathBuf {
+        self.player_loader().path(uuid)
+    }
+
+    /// A loader for the data of the world's players.
+    pub fn player_loader(&self) -> PlayerFileLoader {
+        PlayerFileLoader::new(self.dir.clone())
+    }
+
+    /// The IDs of the in-game maps saved in the world's `data` folder,
+    /// sorted.
+    pub fn maps(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = fs::read_dir(self.dir.join("data"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.strip_prefix("map_")?
+                    .strip_suffix(".dat")?
+                    .parse()
+                    .ok()
+            })
+            .collect();
+
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The path of the data file of the in-game map with the given ID.
+    pub fn map_path(&self, id: u32) -> PathBuf {
+        self.dir.join("data").join(format!("map_{}.dat", id))
+    }
+
+    /// The scoreboard objectives, scores and teams of the world.
+    pub fn scoreboard(&self) -> Result<Scoreboard> {
+        load_saved_data(self.dir.join("data").join("scoreboard.dat"))
+    }
+
+    /// The counter of in-game map IDs.
+    pub fn id_counts(&self) -> Result<IdCounts> {
+        load_saved_data(self.dir.join("data").join("idcounts.dat"))
+    }
+
+    /// The raids going on in a dimension. Since 1.16 each dimension keeps
+    /// these in its own `data` folder.
+    pub fn raids(&self, dimension: &DimensionId) -> Result<Raids> {
+        load_saved_data(dimension.dir(&self.dir).join("data").join("raids.dat"))
+    }
+
+    /// The datapacks in the world's `datapacks` folder, sorted by name.
+    /// Whether they are enabled comes from `level.dat`.
+    pub fn datapacks(&self) -> Result<Vec<Datapack>> {
+        let enabled = self
+            .level_dat()?
+            .data_packs
+            .map(|packs| packs.enabled)
+            .unwrap_or_default();
+
+        let dir = self.dir.join("datapacks");
+        let mut packs: Vec<_> = fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .map(|name| Datapack {
+                enabled: enabled.contains(&format!("file/{}", name)),
+                path: dir.join(&name),
+                name,
+            })
+            .collect();
+
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(packs)
+    }
+
+    fn has_regions(&self, dimension: &DimensionId) -> bool {
+        dimension.dir(&self.dir).join("region").is_dir()
+    }
+}
+
+/// The names of the folders in a folder. Missing folders have none.
+fn dir_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use fastnbt::Value;
+
+use crate::{ChunkPos, Error, RCoord, RegionBuffer, Result};
+
+/// The NBT of a chunk, as edited by a [`DimensionWriter`].
+pub type ChunkNbt = HashMap<String, Value>;
+
+/// Edits the chunks of a dimension in memory and writes them back to the
+/// region files in `region_dir`.
+///
+/// Chunks are loaded as raw NBT when first accessed and kept in memory.
+/// Mutable access marks a chunk as dirty, and [`flush`](Self::flush) writes
+/// all dirty chunks back to their region files. Region files that do not
+/// exist yet are created.
+///
+/// ```no_run
+/// # use fastanvil::{CCoord, DimensionWriter};
+/// # use fastnbt::Value;
+/// let mut writer = DimensionWriter::new("world/region".into());
+///
+/// if let Some(chunk) = writer.chunk_mut((CCoord(0), CCoord(0)))? {
+///     chunk.insert("InhabitedTime".to_owned(), Value::Long(0));
+/// }
+///
+/// writer.flush()?;
+/// # Ok::<(), fastanvil::Error>(())
+/// ```
+pub struct DimensionWriter {
+    region_dir: PathBuf,
+    chunks: HashMap<ChunkPos, EditedChunk>,
+}
+
+struct EditedChunk {
+    // None if the chunk does not exist, so we don't look for it again.
+    nbt: Option<ChunkNbt>,
+    dirty: bool,
+}
+
+impl DimensionWriter {
+    pub fn new(region_dir: PathBuf) -> Self {
+        Self {
+            region_dir,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Get the NBT of the chunk at the given position, a [`ChunkPos`] or a
+    /// tuple of (absolute) chunk coordinates, loading it from its region file
+    /// if it isn't already in memory. Returns None if the chunk does not
+    /// exist.
+    pub fn chunk(&mut self, pos: impl Into<ChunkPos>) -> Result<Option<&ChunkNbt>> {
+        Ok(self.edited(pos.into())?.nbt.as_ref())
+    }
+
+    /// Get the NBT of the chunk at the given position for editing, and mark
+    /// it as dirty. Returns None if the chunk does not exist, use
+    /// [`insert_chunk`](Self::insert_chunk) to create one.
+    pub fn chunk_mut(&mut self, pos: impl Into<ChunkPos>) -> Result<Option<&mut ChunkNbt>> {
+        let edited = self.edited(pos.into())?;
+        if edited.nbt.is_some() {
+            edited.dirty = true;
+        }
+        Ok(edited.nbt.as_mut())
+    }
+
+    /// Set the NBT of the chunk at the given position, replacing any existing
+    /// chunk.
+    pub fn insert_chunk(&mut self, pos: impl Into<ChunkPos>, nbt: ChunkNbt) {
+        self.chunks.insert(
+            pos.into(),
+            EditedChunk {
+                nbt: Some(nbt),
+                dirty: true,
+            },
+        );
+    }
+
+    /// Whether the chunk at the given position has changes that have not
+    /// been flushed.
+    pub fn is_dirty(&self, pos: impl Into<ChunkPos>) -> bool {
+        matches!(self.chunks.get(&pos.into()), Some(c) if c.dirty)
+    }
+
+    /// The positions of all chunks with changes that have not been flushed,
+    /// in no particular order.
+    pub fn dirty_chunks(&self) -> Vec<ChunkPos> {
+        self.chunks
+            .iter()
+            .filter(|(_, c)| c.dirty)
+            .map(|(pos, _)| *pos)
+            .collect()
+    }
+
+    /// Write all dirty chunks back to their region files. Chunks stay in
+    /// memory and can be edited further.
+    ///
+    /// Regions are written one at a time. If an error occurs, chunks of
+    /// regions that were already written are no longer dirty, the rest are
+    /// left dirty.
+    pub fn flush(&mut self) -> Result<()> {
+        let mut regions = HashMap::<_, Vec<ChunkPos>>::new();
+        for pos in self.dirty_chunks() {
+            regions.entry(pos.region()).or_default().push(pos);
+        }
+
+        for (region_pos, chunks) in regions {
+            let (rx, rz) = (region_pos.x, region_pos.z);
+            let mut region = RegionBuffer::new(create_region(&self.region_dir, rx, rz)?)
+                .with_external_chunks(&self.region_dir, rx, rz);
+
+            for pos in &chunks {
+                if let Some(nbt) = &self.chunks[pos].nbt {
+                    let data = fastnbt::ser::to_bytes(nbt)?;
+                    let (x, z) = pos.in_region();
+                    region.write_chunk(x, z, &data)?;
+                }
+            }
+
+            for pos in &chunks {
+                if let Some(c) = self.chunks.get_mut(pos) {
+                    c.dirty = false;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn edited(&mut self, pos: ChunkPos) -> Result<&mut EditedChunk> {
+        if !self.chunks.contains_key(&pos) {
+            let nbt = load_chunk(&self.region_dir, pos)?;
+            self.chunks.insert(pos, EditedChunk { nbt, dirty: false });
+        }
+
+        Ok(self.chunks.get_mut(&pos).unwrap())
+    }
+}
+
+fn region_path(dir: &Path, x: RCoord, z: RCoord) -> PathBuf {
+    dir.join(format!("r.{}.{}.mca", x.0, z.0))
+}
+
+fn load_chunk(dir: &Path, pos: ChunkPos) -> Result<Option<ChunkNbt>> {
+    let (rx, rz) = (pos.region().x, pos.region().z);
+    let file = match File::open(region_path(dir, rx, rz)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    // An empty file is a region with no chunks.
+    if file.metadata()?.len() == 0 {
+        return Ok(None);
+    }
+
+    let region = RegionBuffer::new(file).with_external_chunks(dir, rx, rz);
+    let (rel_x, rel_z) = pos.in_region();
+    match region.load_chunk(rel_x, rel_z) {
+        Ok(data) => Ok(Some(fastnbt::de::from_bytes(&data)?)),
+        Err(Error::ChunkNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn create_region(dir: &Path, x: RCoord, z: RCoord) -> Result<File> {
+    Ok(OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(region_path(dir, x, z))?)
+}
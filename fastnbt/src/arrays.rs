@@ -1,15 +1,47 @@
 use std::ops::Deref;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use crate::ser::{BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN};
 use crate::{CompTag, BYTE_ARRAY_TAG, INT_ARRAY_TAG, LONG_ARRAY_TAG};
 
+/// ByteArray can be used to deserialize the NBT data of the same name. This
+/// owns its data. See [`borrow::ByteArray`][`crate::borrow::ByteArray`] for a
+/// version that borrows from the input.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct ByteArray {
     tag: CompTag<BYTE_ARRAY_TAG>,
     data: Vec<i8>,
 }
 
+impl ByteArray {
+    pub fn new(data: Vec<i8>) -> Self {
+        Self {
+            tag: CompTag::<BYTE_ARRAY_TAG>,
+            data,
+        }
+    }
+
+    /// Get the data as a slice.
+    pub fn as_slice(&self) -> &[i8] {
+        &self.data
+    }
+
+    /// Take the underlying vector of data.
+    pub fn into_inner(self) -> Vec<i8> {
+        self.data
+    }
+}
+
+impl Serialize for ByteArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(BYTE_ARRAY_TOKEN, &self.data)
+    }
+}
+
 impl Deref for ByteArray {
     type Target = Vec<i8>;
 
@@ -18,12 +50,73 @@ impl Deref for ByteArray {
     }
 }
 
+impl Default for ByteArray {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl From<Vec<i8>> for ByteArray {
+    fn from(data: Vec<i8>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl IntoIterator for ByteArray {
+    type Item = i8;
+    type IntoIter = std::vec::IntoIter<i8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ByteArray {
+    type Item = &'a i8;
+    type IntoIter = std::slice::Iter<'a, i8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+/// IntArray can be used to deserialize the NBT data of the same name. This
+/// owns its data. See [`borrow::IntArray`][`crate::borrow::IntArray`] for a
+/// version that borrows from the input.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct IntArray {
     tag: CompTag<INT_ARRAY_TAG>,
     data: Vec<i32>,
 }
 
+impl IntArray {
+    pub fn new(data: Vec<i32>) -> Self {
+        Self {
+            tag: CompTag::<INT_ARRAY_TAG>,
+            data,
+        }
+    }
+
+    /// Get the data as a slice.
+    pub fn as_slice(&self) -> &[i32] {
+        &self.data
+    }
+
+    /// Take the underlying vector of data.
+    pub fn into_inner(self) -> Vec<i32> {
+        self.data
+    }
+}
+
+impl Serialize for IntArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, &self.data)
+    }
+}
+
 impl Deref for IntArray {
     type Target = Vec<i32>;
 
@@ -32,6 +125,39 @@ impl Deref for IntArray {
     }
 }
 
+impl Default for IntArray {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl From<Vec<i32>> for IntArray {
+    fn from(data: Vec<i32>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl IntoIterator for IntArray {
+    type Item = i32;
+    type IntoIter = std::vec::IntoIter<i32>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a IntArray {
+    type Item = &'a i32;
+    type IntoIter = std::slice::Iter<'a, i32>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+/// LongArray can be used to deserialize the NBT data of the same name. This
+/// owns its data. See [`borrow::LongArray`][`crate::borrow::LongArray`] for a
+/// version that borrows from the input.
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct LongArray {
     tag: CompTag<LONG_ARRAY_TAG>,
@@ -45,6 +171,25 @@ impl LongArray {
             data,
         }
     }
+
+    /// Get the data as a slice.
+    pub fn as_slice(&self) -> &[i64] {
+        &self.data
+    }
+
+    /// Take the underlying vector of data.
+    pub fn into_inner(self) -> Vec<i64> {
+        self.data
+    }
+}
+
+impl Serialize for LongArray {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_newtype_struct(LONG_ARRAY_TOKEN, &self.data)
+    }
 }
 
 impl Deref for LongArray {
@@ -54,3 +199,33 @@ impl Deref for LongArray {
         &self.data
     }
 }
+
+impl Default for LongArray {
+    fn default() -> Self {
+        Self::new(vec![])
+    }
+}
+
+impl From<Vec<i64>> for LongArray {
+    fn from(data: Vec<i64>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl IntoIterator for LongArray {
+    type Item = i64;
+    type IntoIter = std::vec::IntoIter<i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a LongArray {
+    type Item = &'a i64;
+    type IntoIter = std::slice::Iter<'a, i64>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
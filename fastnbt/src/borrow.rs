@@ -14,7 +14,9 @@
 //! [`IntArray`][`crate::IntArray`], [`LongArray`][`crate::LongArray`]`}`.
 //!
 //! The `iter()` methods return an iterator to the values read on demand from an
-//! internal reference to the input data.
+//! internal reference to the input data. Individual values can be read with
+//! `get()`, again decoding only what is asked for. Each type can be converted
+//! into its owned counterpart with `From`/`Into`.
 //!
 //! # Example
 //!
@@ -40,9 +42,8 @@
 //!     }
 //! }
 
-use std::{borrow::Cow, fmt};
+use std::{borrow::Cow, convert::TryInto, fmt, slice};
 
-use byteorder::{BigEndian, ReadBytesExt};
 use serde::Deserialize;
 
 use crate::{CompTag, BYTE_ARRAY_TAG, INT_ARRAY_TAG, LONG_ARRAY_TAG};
@@ -59,20 +60,66 @@ pub struct ByteArray<'a> {
 impl<'a> ByteArray<'a> {
     /// Create an iterator over the bytes.
     pub fn iter(&self) -> ByteIter<'a> {
-        ByteIter(*self)
+        ByteIter(self.data.iter())
+    }
+
+    /// The number of bytes in the array.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Get the byte at `index`, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<i8> {
+        self.data.get(index).map(|b| *b as i8)
+    }
+
+    /// The underlying data, borrowed from the input.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
     }
 }
 
-pub struct ByteIter<'a>(ByteArray<'a>);
+impl<'a> IntoIterator for ByteArray<'a> {
+    type Item = i8;
+    type IntoIter = ByteIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> From<ByteArray<'a>> for crate::ByteArray {
+    fn from(arr: ByteArray<'a>) -> Self {
+        crate::ByteArray::new(arr.iter().collect())
+    }
+}
+
+pub struct ByteIter<'a>(slice::Iter<'a, u8>);
 
 impl<'a> Iterator for ByteIter<'a> {
     type Item = i8;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.data.read_i8().ok()
+        self.0.next().map(|b| *b as i8)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for ByteIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|b| *b as i8)
     }
 }
 
+impl<'a> ExactSizeIterator for ByteIter<'a> {}
+
 /// IntArray can be used to deserialize the NBT data of the same name. This
 /// borrows from the original input data when deserializing. Biomes in the chunk
 /// format are an example of this data type.
@@ -85,20 +132,73 @@ pub struct IntArray<'a> {
 impl<'a> IntArray<'a> {
     /// Create an iterator over the i32s
     pub fn iter(&self) -> IntIter<'a> {
-        IntIter(*self)
+        IntIter(self.data.chunks_exact(4))
+    }
+
+    /// The number of i32s in the array.
+    pub fn len(&self) -> usize {
+        self.data.len() / 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Get the i32 at `index`, or `None` if out of bounds. The value is
+    /// decoded from the input on each call.
+    pub fn get(&self, index: usize) -> Option<i32> {
+        let start = index.checked_mul(4)?;
+        let bytes = self.data.get(start..start.checked_add(4)?)?;
+        Some(i32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// The underlying big-endian data, borrowed from the input.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> IntoIterator for IntArray<'a> {
+    type Item = i32;
+    type IntoIter = IntIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> From<IntArray<'a>> for crate::IntArray {
+    fn from(arr: IntArray<'a>) -> Self {
+        crate::IntArray::new(arr.iter().collect())
     }
 }
 
-pub struct IntIter<'a>(IntArray<'a>);
+pub struct IntIter<'a>(slice::ChunksExact<'a, u8>);
 
 impl<'a> Iterator for IntIter<'a> {
     type Item = i32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.data.read_i32::<BigEndian>().ok()
+        self.0
+            .next()
+            .map(|bs| i32::from_be_bytes(bs.try_into().unwrap()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
+impl<'a> DoubleEndedIterator for IntIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0
+            .next_back()
+            .map(|bs| i32::from_be_bytes(bs.try_into().unwrap()))
+    }
+}
+
+impl<'a> ExactSizeIterator for IntIter<'a> {}
+
 /// LongArray can be used to deserialize the NBT data of the same name. This
 /// borrows from the original input data when deserializing. Block states
 /// (storage of all the blocks in a chunk) are an exmple of when this is used.
@@ -111,20 +211,73 @@ pub struct LongArray<'a> {
 impl<'a> LongArray<'a> {
     /// Create an iterator over the i64s
     pub fn iter(&self) -> LongIter<'a> {
-        LongIter(*self)
+        LongIter(self.data.chunks_exact(8))
+    }
+
+    /// The number of i64s in the array.
+    pub fn len(&self) -> usize {
+        self.data.len() / 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Get the i64 at `index`, or `None` if out of bounds. The value is
+    /// decoded from the input on each call.
+    pub fn get(&self, index: usize) -> Option<i64> {
+        let start = index.checked_mul(8)?;
+        let bytes = self.data.get(start..start.checked_add(8)?)?;
+        Some(i64::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// The underlying big-endian data, borrowed from the input.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+impl<'a> IntoIterator for LongArray<'a> {
+    type Item = i64;
+    type IntoIter = LongIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> From<LongArray<'a>> for crate::LongArray {
+    fn from(arr: LongArray<'a>) -> Self {
+        crate::LongArray::new(arr.iter().collect())
     }
 }
 
-pub struct LongIter<'a>(LongArray<'a>);
+pub struct LongIter<'a>(slice::ChunksExact<'a, u8>);
 
 impl<'a> Iterator for LongIter<'a> {
     type Item = i64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.data.read_i64::<BigEndian>().ok()
+        self.0
+            .next()
+            .map(|bs| i64::from_be_bytes(bs.try_into().unwrap()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
     }
 }
 
+impl<'a> DoubleEndedIterator for LongIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0
+            .next_back()
+            .map(|bs| i64::from_be_bytes(bs.try_into().unwrap()))
+    }
+}
+
+impl<'a> ExactSizeIterator for LongIter<'a> {}
+
 struct CowStr<'a>(Cow<'a, str>);
 
 impl<'de> serde::Deserialize<'de> for CowStr<'de> {
@@ -0,0 +1,81 @@
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde::de::DeserializeOwned;
+
+use crate::de::{from_bytes, from_reader};
+use crate::error::Result;
+
+/// The compression of some NBT data, as worked out by [`Compression::detect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Gzip, used for files like `level.dat`.
+    Gzip,
+    /// Zlib, used for most chunks in region files.
+    Zlib,
+    /// Plain NBT.
+    Uncompressed,
+}
+
+impl Compression {
+    /// Work out the compression of some data from its first few bytes. Gzip
+    /// and zlib both start with a header that uncompressed NBT can't, which
+    /// always starts with a compound tag.
+    pub fn detect(data: &[u8]) -> Compression {
+        match data {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // Deflate with any window size, and a valid header checksum.
+            [cmf, flg, ..]
+                if cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0 =>
+            {
+                Compression::Zlib
+            }
+            _ => Compression::Uncompressed,
+        }
+    }
+}
+
+/// Deserialize a `T` from gzip compressed NBT, such as `level.dat` or a
+/// player's data file. The data is decompressed as it is deserialized.
+///
+/// ```no_run
+/// # use fastnbt::Value;
+/// let file = std::fs::File::open("level.dat")?;
+/// let level: Value = fastnbt::from_gzip_reader(file)?;
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn from_gzip_reader<R, T>(reader: R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    from_reader(GzDecoder::new(reader))
+}
+
+/// Deserialize a `T` from zlib compressed NBT, which is how chunks are
+/// usually stored in region files. The data is decompressed as it is
+/// deserialized.
+pub fn from_zlib_reader<R, T>(reader: R) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    from_reader(ZlibDecoder::new(reader))
+}
+
+/// Deserialize a `T` from NBT that may be gzip compressed, zlib compressed,
+/// or not compressed at all. See [`Compression::detect`] for how this is
+/// worked out.
+///
+/// Uncompressed data is deserialized straight from the slice, but `T` must
+/// still own its data since compressed data is not kept around.
+pub fn from_compressed_bytes<T>(data: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    match Compression::detect(data) {
+        Compression::Gzip => from_gzip_reader(data),
+        Compression::Zlib => from_zlib_reader(data),
+        Compression::Uncompressed => from_bytes(data),
+    }
+}
@@ -5,10 +5,13 @@
 //! * an arbitrary [`Value`](../enum.Value.html).
 //! * enums. For NBT typically you want either internally or untagged enums.
 //!
-//! This deserializer only supports [`from_bytes`](fn.from_bytes.html). This is
-//! usually fine as most structures stored in this format are reasonably small,
-//! the largest likely being an individual Chunk which maxes out at 1 MiB
-//! compressed. This enables zero-copy deserialization in places.
+//! This deserializer supports [`from_bytes`](fn.from_bytes.html) and
+//! [`from_reader`](fn.from_reader.html). Most structures stored in this format
+//! are reasonably small, the largest likely being an individual Chunk which
+//! maxes out at 1 MiB compressed, so `from_bytes` is usually fine. It also
+//! enables zero-copy deserialization in places. `from_reader` is useful when
+//! you want to deserialize straight out of a decompressor without collecting
+//! the data into a buffer first.
 //!
 //! # Avoiding allocations
 //!
@@ -27,11 +30,11 @@
 //! | Long | `i64` or `u64` | use owned |
 //! | Float | `f32` | use owned |
 //! | Double | `f64` | use owned |
-//! | String | `String` | [`Cow<'a, str>`][`std::borrow::Cow`] (see below) |
-//! | List | `Vec<T>` | use owned |
-//! | Byte Array | [`ByteArray`][`crate::ByteArray`] | [`borrow::ByteArray`][`crate::borrow::ByteArray`] |
-//! | Int Array | [`IntArray`][`crate::IntArray`] | [`borrow::IntArray`][`crate::borrow::IntArray`] |
-//! | Long Array | [`LongArray`][`crate::LongArray`] | [`borrow::LongArray`][`crate::borrow::LongArray`] |
+//! | String | `String` | `&'a str`, [`Cow<'a, str>`][`std::borrow::Cow`] or `&'a [u8]` (see below) |
+//! | List | `Vec<T>` | `&'a [u8]` for lists of integral types (see below) |
+//! | Byte Array | [`ByteArray`][`crate::ByteArray`] | [`borrow::ByteArray`][`crate::borrow::ByteArray`] or `&'a [u8]` |
+//! | Int Array | [`IntArray`][`crate::IntArray`] | [`borrow::IntArray`][`crate::borrow::IntArray`] or `&'a [u8]` |
+//! | Long Array | [`LongArray`][`crate::LongArray`] | [`borrow::LongArray`][`crate::borrow::LongArray`] or `&'a [u8]` |
 //!
 //! ## Primitives
 //!
@@ -47,14 +50,21 @@
 //! the Basic Multilingual Plane then we need to convert it to utf-8, requiring
 //! us to own the string data.
 //!
-//! Using [`Cow<'a, str>`][`std::borrow::Cow`] lets us borrow when possible, but
-//! produce an owned value when the representation is different. This will be
-//! common for minecrafts internal strings and any world whose language falls in
-//! the basic multilingual plane.
+//! Deserializing to `&'a str` works whenever the representations match, and is
+//! an error otherwise. Using [`Cow<'a, str>`][`std::borrow::Cow`] lets us
+//! borrow when possible, but produce an owned value when the representation is
+//! different. This will be common for minecrafts internal strings and any world
+//! whose language falls in the basic multilingual plane.
 //!
-//! In future we could support a lazy string type that always borrows the
-//! underyling data and decodes when needed. Please open an issue if this is
-//! important to you.
+//! If you only need to compare strings, or want to decode them later, `&'a [u8]`
+//! always borrows the raw Java-encoded data. You can decode it with the
+//! [`cesu8`](https://docs.rs/cesu8) crate.
+//!
+//! ## Bytes
+//!
+//! `&'a [u8]` borrows the raw big-endian payload of any NBT array, or of a list
+//! of Byte, Short, Int or Long. This works for fields in a compound as well as
+//! for elements of a list, so `Vec<&'a [u8]>` can borrow a list of arrays.
 //!
 //! ## Representation of NBT arrays
 //!
@@ -95,6 +105,8 @@
 //!   vectors.
 //! * Any integral value from NBT can be deserialized to bool. Any non-zero
 //!   value becomes `true`.
+//! * `u128` and `i128` deserialize from an IntArray of four ints, most
+//!   significant first. This is how UUIDs are stored, see [`uuid`][crate::uuid].
 //! * You can deserialize a field to the unit type `()`. This ignores the value
 //!   but ensures that it existed.
 //! * You cannot deserialize into anything other than a `struct` or similar
@@ -102,6 +114,26 @@
 //!   format and Rust's types. Attempting to will give a `NoRootCompound` error.
 //!   This means you can never do `let s: String = from_bytes(...)`.
 //!
+//! # Skipping data
+//!
+//! Fields of the NBT that the type being deserialized into does not have are
+//! skipped without being decoded. Nothing is allocated to skip a value, no
+//! matter how large, and `from_bytes` skips arrays without looking at their
+//! contents at all. This means a struct with only the fields you need is
+//! usually the fastest way to scan large amounts of data. Fields can also be
+//! skipped explicitly with [`serde::de::IgnoredAny`].
+//!
+//! To get at a single value deep inside the data, [`extract`] deserializes
+//! just the value at a dotted path, skipping everything else:
+//!
+//! ```no_run
+//! # use fastnbt::LongArray;
+//! # let chunk_data: Vec<u8> = vec![];
+//! let surface: Option<LongArray> =
+//!     fastnbt::de::extract(&chunk_data, "Level.Heightmaps.WORLD_SURFACE")?;
+//! # Ok::<(), fastnbt::error::Error>(())
+//! ```
+//!
 //! # Example Minecraft types
 //!
 //! This section demonstrates writing types for a few real Minecraft structures.
@@ -165,6 +197,33 @@
 //! }
 //! ```
 //!
+//! ## How untagged and internally tagged enums work
+//!
+//! Serde buffers the value for these enums before picking a variant, then
+//! deserializes the variant from its buffer rather than from the NBT. NBT tags
+//! survive the buffering, so numbers keep their exact type and the arrays keep
+//! their kind, meaning variants like `Old(LongArray)` and `New(Vec<i64>)` can
+//! be told apart. Variants are tried in order, so put the most specific first.
+//!
+//! Conversions that rely on knowing the NBT tag are lost in the buffer. The
+//! one that matters is bool: booleans are stored as bytes, and the buffer
+//! cannot turn a byte into a bool. Use [`bool_from_int`] for bool fields of
+//! types inside these enums:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Debug)]
+//! #[serde(tag = "id")]
+//! enum Entity {
+//!     #[serde(rename = "minecraft:creeper")]
+//!     Creeper {
+//!         #[serde(deserialize_with = "fastnbt::de::bool_from_int")]
+//!         ignited: bool,
+//!     },
+//! }
+//! ```
+//!
 //! ## Avoiding allocations in a Chunk
 //!
 //! This example shows how to avoid some allocations. The `Section` type below
@@ -237,14 +296,18 @@
 //! }
 //! ```
 
-use std::borrow::Cow;
 use std::convert::{TryFrom, TryInto};
-use std::ops::Range;
+use std::io::Read;
+use std::marker::PhantomData;
 
+use crate::de_arrays::element_size;
 use crate::de_arrays::ArrayWrapperAccess;
-use crate::error::{Error, Result};
-use crate::Tag;
-use byteorder::{BigEndian, ReadBytesExt};
+use crate::error::{Error, Limit, Result};
+use crate::input::{Reference, Str};
+use crate::maybe::MAYBE_TOKEN;
+use crate::{RootName, Tag, Value};
+
+pub use crate::input::{Input, Reader, Slice};
 
 use serde::{de, forward_to_deserialize_any};
 
@@ -273,18 +336,339 @@ pub fn from_bytes<'a, T>(input: &'a [u8]) -> Result<T>
 where
     T: de::Deserialize<'a>,
 {
-    let mut des = Deserializer::from_bytes(&input);
-    let t = T::deserialize(&mut des)?;
+    let mut des = Deserializer::from_bytes(input);
+    let t = T::deserialize(&mut des).map_err(|e| des.locate(e))?;
+    Ok(t)
+}
+
+/// Deserialize into a `T` from NBT data read from a `Read`. Data is pulled from
+/// the reader as it is needed, so the whole input never has to be in memory at
+/// once. See the [`de`] module for more information.
+///
+/// Nothing can be borrowed from a reader, so `T` has to own its data. Types like
+/// `&str` or [`borrow::LongArray`][`crate::borrow::LongArray`] will fail to
+/// deserialize.
+///
+/// Reads are done in very small pieces, so you will likely want to wrap
+/// unbuffered readers like `File` in a `BufReader`. Decompressors like
+/// `GzDecoder` already buffer their input.
+///
+/// ```no_run
+/// # use fastnbt::Value;
+/// # use flate2::read::GzDecoder;
+/// # use fastnbt::error::Result;
+/// # fn main() -> Result<()> {
+/// let file = std::fs::File::open("level.dat")?;
+/// let decoder = GzDecoder::new(file);
+///
+/// let val: Value = fastnbt::de::from_reader(decoder)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`de`]: ./index.html
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: Read,
+    T: de::DeserializeOwned,
+{
+    let mut des = Deserializer::from_reader(reader);
+    let t = T::deserialize(&mut des).map_err(|e| des.locate(e))?;
+    Ok(t)
+}
+
+/// Options for deserializing, for data that isn't quite like NBT files.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use fastnbt::{de::DeOpts, RootName};
+///
+/// // An unnamed compound containing the byte `a`, as sent over the network.
+/// let data = [10, 1, 0, 1, b'a', 5, 0];
+/// let opts = DeOpts {
+///     root_name: RootName::None,
+///     ..Default::default()
+/// };
+///
+/// let map: HashMap<String, i8> = fastnbt::de::from_bytes_with_opts(&data, opts)?;
+/// assert_eq!(5, map["a"]);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeOpts {
+    /// Whether the root compound has a name to skip over.
+    pub root_name: RootName,
+
+    /// Limits on what the data can ask for. See [`Limits`].
+    pub limits: Limits,
+}
+
+/// Limits on the data the deserializer will accept, for reading NBT from
+/// untrusted sources such as uploaded schematics or proxied packets. By
+/// default only the depth is limited, to [`DEFAULT_MAX_DEPTH`]. Going over the
+/// depth limit fails with
+/// [`ErrorKind::DepthLimit`][`crate::error::ErrorKind::DepthLimit`], and over
+/// any other with
+/// [`ErrorKind::LimitExceeded`][`crate::error::ErrorKind::LimitExceeded`].
+///
+/// The sizes of lists and arrays come from the data itself, so without limits
+/// a few bytes of input can ask for gigabytes of memory.
+///
+/// ```
+/// use fastnbt::de::{DeOpts, Limits};
+/// use fastnbt::error::{ErrorKind, Limit};
+/// use fastnbt::Value;
+///
+/// // A compound with a list of a billion bytes, but no actual bytes.
+/// let data = [10, 0, 0, 9, 0, 1, b'a', 1, 0x3b, 0x9a, 0xca, 0x00, 0];
+/// let opts = DeOpts {
+///     limits: Limits {
+///         max_len: Some(1024),
+///         ..Default::default()
+///     },
+///     ..Default::default()
+/// };
+///
+/// let err = fastnbt::de::from_bytes_with_opts::<Value>(&data, opts).unwrap_err();
+/// assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::Length(1024))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The most compounds and lists that can be nested inside one another,
+    /// counting the root compound. Values that are skipped over rather than
+    /// deserialized count too. Without a limit, deeply nested input can
+    /// overflow the stack.
+    pub max_depth: Option<usize>,
+
+    /// The most elements a single list or array can have.
+    pub max_len: Option<usize>,
+
+    /// The most memory in bytes the data can ask for in total. Strings and
+    /// arrays count their size in bytes, and each list element counts as the
+    /// size of a [`Value`]. Lists are counted as soon as their size is read,
+    /// before any elements are.
+    pub max_alloc: Option<usize>,
+}
+
+/// How deeply compounds and lists can be nested by default. Real data rarely
+/// nests more than a few levels deep. Minecraft itself allows 512, but
+/// deserializing that deep can take more stack than a spawned thread has.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_len: None,
+            max_alloc: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Add `bytes` to the total allocated so far, failing if it goes over the
+    /// allocation limit.
+    fn charge(&self, allocated: &mut usize, bytes: usize) -> Result<()> {
+        *allocated = allocated.saturating_add(bytes);
+        match self.max_alloc {
+            Some(max) if *allocated > max => Err(Error::limit_exceeded(Limit::Allocation(max))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What each list element counts as towards [`Limits::max_alloc`].
+const LIST_ELEMENT_SIZE: usize = std::mem::size_of::<Value>();
+
+/// Like [`from_bytes`], but with options for the format of the data.
+pub fn from_bytes_with_opts<'a, T>(input: &'a [u8], opts: DeOpts) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut des = Deserializer::from_bytes(input).with_opts(opts);
+    let t = T::deserialize(&mut des).map_err(|e| des.locate(e))?;
     Ok(t)
 }
 
+/// Like [`from_reader`], but with options for the format of the data.
+pub fn from_reader_with_opts<R, T>(reader: R, opts: DeOpts) -> Result<T>
+where
+    R: Read,
+    T: de::DeserializeOwned,
+{
+    let mut des = Deserializer::from_reader(reader).with_opts(opts);
+    let t = T::deserialize(&mut des).map_err(|e| des.locate(e))?;
+    Ok(t)
+}
+
+/// Deserialize only the value at `path` into a `T`, skipping everything else.
+/// The path is the names of the compounds to go through separated by dots,
+/// eg `Level.Heightmaps`, and an empty path is the root compound. Names that
+/// contain dots cannot be reached this way.
+///
+/// Returns `None` if there is no value at the path. It is an error if the path
+/// goes through something other than a compound.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use fastnbt::Value;
+/// # let compound = |k: &str, v| Value::Compound(HashMap::from([(k.to_owned(), v)]));
+/// # let root = compound("Data", compound("Version", compound("Id", Value::Int(3700))));
+/// # let data = fastnbt::ser::to_bytes(&root)?;
+/// let id: Option<i32> = fastnbt::de::extract(&data, "Data.Version.Id")?;
+/// assert_eq!(Some(3700), id);
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn extract<'a, T>(input: &'a [u8], path: &str) -> Result<Option<T>>
+where
+    T: de::Deserialize<'a>,
+{
+    let mut des = Deserializer::from_bytes(input);
+    de::DeserializeSeed::deserialize(PathSeed::new(path), &mut des).map_err(|e| des.locate(e))
+}
+
+/// Like [`extract`], but reading from a `Read`. Reading stops as soon as the
+/// value has been deserialized.
+pub fn extract_from_reader<R, T>(reader: R, path: &str) -> Result<Option<T>>
+where
+    R: Read,
+    T: de::DeserializeOwned,
+{
+    let mut des = Deserializer::from_reader(reader);
+    de::DeserializeSeed::deserialize(PathSeed::new(path), &mut des).map_err(|e| des.locate(e))
+}
+
+/// Deserialize a bool from any integer, where non-zero is `true`. This is what
+/// the deserializer does for bool fields anyway, but untagged and internally
+/// tagged enums go through serde's buffering, which only accepts real bools.
+/// See [How untagged and internally tagged enums
+/// work](index.html#how-untagged-and-internally-tagged-enums-work).
+pub fn bool_from_int<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    struct BoolVisitor;
+
+    impl<'de> de::Visitor<'de> for BoolVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a bool or integer")
+        }
+
+        fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<bool, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<bool, E> {
+            Ok(v != 0)
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<bool, E> {
+            Ok(v != 0)
+        }
+    }
+
+    deserializer.deserialize_any(BoolVisitor)
+}
+
+/// Walks down the compounds named in a path, deserializing a `T` at the end.
+struct PathSeed<'p, T> {
+    path: Vec<&'p str>,
+    marker: PhantomData<T>,
+}
+
+impl<'p, T> PathSeed<'p, T> {
+    fn new(path: &'p str) -> Self {
+        Self {
+            path: path.split('.').filter(|s| !s.is_empty()).rev().collect(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'p, T: de::Deserialize<'de>> de::DeserializeSeed<'de> for PathSeed<'p, T> {
+    type Value = Option<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        if self.path.is_empty() {
+            return T::deserialize(deserializer).map(Some);
+        }
+
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'p, T: de::Deserialize<'de>> de::Visitor<'de> for PathSeed<'p, T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a compound containing {:?}", self.path.last())
+    }
+
+    fn visit_map<A>(mut self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let name = self.path.pop().expect("checked path is not empty");
+
+        while let Some(found) = map.next_key_seed(NameMatches(name))? {
+            if found {
+                // The rest of the data is left unread.
+                return map.next_value_seed(self);
+            }
+            map.next_value::<de::IgnoredAny>()?;
+        }
+
+        Ok(None)
+    }
+}
+
+/// Compares a compound key with a name, without allocating for it.
+struct NameMatches<'p>(&'p str);
+
+impl<'de, 'p> de::DeserializeSeed<'de> for NameMatches<'p> {
+    type Value = bool;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de, 'p> de::Visitor<'de> for NameMatches<'p> {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a name")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<bool, E> {
+        Ok(v == self.0)
+    }
+}
+
 /// Deserializer for NBT data. See the [`de`] module for more information.
 ///
 /// [`de`]: ./index.html
-pub struct Deserializer<'de> {
-    pub(crate) input: InputHelper<'de>,
+pub struct Deserializer<'de, In: Input<'de> = Slice<'de>> {
+    pub(crate) input: In,
+    pub(crate) scratch: Vec<u8>,
     layers: Vec<Layer>,
+    // The key most recently read in each compound layer, for error paths.
+    keys: Vec<Key<'de>>,
     last_hint: Option<&'static str>,
+    lossy_strings: bool,
+    root_name: RootName,
+    limits: Limits,
+    // Bytes counted towards the allocation limit so far.
+    allocated: usize,
+    marker: PhantomData<&'de ()>,
 }
 
 impl<'de> Deserializer<'de> {
@@ -293,10 +677,306 @@ impl<'de> Deserializer<'de> {
     ///
     /// [`de`]: ./index.html
     pub fn from_bytes(input: &'de [u8]) -> Self {
+        Self::new(Slice::new(input))
+    }
+}
+
+impl<'de, R: Read> Deserializer<'de, Reader<R>> {
+    /// Create Deserializer for a `T` from NBT data in a reader. See
+    /// [`from_reader`] for more information.
+    pub fn from_reader(reader: R) -> Self {
+        Self::new(Reader::new(reader))
+    }
+}
+
+impl<'de, In: Input<'de>> Deserializer<'de, In> {
+    fn new(input: In) -> Self {
         Self {
-            input: InputHelper(input),
+            input,
+            scratch: Vec::new(),
             layers: vec![],
+            keys: vec![],
             last_hint: None,
+            lossy_strings: false,
+            root_name: RootName::Named,
+            limits: Limits::default(),
+            allocated: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Replace invalid sequences in strings with U+FFFD rather than failing
+    /// to deserialize. Off by default.
+    ///
+    /// Strings are decoded from Java's modified UTF-8, which can hold things
+    /// UTF-8 can't such as unpaired surrogates. These turn up in the names of
+    /// items from some mods, and would otherwise make the whole input fail.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use serde::Deserialize;
+    /// use fastnbt::de::Deserializer;
+    ///
+    /// # let data = [10, 0, 0, 8, 0, 1, b'a', 0, 3, 0xed, 0xa0, 0x80, 0];
+    /// let mut de = Deserializer::from_bytes(&data).lossy_strings(true);
+    /// let map: HashMap<String, String> = Deserialize::deserialize(&mut de)?;
+    /// assert_eq!("\u{fffd}", map["a"]);
+    /// # Ok::<(), fastnbt::error::Error>(())
+    /// ```
+    pub fn lossy_strings(mut self, lossy: bool) -> Self {
+        self.lossy_strings = lossy;
+        self
+    }
+
+    /// Set options for the format of the data. See [`DeOpts`].
+    pub fn with_opts(mut self, opts: DeOpts) -> Self {
+        self.root_name = opts.root_name;
+        self.limits = opts.limits;
+        self
+    }
+
+    /// Set limits on what the data can ask for. See [`Limits`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set how deeply compounds and lists can be nested, counting the root
+    /// compound, or `None` for no limit. Deeper input fails with
+    /// [`ErrorKind::DepthLimit`][`crate::error::ErrorKind::DepthLimit`] rather
+    /// than overflowing the stack. Defaults to [`DEFAULT_MAX_DEPTH`].
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use serde::Deserialize;
+    /// use fastnbt::de::Deserializer;
+    /// use fastnbt::error::ErrorKind;
+    /// use fastnbt::Value;
+    ///
+    /// // A compound holding a compound `a`.
+    /// let data = [10, 0, 0, 10, 0, 1, b'a', 0, 0];
+    /// let mut de = Deserializer::from_bytes(&data).max_depth(Some(1));
+    /// let err = Value::deserialize(&mut de).unwrap_err();
+    /// assert!(matches!(err.kind(), ErrorKind::DepthLimit(1)));
+    /// ```
+    pub fn max_depth(mut self, max: Option<usize>) -> Self {
+        self.limits.max_depth = max;
+        self
+    }
+
+    /// Check that another compound or list can be entered without going over
+    /// the depth limit.
+    fn check_depth(&self) -> Result<()> {
+        check_depth(self.layers.len(), self.limits.max_depth)
+    }
+
+    /// Skip over a value, keeping to the depth limit.
+    fn ignore_value(&mut self, tag: Tag) -> Result<()> {
+        self.input
+            .ignore_value(tag, self.layers.len(), self.limits.max_depth)
+    }
+
+    /// Check the size of a list or array against the limits, counting `width`
+    /// bytes for each element towards the allocation limit. Negative sizes are
+    /// left for the caller to deal with.
+    fn check_size(&mut self, size: i32, width: usize) -> Result<()> {
+        let len = match usize::try_from(size) {
+            Ok(len) => len,
+            Err(_) => return Ok(()),
+        };
+        match self.limits.max_len {
+            Some(max) if len > max => Err(Error::limit_exceeded(Limit::Length(max))),
+            _ => self
+                .limits
+                .charge(&mut self.allocated, len.saturating_mul(width)),
+        }
+    }
+
+    /// Skip the name of the root compound, if it has one.
+    fn ignore_root_name(&mut self) -> Result<()> {
+        match self.root_name {
+            RootName::Named => self.input.ignore_str(),
+            RootName::None => Ok(()),
+        }
+    }
+
+    /// The number of bytes of input consumed so far. After an error this is
+    /// roughly where the error happened.
+    pub fn byte_offset(&self) -> u64 {
+        self.input.position()
+    }
+
+    /// Record where an error happened, both the byte offset and the path to
+    /// the value being deserialized. The entry points like [`from_bytes`] do
+    /// this for you.
+    pub fn locate(&self, err: Error) -> Error {
+        err.at_offset(self.byte_offset()).at_path(self.path())
+    }
+
+    /// Visit lists and arrays with no elements as a unit, so that [`Maybe`]
+    /// can tell them apart from ones with elements. Anything else is visited as
+    /// a newtype.
+    ///
+    /// [`Maybe`]: crate::Maybe
+    fn deserialize_maybe<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let tag = match self.layers.last() {
+            Some(Layer::Compound {
+                current_tag: Some(tag),
+                ..
+            }) => *tag,
+            Some(Layer::List { element_tag, .. }) => *element_tag,
+            _ => return visitor.visit_newtype_struct(self),
+        };
+
+        match tag {
+            Tag::List => {
+                let element_tag = self.input.consume_tag()?;
+                let size = self.input.consume_list_size()?;
+                if size == 0 {
+                    return visitor.visit_unit();
+                }
+                visit_list(self, visitor, element_tag, size)
+            }
+            Tag::ByteArray | Tag::IntArray | Tag::LongArray => {
+                let size = self.input.consume_list_size()?;
+                if size == 0 {
+                    return visitor.visit_unit();
+                }
+                self.check_size(size, element_size(tag))?;
+                visitor.visit_map(ArrayWrapperAccess::new(self, size, tag))
+            }
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    /// Consume an IntArray of four ints as a 128 bit integer, the way UUIDs
+    /// are stored.
+    fn consume_int_array_u128(&mut self) -> Result<u128> {
+        match self.layers.last() {
+            Some(Layer::Compound {
+                current_tag: Some(Tag::IntArray),
+                ..
+            })
+            | Some(Layer::List {
+                element_tag: Tag::IntArray,
+                ..
+            }) => {}
+            _ => {
+                return Err(Error::bespoke(
+                    "expected IntArray of 4 ints for 128 bit integer",
+                ))
+            }
+        }
+
+        let size = self.input.consume_list_size()?;
+        if size != 4 {
+            return Err(Error::bespoke(format!(
+                "expected IntArray of 4 ints for 128 bit integer, found {}",
+                size
+            )));
+        }
+
+        let mut ints = [0; 4];
+        for int in &mut ints {
+            *int = self.input.consume_i32()?;
+        }
+        Ok(crate::uuid::from_ints(ints))
+    }
+
+    /// The path to the current value, like `Level.Sections[3].BlockStates`.
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for (depth, layer) in self.layers.iter().enumerate() {
+            match layer {
+                Layer::Compound { .. } => match self.keys.get(depth).map(Key::as_str) {
+                    Some(key) if !key.is_empty() => {
+                        if !path.is_empty() {
+                            path.push('.');
+                        }
+                        path.push_str(key);
+                    }
+                    _ => break,
+                },
+                Layer::List {
+                    len,
+                    remaining_elements,
+                    ..
+                } => {
+                    if remaining_elements >= len {
+                        break;
+                    }
+                    path.push_str(&format!("[{}]", len - remaining_elements - 1));
+                }
+            }
+        }
+        path
+    }
+
+    /// Forget the key of the compound at the top of the stack, before reading
+    /// the next one.
+    fn clear_key(&mut self) {
+        let depth = self.layers.len();
+        if self.keys.len() < depth {
+            self.keys.resize_with(depth, || Key::Borrowed(""));
+        }
+        if let Some(key) = self.keys.get_mut(depth.wrapping_sub(1)) {
+            key.clear();
+        }
+    }
+}
+
+fn str_len(s: &Str) -> usize {
+    match s {
+        Str::Borrowed(s) => s.len(),
+        Str::Copied(s) => s.len(),
+        Str::Owned(s) => s.len(),
+    }
+}
+
+fn record_key<'de>(keys: &mut [Key<'de>], depth: usize, s: &Str<'de, '_>) {
+    let key = match depth.checked_sub(1).and_then(|i| keys.get_mut(i)) {
+        Some(key) => key,
+        None => return,
+    };
+
+    match (s, key) {
+        (Str::Borrowed(s), key) => *key = Key::Borrowed(s),
+        (Str::Copied(s), Key::Copied(buf)) => {
+            buf.clear();
+            buf.push_str(s);
+        }
+        (Str::Owned(s), Key::Copied(buf)) => {
+            buf.clear();
+            buf.push_str(s);
+        }
+        (Str::Copied(s), key) => *key = Key::Copied(s.to_string()),
+        (Str::Owned(s), key) => *key = Key::Copied(s.clone()),
+    }
+}
+
+/// A key kept for error paths. Keys borrowed from the input are kept as they
+/// are, so reading from a slice doesn't copy them. Others have to be copied,
+/// into a buffer that is reused for the next key at the same depth.
+enum Key<'de> {
+    Borrowed(&'de str),
+    Copied(String),
+}
+
+impl Key<'_> {
+    fn as_str(&self) -> &str {
+        match self {
+            Key::Borrowed(s) => s,
+            Key::Copied(s) => s,
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Key::Borrowed(s) => *s = "",
+            Key::Copied(s) => s.clear(),
         }
     }
 }
@@ -309,6 +989,7 @@ enum Stage {
 
 enum Layer {
     List {
+        len: i32,
         remaining_elements: i32, // would make more sense as usize, but format is i32.
         element_tag: Tag,
     },
@@ -318,38 +999,53 @@ enum Layer {
     },
 }
 
-/// Without this we would not be able to implement helper functions for the
-/// input. If we wrote the helper functions as part of the Deserializer impl, it
-/// would force borrowing the entire deserializer mutably. This helper allows us
-/// to borrow just the input, making us free to also borrow/mutate the layers.
-pub(crate) struct InputHelper<'de>(pub(crate) &'de [u8]);
-
-fn visit_cow_str<'de, V>(v: V, s: Cow<'de, str>) -> Result<V::Value>
+fn visit_str<'de, V>(v: V, s: Str<'de, '_>) -> Result<V::Value>
 where
     V: de::Visitor<'de>,
 {
     match s {
-        Cow::Borrowed(s) => v.visit_borrowed_str(s),
-        Cow::Owned(s) => v.visit_string(s),
+        Str::Borrowed(s) => v.visit_borrowed_str(s),
+        Str::Copied(s) => v.visit_str(s),
+        Str::Owned(s) => v.visit_string(s),
     }
 }
 
-fn consume_value<'de, V>(de: &mut Deserializer<'de>, visitor: V, tag: Tag) -> Result<V::Value>
+pub(crate) fn visit_bytes<'de, V>(v: V, bs: Reference<'de, '_, [u8]>) -> Result<V::Value>
 where
     V: de::Visitor<'de>,
+{
+    match bs {
+        Reference::Borrowed(bs) => v.visit_borrowed_bytes(bs),
+        Reference::Copied(bs) => v.visit_bytes(bs),
+    }
+}
+
+fn consume_value<'de, In, V>(
+    de: &mut Deserializer<'de, In>,
+    visitor: V,
+    tag: Tag,
+) -> Result<V::Value>
+where
+    In: Input<'de>,
+    V: de::Visitor<'de>,
 {
     let last_hint = de.last_hint;
     de.last_hint = None;
 
     match tag {
-        Tag::Byte => visitor.visit_i8(de.input.0.read_i8()?),
-        Tag::Short => visitor.visit_i16(de.input.0.read_i16::<BigEndian>()?),
-        Tag::Int => visitor.visit_i32(de.input.0.read_i32::<BigEndian>()?),
-        Tag::Long => visitor.visit_i64(de.input.0.read_i64::<BigEndian>()?),
-        Tag::String => visit_cow_str(visitor, de.input.consume_size_prefixed_string()?),
-        Tag::Float => visitor.visit_f32(de.input.consume_float()?),
-        Tag::Double => visitor.visit_f64(de.input.consume_double()?),
+        Tag::Byte => visitor.visit_i8(de.input.consume_byte()? as i8),
+        Tag::Short => visitor.visit_i16(de.input.consume_i16()?),
+        Tag::Int => visitor.visit_i32(de.input.consume_i32()?),
+        Tag::Long => visitor.visit_i64(de.input.consume_i64()?),
+        Tag::String => {
+            let s = de.input.consume_str(&mut de.scratch, de.lossy_strings)?;
+            de.limits.charge(&mut de.allocated, str_len(&s))?;
+            visit_str(visitor, s)
+        }
+        Tag::Float => visitor.visit_f32(de.input.consume_f32()?),
+        Tag::Double => visitor.visit_f64(de.input.consume_f64()?),
         Tag::Compound => {
+            de.check_depth()?;
             de.layers.push(Layer::Compound {
                 current_tag: None,
                 stage: Stage::Tag,
@@ -361,128 +1057,87 @@ where
             let element_tag = de.input.consume_tag()?;
             let size = de.input.consume_list_size()?;
 
-            // End values have no payload. An end tag on it's own is the payload
-            // of an empty compound. A logical interpretation is that this could
-            // be a list of zero-sized units, but this mean an easy short
-            // malicious payload of a massive list taking up lots of memory (as
-            // the Value type's unit variant would not be zero sized.
-            //
-            // Some old chunks store empty lists as as 'list of end', so if the
-            // size is zero we let it slide.
-            if element_tag == Tag::End && size != 0 {
-                return Err(Error::bespoke(
-                    "unexpected list of type 'end', which is not supported".into(),
-                ));
-            }
-
-            de.layers.push(Layer::List {
-                remaining_elements: size,
-                element_tag,
-            });
-
-            visitor.visit_seq(ListAccess::new(de, size))
+            visit_list(de, visitor, element_tag, size)
         }
         Tag::ByteArray | Tag::IntArray | Tag::LongArray => {
             if last_hint == Some("seq") {
                 return Err(Error::bespoke(
-                    "expected NBT Array, found seq: use ByteArray, IntArray or LongArray types"
-                        .into(),
+                    "expected NBT Array, found seq: use ByteArray, IntArray or LongArray types",
                 ));
             }
             let size = de.input.consume_list_size()?;
+            de.check_size(size, element_size(tag))?;
             visitor.visit_map(ArrayWrapperAccess::new(de, size, tag))
         }
         // This would really only occur when we encounter a list where the
         // element type is 'End', but we specifically handle that case, so we
         // should never get here.
         Tag::End => Err(Error::bespoke(
-            "unexpected end tag, was expecting payload of a value".into(),
+            "unexpected end tag, was expecting payload of a value",
         )),
     }
 }
 
-impl<'de> InputHelper<'de> {
-    // Safely get a subslice, erroring if there's not enough input.
-    pub(crate) fn subslice(&self, r: Range<usize>) -> Result<&'de [u8]> {
-        if r.end <= self.0.len() {
-            Ok(&self.0[r])
-        } else {
-            Err(Error::unexpected_eof())
-        }
-    }
-
+/// Helpers for reading NBT structure from an input. These are implemented as
+/// an extension trait rather than methods on the Deserializer so that we can
+/// borrow just the input, leaving us free to also borrow/mutate the layers.
+pub(crate) trait InputExt<'de>: Input<'de> {
     fn consume_tag(&mut self) -> Result<Tag> {
-        let tag_byte = self.0.read_u8()?;
+        let tag_byte = self.consume_byte()?;
         Tag::try_from(tag_byte).map_err(|_| Error::invalid_tag(tag_byte))
     }
 
-    fn consume_name(&mut self) -> Result<Cow<'de, str>> {
-        self.consume_size_prefixed_string()
-    }
-
-    fn consume_size_prefixed_string(&mut self) -> Result<Cow<'de, str>> {
-        let len = self.0.read_u16::<BigEndian>()? as usize;
-        let str_data = self.subslice(0..len)?;
-        let s = cesu8::from_java_cesu8(str_data)
-            .map_err(|_| Error::nonunicode_string(&self.0[..len]))?;
-
-        self.0 = &self.0[len..];
-        Ok(s)
-    }
-
-    fn consume_bytes_unchecked(&mut self, size: i32) -> Result<&'de [u8]> {
-        let size: usize = size.try_into().map_err(|_| Error::invalid_size(size))?;
-        let bs = &self.0[..size];
-        self.0 = &self.0[size..];
-        Ok(bs)
-    }
-
     fn consume_list_size(&mut self) -> Result<i32> {
-        Ok(self.0.read_i32::<BigEndian>()?)
+        self.consume_i32()
     }
 
-    fn consume_float(&mut self) -> Result<f32> {
-        Ok(self.0.read_f32::<BigEndian>()?)
+    /// Consume the size prefix of an array whose elements are each `width`
+    /// bytes, returning the number of bytes the elements take up.
+    fn consume_array_len(&mut self, width: usize) -> Result<usize> {
+        let size = self.consume_list_size()?;
+        array_len(size, width)
     }
 
-    fn consume_double(&mut self) -> Result<f64> {
-        Ok(self.0.read_f64::<BigEndian>()?)
-    }
+    /// Skip over a value of type `tag`, found inside `depth` compounds and
+    /// lists.
+    fn ignore_value(&mut self, tag: Tag, depth: usize, max_depth: Option<usize>) -> Result<()> {
+        if let Tag::Compound | Tag::List = tag {
+            check_depth(depth, max_depth)?;
+        }
 
-    fn ignore_value(&mut self, tag: Tag) -> Result<()> {
         match tag {
             Tag::Byte => {
-                self.0.read_i8()?;
+                self.consume_byte()?;
             }
             Tag::Short => {
-                self.0.read_i16::<BigEndian>()?;
+                self.consume_i16()?;
             }
             Tag::Int => {
-                self.0.read_i32::<BigEndian>()?;
+                self.consume_i32()?;
             }
             Tag::Long => {
-                self.0.read_i64::<BigEndian>()?;
+                self.consume_i64()?;
             }
             Tag::Float => {
-                self.consume_float()?;
+                self.consume_f32()?;
             }
             Tag::Double => {
-                self.consume_double()?;
+                self.consume_f64()?;
             }
             Tag::String => {
-                self.consume_size_prefixed_string()?;
+                self.ignore_str()?;
             }
             Tag::ByteArray => {
-                let size = self.consume_list_size()?;
-                self.consume_bytes_unchecked(size)?;
+                let len = self.consume_array_len(1)?;
+                self.ignore_bytes(len)?;
             }
             Tag::IntArray => {
-                let size = self.consume_list_size()?;
-                self.consume_bytes_unchecked(size * 4)?;
+                let len = self.consume_array_len(4)?;
+                self.ignore_bytes(len)?;
             }
             Tag::LongArray => {
-                let size = self.consume_list_size()?;
-                self.consume_bytes_unchecked(size * 8)?;
+                let len = self.consume_array_len(8)?;
+                self.ignore_bytes(len)?;
             }
             Tag::Compound => {
                 // Need to loop and ignore each value until we reach an end tag.
@@ -494,26 +1149,28 @@ impl<'de> InputHelper<'de> {
                         break;
                     }
 
-                    self.consume_name()?;
-                    self.ignore_value(tag)?;
+                    self.ignore_str()?;
+                    self.ignore_value(tag, depth + 1, max_depth)?;
                 }
             }
             Tag::List => {
                 let element_tag = self.consume_tag()?;
                 let size = self.consume_list_size()?;
+                if element_tag == Tag::End && size > 0 {
+                    return Err(Error::bespoke(
+                        "unexpected list of type 'end', which is not supported",
+                    ));
+                }
                 for _ in 0..size {
-                    self.ignore_value(element_tag)?;
+                    self.ignore_value(element_tag, depth + 1, max_depth)?;
                 }
             }
             Tag::End => {
-                // If we are trying to ignore a list of empty compounds, that
-                // list might be indicated by a series of End tags. If this
-                // occurs then we should end the Compound branch of this match
-                // statement, where the end tag will be consumed. So we should
-                // never reach here.
-                //
-                // TODO: Write an explicit test for ignored list of compound.
-                unreachable!()
+                // End tags have no payload, and lists of them can only be
+                // empty. Anything else is not valid NBT.
+                return Err(Error::bespoke(
+                    "unexpected end tag where a value was expected",
+                ));
             }
         }
 
@@ -521,7 +1178,80 @@ impl<'de> InputHelper<'de> {
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de, T: Input<'de>> InputExt<'de> for T {}
+
+/// Check that a compound or list can be entered when already inside `depth`
+/// of them.
+fn check_depth(depth: usize, max_depth: Option<usize>) -> Result<()> {
+    match max_depth {
+        Some(max) if depth >= max => Err(Error::depth_limit(max)),
+        _ => Ok(()),
+    }
+}
+
+/// Get the length in bytes of `size` elements that are each `width` bytes.
+pub(crate) fn array_len(size: i32, width: usize) -> Result<usize> {
+    let len: usize = size.try_into().map_err(|_| Error::invalid_size(size))?;
+    len.checked_mul(width)
+        .ok_or_else(|| Error::invalid_size(size))
+}
+
+fn visit_list<'de, In, V>(
+    de: &mut Deserializer<'de, In>,
+    visitor: V,
+    element_tag: Tag,
+    size: i32,
+) -> Result<V::Value>
+where
+    In: Input<'de>,
+    V: de::Visitor<'de>,
+{
+    // End values have no payload. An end tag on it's own is the payload
+    // of an empty compound. A logical interpretation is that this could
+    // be a list of zero-sized units, but this mean an easy short
+    // malicious payload of a massive list taking up lots of memory (as
+    // the Value type's unit variant would not be zero sized.
+    //
+    // Some old chunks store empty lists as as 'list of end', so if the
+    // size is zero we let it slide.
+    if element_tag == Tag::End && size != 0 {
+        return Err(Error::bespoke(
+            "unexpected list of type 'end', which is not supported",
+        ));
+    }
+
+    de.check_depth()?;
+    de.check_size(size, LIST_ELEMENT_SIZE)?;
+    de.layers.push(Layer::List {
+        len: size,
+        remaining_elements: size,
+        element_tag,
+    });
+    let depth = de.layers.len();
+
+    let value = visitor.visit_seq(ListAccess::new(de, size))?;
+
+    // Visitors for fixed size types like arrays stop once they have
+    // enough elements, without asking for the end of the list. In that
+    // case our layer is still here and needs removing.
+    if de.layers.len() == depth {
+        if let Some(Layer::List {
+            remaining_elements, ..
+        }) = de.layers.pop()
+        {
+            if remaining_elements != 0 {
+                return Err(Error::bespoke(format!(
+                    "list had {} more elements than expected",
+                    remaining_elements
+                )));
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+impl<'de, 'a, In: Input<'de>> de::Deserializer<'de> for &'a mut Deserializer<'de, In> {
     type Error = Error;
 
     forward_to_deserialize_any!(struct map identifier i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 str string tuple);
@@ -534,6 +1264,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
+        let depth = self.layers.len();
         let tag = match self.layers.last_mut().as_mut() {
             None => {
                 // No existing layers. This means we should be at the start of
@@ -544,8 +1275,9 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                     return Err(Error::no_root_compound());
                 }
 
-                self.input.consume_name()?;
+                self.ignore_root_name()?;
 
+                self.check_depth()?;
                 self.layers.push(Layer::Compound {
                     current_tag: None,
                     stage: Stage::Tag,
@@ -563,11 +1295,21 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                         Stage::Tag => {
                             *current_tag = Some(self.input.consume_tag()?);
                             *stage = Stage::Value;
-                            return visit_cow_str(visitor, self.input.consume_name()?);
+                            let key = self
+                                .input
+                                .consume_str(&mut self.scratch, self.lossy_strings)?;
+                            self.limits.charge(&mut self.allocated, str_len(&key))?;
+                            record_key(&mut self.keys, depth, &key);
+                            return visit_str(visitor, key);
                         }
                         Stage::Name => {
                             *stage = Stage::Value;
-                            return visit_cow_str(visitor, self.input.consume_name()?);
+                            let key = self
+                                .input
+                                .consume_str(&mut self.scratch, self.lossy_strings)?;
+                            self.limits.charge(&mut self.allocated, str_len(&key))?;
+                            record_key(&mut self.keys, depth, &key);
+                            return visit_str(visitor, key);
                         }
                         Stage::Value => {
                             *stage = Stage::Tag;
@@ -576,10 +1318,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
                             current_tag.unwrap()
                         }
                     },
-                    Layer::List {
-                        remaining_elements: _,
-                        element_tag,
-                    } => *element_tag,
+                    Layer::List { element_tag, .. } => *element_tag,
                 }
             }
         };
@@ -587,29 +1326,39 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         consume_value(self, visitor, tag)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_i128(self.consume_int_array_u128()? as i128)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_u128(self.consume_int_array_u128()?)
+    }
+
     #[inline]
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
         let tag = match self.layers.last() {
-            Some(Layer::Compound { current_tag, .. }) => current_tag.as_ref().ok_or_else(|| {
-                Error::bespoke("deserialize bool: did not know value's tag".to_string())
-            }),
+            Some(Layer::Compound { current_tag, .. }) => current_tag
+                .as_ref()
+                .ok_or_else(|| Error::bespoke("deserialize bool: did not know value's tag")),
             Some(Layer::List { element_tag, .. }) => Ok(element_tag),
-            None => Err(Error::bespoke(
-                "deserialize bool: not in compound or list".to_string(),
-            )),
+            None => Err(Error::bespoke("deserialize bool: not in compound or list")),
         }?;
 
         match tag {
-            Tag::Byte => visitor.visit_bool(self.input.0.read_i8()? != 0),
-            Tag::Short => visitor.visit_bool(self.input.0.read_i16::<BigEndian>()? != 0),
-            Tag::Int => visitor.visit_bool(self.input.0.read_i32::<BigEndian>()? != 0),
-            Tag::Long => visitor.visit_bool(self.input.0.read_i64::<BigEndian>()? != 0),
-            _ => Err(Error::bespoke(
-                "deserialize bool: expected integral value".to_string(),
-            )),
+            Tag::Byte => visitor.visit_bool(self.input.consume_byte()? != 0),
+            Tag::Short => visitor.visit_bool(self.input.consume_i16()? != 0),
+            Tag::Int => visitor.visit_bool(self.input.consume_i32()? != 0),
+            Tag::Long => visitor.visit_bool(self.input.consume_i64()? != 0),
+            _ => Err(Error::bespoke("deserialize bool: expected integral value")),
         }
     }
 
@@ -626,76 +1375,59 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        let layer = self.layers.last().ok_or_else(|| Error::bespoke(
-            "expected bytes, but not in a compound or list".to_owned(),
-        ))?;
-
-        match layer {
-            Layer::List {
-                remaining_elements,
-                element_tag,
-                ..
-            } => Err(Error::bespoke(format!(
-                "expected bytes, got [{:?}; {}]",
-                element_tag, remaining_elements
-            ))),
-            Layer::Compound {
-                current_tag: None, ..
-            } => Err(Error::bespoke(
-                "expected bytes, but do not know what to deserialize".to_owned(),
+        let tag = match self.layers.last() {
+            Some(Layer::Compound { current_tag, .. }) => current_tag.ok_or_else(|| {
+                Error::bespoke("expected bytes, but do not know what to deserialize")
+            }),
+            Some(Layer::List { element_tag, .. }) => Ok(*element_tag),
+            None => Err(Error::bespoke(
+                "expected bytes, but not in a compound or list",
             )),
-            Layer::Compound {
-                current_tag: Some(Tag::List),
-                ..
-            } => {
+        }?;
+
+        match tag {
+            Tag::List => {
                 let el = self.input.consume_tag()?;
                 let size = self.input.consume_list_size()?;
 
-                match el {
-                    Tag::Byte => {
-                        let bs = self.input.consume_bytes_unchecked(size)?;
-                        visitor.visit_borrowed_bytes(bs)
-                    }
-                    Tag::Short => {
-                        let bs = self.input.consume_bytes_unchecked(size * 2)?;
-                        visitor.visit_borrowed_bytes(bs)
-                    }
-                    Tag::Int => {
-                        let bs = self.input.consume_bytes_unchecked(size * 4)?;
-                        visitor.visit_borrowed_bytes(bs)
+                let width = match el {
+                    Tag::Byte => 1,
+                    Tag::Short => 2,
+                    Tag::Int => 4,
+                    Tag::Long => 8,
+                    _ => {
+                        return Err(Error::bespoke(format!(
+                            "expected bytes, got [{:?}; {}]",
+                            el, size
+                        )))
                     }
-                    Tag::Long => {
-                        let bs = self.input.consume_bytes_unchecked(size * 8)?;
-                        visitor.visit_borrowed_bytes(bs)
-                    }
-                    _ => Err(Error::bespoke(format!(
-                        "expected bytes, got [{:?}; {}]",
-                        el, size
-                    ))),
-                }
+                };
+
+                self.check_size(size, width)?;
+                let len = array_len(size, width)?;
+                visit_bytes(visitor, self.input.consume_bytes(len, &mut self.scratch)?)
             }
-            Layer::Compound {
-                current_tag: Some(tag),
-                ..
-            } => match tag {
-                Tag::ByteArray => {
-                    let size = self.input.consume_list_size()?;
-                    let bs = self.input.consume_bytes_unchecked(size)?;
-                    visitor.visit_borrowed_bytes(bs)
-                }
-                Tag::IntArray => {
-                    let size = self.input.consume_list_size()?;
-                    let bs = self.input.consume_bytes_unchecked(size * 4i32)?;
-                    visitor.visit_borrowed_bytes(bs)
-                }
-                // This allows us to borrow blockstates rather than copy them.
-                Tag::LongArray => {
-                    let size = self.input.consume_list_size()?;
-                    let bs = self.input.consume_bytes_unchecked(size * 8i32)?;
-                    visitor.visit_borrowed_bytes(bs)
-                }
-                _ => Err(Error::bespoke(format!("expected bytes, found {:?}", tag))),
-            },
+            Tag::String => {
+                // The raw modified UTF-8 data. Unlike &str this can always be
+                // borrowed, as no decoding has to happen.
+                let len = self.input.consume_i16()? as u16 as usize;
+                self.limits.charge(&mut self.allocated, len)?;
+                visit_bytes(visitor, self.input.consume_bytes(len, &mut self.scratch)?)
+            }
+            Tag::ByteArray | Tag::IntArray | Tag::LongArray => {
+                let width = match tag {
+                    Tag::ByteArray => 1,
+                    Tag::IntArray => 4,
+                    // This allows us to borrow blockstates rather than copy them.
+                    _ => 8,
+                };
+
+                let size = self.input.consume_list_size()?;
+                self.check_size(size, width)?;
+                let len = array_len(size, width)?;
+                visit_bytes(visitor, self.input.consume_bytes(len, &mut self.scratch)?)
+            }
+            _ => Err(Error::bespoke(format!("expected bytes, found {:?}", tag))),
         }
     }
 
@@ -722,16 +1454,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         V: de::Visitor<'de>,
     {
         let tag = match self.layers.last() {
-            Some(Layer::Compound { current_tag, .. }) => current_tag.as_ref().ok_or_else(|| {
-                Error::bespoke("deserialize unit: did not know value's tag".to_string())
-            }),
+            Some(Layer::Compound { current_tag, .. }) => current_tag
+                .as_ref()
+                .ok_or_else(|| Error::bespoke("deserialize unit: did not know value's tag")),
             Some(Layer::List { element_tag, .. }) => Ok(element_tag),
-            None => Err(Error::bespoke(
-                "deserialize_unit: not in compound or list".to_string(),
-            )),
+            None => Err(Error::bespoke("deserialize_unit: not in compound or list")),
         }?;
 
-        self.input.ignore_value(*tag)?;
+        self.ignore_value(*tag)?;
         visitor.visit_unit()
     }
 
@@ -744,10 +1474,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     #[inline]
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        if name == MAYBE_TOKEN {
+            return self.deserialize_maybe(visitor);
+        }
+
         visitor.visit_newtype_struct(self)
     }
 
@@ -786,31 +1520,28 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         // The last layer should tell us what value we're expecting.
         // We have already read the tag and name. This is the payload.
 
-        let layer = self
-            .layers
-            .last()
-            .ok_or_else(|| {
-                Error::bespoke(
-                    "expected unwanted payload, but not in a compound or list".to_owned(),
-                )
-            })?;
-
-        match layer {
-            Layer::Compound {
+        match self.layers.last() {
+            None => {
+                // Ignoring the whole input, which must still be a compound.
+                if self.input.consume_tag()? != Tag::Compound {
+                    return Err(Error::no_root_compound());
+                }
+                self.ignore_root_name()?;
+                self.ignore_value(Tag::Compound)?;
+            }
+            Some(Layer::Compound {
                 current_tag: Some(tag),
                 stage: Stage::Value,
-            } => {
-                self.input.ignore_value(*tag)?;
+            }) => {
+                self.ignore_value(*tag)?;
             }
-            Layer::Compound {
-                current_tag: _,
-                stage: _,
-            } => todo!("compound(none)"), // ???
-            Layer::List {
-                remaining_elements: _,
-                element_tag: _,
-            } => {
-                todo!();
+            Some(Layer::Compound { .. }) => {
+                return Err(Error::bespoke(
+                    "expected unwanted payload, but compound is not at a value",
+                ))
+            }
+            Some(Layer::List { element_tag, .. }) => {
+                self.ignore_value(*element_tag)?;
             }
         }
 
@@ -828,17 +1559,17 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 }
 
-struct CompoundAccess<'a, 'de> {
-    de: &'a mut Deserializer<'de>,
+struct CompoundAccess<'a, 'de, In: Input<'de>> {
+    de: &'a mut Deserializer<'de, In>,
 }
 
-impl<'a, 'de> CompoundAccess<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
+impl<'a, 'de, In: Input<'de>> CompoundAccess<'a, 'de, In> {
+    fn new(de: &'a mut Deserializer<'de, In>) -> Self {
         Self { de }
     }
 }
 
-impl<'a, 'de> de::MapAccess<'de> for CompoundAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::MapAccess<'de> for CompoundAccess<'a, 'de, In> {
     type Error = Error;
 
     #[inline]
@@ -846,6 +1577,8 @@ impl<'a, 'de> de::MapAccess<'de> for CompoundAccess<'a, 'de> {
     where
         K: serde::de::DeserializeSeed<'de>,
     {
+        self.de.clear_key();
+
         // Need to read the tag of the key.
         let tag = self.de.input.consume_tag()?;
 
@@ -875,18 +1608,18 @@ impl<'a, 'de> de::MapAccess<'de> for CompoundAccess<'a, 'de> {
     }
 }
 
-struct ListAccess<'a, 'de> {
-    de: &'a mut Deserializer<'de>,
+struct ListAccess<'a, 'de, In: Input<'de>> {
+    de: &'a mut Deserializer<'de, In>,
     hint: i32,
 }
 
-impl<'a, 'de> ListAccess<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, hint: i32) -> Self {
+impl<'a, 'de, In: Input<'de>> ListAccess<'a, 'de, In> {
+    fn new(de: &'a mut Deserializer<'de, In>, hint: i32) -> Self {
         Self { de, hint }
     }
 }
 
-impl<'a, 'de> de::SeqAccess<'de> for ListAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::SeqAccess<'de> for ListAccess<'a, 'de, In> {
     type Error = Error;
 
     fn size_hint(&self) -> Option<usize> {
@@ -902,12 +1635,11 @@ impl<'a, 'de> de::SeqAccess<'de> for ListAccess<'a, 'de> {
             .de
             .layers
             .last_mut()
-            .ok_or_else(|| Error::bespoke("expected to be in list".to_owned()))?;
+            .ok_or_else(|| Error::bespoke("expected to be in list"))?;
 
         match layer {
             Layer::List {
-                remaining_elements,
-                element_tag: _,
+                remaining_elements, ..
             } => {
                 if *remaining_elements > 0 {
                     *remaining_elements -= 1;
@@ -929,11 +1661,11 @@ impl<'a, 'de> de::SeqAccess<'de> for ListAccess<'a, 'de> {
     }
 }
 
-struct UnitVariantAccess<'a, 'de> {
-    de: &'a mut Deserializer<'de>,
+struct UnitVariantAccess<'a, 'de, In: Input<'de>> {
+    de: &'a mut Deserializer<'de, In>,
 }
 
-impl<'a, 'de> de::EnumAccess<'de> for UnitVariantAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::EnumAccess<'de> for UnitVariantAccess<'a, 'de, In> {
     type Error = Error;
     type Variant = Self;
 
@@ -946,7 +1678,7 @@ impl<'a, 'de> de::EnumAccess<'de> for UnitVariantAccess<'a, 'de> {
     }
 }
 
-impl<'a, 'de> de::VariantAccess<'de> for UnitVariantAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::VariantAccess<'de> for UnitVariantAccess<'a, 'de, In> {
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
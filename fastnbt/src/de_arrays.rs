@@ -1,13 +1,13 @@
 use core::panic;
 use std::convert::TryInto;
-use std::num::TryFromIntError;
 
-use byteorder::{BigEndian, ReadBytesExt};
 use serde::de::{self, IntoDeserializer};
 use serde::forward_to_deserialize_any;
 
+use crate::de::{array_len, visit_bytes, Deserializer};
 use crate::error::{Error, Result};
-use crate::{de::Deserializer, Tag};
+use crate::input::Input;
+use crate::Tag;
 
 enum ArrWrapStage {
     Tag,
@@ -15,15 +15,15 @@ enum ArrWrapStage {
     Done,
 }
 
-pub(crate) struct ArrayWrapperAccess<'a, 'de> {
-    de: &'a mut Deserializer<'de>,
+pub(crate) struct ArrayWrapperAccess<'a, 'de, In: Input<'de>> {
+    de: &'a mut Deserializer<'de, In>,
     stage: ArrWrapStage,
     tag: Tag,
     size: i32,
 }
 
-impl<'a, 'de> ArrayWrapperAccess<'a, 'de> {
-    pub(crate) fn new(de: &'a mut Deserializer<'de>, size: i32, tag: Tag) -> Self {
+impl<'a, 'de, In: Input<'de>> ArrayWrapperAccess<'a, 'de, In> {
+    pub(crate) fn new(de: &'a mut Deserializer<'de, In>, size: i32, tag: Tag) -> Self {
         Self {
             de,
             tag,
@@ -33,7 +33,7 @@ impl<'a, 'de> ArrayWrapperAccess<'a, 'de> {
     }
 }
 
-impl<'a, 'de> de::MapAccess<'de> for ArrayWrapperAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::MapAccess<'de> for ArrayWrapperAccess<'a, 'de, In> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
@@ -70,15 +70,15 @@ impl<'a, 'de> de::MapAccess<'de> for ArrayWrapperAccess<'a, 'de> {
     }
 }
 
-struct ArrayAccess<'a, 'de> {
-    de: &'a mut Deserializer<'de>,
+struct ArrayAccess<'a, 'de, In: Input<'de>> {
+    de: &'a mut Deserializer<'de, In>,
     hint: i32,
     remaining: i32,
     tag: Tag,
 }
 
-impl<'a, 'de> ArrayAccess<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>, tag: Tag, size: i32) -> Self {
+impl<'a, 'de, In: Input<'de>> ArrayAccess<'a, 'de, In> {
+    fn new(de: &'a mut Deserializer<'de, In>, tag: Tag, size: i32) -> Self {
         Self {
             de,
             hint: size,
@@ -88,7 +88,7 @@ impl<'a, 'de> ArrayAccess<'a, 'de> {
     }
 }
 
-impl<'a, 'de> de::SeqAccess<'de> for ArrayAccess<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> de::SeqAccess<'de> for ArrayAccess<'a, 'de, In> {
     type Error = Error;
 
     fn size_hint(&self) -> Option<usize> {
@@ -112,12 +112,12 @@ impl<'a, 'de> de::SeqAccess<'de> for ArrayAccess<'a, 'de> {
     }
 }
 
-pub(crate) struct ArrayElementDeserializer<'a, 'de> {
-    pub(crate) de: &'a mut Deserializer<'de>,
+pub(crate) struct ArrayElementDeserializer<'a, 'de, In: Input<'de>> {
+    pub(crate) de: &'a mut Deserializer<'de, In>,
     pub(crate) tag: Tag,
 }
 
-impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de, In> {
     type Error = Error;
 
     forward_to_deserialize_any! {
@@ -147,7 +147,7 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_i8()?;
+        let val = self.de.input.consume_byte()? as i8;
         visitor.visit_i8(val)
     }
 
@@ -155,7 +155,7 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_u8()?;
+        let val = self.de.input.consume_byte()?;
         visitor.visit_u8(val)
     }
 
@@ -163,7 +163,7 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_i32::<BigEndian>()?;
+        let val = self.de.input.consume_i32()?;
         visitor.visit_i32(val)
     }
 
@@ -171,7 +171,7 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_u32::<BigEndian>()?;
+        let val = self.de.input.consume_i32()? as u32;
         visitor.visit_u32(val)
     }
 
@@ -179,7 +179,7 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_i64::<BigEndian>()?;
+        let val = self.de.input.consume_i64()?;
         visitor.visit_i64(val)
     }
 
@@ -187,20 +187,20 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayElementDeserializer<'a, 'de> {
     where
         V: de::Visitor<'de>,
     {
-        let val = self.de.input.0.read_u64::<BigEndian>()?;
+        let val = self.de.input.consume_i64()? as u64;
         visitor.visit_u64(val)
     }
 }
 
-pub(crate) struct ArrayDeserializer<'a, 'de> {
-    pub(crate) de: &'a mut Deserializer<'de>,
+pub(crate) struct ArrayDeserializer<'a, 'de, In: Input<'de>> {
+    pub(crate) de: &'a mut Deserializer<'de, In>,
     pub(crate) size: i32,
     pub(crate) tag: Tag,
 }
 
 // Job is to start deserializing a Seq which is a *Array type, and to actually
 // deserialize the elements.
-impl<'a, 'de> serde::Deserializer<'de> for ArrayDeserializer<'a, 'de> {
+impl<'a, 'de, In: Input<'de>> serde::Deserializer<'de> for ArrayDeserializer<'a, 'de, In> {
     type Error = Error;
 
     forward_to_deserialize_any! {
@@ -221,20 +221,16 @@ impl<'a, 'de> serde::Deserializer<'de> for ArrayDeserializer<'a, 'de> {
         V: de::Visitor<'de>,
     {
         // This code path happens when we're deserializing borrow:*Array types.
-        let len: usize = self
-            .size
-            .try_into()
-            .map_err(|e: TryFromIntError| Error::bespoke(e.to_string()))?;
-
-        let total_bytes = len * element_size(self.tag);
-
-        let res = &self.de.input.subslice(0..total_bytes)?;
-        self.de.input.0 = &self.de.input.0[total_bytes..];
-        visitor.visit_borrowed_bytes(res)
+        let total_bytes = array_len(self.size, element_size(self.tag))?;
+        let bs = self
+            .de
+            .input
+            .consume_bytes(total_bytes, &mut self.de.scratch)?;
+        visit_bytes(visitor, bs)
     }
 }
 
-fn element_size(tag: Tag) -> usize {
+pub(crate) fn element_size(tag: Tag) -> usize {
     match tag {
         Tag::ByteArray => std::mem::size_of::<i8>(),
         Tag::IntArray => std::mem::size_of::<i32>(),
@@ -1,58 +1,220 @@
-//! Contains the Error and Result type used by the deserializer.
+//! Contains the Error and Result type used by the serializer and deserializer.
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::sync::Arc;
 
-/// Various errors that can occur during deserialization.
+/// Various errors that can occur during serialization or deserialization.
+///
+/// Errors from the deserializer record the byte offset into the input where
+/// they happened, see [`offset`](Error::offset), and the path to the value
+/// being deserialized, see [`path`](Error::path). Creating an error does not
+/// allocate for most kinds, so failing to parse is cheap when scanning lots of
+/// data where some of it is expected to be bad.
 #[derive(Debug, Clone)]
-pub struct Error(String);
+pub struct Error {
+    kind: ErrorKind,
+    offset: Option<u64>,
+    path: Option<String>,
+}
+
+/// The kind of an [`Error`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended part way through a value.
+    UnexpectedEof,
+
+    /// A byte that should have been a tag isn't one of the NBT tags.
+    InvalidTag(u8),
+
+    /// The size of a list or array is negative, or too large.
+    InvalidSize(i32),
+
+    /// The input doesn't start with a compound, or the value being serialized
+    /// isn't one. NBT always has a compound at the root.
+    NoRootCompound,
+
+    /// A string isn't valid modified UTF-8. See
+    /// [`Deserializer::lossy_strings`][`crate::de::Deserializer::lossy_strings`]
+    /// to replace invalid parts of strings instead.
+    NonunicodeString,
+
+    /// A field the type being deserialized needs is missing.
+    MissingField(&'static str),
+
+    /// Reading the input or writing the output failed.
+    Io(Arc<std::io::Error>),
+
+    /// Compounds and lists were nested deeper than the deserializer allows,
+    /// see [`Deserializer::max_depth`][`crate::de::Deserializer::max_depth`].
+    /// Holds the limit that was set.
+    DepthLimit(usize),
+
+    /// The input went over one of the deserializer's
+    /// [`Limits`][`crate::de::Limits`].
+    LimitExceeded(Limit),
+
+    /// Anything else, such as an error from a `Deserialize` implementation.
+    Message(Cow<'static, str>),
+}
+
+/// Which of the deserializer's [`Limits`][`crate::de::Limits`] was exceeded,
+/// along with the limit that was set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Limit {
+    /// A list or array had too many elements.
+    Length(usize),
+
+    /// The data asked for too much memory in total.
+    Allocation(usize),
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Length(max) => write!(f, "length limit of {}", max),
+            Limit::Allocation(max) => write!(f, "allocation limit of {} bytes", max),
+        }
+    }
+}
 
 /// Convenience type for Result.
 pub type Result<T> = std::result::Result<T, Error>;
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match &self.kind {
+            ErrorKind::UnexpectedEof => f.write_str("eof: unexpectedly ran out of input")?,
+            ErrorKind::InvalidTag(tag) => write!(f, "invalid nbt tag value: {}", tag)?,
+            ErrorKind::InvalidSize(size) => write!(f, "invalid nbt list/array size: {}", size)?,
+            ErrorKind::NoRootCompound => f.write_str("invalid nbt: no root compound")?,
+            ErrorKind::NonunicodeString => f.write_str("invalid nbt string: nonunicode")?,
+            ErrorKind::MissingField(field) => write!(f, "missing field `{}`", field)?,
+            ErrorKind::Io(e) => write!(f, "io error: {}", e)?,
+            ErrorKind::DepthLimit(max) => write!(f, "nbt nested deeper than the limit of {}", max)?,
+            ErrorKind::LimitExceeded(limit) => write!(f, "nbt exceeded {}", limit)?,
+            ErrorKind::Message(msg) => f.write_str(msg)?,
+        }
+
+        match (&self.path, self.offset) {
+            (Some(path), Some(offset)) => write!(f, " (at {}, byte {})", path, offset),
+            (Some(path), None) => write!(f, " (at {})", path),
+            (None, Some(offset)) => write!(f, " (at byte {})", offset),
+            (None, None) => Ok(()),
+        }
     }
 }
 
 impl serde::de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error(msg.to_string())
+        Error::bespoke(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::new(ErrorKind::MissingField(field))
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::bespoke(msg.to_string())
     }
 }
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
-        Error(format!("io error: {}", e))
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::unexpected_eof(),
+            _ => Error::new(ErrorKind::Io(Arc::new(e))),
+        }
     }
 }
 
 impl Error {
+    /// The kind of error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The offset in bytes into the input where the error happened, for
+    /// errors from deserializing.
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// The path to the value that failed to deserialize, such as
+    /// `Level.Sections[3].BlockStates`. Compound keys are separated by dots and
+    /// list elements are given by their index.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Whether the error is from the input ending early.
+    pub fn is_eof(&self) -> bool {
+        matches!(self.kind, ErrorKind::UnexpectedEof)
+    }
+
+    fn new(kind: ErrorKind) -> Error {
+        Error {
+            kind,
+            offset: None,
+            path: None,
+        }
+    }
+
+    /// Record the offset of the error, unless one is already known.
+    pub(crate) fn at_offset(mut self, offset: u64) -> Error {
+        self.offset.get_or_insert(offset);
+        self
+    }
+
+    /// Record the path to the value that failed, unless one is already known.
+    pub(crate) fn at_path(mut self, path: String) -> Error {
+        if self.path.is_none() && !path.is_empty() {
+            self.path = Some(path);
+        }
+        self
+    }
+
     pub(crate) fn invalid_tag(tag: u8) -> Error {
-        Error(format!("invalid nbt tag value: {}", tag))
+        Error::new(ErrorKind::InvalidTag(tag))
     }
 
     pub(crate) fn invalid_size(size: i32) -> Error {
-        Error(format!("invalid nbt list/array size: {}", size))
+        Error::new(ErrorKind::InvalidSize(size))
     }
 
     pub(crate) fn no_root_compound() -> Error {
-        Error("invalid nbt: no root compound".to_string())
+        Error::new(ErrorKind::NoRootCompound)
     }
 
-    pub(crate) fn nonunicode_string(data: &[u8]) -> Error {
-        Error(format!(
-            "invalid nbt string: nonunicode: {}",
-            String::from_utf8_lossy(data)
-        ))
+    pub(crate) fn nonunicode_string() -> Error {
+        Error::new(ErrorKind::NonunicodeString)
     }
 
     pub(crate) fn unexpected_eof() -> Error {
-        Error("eof: unexpectedly ran out of input".to_string())
+        Error::new(ErrorKind::UnexpectedEof)
+    }
+
+    pub(crate) fn depth_limit(max: usize) -> Error {
+        Error::new(ErrorKind::DepthLimit(max))
+    }
+
+    pub(crate) fn limit_exceeded(limit: Limit) -> Error {
+        Error::new(ErrorKind::LimitExceeded(limit))
     }
 
-    pub(crate) fn bespoke(msg: String) -> Error {
-        Error(msg)
+    pub(crate) fn bespoke(msg: impl Into<Cow<'static, str>>) -> Error {
+        Error::new(ErrorKind::Message(msg.into()))
     }
 }
@@ -0,0 +1,324 @@
+//! Sources of NBT data for the deserializer. The deserializer is generic over
+//! where its input comes from so that it can either borrow from an in-memory
+//! buffer or pull bytes on demand from a `Read`.
+
+use std::borrow::Cow;
+use std::convert::TryInto;
+use std::io::Read;
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::error::{Error, Result};
+
+/// Data that was either borrowed from the original input with lifetime `'de`,
+/// or copied into a temporary scratch buffer with the shorter lifetime `'s`.
+pub enum Reference<'de, 's, T: ?Sized + 'static> {
+    Borrowed(&'de T),
+    Copied(&'s T),
+}
+
+/// A decoded NBT string. Java's modified UTF-8 sometimes has to be re-encoded
+/// to be valid Rust UTF-8, in which case we end up owning the string.
+pub enum Str<'de, 's> {
+    Borrowed(&'de str),
+    Copied(&'s str),
+    Owned(String),
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Input for the deserializer. This is sealed, the only implementations are
+/// for byte slices (see [`from_bytes`][`crate::de::from_bytes`]) and for
+/// readers (see [`from_reader`][`crate::de::from_reader`]).
+pub trait Input<'de>: private::Sealed {
+    /// The number of bytes consumed so far.
+    #[doc(hidden)]
+    fn position(&self) -> u64;
+
+    #[doc(hidden)]
+    fn consume_byte(&mut self) -> Result<u8>;
+
+    #[doc(hidden)]
+    fn consume_i16(&mut self) -> Result<i16>;
+
+    #[doc(hidden)]
+    fn consume_i32(&mut self) -> Result<i32>;
+
+    #[doc(hidden)]
+    fn consume_i64(&mut self) -> Result<i64>;
+
+    #[doc(hidden)]
+    fn consume_f32(&mut self) -> Result<f32>;
+
+    #[doc(hidden)]
+    fn consume_f64(&mut self) -> Result<f64>;
+
+    #[doc(hidden)]
+    fn ignore_bytes(&mut self, size: usize) -> Result<()>;
+
+    #[doc(hidden)]
+    fn consume_bytes<'s>(
+        &'s mut self,
+        size: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>>;
+
+    /// Consume a string. If `lossy` is set, invalid sequences in the string
+    /// are replaced with U+FFFD rather than being an error.
+    #[doc(hidden)]
+    fn consume_str<'s>(
+        &'s mut self,
+        scratch: &'s mut Vec<u8>,
+        lossy: bool,
+    ) -> Result<Str<'de, 's>> {
+        let len = self.consume_i16()? as u16 as usize;
+        match self.consume_bytes(len, scratch)? {
+            Reference::Borrowed(bs) => match cesu8::from_java_cesu8(bs) {
+                Ok(Cow::Borrowed(s)) => Ok(Str::Borrowed(s)),
+                Ok(Cow::Owned(s)) => Ok(Str::Owned(s)),
+                Err(_) if lossy => Ok(Str::Owned(from_java_cesu8_lossy(bs))),
+                Err(_) => Err(Error::nonunicode_string()),
+            },
+            Reference::Copied(bs) => match cesu8::from_java_cesu8(bs) {
+                Ok(Cow::Borrowed(s)) => Ok(Str::Copied(s)),
+                Ok(Cow::Owned(s)) => Ok(Str::Owned(s)),
+                Err(_) if lossy => Ok(Str::Owned(from_java_cesu8_lossy(bs))),
+                Err(_) => Err(Error::nonunicode_string()),
+            },
+        }
+    }
+
+    #[doc(hidden)]
+    fn ignore_str(&mut self) -> Result<()> {
+        let len = self.consume_i16()? as u16 as usize;
+        self.ignore_bytes(len)
+    }
+}
+
+/// Decode Java's modified UTF-8, replacing anything invalid with U+FFFD.
+/// Java strings can hold unpaired surrogates, which also become U+FFFD. Four
+/// byte sequences are not used by Java, but some other tools write them, so
+/// they are decoded as in normal UTF-8.
+pub(crate) fn from_java_cesu8_lossy(bytes: &[u8]) -> String {
+    const REPLACEMENT: u16 = 0xfffd;
+
+    // Decode to UTF-16 first, so surrogate pairs encoded separately come back
+    // together, then let std deal with any unpaired ones.
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    let continuation = |i: usize| {
+        bytes
+            .get(i)
+            .filter(|b| *b & 0xc0 == 0x80)
+            .map(|b| (*b & 0x3f) as u32)
+    };
+
+    while i < bytes.len() {
+        let b = bytes[i] as u32;
+        let (point, len) = match b {
+            0x00..=0x7f => (Some(b), 1),
+            0xc0..=0xdf => match continuation(i + 1) {
+                Some(c1) => (Some((b & 0x1f) << 6 | c1), 2),
+                None => (None, 1),
+            },
+            0xe0..=0xef => match (continuation(i + 1), continuation(i + 2)) {
+                (Some(c1), Some(c2)) => (Some((b & 0x0f) << 12 | c1 << 6 | c2), 3),
+                _ => (None, 1),
+            },
+            0xf0..=0xf4 => match (
+                continuation(i + 1),
+                continuation(i + 2),
+                continuation(i + 3),
+            ) {
+                (Some(c1), Some(c2), Some(c3)) => {
+                    (Some((b & 0x07) << 18 | c1 << 12 | c2 << 6 | c3), 4)
+                }
+                _ => (None, 1),
+            },
+            _ => (None, 1),
+        };
+
+        match point.and_then(char::from_u32) {
+            Some(c) => {
+                let mut buf = [0; 2];
+                units.extend_from_slice(c.encode_utf16(&mut buf));
+            }
+            // Surrogates are kept as they are so they can pair up.
+            None => units.push(point.filter(|p| *p <= 0xffff).unwrap_or(REPLACEMENT as u32) as u16),
+        }
+        i += len;
+    }
+
+    String::from_utf16_lossy(&units)
+}
+
+/// Input from an in-memory slice. Strings and arrays can be borrowed from
+/// the slice.
+pub struct Slice<'de> {
+    pub(crate) data: &'de [u8],
+    len: usize,
+}
+
+impl<'de> private::Sealed for Slice<'de> {}
+
+impl<'de> Slice<'de> {
+    pub(crate) fn new(data: &'de [u8]) -> Self {
+        Self {
+            data,
+            len: data.len(),
+        }
+    }
+
+    fn consume(&mut self, size: usize) -> Result<&'de [u8]> {
+        if size > self.data.len() {
+            return Err(Error::unexpected_eof());
+        }
+
+        let (taken, rest) = self.data.split_at(size);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+impl<'de> Input<'de> for Slice<'de> {
+    fn position(&self) -> u64 {
+        (self.len - self.data.len()) as u64
+    }
+
+    fn consume_byte(&mut self) -> Result<u8> {
+        Ok(self.data.read_u8()?)
+    }
+
+    fn consume_i16(&mut self) -> Result<i16> {
+        Ok(self.data.read_i16::<BigEndian>()?)
+    }
+
+    fn consume_i32(&mut self) -> Result<i32> {
+        Ok(self.data.read_i32::<BigEndian>()?)
+    }
+
+    fn consume_i64(&mut self) -> Result<i64> {
+        Ok(self.data.read_i64::<BigEndian>()?)
+    }
+
+    fn consume_f32(&mut self) -> Result<f32> {
+        Ok(self.data.read_f32::<BigEndian>()?)
+    }
+
+    fn consume_f64(&mut self) -> Result<f64> {
+        Ok(self.data.read_f64::<BigEndian>()?)
+    }
+
+    fn ignore_bytes(&mut self, size: usize) -> Result<()> {
+        self.consume(size)?;
+        Ok(())
+    }
+
+    fn consume_bytes<'s>(
+        &'s mut self,
+        size: usize,
+        _scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        Ok(Reference::Borrowed(self.consume(size)?))
+    }
+}
+
+/// Input from a `Read`. Data cannot be borrowed from this input, so types like
+/// `&'a str` or [`borrow::LongArray`][`crate::borrow::LongArray`] cannot be
+/// deserialized from it.
+pub struct Reader<R: Read> {
+    pub(crate) reader: R,
+    position: u64,
+}
+
+impl<R: Read> Reader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0,
+        }
+    }
+
+    /// Note that `n` bytes were consumed, passing the result through.
+    fn advance<T>(&mut self, n: u64, result: std::io::Result<T>) -> Result<T> {
+        let value = result?;
+        self.position += n;
+        Ok(value)
+    }
+}
+
+impl<R: Read> private::Sealed for Reader<R> {}
+
+impl<'de, R: Read> Input<'de> for Reader<R> {
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn consume_byte(&mut self) -> Result<u8> {
+        let result = self.reader.read_u8();
+        self.advance(1, result)
+    }
+
+    fn consume_i16(&mut self) -> Result<i16> {
+        let result = self.reader.read_i16::<BigEndian>();
+        self.advance(2, result)
+    }
+
+    fn consume_i32(&mut self) -> Result<i32> {
+        let result = self.reader.read_i32::<BigEndian>();
+        self.advance(4, result)
+    }
+
+    fn consume_i64(&mut self) -> Result<i64> {
+        let result = self.reader.read_i64::<BigEndian>();
+        self.advance(8, result)
+    }
+
+    fn consume_f32(&mut self) -> Result<f32> {
+        let result = self.reader.read_f32::<BigEndian>();
+        self.advance(4, result)
+    }
+
+    fn consume_f64(&mut self) -> Result<f64> {
+        let result = self.reader.read_f64::<BigEndian>();
+        self.advance(8, result)
+    }
+
+    fn ignore_bytes(&mut self, size: usize) -> Result<()> {
+        let size: u64 = size.try_into().map_err(|_| Error::unexpected_eof())?;
+        let copied = std::io::copy(&mut self.reader.by_ref().take(size), &mut std::io::sink())?;
+        self.position += copied;
+
+        if copied != size {
+            return Err(Error::unexpected_eof());
+        }
+        Ok(())
+    }
+
+    fn consume_bytes<'s>(
+        &'s mut self,
+        size: usize,
+        scratch: &'s mut Vec<u8>,
+    ) -> Result<Reference<'de, 's, [u8]>> {
+        scratch.clear();
+
+        // The size comes from the input, so could be anything. Rather than
+        // allocating it all up front, let the buffer grow as data actually
+        // arrives.
+        let read = self
+            .reader
+            .by_ref()
+            .take(size as u64)
+            .read_to_end(scratch)?;
+        self.position += read as u64;
+
+        if read != size {
+            return Err(Error::unexpected_eof());
+        }
+
+        Ok(Reference::Copied(scratch.as_slice()))
+    }
+}
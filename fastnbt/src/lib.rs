@@ -3,9 +3,12 @@
 //! the world data and player inventories.
 //!
 //! * For documentation and examples of serde deserialization, see [`de`].
+//! * For serializing Rust types back into NBT, see [`ser`].
 //! * For a `serde_json`-like `Value` type see [`Value`].
 //! * For NBT array types see [`ByteArray`], [`IntArray`], and [`LongArray`].
 //! * For 'zero-copy' NBT array types see [`borrow`].
+//! * For fields that may be missing or empty see [`Maybe`].
+//! * For UUIDs see [`uuid`].
 //!
 //! Both this and related crates are under one [fastnbt Github
 //! repository](https://github.com/owengage/fastnbt)
@@ -113,6 +116,42 @@
 //! }
 //! ```
 //!
+//! # Missing and empty fields
+//!
+//! Fields come and go between versions of the game, so in practice most
+//! fields are optional. An `Option<T>` field is `None` when the tag is missing,
+//! and `#[serde(default)]` gives a default instead. An empty list or array is
+//! present, so it becomes `Some` with nothing in it. For the rare case where
+//! the difference between a missing tag and an empty one matters, use
+//! [`Maybe`].
+//!
+//! # Compressed files
+//!
+//! Most NBT files are compressed. Files like `level.dat` and player data are
+//! gzip compressed, and chunks in region files are usually zlib compressed.
+//! With the `compression` feature, which is on by default,
+//! [`from_gzip_reader`] and [`from_zlib_reader`] decompress as they
+//! deserialize, and [`from_compressed_bytes`] works out the compression
+//! from the data itself:
+//!
+//! ```no_run
+//! # use fastnbt::Value;
+//! let data = std::fs::read("level.dat")?;
+//! let level: Value = fastnbt::from_compressed_bytes(&data)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Decompression is done by `flate2` with its pure-Rust backend, so no C
+//! toolchain is needed and the crate builds for targets like
+//! `wasm32-unknown-unknown`. The `zlib` feature switches to the zlib C
+//! library instead.
+//!
+//! # JSON
+//!
+//! With the `json` feature, [`value::to_json`] and [`value::from_json`]
+//! convert between [`Value`] and `serde_json::Value`, which is handy for
+//! dumping NBT in a readable form.
+//!
 //! # `Read` based parser
 //!
 //! A lower level parser also exists in the `stream` module that only requires
@@ -125,12 +164,21 @@ use serde::Deserialize;
 pub mod borrow;
 pub mod de;
 pub mod error;
+pub mod ser;
 pub mod stream;
+pub mod uuid;
+pub mod value;
 
 mod arrays;
-mod value;
+#[cfg(feature = "compression")]
+mod compressed;
+mod input;
+mod maybe;
 
 pub use arrays::*;
+#[cfg(feature = "compression")]
+pub use compressed::*;
+pub use maybe::Maybe;
 pub use value::*;
 
 pub(crate) mod de_arrays;
@@ -172,6 +220,19 @@ pub enum Tag {
     LongArray = 12,
 }
 
+/// Whether the root compound has a name. NBT files always name the root,
+/// usually with an empty string. The network protocol leaves the name out
+/// entirely since Minecraft 1.20.2. See [`de::DeOpts`] and [`ser::SerOpts`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum RootName {
+    /// The root compound's tag is followed by its name, as in files.
+    #[default]
+    Named,
+    /// The root compound's tag is followed straight away by its contents, as
+    /// in the network protocol.
+    None,
+}
+
 pub(crate) const BYTE_ARRAY_TAG: u8 = 7;
 pub(crate) const INT_ARRAY_TAG: u8 = 11;
 pub(crate) const LONG_ARRAY_TAG: u8 = 12;
@@ -0,0 +1,187 @@
+use std::marker::PhantomData;
+
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::{de, Deserialize, Serialize};
+
+/// Name of the newtype that [`Maybe`] asks the deserializer for, so that the
+/// deserializer can check if a list or array is empty before deserializing it.
+pub(crate) const MAYBE_TOKEN: &str = "__fastnbt_maybe";
+
+/// A field that might be missing, present but empty, or present with a value.
+///
+/// An `Option<T>` field is `None` when the tag is missing, but an empty list
+/// or array is `Some`, and an empty list is often stored with the element type
+/// 'end' rather than the type `T` expects. `Maybe<T>` separates the empty case
+/// out without deserializing `T` from it. Lists and arrays are empty when they
+/// have no elements. Other tags are never empty.
+///
+/// Like `Option`, a missing field becomes [`Maybe::Absent`] without needing
+/// `#[serde(default)]`.
+///
+/// ```
+/// use fastnbt::{LongArray, Maybe};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Section {
+///     #[serde(rename = "BlockStates")]
+///     states: Maybe<LongArray>,
+/// }
+///
+/// # let data = [10, 0, 0, 9, 0, 11, b'B', b'l', b'o', b'c', b'k', b'S', b't', b'a', b't', b'e', b's', 0, 0, 0, 0, 0, 0];
+/// let section: Section = fastnbt::de::from_bytes(&data)?;
+/// assert!(section.states.is_empty());
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+///
+/// Serializing leaves out an absent field, and writes an empty field as an
+/// empty list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Maybe<T> {
+    /// The tag was missing.
+    #[default]
+    Absent,
+    /// The tag was a list or array with no elements.
+    Empty,
+    /// The tag had a value.
+    Present(T),
+}
+
+impl<T> Maybe<T> {
+    /// Whether the tag was missing.
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Maybe::Absent)
+    }
+
+    /// Whether the tag was an empty list or array.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Maybe::Empty)
+    }
+
+    /// Whether the tag had a value.
+    pub fn is_present(&self) -> bool {
+        matches!(self, Maybe::Present(_))
+    }
+
+    /// Get the value, if there was one.
+    pub fn as_ref(&self) -> Option<&T> {
+        match self {
+            Maybe::Present(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Take the value, if there was one.
+    pub fn present(self) -> Option<T> {
+        match self {
+            Maybe::Present(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Take the value, or the default if the tag was missing or empty.
+    pub fn unwrap_or_default(self) -> T
+    where
+        T: Default,
+    {
+        self.present().unwrap_or_default()
+    }
+}
+
+impl<T> From<Option<T>> for Maybe<T> {
+    fn from(v: Option<T>) -> Self {
+        match v {
+            Some(v) => Maybe::Present(v),
+            None => Maybe::Absent,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Maybe<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        match self {
+            Maybe::Absent => serializer.serialize_none(),
+            Maybe::Empty => serializer.serialize_seq(Some(0))?.end(),
+            Maybe::Present(v) => serializer.serialize_some(v),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Maybe<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Going through deserialize_option means a missing field is handed to
+        // us as none, rather than being an error.
+        deserializer.deserialize_option(MaybeVisitor(PhantomData))
+    }
+}
+
+struct MaybeVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> de::Visitor<'de> for MaybeVisitor<T> {
+    type Value = Maybe<T>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "an optional value")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Maybe::Absent)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Maybe::Absent)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(MAYBE_TOKEN, PresentVisitor(PhantomData))
+    }
+}
+
+/// Visits a value known to be present. The NBT deserializer visits a unit for
+/// empty lists and arrays, and hands over the list or array it has already
+/// started reading otherwise.
+struct PresentVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> de::Visitor<'de> for PresentVisitor<T> {
+    type Value = Maybe<T>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Maybe::Empty)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Maybe::Present)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        T::deserialize(SeqAccessDeserializer::new(seq)).map(Maybe::Present)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        T::deserialize(MapAccessDeserializer::new(map)).map(Maybe::Present)
+    }
+}
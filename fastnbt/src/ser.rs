@@ -0,0 +1,1215 @@
+//! This module contains a serde serializer, the counterpart of [`de`][`crate::de`].
+//! Anything that can be deserialized from NBT can generally be serialized back
+//! to it:
+//!
+//! * Rust structs and maps become Compounds.
+//! * `Vec`s, slices and tuples become Lists. Every element of a list must be
+//!   the same NBT type.
+//! * [`ByteArray`][`crate::ByteArray`], [`IntArray`][`crate::IntArray`] and
+//!   [`LongArray`][`crate::LongArray`] become the NBT arrays.
+//! * [`Value`][`crate::Value`] round trips exactly, including the type of
+//!   every number.
+//!
+//! ```
+//! use fastnbt::LongArray;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! #[serde(rename_all = "PascalCase")]
+//! struct Section {
+//!     block_states: LongArray,
+//!     y: i8,
+//! }
+//!
+//! let section = Section {
+//!     block_states: LongArray::new(vec![1, 2, 3]),
+//!     y: -4,
+//! };
+//!
+//! let bytes = fastnbt::ser::to_bytes(&section).unwrap();
+//! let back: Section = fastnbt::de::from_bytes(&bytes).unwrap();
+//! assert_eq!(section, back);
+//! ```
+//!
+//! # Quirks
+//!
+//! * The root of NBT is always a compound, so only structs and maps can be
+//!   serialized at the top level. The root compound is given an empty name,
+//!   see [`Serializer::with_root_name`] for giving it another, or
+//!   [`SerOpts`] for leaving it out as the network protocol does.
+//! * Fields that are `None` are left out of the compound entirely, which is
+//!   how optional fields are typically represented in Minecraft's data.
+//!   `None` cannot be an element of a list.
+//! * Unsigned integers are written as the signed NBT type of the same width,
+//!   so `u8` becomes a Byte and large values wrap to negative.
+//! * `bool` becomes a Byte of 0 or 1.
+//! * Unit enum variants become Strings of the variant name. Other enum
+//!   variants become a compound with the variant name as the only key.
+//! * `&[u8]` serialized with `serialize_bytes` (eg via `serde_bytes`) becomes
+//!   a Byte Array.
+
+use std::convert::TryFrom;
+use std::io::Write;
+
+use byteorder::{BigEndian, WriteBytesExt};
+use serde::ser::{self, Serialize};
+
+use crate::error::{Error, Result};
+use crate::{RootName, Tag};
+
+/// Name used for newtype structs wrapping a sequence that should be written
+/// as a Byte Array rather than a List.
+pub(crate) const BYTE_ARRAY_TOKEN: &str = "__fastnbt_byte_array";
+
+/// See [`BYTE_ARRAY_TOKEN`].
+pub(crate) const INT_ARRAY_TOKEN: &str = "__fastnbt_int_array";
+
+/// See [`BYTE_ARRAY_TOKEN`].
+pub(crate) const LONG_ARRAY_TOKEN: &str = "__fastnbt_long_array";
+
+/// Serialize some `T` into NBT data. See the [`ser`](index.html) module for
+/// more information.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    to_writer(&mut out, value)?;
+    Ok(out)
+}
+
+/// Serialize some `T` into NBT data, writing it to `writer`. The writer is
+/// written to in many small pieces, so should be buffered if writing straight
+/// to a file.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer))
+}
+
+/// Like [`to_bytes`], but with options for the format of the data.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use fastnbt::{ser::SerOpts, RootName};
+///
+/// let map = HashMap::from([("a", 5i8)]);
+/// let opts = SerOpts { root_name: RootName::None };
+///
+/// let data = fastnbt::ser::to_bytes_with_opts(&map, opts)?;
+/// assert_eq!(&[10, 1, 0, 1, b'a', 5, 0], data.as_slice());
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+pub fn to_bytes_with_opts<T: Serialize + ?Sized>(value: &T, opts: SerOpts) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    to_writer_with_opts(&mut out, value, opts)?;
+    Ok(out)
+}
+
+/// Like [`to_writer`], but with options for the format of the data.
+pub fn to_writer_with_opts<W: Write, T: Serialize + ?Sized>(
+    writer: W,
+    value: &T,
+    opts: SerOpts,
+) -> Result<()> {
+    value.serialize(&mut Serializer::new(writer).with_opts(opts))
+}
+
+/// Options for serializing, for data that isn't quite like NBT files.
+#[derive(Debug, Clone, Default)]
+pub struct SerOpts {
+    /// Whether to write a name for the root compound. If named, the name is
+    /// the one given to [`Serializer::with_root_name`], or empty.
+    pub root_name: RootName,
+}
+
+/// Serializer for the root of NBT data, which must be a compound.
+pub struct Serializer<W> {
+    writer: W,
+    root_name: String,
+    opts: SerOpts,
+}
+
+impl<W: Write> Serializer<W> {
+    pub fn new(writer: W) -> Self {
+        Self::with_root_name(writer, "")
+    }
+
+    /// Create a serializer that gives the root compound a name. Some formats
+    /// expect a particular name, such as `Schematic` for schematic files.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde::Serialize;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert("Width", 1i16);
+    ///
+    /// let mut out = Vec::new();
+    /// map.serialize(&mut fastnbt::ser::Serializer::with_root_name(&mut out, "Schematic"))
+    ///     .unwrap();
+    /// assert_eq!(b"Schematic", &out[3..12]);
+    /// ```
+    pub fn with_root_name(writer: W, name: impl Into<String>) -> Self {
+        Self {
+            writer,
+            root_name: name.into(),
+            opts: SerOpts::default(),
+        }
+    }
+
+    /// Set options for the format of the data. See [`SerOpts`].
+    pub fn with_opts(mut self, opts: SerOpts) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Take the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn start_root(&mut self) -> Result<Compound<'_, W>> {
+        self.writer.write_u8(Tag::Compound.into())?;
+        if self.opts.root_name == RootName::Named {
+            write_str(&mut self.writer, &self.root_name)?;
+        }
+        Ok(Compound::new(&mut self.writer))
+    }
+}
+
+fn write_str<W: Write>(writer: &mut W, s: &str) -> Result<()> {
+    let data = cesu8::to_java_cesu8(s);
+    let len = u16::try_from(data.len())
+        .map_err(|_| Error::bespoke(format!("string too long for nbt: {} bytes", data.len())))?;
+
+    writer.write_u16::<BigEndian>(len)?;
+    writer.write_all(&data)?;
+    Ok(())
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> Result<()> {
+    let len = i32::try_from(len)
+        .map_err(|_| Error::bespoke(format!("list or array too long for nbt: {}", len)))?;
+    writer.write_i32::<BigEndian>(len)?;
+    Ok(())
+}
+
+fn no_root_compound<T>() -> Result<T> {
+    Err(Error::no_root_compound())
+}
+
+macro_rules! no_root {
+    ($($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<()> {
+                no_root_compound()
+            }
+        )*
+    };
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<(), Error>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    no_root!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        no_root_compound()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        no_root_compound()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        no_root_compound()
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        no_root_compound()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        no_root_compound()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.start_root()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.start_root()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        no_root_compound()
+    }
+}
+
+/// Where a value is being written, which decides what goes before its
+/// payload.
+enum Header<'a> {
+    /// A value in a compound, preceded by its tag and name.
+    Named(&'a str),
+
+    /// An element of a list. The first element decides the tag of the list.
+    Element(&'a mut ListState),
+}
+
+/// Progress through writing a list.
+struct ListState {
+    len: usize,
+    written: usize,
+    element_tag: Option<Tag>,
+}
+
+/// Serializer for a single value, anywhere below the root.
+struct ValueSerializer<'a, W> {
+    writer: &'a mut W,
+    header: Header<'a>,
+}
+
+impl<'a, W: Write> ValueSerializer<'a, W> {
+    fn write_header(&mut self, tag: Tag) -> Result<()> {
+        match &mut self.header {
+            Header::Named(name) => {
+                self.writer.write_u8(tag.into())?;
+                write_str(self.writer, name)?;
+            }
+            Header::Element(state) => {
+                match state.element_tag {
+                    None => {
+                        self.writer.write_u8(tag.into())?;
+                        write_len(self.writer, state.len)?;
+                        state.element_tag = Some(tag);
+                    }
+                    Some(t) if t == tag => {}
+                    Some(t) => {
+                        return Err(Error::bespoke(format!(
+                            "list elements must all be the same type, expected {:?} but found {:?}",
+                            t, tag
+                        )))
+                    }
+                }
+                state.written += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start a compound that wraps a single enum variant.
+    fn start_variant(mut self, variant: &'static str) -> Result<(Compound<'a, W>, &'static str)> {
+        self.write_header(Tag::Compound)?;
+        Ok((Compound::new(self.writer), variant))
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for ValueSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = List<'a, W>;
+    type SerializeTuple = List<'a, W>;
+    type SerializeTupleStruct = List<'a, W>;
+    type SerializeTupleVariant = VariantList<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = VariantCompound<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.serialize_i8(v as i8)
+    }
+
+    fn serialize_i8(mut self, v: i8) -> Result<()> {
+        self.write_header(Tag::Byte)?;
+        self.writer.write_i8(v)?;
+        Ok(())
+    }
+
+    fn serialize_i16(mut self, v: i16) -> Result<()> {
+        self.write_header(Tag::Short)?;
+        self.writer.write_i16::<BigEndian>(v)?;
+        Ok(())
+    }
+
+    fn serialize_i32(mut self, v: i32) -> Result<()> {
+        self.write_header(Tag::Int)?;
+        self.writer.write_i32::<BigEndian>(v)?;
+        Ok(())
+    }
+
+    fn serialize_i64(mut self, v: i64) -> Result<()> {
+        self.write_header(Tag::Long)?;
+        self.writer.write_i64::<BigEndian>(v)?;
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        self.serialize_u128(v as u128)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_i8(v as i8)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_i16(v as i16)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_i32(v as i32)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    /// 128 bit integers are written as an IntArray of four ints, most
+    /// significant first, which is how Minecraft stores UUIDs.
+    fn serialize_u128(mut self, v: u128) -> Result<()> {
+        self.write_header(Tag::IntArray)?;
+        write_len(self.writer, 4)?;
+        for int in crate::uuid::to_ints(v) {
+            self.writer.write_i32::<BigEndian>(int)?;
+        }
+        Ok(())
+    }
+
+    fn serialize_f32(mut self, v: f32) -> Result<()> {
+        self.write_header(Tag::Float)?;
+        self.writer.write_f32::<BigEndian>(v)?;
+        Ok(())
+    }
+
+    fn serialize_f64(mut self, v: f64) -> Result<()> {
+        self.write_header(Tag::Double)?;
+        self.writer.write_f64::<BigEndian>(v)?;
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(mut self, v: &str) -> Result<()> {
+        self.write_header(Tag::String)?;
+        write_str(self.writer, v)
+    }
+
+    fn serialize_bytes(mut self, v: &[u8]) -> Result<()> {
+        self.write_header(Tag::ByteArray)?;
+        write_len(self.writer, v.len())?;
+        self.writer.write_all(v)?;
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        match self.header {
+            // Leave the field out of the compound entirely.
+            Header::Named(_) => Ok(()),
+            Header::Element(_) => Err(Error::bespoke(
+                "cannot serialize None as an element of a list",
+            )),
+        }
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(Error::bespoke("cannot serialize unit to nbt"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let tag = match name {
+            BYTE_ARRAY_TOKEN => Tag::ByteArray,
+            INT_ARRAY_TOKEN => Tag::IntArray,
+            LONG_ARRAY_TOKEN => Tag::LongArray,
+            _ => return value.serialize(self),
+        };
+
+        self.write_header(tag)?;
+        value.serialize(ArraySerializer {
+            writer: self.writer,
+            tag,
+        })
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        use ser::SerializeMap;
+
+        let (mut compound, variant) = self.start_variant(variant)?;
+        compound.serialize_entry(variant, value)?;
+        compound.end()
+    }
+
+    fn serialize_seq(mut self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        let len =
+            len.ok_or_else(|| Error::bespoke("length of list must be known to serialize it"))?;
+
+        self.write_header(Tag::List)?;
+        Ok(List {
+            writer: self.writer,
+            state: ListState {
+                len,
+                written: 0,
+                element_tag: None,
+            },
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        let (outer, variant) = self.start_variant(variant)?;
+
+        // The list borrows the writer from the outer compound, so the outer
+        // compound's end tag is written by VariantList.
+        outer.writer.write_u8(Tag::List.into())?;
+        write_str(outer.writer, variant)?;
+        Ok(VariantList {
+            list: List {
+                writer: outer.writer,
+                state: ListState {
+                    len,
+                    written: 0,
+                    element_tag: None,
+                },
+            },
+        })
+    }
+
+    fn serialize_map(mut self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.write_header(Tag::Compound)?;
+        Ok(Compound::new(self.writer))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        let (outer, variant) = self.start_variant(variant)?;
+
+        outer.writer.write_u8(Tag::Compound.into())?;
+        write_str(outer.writer, variant)?;
+        Ok(VariantCompound {
+            compound: Compound::new(outer.writer),
+        })
+    }
+}
+
+/// Serializer for a compound, from a struct or map.
+pub struct Compound<'a, W> {
+    writer: &'a mut W,
+    key: Option<String>,
+}
+
+impl<'a, W: Write> Compound<'a, W> {
+    fn new(writer: &'a mut W) -> Self {
+        Self { writer, key: None }
+    }
+
+    fn field<T: ?Sized + Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        value.serialize(ValueSerializer {
+            writer: &mut *self.writer,
+            header: Header::Named(key),
+        })
+    }
+
+    fn finish(self) -> Result<()> {
+        self.writer.write_u8(Tag::End.into())?;
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeMap for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::bespoke("serialize_value called before key"))?;
+        self.field(&key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+/// Serializer for the inner compound of a struct enum variant.
+pub struct VariantCompound<'a, W> {
+    compound: Compound<'a, W>,
+}
+
+impl<'a, W: Write> ser::SerializeStructVariant for VariantCompound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.compound.field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        // End both the variant's compound and the wrapping compound.
+        let writer = self.compound.writer;
+        writer.write_u8(Tag::End.into())?;
+        writer.write_u8(Tag::End.into())?;
+        Ok(())
+    }
+}
+
+/// Serializer for a list, from a sequence or tuple.
+pub struct List<'a, W> {
+    writer: &'a mut W,
+    state: ListState,
+}
+
+impl<'a, W: Write> List<'a, W> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(ValueSerializer {
+            writer: &mut *self.writer,
+            header: Header::Element(&mut self.state),
+        })
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.state.written != self.state.len {
+            return Err(Error::bespoke(format!(
+                "list was said to have {} elements but had {}",
+                self.state.len, self.state.written
+            )));
+        }
+
+        // An empty list never had an element to decide the tag.
+        if self.state.element_tag.is_none() {
+            self.writer.write_u8(Tag::End.into())?;
+            write_len(self.writer, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for List<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for List<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for List<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+/// Serializer for the inner list of a tuple enum variant.
+pub struct VariantList<'a, W> {
+    list: List<'a, W>,
+}
+
+impl<'a, W: Write> ser::SerializeTupleVariant for VariantList<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.list.element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        let List { writer, state } = self.list;
+        List {
+            writer: &mut *writer,
+            state,
+        }
+        .finish()?;
+
+        // End the wrapping compound.
+        writer.write_u8(Tag::End.into())?;
+        Ok(())
+    }
+}
+
+/// Serializer for the keys of a map, which must be strings.
+struct KeySerializer;
+
+macro_rules! key_must_be_string {
+    ($($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<String> {
+                Err(Error::bespoke("keys of a map must be strings"))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<String, Error>;
+    type SerializeTuple = ser::Impossible<String, Error>;
+    type SerializeTupleStruct = ser::Impossible<String, Error>;
+    type SerializeTupleVariant = ser::Impossible<String, Error>;
+    type SerializeMap = ser::Impossible<String, Error>;
+    type SerializeStruct = ser::Impossible<String, Error>;
+    type SerializeStructVariant = ser::Impossible<String, Error>;
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String> {
+        value.serialize(self)
+    }
+
+    key_must_be_string!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<String> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::bespoke("keys of a map must be strings"))
+    }
+}
+
+macro_rules! array_unsupported {
+    ($($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<()> {
+                Err(not_array_element(self.tag))
+            }
+        )*
+    };
+}
+
+/// Serializer for the payload of an NBT array. Accepts a sequence of integers
+/// of the array's type, the header has already been written.
+struct ArraySerializer<'a, W> {
+    writer: &'a mut W,
+    tag: Tag,
+}
+
+impl<'a, W: Write> ser::Serializer for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ArrayElements<'a, W>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = ser::Impossible<(), Error>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        let len =
+            len.ok_or_else(|| Error::bespoke("length of array must be known to serialize it"))?;
+        write_len(self.writer, len)?;
+        Ok(ArrayElements {
+            writer: self.writer,
+            tag: self.tag,
+        })
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if self.tag != Tag::ByteArray {
+            return Err(not_array_element(self.tag));
+        }
+        write_len(self.writer, v.len())?;
+        self.writer.write_all(v)?;
+        Ok(())
+    }
+
+    array_unsupported!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_array_element(self.tag))
+    }
+}
+
+fn not_array_element(tag: Tag) -> Error {
+    Error::bespoke(format!("expected a sequence of integers for {:?}", tag))
+}
+
+/// Serializer for the elements of an NBT array.
+struct ArrayElements<'a, W> {
+    writer: &'a mut W,
+    tag: Tag,
+}
+
+impl<'a, W: Write> ser::SerializeSeq for ArrayElements<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(ArrayElement {
+            writer: &mut *self.writer,
+            tag: self.tag,
+        })
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializer for a single element of an NBT array.
+struct ArrayElement<'a, W> {
+    writer: &'a mut W,
+    tag: Tag,
+}
+
+impl<'a, W: Write> ArrayElement<'a, W> {
+    fn write(self, v: i64) -> Result<()> {
+        match self.tag {
+            Tag::ByteArray => self.writer.write_i8(v as i8)?,
+            Tag::IntArray => self.writer.write_i32::<BigEndian>(v as i32)?,
+            _ => self.writer.write_i64::<BigEndian>(v)?,
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for ArrayElement<'a, W> {
+    type Ok = ();
+    type Error = Error;
+
+    type SerializeSeq = ser::Impossible<(), Error>;
+    type SerializeTuple = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ser::Impossible<(), Error>;
+    type SerializeMap = ser::Impossible<(), Error>;
+    type SerializeStruct = ser::Impossible<(), Error>;
+    type SerializeStructVariant = ser::Impossible<(), Error>;
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.write(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.write(v as i64)
+    }
+
+    array_unsupported!(
+        serialize_bool(bool),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str),
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<()> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(not_array_element(self.tag))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(not_array_element(self.tag))
+    }
+}
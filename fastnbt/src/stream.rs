@@ -34,6 +34,30 @@ pub enum Value {
     LongArray(Name, Vec<i64>),
 }
 
+impl Value {
+    /// The name of the value, if it has one. Elements of lists and the ends
+    /// of compounds and lists have no name.
+    pub fn name(&self) -> Option<&str> {
+        let name = match self {
+            Value::CompoundEnd | Value::ListEnd => return None,
+            Value::Byte(n, _)
+            | Value::Short(n, _)
+            | Value::Int(n, _)
+            | Value::Long(n, _)
+            | Value::Float(n, _)
+            | Value::Double(n, _)
+            | Value::ByteArray(n, _)
+            | Value::String(n, _)
+            | Value::List(n, _, _)
+            | Value::Compound(n)
+            | Value::IntArray(n, _)
+            | Value::LongArray(n, _) => n,
+        };
+
+        name.as_deref()
+    }
+}
+
 #[derive(Debug)]
 pub struct Error(String);
 
@@ -172,6 +196,11 @@ impl<R: Read> Parser<R> {
         self.next_inner()
     }
 
+    /// The number of compounds and lists the parser is currently inside.
+    pub fn depth(&self) -> usize {
+        self.layers.len()
+    }
+
     /// Get the next value from the reader. Returns EOF if the stream ended sucessfully, and
     /// IO(err) for any other IO error.
     fn next_inner(&mut self) -> Result<Value> {
@@ -236,9 +265,10 @@ impl<R: Read> Parser<R> {
         let mut buf = vec![0; name_len];
         self.reader.read_exact(&mut buf[..])?;
 
-        Ok(std::str::from_utf8(&buf[..])
+        // Strings are in Java's modified UTF-8 rather than real UTF-8.
+        Ok(cesu8::from_java_cesu8(&buf[..])
             .map_err(|_| Error::nonunicode(Vec::from(&buf[..])))?
-            .to_owned())
+            .into_owned())
     }
 
     fn read_payload(&mut self, tag: Tag, name: Name) -> Result<Value> {
@@ -306,6 +336,86 @@ pub fn skip_compound<R: Read>(parser: &mut Parser<R>) -> Result<()> {
     Ok(())
 }
 
+/// Parse the input until the list we are currently inside is complete.
+/// Handles inner lists and compounds by skipping those as well.
+pub fn skip_list<R: Read>(parser: &mut Parser<R>) -> Result<()> {
+    let mut depth = 1;
+
+    while depth != 0 {
+        match parser.next()? {
+            Value::ListEnd | Value::CompoundEnd => depth -= 1,
+            Value::List(..) | Value::Compound(_) => depth += 1,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Skip the contents of a value that was just parsed. If it was a compound or
+/// list, this parses until the end of it. Other values have no contents, so
+/// nothing is parsed.
+pub fn skip<R: Read>(parser: &mut Parser<R>, value: &Value) -> Result<()> {
+    match value {
+        Value::Compound(_) => skip_compound(parser),
+        Value::List(..) => skip_list(parser),
+        _ => Ok(()),
+    }
+}
+
+/// Parse until the value at the given path of names is found, skipping over
+/// everything else. The path starts in the compound the parser is currently
+/// inside, or at the root compound if nothing has been parsed yet. Returns
+/// `None` if there is no value at the path, or if the path goes through
+/// something that isn't a compound.
+///
+/// If the value found is a compound or list, the parser is left inside it so
+/// its contents can be parsed next.
+///
+/// ```
+/// use fastnbt::stream::{find_path, Parser, Value};
+/// # use std::collections::HashMap;
+/// # let compound = |k: &str, v| fastnbt::Value::Compound(HashMap::from([(k.to_owned(), v)]));
+/// # let root = compound("Data", compound("Player", compound("XpLevel", fastnbt::Value::Int(30))));
+/// # let data = fastnbt::ser::to_bytes(&root).unwrap();
+///
+/// let mut parser = Parser::new(data.as_slice());
+/// let level = find_path(&mut parser, &["Data", "Player", "XpLevel"])?;
+///
+/// assert_eq!(Some(Value::Int(Some("XpLevel".to_owned()), 30)), level);
+/// # Ok::<(), fastnbt::stream::Error>(())
+/// ```
+pub fn find_path<R: Read>(parser: &mut Parser<R>, path: &[&str]) -> Result<Option<Value>> {
+    if parser.depth() == 0 {
+        match parser.next()? {
+            Value::Compound(_) => {}
+            _ => return Ok(None),
+        }
+    }
+
+    for (i, key) in path.iter().enumerate() {
+        let last = i == path.len() - 1;
+
+        loop {
+            let value = parser.next()?;
+            match value {
+                Value::CompoundEnd => return Ok(None),
+                ref v if v.name() == Some(key) => {
+                    if last {
+                        return Ok(Some(value));
+                    }
+                    match value {
+                        Value::Compound(_) => break,
+                        _ => return Ok(None),
+                    }
+                }
+                ref v => skip(parser, v)?,
+            }
+        }
+    }
+
+    Ok(None)
+}
+
 /// Parse until the compound with the given name is found. This will enter other
 /// compounds and lists, rather than find a compound at the current level.
 pub fn find_compound<R: Read>(parser: &mut Parser<R>, name: Option<&str>) -> Result<()> {
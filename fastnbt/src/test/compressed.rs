@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use super::builder::Builder;
+use crate::{from_compressed_bytes, from_gzip_reader, from_zlib_reader, Compression, Value};
+
+fn data() -> Vec<u8> {
+    Builder::new()
+        .start_compound("")
+        .int("a", 1)
+        .string("b", "hello")
+        .end_compound()
+        .build()
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn expected() -> HashMap<String, Value> {
+    HashMap::from([
+        ("a".to_owned(), Value::Int(1)),
+        ("b".to_owned(), Value::String("hello".to_owned())),
+    ])
+}
+
+#[test]
+fn detect() {
+    assert_eq!(Compression::Gzip, Compression::detect(&gzip(&data())));
+    assert_eq!(Compression::Zlib, Compression::detect(&zlib(&data())));
+    assert_eq!(Compression::Uncompressed, Compression::detect(&data()));
+    assert_eq!(Compression::Uncompressed, Compression::detect(&[]));
+
+    for level in 0..=9 {
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::new(level));
+        encoder.write_all(&data()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(Compression::Zlib, Compression::detect(&compressed));
+    }
+}
+
+#[test]
+fn readers() {
+    let v: HashMap<String, Value> = from_gzip_reader(gzip(&data()).as_slice()).unwrap();
+    assert_eq!(expected(), v);
+
+    let v: HashMap<String, Value> = from_zlib_reader(zlib(&data()).as_slice()).unwrap();
+    assert_eq!(expected(), v);
+}
+
+#[test]
+fn compressed_bytes() {
+    for bytes in [gzip(&data()), zlib(&data()), data()] {
+        let v: HashMap<String, Value> = from_compressed_bytes(&bytes).unwrap();
+        assert_eq!(expected(), v);
+    }
+}
+
+#[test]
+fn wrong_compression_is_error() {
+    let res: crate::error::Result<Value> = from_gzip_reader(zlib(&data()).as_slice());
+    assert!(res.is_err());
+
+    let mut truncated = gzip(&data());
+    truncated.truncate(truncated.len() / 2);
+    let res: crate::error::Result<Value> = from_compressed_bytes(&truncated);
+    assert!(res.is_err());
+}
@@ -917,6 +917,49 @@ fn fixed_array() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn fixed_array_followed_by_field() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V {
+        pos: [f64; 3],
+        after: i32,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("pos", Tag::Double, 3)
+        .double_payload(1.0)
+        .double_payload(2.0)
+        .double_payload(3.0)
+        .int("after", 4)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice())?;
+    assert_eq!(v.pos, [1.0, 2.0, 3.0]);
+    assert_eq!(v.after, 4);
+    Ok(())
+}
+
+#[test]
+fn fixed_array_shorter_than_list() {
+    #[derive(Deserialize)]
+    struct V {
+        _pos: [f64; 2],
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("_pos", Tag::Double, 3)
+        .double_payload(1.0)
+        .double_payload(2.0)
+        .double_payload(3.0)
+        .end_compound()
+        .build();
+
+    assert!(from_bytes::<V>(payload.as_slice()).is_err());
+}
+
 #[test]
 fn type_mismatch_string() -> Result<()> {
     #[derive(Deserialize, Debug)]
@@ -1238,3 +1281,681 @@ fn can_cow_cesu8() {
     assert!(matches!(v.borrowed, Cow::Borrowed(_)));
     assert_eq!("abc", v.borrowed);
 }
+
+#[test]
+fn borrow_str_and_bytes_from_input() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        name: &'a str,
+        data: &'a [u8],
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("name", "minecraft:stone")
+        .byte_array("data", &[1, 2, 3])
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice())?;
+
+    // Make sure these point into the input rather than a copy.
+    let input = payload.as_ptr_range();
+    assert!(input.contains(&v.name.as_ptr()));
+    assert!(input.contains(&v.data.as_ptr()));
+    assert_eq!(v.name, "minecraft:stone");
+    assert_eq!(v.data, [1, 2, 3]);
+
+    Ok(())
+}
+
+#[test]
+fn borrow_raw_bytes_of_string() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        ascii: &'a [u8],
+        unicode: &'a [u8],
+    }
+
+    let modified_unicode_str = cesu8::to_java_cesu8("😈");
+
+    let payload = Builder::new()
+        .start_compound("")
+        .string("ascii", "abc")
+        .tag(Tag::String)
+        .name("unicode")
+        .raw_len(modified_unicode_str.len())
+        .raw_bytes(&modified_unicode_str)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice())?;
+
+    assert_eq!(v.ascii, b"abc");
+    assert_eq!(v.unicode, &*modified_unicode_str);
+    assert_eq!("😈", cesu8::from_java_cesu8(v.unicode).unwrap());
+
+    Ok(())
+}
+
+#[test]
+fn borrow_list_of_arrays() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        #[serde(borrow)]
+        bytes: Vec<&'a [u8]>,
+        #[serde(borrow)]
+        longs: Vec<&'a [u8]>,
+        #[serde(borrow)]
+        lists: Vec<&'a [u8]>,
+        #[serde(borrow)]
+        strings: Vec<&'a str>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("bytes", Tag::ByteArray, 2)
+        .int_payload(2)
+        .byte_payload(1)
+        .byte_payload(2)
+        .int_payload(1)
+        .byte_payload(3)
+        .start_list("longs", Tag::LongArray, 1)
+        .int_payload(1)
+        .long_payload(1)
+        .start_list("lists", Tag::List, 2)
+        .tag(Tag::Byte)
+        .int_payload(1)
+        .byte_payload(4)
+        .tag(Tag::Short)
+        .int_payload(1)
+        .short_payload(5)
+        .start_list("strings", Tag::String, 2)
+        .string_payload("a")
+        .string_payload("b")
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice())?;
+
+    assert_eq!(v.bytes, [&[1, 2][..], &[3][..]]);
+    assert_eq!(v.longs, [&[0, 0, 0, 0, 0, 0, 0, 1][..]]);
+    assert_eq!(v.lists, [&[4][..], &[0, 5][..]]);
+    assert_eq!(v.strings, ["a", "b"]);
+
+    Ok(())
+}
+
+#[test]
+fn bytes_from_non_array_errors() {
+    #[derive(Deserialize, Debug)]
+    struct V<'a> {
+        _data: &'a [u8],
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .int("_data", 1)
+        .end_compound()
+        .build();
+
+    let v: Result<V> = from_bytes(payload.as_slice());
+    assert!(v.is_err());
+}
+
+fn extract_payload() -> Vec<u8> {
+    Builder::new()
+        .start_compound("")
+        .start_list("skipped", Tag::Compound, 2)
+        .long_array("big", &[1, 2, 3])
+        .end_compound()
+        .end_compound()
+        .start_compound("Level")
+        .int_array("ints", &[1, 2])
+        .start_compound("Heightmaps")
+        .long_array("WORLD_SURFACE", &[4, 5, 6])
+        .end_compound()
+        .int("after", 7)
+        .end_compound()
+        .end_compound()
+        .build()
+}
+
+#[test]
+fn extract_nested_value() -> Result<()> {
+    let payload = extract_payload();
+
+    let surface: Option<LongArray> =
+        crate::de::extract(&payload, "Level.Heightmaps.WORLD_SURFACE")?;
+    assert_eq!(vec![4, 5, 6], *surface.unwrap());
+
+    let after: Option<i32> = crate::de::extract(&payload, "Level.after")?;
+    assert_eq!(Some(7), after);
+
+    let heightmaps: Option<HashMap<String, Value>> =
+        crate::de::extract(&payload, "Level.Heightmaps")?;
+    assert_eq!(1, heightmaps.unwrap().len());
+    Ok(())
+}
+
+#[test]
+fn extract_missing_value() -> Result<()> {
+    let payload = extract_payload();
+
+    let missing: Option<i32> = crate::de::extract(&payload, "Level.Nope")?;
+    assert_eq!(None, missing);
+
+    let missing: Option<i32> = crate::de::extract(&payload, "Nope.after")?;
+    assert_eq!(None, missing);
+
+    // Can't go through something that isn't a compound.
+    let err: Result<Option<i32>> = crate::de::extract(&payload, "Level.after.x");
+    assert!(err.is_err());
+    Ok(())
+}
+
+#[test]
+fn extract_from_reader() -> Result<()> {
+    let payload = extract_payload();
+    let after: Option<i32> = crate::de::extract_from_reader(payload.as_slice(), "Level.after")?;
+    assert_eq!(Some(7), after);
+
+    let root: Option<HashMap<String, Value>> =
+        crate::de::extract_from_reader(payload.as_slice(), "")?;
+    assert_eq!(2, root.unwrap().len());
+    Ok(())
+}
+
+#[test]
+fn ignored_any_anywhere() -> Result<()> {
+    let payload = extract_payload();
+
+    // The whole input.
+    from_bytes::<serde::de::IgnoredAny>(&payload)?;
+
+    // Elements of a list.
+    #[derive(Deserialize)]
+    struct V {
+        skipped: Vec<serde::de::IgnoredAny>,
+        #[serde(rename = "Level")]
+        level: HashMap<String, serde::de::IgnoredAny>,
+    }
+
+    let v: V = from_bytes(&payload)?;
+    assert_eq!(2, v.skipped.len());
+    assert_eq!(3, v.level.len());
+    Ok(())
+}
+
+fn string_payload(raw: &[u8]) -> Vec<u8> {
+    Builder::new()
+        .start_compound("")
+        .tag(Tag::String)
+        .name("name")
+        .raw_len(raw.len())
+        .raw_bytes(raw)
+        .end_compound()
+        .build()
+}
+
+fn lossy_name(input: &[u8]) -> Result<String> {
+    let mut de = crate::de::Deserializer::from_bytes(input).lossy_strings(true);
+    let map: HashMap<String, String> = Deserialize::deserialize(&mut de)?;
+    Ok(map["name"].clone())
+}
+
+#[test]
+fn cesu8_embedded_nul() -> Result<()> {
+    // Java writes NUL as two bytes so that strings never contain a zero byte.
+    let input = string_payload(&[b'a', 0xc0, 0x80, b'b']);
+    let map: HashMap<String, String> = from_bytes(&input)?;
+    assert_eq!("a\0b", map["name"]);
+    Ok(())
+}
+
+#[test]
+fn unpaired_surrogate_is_error_unless_lossy() -> Result<()> {
+    // A high surrogate on its own, which Java allows in strings.
+    let input = string_payload(&[b'a', 0xed, 0xa0, 0x80, b'b']);
+
+    assert!(from_bytes::<HashMap<String, String>>(&input).is_err());
+    assert_eq!("a\u{fffd}b", lossy_name(&input)?);
+    Ok(())
+}
+
+#[test]
+fn lossy_strings_keep_valid_parts() -> Result<()> {
+    let mut raw = cesu8::to_java_cesu8("😈").into_owned();
+    raw.push(0xff);
+    raw.extend_from_slice("é".as_bytes());
+    // Plain UTF-8 for 4 byte characters, as some tools write.
+    raw.extend_from_slice("😈".as_bytes());
+
+    assert_eq!("😈\u{fffd}é😈", lossy_name(&string_payload(&raw))?);
+
+    // Valid strings are not affected by being lossy.
+    assert_eq!(
+        "😈",
+        lossy_name(&string_payload(&cesu8::to_java_cesu8("😈")))?
+    );
+    Ok(())
+}
+
+#[test]
+fn lossy_strings_from_reader() -> Result<()> {
+    let input = string_payload(&[0xed, 0xb0, 0x80]);
+    let mut de = crate::de::Deserializer::from_reader(input.as_slice()).lossy_strings(true);
+    let map: HashMap<String, String> = Deserialize::deserialize(&mut de)?;
+    assert_eq!("\u{fffd}", map["name"]);
+    Ok(())
+}
+
+#[test]
+fn error_kind_and_offset() {
+    use crate::error::ErrorKind;
+
+    #[derive(Deserialize, Debug)]
+    struct V {
+        #[allow(dead_code)]
+        abc: i32,
+    }
+
+    // Tag 13 doesn't exist. The root compound tag and name take 3 bytes.
+    let input = Builder::new().start_compound("").raw_bytes(&[13]).build();
+    let err = from_bytes::<V>(&input).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::InvalidTag(13)));
+    assert_eq!(Some(4), err.offset());
+    assert!(err.to_string().ends_with("(at byte 4)"));
+
+    let input = Builder::new()
+        .start_compound("")
+        .tag(Tag::Int)
+        .name("abc")
+        .raw_bytes(&[0, 0])
+        .build();
+    let err = from_bytes::<V>(&input).unwrap_err();
+    assert!(err.is_eof());
+    assert!(err.offset().is_some());
+
+    let input = Builder::new().start_compound("").end_compound().build();
+    let err = from_bytes::<V>(&input).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::MissingField("abc")));
+    assert_eq!(Some(4), err.offset());
+}
+
+#[test]
+fn error_offset_from_reader() {
+    let input = Builder::new()
+        .start_compound("")
+        .int("a", 1)
+        .raw_bytes(&[13])
+        .build();
+
+    let err = crate::de::from_reader::<_, Value>(input.as_slice()).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        crate::error::ErrorKind::InvalidTag(13)
+    ));
+    assert_eq!(Some(input.len() as u64), err.offset());
+}
+
+#[test]
+fn error_path() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Section {
+        #[serde(rename = "Y")]
+        y: i8,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Level {
+        #[serde(rename = "Sections")]
+        sections: Vec<Section>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Chunk {
+        #[serde(rename = "Level")]
+        level: Level,
+    }
+
+    let input = Builder::new()
+        .start_compound("")
+        .start_compound("Level")
+        .start_list("Sections", Tag::Compound, 2)
+        .byte("Y", 0)
+        .end_compound()
+        .string("Y", "oops")
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+
+    let err = from_bytes::<Chunk>(&input).unwrap_err();
+    assert_eq!(Some("Level.Sections[1].Y"), err.path());
+    assert!(err.offset().is_some());
+    assert!(err.to_string().contains("(at Level.Sections[1].Y, byte "));
+
+    // Keys read from a reader are copied rather than borrowed.
+    let err = crate::de::from_reader::<_, Chunk>(input.as_slice()).unwrap_err();
+    assert_eq!(Some("Level.Sections[1].Y"), err.path());
+
+    // Errors from the root compound itself have no path.
+    let input = Builder::new().start_compound("").end_compound().build();
+    let err = from_bytes::<Chunk>(&input).unwrap_err();
+    assert_eq!(None, err.path());
+}
+
+#[test]
+fn untagged_enum_over_schema_versions() {
+    #[derive(Deserialize, Debug)]
+    struct Section {
+        #[serde(rename = "Y")]
+        y: i8,
+        #[serde(rename = "BlockStates")]
+        states: Option<LongArray>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct Level {
+        #[serde(rename = "Sections")]
+        sections: Vec<Section>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[serde(untagged)]
+    enum Chunk {
+        Old {
+            #[serde(rename = "Level")]
+            level: Level,
+        },
+        New {
+            sections: Vec<Section>,
+        },
+    }
+
+    let old = Builder::new()
+        .start_compound("")
+        .start_compound("Level")
+        .start_list("Sections", Tag::Compound, 1)
+        .byte("Y", 2)
+        .long_array("BlockStates", &[1, 2])
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+
+    let new = Builder::new()
+        .start_compound("")
+        .start_list("sections", Tag::Compound, 1)
+        .byte("Y", -4)
+        .end_compound()
+        .end_compound()
+        .build();
+
+    match from_bytes(&old).unwrap() {
+        Chunk::Old { level } => {
+            assert_eq!(2, level.sections[0].y);
+            assert_eq!(&[1, 2], &level.sections[0].states.as_ref().unwrap()[..]);
+        }
+        c => panic!("{:?}", c),
+    }
+
+    match from_bytes(&new).unwrap() {
+        Chunk::New { sections } => {
+            assert_eq!(-4, sections[0].y);
+            assert!(sections[0].states.is_none());
+        }
+        c => panic!("{:?}", c),
+    }
+}
+
+#[test]
+fn untagged_enum_distinguishes_arrays_from_lists() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Data {
+        Ints(IntArray),
+        Longs(LongArray),
+        List(Vec<i64>),
+    }
+
+    let input = Builder::new()
+        .start_compound("")
+        .int_array("a", &[1])
+        .long_array("b", &[2])
+        .start_list("c", Tag::Long, 1)
+        .long_payload(3)
+        .end_compound()
+        .build();
+
+    let v: HashMap<String, Data> = from_bytes(&input).unwrap();
+    assert_eq!(Data::Ints(IntArray::new(vec![1])), v["a"]);
+    assert_eq!(Data::Longs(LongArray::new(vec![2])), v["b"]);
+    assert_eq!(Data::List(vec![3]), v["c"]);
+}
+
+#[test]
+fn internally_tagged_enum_with_bool() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(tag = "id")]
+    enum Entity {
+        #[serde(rename = "minecraft:creeper")]
+        Creeper {
+            #[serde(deserialize_with = "crate::de::bool_from_int")]
+            ignited: bool,
+            #[serde(rename = "Pos")]
+            pos: Vec<f64>,
+        },
+    }
+
+    // The tag doesn't need to come first.
+    let input = Builder::new()
+        .start_compound("")
+        .byte("ignited", 1)
+        .start_list("Pos", Tag::Double, 1)
+        .double_payload(1.5)
+        .string("id", "minecraft:creeper")
+        .end_compound()
+        .build();
+
+    let e: Entity = from_bytes(&input).unwrap();
+    assert_eq!(
+        Entity::Creeper {
+            ignited: true,
+            pos: vec![1.5]
+        },
+        e
+    );
+}
+
+#[test]
+fn unnamed_root() {
+    use crate::de::{from_bytes_with_opts, from_reader_with_opts, DeOpts};
+    use crate::RootName;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        a: i32,
+        b: String,
+    }
+
+    // The root compound tag is followed straight away by the first entry.
+    let input = Builder::new()
+        .tag(Tag::Compound)
+        .int("a", 1)
+        .string("b", "text")
+        .end_compound()
+        .build();
+
+    let opts = DeOpts {
+        root_name: RootName::None,
+        ..Default::default()
+    };
+    let expected = V {
+        a: 1,
+        b: "text".to_owned(),
+    };
+
+    assert_eq!(
+        expected,
+        from_bytes_with_opts(&input, opts.clone()).unwrap()
+    );
+    assert_eq!(
+        expected,
+        from_reader_with_opts(input.as_slice(), opts.clone()).unwrap()
+    );
+
+    let v: Value = from_bytes_with_opts(&input, opts).unwrap();
+    assert!(matches!(v, Value::Compound(_)));
+
+    // Reading it as a named root treats the first entry as the name.
+    assert!(from_bytes::<V>(&input).is_err());
+}
+
+#[test]
+fn limits() {
+    use crate::de::{DeOpts, Deserializer, Limits};
+    use crate::error::{ErrorKind, Limit};
+
+    fn limit_hit(input: &[u8], limits: Limits) -> Option<Limit> {
+        let mut de = Deserializer::from_bytes(input).limits(limits);
+        match Value::deserialize(&mut de) {
+            Ok(_) => None,
+            Err(e) => match e.kind() {
+                ErrorKind::LimitExceeded(limit) => Some(*limit),
+                kind => panic!("unexpected error: {:?}", kind),
+            },
+        }
+    }
+
+    // Sizes are checked before any elements are read, so the missing data is
+    // never reached.
+    let huge_list = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Long, i32::MAX)
+        .build();
+    let huge_array = Builder::new()
+        .start_compound("")
+        .tag(Tag::LongArray)
+        .name("arr")
+        .int_payload(i32::MAX)
+        .build();
+
+    let len = Limits {
+        max_len: Some(3),
+        ..Default::default()
+    };
+    assert_eq!(Some(Limit::Length(3)), limit_hit(&huge_list, len));
+    assert_eq!(Some(Limit::Length(3)), limit_hit(&huge_array, len));
+
+    let alloc = |max| Limits {
+        max_alloc: Some(max),
+        ..Default::default()
+    };
+    assert_eq!(
+        Some(Limit::Allocation(1 << 20)),
+        limit_hit(&huge_list, alloc(1 << 20))
+    );
+    assert_eq!(
+        Some(Limit::Allocation(1 << 20)),
+        limit_hit(&huge_array, alloc(1 << 20))
+    );
+
+    // Allocations add up over the whole input, keys included.
+    let arrays = Builder::new()
+        .start_compound("")
+        .long_array("a", &[1; 4])
+        .long_array("b", &[1; 4])
+        .end_compound()
+        .build();
+    assert_eq!(None, limit_hit(&arrays, alloc(66)));
+    assert_eq!(Some(Limit::Allocation(65)), limit_hit(&arrays, alloc(65)));
+    assert_eq!(Some(Limit::Allocation(40)), limit_hit(&arrays, alloc(40)));
+
+    // Limits also apply through DeOpts.
+    let opts = DeOpts {
+        limits: len,
+        ..Default::default()
+    };
+    let err = crate::de::from_bytes_with_opts::<Value>(&huge_list, opts).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::LimitExceeded(Limit::Length(3))
+    ));
+    assert_eq!(Some("list"), err.path());
+}
+
+#[test]
+fn depth_limit() {
+    use crate::de::{Deserializer, DEFAULT_MAX_DEPTH};
+    use crate::error::ErrorKind;
+    use serde::de::IgnoredAny;
+
+    fn depth_hit<'a, T: Deserialize<'a>>(input: &'a [u8], max: Option<usize>) -> Option<usize> {
+        let mut de = Deserializer::from_bytes(input).max_depth(max);
+        match T::deserialize(&mut de) {
+            Ok(_) => None,
+            Err(e) => match e.kind() {
+                ErrorKind::DepthLimit(max) => Some(*max),
+                kind => panic!("unexpected error: {:?}", kind),
+            },
+        }
+    }
+
+    let nested = Builder::new()
+        .start_compound("")
+        .start_compound("a")
+        .start_list("b", Tag::Compound, 1)
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+
+    assert_eq!(None, depth_hit::<Value>(&nested, None));
+    assert_eq!(None, depth_hit::<Value>(&nested, Some(4)));
+    assert_eq!(Some(3), depth_hit::<Value>(&nested, Some(3)));
+    assert_eq!(Some(1), depth_hit::<Value>(&nested, Some(1)));
+
+    // Skipped values count too.
+    assert_eq!(None, depth_hit::<IgnoredAny>(&nested, Some(4)));
+    assert_eq!(Some(3), depth_hit::<IgnoredAny>(&nested, Some(3)));
+    assert_eq!(
+        Some(3),
+        depth_hit::<HashMap<String, IgnoredAny>>(&nested, Some(3))
+    );
+
+    // Lists of lists, nested to a given depth counting the root compound.
+    let lists = |depth: usize| {
+        let mut builder = Builder::new().start_compound("").tag(Tag::List).name("a");
+        for _ in 2..depth {
+            builder = builder.tag(Tag::List).int_payload(1);
+        }
+        builder.tag(Tag::End).int_payload(0).end_compound().build()
+    };
+
+    let at_limit = lists(DEFAULT_MAX_DEPTH);
+    let over_limit = lists(DEFAULT_MAX_DEPTH + 1);
+    let default = Some(DEFAULT_MAX_DEPTH);
+    assert_eq!(None, depth_hit::<Value>(&at_limit, default));
+    assert_eq!(default, depth_hit::<Value>(&over_limit, default));
+    assert_eq!(None, depth_hit::<IgnoredAny>(&at_limit, default));
+    assert_eq!(default, depth_hit::<IgnoredAny>(&over_limit, default));
+
+    // Far deeper input is an error rather than overflowing the stack, with
+    // the default limit.
+    let deep = lists(1_000_000);
+    let err = from_bytes::<Value>(&deep).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::DepthLimit(DEFAULT_MAX_DEPTH)
+    ));
+    let err = from_bytes::<HashMap<String, IgnoredAny>>(&deep).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::DepthLimit(DEFAULT_MAX_DEPTH)
+    ));
+}
@@ -206,3 +206,80 @@ fn nice_error_if_deserialize_array_to_seq() {
         Err(e) => assert!(e.to_string().contains("Array")),
     }
 }
+
+#[test]
+fn borrowed_arrays_random_access() {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        #[serde(borrow)]
+        bytes: borrow::ByteArray<'a>,
+        #[serde(borrow)]
+        ints: borrow::IntArray<'a>,
+        #[serde(borrow)]
+        longs: borrow::LongArray<'a>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .byte_array("bytes", &[1, -2, 3])
+        .int_array("ints", &[4, -5])
+        .long_array("longs", &[i64::MIN, 7, i64::MAX])
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice()).unwrap();
+
+    assert_eq!(v.bytes.len(), 3);
+    assert_eq!(v.bytes.get(1), Some(-2));
+    assert_eq!(v.bytes.get(3), None);
+
+    assert_eq!(v.ints.len(), 2);
+    assert_eq!(v.ints.get(1), Some(-5));
+    assert_eq!(v.ints.get(2), None);
+    assert_eq!(v.ints.as_bytes(), [0, 0, 0, 4, 255, 255, 255, 251]);
+
+    assert_eq!(v.longs.len(), 3);
+    assert_eq!(v.longs.get(0), Some(i64::MIN));
+    assert_eq!(v.longs.get(2), Some(i64::MAX));
+    assert_eq!(v.longs.get(usize::MAX), None);
+    assert!(v.longs.iter().rev().eq([i64::MAX, 7, i64::MIN]));
+    assert_eq!(v.longs.iter().len(), 3);
+}
+
+#[test]
+fn borrowed_arrays_into_owned() {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        #[serde(borrow)]
+        bytes: borrow::ByteArray<'a>,
+        #[serde(borrow)]
+        ints: borrow::IntArray<'a>,
+        #[serde(borrow)]
+        longs: borrow::LongArray<'a>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .byte_array("bytes", &[1, 2])
+        .int_array("ints", &[3, 4])
+        .long_array("longs", &[5, 6])
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(payload.as_slice()).unwrap();
+
+    assert_eq!(ByteArray::from(v.bytes), ByteArray::new(vec![1, 2]));
+    assert_eq!(IntArray::from(v.ints), IntArray::new(vec![3, 4]));
+    assert_eq!(LongArray::from(v.longs), LongArray::new(vec![5, 6]));
+}
+
+#[test]
+fn owned_arrays_access() {
+    let ints = IntArray::from(vec![1, 2, 3]);
+    assert_eq!(ints.as_slice(), [1, 2, 3]);
+    assert_eq!((&ints).into_iter().sum::<i32>(), 6);
+    assert_eq!(ints.into_inner(), vec![1, 2, 3]);
+
+    let longs = LongArray::new(vec![4, 5]);
+    assert!(longs.into_iter().eq([4, 5]));
+}
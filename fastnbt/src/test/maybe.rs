@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::builder::Builder;
+use crate::{de::from_bytes, ser::to_bytes, ByteArray, IntArray, LongArray, Maybe, Tag, Value};
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Options {
+    byte: Option<i8>,
+    short: Option<i16>,
+    int: Option<i32>,
+    long: Option<i64>,
+    float: Option<f32>,
+    double: Option<f64>,
+    string: Option<String>,
+    list: Option<Vec<i32>>,
+    compound: Option<HashMap<String, i32>>,
+    byte_array: Option<ByteArray>,
+    int_array: Option<IntArray>,
+    long_array: Option<LongArray>,
+    value: Option<Value>,
+}
+
+#[test]
+fn missing_tags_are_none() {
+    let input = Builder::new().start_compound("").end_compound().build();
+    let v: Options = from_bytes(&input).unwrap();
+
+    assert_eq!(
+        Options {
+            byte: None,
+            short: None,
+            int: None,
+            long: None,
+            float: None,
+            double: None,
+            string: None,
+            list: None,
+            compound: None,
+            byte_array: None,
+            int_array: None,
+            long_array: None,
+            value: None,
+        },
+        v
+    );
+}
+
+#[test]
+fn present_tags_are_some() {
+    let input = Builder::new()
+        .start_compound("")
+        .byte("byte", 1)
+        .short("short", 2)
+        .int("int", 3)
+        .long("long", 4)
+        .float("float", 5.0)
+        .double("double", 6.0)
+        .string("string", "7")
+        .start_list("list", Tag::Int, 1)
+        .int_payload(8)
+        .start_compound("compound")
+        .int("a", 9)
+        .end_compound()
+        .byte_array("byte_array", &[10])
+        .int_array("int_array", &[11])
+        .long_array("long_array", &[12])
+        .int("value", 13)
+        .end_compound()
+        .build();
+
+    let v: Options = from_bytes(&input).unwrap();
+
+    assert_eq!(
+        Options {
+            byte: Some(1),
+            short: Some(2),
+            int: Some(3),
+            long: Some(4),
+            float: Some(5.0),
+            double: Some(6.0),
+            string: Some("7".to_owned()),
+            list: Some(vec![8]),
+            compound: Some(HashMap::from([("a".to_owned(), 9)])),
+            byte_array: Some(ByteArray::new(vec![10])),
+            int_array: Some(IntArray::new(vec![11])),
+            long_array: Some(LongArray::new(vec![12])),
+            value: Some(Value::Int(13)),
+        },
+        v
+    );
+}
+
+#[test]
+fn empty_tags_are_some() {
+    let input = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::End, 0)
+        .start_compound("compound")
+        .end_compound()
+        .byte_array("byte_array", &[])
+        .long_array("long_array", &[])
+        .end_compound()
+        .build();
+
+    let v: Options = from_bytes(&input).unwrap();
+    assert_eq!(Some(vec![]), v.list);
+    assert_eq!(Some(HashMap::new()), v.compound);
+    assert_eq!(Some(ByteArray::new(vec![])), v.byte_array);
+    assert_eq!(Some(LongArray::new(vec![])), v.long_array);
+}
+
+#[test]
+fn defaults_for_missing_tags() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Defaults {
+        #[serde(default)]
+        int: i32,
+        #[serde(default)]
+        string: String,
+        #[serde(default)]
+        list: Vec<i32>,
+        #[serde(default)]
+        compound: HashMap<String, i32>,
+        #[serde(default)]
+        long_array: LongArray,
+    }
+
+    let input = Builder::new().start_compound("").end_compound().build();
+    let v: Defaults = from_bytes(&input).unwrap();
+
+    assert_eq!(
+        Defaults {
+            int: 0,
+            string: String::new(),
+            list: vec![],
+            compound: HashMap::new(),
+            long_array: LongArray::new(vec![]),
+        },
+        v
+    );
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+struct Maybes {
+    int: Maybe<i32>,
+    list: Maybe<Vec<i32>>,
+    states: Maybe<LongArray>,
+}
+
+#[test]
+fn maybe_absent() {
+    let input = Builder::new().start_compound("").end_compound().build();
+    let v: Maybes = from_bytes(&input).unwrap();
+
+    assert!(v.int.is_absent());
+    assert!(v.list.is_absent());
+    assert!(v.states.is_absent());
+}
+
+#[test]
+fn maybe_empty() {
+    // Empty lists are often a list of 'end', even where the field would be an
+    // array if it had anything in it.
+    let input = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Int, 0)
+        .start_list("states", Tag::End, 0)
+        .end_compound()
+        .build();
+    let v: Maybes = from_bytes(&input).unwrap();
+
+    assert!(v.int.is_absent());
+    assert!(v.list.is_empty());
+    assert!(v.states.is_empty());
+
+    let input = Builder::new()
+        .start_compound("")
+        .long_array("states", &[])
+        .end_compound()
+        .build();
+    let v: Maybes = from_bytes(&input).unwrap();
+    assert!(v.states.is_empty());
+}
+
+#[test]
+fn maybe_present() {
+    let input = Builder::new()
+        .start_compound("")
+        .int("int", 1)
+        .start_list("list", Tag::Int, 2)
+        .int_payload(2)
+        .int_payload(3)
+        .long_array("states", &[4, 5])
+        .end_compound()
+        .build();
+    let v: Maybes = from_bytes(&input).unwrap();
+
+    assert_eq!(
+        Maybes {
+            int: Maybe::Present(1),
+            list: Maybe::Present(vec![2, 3]),
+            states: Maybe::Present(LongArray::new(vec![4, 5])),
+        },
+        v
+    );
+}
+
+#[test]
+fn maybe_in_list() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct V {
+        lists: Vec<Maybe<Vec<i8>>>,
+    }
+
+    let input = Builder::new()
+        .start_compound("")
+        .start_list("lists", Tag::List, 2)
+        .tag(Tag::End)
+        .int_payload(0)
+        .tag(Tag::Byte)
+        .int_payload(1)
+        .byte_payload(6)
+        .end_compound()
+        .build();
+    let v: V = from_bytes(&input).unwrap();
+
+    assert_eq!(vec![Maybe::Empty, Maybe::Present(vec![6])], v.lists);
+}
+
+#[test]
+fn maybe_round_trip() {
+    let v = Maybes {
+        int: Maybe::Absent,
+        list: Maybe::Empty,
+        states: Maybe::Present(LongArray::new(vec![1])),
+    };
+
+    let bs = to_bytes(&v).unwrap();
+    let expected = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::End, 0)
+        .long_array("states", &[1])
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+}
@@ -3,11 +3,17 @@ use std::convert::{TryFrom, TryInto};
 use crate::Tag;
 
 mod builder;
+#[cfg(feature = "compression")]
+mod compressed;
 mod de;
 mod fuzz;
 mod minecraft_chunk;
+mod reader;
+mod ser;
 mod stream;
+mod uuid;
 mod de_arrays;
+mod maybe;
 mod value;
 
 fn assert_try_into(tag: Tag) {
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::de::{from_bytes, from_reader};
+use crate::error::Result;
+use crate::{borrow, IntArray, LongArray, Tag, Value};
+
+use super::builder::Builder;
+
+#[test]
+fn simple_struct() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V {
+        abc: i8,
+        def: String,
+        ghi: f64,
+    }
+
+    let payload = Builder::new()
+        .start_compound("object")
+        .byte("abc", 123)
+        .string("def", "hello")
+        .double("ghi", 1.5)
+        .end_compound()
+        .build();
+
+    let v: V = from_reader(payload.as_slice())?;
+
+    assert_eq!(v.abc, 123);
+    assert_eq!(v.def, "hello");
+    assert_eq!(v.ghi, 1.5);
+    Ok(())
+}
+
+#[test]
+fn same_value_as_from_bytes() -> Result<()> {
+    let payload = Builder::new()
+        .start_compound("")
+        .start_list("list", Tag::Compound, 2)
+        .string("a", "1")
+        .end_compound()
+        .int("b", 2)
+        .end_compound()
+        .int_array("ints", &[1, 2, 3])
+        .long_array("longs", &[4, 5])
+        .byte_array("bytes", &[6, 7])
+        .string("unicode", "😈")
+        .end_compound()
+        .build();
+
+    let from_slice: Value = from_bytes(payload.as_slice())?;
+    let from_read: Value = from_reader(payload.as_slice())?;
+
+    assert_eq!(from_slice, from_read);
+    Ok(())
+}
+
+#[test]
+fn arrays() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V {
+        ints: IntArray,
+        longs: LongArray,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .int_array("ints", &[1, 2, 3])
+        .long_array("longs", &[i64::MAX, -1])
+        .end_compound()
+        .build();
+
+    let v: V = from_reader(payload.as_slice())?;
+
+    assert_eq!(*v.ints, [1, 2, 3]);
+    assert_eq!(*v.longs, [i64::MAX, -1]);
+    Ok(())
+}
+
+#[test]
+fn skips_unwanted_fields() -> Result<()> {
+    #[derive(Deserialize)]
+    struct V {
+        wanted: i32,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .start_compound("unwanted")
+        .long_array("longs", &[1, 2, 3])
+        .string("s", "abc")
+        .end_compound()
+        .int("wanted", 42)
+        .end_compound()
+        .build();
+
+    let v: V = from_reader(payload.as_slice())?;
+
+    assert_eq!(v.wanted, 42);
+    Ok(())
+}
+
+#[test]
+fn cannot_borrow_from_reader() {
+    #[derive(Deserialize)]
+    struct V<'a> {
+        #[serde(borrow)]
+        _longs: borrow::LongArray<'a>,
+    }
+
+    let payload = Builder::new()
+        .start_compound("")
+        .long_array("_longs", &[1, 2, 3])
+        .end_compound()
+        .build();
+
+    let mut de = crate::de::Deserializer::from_reader(payload.as_slice());
+    let v: Result<V> = V::deserialize(&mut de);
+    assert!(v.is_err());
+}
+
+#[test]
+fn truncated_input_errors() {
+    let payload = Builder::new()
+        .start_compound("")
+        .string("some string", "some long value")
+        .end_compound()
+        .build();
+
+    for len in 0..payload.len() {
+        let v: Result<HashMap<String, Value>> = from_reader(&payload[..len]);
+        assert!(v.is_err());
+    }
+}
+
+#[test]
+fn huge_declared_size_does_not_allocate_up_front() {
+    let payload = Builder::new()
+        .start_compound("")
+        .tag(Tag::ByteArray)
+        .name("bytes")
+        .int_payload(i32::MAX)
+        .end_compound()
+        .build();
+
+    let v: Result<Value> = from_reader(payload.as_slice());
+    assert!(v.is_err());
+}
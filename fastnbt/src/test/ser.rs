@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::builder::Builder;
+use crate::{de::from_bytes, ser::to_bytes, ByteArray, IntArray, LongArray, Tag, Value};
+
+#[test]
+fn simple_struct() {
+    #[derive(Serialize)]
+    struct V {
+        abc: i32,
+        def: String,
+    }
+
+    let bs = to_bytes(&V {
+        abc: 123,
+        def: "hello".to_owned(),
+    })
+    .unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .int("abc", 123)
+        .string("def", "hello")
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn numeric_types() {
+    #[derive(Serialize)]
+    struct V {
+        a: i8,
+        b: i16,
+        c: i32,
+        d: i64,
+        e: f32,
+        f: f64,
+        g: bool,
+        h: u8,
+    }
+
+    let bs = to_bytes(&V {
+        a: 1,
+        b: 2,
+        c: 3,
+        d: 4,
+        e: 5.0,
+        f: 6.0,
+        g: true,
+        h: 255,
+    })
+    .unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .byte("a", 1)
+        .short("b", 2)
+        .int("c", 3)
+        .long("d", 4)
+        .float("e", 5.0)
+        .double("f", 6.0)
+        .byte("g", 1)
+        .byte("h", -1)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn lists() {
+    #[derive(Serialize)]
+    struct V {
+        ints: Vec<i32>,
+        empty: Vec<String>,
+        nested: Vec<Vec<i8>>,
+    }
+
+    let bs = to_bytes(&V {
+        ints: vec![1, 2],
+        empty: vec![],
+        nested: vec![vec![3]],
+    })
+    .unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .start_list("ints", Tag::Int, 2)
+        .int_payload(1)
+        .int_payload(2)
+        .start_list("empty", Tag::End, 0)
+        .start_list("nested", Tag::List, 1)
+        .tag(Tag::Byte)
+        .int_payload(1)
+        .byte_payload(3)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn arrays() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V {
+        bytes: ByteArray,
+        ints: IntArray,
+        longs: LongArray,
+    }
+
+    let v = V {
+        bytes: ByteArray::new(vec![1, -1]),
+        ints: IntArray::new(vec![2, -2]),
+        longs: LongArray::new(vec![3, -3]),
+    };
+    let bs = to_bytes(&v).unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .byte_array("bytes", &[1, -1])
+        .int_array("ints", &[2, -2])
+        .long_array("longs", &[3, -3])
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes::<V>(&bs).unwrap());
+}
+
+#[test]
+fn none_fields_are_skipped() {
+    #[derive(Serialize)]
+    struct V {
+        a: Option<i32>,
+        b: Option<i32>,
+    }
+
+    let bs = to_bytes(&V {
+        a: None,
+        b: Some(1),
+    })
+    .unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .int("b", 1)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn enums() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum E {
+        Unit,
+        Newtype(i32),
+        Struct { a: i8 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V {
+        unit: E,
+        newtype: E,
+        structure: E,
+    }
+
+    let v = V {
+        unit: E::Unit,
+        newtype: E::Newtype(1),
+        structure: E::Struct { a: 2 },
+    };
+    let bs = to_bytes(&v).unwrap();
+
+    let expected = Builder::new()
+        .start_compound("")
+        .string("unit", "Unit")
+        .start_compound("newtype")
+        .int("Newtype", 1)
+        .end_compound()
+        .start_compound("structure")
+        .start_compound("Struct")
+        .byte("a", 2)
+        .end_compound()
+        .end_compound()
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+}
+
+#[test]
+fn value_round_trip() {
+    let mut inner = HashMap::new();
+    inner.insert("long".to_owned(), Value::Long(1));
+    inner.insert("string".to_owned(), Value::String("a".to_owned()));
+
+    let mut root = HashMap::new();
+    root.insert("byte".to_owned(), Value::Byte(1));
+    root.insert("short".to_owned(), Value::Short(2));
+    root.insert("int".to_owned(), Value::Int(3));
+    root.insert("double".to_owned(), Value::Double(4.5));
+    root.insert("string".to_owned(), Value::String("🎉".to_owned()));
+    root.insert(
+        "list".to_owned(),
+        Value::List(vec![Value::Short(1), Value::Short(2)]),
+    );
+    root.insert(
+        "longs".to_owned(),
+        Value::LongArray(LongArray::new(vec![1, 2])),
+    );
+    root.insert("compound".to_owned(), Value::Compound(inner));
+
+    let bs = to_bytes(&root).unwrap();
+    let back: HashMap<String, Value> = from_bytes(&bs).unwrap();
+
+    assert_eq!(root.get("byte"), back.get("byte"));
+    assert_eq!(root.get("short"), back.get("short"));
+    assert_eq!(root.get("int"), back.get("int"));
+    assert_eq!(root.get("string"), back.get("string"));
+    assert_eq!(root.get("list"), back.get("list"));
+    assert_eq!(root.get("longs"), back.get("longs"));
+    assert_eq!(root.get("compound"), back.get("compound"));
+}
+
+#[test]
+fn root_must_be_compound() {
+    assert!(to_bytes(&1i32).is_err());
+    assert!(to_bytes(&vec![1i32]).is_err());
+}
+
+#[test]
+fn mixed_list_is_error() {
+    #[derive(Serialize)]
+    struct V {
+        list: Vec<Value>,
+    }
+
+    let v = V {
+        list: vec![Value::Int(1), Value::String("a".to_owned())],
+    };
+
+    assert!(to_bytes(&v).is_err());
+}
+
+#[test]
+fn non_string_keys_are_error() {
+    let mut map = HashMap::new();
+    map.insert(1, 2);
+
+    assert!(to_bytes(&map).is_err());
+}
+
+#[test]
+fn unnamed_root() {
+    use crate::ser::{to_bytes_with_opts, SerOpts};
+    use crate::RootName;
+
+    #[derive(Serialize)]
+    struct V {
+        a: i32,
+    }
+
+    let bs = to_bytes_with_opts(
+        &V { a: 1 },
+        SerOpts {
+            root_name: RootName::None,
+        },
+    )
+    .unwrap();
+
+    let expected = Builder::new()
+        .tag(Tag::Compound)
+        .int("a", 1)
+        .end_compound()
+        .build();
+
+    assert_eq!(expected, bs);
+
+    // Named is the default.
+    let bs = to_bytes_with_opts(&V { a: 1 }, SerOpts::default()).unwrap();
+    assert_eq!(to_bytes(&V { a: 1 }).unwrap(), bs);
+}
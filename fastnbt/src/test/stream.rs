@@ -1,5 +1,5 @@
 use super::builder::Builder;
-use crate::stream::{Name, Parser, Result, Value};
+use crate::stream::{find_path, skip, skip_list, Name, Parser, Result, Value};
 use crate::Tag;
 
 fn name(n: &str) -> Name {
@@ -385,3 +385,108 @@ fn list_of_compounds() -> Result<()> {
     assert!(matches!(parser.next(), Err(e) if e.is_eof()));
     Ok(())
 }
+
+fn nested_payload() -> Vec<u8> {
+    Builder::new()
+        .start_compound("")
+        .start_list("things", Tag::Compound, 2)
+        .string("name", "inner")
+        .tag(Tag::End)
+        .tag(Tag::End)
+        .start_compound("Data")
+        .long_array("big", &[1, 2, 3])
+        .start_compound("Player")
+        .int("XpLevel", 30)
+        .end_compound()
+        .end_compound()
+        .byte("after", 1)
+        .end_compound()
+        .build()
+}
+
+#[test]
+fn find_path_skips_other_values() -> Result<()> {
+    let payload = nested_payload();
+    let mut parser = Parser::new(payload.as_slice());
+
+    let value = find_path(&mut parser, &["Data", "Player", "XpLevel"])?;
+    assert_eq!(value, Some(Value::Int(name("XpLevel"), 30)));
+    assert_eq!(parser.depth(), 3);
+    Ok(())
+}
+
+#[test]
+fn find_path_missing() -> Result<()> {
+    let payload = nested_payload();
+
+    let mut parser = Parser::new(payload.as_slice());
+    assert_eq!(find_path(&mut parser, &["Data", "Nope"])?, None);
+
+    // Can't go through a value that isn't a compound.
+    let mut parser = Parser::new(payload.as_slice());
+    assert_eq!(find_path(&mut parser, &["after", "x"])?, None);
+
+    // The name in the list isn't at the top level.
+    let mut parser = Parser::new(payload.as_slice());
+    assert_eq!(find_path(&mut parser, &["name"])?, None);
+    Ok(())
+}
+
+#[test]
+fn find_path_leaves_parser_inside() -> Result<()> {
+    let payload = nested_payload();
+    let mut parser = Parser::new(payload.as_slice());
+
+    let value = find_path(&mut parser, &["things"])?.unwrap();
+    assert_eq!(value, Value::List(name("things"), Tag::Compound, 2));
+    assert_eq!(parser.next()?, Value::Compound(None));
+    assert_eq!(
+        parser.next()?,
+        Value::String(name("name"), "inner".to_owned())
+    );
+    Ok(())
+}
+
+#[test]
+fn skipping() -> Result<()> {
+    let payload = nested_payload();
+    let mut parser = Parser::new(payload.as_slice());
+
+    parser.next()?;
+    let list = parser.next()?;
+    assert_eq!(list.name(), Some("things"));
+    skip(&mut parser, &list)?;
+
+    let data = parser.next()?;
+    skip(&mut parser, &data)?;
+    assert_eq!(parser.next()?, Value::Byte(name("after"), 1));
+    assert_eq!(parser.depth(), 1);
+
+    let payload = Builder::new()
+        .start_list("lists", Tag::List, 1)
+        .tag(Tag::Int)
+        .int_payload(1)
+        .int_payload(5)
+        .byte("after", 2)
+        .build();
+    let mut parser = Parser::new(payload.as_slice());
+    parser.next()?;
+    skip_list(&mut parser)?;
+    assert_eq!(parser.next()?, Value::Byte(name("after"), 2));
+    Ok(())
+}
+
+#[test]
+fn cesu8_strings() -> Result<()> {
+    let devil = cesu8::to_java_cesu8("😈");
+    let payload = Builder::new()
+        .tag(Tag::String)
+        .name("😈")
+        .raw_len(devil.len())
+        .raw_bytes(&devil)
+        .build();
+
+    let mut parser = Parser::new(payload.as_slice());
+    assert_eq!(parser.next()?, Value::String(name("😈"), "😈".to_owned()));
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use super::builder::Builder;
+use crate::{de::from_bytes, ser::to_bytes, Tag};
+
+const UUID: u128 = 0x0102_0304_f5f6_f7f8_0000_0001_ffff_ffff;
+const INTS: [i32; 4] = [0x0102_0304, 0xf5f6_f7f8_u32 as i32, 1, -1];
+
+#[test]
+fn int_conversions() {
+    assert_eq!(INTS, crate::uuid::to_ints(UUID));
+    assert_eq!(UUID, crate::uuid::from_ints(INTS));
+    assert_eq!(0, crate::uuid::from_ints([0; 4]));
+    assert_eq!(u128::MAX, crate::uuid::from_ints([-1; 4]));
+}
+
+#[test]
+fn u128_is_int_array() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V {
+        id: u128,
+        signed: i128,
+        ids: Vec<u128>,
+    }
+
+    let v = V {
+        id: UUID,
+        signed: -1,
+        ids: vec![1],
+    };
+
+    let expected = Builder::new()
+        .start_compound("")
+        .int_array("id", &INTS)
+        .int_array("signed", &[-1; 4])
+        .start_list("ids", Tag::IntArray, 1)
+        .int_payload(4)
+        .int_array_payload(&[0, 0, 0, 1])
+        .end_compound()
+        .build();
+
+    let bs = to_bytes(&v).unwrap();
+    assert_eq!(expected, bs);
+    assert_eq!(v, from_bytes(&bs).unwrap());
+}
+
+#[test]
+fn u128_needs_four_ints() {
+    #[derive(Deserialize, Debug)]
+    struct V {
+        #[allow(dead_code)]
+        id: u128,
+    }
+
+    let input = Builder::new()
+        .start_compound("")
+        .int_array("id", &[1, 2, 3])
+        .end_compound()
+        .build();
+    assert!(from_bytes::<V>(&input).is_err());
+
+    let input = Builder::new()
+        .start_compound("")
+        .long_array("id", &[1, 2])
+        .end_compound()
+        .build();
+    assert!(from_bytes::<V>(&input).is_err());
+}
+
+#[test]
+fn optional_uuid() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V {
+        #[serde(with = "crate::uuid::option", default)]
+        owner: Option<u128>,
+    }
+
+    let input = Builder::new().start_compound("").end_compound().build();
+    let v: V = from_bytes(&input).unwrap();
+    assert_eq!(None, v.owner);
+    assert_eq!(input, to_bytes(&v).unwrap());
+
+    let input = Builder::new()
+        .start_compound("")
+        .int_array("owner", &INTS)
+        .end_compound()
+        .build();
+    let v: V = from_bytes(&input).unwrap();
+    assert_eq!(Some(UUID), v.owner);
+    assert_eq!(input, to_bytes(&v).unwrap());
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn uuid_crate() {
+    use uuid::Uuid;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct V {
+        #[serde(rename = "UUID", with = "crate::uuid")]
+        uuid: Uuid,
+    }
+
+    let input = Builder::new()
+        .start_compound("")
+        .int_array("UUID", &INTS)
+        .end_compound()
+        .build();
+
+    let v: V = from_bytes(&input).unwrap();
+    assert_eq!(Uuid::from_u128(UUID), v.uuid);
+    assert_eq!(input, to_bytes(&v).unwrap());
+}
@@ -202,3 +202,64 @@ fn distinguish_compound() {
     let v: Value = from_bytes(&input).unwrap();
     assert_contains!(v, "a", Value::Compound(_));
 }
+
+#[cfg(feature = "json")]
+mod json {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use crate::value::{from_json, to_json};
+    use crate::{IntArray, Value};
+
+    #[test]
+    fn nbt_to_json() {
+        let mut map = HashMap::new();
+        map.insert("byte".to_owned(), Value::Byte(-1));
+        map.insert("float".to_owned(), Value::Float(0.5));
+        map.insert("nan".to_owned(), Value::Double(f64::NAN));
+        map.insert(
+            "ints".to_owned(),
+            Value::IntArray(IntArray::new(vec![1, 2])),
+        );
+        map.insert(
+            "list".to_owned(),
+            Value::List(vec![Value::String("a".to_owned())]),
+        );
+
+        assert_eq!(
+            json!({"byte": -1, "float": 0.5, "nan": null, "ints": [1, 2], "list": ["a"]}),
+            to_json(&Value::Compound(map))
+        );
+    }
+
+    #[test]
+    fn json_to_nbt() {
+        let nbt = from_json(&json!({"a": true, "b": [1, 5000000000i64], "c": "s"})).unwrap();
+
+        let mut expected = HashMap::new();
+        expected.insert("a".to_owned(), Value::Byte(1));
+        expected.insert(
+            "b".to_owned(),
+            Value::List(vec![Value::Long(1), Value::Long(5000000000)]),
+        );
+        expected.insert("c".to_owned(), Value::String("s".to_owned()));
+        assert_eq!(Value::Compound(expected), nbt);
+    }
+
+    #[test]
+    fn json_without_nbt_equivalent() {
+        assert!(from_json(&json!(null)).is_err());
+        assert!(from_json(&json!([1, "a"])).is_err());
+        assert!(from_json(&json!([[1], {"a": 1}])).is_err());
+
+        // Lists of lists can hold different types.
+        assert!(from_json(&json!([[1], ["a"]])).is_ok());
+    }
+
+    #[test]
+    fn round_trip() {
+        let json = json!({"pos": [1.5, -2.0], "name": "x", "nested": {"n": 3}});
+        assert_eq!(json, to_json(&from_json(&json).unwrap()));
+    }
+}
@@ -0,0 +1,120 @@
+//! UUIDs, stored as an IntArray of four ints with the most significant first.
+//! Minecraft has stored UUIDs this way since 1.16, for example in the `UUID`
+//! field of entities.
+//!
+//! A `u128` (or `i128`) field serializes and deserializes in this format
+//! without any extra work. With the `uuid` feature, this module can be used
+//! with serde's `with` attribute to do the same for [`uuid::Uuid`]:
+//!
+//! ```
+//! # #[cfg(feature = "uuid")]
+//! # {
+//! use serde::{Deserialize, Serialize};
+//! use uuid::Uuid;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Entity {
+//!     #[serde(rename = "UUID", with = "fastnbt::uuid")]
+//!     uuid: Uuid,
+//!     #[serde(rename = "Owner", with = "fastnbt::uuid::option", default)]
+//!     owner: Option<Uuid>,
+//! }
+//! # }
+//! ```
+//!
+//! [`to_ints`] and [`from_ints`] convert to and from the four ints directly.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Split a UUID into four ints, most significant first.
+///
+/// ```
+/// let ints = fastnbt::uuid::to_ints(0x0000_0001_0000_0002_0000_0003_ffff_ffff);
+/// assert_eq!([1, 2, 3, -1], ints);
+/// ```
+pub fn to_ints(uuid: u128) -> [i32; 4] {
+    [
+        (uuid >> 96) as u32 as i32,
+        (uuid >> 64) as u32 as i32,
+        (uuid >> 32) as u32 as i32,
+        uuid as u32 as i32,
+    ]
+}
+
+/// Join four ints, most significant first, into a UUID.
+pub fn from_ints(ints: [i32; 4]) -> u128 {
+    ints.iter()
+        .fold(0, |acc, i| (acc << 32) | (*i as u32 as u128))
+}
+
+/// Types that can be stored as a UUID. This is implemented for `u128`, and
+/// for [`uuid::Uuid`] with the `uuid` feature.
+pub trait AsUuid: private::Sealed {
+    #[doc(hidden)]
+    fn to_u128(&self) -> u128;
+
+    #[doc(hidden)]
+    fn from_u128(v: u128) -> Self;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+impl private::Sealed for u128 {}
+
+impl AsUuid for u128 {
+    fn to_u128(&self) -> u128 {
+        *self
+    }
+
+    fn from_u128(v: u128) -> Self {
+        v
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl private::Sealed for ::uuid::Uuid {}
+
+#[cfg(feature = "uuid")]
+impl AsUuid for ::uuid::Uuid {
+    fn to_u128(&self) -> u128 {
+        self.as_u128()
+    }
+
+    fn from_u128(v: u128) -> Self {
+        ::uuid::Uuid::from_u128(v)
+    }
+}
+
+/// Serialize a UUID as an IntArray. For use with `#[serde(with = "fastnbt::uuid")]`.
+pub fn serialize<T: AsUuid, S: Serializer>(uuid: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    uuid.to_u128().serialize(serializer)
+}
+
+/// Deserialize a UUID from an IntArray. For use with `#[serde(with = "fastnbt::uuid")]`.
+pub fn deserialize<'de, T: AsUuid, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    u128::deserialize(deserializer).map(T::from_u128)
+}
+
+/// Like the parent module, but for optional UUIDs. Use with
+/// `#[serde(with = "fastnbt::uuid::option", default)]`.
+pub mod option {
+    use super::AsUuid;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize an optional UUID as an IntArray, leaving it out if `None`.
+    pub fn serialize<T: AsUuid, S: Serializer>(
+        uuid: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        uuid.as_ref().map(AsUuid::to_u128).serialize(serializer)
+    }
+
+    /// Deserialize an optional UUID from an IntArray.
+    pub fn deserialize<'de, T: AsUuid, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        Ok(Option::<u128>::deserialize(deserializer)?.map(T::from_u128))
+    }
+}
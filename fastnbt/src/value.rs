@@ -1,6 +1,8 @@
+//! The [`Value`] type, which can hold any NBT, and conversions of it.
+
 use std::collections::HashMap;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{ByteArray, IntArray, LongArray};
 
@@ -35,7 +37,9 @@ pub enum Value {
     #[serde(deserialize_with = "strict_i32")]
     Int(i32),
     Long(i64),
+    #[serde(deserialize_with = "strict_f64")]
     Double(f64),
+    #[serde(deserialize_with = "strict_f32")]
     Float(f32),
     String(String),
     ByteArray(ByteArray),
@@ -45,6 +49,28 @@ pub enum Value {
     Compound(HashMap<String, Value>),
 }
 
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Value::Byte(v) => serializer.serialize_i8(*v),
+            Value::Short(v) => serializer.serialize_i16(*v),
+            Value::Int(v) => serializer.serialize_i32(*v),
+            Value::Long(v) => serializer.serialize_i64(*v),
+            Value::Double(v) => serializer.serialize_f64(*v),
+            Value::Float(v) => serializer.serialize_f32(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::ByteArray(v) => v.serialize(serializer),
+            Value::IntArray(v) => v.serialize(serializer),
+            Value::LongArray(v) => v.serialize(serializer),
+            Value::List(v) => v.serialize(serializer),
+            Value::Compound(v) => v.serialize(serializer),
+        }
+    }
+}
+
 fn strict_i8<'de, D>(de: D) -> std::result::Result<i8, D::Error>
 where
     D: serde::de::Deserializer<'de>,
@@ -113,3 +139,201 @@ where
 
     de.deserialize_i32(Stricti32Visitor)
 }
+
+// Untagged enums try each variant in turn, and serde's visitors for floats
+// accept either width, so without these a Float would come out as a Double.
+fn strict_f32<'de, D>(de: D) -> std::result::Result<f32, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    struct Strictf32Visitor;
+    impl<'de> serde::de::Visitor<'de> for Strictf32Visitor {
+        type Value = f32;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(formatter, "expecting exactly f32")
+        }
+
+        fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(v)
+        }
+    }
+
+    de.deserialize_f32(Strictf32Visitor)
+}
+
+fn strict_f64<'de, D>(de: D) -> std::result::Result<f64, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    struct Strictf64Visitor;
+    impl<'de> serde::de::Visitor<'de> for Strictf64Visitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(formatter, "expecting exactly f64")
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(v)
+        }
+
+        // The default forwards to visit_f64.
+        fn visit_f32<E>(self, v: f32) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Err(E::invalid_type(
+                serde::de::Unexpected::Float(v as f64),
+                &self,
+            ))
+        }
+    }
+
+    de.deserialize_f64(Strictf64Visitor)
+}
+
+/// Convert a [`Value`] into JSON. Numbers and strings map to their JSON
+/// equivalents, lists and the NBT arrays to JSON arrays, and compounds to
+/// objects. Floats that are not finite become `null`, as JSON has no way to
+/// represent them.
+///
+/// This loses the exact NBT types: it is not possible to tell an `Int` from a
+/// `Byte`, or an `IntArray` from a list of ints, in the output. Strings are
+/// always valid Unicode, since NBT strings are decoded from Java's encoding
+/// when deserialized.
+///
+/// ```
+/// # use fastnbt::Value;
+/// let list = Value::List(vec![Value::Byte(1), Value::String("a".to_owned())]);
+/// assert_eq!(r#"[1,"a"]"#, fastnbt::value::to_json(&list).to_string());
+/// ```
+#[cfg(feature = "json")]
+pub fn to_json(value: &Value) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    let float = |f: f64| serde_json::Number::from_f64(f).map_or(Json::Null, Json::Number);
+
+    match value {
+        Value::Byte(v) => Json::from(*v),
+        Value::Short(v) => Json::from(*v),
+        Value::Int(v) => Json::from(*v),
+        Value::Long(v) => Json::from(*v),
+        Value::Float(v) => float(*v as f64),
+        Value::Double(v) => float(*v),
+        Value::String(v) => Json::from(v.as_str()),
+        Value::ByteArray(v) => v.iter().copied().collect(),
+        Value::IntArray(v) => v.iter().copied().collect(),
+        Value::LongArray(v) => v.iter().copied().collect(),
+        Value::List(v) => v.iter().map(to_json).collect(),
+        Value::Compound(v) => {
+            Json::Object(v.iter().map(|(k, v)| (k.clone(), to_json(v))).collect())
+        }
+    }
+}
+
+/// Convert JSON into a [`Value`]. Since JSON has fewer types than NBT, the
+/// following rules are used:
+///
+/// * Integers become `Int` if they fit, and `Long` otherwise.
+/// * Other numbers become `Double`.
+/// * Booleans become `Byte`, 1 for true.
+/// * Arrays become lists, never NBT arrays. All the numbers in an array are
+///   given the same type, the widest any of them needs, as every element of
+///   an NBT list must have the same type.
+/// * Objects become compounds.
+///
+/// It is an error for the JSON to contain `null`, or an array mixing
+/// different kinds of values, since NBT cannot represent these.
+///
+/// ```
+/// # use fastnbt::Value;
+/// let json = serde_json::json!({"pos": [1, 2.5], "big": 5000000000i64});
+/// let nbt = fastnbt::value::from_json(&json)?;
+///
+/// match nbt {
+///     Value::Compound(map) => {
+///         assert_eq!(Value::List(vec![Value::Double(1.0), Value::Double(2.5)]), map["pos"]);
+///         assert_eq!(Value::Long(5000000000), map["big"]);
+///     }
+///     _ => unreachable!(),
+/// }
+/// # Ok::<(), fastnbt::error::Error>(())
+/// ```
+#[cfg(feature = "json")]
+pub fn from_json(json: &serde_json::Value) -> crate::error::Result<Value> {
+    use crate::error::Error;
+    use serde_json::Value as Json;
+    use std::convert::TryFrom;
+
+    Ok(match json {
+        Json::Null => return Err(Error::bespoke("cannot convert null to NBT")),
+        Json::Bool(b) => Value::Byte(*b as i8),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => match i32::try_from(i) {
+                Ok(i) => Value::Int(i),
+                Err(_) => Value::Long(i),
+            },
+            None => Value::Double(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Json::String(s) => Value::String(s.clone()),
+        Json::Array(elements) => {
+            let elements = elements
+                .iter()
+                .map(from_json)
+                .collect::<crate::error::Result<Vec<_>>>()?;
+            Value::List(unify_list(elements)?)
+        }
+        Json::Object(map) => Value::Compound(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), from_json(v)?)))
+                .collect::<crate::error::Result<_>>()?,
+        ),
+    })
+}
+
+/// Make the elements of a list converted from JSON all the same type,
+/// widening numbers where needed.
+#[cfg(feature = "json")]
+fn unify_list(elements: Vec<Value>) -> crate::error::Result<Vec<Value>> {
+    use std::mem::discriminant;
+
+    let is_number = |v: &Value| matches!(v, Value::Int(_) | Value::Long(_) | Value::Double(_));
+
+    if elements.iter().all(is_number) {
+        let widest = elements.iter().fold(0, |widest, v| match v {
+            Value::Double(_) => 2,
+            Value::Long(_) => widest.max(1),
+            _ => widest,
+        });
+
+        return Ok(elements
+            .into_iter()
+            .map(|v| match (widest, v) {
+                (2, Value::Int(i)) => Value::Double(i as f64),
+                (2, Value::Long(i)) => Value::Double(i as f64),
+                (1, Value::Int(i)) => Value::Long(i as i64),
+                (_, v) => v,
+            })
+            .collect());
+    }
+
+    match elements.first() {
+        Some(first)
+            if elements
+                .iter()
+                .any(|v| discriminant(v) != discriminant(first)) =>
+        {
+            Err(crate::error::Error::bespoke(
+                "cannot convert array of mixed types to NBT list",
+            ))
+        }
+        _ => Ok(elements),
+    }
+}
@@ -1,14 +1,17 @@
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use env_logger::Env;
 use fastanvil::{
-    render_region, CCoord, HeightMode, JavaChunk, RCoord, RegionLoader, Rgba, TopShadeRenderer,
+    render_region, stitch_regions, HeightMode, JavaChunk, RCoord, RegionBounds, RegionLoader, Rgba,
+    TopShadeRenderer,
 };
-use fastanvil::{Dimension, RenderedPalette};
+use fastanvil::{Dimension, DimensionKind, RenderedPalette};
 
-use fastanvil::RegionFileLoader;
+use fastanvil::{RegionBuffer, RegionFileLoader};
+use fastnbt::Value;
 use flate2::read::GzDecoder;
 use log::{error, info};
 use rayon::prelude::*;
+use std::fmt::Write;
 use std::path::{Path, PathBuf};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -20,43 +23,16 @@ fn parse_coord(coord: &str) -> Option<(isize, isize)> {
     Some((x, z))
 }
 
-fn auto_size(coords: &[(RCoord, RCoord)]) -> Option<Rectangle> {
-    if coords.is_empty() {
-        return None;
-    }
-
-    let mut bounds = Rectangle {
-        xmin: RCoord(isize::MAX),
-        zmin: RCoord(isize::MAX),
-        xmax: RCoord(isize::MIN),
-        zmax: RCoord(isize::MIN),
-    };
-
-    for coord in coords {
-        bounds.xmin = std::cmp::min(bounds.xmin, coord.0);
-        bounds.xmax = std::cmp::max(bounds.xmax, coord.0);
-        bounds.zmin = std::cmp::min(bounds.zmin, coord.1);
-        bounds.zmax = std::cmp::max(bounds.zmax, coord.1);
-    }
-
-    Some(bounds)
-}
-
-fn make_bounds(size: (isize, isize), off: (isize, isize)) -> Rectangle {
-    Rectangle {
-        xmin: RCoord(off.0 - (size.0) / 2), // size + 1 makes sure that a size of 1,1
-        xmax: RCoord(off.0 + (size.0 + 1) / 2), // produces bounds of size 1,1 rather than
-        zmin: RCoord(off.1 - (size.1) / 2), // the 0,0 you would get without it.
-        zmax: RCoord(off.1 + (size.1 + 1) / 2),
-    }
-}
-
-#[derive(Debug)]
-struct Rectangle {
-    xmin: RCoord,
-    xmax: RCoord,
-    zmin: RCoord,
-    zmax: RCoord,
+/// The regions within `size` regions of `off`. A size of 1,1 gives just the
+/// region at `off`.
+fn make_bounds(size: (isize, isize), off: (isize, isize)) -> RegionBounds {
+    RegionBounds::new(
+        (RCoord(off.0 - size.0 / 2), RCoord(off.1 - size.1 / 2)),
+        (
+            RCoord(off.0 + (size.0 + 1) / 2 - 1),
+            RCoord(off.1 + (size.1 + 1) / 2 - 1),
+        ),
+    )
 }
 
 fn get_palette(path: Option<&str>) -> Result<RenderedPalette> {
@@ -105,6 +81,9 @@ fn get_palette(path: Option<&str>) -> Result<RenderedPalette> {
         blockstates: blockstates?,
         grass: grass?,
         foliage: foliage?,
+        tints: Default::default(),
+        cover: Default::default(),
+        fallback: None,
     };
 
     Ok(p)
@@ -118,10 +97,10 @@ fn render(args: &ArgMatches) -> Result<()> {
         false => HeightMode::Trust,
     };
 
-    let subpath = match dim {
-        "end" => "DIM1/region",
-        "nether" => "DIM-1/region",
-        _ => "region",
+    let (subpath, kind) = match dim {
+        "end" => ("DIM1/region", DimensionKind::End),
+        "nether" => ("DIM-1/region", DimensionKind::Nether),
+        _ => ("region", DimensionKind::Overworld),
     };
 
     let loader: RegionFileLoader<JavaChunk> = RegionFileLoader::new(world.join(subpath));
@@ -132,18 +111,16 @@ fn render(args: &ArgMatches) -> Result<()> {
         (Some(size), Some(offset)) => {
             make_bounds(parse_coord(size).unwrap(), parse_coord(offset).unwrap())
         }
-        (None, _) => auto_size(&coords).unwrap(),
+        (None, _) => RegionBounds::covering(&coords).ok_or("no regions")?,
         _ => panic!(),
     };
 
     info!("Bounds: {:?}", bounds);
 
-    let x_range = bounds.xmin..bounds.xmax;
-    let z_range = bounds.zmin..bounds.zmax;
-
-    let region_len: usize = 32 * 16;
-
-    let pal = get_palette(args.value_of("palette"))?;
+    let pal = match args.value_of("jar") {
+        Some(jar) => fastanvil::tex::ResourcePack::open(jar)?.palette()?,
+        None => get_palette(args.value_of("palette"))?,
+    };
 
     let region_maps: Vec<_> = coords
         .into_par_iter()
@@ -153,8 +130,8 @@ fn render(args: &ArgMatches) -> Result<()> {
 
             let (x, z) = coord;
 
-            if x < x_range.end && x >= x_range.start && z < z_range.end && z >= z_range.start {
-                let drawer = TopShadeRenderer::new(&pal, height_mode);
+            if bounds.contains(x, z) {
+                let drawer = TopShadeRenderer::new(&pal, height_mode).with_dimension(kind);
                 let map = render_region(x, z, dimension, drawer);
                 info!("processed r.{}.{}.mca", x.0, z.0);
                 Some(map)
@@ -166,34 +143,156 @@ fn render(args: &ArgMatches) -> Result<()> {
 
     info!("{} regions processed", region_maps.len());
 
-    let dx = (x_range.end.0 - x_range.start.0) as usize;
-    let dz = (z_range.end.0 - z_range.start.0) as usize;
-
-    let mut img = image::ImageBuffer::new((dx * region_len) as u32, (dz * region_len) as u32);
+    let img = stitch_regions(bounds, region_maps);
+    img.save(args.value_of("out").unwrap())?;
+    Ok(())
+}
 
-    for map in region_maps {
-        let xrp = map.x.0 - x_range.start.0;
-        let zrp = map.z.0 - z_range.start.0;
+/// Write `value` as SNBT, the text format used by Minecraft's commands.
+/// Compound keys are sorted so that the output is stable.
+fn write_snbt(out: &mut String, value: &Value) {
+    fn write_str(out: &mut String, s: &str) {
+        out.push('"');
+        for c in s.chars() {
+            if c == '"' || c == '\\' {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push('"');
+    }
 
-        for xc in 0..32 {
-            for zc in 0..32 {
-                let chunk = map.chunk(CCoord(xc), CCoord(zc));
-                let xcp = xrp * 32 + xc as isize;
-                let zcp = zrp * 32 + zc as isize;
+    fn write_array<T: std::fmt::Display>(
+        out: &mut String,
+        prefix: &str,
+        suffix: &str,
+        values: &[T],
+    ) {
+        out.push('[');
+        out.push_str(prefix);
+        out.push(';');
+        for (i, v) in values.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "{}{}", v, suffix).unwrap();
+        }
+        out.push(']');
+    }
 
-                for z in 0..16 {
-                    for x in 0..16 {
-                        let pixel = chunk[z * 16 + x];
-                        let x = xcp * 16 + x as isize;
-                        let z = zcp * 16 + z as isize;
-                        img.put_pixel(x as u32, z as u32, image::Rgba(pixel))
-                    }
+    match value {
+        Value::Byte(v) => write!(out, "{}b", v).unwrap(),
+        Value::Short(v) => write!(out, "{}s", v).unwrap(),
+        Value::Int(v) => write!(out, "{}", v).unwrap(),
+        Value::Long(v) => write!(out, "{}L", v).unwrap(),
+        Value::Float(v) => write!(out, "{}f", v).unwrap(),
+        Value::Double(v) => write!(out, "{}d", v).unwrap(),
+        Value::String(v) => write_str(out, v),
+        Value::ByteArray(v) => write_array(out, "B", "b", v),
+        Value::IntArray(v) => write_array(out, "I", "", v),
+        Value::LongArray(v) => write_array(out, "L", "L", v),
+        Value::List(values) => {
+            out.push('[');
+            for (i, v) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_snbt(out, v);
+            }
+            out.push(']');
+        }
+        Value::Compound(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            out.push('{');
+            for (i, (k, v)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                let plain = !k.is_empty()
+                    && k.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c));
+                if plain {
+                    out.push_str(k);
+                } else {
+                    write_str(out, k);
                 }
+                out.push(':');
+                write_snbt(out, v);
             }
+            out.push('}');
+        }
+    }
+}
+
+fn dump(args: &ArgMatches) -> Result<()> {
+    let path = args.value_of("region").unwrap();
+    let x: isize = args.value_of("x").unwrap().parse()?;
+    let z: isize = args.value_of("z").unwrap().parse()?;
+
+    // Chunk coordinates can be given relative to the region or to the world.
+    let (x, z) = (x.rem_euclid(32) as usize, z.rem_euclid(32) as usize);
+
+    let region = RegionBuffer::new(std::fs::File::open(path)?);
+    let data = region.load_chunk(x, z)?;
+    let chunk: Value = fastnbt::de::from_bytes(&data)?;
+
+    match args.value_of("format").unwrap() {
+        "json" => println!("{}", serde_json::to_string_pretty(&chunk)?),
+        _ => {
+            let mut out = String::new();
+            write_snbt(&mut out, &chunk);
+            println!("{}", out);
         }
     }
 
-    img.save("map.png").unwrap();
+    Ok(())
+}
+
+fn query(args: &ArgMatches) -> Result<()> {
+    let world: PathBuf = args.value_of("world").unwrap().parse().unwrap();
+    let dim: &str = args.value_of("dimension").unwrap();
+    let block = args.value_of("block").unwrap();
+    let name = if block.contains(':') {
+        block.to_owned()
+    } else {
+        format!("minecraft:{}", block)
+    };
+
+    let subpath = match dim {
+        "end" => "DIM1/region",
+        "nether" => "DIM-1/region",
+        _ => "region",
+    };
+
+    let loader: RegionFileLoader<JavaChunk> = RegionFileLoader::new(world.join(subpath));
+    let coords = loader.list()?;
+
+    let mut found: Vec<_> = coords
+        .into_par_iter()
+        .flat_map_iter(|(x, z)| {
+            let loader = RegionFileLoader::<JavaChunk>::new(world.join(subpath));
+            let dimension = Dimension::new(loader);
+
+            let min = (x.0 * 512, isize::MIN / 2, z.0 * 512);
+            let max = (x.0 * 512 + 511, isize::MAX / 2, z.0 * 512 + 511);
+            let found: Vec<_> = dimension
+                .find_blocks(min, max, |b| b.name() == name)
+                .map(|(pos, _)| pos)
+                .collect();
+
+            info!("searched r.{}.{}.mca", x.0, z.0);
+            found
+        })
+        .collect();
+
+    found.sort_unstable();
+    for pos in &found {
+        println!("{} {} {}", pos.x, pos.y, pos.z);
+    }
+
+    info!("{} found", found.len());
     Ok(())
 }
 
@@ -206,10 +305,10 @@ fn tiles(args: &ArgMatches) -> Result<()> {
         false => HeightMode::Trust,
     };
 
-    let subpath = match dim {
-        "end" => "DIM1/region",
-        "nether" => "DIM-1/region",
-        _ => "region",
+    let (subpath, kind) = match dim {
+        "end" => ("DIM1/region", DimensionKind::End),
+        "nether" => ("DIM-1/region", DimensionKind::Nether),
+        _ => ("region", DimensionKind::Overworld),
     };
 
     // don't care if dir already exists.
@@ -223,17 +322,12 @@ fn tiles(args: &ArgMatches) -> Result<()> {
         (Some(size), Some(offset)) => {
             make_bounds(parse_coord(size).unwrap(), parse_coord(offset).unwrap())
         }
-        (None, _) => auto_size(&coords).unwrap(),
+        (None, _) => RegionBounds::covering(coords).ok_or("no regions")?,
         _ => panic!(),
     };
 
     info!("Bounds: {:?}", bounds);
 
-    let x_range = bounds.xmin..bounds.xmax;
-    let z_range = bounds.zmin..bounds.zmax;
-
-    let region_len: usize = 32 * 16;
-
     let pal = get_palette(args.value_of("palette"))?;
 
     let regions_processed = coords
@@ -244,8 +338,8 @@ fn tiles(args: &ArgMatches) -> Result<()> {
 
             let (x, z) = *coord;
 
-            if x < x_range.end && x >= x_range.start && z < z_range.end && z >= z_range.start {
-                let drawer = TopShadeRenderer::new(&pal, height_mode);
+            if bounds.contains(x, z) {
+                let drawer = TopShadeRenderer::new(&pal, height_mode).with_dimension(kind);
                 let map = render_region(x, z, dimension, drawer);
                 info!("processed r.{}.{}.mca", x.0, z.0);
                 Some(map)
@@ -255,26 +349,9 @@ fn tiles(args: &ArgMatches) -> Result<()> {
         })
         .filter_map(|region| region)
         .map(|region| {
-            let mut img = image::ImageBuffer::new(region_len as u32, region_len as u32);
-
-            for xc in 0..32 {
-                for zc in 0..32 {
-                    let heightmap = region.chunk(CCoord(xc), CCoord(zc));
-                    let xcp = xc as isize;
-                    let zcp = zc as isize;
-
-                    for z in 0..16 {
-                        for x in 0..16 {
-                            let pixel = heightmap[z * 16 + x];
-                            let x = xcp * 16 + x as isize;
-                            let z = zcp * 16 + z as isize;
-                            img.put_pixel(x as u32, z as u32, image::Rgba(pixel))
-                        }
-                    }
-                }
-            }
-
-            img.save(format!("{}/{}.{}.png", out, region.x.0, region.z.0))
+            region
+                .to_image()
+                .save(format!("{}/{}.{}.png", out, region.x.0, region.z.0))
                 .unwrap();
         })
         .count();
@@ -324,6 +401,13 @@ fn main() -> Result<()> {
                         .takes_value(true)
                         .required(false),
                 )
+                .arg(
+                    Arg::with_name("out")
+                        .long("out")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("map.png"),
+                )
                 .arg(
                     Arg::with_name("calculate-heights")
                         .long("calculate-heights")
@@ -374,11 +458,42 @@ fn main() -> Result<()> {
                         .required(false),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about("Print the NBT of a chunk in a region file")
+                .setting(AppSettings::AllowNegativeNumbers)
+                .arg(Arg::with_name("region").takes_value(true).required(true))
+                .arg(Arg::with_name("x").takes_value(true).required(true))
+                .arg(Arg::with_name("z").takes_value(true).required(true))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .required(false)
+                        .possible_values(&["snbt", "json"])
+                        .default_value("snbt"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("List the coordinates of every block with a given name")
+                .arg(Arg::with_name("world").takes_value(true).required(true))
+                .arg(Arg::with_name("block").takes_value(true).required(true))
+                .arg(
+                    Arg::with_name("dimension")
+                        .long("dimension")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("overworld"),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
         ("render", Some(args)) => render(args)?,
         ("tiles", Some(args)) => tiles(args)?,
+        ("dump", Some(args)) => dump(args)?,
+        ("query", Some(args)) => query(args)?,
         _ => error!("{}", matches.usage()),
     };
 
@@ -9,7 +9,7 @@ fn main() {
     let mut region = RegionBuffer::new(file);
 
     region
-        .for_each_chunk(|x, z, data| {
+        .for_each_chunk(|x, z, _timestamp, data| {
             let mut file = std::fs::File::create(format!("chunks/{}.{}.nbt", x, z)).unwrap();
             file.write_all(data).unwrap();
 